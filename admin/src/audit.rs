@@ -0,0 +1,106 @@
+use colored_json::to_colored_json_auto;
+use serde_json::Value;
+use std::process::ExitCode;
+
+use reqwest::StatusCode;
+use trustification_auth::client::{OpenIdTokenProviderConfigArguments, TokenInjector};
+use trustification_common::tls::ClientConfig;
+
+/// Audit the consistency of the storage and the index of an indexer
+#[derive(clap::Subcommand, Debug)]
+pub enum Audit {
+    Start(AuditStart),
+    Report(AuditReport),
+}
+
+impl Audit {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        match self {
+            Self::Start(run) => run.run().await,
+            Self::Report(run) => run.run().await,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[command(about = "Trigger an audit", args_conflicts_with_subcommands = true)]
+pub struct AuditStart {
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    /// The authorized admin endpoint of the indexer, on its infrastructure port
+    #[arg(short = 'i', long = "indexer", default_value = "http://localhost:9010/admin/audit")]
+    pub indexer_url: String,
+
+    /// Send the events repairing the discrepancies, instead of only reporting them
+    #[arg(long = "repair", default_value_t = false)]
+    pub repair: bool,
+
+    #[command(flatten)]
+    pub client: ClientConfig,
+
+    /// OIDC parameters
+    #[command(flatten)]
+    pub oidc: OpenIdTokenProviderConfigArguments,
+}
+
+impl AuditStart {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let client = self.client.build_client()?;
+        let provider = self.oidc.clone().into_provider_or_devmode(self.devmode).await?;
+        let request = client
+            .post(self.indexer_url)
+            .json(&serde_json::json!({ "repair": self.repair }))
+            .inject_token(&provider)
+            .await?;
+        let response = request.send().await?;
+        match response.status() {
+            StatusCode::ACCEPTED => {
+                println!("Audit requested, its report is available once it's done");
+                Ok(ExitCode::SUCCESS)
+            }
+            status => {
+                println!("Error starting the audit: {status} {:?}", response.text().await);
+                Ok(ExitCode::FAILURE)
+            }
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Show the report of the latest audit",
+    args_conflicts_with_subcommands = true
+)]
+pub struct AuditReport {
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    #[arg(short = 'i', long = "indexer", default_value = "http://localhost:9010/admin/audit")]
+    pub indexer_url: String,
+
+    #[command(flatten)]
+    pub client: ClientConfig,
+
+    /// OIDC parameters
+    #[command(flatten)]
+    pub oidc: OpenIdTokenProviderConfigArguments,
+}
+
+impl AuditReport {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let client = self.client.build_client()?;
+        let provider = self.oidc.clone().into_provider_or_devmode(self.devmode).await?;
+        let request = client.get(self.indexer_url).inject_token(&provider).await?;
+        let response = request.send().await?.error_for_status()?;
+        if response.status() == StatusCode::NO_CONTENT {
+            println!("No audit finished yet");
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        let report = response.json::<Value>().await?;
+        println!("{}", to_colored_json_auto(&report)?);
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
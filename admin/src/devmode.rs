@@ -0,0 +1,219 @@
+use crate::upload::upload;
+use anyhow::bail;
+use reqwest::StatusCode;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use trustification_auth::client::{OpenIdTokenProviderConfigArguments, TokenInjector, TokenProvider};
+use trustification_infrastructure::endpoint::{self, Endpoint};
+use url::Url;
+
+/// SBOMs bundled for seeding, by the ID they are stored as.
+const SBOMS: &[(&str, &[u8])] = &[
+    ("my-sbom", include_bytes!("../../bombastic/testdata/my-sbom.json")),
+    ("kmm-1", include_bytes!("../../bombastic/testdata/kmm-1.json")),
+    ("ubi9-sbom", include_bytes!("../../bombastic/testdata/ubi9-sbom.json")),
+    (
+        "openssl-3.0.7-18.el9_2",
+        include_bytes!("../../bombastic/testdata/openssl-3.0.7-18.el9_2.spdx.json"),
+    ),
+    ("syft-spdx", include_bytes!("../../bombastic/testdata/syft.spdx.json")),
+    (
+        "syft-cyclonedx",
+        include_bytes!("../../bombastic/testdata/syft.cyclonedx.json"),
+    ),
+];
+
+/// Advisories bundled for seeding, by their advisory ID.
+const ADVISORIES: &[(&str, &[u8])] = &[
+    (
+        "RHSA-2021:3029",
+        include_bytes!("../../vexination/testdata/rhsa-2021_3029.json"),
+    ),
+    (
+        "RHSA-2023:1441",
+        include_bytes!("../../vexination/testdata/rhsa-2023_1441.json"),
+    ),
+    (
+        "RHSA-2023:3408",
+        include_bytes!("../../vexination/testdata/rhsa-2023_3408.json"),
+    ),
+    (
+        "RHSA-2023:4378",
+        include_bytes!("../../vexination/testdata/rhsa-2023_4378.json"),
+    ),
+];
+
+/// Development mode tooling
+#[derive(clap::Subcommand, Debug)]
+pub enum Devmode {
+    Seed(DevmodeSeed),
+}
+
+impl Devmode {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        if let Err(e) = env_logger::builder().format_timestamp_millis().try_init() {
+            eprintln!("Error initializing logging: {:?}", e);
+        }
+        match self {
+            Self::Seed(run) => run.run().await,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Seed a local instance with the bundled SBOMs and advisories",
+    args_conflicts_with_subcommands = true
+)]
+pub struct DevmodeSeed {
+    /// URL of the Bombastic instance
+    #[arg(long = "bombastic-url", default_value_t = endpoint::Bombastic::url())]
+    pub bombastic_url: Url,
+
+    /// URL of the Vexination instance
+    #[arg(long = "vexination-url", default_value_t = endpoint::Vexination::url())]
+    pub vexination_url: Url,
+
+    /// Only seed SBOMs
+    #[arg(long = "sboms-only", conflicts_with = "advisories_only", default_value_t = false)]
+    pub sboms_only: bool,
+
+    /// Only seed advisories
+    #[arg(long = "advisories-only", conflicts_with = "sboms_only", default_value_t = false)]
+    pub advisories_only: bool,
+
+    /// Don't wait for the seeded documents to be indexed
+    #[arg(long = "no-wait", default_value_t = false)]
+    pub no_wait: bool,
+
+    /// Time to wait for the seeded documents to be indexed
+    #[arg(long = "timeout", default_value = "2m")]
+    pub timeout: humantime::Duration,
+
+    /// OIDC parameters
+    #[command(flatten)]
+    pub oidc: OpenIdTokenProviderConfigArguments,
+}
+
+impl DevmodeSeed {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let client = reqwest::Client::new();
+        // seeding is meant for local instances only, which always run in development mode
+        let provider = self.oidc.clone().into_provider_or_devmode(true).await?;
+
+        let mut seeded = Vec::new();
+        if !self.advisories_only {
+            let url = format!("{}api/v1/sbom", self.bombastic_url);
+            for (id, data) in SBOMS {
+                upload(&url, &client, &provider, data.to_vec(), vec![], Some(id.to_string())).await?;
+                seeded.push(Seeded::Sbom(*id));
+            }
+        }
+        if !self.sboms_only {
+            let url = format!("{}api/v1/vex", self.vexination_url);
+            for (id, data) in ADVISORIES {
+                upload(&url, &client, &provider, data.to_vec(), vec![], None).await?;
+                seeded.push(Seeded::Advisory(*id));
+            }
+        }
+
+        if !self.no_wait {
+            let deadline = Instant::now() + *self.timeout;
+            for seeded in &seeded {
+                self.wait_indexed(&client, &provider, seeded, deadline).await?;
+            }
+        }
+
+        for seeded in &seeded {
+            match seeded {
+                Seeded::Sbom(id) => println!("sbom\t{id}"),
+                Seeded::Advisory(id) => println!("vex\t{id}"),
+            }
+        }
+
+        Ok(ExitCode::SUCCESS)
+    }
+
+    /// Wait until a seeded document can be found by searching for it.
+    async fn wait_indexed(
+        &self,
+        client: &reqwest::Client,
+        provider: &impl TokenProvider,
+        seeded: &Seeded,
+        deadline: Instant,
+    ) -> anyhow::Result<()> {
+        const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+        let (url, query) = match seeded {
+            Seeded::Sbom(id) => (
+                format!("{}api/v1/sbom/search", self.bombastic_url),
+                format!(r#"id:"{id}""#),
+            ),
+            Seeded::Advisory(id) => (
+                format!("{}api/v1/vex/search", self.vexination_url),
+                format!(r#"id:"{id}""#),
+            ),
+        };
+
+        loop {
+            let response = client
+                .get(&url)
+                .query(&[("q", query.as_str()), ("limit", "1")])
+                .inject_token(provider)
+                .await?
+                .send()
+                .await?;
+
+            if response.status() == StatusCode::OK {
+                let total = match seeded {
+                    Seeded::Sbom(_) => response.json::<bombastic_model::prelude::SearchResult>().await?.total,
+                    Seeded::Advisory(_) => response.json::<vexination_model::prelude::SearchResult>().await?.total,
+                };
+                if total > 0 {
+                    log::info!("{} was indexed", seeded.id());
+                    return Ok(());
+                }
+            } else {
+                log::debug!("Failed to search for {}: {}", seeded.id(), response.status());
+            }
+
+            if Instant::now() >= deadline {
+                bail!("{} was not indexed in time", seeded.id());
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+enum Seeded {
+    Sbom(&'static str),
+    Advisory(&'static str),
+}
+
+impl Seeded {
+    fn id(&self) -> &str {
+        match self {
+            Self::Sbom(id) | Self::Advisory(id) => id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advisory_ids() {
+        for (id, data) in ADVISORIES {
+            let advisory: serde_json::Value = serde_json::from_slice(data).unwrap();
+            assert_eq!(advisory["document"]["tracking"]["id"], *id);
+        }
+    }
+
+    #[test]
+    fn sboms() {
+        for (id, data) in SBOMS {
+            assert!(bombastic_model::prelude::SBOM::parse(data).is_ok(), "{id}");
+        }
+    }
+}
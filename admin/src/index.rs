@@ -0,0 +1,763 @@
+use prometheus::Registry;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, num::NonZeroUsize, process::ExitCode};
+use time::OffsetDateTime;
+use tokio::time::MissedTickBehavior;
+use trustification_index::{Index as SearchIndex, IndexConfig, IndexStore};
+use trustification_storage::{Error as StorageError, Storage, StorageConfig};
+
+/// Name of the manifest, stored next to the snapshots of a backup.
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Version of the manifest format, manifests of prior versions can be restored as well.
+const MANIFEST_VERSION: u32 = 2;
+
+/// Backup, restore and export search index snapshots
+#[derive(clap::Subcommand, Debug)]
+pub enum Index {
+    Backup(IndexBackup),
+    Restore(IndexRestore),
+    Export(IndexExport),
+}
+
+impl Index {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        if let Err(e) = env_logger::builder().format_timestamp_millis().try_init() {
+            eprintln!("Error initializing logging: {:?}", e);
+        }
+        match self {
+            Self::Backup(run) => run.run().await,
+            Self::Restore(run) => run.run().await,
+            Self::Export(run) => run.run().await,
+        }
+    }
+}
+
+/// A search index which can be backed up.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexKind {
+    Bombastic,
+    Vexination,
+}
+
+impl IndexKind {
+    /// The name of the index, which also is the default bucket its snapshots are published to.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Bombastic => "bombastic",
+            Self::Vexination => "vexination",
+        }
+    }
+}
+
+/// Arguments to access the buckets the indexes are published to.
+#[derive(clap::Args, Debug)]
+pub struct IndexStorage {
+    /// The indexes to back up or restore
+    #[arg(long = "index", value_enum, default_values_t = [IndexKind::Bombastic, IndexKind::Vexination])]
+    pub indexes: Vec<IndexKind>,
+
+    /// Bucket the Bombastic index is published to
+    #[arg(long = "bombastic-bucket", default_value = "bombastic")]
+    pub bombastic_bucket: String,
+
+    /// Bucket the Vexination index is published to
+    #[arg(long = "vexination-bucket", default_value = "vexination")]
+    pub vexination_bucket: String,
+
+    /// Only report what would be done, without writing anything
+    #[arg(long = "dry-run", default_value_t = false)]
+    pub dry_run: bool,
+
+    /// Development mode
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    #[command(flatten)]
+    pub storage: StorageConfig,
+}
+
+impl IndexStorage {
+    fn index_storage(&self, index: IndexKind) -> anyhow::Result<Storage> {
+        let bucket = match index {
+            IndexKind::Bombastic => &self.bombastic_bucket,
+            IndexKind::Vexination => &self.vexination_bucket,
+        };
+        self.storage_for(bucket)
+    }
+
+    fn storage_for(&self, bucket: &str) -> anyhow::Result<Storage> {
+        Ok(Storage::new(self.storage_config_for(bucket), &Registry::new())?)
+    }
+
+    fn storage_config_for(&self, bucket: &str) -> StorageConfig {
+        let mut config = self.storage.clone();
+        config.bucket = Some(bucket.to_string());
+        config.process(bucket, self.devmode)
+    }
+}
+
+/// Location of a backup or export, in the form of `<bucket>/<path>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackupLocation {
+    pub bucket: String,
+    pub path: String,
+}
+
+impl BackupLocation {
+    fn object(&self, name: &str) -> String {
+        match self.path.is_empty() {
+            true => name.to_string(),
+            false => format!("{}/{}", self.path, name),
+        }
+    }
+
+    fn snapshot(&self, entry: &ManifestEntry) -> String {
+        self.object(&format!("{}/{}", entry.index.name(), entry.name))
+    }
+
+    fn delta_manifest(&self, entry: &ManifestEntry) -> String {
+        self.object(&format!("{}/{}.deltas/{MANIFEST_NAME}", entry.index.name(), entry.name))
+    }
+
+    fn delta(&self, entry: &ManifestEntry, sequence: u64) -> String {
+        self.object(&format!("{}/{}.deltas/{sequence}", entry.index.name(), entry.name))
+    }
+
+    fn child(&self, name: &str) -> Self {
+        Self {
+            bucket: self.bucket.clone(),
+            path: self.object(name),
+        }
+    }
+}
+
+impl std::str::FromStr for BackupLocation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim_start_matches("s3://").trim_matches('/');
+        let (bucket, path) = s.split_once('/').unwrap_or((s, ""));
+        if bucket.is_empty() {
+            return Err("the location requires a bucket, like <bucket>/<path>".to_string());
+        }
+        Ok(Self {
+            bucket: bucket.to_string(),
+            path: path.trim_matches('/').to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for BackupLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.bucket, self.path)
+    }
+}
+
+/// The manifest of a backup, listing all snapshots with their size and digest.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created: OffsetDateTime,
+    pub entries: Vec<ManifestEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub index: IndexKind,
+    /// Name of the snapshot, like the one of the index or of a partition
+    pub name: String,
+    pub size: usize,
+    /// SHA-256 digest of the snapshot, hex encoded
+    pub sha256: String,
+    /// The delta snapshots published on top of the snapshot, if there are any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deltas: Option<Deltas>,
+}
+
+impl ManifestEntry {
+    fn new(index: IndexKind, name: String, data: &[u8]) -> Self {
+        Self {
+            index,
+            name,
+            size: data.len(),
+            sha256: digest(data),
+            deltas: None,
+        }
+    }
+
+    /// Verify the data of the snapshot matches the manifest.
+    pub fn verify(&self, data: &[u8]) -> Result<(), VerifyError> {
+        verify(self.size, &self.sha256, data)
+    }
+}
+
+/// The delta snapshots published on top of a snapshot, and the manifest of the index describing them.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deltas {
+    /// The full snapshot the deltas apply to, as named by the manifest of the index
+    pub base: String,
+    pub manifest: BackupObject,
+    /// The deltas, in the order they apply
+    pub deltas: Vec<BackupObject>,
+}
+
+/// An object of a backup, other than a snapshot.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupObject {
+    pub size: usize,
+    /// SHA-256 digest of the object, hex encoded
+    pub sha256: String,
+}
+
+impl BackupObject {
+    fn new(data: &[u8]) -> Self {
+        Self {
+            size: data.len(),
+            sha256: digest(data),
+        }
+    }
+
+    /// Verify the data of the object matches the manifest.
+    pub fn verify(&self, data: &[u8]) -> Result<(), VerifyError> {
+        verify(self.size, &self.sha256, data)
+    }
+}
+
+/// The parts of the manifest of an index needed to back up its delta snapshots.
+#[derive(Debug, Deserialize)]
+struct DeltaManifest {
+    base: String,
+    sequence: u64,
+}
+
+fn verify(size: usize, sha256: &str, data: &[u8]) -> Result<(), VerifyError> {
+    if data.len() != size {
+        return Err(VerifyError::Size {
+            expected: size,
+            actual: data.len(),
+        });
+    }
+    let actual = digest(data);
+    if actual != sha256 {
+        return Err(VerifyError::Digest {
+            expected: sha256.to_string(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+impl Manifest {
+    /// Verify the manifest can be restored by this version.
+    pub fn verify(&self) -> Result<(), VerifyError> {
+        if !(1..=MANIFEST_VERSION).contains(&self.version) {
+            return Err(VerifyError::Version(self.version));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum VerifyError {
+    #[error("unsupported manifest version {0}")]
+    Version(u32),
+    #[error("size mismatch, expected {expected}, was {actual}")]
+    Size { expected: usize, actual: usize },
+    #[error("digest mismatch, expected {expected}, was {actual}")]
+    Digest { expected: String, actual: String },
+}
+
+fn digest(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Back up the published search index snapshots",
+    args_conflicts_with_subcommands = true
+)]
+pub struct IndexBackup {
+    /// Location to back up to, like <bucket>/<path>
+    #[arg(long = "to")]
+    pub to: BackupLocation,
+
+    /// Back up repeatedly at this interval, instead of only once
+    #[arg(long = "interval")]
+    pub interval: Option<humantime::Duration>,
+
+    /// Number of backups to keep, removing older ones. Backups are then written below the location backed up to, named
+    /// by the time they are taken, like when backing up repeatedly.
+    #[arg(long = "keep")]
+    pub keep: Option<NonZeroUsize>,
+
+    #[command(flatten)]
+    pub storage: IndexStorage,
+}
+
+impl IndexBackup {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let target = self.storage.storage_for(&self.to.bucket)?;
+
+        let Some(interval) = self.interval else {
+            match self.keep {
+                Some(keep) => {
+                    self.backup(&target, &self.to.child(&backup_name(OffsetDateTime::now_utc())))
+                        .await?;
+                    self.prune(&target, keep).await?;
+                }
+                None => self.backup(&target, &self.to).await?,
+            }
+            return Ok(ExitCode::SUCCESS);
+        };
+
+        let mut ticker = tokio::time::interval(interval.into());
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let location = self.to.child(&backup_name(OffsetDateTime::now_utc()));
+            // a failed backup is taken again on the next tick, keeping the previous ones
+            if let Err(e) = self.backup(&target, &location).await {
+                log::error!("Error backing up to {location}: {e:?}");
+                continue;
+            }
+            if let Some(keep) = self.keep {
+                if let Err(e) = self.prune(&target, keep).await {
+                    log::warn!("(Ignored) Error removing old backups: {e:?}");
+                }
+            }
+        }
+    }
+
+    async fn backup(&self, target: &Storage, to: &BackupLocation) -> anyhow::Result<()> {
+        let mut entries = Vec::new();
+
+        for index in &self.storage.indexes {
+            let source = self.storage.index_storage(*index)?;
+            let names = source.list_indexes().await?;
+            if names.is_empty() {
+                log::warn!("No published snapshots of the {} index", index.name());
+            }
+
+            for name in names {
+                entries.push(self.backup_snapshot(&source, target, to, *index, name).await?);
+            }
+        }
+
+        let manifest = Manifest {
+            version: MANIFEST_VERSION,
+            created: OffsetDateTime::now_utc(),
+            entries,
+        };
+
+        // the manifest is written last, so an incomplete backup can't be restored
+        if self.storage.dry_run {
+            println!("Would write manifest of {} snapshots", manifest.entries.len());
+        } else {
+            target
+                .put_object(&to.object(MANIFEST_NAME), &serde_json::to_vec_pretty(&manifest)?)
+                .await?;
+            println!("Backed up {} snapshots to {}", manifest.entries.len(), to);
+        }
+
+        Ok(())
+    }
+
+    /// Back up a snapshot, along with the delta snapshots published on top of it.
+    async fn backup_snapshot(
+        &self,
+        source: &Storage,
+        target: &Storage,
+        to: &BackupLocation,
+        index: IndexKind,
+        name: String,
+    ) -> anyhow::Result<ManifestEntry> {
+        // the manifest is read first, as the deltas it names are only removed once a new full snapshot was published
+        let delta_manifest = source.get_index_manifest(&name).await?;
+        let chain = delta_manifest
+            .as_deref()
+            .map(serde_json::from_slice::<DeltaManifest>)
+            .transpose()?
+            .filter(|chain| chain.sequence > 0);
+
+        // snapshots are copied as they are, encrypted snapshots require the same key when restored
+        let data = source.get_index(&name).await?;
+        let mut entry = ManifestEntry::new(index, name, &data);
+        let mut objects = vec![(to.snapshot(&entry), data)];
+
+        if let (Some(chain), Some(manifest)) = (chain, delta_manifest) {
+            let mut deltas = Vec::new();
+            for sequence in 1..=chain.sequence {
+                let data = match source.get_index_delta(&entry.name, &chain.base, sequence).await {
+                    Ok(data) => data,
+                    Err(StorageError::NotFound) => anyhow::bail!(
+                        "Delta snapshot {sequence} of {} is missing, a full snapshot was published meanwhile",
+                        entry.name
+                    ),
+                    Err(e) => return Err(e.into()),
+                };
+                deltas.push(BackupObject::new(&data));
+                objects.push((to.delta(&entry, sequence), data));
+            }
+
+            // the snapshot matches the deltas, unless a full snapshot was published meanwhile
+            let current = source
+                .get_index_manifest(&entry.name)
+                .await?
+                .map(|data| serde_json::from_slice::<DeltaManifest>(&data))
+                .transpose()?;
+            if current.map(|current| current.base) != Some(chain.base.clone()) {
+                anyhow::bail!(
+                    "A full snapshot of {} was published meanwhile, back up again",
+                    entry.name
+                );
+            }
+
+            entry.deltas = Some(Deltas {
+                base: chain.base,
+                manifest: BackupObject::new(&manifest),
+                deltas,
+            });
+            objects.push((to.delta_manifest(&entry), manifest));
+        }
+
+        for (path, data) in objects {
+            if self.storage.dry_run {
+                println!("Would back up {} bytes of {} to {path}", data.len(), entry.name);
+            } else {
+                target.put_object(&path, &data).await?;
+                log::info!("Backed up {} bytes of {} to {path}", data.len(), entry.name);
+            }
+        }
+        Ok(entry)
+    }
+
+    /// Remove all but the latest `keep` backups below the location backed up to, including incomplete ones older
+    /// than those.
+    async fn prune(&self, target: &Storage, keep: NonZeroUsize) -> anyhow::Result<()> {
+        let prefix = self.to.object("");
+        let objects = target.list_objects(&prefix).await?;
+        let mut backups = BTreeMap::<&str, bool>::new();
+        for object in &objects {
+            let Some((name, rest)) = object[prefix.len()..].split_once('/') else {
+                continue;
+            };
+            if is_backup_name(name) {
+                *backups.entry(name).or_default() |= rest == MANIFEST_NAME;
+            }
+        }
+
+        for name in expired(&backups, keep) {
+            let location = self.to.child(name);
+            if self.storage.dry_run {
+                println!("Would remove backup {location}");
+                continue;
+            }
+            let path = location.object("");
+            for object in objects.iter().filter(|object| object.starts_with(&path)) {
+                target.delete_object(object).await?;
+            }
+            println!("Removed backup {location}");
+        }
+
+        Ok(())
+    }
+}
+
+/// The name of a backup taken at the given time, ordered like the times.
+fn backup_name(time: OffsetDateTime) -> String {
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        time.year(),
+        time.month() as u8,
+        time.day(),
+        time.hour(),
+        time.minute(),
+        time.second()
+    )
+}
+
+fn is_backup_name(name: &str) -> bool {
+    name.len() == 16
+        && name.char_indices().all(|(i, c)| match i {
+            8 => c == 'T',
+            15 => c == 'Z',
+            _ => c.is_ascii_digit(),
+        })
+}
+
+/// The backups to remove, given all backups and whether they are complete: those older than the latest `keep`
+/// complete ones.
+fn expired<'a>(backups: &BTreeMap<&'a str, bool>, keep: NonZeroUsize) -> Vec<&'a str> {
+    let Some(oldest) = backups
+        .iter()
+        .rev()
+        .filter(|(_, complete)| **complete)
+        .map(|(name, _)| *name)
+        .nth(keep.get() - 1)
+    else {
+        return Vec::new();
+    };
+    backups.keys().copied().filter(|name| *name < oldest).collect()
+}
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Restore search index snapshots from a backup",
+    args_conflicts_with_subcommands = true
+)]
+pub struct IndexRestore {
+    /// Location to restore from, like <bucket>/<path>
+    #[arg(long = "from")]
+    pub from: BackupLocation,
+
+    #[command(flatten)]
+    pub storage: IndexStorage,
+}
+
+impl IndexRestore {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let source = self.storage.storage_for(&self.from.bucket)?;
+        let manifest: Manifest = serde_json::from_slice(&source.get_object(&self.from.object(MANIFEST_NAME)).await?)?;
+        manifest.verify()?;
+        log::info!("Restoring backup of {} created at {}", self.from, manifest.created);
+
+        // verify all snapshots before restoring any, so a damaged backup doesn't leave a mix of indexes behind
+        let mut snapshots = Vec::new();
+        for entry in manifest
+            .entries
+            .iter()
+            .filter(|entry| self.storage.indexes.contains(&entry.index))
+        {
+            let data = source.get_object(&self.from.snapshot(entry)).await?;
+            entry
+                .verify(&data)
+                .map_err(|err| anyhow::anyhow!("Snapshot {} failed verification: {err}", entry.name))?;
+
+            let mut deltas = Vec::new();
+            let mut delta_manifest = None;
+            if let Some(chain) = &entry.deltas {
+                for (sequence, delta) in (1..).zip(&chain.deltas) {
+                    let data = source.get_object(&self.from.delta(entry, sequence)).await?;
+                    delta.verify(&data).map_err(|err| {
+                        anyhow::anyhow!("Delta snapshot {sequence} of {} failed verification: {err}", entry.name)
+                    })?;
+                    deltas.push(data);
+                }
+                let data = source.get_object(&self.from.delta_manifest(entry)).await?;
+                chain.manifest.verify(&data).map_err(|err| {
+                    anyhow::anyhow!("Manifest of the deltas of {} failed verification: {err}", entry.name)
+                })?;
+                delta_manifest = Some(data);
+            }
+
+            snapshots.push((entry, data, deltas, delta_manifest));
+        }
+
+        if snapshots.is_empty() {
+            println!("The backup contains no snapshots of the selected indexes");
+            return Ok(ExitCode::FAILURE);
+        }
+
+        for (entry, data, deltas, delta_manifest) in snapshots {
+            if self.storage.dry_run {
+                println!(
+                    "Verified {} ({} bytes, {} deltas), would restore it to the {} index",
+                    entry.name,
+                    entry.size,
+                    deltas.len(),
+                    entry.index.name()
+                );
+            } else {
+                let storage = self.storage.index_storage(entry.index)?;
+                storage.put_index(&entry.name, &data).await?;
+                match (&entry.deltas, delta_manifest) {
+                    (Some(chain), Some(manifest)) => {
+                        for (sequence, delta) in (1..).zip(&deltas) {
+                            storage
+                                .put_index_delta(&entry.name, &chain.base, sequence, delta)
+                                .await?;
+                        }
+                        // the manifest is written last, so the deltas are only applied once all are restored
+                        storage.put_index_manifest(&entry.name, &manifest).await?;
+                    }
+                    // sync the restored snapshot, instead of the delta snapshots published on top of the replaced one
+                    _ => storage.delete_index_manifest(&entry.name).await?,
+                }
+                println!(
+                    "Restored {} ({} bytes, {} deltas) to the {} index",
+                    entry.name,
+                    entry.size,
+                    deltas.len(),
+                    entry.index.name()
+                );
+            }
+        }
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Export the published search indexes as Parquet files, for offline analytics",
+    args_conflicts_with_subcommands = true
+)]
+pub struct IndexExport {
+    /// Location to export to, like <bucket>/<path>, writing a <index>.parquet file for each index
+    #[arg(long = "to")]
+    pub to: BackupLocation,
+
+    #[command(flatten)]
+    pub storage: IndexStorage,
+
+    #[command(flatten)]
+    pub index: IndexConfig,
+}
+
+impl IndexExport {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let target = self.storage.storage_for(&self.to.bucket)?;
+
+        for index in &self.storage.indexes {
+            let data = match index {
+                IndexKind::Bombastic => self.export(*index, bombastic_index::sbom::Index::new()).await?,
+                IndexKind::Vexination => self.export(*index, vexination_index::Index::new()).await?,
+            };
+
+            let path = self.to.object(&format!("{}.parquet", index.name()));
+            if self.storage.dry_run {
+                println!(
+                    "Would export the {} index ({} bytes) to {path}",
+                    index.name(),
+                    data.len()
+                );
+            } else {
+                target.put_object(&path, &data).await?;
+                println!("Exported the {} index ({} bytes) to {path}", index.name(), data.len());
+            }
+        }
+
+        Ok(ExitCode::SUCCESS)
+    }
+
+    /// Load the latest snapshot of an index, including its partitions, and export it.
+    async fn export<INDEX: SearchIndex + 'static>(&self, kind: IndexKind, index: INDEX) -> anyhow::Result<Vec<u8>> {
+        let bucket = match kind {
+            IndexKind::Bombastic => &self.storage.bombastic_bucket,
+            IndexKind::Vexination => &self.storage.vexination_bucket,
+        };
+        let store = IndexStore::new(
+            &self.storage.storage_config_for(bucket),
+            &self.index,
+            index,
+            &Registry::new(),
+        )?;
+        store.sync(&self.storage.index_storage(kind)?).await?;
+
+        let mut data = Vec::new();
+        let rows = store.export_parquet(&mut data)?;
+        log::info!("Exported {rows} documents of the {} index", kind.name());
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn location() {
+        assert_eq!(
+            "s3://backups/index/2023-12-01".parse(),
+            Ok(BackupLocation {
+                bucket: "backups".to_string(),
+                path: "index/2023-12-01".to_string()
+            })
+        );
+        let location: BackupLocation = "backups/".parse().unwrap();
+        assert_eq!(location.object(MANIFEST_NAME), MANIFEST_NAME);
+        assert!("/index".parse::<BackupLocation>().is_err());
+    }
+
+    #[test]
+    fn verify() {
+        let entry = ManifestEntry::new(IndexKind::Bombastic, "sbom".to_string(), b"snapshot");
+        assert_eq!(entry.verify(b"snapshot"), Ok(()));
+        assert!(matches!(entry.verify(b"snapshot!"), Err(VerifyError::Size { .. })));
+        assert!(matches!(entry.verify(b"Snapshot"), Err(VerifyError::Digest { .. })));
+
+        let location: BackupLocation = "backups/daily".parse().unwrap();
+        assert_eq!(location.snapshot(&entry), "daily/bombastic/sbom");
+        assert_eq!(location.delta(&entry, 2), "daily/bombastic/sbom.deltas/2");
+        assert_eq!(
+            location.delta_manifest(&entry),
+            "daily/bombastic/sbom.deltas/manifest.json"
+        );
+
+        let delta = BackupObject::new(b"delta");
+        assert_eq!(delta.verify(b"delta"), Ok(()));
+        assert!(matches!(delta.verify(b"Delta"), Err(VerifyError::Digest { .. })));
+    }
+
+    #[test]
+    fn backup_names() {
+        let name = backup_name(OffsetDateTime::from_unix_timestamp(1_701_388_800).unwrap());
+        assert_eq!(name, "20231201T000000Z");
+        assert!(is_backup_name(&name));
+        assert!(!is_backup_name("2023-12-01"));
+        assert!(!is_backup_name("20231201T000000"));
+    }
+
+    #[test]
+    fn retention() {
+        let keep = |n| NonZeroUsize::new(n).unwrap();
+        let backups = BTreeMap::from([
+            ("20231201T000000Z", true),
+            ("20231202T000000Z", false),
+            ("20231203T000000Z", true),
+            ("20231204T000000Z", true),
+            ("20231205T000000Z", false),
+        ]);
+        // incomplete backups older than the ones kept are removed, the one being taken is not
+        assert_eq!(expired(&backups, keep(2)), vec!["20231201T000000Z", "20231202T000000Z"]);
+        assert_eq!(
+            expired(&backups, keep(1)),
+            vec!["20231201T000000Z", "20231202T000000Z", "20231203T000000Z"]
+        );
+        assert!(expired(&backups, keep(3)).is_empty());
+        assert!(expired(&backups, keep(4)).is_empty());
+    }
+
+    #[test]
+    fn manifest_version() {
+        let manifest = Manifest {
+            version: MANIFEST_VERSION + 1,
+            created: OffsetDateTime::UNIX_EPOCH,
+            entries: vec![],
+        };
+        assert_eq!(manifest.verify(), Err(VerifyError::Version(MANIFEST_VERSION + 1)));
+
+        let manifest: Manifest = serde_json::from_value(serde_json::json!({
+            "version": MANIFEST_VERSION,
+            "created": "2023-12-01T00:00:00Z",
+            "entries": [{"index": "vexination", "name": "vex", "size": 1, "sha256": ""}],
+        }))
+        .unwrap();
+        assert_eq!(manifest.verify(), Ok(()));
+        assert_eq!(manifest.entries[0].index, IndexKind::Vexination);
+
+        // backups taken before deltas were backed up can still be restored
+        let manifest: Manifest = serde_json::from_value(serde_json::json!({
+            "version": 1,
+            "created": "2023-12-01T00:00:00Z",
+            "entries": [{"index": "bombastic", "name": "sbom", "size": 1, "sha256": ""}],
+        }))
+        .unwrap();
+        assert_eq!(manifest.verify(), Ok(()));
+        assert_eq!(manifest.entries[0].deltas, None);
+    }
+}
@@ -1,6 +1,11 @@
 use std::process::ExitCode;
 
+mod audit;
 mod delete;
+pub mod devmode;
+pub mod index;
+mod loadtest;
+mod pause;
 mod reindex;
 mod upload;
 
@@ -9,10 +14,16 @@ mod upload;
 pub enum Command {
     #[command(subcommand)]
     Reindex(reindex::Reindex),
+    Pause(pause::Pause),
+    Resume(pause::Resume),
+    #[command(subcommand)]
+    Audit(audit::Audit),
     #[command(subcommand)]
     Delete(delete::Delete),
     #[command(subcommand)]
     Upload(upload::Upload),
+    #[command(name = "loadtest")]
+    LoadTest(loadtest::LoadTest),
 }
 
 impl Command {
@@ -22,8 +33,12 @@ impl Command {
         }
         match self {
             Self::Reindex(reindex) => reindex.run().await,
+            Self::Pause(pause) => pause.run().await,
+            Self::Resume(resume) => resume.run().await,
+            Self::Audit(audit) => audit.run().await,
             Self::Delete(delete) => delete.run().await,
             Self::Upload(upload) => upload.run().await,
+            Self::LoadTest(loadtest) => loadtest.run().await,
         }
     }
 }
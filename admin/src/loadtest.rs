@@ -0,0 +1,544 @@
+use anyhow::bail;
+use futures::{stream, StreamExt};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{
+    path::PathBuf,
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use trustification_auth::client::OpenIdTokenProviderConfigArguments;
+use trustification_client::{Client, Endpoints, RetryPolicy, SearchOptions};
+use trustification_infrastructure::endpoint::{self, Endpoint};
+use url::Url;
+
+/// Creation time of the first synthetic SBOM, the following ones are created a minute apart.
+const CREATED_BASE: i64 = 1_672_531_200;
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Run a load test against Bombastic",
+    long_about = "Upload a corpus of synthetic SBOMs to Bombastic, then run a mix of uploads and searches against it, \
+    reporting the throughput and latency percentiles. Exits with a failure if a performance budget is exceeded.",
+    args_conflicts_with_subcommands = true
+)]
+pub struct LoadTest {
+    /// URL of the Bombastic instance
+    #[arg(short = 'u', long = "url", default_value_t = endpoint::Bombastic::url())]
+    pub url: Url,
+
+    /// Number of SBOMs of the corpus uploaded before the mixed phase
+    #[arg(long = "sboms", default_value_t = 100)]
+    pub sboms: usize,
+
+    /// Number of packages of each SBOM
+    #[arg(long = "packages", default_value_t = 50)]
+    pub packages: usize,
+
+    /// Number of operations of the mixed phase
+    #[arg(long = "operations", default_value_t = 1000)]
+    pub operations: usize,
+
+    /// Share of searches in the mixed phase, the other operations upload further SBOMs
+    #[arg(long = "search-ratio", default_value_t = 0.9)]
+    pub search_ratio: f64,
+
+    /// Number of concurrent requests
+    #[arg(short = 'c', long = "concurrency", default_value_t = 8)]
+    pub concurrency: usize,
+
+    /// Time to wait after uploading the corpus, for the indexer to catch up
+    #[arg(long = "settle-time", default_value = "0s")]
+    pub settle_time: humantime::Duration,
+
+    /// Seed of the generated SBOMs and operations, the same seed results in the same load
+    #[arg(long = "seed", default_value_t = 0)]
+    pub seed: u64,
+
+    /// Prefix of the identifiers and package names of the generated SBOMs
+    #[arg(long = "prefix", default_value = "loadtest")]
+    pub prefix: String,
+
+    /// Write the report as JSON to this file
+    #[arg(long = "report")]
+    pub report: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub budget: Budget,
+
+    /// OIDC parameters
+    #[command(flatten)]
+    pub oidc: OpenIdTokenProviderConfigArguments,
+
+    /// Development mode
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+}
+
+/// Performance budget of a load test, unset limits are not checked.
+#[derive(clap::Args, Debug, Default)]
+#[command(next_help_heading = "Performance budget")]
+pub struct Budget {
+    /// Maximum 99th percentile latency of uploads
+    #[arg(long = "max-upload-p99")]
+    pub max_upload_p99: Option<humantime::Duration>,
+
+    /// Maximum 99th percentile latency of searches
+    #[arg(long = "max-search-p99")]
+    pub max_search_p99: Option<humantime::Duration>,
+
+    /// Minimum throughput of the mixed phase, in operations per second
+    #[arg(long = "min-throughput")]
+    pub min_throughput: Option<f64>,
+
+    /// Maximum share of failed operations
+    #[arg(long = "max-error-rate")]
+    pub max_error_rate: Option<f64>,
+}
+
+impl Budget {
+    /// Check a report against the budget, returning the violations.
+    fn check(&self, report: &Report) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        for (kind, max) in [
+            (Kind::Upload, &self.max_upload_p99),
+            (Kind::Search, &self.max_search_p99),
+        ] {
+            let (Some(max), Some(total)) = (max, report.total(kind)) else {
+                continue;
+            };
+            let max = millis(**max);
+            if total.p99_ms > max {
+                violations.push(format!(
+                    "p99 latency of {kind} is {:.1} ms, exceeding {max:.1} ms",
+                    total.p99_ms
+                ));
+            }
+        }
+
+        if let (Some(min), Some(phase)) = (self.min_throughput, report.phase(Phase::Mixed)) {
+            if phase.throughput < min {
+                violations.push(format!(
+                    "throughput is {:.1} operations per second, below {min:.1}",
+                    phase.throughput
+                ));
+            }
+        }
+
+        if let Some(max) = self.max_error_rate {
+            let count: usize = report.totals.iter().map(|t| t.count).sum();
+            let errors: usize = report.totals.iter().map(|t| t.errors).sum();
+            let rate = if count > 0 { errors as f64 / count as f64 } else { 0.0 };
+            if rate > max {
+                violations.push(format!("error rate is {rate:.4}, exceeding {max:.4}"));
+            }
+        }
+
+        violations
+    }
+}
+
+impl LoadTest {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        if !(0.0..=1.0).contains(&self.search_ratio) {
+            bail!("The search ratio must be between 0 and 1");
+        }
+
+        let provider = self.oidc.clone().into_provider_or_devmode(self.devmode).await?;
+        let endpoints = Endpoints {
+            bombastic: Some(self.url.clone()),
+            ..Default::default()
+        };
+        // retries would hide the latency of failing requests
+        let client = Client::new(reqwest::Client::new(), endpoints, provider).with_retry(RetryPolicy::none());
+
+        let mut phases = Vec::new();
+
+        log::info!("Uploading {} SBOMs", self.sboms);
+        let corpus = (0..self.sboms).map(Operation::Upload).collect();
+        phases.push(self.run_phase(&client, Phase::Corpus, corpus).await);
+
+        let settle_time = *self.settle_time;
+        if !settle_time.is_zero() {
+            log::info!("Waiting {} for the indexer", self.settle_time);
+            tokio::time::sleep(settle_time).await;
+        }
+
+        log::info!("Running {} operations", self.operations);
+        let mixed = self.mixed_operations();
+        phases.push(self.run_phase(&client, Phase::Mixed, mixed).await);
+
+        let report = Report::new(&phases);
+        report.print();
+        if let Some(path) = &self.report {
+            std::fs::write(path, serde_json::to_vec_pretty(&report)?)?;
+        }
+
+        let violations = self.budget.check(&report);
+        if violations.is_empty() {
+            Ok(ExitCode::SUCCESS)
+        } else {
+            for violation in violations {
+                eprintln!("Budget exceeded: {violation}");
+            }
+            Ok(ExitCode::FAILURE)
+        }
+    }
+
+    /// Number of distinct package names, each of them is shared by about ten SBOMs.
+    fn vocabulary(&self) -> usize {
+        (self.sboms.max(1) * self.packages / 10).max(1)
+    }
+
+    fn mixed_operations(&self) -> Vec<Operation> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut next = self.sboms;
+        (0..self.operations)
+            .map(|_| {
+                if rng.gen_bool(self.search_ratio) {
+                    let k = rng.gen_range(0..self.vocabulary());
+                    Operation::Search(format!("{}-component-{k}", self.prefix))
+                } else {
+                    next += 1;
+                    Operation::Upload(next - 1)
+                }
+            })
+            .collect()
+    }
+
+    async fn run_phase(&self, client: &Client, phase: Phase, operations: Vec<Operation>) -> PhaseResult {
+        let start = Instant::now();
+        let samples = stream::iter(operations)
+            .map(|operation| self.execute(client, operation))
+            .buffer_unordered(self.concurrency.max(1))
+            .collect()
+            .await;
+        PhaseResult {
+            phase,
+            duration: start.elapsed(),
+            samples,
+        }
+    }
+
+    async fn execute(&self, client: &Client, operation: Operation) -> Sample {
+        match operation {
+            Operation::Upload(index) => {
+                let (id, sbom) = synthetic_sbom(&self.prefix, self.seed, index, self.packages, self.vocabulary());
+                let data = serde_json::to_vec(&sbom).unwrap_or_default();
+                let start = Instant::now();
+                let result = match client.bombastic() {
+                    Ok(bombastic) => bombastic.upload_sbom(Some(&id), data.into()).await.map(|_| ()),
+                    Err(e) => Err(e),
+                };
+                Sample::new(Kind::Upload, start.elapsed(), result)
+            }
+            Operation::Search(q) => {
+                let start = Instant::now();
+                let result = match client.bombastic() {
+                    Ok(bombastic) => bombastic
+                        .search_sbom(&q, 0, 10, &SearchOptions::default())
+                        .await
+                        .map(|_| ()),
+                    Err(e) => Err(e),
+                };
+                Sample::new(Kind::Search, start.elapsed(), result)
+            }
+        }
+    }
+}
+
+/// Generate a CycloneDX SBOM, the same arguments always result in the same SBOM.
+fn synthetic_sbom(prefix: &str, seed: u64, index: usize, packages: usize, vocabulary: usize) -> (String, Value) {
+    let mut rng = StdRng::seed_from_u64(seed.rotate_left(32) ^ index as u64);
+    let id = format!("{prefix}-{index}");
+
+    let components = (0..packages)
+        .map(|n| {
+            let name = format!("{prefix}-component-{}", rng.gen_range(0..vocabulary.max(1)));
+            let version = format!("1.{}.{}", rng.gen_range(0..10), rng.gen_range(0..10));
+            json!({
+                "type": "library",
+                "bom-ref": format!("component-{n}"),
+                "name": name,
+                "version": version,
+                "purl": format!("pkg:generic/{prefix}/{name}@{version}"),
+            })
+        })
+        .collect::<Vec<_>>();
+    let depends_on = (0..packages).map(|n| format!("component-{n}")).collect::<Vec<_>>();
+    let created = OffsetDateTime::from_unix_timestamp(CREATED_BASE + index as i64 * 60)
+        .ok()
+        .and_then(|created| created.format(&Rfc3339).ok());
+
+    let sbom = json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.4",
+        "serialNumber": format!("urn:uuid:{:08x}-0000-4000-8000-{:012x}", seed as u32, index),
+        "version": 1,
+        "metadata": {
+            "timestamp": created,
+            "component": {
+                "type": "application",
+                "bom-ref": "root",
+                "name": id,
+                "version": "1.0.0",
+                "purl": format!("pkg:generic/{prefix}/{id}@1.0.0"),
+            }
+        },
+        "components": components,
+        "dependencies": [
+            {
+                "ref": "root",
+                "dependsOn": depends_on,
+            }
+        ]
+    });
+    (id, sbom)
+}
+
+enum Operation {
+    /// Upload the synthetic SBOM of an index
+    Upload(usize),
+    /// Search for a query
+    Search(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    Upload,
+    Search,
+}
+
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Upload => write!(f, "upload"),
+            Self::Search => write!(f, "search"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Phase {
+    Corpus,
+    Mixed,
+}
+
+struct Sample {
+    kind: Kind,
+    latency: Duration,
+    ok: bool,
+}
+
+impl Sample {
+    fn new(kind: Kind, latency: Duration, result: Result<(), trustification_client::Error>) -> Self {
+        if let Err(e) = &result {
+            log::debug!("Failed {kind}: {e}");
+        }
+        Self {
+            kind,
+            latency,
+            ok: result.is_ok(),
+        }
+    }
+}
+
+struct PhaseResult {
+    phase: Phase,
+    duration: Duration,
+    samples: Vec<Sample>,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    phases: Vec<PhaseReport>,
+    /// Operations of all phases
+    totals: Vec<OperationReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct PhaseReport {
+    phase: Phase,
+    duration_ms: f64,
+    /// Operations per second
+    throughput: f64,
+    operations: Vec<OperationReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct OperationReport {
+    kind: Kind,
+    count: usize,
+    errors: usize,
+    p50_ms: f64,
+    p90_ms: f64,
+    p99_ms: f64,
+    max_ms: f64,
+}
+
+impl Report {
+    fn new(phases: &[PhaseResult]) -> Self {
+        let all = phases.iter().flat_map(|p| &p.samples).collect::<Vec<_>>();
+        Self {
+            phases: phases
+                .iter()
+                .map(|p| {
+                    let seconds = p.duration.as_secs_f64();
+                    PhaseReport {
+                        phase: p.phase,
+                        duration_ms: seconds * 1000.0,
+                        throughput: if seconds > 0.0 {
+                            p.samples.len() as f64 / seconds
+                        } else {
+                            0.0
+                        },
+                        operations: OperationReport::by_kind(&p.samples.iter().collect::<Vec<_>>()),
+                    }
+                })
+                .collect(),
+            totals: OperationReport::by_kind(&all),
+        }
+    }
+
+    fn phase(&self, phase: Phase) -> Option<&PhaseReport> {
+        self.phases.iter().find(|p| p.phase == phase)
+    }
+
+    fn total(&self, kind: Kind) -> Option<&OperationReport> {
+        self.totals.iter().find(|t| t.kind == kind)
+    }
+
+    fn print(&self) {
+        println!(
+            "{:<8} {:<8} {:>8} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            "Phase", "Kind", "Count", "Errors", "p50 ms", "p90 ms", "p99 ms", "max ms", "ops/s"
+        );
+        for phase in &self.phases {
+            for op in &phase.operations {
+                println!(
+                    "{:<8} {:<8} {:>8} {:>8} {:>10.1} {:>10.1} {:>10.1} {:>10.1} {:>10.1}",
+                    format!("{:?}", phase.phase).to_lowercase(),
+                    op.kind.to_string(),
+                    op.count,
+                    op.errors,
+                    op.p50_ms,
+                    op.p90_ms,
+                    op.p99_ms,
+                    op.max_ms,
+                    phase.throughput
+                );
+            }
+        }
+    }
+}
+
+impl OperationReport {
+    fn by_kind(samples: &[&Sample]) -> Vec<Self> {
+        [Kind::Upload, Kind::Search]
+            .into_iter()
+            .filter_map(|kind| {
+                let mut latencies = samples
+                    .iter()
+                    .filter(|s| s.kind == kind)
+                    .map(|s| s.latency)
+                    .collect::<Vec<_>>();
+                if latencies.is_empty() {
+                    return None;
+                }
+                latencies.sort();
+                Some(Self {
+                    kind,
+                    count: latencies.len(),
+                    errors: samples.iter().filter(|s| s.kind == kind && !s.ok).count(),
+                    p50_ms: millis(percentile(&latencies, 50.0)),
+                    p90_ms: millis(percentile(&latencies, 90.0)),
+                    p99_ms: millis(percentile(&latencies, 99.0)),
+                    max_ms: millis(latencies[latencies.len() - 1]),
+                })
+            })
+            .collect()
+    }
+}
+
+/// The nearest-rank percentile of sorted, non-empty latencies.
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample(kind: Kind, millis: u64, ok: bool) -> Sample {
+        Sample {
+            kind,
+            latency: Duration::from_millis(millis),
+            ok,
+        }
+    }
+
+    #[test]
+    fn test_percentile() {
+        let latencies = (1..=100).map(Duration::from_millis).collect::<Vec<_>>();
+        assert_eq!(percentile(&latencies, 50.0), Duration::from_millis(50));
+        assert_eq!(percentile(&latencies, 99.0), Duration::from_millis(99));
+        assert_eq!(percentile(&latencies, 100.0), Duration::from_millis(100));
+        assert_eq!(percentile(&latencies[..1], 99.0), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn test_synthetic_sbom() {
+        let (id, sbom) = synthetic_sbom("test", 1, 7, 20, 50);
+        assert_eq!(id, "test-7");
+        assert_eq!(sbom["components"].as_array().unwrap().len(), 20);
+        assert_eq!(sbom["metadata"]["timestamp"], "2023-01-01T00:07:00Z");
+        assert_eq!(sbom, synthetic_sbom("test", 1, 7, 20, 50).1);
+        assert_ne!(sbom, synthetic_sbom("test", 1, 8, 20, 50).1);
+        assert_ne!(sbom, synthetic_sbom("test", 2, 7, 20, 50).1);
+    }
+
+    #[test]
+    fn test_budget() {
+        let phases = vec![PhaseResult {
+            phase: Phase::Mixed,
+            duration: Duration::from_secs(2),
+            samples: vec![
+                sample(Kind::Upload, 200, true),
+                sample(Kind::Search, 10, true),
+                sample(Kind::Search, 30, true),
+                sample(Kind::Search, 20, false),
+            ],
+        }];
+        let report = Report::new(&phases);
+        assert_eq!(report.phase(Phase::Mixed).unwrap().throughput, 2.0);
+        assert_eq!(report.total(Kind::Search).unwrap().errors, 1);
+
+        assert!(Budget::default().check(&report).is_empty());
+
+        let budget = Budget {
+            max_upload_p99: Some(Duration::from_millis(500).into()),
+            max_search_p99: Some(Duration::from_millis(50).into()),
+            min_throughput: Some(1.0),
+            max_error_rate: Some(0.5),
+        };
+        assert!(budget.check(&report).is_empty());
+
+        let budget = Budget {
+            max_upload_p99: Some(Duration::from_millis(100).into()),
+            max_search_p99: Some(Duration::from_millis(25).into()),
+            min_throughput: Some(10.0),
+            max_error_rate: Some(0.1),
+        };
+        assert_eq!(budget.check(&report).len(), 4);
+    }
+}
@@ -0,0 +1,86 @@
+use reqwest::StatusCode;
+use std::process::ExitCode;
+use trustification_auth::client::{OpenIdTokenProviderConfigArguments, TokenInjector};
+use trustification_common::tls::ClientConfig;
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Pause consuming events of an indexer, like during maintenance",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Pause {
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    /// The authorized admin endpoint of the indexer, on its infrastructure port
+    #[arg(short = 'i', long = "indexer", default_value = "http://localhost:9010/admin/pause")]
+    pub indexer_url: String,
+
+    /// Why the indexer is paused, reported by its status
+    #[arg(long = "reason")]
+    pub reason: Option<String>,
+
+    #[command(flatten)]
+    pub client: ClientConfig,
+
+    /// OIDC parameters
+    #[command(flatten)]
+    pub oidc: OpenIdTokenProviderConfigArguments,
+}
+
+impl Pause {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let client = self.client.build_client()?;
+        let provider = self.oidc.clone().into_provider_or_devmode(self.devmode).await?;
+        let request = client
+            .post(self.indexer_url)
+            .json(&serde_json::json!({ "reason": self.reason }))
+            .inject_token(&provider)
+            .await?;
+        send(request, "Pausing").await
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Resume consuming events of a paused indexer",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Resume {
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    /// The authorized admin endpoint of the indexer, on its infrastructure port
+    #[arg(short = 'i', long = "indexer", default_value = "http://localhost:9010/admin/resume")]
+    pub indexer_url: String,
+
+    #[command(flatten)]
+    pub client: ClientConfig,
+
+    /// OIDC parameters
+    #[command(flatten)]
+    pub oidc: OpenIdTokenProviderConfigArguments,
+}
+
+impl Resume {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let client = self.client.build_client()?;
+        let provider = self.oidc.clone().into_provider_or_devmode(self.devmode).await?;
+        let request = client.post(self.indexer_url).inject_token(&provider).await?;
+        send(request, "Resuming").await
+    }
+}
+
+async fn send(request: reqwest::RequestBuilder, action: &str) -> anyhow::Result<ExitCode> {
+    let response = request.send().await?;
+    match response.status() {
+        StatusCode::ACCEPTED => {
+            println!("{action} requested, the status of the indexer reports once it's done");
+            Ok(ExitCode::SUCCESS)
+        }
+        status => {
+            println!("Error {}: {status} {:?}", action.to_lowercase(), response.text().await);
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
@@ -3,6 +3,7 @@ use serde_json::Value;
 use std::process::ExitCode;
 
 use reqwest::StatusCode;
+use trustification_auth::client::{OpenIdTokenProviderConfigArguments, TokenInjector};
 use trustification_common::tls::ClientConfig;
 
 /// Reindex
@@ -27,20 +28,29 @@ pub struct ReindexStart {
     #[arg(long = "devmode", default_value_t = false)]
     pub devmode: bool,
 
-    #[arg(short = 'i', long = "indexer", default_value = "http://localhost:8080/")]
+    /// The authorized admin endpoint of the indexer, on its infrastructure port
+    #[arg(short = 'i', long = "indexer", default_value = "http://localhost:9010/admin/reindex")]
     pub indexer_url: String,
 
     #[command(flatten)]
     pub client: ClientConfig,
+
+    /// OIDC parameters
+    #[command(flatten)]
+    pub oidc: OpenIdTokenProviderConfigArguments,
 }
 
 impl ReindexStart {
     pub async fn run(self) -> anyhow::Result<ExitCode> {
         let client = self.client.build_client()?;
-        match client.post(self.indexer_url).send().await {
+        let provider = self.oidc.clone().into_provider_or_devmode(self.devmode).await?;
+        let request = client.post(self.indexer_url).inject_token(&provider).await?;
+        match request.send().await {
             Ok(response) => {
-                if response.status() == StatusCode::OK {
+                if matches!(response.status(), StatusCode::OK | StatusCode::ACCEPTED) {
                     println!("Reindexing started successfully");
+                } else if response.status() == StatusCode::CONFLICT {
+                    println!("Reindexing is already running");
                 } else {
                     let body = response.text().await;
                     println!("Error starting reindexing: {:?}", body);
@@ -61,17 +71,23 @@ pub struct ReindexStatus {
     #[arg(long = "devmode", default_value_t = false)]
     pub devmode: bool,
 
-    #[arg(short = 'i', long = "indexer", default_value = "http://localhost:9010/reindex")]
+    #[arg(short = 'i', long = "indexer", default_value = "http://localhost:9010/admin/reindex")]
     pub indexer_url: String,
 
     #[command(flatten)]
     pub client: ClientConfig,
+
+    /// OIDC parameters
+    #[command(flatten)]
+    pub oidc: OpenIdTokenProviderConfigArguments,
 }
 
 impl ReindexStatus {
     pub async fn run(self) -> anyhow::Result<ExitCode> {
         let client = self.client.build_client()?;
-        let status = client.get(self.indexer_url).send().await?.json::<Value>().await?;
+        let provider = self.oidc.clone().into_provider_or_devmode(self.devmode).await?;
+        let request = client.get(self.indexer_url).inject_token(&provider).await?;
+        let status = request.send().await?.json::<Value>().await?;
 
         println!("{}", to_colored_json_auto(&status)?);
 
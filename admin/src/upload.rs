@@ -115,7 +115,7 @@ impl VexinationUpload {
     }
 }
 
-async fn upload(
+pub(crate) async fn upload(
     url: &str,
     client: &reqwest::Client,
     provider: &impl TokenProvider,
@@ -1,4 +1,6 @@
+pub mod multi_get;
 pub mod search;
+pub mod version;
 
 pub trait Apply<T> {
     fn apply(self, value: &T) -> Self;
@@ -0,0 +1,44 @@
+//! Fetching multiple documents with a single request.
+
+/// Request to fetch multiple documents by their identifiers.
+#[derive(utoipa::ToSchema, Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct MultiGetRequest {
+    /// Identifiers of the documents to fetch
+    pub ids: Vec<String>,
+}
+
+/// Result of fetching a single document, returned as one line of the (NDJSON) response.
+#[derive(utoipa::ToSchema, Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct MultiGetEntry {
+    /// Identifier of the document
+    pub id: String,
+    /// HTTP status code for fetching this document
+    pub status: u16,
+    /// The document, if it was found
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Object)]
+    pub document: Option<serde_json::Value>,
+    /// The error, if the document could not be fetched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl MultiGetEntry {
+    pub fn found(id: String, document: serde_json::Value) -> Self {
+        Self {
+            id,
+            status: 200,
+            document: Some(document),
+            error: None,
+        }
+    }
+
+    pub fn failed(id: String, status: u16, error: impl Into<String>) -> Self {
+        Self {
+            id,
+            status,
+            document: None,
+            error: Some(error.into()),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+/// Description of a qualifier supported by the search query language of an index.
+#[derive(utoipa::ToSchema, Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct SearchField {
+    /// The qualifier, as used in a query (e.g. `qualifier:value`)
+    pub qualifier: String,
+    /// A short description of what the qualifier matches
+    pub description: String,
+    /// The type of value the qualifier accepts
+    #[serde(rename = "type")]
+    pub r#type: SearchFieldType,
+    /// If the qualifier can be used for sorting (`sort:qualifier`)
+    pub sortable: bool,
+    /// If the qualifier can be used as a scope for free text (`value in:qualifier`)
+    pub scope: bool,
+    /// An example query using the qualifier
+    pub example: String,
+}
+
+/// The type of value accepted by a search qualifier.
+#[derive(utoipa::ToSchema, Copy, Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchFieldType {
+    /// An exact string value
+    String,
+    /// Full text, matching individual words
+    Text,
+    /// A signed integer
+    Integer,
+    /// A floating point number
+    Number,
+    /// A date, in the format `YYYY-MM-DD`
+    Date,
+    /// A boolean value
+    Boolean,
+    /// A predicate without a value (`is:qualifier`)
+    Predicate,
+}
@@ -0,0 +1,26 @@
+use time::OffsetDateTime;
+
+/// How current a search index is, relative to the documents in storage.
+#[derive(utoipa::ToSchema, Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct IndexFreshness {
+    /// Time of the last commit of the index being served, in RFC3339 format
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    #[schema(value_type = Option<String>)]
+    pub index_updated_at: Option<OffsetDateTime>,
+    /// Time the index was last synced successfully from storage, in RFC3339 format
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    #[schema(value_type = Option<String>)]
+    pub synced_at: Option<OffsetDateTime>,
+    /// Seconds since the last commit of the index being served
+    pub age_seconds: Option<i64>,
+}
+
+impl IndexFreshness {
+    pub fn new(index_updated_at: Option<OffsetDateTime>, synced_at: Option<OffsetDateTime>) -> Self {
+        Self {
+            index_updated_at,
+            synced_at,
+            age_seconds: index_updated_at.map(|t| (OffsetDateTime::now_utc() - t).whole_seconds()),
+        }
+    }
+}
@@ -1,11 +1,15 @@
+mod field;
+mod freshness;
 mod result;
 
+pub use field::*;
+pub use freshness::*;
 pub use result::*;
 use utoipa::IntoParams;
 
 use crate::Apply;
 
-#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, IntoParams)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize, IntoParams)]
 pub struct SearchOptions {
     /// Return index "explain" output
     #[serde(default)]
@@ -0,0 +1,104 @@
+use std::fmt::{Display, Formatter};
+use utoipa::openapi::OpenApi;
+
+/// The path prefix of the routes declared in the OpenAPI documents of the handlers.
+const DOCUMENTED_PREFIX: &str = "/api/v1/";
+
+/// A version of the HTTP API of a service.
+///
+/// The version is selected by the path prefix (`/api/v1`, `/api/v2`), all versions are served by the same handlers.
+/// Handlers which change their response in a newer version extract the version using `web::Data<ApiVersion>`, which
+/// is provided by each versioned scope.
+///
+/// Responses carry the served version in the `API-Version` header. Deprecated versions additionally announce their
+/// deprecation with the `Deprecation` header and link to the latest version. A version is only removed after it has
+/// been deprecated for at least one release.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    /// All served versions, oldest first.
+    pub const ALL: [ApiVersion; 2] = [ApiVersion::V1, ApiVersion::V2];
+
+    /// The latest version, which new clients should use.
+    pub const LATEST: ApiVersion = ApiVersion::V2;
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::V1 => "v1",
+            Self::V2 => "v2",
+        }
+    }
+
+    /// The path prefix of the routes of this version, like `/api/v1`.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Self::V1 => "/api/v1",
+            Self::V2 => "/api/v2",
+        }
+    }
+
+    /// The URL of the OpenAPI document of this version, the one of v1 is kept for existing clients.
+    pub fn openapi_url(&self) -> &'static str {
+        match self {
+            Self::V1 => "/openapi.json",
+            Self::V2 => "/openapi/v2.json",
+        }
+    }
+
+    /// Whether clients should migrate to the latest version.
+    pub fn is_deprecated(&self) -> bool {
+        match self {
+            Self::V1 | Self::V2 => false,
+        }
+    }
+
+    /// Adapt the OpenAPI document of the handlers, which declare their paths for `/api/v1`, to this version.
+    pub fn openapi(&self, mut openapi: OpenApi) -> OpenApi {
+        let paths = std::mem::take(&mut openapi.paths.paths);
+        openapi.paths.paths = paths
+            .into_iter()
+            .map(|(path, item)| match path.strip_prefix(DOCUMENTED_PREFIX) {
+                Some(rest) => (format!("{}/{rest}", self.prefix()), item),
+                None => (path, item),
+            })
+            .collect();
+        openapi.info.version = format!("{} ({})", openapi.info.version, self.as_str());
+        openapi
+    }
+}
+
+impl Display for ApiVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utoipa::openapi::{path::PathItemType, OpenApiBuilder, PathItem, PathsBuilder};
+
+    #[test]
+    fn openapi_paths() {
+        let openapi = OpenApiBuilder::new()
+            .paths(
+                PathsBuilder::new()
+                    .path("/api/v1/sbom", PathItem::new(PathItemType::Get, Default::default()))
+                    .path("/health", PathItem::new(PathItemType::Get, Default::default())),
+            )
+            .build();
+
+        let v1 = ApiVersion::V1.openapi(openapi.clone());
+        assert!(v1.paths.paths.contains_key("/api/v1/sbom"));
+
+        let v2 = ApiVersion::V2.openapi(openapi);
+        assert!(v2.paths.paths.contains_key("/api/v2/sbom"));
+        assert!(v2.paths.paths.contains_key("/health"));
+        assert!(!v2.paths.paths.contains_key("/api/v1/sbom"));
+        assert!(v2.info.version.ends_with("(v2)"));
+    }
+}
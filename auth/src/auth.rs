@@ -30,6 +30,16 @@ pub struct AuthConfigArguments {
     )]
     pub config: Option<PathBuf>,
 
+    /// Allow reading without authentication. Safe requests (`GET` and `HEAD`) without a token are processed as an
+    /// anonymous user, which is granted all read permissions. Writes still require authentication.
+    #[arg(
+        id = "auth-anonymous-read",
+        default_value_t = false,
+        long = "auth-anonymous-read",
+        env = "AUTH_ANONYMOUS_READ"
+    )]
+    pub anonymous_read: bool,
+
     #[command(flatten)]
     pub clients: SingleAuthenticatorClientConfig,
 }
@@ -39,6 +49,10 @@ pub struct AuthConfig {
     #[serde(default, skip_serializing_if = "is_default")]
     pub disabled: bool,
 
+    /// Allow reading without authentication, see [`AuthConfigArguments::anonymous_read`]
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub anonymous_read: bool,
+
     pub authentication: AuthenticatorConfig,
 
     #[serde(default)]
@@ -56,33 +70,46 @@ impl AuthConfigArguments {
             return Ok(None);
         }
 
+        let mut anonymous_read = self.anonymous_read;
+
         // check for devmode
-        if devmode {
+        let (mut authn, mut authz) = if devmode {
             log::warn!("Running in developer mode");
-            return Ok(Some((AuthenticatorConfig::devmode(), Default::default())));
-        }
+            (AuthenticatorConfig::devmode(), Default::default())
+        } else {
+            match self.config {
+                Some(config) => {
+                    let AuthConfig {
+                        disabled,
+                        anonymous_read: config_anonymous_read,
+                        authentication,
+                        authorization,
+                    } = serde_yaml::from_reader(std::fs::File::open(config)?)?;
 
-        Ok(Some(match self.config {
-            Some(config) => {
-                let AuthConfig {
-                    disabled,
-                    authentication,
-                    authorization,
-                } = serde_yaml::from_reader(std::fs::File::open(config)?)?;
+                    if disabled {
+                        return Ok(None);
+                    }
 
-                if disabled {
-                    return Ok(None);
+                    anonymous_read |= config_anonymous_read;
+                    (authentication, authorization)
                 }
+                None => {
+                    let authn = AuthenticatorConfig {
+                        clients: self.clients.expand().collect(),
+                        anonymous_read: false,
+                    };
 
-                (authentication, authorization)
+                    (authn, Default::default())
+                }
             }
-            None => {
-                let authn = AuthenticatorConfig {
-                    clients: self.clients.expand().collect(),
-                };
+        };
 
-                (authn, Default::default())
-            }
-        }))
+        if anonymous_read {
+            log::warn!("Anonymous read access is enabled");
+        }
+        authn.anonymous_read = anonymous_read;
+        authz.anonymous_read = anonymous_read;
+
+        Ok(Some((authn, authz)))
     }
 }
@@ -1,8 +1,11 @@
 use super::user::UserInformation;
 use super::Authenticator;
-use actix_http::HttpMessage;
-use actix_web::dev::ServiceRequest;
+use actix_http::{header::AUTHORIZATION, HttpMessage, Method};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::FromRequest;
 use actix_web_httpauth::extractors::bearer::BearerAuth;
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use std::rc::Rc;
 use std::sync::Arc;
 
 pub async fn openid_validator(
@@ -20,3 +23,141 @@ pub async fn openid_validator(
         Err(err) => Err((err.into(), req)),
     }
 }
+
+/// Check if a request may pass without authentication, when anonymous reads are allowed.
+///
+/// Only safe requests without any credentials qualify. Requests providing a token are always authenticated, so that
+/// an invalid token is reported instead of being silently ignored.
+pub fn is_anonymous_read(req: &ServiceRequest) -> bool {
+    matches!(*req.method(), Method::GET | Method::HEAD) && !req.headers().contains_key(AUTHORIZATION)
+}
+
+/// Middleware authenticating requests using a bearer token.
+///
+/// If the authenticator allows anonymous reads, requests qualifying by [`is_anonymous_read`] are passed on as
+/// [`UserInformation::Anonymous`].
+#[derive(Clone)]
+pub struct Authentication(Arc<Authenticator>);
+
+impl Authentication {
+    pub fn new(authenticator: Arc<Authenticator>) -> Self {
+        Self(authenticator)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Authentication
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = AuthenticationMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AuthenticationMiddleware {
+            service: Rc::new(service),
+            authenticator: self.0.clone(),
+        }))
+    }
+}
+
+pub struct AuthenticationMiddleware<S> {
+    service: Rc<S>,
+    authenticator: Arc<Authenticator>,
+}
+
+impl<S, B> Service<ServiceRequest> for AuthenticationMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let authenticator = self.authenticator.clone();
+
+        Box::pin(async move {
+            if authenticator.anonymous_read && is_anonymous_read(&req) {
+                req.extensions_mut().insert(UserInformation::Anonymous);
+                return service.call(req).await;
+            }
+
+            let auth = BearerAuth::extract(req.request()).await?;
+            let req = openid_validator(req, auth, authenticator)
+                .await
+                .map_err(|(err, _)| err)?;
+            service.call(req).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{http::StatusCode, test, web, App, HttpResponse};
+
+    async fn user(user: UserInformation) -> HttpResponse {
+        match user {
+            UserInformation::Authenticated(details) => HttpResponse::Ok().body(details.id),
+            UserInformation::Anonymous => HttpResponse::Ok().body("anonymous"),
+        }
+    }
+
+    #[actix_web::test]
+    async fn anonymous_read() {
+        let authenticator = Arc::new(Authenticator {
+            clients: vec![],
+            anonymous_read: true,
+        });
+        let app = test::init_service(
+            App::new()
+                .wrap(Authentication::new(authenticator))
+                .route("/", web::get().to(user))
+                .route("/", web::post().to(user)),
+        )
+        .await;
+
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(test::read_body(resp).await, "anonymous");
+
+        // writes still require a token
+        let resp = test::call_service(&app, test::TestRequest::post().uri("/").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // a provided token must be valid
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((AUTHORIZATION, "Bearer invalid"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn anonymous_read_disabled() {
+        let authenticator = Arc::new(Authenticator {
+            clients: vec![],
+            anonymous_read: false,
+        });
+        let app = test::init_service(
+            App::new()
+                .wrap(Authentication::new(authenticator))
+                .route("/", web::get().to(user)),
+        )
+        .await;
+
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+}
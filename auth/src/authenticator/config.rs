@@ -24,6 +24,10 @@ pub struct AuthenticatorConfigArguments {
 #[serde(rename_all = "camelCase")]
 pub struct AuthenticatorConfig {
     pub clients: Vec<AuthenticatorClientConfig>,
+
+    /// Allow reading requests without a token, set from the [`crate::auth::AuthConfig`]
+    #[serde(skip)]
+    pub anonymous_read: bool,
 }
 
 impl AuthenticatorConfig {
@@ -44,6 +48,7 @@ impl AuthenticatorConfig {
                     tls_ca_certificates: Default::default(),
                 })
                 .collect(),
+            anonymous_read: false,
         }
     }
 }
@@ -54,6 +59,7 @@ impl From<AuthenticatorConfigArguments> for Option<AuthenticatorConfig> {
             true => None,
             false => Some(AuthenticatorConfig {
                 clients: value.clients.expand().collect(),
+                anonymous_read: false,
             }),
         }
     }
@@ -14,6 +14,7 @@ pub const DEFAULT_SCOPE_MAPPINGS: &[(&str, &[&str])] = &[
     ("read:document", &["read.sbom", "read.vex"]),
     ("update:document", &["update.sbom", "update.vex"]),
     ("delete:document", &["delete.sbom", "delete.vex"]),
+    ("ingest:document", &["ingest.document"]),
 ];
 
 /// A convenience function to get the default scopes in an allocated form.
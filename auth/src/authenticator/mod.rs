@@ -33,11 +33,16 @@ use trustification_common::reqwest::ClientFactory;
 #[derive(Clone)]
 pub struct Authenticator {
     pub clients: Vec<AuthenticatorClient>,
+    /// Let safe requests without a token pass as anonymous user
+    pub anonymous_read: bool,
 }
 
 impl Authenticator {
     fn from_clients(clients: Vec<AuthenticatorClient>) -> Self {
-        Self { clients }
+        Self {
+            clients,
+            anonymous_read: false,
+        }
     }
 
     pub async fn from_config(config: Option<AuthenticatorConfig>) -> anyhow::Result<Option<Self>> {
@@ -46,7 +51,10 @@ impl Authenticator {
             None => return Ok(None),
         };
 
-        Ok(Some(Self::from_configs(config.clients).await?))
+        Ok(Some(Self {
+            anonymous_read: config.anonymous_read,
+            ..Self::from_configs(config.clients).await?
+        }))
     }
 
     pub async fn from_configs<I>(configs: I) -> anyhow::Result<Self>
@@ -5,7 +5,11 @@ use crate::{
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize, schemars::JsonSchema)]
 #[serde(rename_all = "camelCase")]
-pub struct AuthorizerConfig {}
+pub struct AuthorizerConfig {
+    /// Grant read permissions to anonymous users, set from the [`crate::auth::AuthConfig`]
+    #[serde(skip)]
+    pub anonymous_read: bool,
+}
 
 #[derive(Default, Debug, Clone)]
 pub struct Authorizer {
@@ -17,6 +21,11 @@ impl Authorizer {
         Self { config }
     }
 
+    /// Whether anonymous users are granted read permissions.
+    pub fn anonymous_read(&self) -> bool {
+        self.config.as_ref().is_some_and(|config| config.anonymous_read)
+    }
+
     /// Require a permission from a user.
     ///
     /// If the user passes the check, the function will return `Ok(())`. Otherwise, an error will be
@@ -27,6 +36,11 @@ impl Authorizer {
             return Ok(());
         }
 
+        // reading is granted to everyone, authenticated users must not have fewer permissions than anonymous ones
+        if self.anonymous_read() && permission.is_read() {
+            return Ok(());
+        }
+
         // check if the user is authenticated
 
         let user = match user {
@@ -39,4 +53,38 @@ impl Authorizer {
         // we passed
         Ok(())
     }
+
+    /// Whether a user is granted a permission, like [`Self::require`] but without failing.
+    pub fn allows(&self, user: &UserInformation, permission: Permission) -> bool {
+        self.require(user, permission).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::authenticator::user::UserDetails;
+
+    #[test]
+    fn anonymous_read() {
+        let anonymous = UserInformation::Anonymous;
+
+        let authorizer = Authorizer::new(Some(AuthorizerConfig::default()));
+        assert!(authorizer.require(&anonymous, Permission::ReadSbom).is_err());
+
+        let authorizer = Authorizer::new(Some(AuthorizerConfig { anonymous_read: true }));
+        assert!(authorizer.require(&anonymous, Permission::ReadSbom).is_ok());
+        assert!(authorizer.require(&anonymous, Permission::ReadVex).is_ok());
+        assert!(authorizer.require(&anonymous, Permission::CreateSbom).is_err());
+        assert!(authorizer.require(&anonymous, Permission::DeleteVex).is_err());
+        assert!(!authorizer.allows(&anonymous, Permission::ReadVexAmber));
+        assert!(!authorizer.allows(&anonymous, Permission::ReadVexRed));
+
+        let user = UserInformation::Authenticated(UserDetails {
+            id: "user".to_string(),
+            permissions: vec![],
+        });
+        assert!(authorizer.require(&user, Permission::ReadSbom).is_ok());
+        assert!(authorizer.require(&user, Permission::UpdateSbom).is_err());
+    }
 }
@@ -4,6 +4,8 @@ pub mod authorizer;
 pub mod client;
 pub mod devmode;
 
+#[cfg(feature = "swagger")]
+pub mod openapi;
 #[cfg(feature = "swagger")]
 pub mod swagger_ui;
 
@@ -26,12 +28,31 @@ pub enum Permission {
     UpdateVex,
     #[serde(rename = "delete.vex")]
     DeleteVex,
+    /// Read advisories labeled TLP:AMBER
+    #[serde(rename = "read.vex.amber")]
+    ReadVexAmber,
+    /// Read advisories labeled TLP:RED, as well as TLP:AMBER
+    #[serde(rename = "read.vex.red")]
+    ReadVexRed,
 
     #[serde(rename = "read.cve")]
     ReadCve,
 
     #[serde(rename = "create.vulnerability")]
     IngestVulnerability,
+
+    /// Store documents as discovered by a walker or mirrored from another instance
+    #[serde(rename = "ingest.document")]
+    IngestDocument,
+}
+
+impl Permission {
+    /// Whether the permission only allows reading unrestricted data.
+    ///
+    /// Reading restricted data, like advisories labeled TLP:AMBER or TLP:RED, must be granted explicitly.
+    pub fn is_read(&self) -> bool {
+        matches!(self, Self::ReadSbom | Self::ReadVex | Self::ReadCve)
+    }
 }
 
 impl AsRef<str> for Permission {
@@ -46,10 +67,14 @@ impl AsRef<str> for Permission {
             Self::ReadVex => "read.vex",
             Self::UpdateVex => "update.vex",
             Self::DeleteVex => "delete.vex",
+            Self::ReadVexAmber => "read.vex.amber",
+            Self::ReadVexRed => "read.vex.red",
 
             Self::ReadCve => "read.cve",
 
             Self::IngestVulnerability => "create.vulnerability",
+
+            Self::IngestDocument => "ingest.document",
         }
     }
 }
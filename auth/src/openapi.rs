@@ -0,0 +1,136 @@
+//! Permissions required by the operations of OpenAPI documents.
+//!
+//! Operations are documented with the permission they require, and documents can be narrowed down to the operations a
+//! caller is permitted to call, so that integrators see exactly what they can use.
+
+use crate::{authenticator::user::UserInformation, authorizer::Authorizer, Permission};
+use std::collections::HashMap;
+use utoipa::openapi::{path::PathItemType, OpenApi};
+
+/// The permission required by an operation, by its documented path and its method.
+pub type OperationPermission = (&'static str, PathItemType, Permission);
+
+fn required(permissions: &[OperationPermission], path: &str, method: &PathItemType) -> Option<Permission> {
+    permissions
+        .iter()
+        .find(|(p, m, _)| *p == path && m == method)
+        .map(|(_, _, permission)| *permission)
+}
+
+/// Mention the permission required by each operation in its description.
+pub fn annotate_permissions(openapi: &mut OpenApi, permissions: &[OperationPermission]) {
+    for (path, item) in &mut openapi.paths.paths {
+        for (method, operation) in &mut item.operations {
+            let Some(permission) = required(permissions, path, method) else {
+                continue;
+            };
+            let note = format!("Requires the `{}` permission.", permission.as_ref());
+            operation.description = Some(match operation.description.take() {
+                Some(description) if !description.is_empty() => format!("{description}\n\n{note}"),
+                _ => note,
+            });
+        }
+    }
+}
+
+/// Only keep the operations a user is permitted to call, dropping the paths left without any.
+///
+/// Operations which don't require a permission are kept.
+pub fn permitted_operations(
+    mut openapi: OpenApi,
+    permissions: &[OperationPermission],
+    authorizer: &Authorizer,
+    user: &UserInformation,
+) -> OpenApi {
+    let mut allowed = HashMap::new();
+    for (_, _, permission) in permissions {
+        allowed
+            .entry(*permission)
+            .or_insert_with(|| authorizer.allows(user, *permission));
+    }
+
+    openapi.paths.paths.retain(|path, item| {
+        item.operations
+            .retain(|method, _| required(permissions, path, method).map_or(true, |permission| allowed[&permission]));
+        !item.operations.is_empty()
+    });
+    openapi
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::authorizer::AuthorizerConfig;
+    use utoipa::openapi::{path::OperationBuilder, OpenApiBuilder, PathItem, PathsBuilder};
+
+    const PERMISSIONS: &[OperationPermission] = &[
+        ("/api/v1/sbom", PathItemType::Get, Permission::ReadSbom),
+        ("/api/v1/sbom", PathItemType::Delete, Permission::DeleteSbom),
+        ("/api/v1/walker/runs", PathItemType::Post, Permission::CreateSbom),
+    ];
+
+    fn openapi() -> OpenApi {
+        let mut sbom = PathItem::new(
+            PathItemType::Get,
+            OperationBuilder::new().description(Some("Retrieve an SBOM")),
+        );
+        sbom.operations.insert(PathItemType::Delete, Default::default());
+
+        OpenApiBuilder::new()
+            .paths(
+                PathsBuilder::new()
+                    .path("/api/v1/sbom", sbom)
+                    .path(
+                        "/api/v1/walker/runs",
+                        PathItem::new(PathItemType::Post, Default::default()),
+                    )
+                    .path(
+                        "/api/v1/sbom/validate",
+                        PathItem::new(PathItemType::Post, Default::default()),
+                    ),
+            )
+            .build()
+    }
+
+    #[test]
+    fn annotate() {
+        let mut openapi = openapi();
+        annotate_permissions(&mut openapi, PERMISSIONS);
+
+        let sbom = &openapi.paths.paths["/api/v1/sbom"].operations;
+        assert_eq!(
+            sbom[&PathItemType::Get].description.as_deref(),
+            Some("Retrieve an SBOM\n\nRequires the `read.sbom` permission.")
+        );
+        assert_eq!(
+            sbom[&PathItemType::Delete].description.as_deref(),
+            Some("Requires the `delete.sbom` permission.")
+        );
+        assert_eq!(
+            openapi.paths.paths["/api/v1/sbom/validate"].operations[&PathItemType::Post].description,
+            None
+        );
+    }
+
+    #[test]
+    fn permitted() {
+        let authorizer = Authorizer::new(Some(AuthorizerConfig { anonymous_read: true }));
+        let openapi = permitted_operations(openapi(), PERMISSIONS, &authorizer, &UserInformation::Anonymous);
+
+        let paths = &openapi.paths.paths;
+        assert_eq!(
+            paths["/api/v1/sbom"].operations.keys().collect::<Vec<_>>(),
+            vec![&PathItemType::Get]
+        );
+        assert!(!paths.contains_key("/api/v1/walker/runs"));
+        assert!(paths.contains_key("/api/v1/sbom/validate"));
+
+        let openapi = permitted_operations(
+            openapi(),
+            PERMISSIONS,
+            &Authorizer::new(None),
+            &UserInformation::Anonymous,
+        );
+        assert_eq!(openapi.paths.paths.len(), 3);
+    }
+}
@@ -8,7 +8,7 @@ use utoipa::openapi::{
     security::{AuthorizationCode, Flow, OAuth2, Scopes, SecurityScheme},
     OpenApi, SecurityRequirement,
 };
-use utoipa_swagger_ui::{oauth, SwaggerUi};
+use utoipa_swagger_ui::{oauth, SwaggerUi, Url as SwaggerUrl};
 
 #[derive(Clone, Debug, Default, clap::Args)]
 #[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Swagger UI OIDC")]
@@ -101,6 +101,12 @@ impl SwaggerUiOidc {
     }
 
     pub fn apply(&self, swagger: SwaggerUi, openapi: &mut OpenApi) -> SwaggerUi {
+        self.secure(openapi);
+        self.oauth(swagger)
+    }
+
+    /// Add the OIDC security scheme to an OpenAPI document.
+    pub fn secure(&self, openapi: &mut OpenApi) {
         if let Some(components) = &mut openapi.components {
             // the swagger UI expects the full "well known" endpoint
             // let url = format!("{}/.well-known/openid-configuration", self.issuer_url);
@@ -119,7 +125,10 @@ impl SwaggerUiOidc {
         }
 
         openapi.security = Some(vec![SecurityRequirement::new::<_, _, String>("oidc", [])]);
+    }
 
+    /// Configure the OAuth client of the Swagger UI.
+    pub fn oauth(&self, swagger: SwaggerUi) -> SwaggerUi {
         swagger.oauth(
             oauth::Config::new()
                 .client_id(&self.client_id)
@@ -144,3 +153,30 @@ pub fn swagger_ui_with_auth(
 
     swagger.url("/openapi.json", openapi)
 }
+
+/// Create an [`HttpServiceFactory`] for Swagger UI with OIDC authentication, serving one document per API version
+///
+/// The documents are given as name, URL of the document and the document itself. The first one is selected initially.
+#[cfg(feature = "actix")]
+pub fn versioned_swagger_ui_with_auth(
+    docs: impl IntoIterator<Item = (&'static str, &'static str, utoipa::openapi::OpenApi)>,
+    swagger_ui_oidc: Option<Arc<SwaggerUiOidc>>,
+) -> impl HttpServiceFactory {
+    let mut swagger = SwaggerUi::new("/swagger-ui/{_:.*}");
+
+    let docs = docs
+        .into_iter()
+        .map(|(name, url, mut openapi)| {
+            if let Some(swagger_ui_oidc) = &swagger_ui_oidc {
+                swagger_ui_oidc.secure(&mut openapi);
+            }
+            (SwaggerUrl::new(name, url), openapi)
+        })
+        .collect();
+
+    if let Some(swagger_ui_oidc) = &swagger_ui_oidc {
+        swagger = swagger_ui_oidc.oauth(swagger);
+    }
+
+    swagger.urls(docs)
+}
@@ -0,0 +1,228 @@
+//! Python bindings for Trustification
+//!
+//! Exposes parsing and linting of SBOMs, as well as a blocking wrapper of [`trustification_client`]. Results are
+//! converted into plain Python objects (dictionaries and lists), matching the JSON returned by the services.
+
+use bombastic_model::{data::SBOM, lint::lint};
+use bytes::Bytes;
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyValueError},
+    prelude::*,
+    types::PyBytes,
+};
+use serde::Serialize;
+use std::{future::Future, sync::Arc, time::Duration};
+use tokio::runtime::Runtime;
+use trustification_auth::client::{
+    BearerTokenProvider, NoTokenProvider, OpenIdTokenProvider, OpenIdTokenProviderConfig, TokenProvider,
+};
+use trustification_client::{Endpoints, SearchOptions};
+use url::Url;
+
+create_exception!(
+    trustification,
+    TrustificationError,
+    PyException,
+    "Error returned by a Trustification service."
+);
+
+fn client_error(err: trustification_client::Error) -> PyErr {
+    match err {
+        trustification_client::Error::NotConfigured(_) => PyValueError::new_err(err.to_string()),
+        err => TrustificationError::new_err(err.to_string()),
+    }
+}
+
+fn to_python<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let value = pythonize::pythonize(py, value).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(value.into())
+}
+
+fn parse_url(url: Option<&str>) -> PyResult<Option<Url>> {
+    url.map(|url| Url::parse(url).map_err(|e| PyValueError::new_err(format!("invalid URL {url}: {e}"))))
+        .transpose()
+}
+
+/// Parse an SBOM, returning its format (e.g. `SPDX/SPDX-2.3`).
+///
+/// Raises `ValueError` if the document is not a supported SBOM.
+#[pyfunction]
+fn parse_sbom(data: &[u8]) -> PyResult<String> {
+    SBOM::parse(data)
+        .map(|sbom| sbom.type_str())
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Lint an SBOM, returning the report as a dictionary.
+#[pyfunction]
+fn lint_sbom(py: Python<'_>, data: &[u8]) -> PyResult<PyObject> {
+    to_python(py, &lint(data))
+}
+
+/// A blocking client for the Trustification services.
+///
+/// Authenticates using either a bearer token or OIDC client credentials, if provided.
+#[pyclass]
+struct Client {
+    client: trustification_client::Client,
+    runtime: Runtime,
+}
+
+impl Client {
+    /// Run a request on the runtime, releasing the GIL while waiting.
+    fn block_on<F, T>(&self, py: Python<'_>, f: F) -> PyResult<T>
+    where
+        F: Future<Output = Result<T, trustification_client::Error>> + Send,
+        T: Send,
+    {
+        py.allow_threads(|| self.runtime.block_on(f)).map_err(client_error)
+    }
+}
+
+#[pymethods]
+impl Client {
+    #[new]
+    #[pyo3(signature = (
+        *,
+        bombastic = None,
+        vexination = None,
+        exhort = None,
+        spog = None,
+        v11y = None,
+        token = None,
+        oidc_issuer_url = None,
+        oidc_client_id = None,
+        oidc_client_secret = None
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        bombastic: Option<&str>,
+        vexination: Option<&str>,
+        exhort: Option<&str>,
+        spog: Option<&str>,
+        v11y: Option<&str>,
+        token: Option<String>,
+        oidc_issuer_url: Option<String>,
+        oidc_client_id: Option<String>,
+        oidc_client_secret: Option<String>,
+    ) -> PyResult<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+
+        let provider: Arc<dyn TokenProvider> = match (token, oidc_issuer_url, oidc_client_id, oidc_client_secret) {
+            (Some(token), _, _, _) => Arc::new(BearerTokenProvider { token }),
+            (None, Some(issuer_url), Some(client_id), Some(client_secret)) => {
+                let config = OpenIdTokenProviderConfig {
+                    client_id,
+                    client_secret,
+                    issuer_url,
+                    refresh_before: Duration::from_secs(30).into(),
+                    tls_insecure: false,
+                    tls_ca_certificates: vec![],
+                };
+                let provider = runtime
+                    .block_on(OpenIdTokenProvider::with_config(config))
+                    .map_err(|e| TrustificationError::new_err(e.to_string()))?;
+                Arc::new(provider)
+            }
+            (None, None, None, None) => Arc::new(NoTokenProvider),
+            _ => {
+                return Err(PyValueError::new_err(
+                    "OIDC requires the issuer URL, client ID and client secret",
+                ))
+            }
+        };
+
+        let endpoints = Endpoints {
+            bombastic: parse_url(bombastic)?,
+            vexination: parse_url(vexination)?,
+            v11y: parse_url(v11y)?,
+            exhort: parse_url(exhort)?,
+            spog: parse_url(spog)?,
+        };
+
+        Ok(Self {
+            client: trustification_client::Client::new(reqwest_client()?, endpoints, provider),
+            runtime,
+        })
+    }
+
+    /// Search SBOMs, using the bombastic query language.
+    #[pyo3(signature = (q, offset = 0, limit = 10))]
+    fn search_sbom(&self, py: Python<'_>, q: &str, offset: usize, limit: usize) -> PyResult<PyObject> {
+        let result = self.block_on(py, async {
+            self.client
+                .bombastic()?
+                .search_sbom(q, offset, limit, &SearchOptions::default())
+                .await
+        })?;
+        to_python(py, &result)
+    }
+
+    /// Fetch an SBOM, returns `None` if it does not exist.
+    fn get_sbom(&self, py: Python<'_>, id: &str) -> PyResult<Option<PyObject>> {
+        let data = self.block_on(py, async { self.client.bombastic()?.get_sbom(id).await })?;
+        Ok(data.map(|data| PyBytes::new_bound(py, &data).into()))
+    }
+
+    /// Publish an SBOM, returning its identifier.
+    #[pyo3(signature = (data, id = None))]
+    fn publish_sbom(&self, py: Python<'_>, data: &[u8], id: Option<&str>) -> PyResult<String> {
+        let data = Bytes::copy_from_slice(data);
+        self.block_on(py, async { self.client.bombastic()?.upload_sbom(id, data).await })
+    }
+
+    fn delete_sbom(&self, py: Python<'_>, id: &str) -> PyResult<()> {
+        self.block_on(py, async { self.client.bombastic()?.delete_sbom(id).await })
+    }
+
+    /// Search advisories, using the vexination query language.
+    #[pyo3(signature = (q, offset = 0, limit = 10))]
+    fn search_vex(&self, py: Python<'_>, q: &str, offset: usize, limit: usize) -> PyResult<PyObject> {
+        let result = self.block_on(py, async {
+            self.client
+                .vexination()?
+                .search_vex(q, offset, limit, &SearchOptions::default())
+                .await
+        })?;
+        to_python(py, &result)
+    }
+
+    /// Fetch an advisory, returns `None` if it does not exist.
+    fn get_vex(&self, py: Python<'_>, advisory: &str) -> PyResult<Option<PyObject>> {
+        let data = self.block_on(py, async { self.client.vexination()?.get_vex(advisory).await })?;
+        Ok(data.map(|data| PyBytes::new_bound(py, &data).into()))
+    }
+
+    /// Publish a CSAF document, identified by its tracking ID unless overridden.
+    #[pyo3(signature = (data, advisory = None))]
+    fn publish_vex(&self, py: Python<'_>, data: &[u8], advisory: Option<&str>) -> PyResult<()> {
+        let data = Bytes::copy_from_slice(data);
+        self.block_on(py, async { self.client.vexination()?.upload_vex(advisory, data).await })
+    }
+
+    fn delete_vex(&self, py: Python<'_>, advisory: &str) -> PyResult<()> {
+        self.block_on(py, async { self.client.vexination()?.delete_vex(advisory).await })
+    }
+
+    /// Analyze packages, given as package URLs, for known vulnerabilities.
+    fn analyze(&self, py: Python<'_>, purls: Vec<String>) -> PyResult<PyObject> {
+        let result = self.block_on(py, async { self.client.exhort()?.analyze(purls).await })?;
+        to_python(py, &result)
+    }
+}
+
+fn reqwest_client() -> PyResult<trustification_client::reqwest::Client> {
+    trustification_client::reqwest::Client::builder()
+        .build()
+        .map_err(|e| TrustificationError::new_err(e.to_string()))
+}
+
+#[pymodule]
+fn trustification(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(parse_sbom, m)?)?;
+    m.add_function(wrap_pyfunction!(lint_sbom, m)?)?;
+    m.add_class::<Client>()?;
+    m.add("TrustificationError", m.py().get_type_bound::<TrustificationError>())?;
+    Ok(())
+}
@@ -0,0 +1,86 @@
+import { Client, queryUrl, searchUrl } from "./client.js";
+import type {
+  IndexFreshness,
+  LintReport,
+  PackageDocument,
+  SbomDocument,
+  SbomStatus,
+  SearchOptions,
+  SearchResult,
+} from "./types.js";
+
+/** The SBOM body of uploads, either JSON or protobuf encoded CycloneDX. */
+export type SbomBody = string | Blob | ArrayBuffer | Uint8Array;
+
+const CYCLONEDX_PROTOBUF = "application/x.cyclonedx+protobuf";
+
+/** Client for the bombastic (SBOM) API. */
+export class BombasticClient {
+  constructor(
+    private readonly client: Client,
+    private readonly url: string,
+  ) {}
+
+  /** Fetch an SBOM, returns `undefined` if it does not exist. */
+  async getSbom(id: string): Promise<Blob | undefined> {
+    const response = await this.client.sendOpt(queryUrl(this.url, "/api/v1/sbom", { id }));
+    return response?.blob();
+  }
+
+  /**
+   * Upload an SBOM, returning its identifier.
+   *
+   * If no identifier is provided, the server derives it from the content (if enabled). Binary bodies are sent as
+   * protobuf encoded CycloneDX, unless a content type is provided.
+   */
+  async uploadSbom(data: SbomBody, id?: string, contentType?: string): Promise<string> {
+    const type = contentType ?? (typeof data === "string" ? "application/json" : CYCLONEDX_PROTOBUF);
+    const response = await this.client.send(queryUrl(this.url, "/api/v1/sbom", { id }), {
+      method: "PUT",
+      headers: { "Content-Type": type },
+      body: data,
+    });
+
+    // the location carries the assigned identifier
+    const location = response.headers.get("Location");
+    return (location && new URL(location, this.url).searchParams.get("id")) || id || "";
+  }
+
+  /** Validate an SBOM without storing it. */
+  async validateSbom(data: SbomBody, contentType?: string): Promise<LintReport> {
+    const type = contentType ?? (typeof data === "string" ? "application/json" : CYCLONEDX_PROTOBUF);
+    const response = await this.client.send(queryUrl(this.url, "/api/v1/sbom/validate"), {
+      method: "POST",
+      headers: { "Content-Type": type },
+      body: data,
+    });
+    return response.json();
+  }
+
+  /** Delete an SBOM, succeeds if it does not exist. */
+  async deleteSbom(id: string): Promise<void> {
+    await this.client.send(queryUrl(this.url, "/api/v1/sbom", { id }), { method: "DELETE" });
+  }
+
+  async searchSbom(q: string, options?: SearchOptions): Promise<SearchResult<SbomDocument>> {
+    const response = await this.client.send(searchUrl(this.url, "/api/v1/sbom/search", q, options));
+    return response.json();
+  }
+
+  async searchPackage(q: string, options?: SearchOptions): Promise<SearchResult<PackageDocument>> {
+    const response = await this.client.send(searchUrl(this.url, "/api/v1/package/search", q, options));
+    return response.json();
+  }
+
+  /** Total number of SBOMs and the last updated one. */
+  async status(): Promise<SbomStatus> {
+    const response = await this.client.send(queryUrl(this.url, "/api/v1/sbom/status"));
+    return response.json();
+  }
+
+  /** How current the SBOM index is. */
+  async freshness(): Promise<IndexFreshness> {
+    const response = await this.client.send(queryUrl(this.url, "/api/v1/sbom/freshness"));
+    return response.json();
+  }
+}
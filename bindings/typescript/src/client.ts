@@ -0,0 +1,138 @@
+import { BombasticClient } from "./bombastic.js";
+import { SpogClient } from "./spog.js";
+import { VexinationClient } from "./vexination.js";
+import type { SearchOptions } from "./types.js";
+
+/** Base URLs of the services, a client can only be obtained for configured services. */
+export interface Endpoints {
+  bombastic?: string;
+  vexination?: string;
+  spog?: string;
+}
+
+/**
+ * Provides the access token injected into every request.
+ *
+ * It is called for each request, so that an expired token can be refreshed. Returning `undefined` sends the request
+ * without authentication.
+ */
+export type TokenProvider = () => string | undefined | Promise<string | undefined>;
+
+export interface ClientOptions {
+  endpoints: Endpoints;
+  /** A static access token, or a function providing it. */
+  token?: string | TokenProvider;
+  /** The fetch implementation to use, defaults to the global one. */
+  fetch?: typeof fetch;
+}
+
+/** Error returned by a Trustification service, or raised for a service which is not configured. */
+export class TrustificationError extends Error {
+  /** HTTP status of the response, absent if no request was sent. */
+  readonly status?: number;
+
+  constructor(message: string, status?: number) {
+    super(message);
+    this.name = "TrustificationError";
+    this.status = status;
+  }
+}
+
+/** A client for the Trustification services. All services share the same token provider. */
+export class Client {
+  private readonly endpoints: Endpoints;
+  private readonly token: TokenProvider;
+  private readonly fetchFn: typeof fetch;
+
+  constructor(options: ClientOptions) {
+    const token = options.token;
+    this.endpoints = options.endpoints;
+    this.token = typeof token === "function" ? token : () => token;
+    this.fetchFn = options.fetch ?? globalThis.fetch.bind(globalThis);
+  }
+
+  bombastic(): BombasticClient {
+    return new BombasticClient(this, endpoint(this.endpoints.bombastic, "bombastic"));
+  }
+
+  vexination(): VexinationClient {
+    return new VexinationClient(this, endpoint(this.endpoints.vexination, "vexination"));
+  }
+
+  spog(): SpogClient {
+    return new SpogClient(this, endpoint(this.endpoints.spog, "spog"));
+  }
+
+  /**
+   * Send a request, injecting the access token.
+   *
+   * Responses with a status other than success are rejected with a {@link TrustificationError}.
+   *
+   * @internal
+   */
+  async send(url: URL, init: RequestInit = {}): Promise<Response> {
+    const headers = new Headers(init.headers);
+    const token = await this.token();
+    if (token !== undefined) {
+      headers.set("Authorization", `Bearer ${token}`);
+    }
+    const response = await this.fetchFn(url, { ...init, headers });
+    if (!response.ok) {
+      throw new TrustificationError(
+        `unexpected response ${response.status}: ${await response.text()}`,
+        response.status,
+      );
+    }
+    return response;
+  }
+
+  /**
+   * Send a request, returning `undefined` if the resource was not found.
+   *
+   * @internal
+   */
+  async sendOpt(url: URL, init: RequestInit = {}): Promise<Response | undefined> {
+    try {
+      return await this.send(url, init);
+    } catch (e) {
+      if (e instanceof TrustificationError && e.status === 404) {
+        return undefined;
+      }
+      throw e;
+    }
+  }
+}
+
+function endpoint(url: string | undefined, service: string): string {
+  if (url === undefined) {
+    throw new TrustificationError(`no endpoint configured for ${service}`);
+  }
+  return url;
+}
+
+/** @internal */
+export function searchUrl(base: string, path: string, q: string, options: SearchOptions = {}): URL {
+  const url = new URL(path, base);
+  url.searchParams.set("q", q);
+  url.searchParams.set("offset", String(options.offset ?? 0));
+  url.searchParams.set("limit", String(options.limit ?? 10));
+  if (options.explain) {
+    url.searchParams.set("explain", "true");
+  }
+  if (options.metadata) {
+    url.searchParams.set("metadata", "true");
+  }
+  url.searchParams.set("summaries", String(options.summaries ?? true));
+  return url;
+}
+
+/** @internal */
+export function queryUrl(base: string, path: string, query: Record<string, string | undefined> = {}): URL {
+  const url = new URL(path, base);
+  for (const [key, value] of Object.entries(query)) {
+    if (value !== undefined) {
+      url.searchParams.set(key, value);
+    }
+  }
+  return url;
+}
@@ -0,0 +1,26 @@
+/**
+ * Trustification Client
+ *
+ * A client for the APIs of bombastic, vexination and spog, injecting an access token into every request.
+ *
+ * ```ts
+ * import { Client } from "@trustification/client";
+ *
+ * const client = new Client({
+ *   endpoints: { bombastic: "http://localhost:8082" },
+ *   token: async () => auth.accessToken,
+ * });
+ * const result = await client.bombastic().searchSbom("openssl", { limit: 10 });
+ * console.log(`Found ${result.total} SBOMs`);
+ * ```
+ *
+ * @packageDocumentation
+ */
+
+export { Client, TrustificationError } from "./client.js";
+export type { ClientOptions, Endpoints, TokenProvider } from "./client.js";
+export { BombasticClient } from "./bombastic.js";
+export type { SbomBody } from "./bombastic.js";
+export { VexinationClient } from "./vexination.js";
+export { SpogClient } from "./spog.js";
+export type * from "./types.js";
@@ -0,0 +1,39 @@
+import { Client, queryUrl, searchUrl } from "./client.js";
+import type { AdvisorySummary, ProvenanceReport, SbomSummary, SearchOptions, SummaryResult } from "./types.js";
+
+/** Client for the spog API, aggregating the information of the other services. */
+export class SpogClient {
+  constructor(
+    private readonly client: Client,
+    private readonly url: string,
+  ) {}
+
+  async searchSbom(q: string, options?: SearchOptions): Promise<SummaryResult<SbomSummary>> {
+    const response = await this.client.send(searchUrl(this.url, "/api/v1/sbom/search", q, options));
+    return response.json();
+  }
+
+  async searchAdvisories(q: string, options?: SearchOptions): Promise<SummaryResult<AdvisorySummary>> {
+    const response = await this.client.send(searchUrl(this.url, "/api/v1/advisory/search", q, options));
+    return response.json();
+  }
+
+  /** Fetch an SBOM, returns `undefined` if it does not exist. */
+  async getSbom(id: string): Promise<Blob | undefined> {
+    const response = await this.client.sendOpt(queryUrl(this.url, "/api/v1/sbom", { id }));
+    return response?.blob();
+  }
+
+  /** Fetch an advisory, returns `undefined` if it does not exist. */
+  async getAdvisory(id: string): Promise<Blob | undefined> {
+    const response = await this.client.sendOpt(queryUrl(this.url, "/api/v1/advisory", { id }));
+    return response?.blob();
+  }
+
+  /** Check the components of an SBOM against their package registries, optionally only a sample of them. */
+  async sbomProvenance(id: string, sample?: number): Promise<ProvenanceReport> {
+    const query = { id, sample: sample?.toString() };
+    const response = await this.client.send(queryUrl(this.url, "/api/v1/sbom/provenance", query));
+    return response.json();
+  }
+}
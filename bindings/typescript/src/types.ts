@@ -0,0 +1,189 @@
+// Types of the JSON payloads returned by the services, matching the OpenAPI specifications.
+
+/** Options of search requests. */
+export interface SearchOptions {
+  /** Number of results to skip, defaults to 0. */
+  offset?: number;
+  /** Maximum number of results, defaults to 10. */
+  limit?: number;
+  /** Return index "explain" output. */
+  explain?: boolean;
+  /** Return additional search metadata. */
+  metadata?: boolean;
+  /** Return summaries, defaults to true. */
+  summaries?: boolean;
+}
+
+/** A matching document, with its score. */
+export interface SearchHit<D> {
+  document: D;
+  score: number;
+  explanation?: unknown;
+  $metadata?: unknown;
+}
+
+/** How many documents matched, and the matching documents within offset and limit. */
+export interface SearchResult<D> {
+  total: number;
+  result: SearchHit<D>[];
+  /** Time of the last commit of the searched index, in RFC3339 format. */
+  index_updated_at?: string | null;
+}
+
+/** How current a search index is. */
+export interface IndexFreshness {
+  index_updated_at?: string | null;
+  synced_at?: string | null;
+  age_seconds?: number | null;
+}
+
+export interface SbomDocument {
+  id: string;
+  uid?: string | null;
+  indexed_timestamp: number;
+  name: string;
+  version: string;
+  cpe?: string | null;
+  purl?: string | null;
+  file_sha256: string;
+  sha256: string;
+  license: string;
+  supplier: string;
+  classifier: string;
+  description: string;
+  snippet: string;
+  created: string;
+  dependencies: number;
+  ecosystems?: Record<string, number>;
+  [key: string]: unknown;
+}
+
+export interface PackageDocument {
+  name: string;
+  version: string;
+  purl: string;
+  sha256: string;
+  license: string;
+  supplier: string;
+  classifier: string;
+  description: string;
+  [key: string]: unknown;
+}
+
+export interface LintFinding {
+  severity: string;
+  rule: string;
+  message: string;
+  /** JSON pointer to the location of the finding, if known. */
+  location?: string;
+}
+
+export interface LintReport {
+  /** The detected format of the document, if it could be parsed. */
+  format?: string;
+  findings: LintFinding[];
+}
+
+export interface SbomStatus {
+  total?: number | null;
+  last_updated_sbom_id?: string | null;
+  last_updated_sbom_name?: string | null;
+  last_updated_date?: string | null;
+}
+
+export interface VexDocument {
+  advisory_id: string;
+  advisory_title: string;
+  advisory_version: string;
+  advisory_date: string;
+  advisory_snippet: string;
+  advisory_desc: string;
+  advisory_severity?: string | null;
+  cves: string[];
+  /** Highest CVSS score, preferring CVSS v4 over v3. */
+  cvss_max?: number | null;
+  cvss3_max?: number | null;
+  cvss4_max?: number | null;
+  cve_severity_count: Record<string, number>;
+  indexed_timestamp: number;
+}
+
+export interface VexStatus {
+  total?: number | null;
+  last_updated_vex_id?: string | null;
+  last_updated_vex_name?: string | null;
+  last_updated_date?: string | null;
+}
+
+export interface TimelineEvent {
+  date: string;
+  kind: string;
+  advisory_id: string;
+  version?: string;
+  status?: string;
+  [key: string]: unknown;
+}
+
+export interface CveTimeline {
+  cve: string;
+  advisories: number;
+  events: TimelineEvent[];
+}
+
+/** Search result of the aggregating spog API. */
+export interface SummaryResult<S> {
+  result: S[];
+  total?: number | null;
+}
+
+export interface SbomSummary {
+  id: string;
+  name: string;
+  version: string;
+  purl?: string | null;
+  cpe?: string | null;
+  sha256: string;
+  license: string;
+  snippet: string;
+  classifier: string;
+  description: string;
+  supplier: string;
+  dependencies: number;
+  ecosystems: Record<string, number>;
+  href: string;
+  advisories?: number | null;
+  created: string;
+  vulnerabilities: string[];
+  $metadata?: unknown;
+}
+
+export interface AdvisorySummary {
+  id: string;
+  title: string;
+  severity?: string | null;
+  snippet: string;
+  desc: string;
+  date: string;
+  cves: string[];
+  cvss_max?: number | null;
+  href: string;
+  cve_severity_count: Record<string, number>;
+  $metadata?: unknown;
+}
+
+export type ProvenanceStatus = "verified" | "unverified" | "mismatch" | "unknown" | "error";
+
+export interface ComponentProvenance {
+  purl: string;
+  registry: "cargo" | "npm" | "pypi" | "maven";
+  status: ProvenanceStatus;
+  details?: string;
+}
+
+/** Provenance of the components of an SBOM, flagged (mismatching or unknown) components first. */
+export interface ProvenanceReport {
+  supported: number;
+  checked: number;
+  summary: Partial<Record<ProvenanceStatus, number>>;
+  components: ComponentProvenance[];
+}
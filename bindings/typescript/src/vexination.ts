@@ -0,0 +1,53 @@
+import { Client, queryUrl, searchUrl } from "./client.js";
+import type { CveTimeline, IndexFreshness, SearchOptions, SearchResult, VexDocument, VexStatus } from "./types.js";
+
+/** Client for the vexination (CSAF advisory) API. */
+export class VexinationClient {
+  constructor(
+    private readonly client: Client,
+    private readonly url: string,
+  ) {}
+
+  /** Fetch an advisory, returns `undefined` if it does not exist. */
+  async getVex(advisory: string): Promise<Blob | undefined> {
+    const response = await this.client.sendOpt(queryUrl(this.url, "/api/v1/vex", { advisory }));
+    return response?.blob();
+  }
+
+  /** Upload a CSAF document, using the identifier of the document unless overridden. */
+  async uploadVex(data: string | Blob | ArrayBuffer | Uint8Array, advisory?: string): Promise<void> {
+    await this.client.send(queryUrl(this.url, "/api/v1/vex", { advisory }), {
+      method: "PUT",
+      headers: { "Content-Type": "application/json" },
+      body: data,
+    });
+  }
+
+  /** Delete an advisory, succeeds if it does not exist. */
+  async deleteVex(advisory: string): Promise<void> {
+    await this.client.send(queryUrl(this.url, "/api/v1/vex", { advisory }), { method: "DELETE" });
+  }
+
+  async searchVex(q: string, options?: SearchOptions): Promise<SearchResult<VexDocument>> {
+    const response = await this.client.send(searchUrl(this.url, "/api/v1/vex/search", q, options));
+    return response.json();
+  }
+
+  /** Timeline of the advisory events related to a CVE. */
+  async timeline(cve: string): Promise<CveTimeline> {
+    const response = await this.client.send(queryUrl(this.url, "/api/v1/vex/timeline", { cve }));
+    return response.json();
+  }
+
+  /** Total number of advisories and the last updated one. */
+  async status(): Promise<VexStatus> {
+    const response = await this.client.send(queryUrl(this.url, "/api/v1/vex/status"));
+    return response.json();
+  }
+
+  /** How current the advisory index is. */
+  async freshness(): Promise<IndexFreshness> {
+    const response = await this.client.send(queryUrl(this.url, "/api/v1/vex/freshness"));
+    return response.json();
+  }
+}
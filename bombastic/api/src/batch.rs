@@ -0,0 +1,231 @@
+//! Batches of SBOMs, published in a single request as an archive or as newline delimited JSON.
+
+use actix_web::web::Bytes;
+use bombastic_model::xml::CYCLONEDX_XML;
+use std::{
+    io::{self, Cursor, Read},
+    path::Path,
+};
+
+/// The formats of a batch, by content type.
+pub const FORMATS: [(&str, Format); 3] = [
+    ("application/x-ndjson", Format::NdJson),
+    ("application/x-tar", Format::Tar),
+    ("application/zip", Format::Zip),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// One JSON SBOM per line, identified by the digest of their content
+    NdJson,
+    /// A tar archive of SBOMs, identified by their file name
+    Tar,
+    /// A zip archive of SBOMs, identified by their file name
+    Zip,
+}
+
+/// A document of a batch.
+#[derive(Debug)]
+pub struct Entry {
+    /// Where the document is in the batch: its path in an archive, or its line
+    pub name: String,
+    /// Identifier of the document, if it isn't derived from its content
+    pub id: Option<String>,
+    /// Content type of the document, XML encoded CycloneDX for `.xml` files and JSON otherwise
+    pub content_type: &'static str,
+    pub data: Bytes,
+}
+
+impl Format {
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        FORMATS
+            .iter()
+            .find(|(typ, _)| *typ == content_type)
+            .map(|(_, format)| *format)
+    }
+
+    /// Split a batch into its documents, failing if a document exceeds the limit.
+    pub fn entries(self, data: Bytes, limit: usize) -> io::Result<Vec<Entry>> {
+        match self {
+            Self::NdJson => Ok(ndjson_entries(data)),
+            Self::Tar => tar_entries(&data, limit),
+            Self::Zip => zip_entries(data, limit),
+        }
+    }
+}
+
+fn ndjson_entries(data: Bytes) -> Vec<Entry> {
+    data.split(|b| *b == b'\n')
+        .enumerate()
+        .map(|(n, line)| (n, trim(line)))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(n, line)| Entry {
+            name: format!("line {}", n + 1),
+            id: None,
+            content_type: "application/json",
+            data: data.slice_ref(line),
+        })
+        .collect()
+}
+
+fn trim(mut line: &[u8]) -> &[u8] {
+    while let [first, rest @ ..] = line {
+        if !first.is_ascii_whitespace() {
+            break;
+        }
+        line = rest;
+    }
+    while let [rest @ .., last] = line {
+        if !last.is_ascii_whitespace() {
+            break;
+        }
+        line = rest;
+    }
+    line
+}
+
+fn tar_entries(data: &[u8], limit: usize) -> io::Result<Vec<Entry>> {
+    let mut archive = tar::Archive::new(data);
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.to_string_lossy().into_owned();
+        entries.push(file_entry(name, entry, limit)?);
+    }
+    Ok(entries)
+}
+
+fn zip_entries(data: Bytes, limit: usize) -> io::Result<Vec<Entry>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))?;
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let file = archive.by_index(i)?;
+        if !file.is_file() {
+            continue;
+        }
+        let name = file.name().to_string();
+        entries.push(file_entry(name, file, limit)?);
+    }
+    Ok(entries)
+}
+
+fn file_entry(name: String, reader: impl Read, limit: usize) -> io::Result<Entry> {
+    let mut data = Vec::new();
+    reader.take(limit as u64 + 1).read_to_end(&mut data)?;
+    if data.len() > limit {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{name} exceeds the limit of {limit} bytes"),
+        ));
+    }
+
+    let path = Path::new(&name);
+    let content_type = match path.extension() {
+        Some(ext) if ext == "xml" => CYCLONEDX_XML,
+        _ => "application/json",
+    };
+    let id = path.file_stem().map(|stem| stem.to_string_lossy().into_owned());
+    Ok(Entry {
+        name,
+        id,
+        content_type,
+        data: data.into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const LIMIT: usize = 1024;
+
+    fn summary(entries: Vec<Entry>) -> Vec<(String, Option<String>, &'static str, Bytes)> {
+        entries
+            .into_iter()
+            .map(|entry| (entry.name, entry.id, entry.content_type, entry.data))
+            .collect()
+    }
+
+    #[test]
+    fn ndjson() {
+        let data = Bytes::from_static(b"{\"a\":1}\n\n  {\"b\":2}\r\n");
+        assert_eq!(
+            summary(Format::NdJson.entries(data, LIMIT).unwrap()),
+            vec![
+                (
+                    "line 1".into(),
+                    None,
+                    "application/json",
+                    Bytes::from_static(b"{\"a\":1}")
+                ),
+                (
+                    "line 3".into(),
+                    None,
+                    "application/json",
+                    Bytes::from_static(b"{\"b\":2}")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn tar() {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, data) in [("sboms/app.json", &b"{}"[..]), ("sboms/lib.xml", b"<bom/>")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_cksum();
+            builder.append_data(&mut header, name, data).unwrap();
+        }
+        let data = Bytes::from(builder.into_inner().unwrap());
+
+        assert_eq!(
+            summary(Format::Tar.entries(data.clone(), LIMIT).unwrap()),
+            vec![
+                (
+                    "sboms/app.json".into(),
+                    Some("app".into()),
+                    "application/json",
+                    Bytes::from_static(b"{}")
+                ),
+                (
+                    "sboms/lib.xml".into(),
+                    Some("lib".into()),
+                    CYCLONEDX_XML,
+                    Bytes::from_static(b"<bom/>")
+                ),
+            ]
+        );
+        assert!(Format::Tar.entries(data, 4).is_err());
+    }
+
+    #[test]
+    fn zip() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer.add_directory("sboms/", Default::default()).unwrap();
+        writer.start_file("sboms/app.json", Default::default()).unwrap();
+        writer.write_all(b"{}").unwrap();
+        let data = Bytes::from(writer.finish().unwrap().into_inner());
+
+        assert_eq!(
+            summary(Format::Zip.entries(data, LIMIT).unwrap()),
+            vec![(
+                "sboms/app.json".into(),
+                Some("app".into()),
+                "application/json",
+                Bytes::from_static(b"{}")
+            )]
+        );
+        assert!(Format::Zip.entries(Bytes::from_static(b"not a zip"), LIMIT).is_err());
+    }
+
+    #[test]
+    fn content_types() {
+        assert_eq!(Format::from_content_type("application/x-ndjson"), Some(Format::NdJson));
+        assert_eq!(Format::from_content_type("application/json"), None);
+    }
+}
@@ -9,18 +9,28 @@ use trustification_auth::{
     authorizer::Authorizer,
     swagger_ui::{SwaggerUiOidc, SwaggerUiOidcConfig},
 };
-use trustification_index::{IndexConfig, IndexStore};
+use trustification_index::{
+    cache::{SearchCache, SearchCacheConfig},
+    Facets, IndexConfig, IndexStore, RealtimeConfig,
+};
 use trustification_infrastructure::{
     app::http::BinaryByteSize,
     app::http::{HttpServerBuilder, HttpServerConfig},
+    app::multi_get::{MultiGet, MultiGetConfig},
     endpoint::Bombastic,
     health::checks::Probe,
     Infrastructure, InfrastructureConfig,
 };
 use trustification_storage::{Storage, StorageConfig};
 
+mod batch;
+mod redact;
 mod sbom;
 mod server;
+mod signature;
+
+pub use redact::RedactionConfig;
+pub use signature::SignatureConfig;
 
 #[derive(clap::Args, Debug)]
 #[command(about = "Run the api server", args_conflicts_with_subcommands = true)]
@@ -31,6 +41,12 @@ pub struct Run {
     #[command(flatten)]
     pub index: IndexConfig,
 
+    #[command(flatten)]
+    pub search_cache: SearchCacheConfig,
+
+    #[command(flatten)]
+    pub realtime: RealtimeConfig,
+
     #[command(flatten)]
     pub storage: StorageConfig,
 
@@ -46,14 +62,46 @@ pub struct Run {
     #[command(flatten)]
     pub http: HttpServerConfig<Bombastic>,
 
+    #[command(flatten)]
+    pub redaction: RedactionConfig,
+
+    #[command(flatten)]
+    pub signature: SignatureConfig,
+
     /// Request limit for publish requests
     #[arg(long, default_value_t = ByteSize::mib(64).into())]
     pub publish_limit: BinaryByteSize,
+
+    /// Request limit for batch publish requests, each SBOM of a batch is limited by the publish limit
+    #[arg(long, default_value_t = ByteSize::mib(256).into())]
+    pub batch_limit: BinaryByteSize,
+
+    #[command(flatten)]
+    pub mget: MultiGetConfig,
+
+    /// Assign content derived identifiers (`sha256:<digest>`) to SBOMs published without an id
+    #[arg(long, default_value_t = false)]
+    pub content_ids: bool,
+
+    /// Maximum size of a part of an SBOM uploaded in parts. Parts but the last one must be at least 5 MiB.
+    #[arg(long, default_value_t = ByteSize::mib(64).into())]
+    pub upload_chunk_limit: BinaryByteSize,
+
+    /// Number of prior revisions kept when an SBOM is stored again, `0` disables keeping them
+    #[arg(long, default_value_t = 10)]
+    pub max_revisions: usize,
+
+    /// Maximum size of a compressed SBOM decompressed before being stored, refusing compression bombs. SBOMs stored as
+    /// they are are limited by the maximum document size of the storage while being validated.
+    #[arg(long, default_value_t = ByteSize::gib(1).into())]
+    pub max_decompressed_size: BinaryByteSize,
 }
 
 impl Run {
     pub async fn run(self, listener: Option<TcpListener>) -> anyhow::Result<ExitCode> {
         let index = self.index;
+        let search_cache = self.search_cache;
+        let realtime = self.realtime;
         let storage = self.storage;
 
         let (authn, authz) = self.auth.split(self.devmode)?.unzip();
@@ -71,6 +119,14 @@ impl Run {
 
         let tracing = self.infra.tracing;
         let publish_limit = self.publish_limit.as_u64() as usize;
+        let batch_limit = self.batch_limit.as_u64() as usize;
+        let mget = self.mget;
+        let content_ids = self.content_ids;
+        let upload_chunk_limit = self.upload_chunk_limit.as_u64() as usize;
+        let redaction = redact::Profiles::load(&self.redaction)?;
+        let trust_roots = signature::TrustRoots::load(&self.signature)?;
+        let max_revisions = self.max_revisions;
+        let max_decompressed_size = self.max_decompressed_size.0;
 
         Infrastructure::from(self.infra)
             .run(
@@ -88,11 +144,22 @@ impl Run {
                         .await;
                     let state = Self::configure(
                         index,
+                        search_cache,
+                        realtime,
                         storage,
                         synced_probe,
                         available_probe,
                         context.metrics.registry(),
                         self.devmode,
+                        mget,
+                        content_ids.then_some(publish_limit),
+                        publish_limit,
+                        batch_limit,
+                        upload_chunk_limit,
+                        redaction,
+                        trust_roots,
+                        max_revisions,
+                        max_decompressed_size,
                     )?;
 
                     let mut http = HttpServerBuilder::try_from(self.http)?
@@ -104,7 +171,13 @@ impl Run {
                             let swagger_oidc = swagger_oidc.clone();
 
                             svc.app_data(web::Data::new(state.clone())).configure(move |svc| {
-                                server::config(svc, authenticator.clone(), swagger_oidc.clone(), publish_limit)
+                                server::config(
+                                    svc,
+                                    authenticator.clone(),
+                                    swagger_oidc.clone(),
+                                    publish_limit,
+                                    upload_chunk_limit,
+                                )
                             });
                         });
 
@@ -120,16 +193,30 @@ impl Run {
         Ok(ExitCode::SUCCESS)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn configure(
         index_config: IndexConfig,
+        search_cache: SearchCacheConfig,
+        realtime: RealtimeConfig,
         storage: StorageConfig,
         synced_probe: Probe,
         available_probe: Probe,
         registry: &Registry,
         devmode: bool,
+        mget: MultiGetConfig,
+        content_ids: Option<usize>,
+        publish_limit: usize,
+        batch_limit: usize,
+        upload_chunk_limit: usize,
+        redaction: redact::Profiles,
+        trust_roots: signature::TrustRoots,
+        max_revisions: usize,
+        max_decompressed_size: ByteSize,
     ) -> anyhow::Result<Arc<AppState>> {
-        let sbom_index =
-            block_in_place(|| IndexStore::new(&storage, &index_config, bombastic_index::sbom::Index::new(), registry))?;
+        let sbom_index = block_in_place(|| {
+            IndexStore::new(&storage, &index_config, bombastic_index::sbom::Index::new(), registry)?
+                .with_realtime(&realtime)
+        })?;
 
         let package_index = block_in_place(|| {
             IndexStore::new(
@@ -140,12 +227,26 @@ impl Run {
             )
         })?;
 
+        let sbom_cache = SearchCache::new(&search_cache, registry, "sbom")?;
+        let package_cache = SearchCache::new(&search_cache, registry, "package")?;
+
         let storage = Storage::new(storage.process("bombastic", devmode), registry)?;
 
         let state = Arc::new(AppState {
             storage,
             sbom_index,
             package_index,
+            sbom_cache,
+            package_cache,
+            mget: MultiGet::new(mget),
+            content_ids,
+            publish_limit,
+            batch_limit,
+            upload_chunk_limit,
+            redaction,
+            trust_roots,
+            max_revisions,
+            max_decompressed_size,
         });
 
         let sinker = state.clone();
@@ -195,10 +296,33 @@ impl Run {
 pub(crate) type SbomIndex = IndexStore<bombastic_index::sbom::Index>;
 pub(crate) type PackageIndex = IndexStore<bombastic_index::packages::Index>;
 
+pub(crate) type SbomCache = SearchCache<(Vec<bombastic_model::prelude::SearchHit>, usize, Facets)>;
+pub(crate) type PackageCache = SearchCache<(Vec<bombastic_model::prelude::SearchPackageHit>, usize)>;
+
 pub struct AppState {
     storage: Storage,
     sbom_index: SbomIndex,
     package_index: PackageIndex,
+    /// Cached results of SBOM searches, including facets
+    sbom_cache: SbomCache,
+    package_cache: PackageCache,
+    mget: MultiGet,
+    /// When content ids are enabled, the maximum size of a document to derive the id from
+    content_ids: Option<usize>,
+    /// Maximum size of a document which needs to be converted before being stored
+    publish_limit: usize,
+    /// Maximum size of a batch of documents
+    batch_limit: usize,
+    /// Maximum size of a part of a document uploaded in parts
+    upload_chunk_limit: usize,
+    /// Profiles redacting exported documents
+    redaction: redact::Profiles,
+    /// Keys and certificates verifying the signatures documents are uploaded with
+    trust_roots: signature::TrustRoots,
+    /// Number of prior revisions kept when a document is stored again
+    max_revisions: usize,
+    /// Maximum size of a compressed document once decompressed
+    max_decompressed_size: ByteSize,
 }
 
 pub(crate) type SharedState = Arc<AppState>;
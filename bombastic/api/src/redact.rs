@@ -0,0 +1,311 @@
+//! Redaction of SBOMs shared outside of the organization.
+//!
+//! Profiles, configured in a YAML file, are ordered lists of rules applied to a copy of the JSON document when it's
+//! exported. The stored SBOM is never modified.
+
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// Number of hex digits of the SHA256 digest of a Package URL used in its mask.
+const MASK_DIGEST_LEN: usize = 16;
+
+/// Configuration of the redaction profiles.
+#[derive(Clone, Debug, Default, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Redaction")]
+pub struct RedactionConfig {
+    /// YAML file configuring the redaction profiles available when exporting SBOMs.
+    #[arg(env = "REDACTION_PROFILES", long = "redaction-profiles")]
+    pub profiles: Option<PathBuf>,
+}
+
+/// The redaction profiles, by their name.
+///
+/// ```yaml
+/// profiles:
+///   external:
+///     rules:
+///       - type: drop
+///         paths:
+///           - "**.externalReferences"
+///           - "packages.*.downloadLocation"
+///       - type: mask-purl
+///         patterns:
+///           - "pkg:maven/com.example.internal/*"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct Profiles {
+    profiles: HashMap<String, Profile>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Profile {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Rule {
+    /// Remove fields by their path, of keys separated by dots. `*` matches any key or array element, `**` any number
+    /// of levels.
+    Drop { paths: Vec<String> },
+    /// Replace Package URLs matching a wildcard pattern (`*`, `?`) by a mask, wherever they occur.
+    ///
+    /// The mask is derived from the Package URL, so references between components, like the `bom-ref` of CycloneDX,
+    /// stay consistent.
+    MaskPurl { patterns: Vec<String> },
+}
+
+impl Profiles {
+    /// Load the profiles of the configuration file, there are none if no file is configured.
+    pub fn load(config: &RedactionConfig) -> anyhow::Result<Self> {
+        match &config.profiles {
+            Some(path) => Self::from_file(path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let profiles = Self::from_yaml(&std::fs::read_to_string(path)?)?;
+        log::info!(
+            "Loaded {} redaction profiles from {}",
+            profiles.profiles.len(),
+            path.display()
+        );
+        Ok(profiles)
+    }
+
+    pub fn from_yaml(config: &str) -> anyhow::Result<Self> {
+        Ok(serde_yaml::from_str(config)?)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.profiles.get(name)
+    }
+}
+
+impl Profile {
+    /// Apply the rules to a document, in order.
+    pub fn redact(&self, document: &mut Value) {
+        for rule in &self.rules {
+            match rule {
+                Rule::Drop { paths } => {
+                    for path in paths {
+                        drop_path(document, &path.split('.').collect::<Vec<_>>());
+                    }
+                }
+                Rule::MaskPurl { patterns } => mask_purls(document, patterns),
+            }
+        }
+    }
+}
+
+fn drop_path(value: &mut Value, path: &[&str]) {
+    let Some((first, rest)) = path.split_first() else {
+        return;
+    };
+    if *first == "**" {
+        drop_path(value, rest);
+        for child in children(value) {
+            drop_path(child, path);
+        }
+        return;
+    }
+    match (rest.is_empty(), value) {
+        (true, Value::Object(object)) if *first == "*" => object.clear(),
+        (true, Value::Array(array)) if *first == "*" => array.clear(),
+        (true, Value::Object(object)) => {
+            object.remove(*first);
+        }
+        (false, value) if *first == "*" => {
+            for child in children(value) {
+                drop_path(child, rest);
+            }
+        }
+        (false, Value::Object(object)) => {
+            if let Some(child) = object.get_mut(*first) {
+                drop_path(child, rest);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn children(value: &mut Value) -> Vec<&mut Value> {
+    match value {
+        Value::Object(object) => object.values_mut().collect(),
+        Value::Array(array) => array.iter_mut().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn mask_purls(value: &mut Value, patterns: &[String]) {
+    match value {
+        Value::String(s) if s.starts_with("pkg:") && patterns.iter().any(|pattern| matches(pattern, s)) => {
+            *s = mask(s);
+        }
+        value => {
+            for child in children(value) {
+                mask_purls(child, patterns);
+            }
+        }
+    }
+}
+
+/// The mask replacing a Package URL, the same for the same Package URL.
+fn mask(purl: &str) -> String {
+    let digest = format!("{:x}", Sha256::digest(purl.as_bytes()));
+    format!("pkg:generic/redacted@{}", &digest[..MASK_DIGEST_LEN])
+}
+
+/// Match a value against a wildcard pattern, `*` matching any number and `?` matching a single character.
+fn matches(pattern: &str, value: &str) -> bool {
+    let (pattern, value): (Vec<char>, Vec<char>) = (pattern.chars().collect(), value.chars().collect());
+    let (mut p, mut v) = (0, 0);
+    // position of the last `*` in the pattern, and of the value when it was reached
+    let mut backtrack = None;
+    while v < value.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, v));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == value[v] => {
+                p += 1;
+                v += 1;
+            }
+            _ => match backtrack {
+                // let the last `*` match one more character
+                Some((star, matched)) => {
+                    p = star + 1;
+                    v = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const PROFILES: &str = r#"
+profiles:
+  external:
+    rules:
+      - type: drop
+        paths:
+          - "**.externalReferences"
+          - "packages.*.downloadLocation"
+      - type: mask-purl
+        patterns:
+          - "pkg:maven/com.example.internal/*"
+"#;
+
+    #[test]
+    fn wildcards() {
+        assert!(matches("pkg:maven/com.example/*", "pkg:maven/com.example/foo@1.0"));
+        assert!(matches("pkg:maven/*/foo@?.0", "pkg:maven/com.example/foo@1.0"));
+        assert!(matches("*", ""));
+        assert!(!matches("pkg:maven/com.example/*", "pkg:maven/com.example.other/foo"));
+        assert!(!matches("pkg:npm/foo", "pkg:npm/foo@1.0"));
+        assert!(matches("pkg:*/foo*@*", "pkg:npm/foobar@1.0"));
+    }
+
+    #[test]
+    fn redact_cyclonedx() {
+        let profiles = Profiles::from_yaml(PROFILES).unwrap();
+        assert!(profiles.get("internal").is_none());
+
+        let internal = "pkg:maven/com.example.internal/core@1.0";
+        let mut document = json!({
+            "bomFormat": "CycloneDX",
+            "metadata": {
+                "component": {
+                    "bom-ref": "app",
+                    "purl": "pkg:maven/com.example/app@1.0",
+                    "externalReferences": [{"type": "vcs", "url": "https://git.example.com/app"}]
+                }
+            },
+            "components": [
+                {
+                    "bom-ref": internal,
+                    "purl": internal,
+                    "components": [{"name": "nested", "externalReferences": []}]
+                },
+                {
+                    "bom-ref": "pkg:maven/org.public/lib@2.0",
+                    "purl": "pkg:maven/org.public/lib@2.0"
+                }
+            ],
+            "dependencies": [{"ref": "app", "dependsOn": [internal]}]
+        });
+        profiles.get("external").unwrap().redact(&mut document);
+
+        let masked = mask(internal);
+        assert!(!masked.contains("internal"));
+        assert_eq!(
+            document,
+            json!({
+                "bomFormat": "CycloneDX",
+                "metadata": {
+                    "component": {
+                        "bom-ref": "app",
+                        "purl": "pkg:maven/com.example/app@1.0"
+                    }
+                },
+                "components": [
+                    {
+                        "bom-ref": masked,
+                        "purl": masked,
+                        "components": [{"name": "nested"}]
+                    },
+                    {
+                        "bom-ref": "pkg:maven/org.public/lib@2.0",
+                        "purl": "pkg:maven/org.public/lib@2.0"
+                    }
+                ],
+                "dependencies": [{"ref": "app", "dependsOn": [masked]}]
+            })
+        );
+    }
+
+    #[test]
+    fn redact_spdx() {
+        let profiles = Profiles::from_yaml(PROFILES).unwrap();
+        let mut document = json!({
+            "spdxVersion": "SPDX-2.3",
+            "packages": [{
+                "SPDXID": "SPDXRef-core",
+                "downloadLocation": "https://git.example.com/core",
+                "externalRefs": [{
+                    "referenceType": "purl",
+                    "referenceLocator": "pkg:maven/com.example.internal/core@1.0"
+                }]
+            }]
+        });
+        profiles.get("external").unwrap().redact(&mut document);
+
+        assert_eq!(
+            document,
+            json!({
+                "spdxVersion": "SPDX-2.3",
+                "packages": [{
+                    "SPDXID": "SPDXRef-core",
+                    "externalRefs": [{
+                        "referenceType": "purl",
+                        "referenceLocator": mask("pkg:maven/com.example.internal/core@1.0")
+                    }]
+                }]
+            })
+        );
+    }
+}
@@ -1,7 +1,11 @@
 use std::io::{self};
 use std::sync::Arc;
 
-use crate::SharedState;
+use crate::{
+    batch::{self, Format},
+    signature::Signed,
+    AppState, SharedState,
+};
 use actix_web::{
     delete,
     error::{self, PayloadError},
@@ -10,58 +14,242 @@ use actix_web::{
         header::{self, Accept, AcceptEncoding, ContentType, Encoding, HeaderValue, CONTENT_ENCODING},
         Method, StatusCode,
     },
-    web, HttpRequest, HttpResponse, Responder,
+    post,
+    web::{self, Bytes},
+    HttpRequest, HttpResponse, Responder,
 };
+use bombastic_index::component::{is_component_id, Component};
+use bombastic_model::compression::Compression;
 use bombastic_model::prelude::*;
+use bombastic_model::protobuf::{self, CYCLONEDX_PROTOBUF};
+use bombastic_model::xml::{self, CYCLONEDX_XML};
 use derive_more::{Display, Error, From};
-use futures::TryStreamExt;
-use serde::Deserialize;
-use trustification_api::search::SearchOptions;
+use futures::{future::ok, stream::once, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use trustification_api::{
+    multi_get::{MultiGetEntry, MultiGetRequest},
+    search::{IndexFreshness, SearchField, SearchFieldType, SearchOptions},
+    version::ApiVersion,
+};
 use trustification_auth::{
     authenticator::{user::UserInformation, Authenticator},
     authorizer::Authorizer,
-    swagger_ui::{swagger_ui_with_auth, SwaggerUiOidc},
+    openapi::{annotate_permissions, permitted_operations, OperationPermission},
+    swagger_ui::{versioned_swagger_ui_with_auth, SwaggerUiOidc},
     Permission,
 };
 use trustification_index::tantivy::time::OffsetDateTime;
-use trustification_index::Error as IndexError;
-use trustification_infrastructure::new_auth;
-use trustification_storage::{Error as StorageError, Key, S3Path};
-use utoipa::OpenApi;
+use trustification_index::{cache::SearchKey, Error as IndexError};
+use trustification_infrastructure::{
+    app::{
+        conditional::Validators,
+        ndjson::ndjson_response,
+        range::{self, Requested},
+        search::SearchParams,
+        version::versioned_scope,
+    },
+    new_auth,
+};
+use trustification_storage::{
+    revision_id, stored_at, Error as StorageError, Head, Key, Provenance, S3Path, Source, Storage, Tombstone, Upload,
+    UploadPart, WalkerRun, WalkerRuns,
+};
+use utoipa::{openapi::path::PathItemType, OpenApi};
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(query_sbom, publish_sbom, search_sbom, delete_sbom, search_package),
-    components(schemas(SearchDocument, SearchResult, SearchPackageDocument, SearchPackageResult),)
+    paths(
+        query_sbom,
+        mget_sbom,
+        publish_sbom,
+        publish_batch,
+        start_upload,
+        upload_part,
+        complete_upload,
+        abort_upload,
+        validate_sbom,
+        search_sbom,
+        export_search_sbom,
+        search_sbom_schema,
+        delete_sbom,
+        search_package,
+        export_search_package,
+        search_package_schema,
+        component_usage,
+        sbom_provenance,
+        export_sbom,
+        sbom_graph,
+        sbom_dependents,
+        sbom_versions,
+        sbom_revision,
+        sbom_freshness,
+        sbom_changes,
+        export_sbom_changes,
+        publish_walker_run,
+        walker_runs
+    ),
+    components(schemas(
+        SearchDocument,
+        ExternalReference,
+        SearchResult,
+        SearchHit,
+        SearchPackageDocument,
+        SearchPackageHit,
+        SearchPackageResult,
+        ComponentUsage,
+        ComponentVersion,
+        ComponentTrend,
+        ComponentSbom,
+        SearchField,
+        SearchFieldType,
+        MultiGetRequest,
+        MultiGetEntry,
+        LintReport,
+        LintFinding,
+        LintSeverity,
+        LintRule,
+        IndexFreshness,
+        SbomGraph,
+        GraphNode,
+        GraphEdge,
+        EdgeType,
+        Dependents,
+        Change,
+        Changes
+    ),)
 )]
 pub struct ApiDoc;
 
+/// The permissions required by the operations of [`ApiDoc`].
+const PERMISSIONS: &[OperationPermission] = &[
+    ("/api/v1/sbom", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/sbom", PathItemType::Put, Permission::CreateSbom),
+    ("/api/v1/sbom", PathItemType::Delete, Permission::DeleteSbom),
+    ("/api/v1/sbom/_mget", PathItemType::Post, Permission::ReadSbom),
+    ("/api/v1/sbom/batch", PathItemType::Post, Permission::CreateSbom),
+    ("/api/v1/sbom/upload", PathItemType::Post, Permission::CreateSbom),
+    ("/api/v1/sbom/validate", PathItemType::Post, Permission::CreateSbom),
+    (
+        "/api/v1/sbom/upload/{token}",
+        PathItemType::Post,
+        Permission::CreateSbom,
+    ),
+    (
+        "/api/v1/sbom/upload/{token}",
+        PathItemType::Delete,
+        Permission::CreateSbom,
+    ),
+    (
+        "/api/v1/sbom/upload/{token}/{part}",
+        PathItemType::Put,
+        Permission::CreateSbom,
+    ),
+    ("/api/v1/sbom/search", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/sbom/search/export", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/sbom/search/schema", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/sbom/provenance", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/sbom/freshness", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/sbom/changes", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/sbom/changes/export", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/sbom/{id}/export", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/sbom/{id}/graph", PathItemType::Get, Permission::ReadSbom),
+    (
+        "/api/v1/sbom/{id}/dependencies",
+        PathItemType::Get,
+        Permission::ReadSbom,
+    ),
+    ("/api/v1/sbom/{id}/versions", PathItemType::Get, Permission::ReadSbom),
+    (
+        "/api/v1/sbom/{id}/versions/{revision}",
+        PathItemType::Get,
+        Permission::ReadSbom,
+    ),
+    ("/api/v1/package/search", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/package/search/export", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/package/search/schema", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/component", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/walker/runs", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/walker/runs", PathItemType::Post, Permission::CreateSbom),
+];
+
+/// The OpenAPI document of the handlers, mentioning the permission each operation requires.
+fn api_doc() -> utoipa::openapi::OpenApi {
+    let mut openapi = ApiDoc::openapi();
+    annotate_permissions(&mut openapi, PERMISSIONS);
+    openapi
+}
+
 pub fn config(
     cfg: &mut web::ServiceConfig,
     auth: Option<Arc<Authenticator>>,
     swagger_ui_oidc: Option<Arc<SwaggerUiOidc>>,
     publish_limit: usize,
+    upload_chunk_limit: usize,
 ) {
-    cfg.service(
-        web::scope("/api/v1")
-            .wrap(new_auth!(auth))
-            .service(query_sbom)
-            .service(search_sbom)
-            .service(search_package)
-            .service(sbom_status)
-            .service(
-                web::resource("/sbom")
-                    .app_data(web::PayloadConfig::new(publish_limit))
-                    .guard(guard::Any(guard::Method(Method::PUT)).or(guard::Method(Method::POST)))
-                    .to(publish_sbom),
-            )
-            .service(delete_sbom)
-            .service(delete_sboms),
-    )
-    .service(swagger_ui_with_auth(ApiDoc::openapi(), swagger_ui_oidc));
+    for version in ApiVersion::ALL {
+        cfg.service(
+            versioned_scope(version)
+                .wrap(new_auth!(auth.clone()))
+                .configure(|svc| services(svc, publish_limit, upload_chunk_limit)),
+        );
+    }
+    cfg.service(versioned_swagger_ui_with_auth(
+        ApiVersion::ALL.map(|version| (version.as_str(), version.openapi_url(), version.openapi(api_doc()))),
+        swagger_ui_oidc,
+    ));
 }
 
-const ACCEPT_ENCODINGS: [&str; 2] = ["bzip2", "zstd"];
+/// The routes shared by all API versions.
+fn services(cfg: &mut web::ServiceConfig, publish_limit: usize, upload_chunk_limit: usize) {
+    cfg.service(query_sbom)
+        .service(mget_sbom)
+        .service(search_sbom)
+        .service(search_sbom_schema)
+        .service(search_package)
+        .service(export_search_package)
+        .service(search_package_schema)
+        .service(component_usage)
+        .service(sbom_status)
+        .service(sbom_freshness)
+        .service(sbom_changes)
+        .service(sbom_provenance)
+        // before `export_sbom`, which would take `search` or `changes` for the id of an SBOM
+        .service(export_search_sbom)
+        .service(export_sbom_changes)
+        .service(export_sbom)
+        .service(sbom_graph)
+        .service(sbom_dependents)
+        .service(sbom_versions)
+        .service(sbom_revision)
+        .service(publish_walker_run)
+        .service(walker_runs)
+        .service(permitted_openapi)
+        .service(
+            web::resource("/sbom/validate")
+                .app_data(web::PayloadConfig::new(publish_limit))
+                .route(web::post().to(validate_sbom)),
+        )
+        .service(
+            web::resource("/sbom")
+                .app_data(web::PayloadConfig::new(publish_limit))
+                .guard(guard::Any(guard::Method(Method::PUT)).or(guard::Method(Method::POST)))
+                .to(publish_sbom),
+        )
+        .service(publish_batch)
+        .service(start_upload)
+        .service(
+            web::resource("/sbom/upload/{token}/{part}")
+                .app_data(web::PayloadConfig::new(upload_chunk_limit))
+                .route(web::put().to(upload_part)),
+        )
+        .service(complete_upload)
+        .service(abort_upload)
+        .service(delete_sbom)
+        .service(delete_sboms);
+}
+
+const ACCEPT_ENCODINGS: [&str; 3] = ["bzip2", "gzip", "zstd"];
 
 #[derive(Debug, Display, Error, From)]
 enum Error {
@@ -73,6 +261,46 @@ enum Error {
     InvalidContentType,
     #[display(fmt = "invalid encoding, see Accept-Encoding header")]
     InvalidContentEncoding,
+    #[display(fmt = "missing id")]
+    MissingId,
+    #[display(fmt = "expecting either an id or a digest")]
+    IdOrDigest,
+    #[display(fmt = "invalid digest '{}', expecting sha256:<hex>", "_0")]
+    InvalidDigest(#[error(not(source))] String),
+    #[display(fmt = "payload exceeds the limit of {} bytes", "_0")]
+    PayloadTooLarge(#[error(not(source))] usize),
+    #[display(fmt = "invalid protobuf content: {}", "_0")]
+    InvalidProtobuf(#[error(not(source))] String),
+    #[display(fmt = "invalid XML content: {}", "_0")]
+    InvalidXml(#[error(not(source))] String),
+    #[display(fmt = "expecting a valid package URL or component id")]
+    InvalidComponent,
+    #[display(fmt = "SBOMs which are converted before being stored can't be uploaded in parts")]
+    UnsupportedUploadType,
+    #[display(fmt = "unknown upload, it may have been completed or aborted")]
+    UnknownUpload,
+    #[display(fmt = "invalid part number {}, expecting 1 to 10000", "_0")]
+    InvalidPart(#[error(not(source))] u32),
+    #[display(fmt = "SBOM {}", "_0")]
+    Deleted(#[error(not(source))] Tombstone),
+    #[display(fmt = "SBOM {}, it can only be stored again through the API", "_0")]
+    Tombstoned(#[error(not(source))] Tombstone),
+    #[display(fmt = "unknown redaction profile '{}'", "_0")]
+    UnknownProfile(#[error(not(source))] String),
+    #[display(fmt = "document error: {}", "_0")]
+    Document(serde_json::Error),
+    #[display(fmt = "invalid batch type, see Accept header")]
+    InvalidBatchType,
+    #[display(fmt = "invalid batch: {}", "_0")]
+    InvalidBatch(#[error(not(source))] String),
+    #[display(fmt = "invalid JSON content: {}", "_0")]
+    InvalidJson(#[error(not(source))] String),
+    #[display(fmt = "package {} is not part of the SBOM", "_0")]
+    UnknownPackage(#[error(not(source))] String),
+    #[display(fmt = "invalid signature: {}", "_0")]
+    InvalidSignature(#[error(not(source))] String),
+    #[display(fmt = "signature couldn't be verified: {}", "_0")]
+    UnverifiedSignature(#[error(not(source))] String),
 }
 
 impl error::ResponseError for Error {
@@ -80,7 +308,13 @@ impl error::ResponseError for Error {
         let mut res = HttpResponse::build(self.status_code());
         res.insert_header(ContentType::plaintext());
         match self {
-            Self::InvalidContentType => res.insert_header(Accept::json()),
+            Self::InvalidContentType => res.insert_header((
+                header::ACCEPT,
+                format!("application/json, {CYCLONEDX_PROTOBUF}, {CYCLONEDX_XML}"),
+            )),
+            Self::InvalidBatchType => {
+                res.insert_header((header::ACCEPT, batch::FORMATS.map(|(typ, _)| typ).join(", ")))
+            }
             Self::InvalidContentEncoding => res.insert_header(AcceptEncoding(
                 ACCEPT_ENCODINGS
                     .iter()
@@ -100,8 +334,22 @@ impl error::ResponseError for Error {
         match self {
             Self::Storage(StorageError::NotFound) => StatusCode::NOT_FOUND,
             Self::Storage(StorageError::InvalidContent) => StatusCode::BAD_REQUEST,
+            Self::Storage(StorageError::ExceedsMaxSize(_)) => StatusCode::PAYLOAD_TOO_LARGE,
             Self::InvalidContentType | Self::InvalidContentEncoding => StatusCode::BAD_REQUEST,
+            Self::MissingId | Self::InvalidComponent => StatusCode::BAD_REQUEST,
+            Self::IdOrDigest | Self::InvalidDigest(_) => StatusCode::BAD_REQUEST,
+            Self::UnsupportedUploadType | Self::InvalidPart(_) => StatusCode::BAD_REQUEST,
+            Self::UnknownProfile(_) => StatusCode::BAD_REQUEST,
+            Self::InvalidSignature(_) | Self::UnverifiedSignature(_) => StatusCode::BAD_REQUEST,
+            Self::InvalidBatchType | Self::InvalidBatch(_) | Self::InvalidJson(_) => StatusCode::BAD_REQUEST,
+            Self::UnknownUpload | Self::UnknownPackage(_) => StatusCode::NOT_FOUND,
+            Self::Deleted(_) => StatusCode::GONE,
+            Self::Tombstoned(_) => StatusCode::CONFLICT,
+            Self::InvalidProtobuf(_) | Self::InvalidXml(_) => StatusCode::BAD_REQUEST,
+            Self::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
             Self::Index(IndexError::QueryParser(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::InvalidFacet(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::InvalidPattern(_)) => StatusCode::BAD_REQUEST,
             e => {
                 log::error!("{e:?}");
                 StatusCode::INTERNAL_SERVER_ERROR
@@ -110,352 +358,1741 @@ impl error::ResponseError for Error {
     }
 }
 
-/// Parameters to fetch and publish requests.
+/// Parameters to fetch and delete requests.
 #[derive(Debug, Deserialize)]
 struct IdentifierParams {
     /// Identifier of SBOM
     id: String,
 }
 
-/// Retrieve an SBOM using its identifier.
+/// Parameters to fetch requests, by identifier or by digest.
+#[derive(Debug, Deserialize)]
+struct QueryParams {
+    /// Identifier of SBOM
+    id: Option<String>,
+    /// SHA-256 digest of the content of SBOM, like `sha256:<hex>`
+    digest: Option<String>,
+}
+
+/// Parameters to publish requests.
+#[derive(Debug, Deserialize)]
+struct PublishParams {
+    /// Identifier of SBOM, derived from the content if missing
+    id: Option<String>,
+    /// How the SBOM was ingested, defaults to `api`
+    #[serde(default)]
+    source: Source,
+    /// Where the SBOM was retrieved from
+    source_url: Option<String>,
+}
+
+/// Retrieve an SBOM using its identifier, or the digest of its content.
+///
+/// SBOMs published as protobuf or XML encoded CycloneDX are stored as JSON, keeping the original. Clients preferring
+/// `application/x.cyclonedx+protobuf` or `application/vnd.cyclonedx+xml` receive the document as it was published, if
+/// it was published using that encoding.
+///
+/// SBOMs can only be retrieved by digest if the server stores them by the digest of their content.
+///
+/// Responses carry an `ETag` and a `Last-Modified` header. Clients polling for changes can pass them back using
+/// `If-None-Match` or `If-Modified-Since`, and receive `304 Not Modified` without the document if it didn't change.
+///
+/// A single byte range of the document can be requested using the `Range` header, like for resuming an interrupted
+/// download. Ranges are taken of the document as it is stored, so only if the client accepts its content encoding.
+/// Combined with `If-Range`, the range is only served if the document wasn't modified since the given date.
 #[utoipa::path(
     get,
     tag = "bombastic",
     path = "/api/v1/sbom",
     responses(
-        (status = 200, description = "SBOM found"),
+        (status = 200, description = "SBOM found", content_type = ["application/json", "application/x.cyclonedx+protobuf", "application/vnd.cyclonedx+xml"]),
+        (status = PARTIAL_CONTENT, description = "Requested range of the SBOM"),
+        (status = NOT_MODIFIED, description = "SBOM didn't change since the client retrieved it"),
         (status = NOT_FOUND, description = "SBOM not found in archive"),
-        (status = BAD_REQUEST, description = "Missing valid id or index entry"),
+        (status = GONE, description = "SBOM was deleted"),
+        (status = RANGE_NOT_SATISFIABLE, description = "Requested range is outside of the SBOM"),
+        (status = BAD_REQUEST, description = "Missing valid id, digest or index entry"),
     ),
     params(
-        ("id" = String, Query, description = "Identifier of SBOM to fetch"),
+        ("id" = Option<String>, Query, description = "Identifier of SBOM to fetch"),
+        ("digest" = Option<String>, Query, description = "SHA-256 digest of the content of SBOM to fetch, like sha256:<hex>, instead of its identifier"),
+        ("If-None-Match" = Option<String>, Header, description = "Entity tag of the SBOM the client has"),
+        ("If-Modified-Since" = Option<String>, Header, description = "When the client retrieved the SBOM"),
+        ("Range" = Option<String>, Header, description = "Single byte range of the SBOM to fetch, like bytes=0-1023"),
+        ("If-Range" = Option<String>, Header, description = "Only fetch the range if the SBOM wasn't modified since this date"),
     )
 )]
 #[get("/sbom")]
 async fn query_sbom(
+    req: HttpRequest,
     state: web::Data<SharedState>,
-    params: web::Query<IdentifierParams>,
+    params: web::Query<QueryParams>,
+    accept: Option<web::Header<Accept>>,
     accept_encoding: web::Header<AcceptEncoding>,
     authorizer: web::Data<Authorizer>,
     user: UserInformation,
 ) -> actix_web::Result<impl Responder> {
     authorizer.require(&user, Permission::ReadSbom)?;
 
-    let key = params.into_inner().id;
-    let path: S3Path = S3Path::from_key(Key::from(&key));
-    log::trace!("Querying SBOM using id {}", key);
+    let params = params.into_inner();
+    let (key, path) = match (params.id, params.digest) {
+        (Some(id), None) => {
+            log::trace!("Querying SBOM using id {}", id);
+            let path = S3Path::from_key(Key::from(&id));
+            (Some(id), path)
+        }
+        (None, Some(digest)) => {
+            log::trace!("Querying SBOM using digest {}", digest);
+            if S3Path::from_digest(&digest).is_none() {
+                return Err(Error::InvalidDigest(digest).into());
+            }
+            // only the content of SBOMs which weren't deleted
+            let path = state.storage.resolve_digest(&digest).await.map_err(Error::Storage)?;
+            (None, path)
+        }
+        _ => return Err(Error::IdOrDigest.into()),
+    };
     let storage = &state.storage;
+    let preferred = accept
+        .and_then(|accept| accept.ranked().into_iter().next())
+        .and_then(|mime| converter(mime.essence_str()))
+        .map(|(content_type, _)| content_type);
+    // originals are kept by identifier
+    if let (Some(preferred), Some(key)) = (preferred, &key) {
+        match storage.get_original(Key::from(key)).await {
+            Ok((content_type, data)) if content_type.as_deref() == Some(preferred) => {
+                return Ok(HttpResponse::Ok().content_type(preferred).body(data));
+            }
+            // fall back to the JSON document
+            Ok(_) | Err(StorageError::NotFound) => {}
+            Err(e) => return Err(Error::Storage(e).into()),
+        }
+    }
+    let head = match storage.get_head(path.clone()).await {
+        Ok(head) => Some(head),
+        Err(StorageError::NotFound) => {
+            return Err(match key {
+                Some(key) => not_found(storage, &key).await,
+                None => Error::Storage(StorageError::NotFound),
+            }
+            .into())
+        }
+        Err(_) => None,
+    };
+    let validators = head
+        .as_ref()
+        .map(|head| Validators::new(head.etag.as_deref(), head.last_modified.as_deref()))
+        .unwrap_or_default();
+    if validators.is_not_modified(&req) {
+        return Ok(validators.not_modified());
+    }
+    let mut response = HttpResponse::Ok();
+    validators.apply(&mut response);
     // determine the encoding of the stored object, if any
-    let encoding = storage.get_head(path.clone()).await.ok().and_then(|head| {
+    let encoding = head.as_ref().and_then(|head| {
         head.content_encoding
             .as_ref()
             .and_then(|e| e.parse::<Encoding>().ok())
             .and_then(|e| accept_encoding.negotiate([&e].into_iter()).filter(|x| x == &e))
     });
+    // ranges are served of the stored object, so only if it is served as it is stored
+    let length = head
+        .filter(|head| head.content_encoding.is_none() || encoding.is_some())
+        .and_then(|head| head.content_length);
+    if let Some(length) = length {
+        range::accept_ranges(&mut response);
+        match Requested::new(&req, length, &validators) {
+            Requested::Full => {}
+            Requested::Partial(start, end) => {
+                let data = storage
+                    .get_encoded_range(path, start, end)
+                    .await
+                    .map_err(Error::Storage)?;
+                range::partial(&mut response, start, end, length);
+                if let Some(enc) = encoding {
+                    response.insert_header((header::CONTENT_ENCODING, enc.to_string()));
+                }
+                return Ok(response.content_type(ContentType::json()).body(data));
+            }
+            Requested::Unsatisfiable => return Ok(range::unsatisfiable(length)),
+        }
+    }
     match encoding {
         // if client's accept-encoding includes S3 encoding, return encoded stream
-        Some(enc) => Ok(HttpResponse::Ok()
+        Some(enc) => Ok(response
             .content_type(ContentType::json())
             .insert_header((header::CONTENT_ENCODING, enc.to_string()))
             .streaming(storage.get_encoded_stream(path).await.map_err(Error::Storage)?)),
         // otherwise, decode the stream
-        None => Ok(HttpResponse::Ok()
+        None => Ok(response
             .content_type(ContentType::json())
             .streaming(storage.get_decoded_stream(&path).await.map_err(Error::Storage)?)),
     }
 }
 
-/// Parameters for search query.
-#[derive(Debug, Deserialize)]
-pub struct SearchParams {
-    /// Search query string
-    pub q: String,
-    /// Offset of documents to return (for pagination)
-    #[serde(default = "default_offset")]
-    pub offset: usize,
-    /// Max number of documents to return
-    #[serde(default = "default_limit")]
-    pub limit: usize,
-    /// Provide a detailed explanation of query matches
-    #[serde(default = "default_explain")]
-    pub explain: bool,
-    /// Provide additional metadata from the index
-    #[serde(default = "default_metadata")]
-    pub metadata: bool,
-    /// Enable fetching document summaries
-    #[serde(default = "default_summaries")]
-    pub summaries: bool,
-}
-
-const fn default_offset() -> usize {
-    0
-}
-
-const fn default_limit() -> usize {
-    10
+/// The error of an SBOM which isn't stored, telling a deleted SBOM from one which was never stored.
+async fn not_found(storage: &Storage, id: &str) -> Error {
+    match storage.get_tombstone(Key::from(id)).await {
+        Ok(tombstone) => tombstone.map_or(Error::Storage(StorageError::NotFound), Error::Deleted),
+        Err(e) => Error::Storage(e),
+    }
 }
 
-const fn default_explain() -> bool {
-    false
+/// Parameters passed when exporting an SBOM.
+#[derive(Debug, Deserialize)]
+struct ExportParams {
+    /// Name of the redaction profile
+    profile: String,
 }
 
-const fn default_metadata() -> bool {
-    false
-}
+/// Export an SBOM for sharing, redacted using a profile.
+///
+/// The rules of the profile, configured by the administrator, drop fields like internal repository URLs and mask the
+/// Package URLs of proprietary components. The stored SBOM isn't modified.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/{id}/export",
+    responses(
+        (status = 200, description = "Redacted SBOM", content_type = "application/json"),
+        (status = NOT_FOUND, description = "SBOM not found in archive"),
+        (status = GONE, description = "SBOM was deleted"),
+        (status = BAD_REQUEST, description = "Unknown redaction profile"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("id" = String, Path, description = "Identifier of the SBOM"),
+        ("profile" = String, Query, description = "Name of the redaction profile, like `external`"),
+    )
+)]
+#[get("/sbom/{id}/export")]
+async fn export_sbom(
+    state: web::Data<SharedState>,
+    id: web::Path<String>,
+    params: web::Query<ExportParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
 
-const fn default_summaries() -> bool {
-    true
-}
+    let id = id.into_inner();
+    let profile = state
+        .redaction
+        .get(&params.profile)
+        .ok_or_else(|| Error::UnknownProfile(params.profile.clone()))?;
+    let data = match read_sbom(&state.storage, &id).await {
+        Ok(data) => data,
+        Err(StorageError::NotFound) => return Err(not_found(&state.storage, &id).await.into()),
+        Err(e) => return Err(Error::Storage(e).into()),
+    };
+    let mut document: serde_json::Value = serde_json::from_slice(&data).map_err(Error::Document)?;
+    profile.redact(&mut document);
+    log::debug!("Exporting SBOM {id} using redaction profile {}", params.profile);
 
-impl From<&SearchParams> for SearchOptions {
-    fn from(value: &SearchParams) -> Self {
-        Self {
-            explain: value.explain,
-            metadata: value.metadata,
-            summaries: value.summaries,
-        }
-    }
+    Ok(HttpResponse::Ok().json(document))
 }
 
-/// Search for an SBOM using a free form search query.
+/// Retrieve the graph of the packages of an SBOM.
 ///
-/// See the [documentation](https://docs.trustification.dev/trustification/user/retrieve.html) for a description of the query language.
+/// Edges are typed: besides dependencies (`DEPENDS_ON`) and contained packages (`CONTAINS`), the SPDX `VARIANT_OF` and
+/// `GENERATED_FROM` relationships link packages to the ones they were built from, tracing binaries back to their
+/// sources. Inverse relationships, like `DEPENDENCY_OF` or `GENERATES`, are turned around.
 #[utoipa::path(
     get,
     tag = "bombastic",
-    path = "/api/v1/sbom/search",
+    path = "/api/v1/sbom/{id}/graph",
     responses(
-        (status = 200, description = "Search completed"),
-        (status = BAD_REQUEST, description = "Bad query"),
+        (status = 200, description = "Graph of the packages of the SBOM", body = SbomGraph),
+        (status = NOT_FOUND, description = "SBOM not found in archive"),
+        (status = GONE, description = "SBOM was deleted"),
         (status = 401, description = "Not authenticated"),
     ),
     params(
-        ("q" = String, Query, description = "Search query"),
+        ("id" = String, Path, description = "Identifier of the SBOM"),
     )
 )]
-#[get("/sbom/search")]
-async fn search_sbom(
+#[get("/sbom/{id}/graph")]
+async fn sbom_graph(
     state: web::Data<SharedState>,
-    params: web::Query<SearchParams>,
+    id: web::Path<String>,
     authorizer: web::Data<Authorizer>,
     user: UserInformation,
 ) -> actix_web::Result<impl Responder> {
     authorizer.require(&user, Permission::ReadSbom)?;
 
-    let params = params.into_inner();
-
-    log::info!("Querying SBOM: '{}'", params.q);
+    let id = id.into_inner();
+    let data = match read_sbom(&state.storage, &id).await {
+        Ok(data) => data,
+        Err(StorageError::NotFound) => return Err(not_found(&state.storage, &id).await.into()),
+        Err(e) => return Err(Error::Storage(e).into()),
+    };
+    let document: serde_json::Value = serde_json::from_slice(&data).map_err(Error::Document)?;
 
-    let (result, total) = actix_web::web::block(move || {
-        state
-            .sbom_index
-            .search(&params.q, params.offset, params.limit, (&params).into())
-    })
-    .await?
-    .map_err(Error::Index)?;
+    Ok(HttpResponse::Ok().json(SbomGraph::from_json(&document)))
+}
 
-    Ok(HttpResponse::Ok().json(SearchResult { total, result }))
+/// Parameters to retrieve the dependents of a package.
+#[derive(Debug, Deserialize)]
+struct DependentsParams {
+    /// Package URL of the package
+    purl: String,
 }
 
-/// Search for a package using a free form search query.
+/// Retrieve the packages of an SBOM depending on a package, directly or transitively.
 ///
-/// See the [documentation](https://docs.trustification.dev/trustification/user/retrieve.html) for a description of the query language.
+/// Dependencies are taken from the `dependencies` section of CycloneDX SBOMs and the `DEPENDS_ON` and `CONTAINS`
+/// relationships of SPDX SBOMs, and their inverse, like `CONTAINED_BY`.
 #[utoipa::path(
     get,
     tag = "bombastic",
-    path = "/api/v1/package/search",
+    path = "/api/v1/sbom/{id}/dependencies",
     responses(
-        (status = 200, description = "Search completed"),
-        (status = BAD_REQUEST, description = "Bad query"),
+        (status = 200, description = "Packages depending on the package", body = Dependents),
+        (status = NOT_FOUND, description = "SBOM not found in archive, or the package is not part of it"),
+        (status = GONE, description = "SBOM was deleted"),
         (status = 401, description = "Not authenticated"),
     ),
     params(
-        ("q" = String, Query, description = "Search query"),
+        ("id" = String, Path, description = "Identifier of the SBOM"),
+        ("purl" = String, Query, description = "Package URL of the package"),
     )
 )]
-#[get("/package/search")]
-async fn search_package(
+#[get("/sbom/{id}/dependencies")]
+async fn sbom_dependents(
     state: web::Data<SharedState>,
-    params: web::Query<SearchParams>,
+    id: web::Path<String>,
+    params: web::Query<DependentsParams>,
     authorizer: web::Data<Authorizer>,
     user: UserInformation,
 ) -> actix_web::Result<impl Responder> {
-    // TODO: Should this use a different permission?
     authorizer.require(&user, Permission::ReadSbom)?;
 
-    let params = params.into_inner();
-
-    log::info!("Querying Package: '{}'", params.q);
-
-    let (result, total) = actix_web::web::block(move || {
-        state
-            .package_index
-            .search(&params.q, params.offset, params.limit, (&params).into())
-    })
-    .await?
-    .map_err(Error::Index)?;
+    let id = id.into_inner();
+    let data = match read_sbom(&state.storage, &id).await {
+        Ok(data) => data,
+        Err(StorageError::NotFound) => return Err(not_found(&state.storage, &id).await.into()),
+        Err(e) => return Err(Error::Storage(e).into()),
+    };
+    let document: serde_json::Value = serde_json::from_slice(&data).map_err(Error::Document)?;
 
-    Ok(HttpResponse::Ok().json(SearchPackageResult { total, result }))
+    let params = params.into_inner();
+    let dependents = SbomGraph::from_json(&document)
+        .dependents(&params.purl)
+        .ok_or(Error::UnknownPackage(params.purl))?;
+    Ok(HttpResponse::Ok().json(dependents))
 }
 
-/// Upload an SBOM with an identifier.
+/// List the versions of an SBOM: the current one, followed by the prior revisions kept when it was stored again.
 ///
-/// Clients may split the transfer using multipart uploads. The only supported content type is JSON, but content encoding can be unset, bzip2 or zstd.
+/// Revisions are listed from the newest to the oldest, only the current one is indexed. The number of kept revisions is
+/// limited by the server, older ones are removed.
 #[utoipa::path(
-    put,
+    get,
     tag = "bombastic",
-    path = "/api/v1/sbom",
-    request_body(content = Value, description = "The SBOM to be uploaded", content_type = "application/json"),
+    path = "/api/v1/sbom/{id}/versions",
     responses(
-        (status = 200, description = "SBOM uploaded successfully"),
-        (status = 401, description = "User is not authenticated"),
-        (status = 403, description = "User is not allowed to perform operation"),
-        (status = BAD_REQUEST, description = "Missing valid id or invalid content"),
+        (status = 200, description = "Versions of the SBOM"),
+        (status = NOT_FOUND, description = "SBOM not found in archive"),
+        (status = GONE, description = "SBOM was deleted"),
+        (status = 401, description = "Not authenticated"),
     ),
     params(
-        ("id" = String, Query, description = "Identifier assigned to the SBOM"),
+        ("id" = String, Path, description = "Identifier of the SBOM"),
     )
 )]
-async fn publish_sbom(
-    req: HttpRequest,
+#[get("/sbom/{id}/versions")]
+async fn sbom_versions(
     state: web::Data<SharedState>,
-    params: web::Query<IdentifierParams>,
-    payload: web::Payload,
-    content_type: Option<web::Header<ContentType>>,
+    id: web::Path<String>,
     authorizer: web::Data<Authorizer>,
     user: UserInformation,
 ) -> actix_web::Result<impl Responder> {
-    authorizer.require(&user, Permission::CreateSbom)?;
+    authorizer.require(&user, Permission::ReadSbom)?;
 
-    let typ = verify_type(content_type)?;
-    let enc = verify_encoding(req.headers().get(CONTENT_ENCODING))?;
-    let id = &params.id;
-    let payload = payload.map_err(|e| match e {
-        PayloadError::Io(e) => StorageError::Io(e),
-        _ => StorageError::Io(io::Error::new(io::ErrorKind::Other, e)),
-    });
-    let size = state
-        .storage
-        .put_stream(id.into(), typ.as_ref(), enc, payload)
-        .await
-        .map_err(Error::Storage)?;
-    let msg = format!("Successfully uploaded SBOM: id={id}, size={size}");
-    log::info!("{}", msg);
-    Ok(HttpResponse::Created().body(msg))
+    match state.storage.list_revisions(Key::from(id.as_str())).await {
+        Ok(revisions) => Ok(HttpResponse::Ok().json(revisions)),
+        Err(StorageError::NotFound) => Err(not_found(&state.storage, &id).await.into()),
+        Err(e) => Err(Error::Storage(e).into()),
+    }
 }
 
-fn verify_type(content_type: Option<web::Header<ContentType>>) -> Result<ContentType, Error> {
-    if let Some(hdr) = content_type {
-        let ct = hdr.into_inner();
-        if ct == ContentType::json() {
-            return Ok(ct);
+/// Retrieve a version of an SBOM by its revision, as listed by the versions of the SBOM.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/{id}/versions/{revision}",
+    responses(
+        (status = 200, description = "SBOM revision found", content_type = "application/json"),
+        (status = NOT_FOUND, description = "SBOM or revision not found in archive"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("id" = String, Path, description = "Identifier of the SBOM"),
+        ("revision" = String, Path, description = "Identifier of the revision"),
+    )
+)]
+#[get("/sbom/{id}/versions/{revision}")]
+async fn sbom_revision(
+    state: web::Data<SharedState>,
+    path: web::Path<(String, String)>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let (id, revision) = path.into_inner();
+    let key = Key::from(&id);
+    let path = match state.storage.get_head(S3Path::from_revision(key, &revision)).await {
+        Ok(_) => S3Path::from_revision(key, &revision),
+        // the revision may be the current version
+        Err(StorageError::NotFound) => {
+            let path = S3Path::from_key(key);
+            let provenance = state.storage.get_provenance(&path).await.map_err(Error::Storage)?;
+            if revision_id(stored_at(provenance.as_ref())) != revision {
+                return Err(Error::Storage(StorageError::NotFound).into());
+            }
+            path
         }
-    }
-    Err(Error::InvalidContentType)
-}
+        Err(e) => return Err(Error::Storage(e).into()),
+    };
 
-// bzip2 prevents us from using the ContentEncoding enum
-fn verify_encoding(content_encoding: Option<&HeaderValue>) -> Result<Option<&str>, Error> {
-    match content_encoding {
-        Some(enc) => match enc.to_str() {
-            Ok(v) if ACCEPT_ENCODINGS.contains(&v) => Ok(Some(v)),
-            _ => Err(Error::InvalidContentEncoding),
-        },
-        None => Ok(None),
-    }
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .streaming(state.storage.get_decoded_stream(&path).await.map_err(Error::Storage)?))
 }
 
-/// Delete an SBOM using its identifier.
+/// Retrieve the provenance of an SBOM: who stored it, from where and when.
+///
+/// The principal which stored the SBOM is left out for anonymous readers.
 #[utoipa::path(
-    delete,
+    get,
     tag = "bombastic",
-    path = "/api/v1/sbom",
+    path = "/api/v1/sbom/provenance",
     responses(
-        (status = 204, description = "SBOM either deleted or nonexistent"),
-        (status = 401, description = "User is not authenticated"),
-        (status = 403, description = "User is not allowed to perform operation"),
-        (status = BAD_REQUEST, description = "Missing id"),
+        (status = 200, description = "Provenance of the SBOM"),
+        (status = NOT_FOUND, description = "SBOM not found, or stored without provenance"),
+        (status = 401, description = "Not authenticated"),
     ),
     params(
-        ("id" = String, Query, description = "Package URL or product identifier of SBOM to query"),
+        ("id" = String, Query, description = "Identifier of the SBOM"),
     )
 )]
-#[delete("/sbom")]
-async fn delete_sbom(
+#[get("/sbom/provenance")]
+async fn sbom_provenance(
     state: web::Data<SharedState>,
     params: web::Query<IdentifierParams>,
     authorizer: web::Data<Authorizer>,
     user: UserInformation,
 ) -> actix_web::Result<impl Responder> {
-    authorizer.require(&user, Permission::DeleteSbom)?;
-
-    let params = params.into_inner();
-    let id = &params.id;
-    log::trace!("Deleting SBOM using id {id}");
-    state.storage.delete(id.into()).await.map_err(Error::Storage)?;
+    authorizer.require(&user, Permission::ReadSbom)?;
 
-    Ok(HttpResponse::NoContent().finish())
+    let path = S3Path::from_key(Key::from(&params.id));
+    match state.storage.get_head(path).await {
+        Ok(Head {
+            provenance: Some(provenance),
+            ..
+        }) => Ok(HttpResponse::Ok().json(match user {
+            // principals are accounts of the uploaders, only shown to authenticated readers
+            UserInformation::Authenticated(_) => provenance,
+            UserInformation::Anonymous => provenance.without_principal(),
+        })),
+        Ok(_) => Ok(HttpResponse::NotFound().finish()),
+        Err(e) => {
+            log::warn!("Unable to locate object with key {}: {:?}", params.id, e);
+            Ok(HttpResponse::NotFound().finish())
+        }
+    }
 }
 
-/// Delete all SBOMs
-#[delete("/sbom/all")]
-async fn delete_sboms(
+/// Fetch multiple SBOMs using their identifiers.
+///
+/// The response is a stream of newline delimited JSON objects, one for each requested identifier, in the
+/// order of the request. Each entry carries its own status code, as well as the document or an error.
+#[utoipa::path(
+    post,
+    tag = "bombastic",
+    path = "/api/v1/sbom/_mget",
+    request_body = MultiGetRequest,
+    responses(
+        (status = 200, description = "Stream of results", body = MultiGetEntry, content_type = "application/x-ndjson"),
+        (status = BAD_REQUEST, description = "Too many identifiers requested"),
+        (status = 401, description = "Not authenticated"),
+    ),
+)]
+#[post("/sbom/_mget")]
+async fn mget_sbom(
     state: web::Data<SharedState>,
+    request: web::Json<MultiGetRequest>,
     authorizer: web::Data<Authorizer>,
     user: UserInformation,
-) -> actix_web::Result<impl Responder> {
-    authorizer.require(&user, Permission::DeleteSbom)?;
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadSbom)?;
 
-    state.storage.delete_all().await.map_err(Error::Storage)?;
+    let request = request.into_inner();
+    log::debug!("Fetching {} SBOMs", request.ids.len());
 
-    Ok(HttpResponse::NoContent().finish())
+    let fetcher = SharedState::clone(&state);
+    Ok(state.mget.respond(request, move |id| {
+        let state = fetcher.clone();
+        async move { fetch_entry(&state, id).await }
+    })?)
 }
 
-/// Search for a status of sbom using a free form search query.
+/// Read a stored SBOM, decoding it.
+async fn read_sbom(storage: &Storage, id: &str) -> Result<Vec<u8>, StorageError> {
+    storage
+        .get_decoded_stream(&S3Path::from_key(Key::from(id)))
+        .await?
+        .try_fold(Vec::new(), |mut data, chunk| async move {
+            data.extend_from_slice(&chunk);
+            Ok(data)
+        })
+        .await
+}
+
+/// Fetch a single document for a multi-get request.
+async fn fetch_entry(state: &AppState, id: String) -> MultiGetEntry {
+    match read_sbom(&state.storage, &id).await {
+        Ok(data) => match serde_json::from_slice(&data) {
+            Ok(document) => MultiGetEntry::found(id, document),
+            Err(err) => {
+                log::warn!("Failed to parse SBOM {id}: {err}");
+                MultiGetEntry::failed(id, 500, "Invalid document")
+            }
+        },
+        Err(StorageError::NotFound) => MultiGetEntry::failed(id, 404, "Not found"),
+        Err(err) => {
+            log::warn!("Failed to fetch SBOM {id}: {err}");
+            MultiGetEntry::failed(id, 500, "Internal server error")
+        }
+    }
+}
+
+/// Search for an SBOM using a free form search query.
 ///
 /// See the [documentation](https://docs.trustification.dev/trustification/user/retrieve.html) for a description of the query language.
 #[utoipa::path(
     get,
     tag = "bombastic",
-    path = "/api/v1/sbom/status",
+    path = "/api/v1/sbom/search",
     responses(
-    (status = 200, description = "Search completed"),
-    (status = BAD_REQUEST, description = "Bad query"),
-    (status = 401, description = "Not authenticated"),
+        (status = 200, description = "Search completed"),
+        (status = BAD_REQUEST, description = "Bad query, or search limits exceeded"),
+        (status = 401, description = "Not authenticated"),
     ),
     params(
-    ("q" = String, Query, description = "Search query"),
+        ("q" = String, Query, description = "Search query"),
+        ("facets" = Option<String>, Query, description = "Comma separated list of facets to return counts for (ecosystem, license, supplier, type, classifier)"),
     )
 )]
-#[get("/sbom/status")]
-async fn sbom_status(
+#[get("/sbom/search")]
+async fn search_sbom(
     state: web::Data<SharedState>,
+    params: SearchParams,
     authorizer: web::Data<Authorizer>,
     user: UserInformation,
 ) -> actix_web::Result<impl Responder> {
     authorizer.require(&user, Permission::ReadSbom)?;
 
-    let state_clone = Arc::clone(&state);
+    log::info!("Querying SBOM: '{}'", params.q);
 
-    let (result, _total) = actix_web::web::block(move || {
-        state_clone.sbom_index.search(
-            "-sort:indexedTimestamp",
-            0,
-            1,
-            SearchOptions {
-                metadata: false,
-                explain: false,
-                summaries: true,
-            },
-        )
+    let index_updated_at = state.sbom_index.updated_at();
+    let key = SearchKey::new(&params.q, params.offset, params.limit, (&params).into()).with_facets(params.facets());
+    let (result, total, facets) = actix_web::web::block(move || {
+        let generation = state.sbom_index.generation();
+        state.sbom_cache.get_or_search(generation, key, |key| {
+            let (result, total) = state
+                .sbom_index
+                .search(&key.q, key.offset, key.limit, key.options.clone())?;
+            let facets = state.sbom_index.facets(&key.q, &key.facets)?;
+            Ok::<_, IndexError>((result, total, facets))
+        })
     })
     .await?
     .map_err(Error::Index)?;
 
-    let state_clone = Arc::clone(&state);
-    let total_docs = actix_web::web::block(move || state_clone.sbom_index.get_total_docs())
-        .await?
-        .map_err(Error::Index)?;
+    params.response(&SearchResult {
+        total,
+        result,
+        facets,
+        index_updated_at,
+    })
+}
+
+/// Number of documents read from the index at once when exporting search results.
+const EXPORT_PAGE_SIZE: usize = 500;
+
+/// Parameters passed when exporting search results.
+#[derive(Debug, Deserialize)]
+struct SearchExportParams {
+    /// Search query
+    q: String,
+}
+
+/// Export all the SBOMs matching a free form search query.
+///
+/// Unlike the search endpoint, results aren't limited to a page: the response is a chunked stream of newline delimited
+/// JSON objects, one for each matching SBOM, read page by page from a snapshot of the index taken when the request was
+/// received. Lines starting with `#` are heartbeat comments, sent to keep the connection alive while the next page is
+/// read, which must be skipped.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/search/export",
+    responses(
+        (status = 200, description = "Stream of matching SBOMs", body = SearchHit, content_type = "application/x-ndjson"),
+        (status = BAD_REQUEST, description = "Bad query"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("q" = String, Query, description = "Search query"),
+    )
+)]
+#[get("/sbom/search/export")]
+async fn export_search_sbom(
+    state: web::Data<SharedState>,
+    params: web::Query<SearchExportParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    log::info!("Exporting SBOMs matching: '{}'", params.q);
+
+    let state = SharedState::clone(&state);
+    let q = params.into_inner().q;
+    let cursor = {
+        let state = state.clone();
+        web::block(move || state.sbom_index.cursor(&q, None))
+            .await?
+            .map_err(Error::Index)?
+    };
+
+    let hits = futures::stream::try_unfold((state, cursor), |(state, mut cursor)| async move {
+        if cursor.is_done() {
+            return Ok(None);
+        }
+        let (hits, state, cursor) = web::block(move || {
+            let hits = state
+                .sbom_index
+                .next_page(&mut cursor, EXPORT_PAGE_SIZE, &SearchOptions::default());
+            (hits, state, cursor)
+        })
+        .await?;
+        let hits = hits.map_err(Error::Index)?;
+        Ok::<_, actix_web::Error>(Some((futures::stream::iter(hits.into_iter().map(Ok)), (state, cursor))))
+    })
+    .try_flatten();
+
+    Ok(ndjson_response(hits))
+}
+
+/// List the qualifiers supported by the SBOM search query language.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/search/schema",
+    responses(
+        (status = 200, description = "Qualifiers of the query language", body = Vec<SearchField>),
+        (status = 401, description = "Not authenticated"),
+    ),
+)]
+#[get("/sbom/search/schema")]
+async fn search_sbom_schema(
+    state: web::Data<SharedState>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    Ok(HttpResponse::Ok().json(state.sbom_index.search_fields()))
+}
+
+/// Search for a package using a free form search query.
+///
+/// See the [documentation](https://docs.trustification.dev/trustification/user/retrieve.html) for a description of the query language.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/package/search",
+    responses(
+        (status = 200, description = "Search completed"),
+        (status = BAD_REQUEST, description = "Bad query, or search limits exceeded"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("q" = String, Query, description = "Search query"),
+    )
+)]
+#[get("/package/search")]
+async fn search_package(
+    state: web::Data<SharedState>,
+    params: SearchParams,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    // TODO: Should this use a different permission?
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    log::info!("Querying Package: '{}'", params.q);
+
+    let index_updated_at = state.package_index.updated_at();
+    let key = SearchKey::new(&params.q, params.offset, params.limit, (&params).into());
+    let (result, total) = actix_web::web::block(move || {
+        let generation = state.package_index.generation();
+        state.package_cache.get_or_search(generation, key, |key| {
+            state
+                .package_index
+                .search(&key.q, key.offset, key.limit, key.options.clone())
+        })
+    })
+    .await?
+    .map_err(Error::Index)?;
+
+    params.response(&SearchPackageResult {
+        total,
+        result,
+        index_updated_at,
+    })
+}
+
+/// Export all the packages matching a free form search query.
+///
+/// Like exporting SBOMs, the response is a chunked stream of newline delimited JSON objects, one for each matching
+/// package, read from a snapshot of the index. Lines starting with `#` are heartbeat comments, which must be skipped.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/package/search/export",
+    responses(
+        (status = 200, description = "Stream of matching packages", body = SearchPackageHit, content_type = "application/x-ndjson"),
+        (status = BAD_REQUEST, description = "Bad query"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("q" = String, Query, description = "Search query"),
+    )
+)]
+#[get("/package/search/export")]
+async fn export_search_package(
+    state: web::Data<SharedState>,
+    params: web::Query<SearchExportParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    log::info!("Exporting packages matching: '{}'", params.q);
+
+    let state = SharedState::clone(&state);
+    let q = params.into_inner().q;
+    let cursor = {
+        let state = state.clone();
+        web::block(move || state.package_index.cursor(&q, None))
+            .await?
+            .map_err(Error::Index)?
+    };
+
+    let hits = futures::stream::try_unfold((state, cursor), |(state, mut cursor)| async move {
+        if cursor.is_done() {
+            return Ok(None);
+        }
+        let (hits, state, cursor) = web::block(move || {
+            let hits = state
+                .package_index
+                .next_page(&mut cursor, EXPORT_PAGE_SIZE, &SearchOptions::default());
+            (hits, state, cursor)
+        })
+        .await?;
+        let hits = hits.map_err(Error::Index)?;
+        Ok::<_, actix_web::Error>(Some((futures::stream::iter(hits.into_iter().map(Ok)), (state, cursor))))
+    })
+    .try_flatten();
+
+    Ok(ndjson_response(hits))
+}
+
+/// List the qualifiers supported by the package search query language.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/package/search/schema",
+    responses(
+        (status = 200, description = "Qualifiers of the query language", body = Vec<SearchField>),
+        (status = 401, description = "Not authenticated"),
+    ),
+)]
+#[get("/package/search/schema")]
+async fn search_package_schema(
+    state: web::Data<SharedState>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    Ok(HttpResponse::Ok().json(state.package_index.search_fields()))
+}
+
+/// Parameters to component requests, identifying the component by a package URL or its id.
+#[derive(Debug, Deserialize)]
+struct ComponentParams {
+    /// Package URL of any version of the component
+    purl: Option<String>,
+    /// Id of the component
+    id: Option<String>,
+    /// Number of SBOMs to skip
+    #[serde(default)]
+    offset: usize,
+    /// Maximum number of SBOMs to return
+    #[serde(default = "default_component_limit")]
+    limit: usize,
+}
+
+const fn default_component_limit() -> usize {
+    100
+}
+
+/// Report where a component appears across all SBOMs.
+///
+/// A component is a package, whatever its version, qualifiers and subpath. The report lists the versions of the
+/// component, the number of SBOMs containing each of them and by month of their creation, and the SBOMs containing
+/// the component.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/component",
+    responses(
+        (status = 200, description = "Usage of the component", body = ComponentUsage),
+        (status = BAD_REQUEST, description = "Missing or invalid package URL or component id"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("purl" = Option<String>, Query, description = "Package URL of any version of the component"),
+        ("id" = Option<String>, Query, description = "Id of the component, if no package URL is provided"),
+        ("offset" = Option<usize>, Query, description = "Number of SBOMs to skip"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of SBOMs to return, defaults to 100"),
+    )
+)]
+#[get("/component")]
+async fn component_usage(
+    state: web::Data<SharedState>,
+    params: web::Query<ComponentParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let params = params.into_inner();
+    let (id, purl) = match (params.purl, params.id) {
+        (Some(purl), _) => {
+            let (component, _) = Component::from_purl(&purl).ok_or(Error::InvalidComponent)?;
+            (component.id, Some(component.purl))
+        }
+        (None, Some(id)) if is_component_id(&id) => (id, None),
+        _ => return Err(Error::InvalidComponent.into()),
+    };
+
+    let mut usage = actix_web::web::block(move || {
+        bombastic_index::sbom::component_usage(&state.sbom_index, &id, params.offset, params.limit)
+    })
+    .await?
+    .map_err(Error::Index)?;
+    if usage.purl.is_none() {
+        usage.purl = purl;
+    }
+
+    Ok(HttpResponse::Ok().json(usage))
+}
+
+/// Upload an SBOM with an identifier.
+///
+/// Clients may split the transfer using multipart uploads. Supported content types are JSON, protobuf encoded CycloneDX (`application/x.cyclonedx+protobuf`) and XML encoded CycloneDX 1.3 to 1.5 (`application/vnd.cyclonedx+xml`), content encoding can be unset, bzip2, gzip or zstd.
+///
+/// SBOMs compressed with one of these but sent without content encoding, like `.json.bz2` files, are detected and decompressed as well. SBOMs exceeding the maximum decompressed size of the server are refused.
+///
+/// Protobuf and XML encoded SBOMs are converted to JSON before being stored. As the conversion only keeps what is needed for indexing, the original document is kept as well.
+///
+/// If enabled on the server, the identifier can be omitted. The SBOM will then be stored using the SHA-256 digest of its (decoded) content, as `sha256:<digest>`.
+/// The assigned identifier is returned in the `Location` header.
+///
+/// A deleted SBOM can only be stored again through the API: walkers and federation would otherwise bring it back.
+///
+/// A detached signature or a sigstore bundle of the (decoded) SBOM can be sent alongside it, base64 encoded in the `X-Signature` or `X-Sigstore-Bundle` header. The signature is verified against the trust roots of the server, and the outcome stored with the provenance of the SBOM, which search results carry as labels like `signature:verified`. Signed SBOMs are limited by the publish limit of the server, as they are verified before being stored. If required by the server, SBOMs without a verified signature are refused.
+#[utoipa::path(
+    put,
+    tag = "bombastic",
+    path = "/api/v1/sbom",
+    request_body(content = Value, description = "The SBOM to be uploaded", content_type = ["application/json", "application/x.cyclonedx+protobuf", "application/vnd.cyclonedx+xml"]),
+    responses(
+        (status = 201, description = "SBOM uploaded successfully", headers(("location" = String, description = "Location of the uploaded SBOM"))),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = BAD_REQUEST, description = "Missing valid id, invalid content, or invalid or unverified signature"),
+        (status = PAYLOAD_TOO_LARGE, description = "SBOM is too large to derive an id from or verify its signature, or once decompressed"),
+        (status = CONFLICT, description = "SBOM was deleted, and isn't uploaded through the API"),
+    ),
+    params(
+        ("id" = Option<String>, Query, description = "Identifier assigned to the SBOM, derived from the content if omitted"),
+        ("source" = Option<String>, Query, description = "How the SBOM was ingested: api (default), walker or federation, the latter two requiring the ingest.document permission"),
+        ("source_url" = Option<String>, Query, description = "Where the SBOM was retrieved from"),
+        ("x-signature" = Option<String>, Header, description = "Base64 encoded detached signature of the SBOM"),
+        ("x-sigstore-bundle" = Option<String>, Header, description = "Base64 encoded sigstore bundle of the SBOM"),
+    )
+)]
+async fn publish_sbom(
+    req: HttpRequest,
+    version: web::Data<ApiVersion>,
+    state: web::Data<SharedState>,
+    params: web::Query<PublishParams>,
+    payload: web::Payload,
+    content_type: Option<web::Header<ContentType>>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::CreateSbom)?;
+
+    let typ = verify_type(content_type)?;
+    let enc = verify_encoding(req.headers().get(CONTENT_ENCODING))?;
+    let payload = payload.map_err(|e| match e {
+        PayloadError::Io(e) => StorageError::Io(e),
+        _ => StorageError::Io(io::Error::new(io::ErrorKind::Other, e)),
+    });
+    let (enc, payload) = detect_encoding(enc, payload).await?;
+    let params = params.into_inner();
+    if params.source != Source::Api {
+        // documents of walkers and other instances are treated differently, only trusted clients may claim them
+        authorizer.require(&user, Permission::IngestDocument)?;
+    }
+    let mut provenance = Provenance::new(params.source, user.id(), params.source_url.as_deref());
+    let signed = Signed::from_headers(req.headers()).map_err(Error::InvalidSignature)?;
+    let payload = if signed.is_some() || state.trust_roots.required() {
+        // the complete document is needed for verifying its signature
+        let data = collect(payload, state.publish_limit).await?;
+        let decoded = Storage::decode_bytes(enc, data.clone(), state.max_decompressed_size)
+            .await
+            .map_err(Error::Storage)?;
+        let signature = state.trust_roots.verify(signed.as_ref(), &decoded);
+        if state.trust_roots.required() && !signature.verified {
+            return Err(Error::UnverifiedSignature(signature.error.unwrap_or_default()).into());
+        }
+        provenance.signature = Some(signature);
+        once(ok(data)).left_stream()
+    } else {
+        payload.right_stream()
+    };
+    if let Some(converter) = converter(typ.0.essence_str()) {
+        let (id, size) = publish_converted(&state, params.id, enc, &provenance, payload, converter).await?;
+        index_realtime(&state, &id, provenance.source.as_str()).await;
+        return Ok(created(&version, id, size));
+    }
+    let (id, size) = match (params.id, state.content_ids) {
+        (Some(id), _) => {
+            let deleted = check_tombstone(&state, &id, &provenance).await?;
+            let size = state
+                .storage
+                .replace_stream(
+                    (&id).into(),
+                    typ.as_ref(),
+                    enc,
+                    &provenance,
+                    payload,
+                    state.max_revisions,
+                )
+                .await
+                .map_err(Error::Storage)?;
+            clear_tombstone(&state, &id, deleted).await?;
+            (id, size)
+        }
+        (None, Some(limit)) => {
+            // we need the full content before we can store it
+            let data = collect(payload, limit).await?;
+            let decoded = Storage::decode_bytes(enc, data.clone(), state.max_decompressed_size)
+                .await
+                .map_err(Error::Storage)?;
+            let id = content_id(&decoded);
+            let deleted = check_tombstone(&state, &id, &provenance).await?;
+            let size = state
+                .storage
+                .replace_stream(
+                    (&id).into(),
+                    typ.as_ref(),
+                    enc,
+                    &provenance,
+                    once(ok(data)),
+                    state.max_revisions,
+                )
+                .await
+                .map_err(Error::Storage)?;
+            clear_tombstone(&state, &id, deleted).await?;
+            (id, size)
+        }
+        (None, None) => return Err(Error::MissingId.into()),
+    };
+    index_realtime(&state, &id, provenance.source.as_str()).await;
+    Ok(created(&version, id, size))
+}
+
+/// Parameters to batch publish requests.
+#[derive(Debug, Deserialize)]
+struct BatchParams {
+    /// How the SBOMs were ingested, defaults to `api`
+    #[serde(default)]
+    source: Source,
+    /// Where the SBOMs were retrieved from
+    source_url: Option<String>,
+}
+
+/// Outcome of publishing an SBOM of a batch.
+#[derive(Debug, Serialize)]
+struct BatchStatus {
+    /// Where the SBOM is in the batch: its path in an archive, or its line
+    name: String,
+    /// Identifier of the SBOM, unless it couldn't be derived
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    /// The status of publishing the SBOM on its own
+    status: u16,
+    /// Size of the stored SBOM
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<usize>,
+    /// Why the SBOM wasn't stored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Outcome of publishing a batch of SBOMs.
+#[derive(Debug, Serialize)]
+struct BatchResult {
+    /// Number of stored SBOMs
+    created: usize,
+    /// Number of SBOMs which failed to be stored
+    failed: usize,
+    documents: Vec<BatchStatus>,
+}
+
+/// Upload a batch of SBOMs in a single request.
+///
+/// The batch is a tar or zip archive of SBOMs, or newline delimited JSON with an SBOM per line, optionally encoded with bzip2, gzip or zstd. SBOMs of an archive are identified by their file name without extension, and `.xml` files are converted from XML encoded CycloneDX. SBOMs of newline delimited JSON are identified by the SHA-256 digest of their content, which requires content derived identifiers to be enabled on the server.
+///
+/// The SBOMs are stored in the order of the batch, each one as if it was uploaded on its own. The response lists the outcome for each SBOM, with the status it would have gotten on its own, so a failing SBOM doesn't fail the whole batch.
+#[utoipa::path(
+    post,
+    tag = "bombastic",
+    path = "/api/v1/sbom/batch",
+    request_body(content = Vec<u8>, description = "The batch of SBOMs", content_type = ["application/x-tar", "application/zip", "application/x-ndjson"]),
+    responses(
+        (status = 200, description = "Batch processed, returns the outcome for each SBOM"),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = BAD_REQUEST, description = "Invalid batch type, encoding or archive"),
+        (status = PAYLOAD_TOO_LARGE, description = "Batch or one of its SBOMs is too large"),
+    ),
+    params(
+        ("source" = Option<String>, Query, description = "How the SBOMs were ingested: api (default), walker or federation, the latter two requiring the ingest.document permission"),
+        ("source_url" = Option<String>, Query, description = "Where the SBOMs were retrieved from"),
+    )
+)]
+#[post("/sbom/batch")]
+async fn publish_batch(
+    req: HttpRequest,
+    state: web::Data<SharedState>,
+    params: web::Query<BatchParams>,
+    payload: web::Payload,
+    content_type: Option<web::Header<ContentType>>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::CreateSbom)?;
+    if state.trust_roots.required() {
+        return Err(Error::UnverifiedSignature("batches can't carry signatures".to_string()).into());
+    }
+
+    let format = content_type
+        .and_then(|typ| Format::from_content_type(typ.0.essence_str()))
+        .ok_or(Error::InvalidBatchType)?;
+    let enc = verify_encoding(req.headers().get(CONTENT_ENCODING))?;
+    let payload = payload.map_err(|e| StorageError::Io(io::Error::new(io::ErrorKind::Other, e)));
+    let (enc, payload) = detect_encoding(enc, payload).await?;
+    let data = collect(payload, state.batch_limit).await?;
+    let data = Storage::decode_bytes(enc, data, state.max_decompressed_size)
+        .await
+        .map_err(Error::Storage)?;
+    let entries = format
+        .entries(data.into(), state.publish_limit)
+        .map_err(|e| Error::InvalidBatch(e.to_string()))?;
+
+    let params = params.into_inner();
+    if params.source != Source::Api {
+        // documents of walkers and other instances are treated differently, only trusted clients may claim them
+        authorizer.require(&user, Permission::IngestDocument)?;
+    }
+    let provenance = Provenance::new(params.source, user.id(), params.source_url.as_deref());
+    let mut result = BatchResult {
+        created: 0,
+        failed: 0,
+        documents: Vec::with_capacity(entries.len()),
+    };
+    // one at a time, so that SBOMs with the same id are stored in the order of the batch
+    for entry in entries {
+        let (name, id) = (entry.name.clone(), entry.id.clone());
+        let status = match publish_entry(&state, entry, &provenance).await {
+            Ok((id, size)) => {
+                index_realtime(&state, &id, provenance.source.as_str()).await;
+                result.created += 1;
+                BatchStatus {
+                    name,
+                    id: Some(id),
+                    status: StatusCode::CREATED.as_u16(),
+                    size: Some(size),
+                    error: None,
+                }
+            }
+            Err(e) => {
+                result.failed += 1;
+                let status = error::ResponseError::status_code(&e);
+                BatchStatus {
+                    name,
+                    id,
+                    status: status.as_u16(),
+                    size: None,
+                    error: Some(match status {
+                        StatusCode::INTERNAL_SERVER_ERROR => "Internal server error".to_string(),
+                        _ => e.to_string(),
+                    }),
+                }
+            }
+        };
+        result.documents.push(status);
+    }
+    log::info!(
+        "Published batch of SBOMs: created={}, failed={}",
+        result.created,
+        result.failed
+    );
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+/// Store an SBOM of a batch.
+async fn publish_entry(
+    state: &AppState,
+    entry: batch::Entry,
+    provenance: &Provenance,
+) -> Result<(String, usize), Error> {
+    if let Some(converter) = converter(entry.content_type) {
+        let payload = once(ok(entry.data));
+        return publish_converted(state, entry.id, None, provenance, payload, converter).await;
+    }
+    serde_json::from_slice::<serde::de::IgnoredAny>(&entry.data).map_err(|e| Error::InvalidJson(e.to_string()))?;
+    let id = match (entry.id, state.content_ids) {
+        (Some(id), _) => id,
+        (None, Some(_)) => content_id(&entry.data),
+        (None, None) => return Err(Error::MissingId),
+    };
+    let deleted = check_tombstone(state, &id, provenance).await?;
+    keep_revision(state, &id).await?;
+    let size = state
+        .storage
+        .put_json_slice((&id).into(), provenance, &entry.data)
+        .await
+        .map_err(Error::Storage)?;
+    delete_original(state, &id).await?;
+    clear_tombstone(state, &id, deleted).await?;
+    Ok((id, size))
+}
+
+/// Index a stored SBOM right away, if real-time updates are enabled, so that it can be searched before a snapshot of
+/// the indexer includes it.
+///
+/// Failures are only logged, as the indexer indexes the SBOM anyway.
+async fn index_realtime(state: &SharedState, id: &str, source: &'static str) {
+    if !state.sbom_index.realtime_enabled() {
+        return;
+    }
+
+    let data = match read_sbom(&state.storage, id).await {
+        Ok(data) => data,
+        Err(err) => {
+            log::warn!("Failed to read SBOM {id} for real-time indexing: {err}");
+            return;
+        }
+    };
+    let (state, key) = (state.clone(), id.to_string());
+    match web::block(move || state.sbom_index.index_realtime(&key, &data, Some(source))).await {
+        Ok(Ok(())) => log::debug!("Indexed SBOM {id} in real-time"),
+        Ok(Err(err)) => log::warn!("Failed to index SBOM {id} in real-time: {err}"),
+        Err(err) => log::warn!("Failed to index SBOM {id} in real-time: {err}"),
+    }
+}
+
+/// Hide a deleted SBOM from searches right away, if real-time updates are enabled.
+async fn delete_realtime(state: &SharedState, id: &str) {
+    if !state.sbom_index.realtime_enabled() {
+        return;
+    }
+
+    let (state, key) = (state.clone(), id.to_string());
+    match web::block(move || state.sbom_index.delete_realtime(&key)).await {
+        Ok(Ok(())) => log::debug!("Deleted SBOM {id} in real-time"),
+        Ok(Err(err)) => log::warn!("Failed to delete SBOM {id} in real-time: {err}"),
+        Err(err) => log::warn!("Failed to delete SBOM {id} in real-time: {err}"),
+    }
+}
+
+fn created(version: &ApiVersion, id: String, size: usize) -> HttpResponse {
+    let msg = format!("Successfully uploaded SBOM: id={id}, size={size}");
+    log::info!("{}", msg);
+    HttpResponse::Created()
+        .insert_header((
+            header::LOCATION,
+            format!("{}/sbom?id={}", version.prefix(), urlencoding::encode(&id)),
+        ))
+        .body(msg)
+}
+
+/// Converts a document of another encoding to JSON.
+type Converter = (&'static str, fn(&[u8]) -> Result<Vec<u8>, Error>);
+
+/// The converter of documents of a content type, if they are not stored as they are.
+fn converter(content_type: &str) -> Option<Converter> {
+    match content_type {
+        CYCLONEDX_PROTOBUF => Some((CYCLONEDX_PROTOBUF, protobuf_to_json)),
+        CYCLONEDX_XML => Some((CYCLONEDX_XML, xml_to_json)),
+        _ => None,
+    }
+}
+
+fn protobuf_to_json(data: &[u8]) -> Result<Vec<u8>, Error> {
+    protobuf::cyclonedx_to_json(data).map_err(|e| Error::InvalidProtobuf(e.to_string()))
+}
+
+fn xml_to_json(data: &[u8]) -> Result<Vec<u8>, Error> {
+    xml::cyclonedx_to_json(data).map_err(|e| Error::InvalidXml(e.to_string()))
+}
+
+/// Store an SBOM converted to JSON, keeping the original.
+async fn publish_converted(
+    state: &AppState,
+    id: Option<String>,
+    enc: Option<&str>,
+    provenance: &Provenance,
+    payload: impl futures::Stream<Item = Result<Bytes, StorageError>>,
+    (content_type, convert): Converter,
+) -> Result<(String, usize), Error> {
+    // the document needs to be complete for decoding
+    let data = collect(payload, state.publish_limit).await?;
+    let original = Storage::decode_bytes(enc, data, state.max_decompressed_size)
+        .await
+        .map_err(Error::Storage)?;
+    let json = convert(&original)?;
+    // nothing is stored unless the converted document is valid
+    state.storage.validate_json(&json).await.map_err(Error::Storage)?;
+    let id = match (id, state.content_ids) {
+        (Some(id), _) => id,
+        (None, Some(_)) => content_id(&original),
+        (None, None) => return Err(Error::MissingId),
+    };
+    let deleted = check_tombstone(state, &id, provenance).await?;
+    keep_revision(state, &id).await?;
+    // the conversion is lossy, the original is what the client published
+    state
+        .storage
+        .put_original((&id).into(), content_type, &original)
+        .await
+        .map_err(Error::Storage)?;
+    let size = state
+        .storage
+        .put_json_slice((&id).into(), provenance, &json)
+        .await
+        .map_err(Error::Storage)?;
+    clear_tombstone(state, &id, deleted).await?;
+    Ok((id, size))
+}
+
+/// Refuse storing a deleted SBOM again, unless it's uploaded through the API.
+///
+/// Returns whether the SBOM was deleted, so that its tombstone is removed once it's stored.
+async fn check_tombstone(state: &AppState, id: &str, provenance: &Provenance) -> Result<bool, Error> {
+    match state.storage.get_tombstone(id.into()).await.map_err(Error::Storage)? {
+        Some(tombstone) if provenance.source != Source::Api => Err(Error::Tombstoned(tombstone)),
+        Some(_) => Ok(true),
+        None => Ok(false),
+    }
+}
+
+/// Keep the stored SBOM as a prior revision, before it's replaced.
+async fn keep_revision(state: &AppState, id: &str) -> Result<(), Error> {
+    if let Some(revision) = state
+        .storage
+        .put_revision(id.into(), state.max_revisions)
+        .await
+        .map_err(Error::Storage)?
+    {
+        log::debug!("Kept revision {revision} of SBOM {id}");
+    }
+    Ok(())
+}
+
+/// Remove the original of an SBOM stored again as JSON, so that the outdated document isn't served in its place.
+async fn delete_original(state: &AppState, id: &str) -> Result<(), Error> {
+    state.storage.delete_original(id.into()).await.map_err(Error::Storage)
+}
+
+/// Remove the tombstone of an SBOM stored again.
+async fn clear_tombstone(state: &AppState, id: &str, deleted: bool) -> Result<(), Error> {
+    if deleted {
+        state
+            .storage
+            .delete_tombstone(id.into())
+            .await
+            .map_err(Error::Storage)?;
+        log::info!("SBOM {id} was stored again after being deleted");
+    }
+    Ok(())
+}
+
+/// Collect the payload, failing if it exceeds the limit.
+async fn collect(
+    payload: impl futures::Stream<Item = Result<Bytes, StorageError>>,
+    limit: usize,
+) -> Result<Bytes, Error> {
+    let mut data = web::BytesMut::new();
+    futures::pin_mut!(payload);
+    while let Some(chunk) = payload.next().await {
+        data.extend_from_slice(&chunk.map_err(Error::Storage)?);
+        if data.len() > limit {
+            return Err(Error::PayloadTooLarge(limit));
+        }
+    }
+    Ok(data.freeze())
+}
+
+/// The identifier of a document, derived from its content.
+fn content_id(data: &[u8]) -> String {
+    format!("sha256:{:x}", Sha256::digest(data))
+}
+
+/// Maximum number of parts of an upload, as supported by S3.
+const MAX_UPLOAD_PARTS: u32 = 10_000;
+
+/// Response to the start of an upload.
+#[derive(Debug, Serialize)]
+struct UploadStarted {
+    /// Token identifying the upload in the requests uploading its parts and completing it
+    token: String,
+    /// Maximum size of a part, in bytes
+    chunk_limit: usize,
+}
+
+/// The parts to complete an upload with.
+#[derive(Debug, Deserialize)]
+struct UploadCompletion {
+    parts: Vec<UploadPart>,
+}
+
+/// Start uploading an SBOM in parts, for SBOMs too large to be uploaded in a single request.
+///
+/// Parts are stored as they are sent, so only JSON SBOMs, optionally encoded with bzip2, gzip or zstd, can be uploaded in parts, and they are validated when being indexed instead of when being uploaded. The returned token identifies the upload until it's completed or aborted, and allows resuming it from any API instance.
+#[utoipa::path(
+    post,
+    tag = "bombastic",
+    path = "/api/v1/sbom/upload",
+    responses(
+        (status = 201, description = "Upload started, returns its token and the maximum size of a part"),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = BAD_REQUEST, description = "Missing id, or invalid content type or encoding"),
+        (status = CONFLICT, description = "SBOM was deleted, and isn't uploaded through the API"),
+    ),
+    params(
+        ("id" = String, Query, description = "Identifier assigned to the SBOM"),
+        ("source" = Option<String>, Query, description = "How the SBOM was ingested: api (default), walker or federation, the latter two requiring the ingest.document permission"),
+        ("source_url" = Option<String>, Query, description = "Where the SBOM was retrieved from"),
+    )
+)]
+#[post("/sbom/upload")]
+async fn start_upload(
+    req: HttpRequest,
+    state: web::Data<SharedState>,
+    params: web::Query<PublishParams>,
+    content_type: Option<web::Header<ContentType>>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::CreateSbom)?;
+    if state.trust_roots.required() {
+        return Err(Error::UnverifiedSignature("SBOMs uploaded in parts can't carry signatures".to_string()).into());
+    }
+
+    let typ = verify_type(content_type)?;
+    if converter(typ.0.essence_str()).is_some() {
+        return Err(Error::UnsupportedUploadType.into());
+    }
+    let enc = verify_encoding(req.headers().get(CONTENT_ENCODING))?;
+    let params = params.into_inner();
+    let id = params.id.ok_or(Error::MissingId)?;
+    if params.source != Source::Api {
+        // documents of walkers and other instances are treated differently, only trusted clients may claim them
+        authorizer.require(&user, Permission::IngestDocument)?;
+    }
+    let provenance = Provenance::new(params.source, user.id(), params.source_url.as_deref());
+    check_tombstone(&state, &id, &provenance).await?;
+
+    let upload = state
+        .storage
+        .start_upload(&id, typ.as_ref(), enc, &provenance)
+        .await
+        .map_err(Error::Storage)?;
+    let token = format!("{:x}", Sha256::digest(upload.upload_id.as_bytes()));
+    state
+        .storage
+        .put_upload(&token, &upload)
+        .await
+        .map_err(Error::Storage)?;
+    log::info!("Started upload of SBOM {id}");
+
+    Ok(HttpResponse::Created().json(UploadStarted {
+        token,
+        chunk_limit: state.upload_chunk_limit,
+    }))
+}
+
+/// Upload a part of an SBOM.
+///
+/// Parts are numbered from 1, and joined in the order of their numbers. All parts but the last one must be at least 5 MiB. Uploading a part again replaces it, so failed parts can be retried.
+#[utoipa::path(
+    put,
+    tag = "bombastic",
+    path = "/api/v1/sbom/upload/{token}/{part}",
+    request_body(content = Vec<u8>, description = "The part of the SBOM", content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "Part stored, returns its number and entity tag, required to complete the upload"),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = 404, description = "Unknown upload"),
+        (status = BAD_REQUEST, description = "Invalid part number"),
+        (status = PAYLOAD_TOO_LARGE, description = "Part exceeds the maximum size of a part"),
+    ),
+    params(
+        ("token" = String, Path, description = "Token of the upload"),
+        ("part" = u32, Path, description = "Number of the part, from 1 to 10000"),
+    )
+)]
+async fn upload_part(
+    state: web::Data<SharedState>,
+    path: web::Path<(String, u32)>,
+    data: Bytes,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::CreateSbom)?;
+
+    let (token, part) = path.into_inner();
+    if !(1..=MAX_UPLOAD_PARTS).contains(&part) {
+        return Err(Error::InvalidPart(part).into());
+    }
+    let upload = get_upload(&state, &token).await?;
+    let part = state
+        .storage
+        .put_upload_part(&upload, part, data.to_vec())
+        .await
+        .map_err(Error::Storage)?;
+
+    Ok(HttpResponse::Ok().json(part))
+}
+
+/// Complete the upload of an SBOM, storing it.
+///
+/// The request lists the uploaded parts, with the entity tags returned when uploading them, as `{"parts": [{"part": 1, "etag": "..."}]}`.
+#[utoipa::path(
+    post,
+    tag = "bombastic",
+    path = "/api/v1/sbom/upload/{token}",
+    responses(
+        (status = 201, description = "SBOM uploaded successfully", headers(("location" = String, description = "Location of the uploaded SBOM"))),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = 404, description = "Unknown upload"),
+        (status = BAD_REQUEST, description = "Missing or invalid parts"),
+    ),
+    params(
+        ("token" = String, Path, description = "Token of the upload"),
+    )
+)]
+#[post("/sbom/upload/{token}")]
+async fn complete_upload(
+    version: web::Data<ApiVersion>,
+    state: web::Data<SharedState>,
+    token: web::Path<String>,
+    completion: web::Json<UploadCompletion>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::CreateSbom)?;
+
+    let upload = get_upload(&state, &token).await?;
+    let parts = completion.into_inner().parts;
+    if let Some(part) = parts.iter().find(|p| !(1..=MAX_UPLOAD_PARTS).contains(&p.part)) {
+        return Err(Error::InvalidPart(part.part).into());
+    }
+    keep_revision(&state, &upload.id).await?;
+    state
+        .storage
+        .complete_upload(&upload, parts)
+        .await
+        .map_err(|e| match e {
+            // S3 rejects missing or too small parts
+            StorageError::S3(_) => Error::Storage(StorageError::InvalidContent),
+            e => Error::Storage(e),
+        })?;
+    state.storage.delete_upload(&token).await.map_err(Error::Storage)?;
+    delete_original(&state, &upload.id).await?;
+    // the upload could only start if the SBOM wasn't deleted, or was uploaded through the API
+    clear_tombstone(&state, &upload.id, true).await?;
+    index_realtime(&state, &upload.id, Source::Api.as_str()).await;
+
+    let msg = format!("Successfully uploaded SBOM in parts: id={}", upload.id);
+    log::info!("{}", msg);
+    Ok(HttpResponse::Created()
+        .insert_header((
+            header::LOCATION,
+            format!("{}/sbom?id={}", version.prefix(), urlencoding::encode(&upload.id)),
+        ))
+        .body(msg))
+}
+
+/// Abort the upload of an SBOM, dropping its uploaded parts.
+#[utoipa::path(
+    delete,
+    tag = "bombastic",
+    path = "/api/v1/sbom/upload/{token}",
+    responses(
+        (status = 204, description = "Upload aborted"),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = 404, description = "Unknown upload"),
+    ),
+    params(
+        ("token" = String, Path, description = "Token of the upload"),
+    )
+)]
+#[delete("/sbom/upload/{token}")]
+async fn abort_upload(
+    state: web::Data<SharedState>,
+    token: web::Path<String>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::CreateSbom)?;
+
+    let upload = get_upload(&state, &token).await?;
+    state.storage.abort_upload(&upload).await.map_err(Error::Storage)?;
+    state.storage.delete_upload(&token).await.map_err(Error::Storage)?;
+    log::info!("Aborted upload of SBOM {}", upload.id);
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Get an upload in progress, by its token.
+async fn get_upload(state: &AppState, token: &str) -> Result<Upload, Error> {
+    // tokens are hex encoded digests, anything else can't be a stored upload
+    if token.is_empty() || !token.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(Error::UnknownUpload);
+    }
+    state
+        .storage
+        .get_upload(token)
+        .await
+        .map_err(Error::Storage)?
+        .ok_or(Error::UnknownUpload)
+}
+
+/// Validate an SBOM without storing it.
+///
+/// The document is parsed and checked for schema validity, the NTIA minimum elements, the syntax of package URLs and duplicate identifiers.
+/// Findings with severity `error` would prevent the SBOM from being processed, findings with severity `warning` should be addressed by the producer.
+#[utoipa::path(
+    post,
+    tag = "bombastic",
+    path = "/api/v1/sbom/validate",
+    request_body(content = Value, description = "The SBOM to be validated", content_type = ["application/json", "application/x.cyclonedx+protobuf", "application/vnd.cyclonedx+xml"]),
+    responses(
+        (status = 200, description = "SBOM was linted, see the report for findings", body = LintReport),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = BAD_REQUEST, description = "Invalid content type"),
+    ),
+)]
+async fn validate_sbom(
+    body: Bytes,
+    content_type: Option<web::Header<ContentType>>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    // validating is meant for producers checking their SBOMs before publishing them
+    authorizer.require(&user, Permission::CreateSbom)?;
+
+    let report = if let Some((_, convert)) = converter(verify_type(content_type)?.0.essence_str()) {
+        bombastic_model::lint::lint(&convert(&body)?)
+    } else {
+        bombastic_model::lint::lint(&body)
+    };
+    Ok(HttpResponse::Ok().json(report))
+}
+
+fn verify_type(content_type: Option<web::Header<ContentType>>) -> Result<ContentType, Error> {
+    if let Some(hdr) = content_type {
+        let ct = hdr.into_inner();
+        if ct == ContentType::json() || converter(ct.0.essence_str()).is_some() {
+            return Ok(ct);
+        }
+    }
+    Err(Error::InvalidContentType)
+}
+
+// bzip2 prevents us from using the ContentEncoding enum
+fn verify_encoding(content_encoding: Option<&HeaderValue>) -> Result<Option<&str>, Error> {
+    match content_encoding {
+        Some(enc) => match enc.to_str() {
+            Ok(v) if ACCEPT_ENCODINGS.contains(&v) => Ok(Some(v)),
+            _ => Err(Error::InvalidContentEncoding),
+        },
+        None => Ok(None),
+    }
+}
+
+/// The encoding of a payload: the content encoding if set, otherwise the compression detected from its first bytes,
+/// like for `.json.bz2` files sent as they are.
+async fn detect_encoding<'a, S>(
+    enc: Option<&'a str>,
+    mut payload: S,
+) -> Result<
+    (
+        Option<&'a str>,
+        impl futures::Stream<Item = Result<Bytes, StorageError>>,
+    ),
+    Error,
+>
+where
+    S: futures::Stream<Item = Result<Bytes, StorageError>> + Unpin,
+{
+    let first = match enc {
+        Some(_) => None,
+        None => payload.next().await.transpose().map_err(Error::Storage)?,
+    };
+    let enc = enc.or_else(|| {
+        first
+            .as_deref()
+            .and_then(Compression::detect)
+            .map(|compression| compression.content_encoding())
+    });
+    Ok((enc, futures::stream::iter(first.map(Ok)).chain(payload)))
+}
+
+/// Delete an SBOM using its identifier.
+///
+/// The SBOM is removed from the search indexes once the indexer processes the deletion, which it reports on its deleted topic. A tombstone is kept, so that fetching the SBOM reports it as gone, and walkers and federation don't store it again.
+#[utoipa::path(
+    delete,
+    tag = "bombastic",
+    path = "/api/v1/sbom",
+    responses(
+        (status = 204, description = "SBOM either deleted or nonexistent"),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = BAD_REQUEST, description = "Missing id"),
+    ),
+    params(
+        ("id" = String, Query, description = "Package URL or product identifier of SBOM to query"),
+    )
+)]
+#[delete("/sbom")]
+async fn delete_sbom(
+    state: web::Data<SharedState>,
+    params: web::Query<IdentifierParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::DeleteSbom)?;
+
+    let params = params.into_inner();
+    let id = &params.id;
+    log::trace!("Deleting SBOM using id {id}");
+    state.storage.delete(id.into()).await.map_err(Error::Storage)?;
+    state
+        .storage
+        .delete_revisions(id.into())
+        .await
+        .map_err(Error::Storage)?;
+    state
+        .storage
+        .put_tombstone(&Tombstone::new(id, user.id()))
+        .await
+        .map_err(Error::Storage)?;
+    delete_realtime(&state, id).await;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Delete all SBOMs
+#[delete("/sbom/all")]
+async fn delete_sboms(
+    state: web::Data<SharedState>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::DeleteSbom)?;
+
+    state.storage.delete_all().await.map_err(Error::Storage)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Search for a status of sbom using a free form search query.
+///
+/// See the [documentation](https://docs.trustification.dev/trustification/user/retrieve.html) for a description of the query language.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/status",
+    responses(
+    (status = 200, description = "Search completed"),
+    (status = BAD_REQUEST, description = "Bad query"),
+    (status = 401, description = "Not authenticated"),
+    ),
+    params(
+    ("q" = String, Query, description = "Search query"),
+    )
+)]
+#[get("/sbom/status")]
+async fn sbom_status(
+    state: web::Data<SharedState>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let state_clone = Arc::clone(&state);
+
+    let (result, _total) = actix_web::web::block(move || {
+        state_clone.sbom_index.search(
+            "-sort:indexedTimestamp",
+            0,
+            1,
+            SearchOptions {
+                metadata: false,
+                explain: false,
+                summaries: true,
+            },
+        )
+    })
+    .await?
+    .map_err(Error::Index)?;
+
+    let state_clone = Arc::clone(&state);
+    let total_docs = actix_web::web::block(move || state_clone.sbom_index.get_total_docs())
+        .await?
+        .map_err(Error::Index)?;
 
     if let Some(sbom) = result.first() {
         Ok(HttpResponse::Ok().json(StatusResult {
@@ -471,3 +2108,254 @@ async fn sbom_status(
         Ok(HttpResponse::Ok().json(StatusResult::default()))
     }
 }
+
+/// Report how current the SBOM index is, suitable for monitoring.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/freshness",
+    responses(
+        (status = 200, description = "Freshness of the index", body = IndexFreshness),
+        (status = 401, description = "Not authenticated"),
+    ),
+)]
+#[get("/sbom/freshness")]
+async fn sbom_freshness(
+    state: web::Data<SharedState>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    Ok(HttpResponse::Ok().json(IndexFreshness::new(
+        state.sbom_index.updated_at(),
+        state.sbom_index.synced_at(),
+    )))
+}
+
+/// Maximum number of changes returned by a page of the changes feed.
+const MAX_CHANGES: usize = 1000;
+
+/// Parameters to changes feed requests.
+#[derive(Debug, Deserialize)]
+struct ChangesParams {
+    /// Cursor of the last page, the time the last SBOM of the page was indexed
+    #[serde(default)]
+    since: i64,
+    /// Maximum number of changes to return
+    #[serde(default = "default_changes_limit")]
+    limit: usize,
+}
+
+const fn default_changes_limit() -> usize {
+    100
+}
+
+/// List the SBOMs in the order they were indexed, for mirroring them.
+///
+/// Clients start without a cursor, and request the next page using the `next` cursor of the page, until there are no
+/// `more` changes. Storing an SBOM again, or reindexing it, lists it again. Deleted SBOMs aren't listed.
+///
+/// SBOMs are ordered by the time they were indexed, which is taken before committing the index, so an SBOM may show up
+/// after SBOMs indexed later. Clients resuming from a cursor should request some time before it again, skipping the
+/// SBOMs they have by their digest.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/changes",
+    responses(
+        (status = 200, description = "Page of the changes feed", body = Changes),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("since" = Option<i64>, Query, description = "Cursor of the last page, starts from the beginning if omitted"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of changes to return, defaults to 100, at most 1000"),
+    )
+)]
+#[get("/sbom/changes")]
+async fn sbom_changes(
+    state: web::Data<SharedState>,
+    params: web::Query<ChangesParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let ChangesParams { since, limit } = params.into_inner();
+    let q = changes_query(since);
+    let (hits, total) = web::block(move || {
+        state
+            .sbom_index
+            .search(&q, 0, limit.clamp(1, MAX_CHANGES), SearchOptions::default())
+    })
+    .await?
+    .map_err(Error::Index)?;
+
+    let changes = hits.into_iter().map(change).collect();
+    Ok(HttpResponse::Ok().json(Changes::new(since, changes, total)))
+}
+
+/// Parameters to streamed changes feed requests.
+#[derive(Debug, Deserialize)]
+struct ChangesExportParams {
+    /// Cursor of the last change processed
+    #[serde(default)]
+    since: i64,
+}
+
+/// Stream all changes after a cursor, for mirroring the SBOMs.
+///
+/// Unlike the changes feed, changes aren't limited to a page: the response is a chunked stream of newline delimited JSON
+/// objects, one for each change in the order the SBOMs were indexed, read page by page from a snapshot of the index
+/// taken when the request was received. Lines starting with `#` are heartbeat comments, which must be skipped. Clients
+/// continue from the `indexed_timestamp` of the last change they processed.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/changes/export",
+    responses(
+        (status = 200, description = "Stream of changes", body = Change, content_type = "application/x-ndjson"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("since" = Option<i64>, Query, description = "Cursor of the last change processed, starts from the beginning if omitted"),
+    )
+)]
+#[get("/sbom/changes/export")]
+async fn export_sbom_changes(
+    state: web::Data<SharedState>,
+    params: web::Query<ChangesExportParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let state = SharedState::clone(&state);
+    let q = changes_query(params.since);
+    let cursor = {
+        let state = state.clone();
+        web::block(move || state.sbom_index.cursor(&q, None))
+            .await?
+            .map_err(Error::Index)?
+    };
+
+    let changes = futures::stream::try_unfold((state, cursor), |(state, mut cursor)| async move {
+        if cursor.is_done() {
+            return Ok(None);
+        }
+        let (hits, state, cursor) = web::block(move || {
+            let hits = state
+                .sbom_index
+                .next_page(&mut cursor, EXPORT_PAGE_SIZE, &SearchOptions::default());
+            (hits, state, cursor)
+        })
+        .await?;
+        let changes = hits.map_err(Error::Index)?.into_iter().map(change).map(Ok);
+        Ok::<_, actix_web::Error>(Some((futures::stream::iter(changes), (state, cursor))))
+    })
+    .try_flatten();
+
+    Ok(ndjson_response(changes))
+}
+
+/// The query of the SBOMs indexed after a cursor of the changes feed, oldest first.
+fn changes_query(since: i64) -> String {
+    format!("indexedTimestamp:>{since} sort:indexedTimestamp")
+}
+
+fn change(hit: SearchHit) -> Change {
+    Change {
+        id: hit.document.id,
+        sha256: hit.document.file_sha256,
+        indexed_timestamp: hit.document.indexed_timestamp,
+        labels: hit.document.labels,
+    }
+}
+
+/// Number of walker run reports kept in storage.
+const WALKER_RUNS_RETAINED: usize = 100;
+
+/// Parameters to walker run requests.
+#[derive(Debug, Deserialize)]
+struct WalkerRunsParams {
+    /// Maximum number of runs to return
+    #[serde(default = "default_walker_runs_limit")]
+    limit: usize,
+}
+
+const fn default_walker_runs_limit() -> usize {
+    10
+}
+
+/// Record the report of a run of the SBOM walker.
+#[utoipa::path(
+    post,
+    tag = "bombastic",
+    path = "/api/v1/walker/runs",
+    responses(
+        (status = 201, description = "Report recorded"),
+        (status = BAD_REQUEST, description = "Invalid report"),
+        (status = 401, description = "Not authenticated"),
+    ),
+)]
+#[post("/walker/runs")]
+async fn publish_walker_run(
+    state: web::Data<SharedState>,
+    run: web::Json<WalkerRun>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::CreateSbom)?;
+
+    state
+        .storage
+        .put_walker_run(&run, WALKER_RUNS_RETAINED)
+        .await
+        .map_err(Error::Storage)?;
+
+    Ok(HttpResponse::Created().finish())
+}
+
+/// Get the reports of the latest runs of the SBOM walker.
+///
+/// The most recent run is also returned as `latest`, for dashboards to show the current status of the walker.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/walker/runs",
+    responses(
+        (status = 200, description = "Reports of the latest runs, newest first"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("limit" = Option<usize>, Query, description = "Maximum number of runs to return, defaults to 10"),
+    )
+)]
+#[get("/walker/runs")]
+async fn walker_runs(
+    state: web::Data<SharedState>,
+    params: web::Query<WalkerRunsParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let runs = state
+        .storage
+        .get_walker_runs(params.limit.min(WALKER_RUNS_RETAINED))
+        .await
+        .map_err(Error::Storage)?;
+
+    Ok(HttpResponse::Ok().json(WalkerRuns::from(runs)))
+}
+
+/// Get the OpenAPI document of this API version, only containing the operations the caller is permitted to call.
+#[get("/openapi.json")]
+async fn permitted_openapi(
+    version: web::Data<ApiVersion>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> HttpResponse {
+    let openapi = permitted_operations(api_doc(), PERMISSIONS, &authorizer, &user);
+    HttpResponse::Ok().json(version.openapi(openapi))
+}
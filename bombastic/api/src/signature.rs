@@ -0,0 +1,607 @@
+//! Verification of the signatures SBOMs are uploaded with.
+//!
+//! Clients may send a detached signature of an SBOM, or a sigstore bundle, alongside it. Detached signatures are
+//! verified against the configured public keys. Bundles are verified like cosign does: the signing certificate must
+//! chain up to a configured Fulcio root, be valid when the signature was logged, and the log entry must be promised
+//! by Rekor, through the signed entry timestamp of the bundle. The outcome is stored with the provenance of the SBOM.
+
+use actix_web::http::header::HeaderMap;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use openssl::{
+    asn1::Asn1Time,
+    hash::MessageDigest,
+    pkey::{Id, PKey, Public},
+    sign::Verifier,
+    stack::Stack,
+    x509::{store::X509StoreBuilder, verify::X509VerifyFlags, X509StoreContext, X509},
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{cmp::Ordering, path::PathBuf};
+use trustification_storage::Signature;
+
+/// Header carrying the base64 encoded detached signature of the uploaded SBOM.
+pub(crate) const SIGNATURE_HEADER: &str = "x-signature";
+/// Header carrying the base64 encoded sigstore bundle of the uploaded SBOM.
+pub(crate) const BUNDLE_HEADER: &str = "x-sigstore-bundle";
+
+/// Configuration of the trust roots verifying signatures.
+#[derive(Clone, Debug, Default, clap::Args)]
+#[command(
+    rename_all_env = "SCREAMING_SNAKE_CASE",
+    next_help_heading = "Signature verification"
+)]
+pub struct SignatureConfig {
+    /// PEM encoded public keys verifying detached signatures, identified by their file name.
+    #[arg(env = "SIGNATURE_KEYS", long = "signature-keys", value_delimiter = ',')]
+    pub keys: Vec<PathBuf>,
+
+    /// PEM encoded Fulcio root and intermediate certificates, verifying the certificates of sigstore bundles.
+    #[arg(
+        env = "SIGNATURE_FULCIO_ROOTS",
+        long = "signature-fulcio-roots",
+        requires = "rekor_keys"
+    )]
+    pub fulcio_roots: Option<PathBuf>,
+
+    /// PEM encoded Rekor public keys, verifying the transparency log entries of sigstore bundles.
+    #[arg(
+        env = "SIGNATURE_REKOR_KEYS",
+        long = "signature-rekor-keys",
+        value_delimiter = ',',
+        requires = "fulcio_roots"
+    )]
+    pub rekor_keys: Vec<PathBuf>,
+
+    /// Identities allowed to sign bundles, the email or URI of their certificate. Any identity if none are given.
+    #[arg(env = "SIGNATURE_IDENTITIES", long = "signature-identities", value_delimiter = ',')]
+    pub identities: Vec<String>,
+
+    /// Refuse SBOMs uploaded without a signature, or with one which can't be verified.
+    #[arg(env = "SIGNATURE_REQUIRED", long = "signature-required", default_value_t = false)]
+    pub required: bool,
+}
+
+/// The keys and certificates signatures are verified against.
+#[derive(Default)]
+pub struct TrustRoots {
+    keys: Vec<(String, PKey<Public>)>,
+    fulcio: Vec<X509>,
+    rekor: Vec<PKey<Public>>,
+    identities: Vec<String>,
+    required: bool,
+}
+
+impl TrustRoots {
+    /// Load the trust roots of the configuration, there are none if nothing is configured.
+    pub fn load(config: &SignatureConfig) -> anyhow::Result<Self> {
+        let mut keys = Vec::new();
+        for path in &config.keys {
+            let name = path
+                .file_stem()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            keys.push((name, PKey::public_key_from_pem(&std::fs::read(path)?)?));
+        }
+        let fulcio = match &config.fulcio_roots {
+            Some(path) => X509::stack_from_pem(&std::fs::read(path)?)?,
+            None => vec![],
+        };
+        let mut rekor = Vec::new();
+        for path in &config.rekor_keys {
+            rekor.push(PKey::public_key_from_pem(&std::fs::read(path)?)?);
+        }
+
+        log::info!(
+            "Verifying signatures with {} keys and {} Fulcio certificates, signatures are {}",
+            keys.len(),
+            fulcio.len(),
+            if config.required { "required" } else { "optional" }
+        );
+        Ok(Self {
+            keys,
+            fulcio,
+            rekor,
+            identities: config.identities.clone(),
+            required: config.required,
+        })
+    }
+
+    /// Whether documents must be uploaded with a verified signature.
+    pub(crate) fn required(&self) -> bool {
+        self.required
+    }
+
+    /// Verify the signature a document was uploaded with.
+    pub(crate) fn verify(&self, signed: Option<&Signed>, data: &[u8]) -> Signature {
+        let result = match signed {
+            Some(Signed::Detached(signature)) => self.verify_detached(signature, data),
+            Some(Signed::Bundle(bundle)) => self.verify_bundle(bundle, data),
+            None => Err("missing signature".to_string()),
+        };
+        match result {
+            Ok(signer) => Signature {
+                verified: true,
+                signer: Some(signer),
+                error: None,
+            },
+            Err(error) => Signature {
+                verified: false,
+                signer: None,
+                error: Some(error),
+            },
+        }
+    }
+
+    /// Verify a detached signature, returning the name of the key which verified it.
+    fn verify_detached(&self, signature: &[u8], data: &[u8]) -> Result<String, String> {
+        if self.keys.is_empty() {
+            return Err("no keys are configured to verify detached signatures".to_string());
+        }
+        self.keys
+            .iter()
+            .find(|(_, key)| verify_with(key, data, signature))
+            .map(|(name, _)| name.clone())
+            .ok_or_else(|| "signature doesn't match any of the configured keys".to_string())
+    }
+
+    /// Verify a bundle, returning the identity of its certificate.
+    fn verify_bundle(&self, bundle: &Bundle, data: &[u8]) -> Result<String, String> {
+        if self.fulcio.is_empty() {
+            return Err("no Fulcio roots are configured to verify bundles".to_string());
+        }
+        let signature = bundle
+            .message_signature
+            .as_ref()
+            .ok_or_else(|| "only bundles of message signatures are supported".to_string())?;
+        let signature = decode(&signature.signature)?;
+
+        let material = &bundle.verification_material;
+        let mut certificates = match (&material.x509_certificate_chain, &material.certificate) {
+            (Some(chain), _) => chain.certificates.iter().collect::<Vec<_>>(),
+            (None, Some(certificate)) => vec![certificate],
+            (None, None) => return Err("bundle has no signing certificate".to_string()),
+        }
+        .into_iter()
+        .map(|certificate| X509::from_der(&decode(&certificate.raw_bytes)?).map_err(|err| err.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+        if certificates.is_empty() {
+            return Err("bundle has no signing certificate".to_string());
+        }
+        let leaf = certificates.remove(0);
+
+        let key = leaf.public_key().map_err(|err| err.to_string())?;
+        if !verify_with(&key, data, &signature) {
+            return Err("signature doesn't match the signing certificate".to_string());
+        }
+
+        // signing certificates are short-lived, they must have been valid when the signature was logged
+        let integrated_time = self.verify_log_entry(&material.tlog_entries, &signature, data)?;
+        self.verify_certificate(&leaf, certificates, integrated_time)?;
+
+        let identity = identity(&leaf).ok_or_else(|| "signing certificate has no identity".to_string())?;
+        if !self.identities.is_empty() && !self.identities.contains(&identity) {
+            return Err(format!("identity {identity} isn't allowed to sign"));
+        }
+        Ok(identity)
+    }
+
+    /// Verify that the signature was logged, returning when it was.
+    fn verify_log_entry(&self, entries: &[TlogEntry], signature: &[u8], data: &[u8]) -> Result<i64, String> {
+        let digest = hex::encode(Sha256::digest(data));
+        for entry in entries {
+            let Some(promise) = &entry.inclusion_promise else {
+                continue;
+            };
+            let payload = entry.promised_payload()?;
+            let set = decode(&promise.signed_entry_timestamp)?;
+            if !self.rekor.iter().any(|key| verify_with(key, &payload, &set)) {
+                continue;
+            }
+
+            // the entry must be the one of this signature
+            let body: HashedRekord = serde_json::from_slice(&decode(&entry.canonicalized_body)?)
+                .map_err(|err| format!("unsupported log entry: {err}"))?;
+            if body.spec.data.hash.value != digest || decode(&body.spec.signature.content)? != signature {
+                return Err("log entry doesn't match the signature".to_string());
+            }
+            return entry
+                .integrated_time
+                .parse()
+                .map_err(|_| "invalid integrated time".to_string());
+        }
+        Err("bundle has no log entry promised by the configured Rekor keys".to_string())
+    }
+
+    /// Verify that the certificate chains up to the Fulcio roots, and was valid at a time.
+    fn verify_certificate(&self, leaf: &X509, intermediates: Vec<X509>, time: i64) -> Result<(), String> {
+        let time = Asn1Time::from_unix(time).map_err(|err| err.to_string())?;
+        let valid = leaf.not_before().compare(&time).map_err(|err| err.to_string())? != Ordering::Greater
+            && leaf.not_after().compare(&time).map_err(|err| err.to_string())? != Ordering::Less;
+        if !valid {
+            return Err("signing certificate wasn't valid when the signature was logged".to_string());
+        }
+
+        let chained = || -> Result<bool, openssl::error::ErrorStack> {
+            let mut builder = X509StoreBuilder::new()?;
+            for root in &self.fulcio {
+                builder.add_cert(root.clone())?;
+            }
+            // the validity of the signing certificate was checked at the time of logging, not now
+            builder.set_flags(X509VerifyFlags::NO_CHECK_TIME)?;
+            let store = builder.build();
+
+            let mut chain = Stack::new()?;
+            for intermediate in intermediates {
+                chain.push(intermediate)?;
+            }
+            let mut context = X509StoreContext::new()?;
+            context.init(&store, leaf, &chain, |context| context.verify_cert())
+        };
+        match chained() {
+            Ok(true) => Ok(()),
+            Ok(false) => Err("signing certificate doesn't chain up to the Fulcio roots".to_string()),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+}
+
+/// The signature an SBOM was uploaded with.
+#[derive(Debug)]
+pub(crate) enum Signed {
+    Detached(Vec<u8>),
+    Bundle(Box<Bundle>),
+}
+
+impl Signed {
+    /// Read the signature sent with an upload, `None` if there is none.
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Result<Option<Self>, String> {
+        let value = |name: &str| {
+            headers
+                .get(name)
+                .map(|value| value.to_str().map_err(|err| format!("{name}: {err}")))
+                .transpose()
+        };
+        if let Some(bundle) = value(BUNDLE_HEADER)? {
+            let bundle = serde_json::from_slice(&decode(bundle)?).map_err(|err| format!("invalid bundle: {err}"))?;
+            return Ok(Some(Self::Bundle(Box::new(bundle))));
+        }
+        Ok(value(SIGNATURE_HEADER)?.map(decode).transpose()?.map(Self::Detached))
+    }
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, String> {
+    STANDARD
+        .decode(value.trim())
+        .map_err(|err| format!("invalid base64: {err}"))
+}
+
+/// Verify a signature over SHA-256, or over the data itself for Ed25519 keys.
+fn verify_with(key: &PKey<Public>, data: &[u8], signature: &[u8]) -> bool {
+    let verified = match key.id() {
+        Id::ED25519 => {
+            Verifier::new_without_digest(key).and_then(|mut verifier| verifier.verify_oneshot(signature, data))
+        }
+        _ => Verifier::new(MessageDigest::sha256(), key).and_then(|mut verifier| {
+            verifier.update(data)?;
+            verifier.verify(signature)
+        }),
+    };
+    // malformed signatures fail verifying
+    verified.unwrap_or(false)
+}
+
+/// The identity of a Fulcio certificate: the email or URI of its subject alternative name.
+fn identity(certificate: &X509) -> Option<String> {
+    certificate
+        .subject_alt_names()?
+        .iter()
+        .find_map(|name| name.email().or_else(|| name.uri()).map(ToString::to_string))
+}
+
+/// A sigstore bundle, as produced by `cosign sign-blob --bundle` with the new bundle format.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Bundle {
+    verification_material: VerificationMaterial,
+    message_signature: Option<MessageSignature>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VerificationMaterial {
+    x509_certificate_chain: Option<CertificateChain>,
+    certificate: Option<Certificate>,
+    #[serde(default)]
+    tlog_entries: Vec<TlogEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CertificateChain {
+    certificates: Vec<Certificate>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Certificate {
+    raw_bytes: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageSignature {
+    signature: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TlogEntry {
+    log_index: String,
+    log_id: LogId,
+    integrated_time: String,
+    inclusion_promise: Option<InclusionPromise>,
+    canonicalized_body: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LogId {
+    key_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InclusionPromise {
+    signed_entry_timestamp: String,
+}
+
+/// What Rekor signs in a signed entry timestamp, serialized as canonical JSON: sorted keys, no whitespace.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PromisedEntry<'a> {
+    body: &'a str,
+    integrated_time: i64,
+    #[serde(rename = "logID")]
+    log_id: String,
+    log_index: i64,
+}
+
+impl TlogEntry {
+    fn promised_payload(&self) -> Result<Vec<u8>, String> {
+        let invalid = |field: &str| format!("invalid {field} of log entry");
+        serde_json::to_vec(&PromisedEntry {
+            body: &self.canonicalized_body,
+            integrated_time: self.integrated_time.parse().map_err(|_| invalid("integrated time"))?,
+            log_id: hex::encode(decode(&self.log_id.key_id)?),
+            log_index: self.log_index.parse().map_err(|_| invalid("index"))?,
+        })
+        .map_err(|err| err.to_string())
+    }
+}
+
+/// The body of a `hashedrekord` log entry, as far as it's verified.
+#[derive(Debug, Deserialize)]
+struct HashedRekord {
+    spec: HashedRekordSpec,
+}
+
+#[derive(Debug, Deserialize)]
+struct HashedRekordSpec {
+    data: HashedRekordData,
+    signature: HashedRekordSignature,
+}
+
+#[derive(Debug, Deserialize)]
+struct HashedRekordData {
+    hash: HashedRekordHash,
+}
+
+#[derive(Debug, Deserialize)]
+struct HashedRekordHash {
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HashedRekordSignature {
+    content: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use openssl::{
+        bn::BigNum,
+        ec::{EcGroup, EcKey},
+        nid::Nid,
+        pkey::Private,
+        sign::Signer,
+        x509::{
+            extension::{BasicConstraints, KeyUsage, SubjectAlternativeName},
+            X509Builder, X509NameBuilder,
+        },
+    };
+    use serde_json::json;
+
+    const SBOM: &[u8] = br#"{"bomFormat":"CycloneDX","specVersion":"1.4"}"#;
+    const INTEGRATED_TIME: i64 = 1_700_000_000;
+
+    fn key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    fn public(key: &PKey<Private>) -> PKey<Public> {
+        PKey::public_key_from_pem(&key.public_key_to_pem().unwrap()).unwrap()
+    }
+
+    fn sign(key: &PKey<Private>, data: &[u8]) -> Vec<u8> {
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+        signer.update(data).unwrap();
+        signer.sign_to_vec().unwrap()
+    }
+
+    fn certificate(key: &PKey<Private>, issuer: Option<(&X509, &PKey<Private>)>, email: Option<&str>) -> X509 {
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", if issuer.is_some() { "signer" } else { "root" })
+            .unwrap();
+        let name = name.build();
+
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder
+            .set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap())
+            .unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_pubkey(key).unwrap();
+        match issuer {
+            Some((issuer, _)) => {
+                builder.set_issuer_name(issuer.subject_name()).unwrap();
+                // short-lived, like Fulcio certificates
+                builder
+                    .set_not_before(&Asn1Time::from_unix(INTEGRATED_TIME - 60).unwrap())
+                    .unwrap();
+                builder
+                    .set_not_after(&Asn1Time::from_unix(INTEGRATED_TIME + 540).unwrap())
+                    .unwrap();
+            }
+            None => {
+                builder.set_issuer_name(&name).unwrap();
+                builder
+                    .append_extension(BasicConstraints::new().critical().ca().build().unwrap())
+                    .unwrap();
+                builder
+                    .append_extension(KeyUsage::new().critical().key_cert_sign().build().unwrap())
+                    .unwrap();
+                builder
+                    .set_not_before(&Asn1Time::from_unix(INTEGRATED_TIME - 3600).unwrap())
+                    .unwrap();
+                builder.set_not_after(&Asn1Time::days_from_now(365).unwrap()).unwrap();
+            }
+        }
+        if let Some(email) = email {
+            let san = SubjectAlternativeName::new()
+                .email(email)
+                .build(&builder.x509v3_context(issuer.map(|(issuer, _)| &**issuer), None))
+                .unwrap();
+            builder.append_extension(san).unwrap();
+        }
+        builder
+            .sign(issuer.map(|(_, key)| key).unwrap_or(key), MessageDigest::sha256())
+            .unwrap();
+        builder.build()
+    }
+
+    /// A bundle of a signature by an email identity, logged by a Rekor key.
+    fn signed_bundle(root: (&X509, &PKey<Private>), rekor: &PKey<Private>, data: &[u8], email: &str) -> Bundle {
+        let key = key();
+        let leaf = certificate(&key, Some(root), Some(email));
+        let signature = STANDARD.encode(sign(&key, data));
+        let body = STANDARD.encode(
+            json!({
+                "apiVersion": "0.0.1",
+                "kind": "hashedrekord",
+                "spec": {
+                    "data": { "hash": { "algorithm": "sha256", "value": hex::encode(Sha256::digest(data)) } },
+                    "signature": { "content": signature, "publicKey": { "content": "" } },
+                },
+            })
+            .to_string(),
+        );
+        let mut entry = TlogEntry {
+            log_index: "42".to_string(),
+            log_id: LogId {
+                key_id: STANDARD.encode([0xab; 32]),
+            },
+            integrated_time: INTEGRATED_TIME.to_string(),
+            inclusion_promise: None,
+            canonicalized_body: body,
+        };
+        entry.inclusion_promise = Some(InclusionPromise {
+            signed_entry_timestamp: STANDARD.encode(sign(rekor, &entry.promised_payload().unwrap())),
+        });
+
+        Bundle {
+            verification_material: VerificationMaterial {
+                x509_certificate_chain: None,
+                certificate: Some(Certificate {
+                    raw_bytes: STANDARD.encode(leaf.to_der().unwrap()),
+                }),
+                tlog_entries: vec![entry],
+            },
+            message_signature: Some(MessageSignature { signature }),
+        }
+    }
+
+    #[test]
+    fn detached() {
+        let release = key();
+        let roots = TrustRoots {
+            keys: vec![("release".to_string(), public(&release))],
+            ..Default::default()
+        };
+
+        let signature = roots.verify(Some(&Signed::Detached(sign(&release, SBOM))), SBOM);
+        assert!(signature.verified);
+        assert_eq!(signature.signer.as_deref(), Some("release"));
+
+        // signed by another key
+        let signature = roots.verify(Some(&Signed::Detached(sign(&key(), SBOM))), SBOM);
+        assert!(!signature.verified);
+        assert_eq!(signature.signer, None);
+
+        let signature = roots.verify(None, SBOM);
+        assert_eq!(signature.error.as_deref(), Some("missing signature"));
+    }
+
+    #[test]
+    fn sigstore_bundle() {
+        let (root_key, rekor) = (key(), key());
+        let root = certificate(&root_key, None, None);
+        let roots = TrustRoots {
+            fulcio: vec![root.clone()],
+            rekor: vec![public(&rekor)],
+            identities: vec!["release@example.com".to_string()],
+            ..Default::default()
+        };
+
+        let bundle = signed_bundle((&root, &root_key), &rekor, SBOM, "release@example.com");
+        let signature = roots.verify(Some(&Signed::Bundle(Box::new(bundle))), SBOM);
+        assert_eq!(signature.error, None);
+        assert_eq!(signature.signer.as_deref(), Some("release@example.com"));
+
+        // another document
+        let bundle = signed_bundle((&root, &root_key), &rekor, SBOM, "release@example.com");
+        assert!(!roots.verify(Some(&Signed::Bundle(Box::new(bundle))), b"{}").verified);
+
+        // an identity which isn't allowed
+        let bundle = signed_bundle((&root, &root_key), &rekor, SBOM, "someone@example.com");
+        assert!(!roots.verify(Some(&Signed::Bundle(Box::new(bundle))), SBOM).verified);
+
+        // logged by another Rekor instance
+        let bundle = signed_bundle((&root, &root_key), &key(), SBOM, "release@example.com");
+        assert!(!roots.verify(Some(&Signed::Bundle(Box::new(bundle))), SBOM).verified);
+
+        // issued by another Fulcio instance
+        let other_key = key();
+        let other = certificate(&other_key, None, None);
+        let bundle = signed_bundle((&other, &other_key), &rekor, SBOM, "release@example.com");
+        assert!(!roots.verify(Some(&Signed::Bundle(Box::new(bundle))), SBOM).verified);
+    }
+
+    #[test]
+    fn headers() {
+        let mut headers = HeaderMap::new();
+        assert!(Signed::from_headers(&headers).unwrap().is_none());
+
+        headers.insert(
+            SIGNATURE_HEADER.parse().unwrap(),
+            STANDARD.encode(b"signature").parse().unwrap(),
+        );
+        assert!(matches!(
+            Signed::from_headers(&headers).unwrap(),
+            Some(Signed::Detached(signature)) if signature == b"signature"
+        ));
+
+        headers.insert(BUNDLE_HEADER.parse().unwrap(), "not base64!".parse().unwrap());
+        assert!(Signed::from_headers(&headers).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use bombastic_index::{packages, sbom};
+use colored_json::to_colored_json_auto;
+use serde::Serialize;
+use std::{fmt::Debug, path::PathBuf, process::ExitCode};
+use trustification_api::search::SearchOptions;
+use trustification_index::{Index, IndexStore};
+
+/// Work with a local copy of an index
+#[derive(clap::Subcommand, Debug)]
+pub enum IndexCommand {
+    Inspect(Inspect),
+}
+
+impl IndexCommand {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        match self {
+            Self::Inspect(inspect) => inspect.run(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum IndexKind {
+    Sbom,
+    Package,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(about = "Inspect a local index directory")]
+pub struct Inspect {
+    /// Path to the index directory (an unpacked index snapshot)
+    #[arg(short = 'p', long = "path")]
+    pub path: PathBuf,
+
+    /// The kind of index stored in the directory
+    #[arg(short = 'k', long = "kind", value_enum, default_value_t = IndexKind::Sbom)]
+    pub kind: IndexKind,
+
+    #[command(subcommand)]
+    pub command: InspectCommand,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum InspectCommand {
+    /// Print the schema of the index
+    Schema,
+    /// Print segment statistics and document counts per field
+    Stats,
+    /// Run a search query against the index
+    Query(Query),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct Query {
+    /// The search query
+    pub q: String,
+
+    /// Offset of documents to return
+    #[arg(long, default_value_t = 0)]
+    pub offset: usize,
+
+    /// Max number of documents to return
+    #[arg(long, default_value_t = 10)]
+    pub limit: usize,
+
+    /// Provide a detailed explanation of query matches
+    #[arg(long)]
+    pub explain: bool,
+
+    /// Provide additional metadata from the index
+    #[arg(long)]
+    pub metadata: bool,
+}
+
+impl Inspect {
+    pub fn run(self) -> anyhow::Result<ExitCode> {
+        match self.kind {
+            IndexKind::Sbom => self.command.run(IndexStore::open_dir(&self.path, sbom::Index::new())?),
+            IndexKind::Package => self
+                .command
+                .run(IndexStore::open_dir(&self.path, packages::Index::new())?),
+        }
+    }
+}
+
+impl InspectCommand {
+    fn run<INDEX>(self, store: IndexStore<INDEX>) -> anyhow::Result<ExitCode>
+    where
+        INDEX: Index + 'static,
+        INDEX::MatchedDocument: Serialize,
+    {
+        match self {
+            Self::Schema => {
+                println!("Schema version: {}", store.schema_version());
+                for (_, entry) in store.index().schema().fields() {
+                    println!(
+                        "{:<40} {:<8} {}",
+                        entry.name(),
+                        format!("{:?}", entry.field_type().value_type()),
+                        flags(entry.is_indexed(), entry.is_stored(), entry.is_fast())
+                    );
+                }
+            }
+            Self::Stats => {
+                let segments = store.segment_stats()?;
+                println!("Segments: {}", segments.len());
+                for segment in &segments {
+                    println!(
+                        "  {}  docs: {}  deleted: {}",
+                        segment.id, segment.num_docs, segment.num_deleted_docs
+                    );
+                }
+                println!("Documents: {}", store.get_total_docs()?);
+                println!();
+                println!("{:<40} {:>12} {:>12}", "Field", "Documents", "Terms");
+                for field in store.field_stats()? {
+                    println!(
+                        "{:<40} {:>12} {:>12}",
+                        field.name,
+                        field.num_docs.map(|n| n.to_string()).unwrap_or_else(|| "-".into()),
+                        field.num_terms.map(|n| n.to_string()).unwrap_or_else(|| "-".into()),
+                    );
+                }
+            }
+            Self::Query(query) => {
+                let (hits, total) = store.search(
+                    &query.q,
+                    query.offset,
+                    query.limit,
+                    SearchOptions {
+                        explain: query.explain,
+                        metadata: query.metadata,
+                        summaries: true,
+                    },
+                )?;
+                println!("{}", to_colored_json_auto(&serde_json::to_value(hits)?)?);
+                println!("Total: {total}");
+            }
+        }
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+fn flags(indexed: bool, stored: bool, fast: bool) -> String {
+    [(indexed, "INDEXED"), (stored, "STORED"), (fast, "FAST")]
+        .into_iter()
+        .filter_map(|(set, name)| set.then_some(name))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
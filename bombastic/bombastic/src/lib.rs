@@ -1,11 +1,18 @@
 use std::process::ExitCode;
 
+mod index;
+mod reindex;
+
 /// Run bombastic services (`trust bombastic --help` for details)
 #[derive(clap::Subcommand, Debug)]
 pub enum Command {
     Api(bombastic_api::Run),
     Indexer(bombastic_indexer::Run),
     Walker(bombastic_walker::Run),
+    Federation(bombastic_federation::Run),
+    #[command(subcommand)]
+    Index(index::IndexCommand),
+    Reindex(reindex::Reindex),
 }
 
 impl Command {
@@ -14,6 +21,9 @@ impl Command {
             Self::Api(run) => run.run(None).await,
             Self::Indexer(run) => run.run().await,
             Self::Walker(run) => run.run().await,
+            Self::Federation(run) => run.run().await,
+            Self::Index(run) => run.run().await,
+            Self::Reindex(run) => run.run().await,
         }
     }
 }
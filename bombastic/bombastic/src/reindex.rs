@@ -0,0 +1,123 @@
+use bombastic_index::{custom::CustomIds, document::ParsedSbom, packages, sbom};
+use futures::{pin_mut, StreamExt};
+use prometheus::Registry;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tokio::task::block_in_place;
+use trustification_index::{IndexConfig, IndexMode, IndexStore, WriteIndex};
+use trustification_storage::{ContinuationToken, Storage, StorageConfig};
+
+type SbomIndex = Box<dyn WriteIndex<Document = (ParsedSbom, String, Vec<String>)>>;
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Rebuild the indexes from the stored SBOMs",
+    long_about = "Rebuild the indexes from the stored SBOMs, replacing the published index snapshots. \
+    Used to migrate the indexes after their schema changed. The indexers should be stopped while running it, \
+    as their snapshots would replace the rebuilt ones."
+)]
+pub struct Reindex {
+    /// Index the names and SHA256 digests of the files of SPDX documents, which may grow the index considerably
+    #[arg(long = "index-files", env = "INDEX_FILES", default_value_t = false)]
+    pub index_files: bool,
+
+    /// YAML file configuring the rules extracting custom identifiers of vendors, searchable using `custom:`
+    #[arg(long = "custom-id-config", env = "CUSTOM_ID_CONFIG")]
+    pub custom_id_config: Option<PathBuf>,
+
+    /// Build the indexes without publishing them
+    #[arg(long = "dry-run", default_value_t = false)]
+    pub dry_run: bool,
+
+    /// Development mode
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    #[command(flatten)]
+    pub storage: StorageConfig,
+
+    #[command(flatten)]
+    pub index: IndexConfig,
+}
+
+impl Reindex {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        if let Err(e) = env_logger::builder().format_timestamp_millis().try_init() {
+            eprintln!("Error initializing logging: {:?}", e);
+        }
+
+        if !matches!(self.index.mode, IndexMode::File) {
+            anyhow::bail!("Reindexing is only supported for file indices");
+        }
+
+        let registry = Registry::new();
+        let storage = Storage::new(self.storage.clone().process("bombastic", self.devmode), &registry)?;
+
+        let custom_ids = CustomIds::load(self.custom_id_config.as_deref())?;
+        let sbom_index: SbomIndex = Box::new(
+            sbom::Index::new()
+                .with_files(self.index_files)
+                .with_custom_ids(custom_ids),
+        );
+        let package_index: SbomIndex = Box::new(packages::Index::new());
+        let mut stores = Vec::new();
+        for index in [sbom_index, package_index] {
+            let store = block_in_place(|| IndexStore::new(&self.storage, &self.index, index, &registry))?;
+            println!(
+                "Index {}: schema version {}",
+                store.index().name(),
+                store.schema_version()
+            );
+            stores.push(store);
+        }
+        let mut writers = stores
+            .iter_mut()
+            .map(|store| block_in_place(|| store.writer()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut total = 0;
+        let mut failed = 0;
+        let objects = storage.list_objects_from(ContinuationToken::default());
+        pin_mut!(objects);
+        while let Some(next) = objects.next().await {
+            let (path, data) = next.map_err(|(e, _)| e)?;
+            let key = path.key();
+            let (provenance, archived) = match storage.get_head(path.clone()).await {
+                Ok(head) => (head.provenance, head.archived),
+                Err(e) => {
+                    log::warn!("(Ignored) Unable to read provenance of {}: {:?}", key, e);
+                    (None, false)
+                }
+            };
+            let source = provenance.as_ref().map(|p| p.source.as_str());
+            let labels = provenance.as_ref().map(|p| p.labels()).unwrap_or_default();
+
+            total += 1;
+            let mut result = Ok(());
+            for (store, writer) in stores.iter().zip(writers.iter_mut()) {
+                result = result.and(block_in_place(|| {
+                    writer.add_stored_document(store.index(), &key, &data, source, archived, &labels)
+                }));
+            }
+            match result {
+                Ok(()) => log::debug!("Reindexed {key}"),
+                Err(e) => {
+                    log::warn!("Unable to reindex {key}: {e}");
+                    failed += 1;
+                }
+            }
+        }
+
+        for (store, writer) in stores.iter_mut().zip(writers) {
+            if self.dry_run {
+                store.commit(writer)?;
+            } else {
+                store.snapshot(writer, &storage, true).await?;
+                println!("Index {} published", store.index().name());
+            }
+        }
+
+        println!("Reindexed {} of {total} SBOMs, {failed} failed", total - failed);
+        Ok(ExitCode::SUCCESS)
+    }
+}
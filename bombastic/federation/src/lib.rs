@@ -0,0 +1,131 @@
+//! Federation of Bombastic instances, mirroring the SBOMs of a remote instance.
+//!
+//! The federation pages through the changes feed of the remote, `/api/v1/sbom/changes`, retrieves the SBOMs which
+//! changed, and stores them in the local storage with the `federation` source, keeping prior revisions like the API
+//! does. It sends the events of the stored SBOMs to the stored topic, for the local indexer to pick them up. SBOMs
+//! deleted locally aren't mirrored again, and SBOMs stored locally from another source aren't replaced.
+
+use clap::ArgAction;
+use mirror::{Mirror, Options, StoredEvents};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use trustification_auth::client::OpenIdTokenProviderConfigArguments;
+use trustification_common::tls::ClientConfig;
+use trustification_event_bus::EventBusConfig;
+use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
+use trustification_storage::{Storage, StorageConfig};
+use url::Url;
+
+mod mirror;
+mod state;
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Mirror the SBOMs of another Bombastic instance",
+    args_conflicts_with_subcommands = true,
+    rename_all_env = "SCREAMING_SNAKE_CASE"
+)]
+pub struct Run {
+    /// Apply reasonable settings for local development. Do not use in production!
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    /// URL of the Bombastic API to mirror
+    #[arg(long = "remote", env = "FEDERATION_REMOTE")]
+    pub remote: Url,
+
+    /// Long-running mode. The remote will be synced with every interval, otherwise it's synced once.
+    #[arg(long = "interval", env = "FEDERATION_INTERVAL")]
+    pub interval: Option<humantime::Duration>,
+
+    /// Number of changes requested from the remote at once
+    #[arg(long = "page-size", default_value_t = 100)]
+    pub page_size: usize,
+
+    /// Request the changes of this period before the cursor again, to pick up SBOMs which the remote committed after
+    /// SBOMs indexed later. It should exceed the time the remote takes to commit and publish its index.
+    #[arg(long = "overlap", env = "FEDERATION_OVERLAP", default_value = "1h")]
+    pub overlap: humantime::Duration,
+
+    /// A file to keep the state of the federation in: the cursor of the changes feed of the remote, and the digests
+    /// of the mirrored SBOMs. Without it, all SBOMs of the remote are mirrored again when restarting.
+    #[arg(long = "state-file", env = "FEDERATION_STATE_FILE")]
+    pub state_file: Option<PathBuf>,
+
+    /// Number of prior revisions kept when an SBOM is mirrored again, which should match the one of the local Bombastic
+    /// API, `0` disables keeping them
+    #[arg(long = "max-revisions", env = "FEDERATION_MAX_REVISIONS", default_value_t = 10)]
+    pub max_revisions: usize,
+
+    /// Send the events of mirrored SBOMs to the stored topic. Disable it if the storage sends its own notifications to
+    /// the topic, so that SBOMs aren't indexed twice.
+    #[arg(long = "send-events", env = "FEDERATION_SEND_EVENTS", default_value_t = true, action = ArgAction::Set)]
+    pub send_events: bool,
+
+    #[arg(long = "stored-topic", default_value = "sbom-stored")]
+    pub stored_topic: String,
+
+    /// TLS configuration of the client of the remote
+    #[command(flatten)]
+    pub client: ClientConfig,
+
+    /// OIDC client, to authenticate with the remote
+    #[command(flatten)]
+    pub oidc: OpenIdTokenProviderConfigArguments,
+
+    #[command(flatten)]
+    pub bus: EventBusConfig,
+
+    #[command(flatten)]
+    pub storage: StorageConfig,
+
+    #[command(flatten)]
+    pub infra: InfrastructureConfig,
+}
+
+impl Run {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        Infrastructure::from(self.infra)
+            .run(
+                "bombastic-federation",
+                |_context| async { Ok(()) },
+                |context| async move {
+                    let provider = self.oidc.into_provider_or_devmode(self.devmode).await?;
+                    let storage = self.storage.process("bombastic", self.devmode);
+                    let storage = Storage::new(storage, context.metrics.registry())?;
+
+                    let events = match self.send_events {
+                        true => {
+                            let bus = self.bus.create(context.metrics.registry()).await?;
+                            if self.devmode {
+                                bus.create(&[self.stored_topic.as_str()]).await?;
+                            }
+                            Some(StoredEvents {
+                                bus,
+                                topic: self.stored_topic,
+                            })
+                        }
+                        false => None,
+                    };
+
+                    let options = Options {
+                        remote: self.remote,
+                        client: self.client.build_client()?,
+                        provider,
+                        page_size: self.page_size,
+                        overlap: self.overlap.into(),
+                        state_file: self.state_file,
+                        max_revisions: self.max_revisions,
+                    };
+                    let mut mirror = Mirror::new(options, storage, events, context.metrics.registry())?;
+
+                    match self.interval {
+                        Some(interval) => mirror.run(interval.into()).await,
+                        None => mirror.sync().await,
+                    }
+                },
+            )
+            .await?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
@@ -0,0 +1,339 @@
+use crate::state::FederationState;
+use bombastic_model::changes::{Change, Changes};
+use futures::TryStreamExt;
+use prometheus::{
+    opts, register_int_counter_with_registry, register_int_gauge_with_registry, IntCounter, IntGauge, Registry,
+};
+use reqwest::{header::CONTENT_TYPE, StatusCode};
+use std::{io, path::PathBuf, sync::Arc, time::Duration};
+use time::OffsetDateTime;
+use tokio::time::MissedTickBehavior;
+use trustification_auth::client::{TokenInjector, TokenProvider};
+use trustification_event_bus::EventBus;
+use trustification_storage::{Error as StorageError, Provenance, S3Path, Source, Storage};
+use url::Url;
+
+struct Metrics {
+    mirrored_total: IntCounter,
+    failed_total: IntCounter,
+    last_sync: IntGauge,
+}
+
+impl Metrics {
+    fn register(registry: &Registry) -> anyhow::Result<Self> {
+        let mirrored_total = register_int_counter_with_registry!(
+            opts!(
+                "federation_mirrored_total",
+                "Total number of SBOMs mirrored from the remote"
+            ),
+            registry
+        )?;
+
+        let failed_total = register_int_counter_with_registry!(
+            opts!(
+                "federation_failed_total",
+                "Total number of SBOMs of the remote which failed to be mirrored"
+            ),
+            registry
+        )?;
+
+        let last_sync = register_int_gauge_with_registry!(
+            opts!(
+                "federation_last_sync_timestamp_seconds",
+                "Time the latest complete sync with the remote ended, as Unix timestamp"
+            ),
+            registry
+        )?;
+
+        Ok(Self {
+            mirrored_total,
+            failed_total,
+            last_sync,
+        })
+    }
+}
+
+/// What became of a change of the remote.
+enum Outcome {
+    Mirrored,
+    /// The SBOM was mirrored with this content already
+    Unchanged,
+    /// The SBOM was deleted locally, and isn't brought back
+    Deleted,
+    /// An SBOM with the same id was stored locally, not mirrored from the remote, and isn't replaced
+    Local,
+    /// The SBOM was deleted on the remote after it was indexed
+    Gone,
+    /// The SBOM was refused by the local storage, like for its size
+    Refused,
+}
+
+/// The topic to send the events of stored SBOMs to, for the local indexer to pick them up.
+pub struct StoredEvents {
+    pub bus: EventBus,
+    pub topic: String,
+}
+
+pub struct Options {
+    pub remote: Url,
+    pub client: reqwest::Client,
+    pub provider: Arc<dyn TokenProvider>,
+    pub page_size: usize,
+    pub overlap: Duration,
+    pub state_file: Option<PathBuf>,
+    /// Number of prior revisions kept when an SBOM is mirrored again
+    pub max_revisions: usize,
+}
+
+/// Mirrors the SBOMs of a remote Bombastic instance into the local storage.
+pub struct Mirror {
+    options: Options,
+    storage: Storage,
+    events: Option<StoredEvents>,
+    state: FederationState,
+    metrics: Metrics,
+}
+
+impl Mirror {
+    pub fn new(
+        mut options: Options,
+        storage: Storage,
+        events: Option<StoredEvents>,
+        registry: &Registry,
+    ) -> anyhow::Result<Self> {
+        let state = match &options.state_file {
+            Some(path) => FederationState::load(path)?,
+            None => FederationState::default(),
+        };
+        options.remote = base_url(options.remote);
+        Ok(Self {
+            options,
+            storage,
+            events,
+            state,
+            metrics: Metrics::register(registry)?,
+        })
+    }
+
+    /// Sync with the remote every interval, until the process is stopped.
+    pub async fn run(&mut self, interval: Duration) -> anyhow::Result<()> {
+        let mut interval = tokio::time::interval(interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.sync().await {
+                log::warn!("Failed to sync with {}: {err}", self.options.remote);
+            }
+        }
+    }
+
+    /// Mirror the SBOMs which changed on the remote since the last sync.
+    ///
+    /// A sync stops at the first SBOM failing to be retrieved or stored, so that the next sync resumes from it. SBOMs
+    /// refused by the local storage are skipped, as they would be refused again.
+    pub async fn sync(&mut self) -> anyhow::Result<()> {
+        log::info!("Syncing with {} since {}", self.options.remote, self.state.since);
+        let res = self.sync_pages().await;
+        if let Some(path) = &self.options.state_file {
+            // the processed changes are recorded even if the sync failed
+            self.state.store(path)?;
+        }
+        if res.is_ok() {
+            self.metrics.last_sync.set(OffsetDateTime::now_utc().unix_timestamp());
+        }
+        res
+    }
+
+    /// Page through the changes, starting the overlap before the cursor.
+    ///
+    /// The remote takes the time an SBOM was indexed before committing it, so an SBOM may only show up in the feed after
+    /// SBOMs indexed later. The overlap picks it up with a later sync, while the SBOMs mirrored already are skipped by
+    /// their digest.
+    async fn sync_pages(&mut self) -> anyhow::Result<()> {
+        let overlap = i64::try_from(self.options.overlap.as_nanos()).unwrap_or(i64::MAX);
+        let mut since = self.state.since.saturating_sub(overlap).max(0);
+        loop {
+            let page = self.changes(since).await?;
+            for change in &page.changes {
+                match self.mirror(change).await {
+                    Ok(outcome) => {
+                        match outcome {
+                            Outcome::Mirrored => self.metrics.mirrored_total.inc(),
+                            Outcome::Refused => self.metrics.failed_total.inc(),
+                            Outcome::Unchanged | Outcome::Deleted | Outcome::Local | Outcome::Gone => {}
+                        }
+                        self.state.since = self.state.since.max(change.indexed_timestamp);
+                    }
+                    Err(err) => {
+                        self.metrics.failed_total.inc();
+                        return Err(err.context(format!("failed to mirror SBOM {}", change.id)));
+                    }
+                }
+            }
+            since = page.next;
+            self.state.since = self.state.since.max(page.next);
+            if let Some(path) = &self.options.state_file {
+                self.state.store(path)?;
+            }
+            if !page.more {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Retrieve the page of the changes feed of the remote after a cursor.
+    async fn changes(&self, since: i64) -> anyhow::Result<Changes> {
+        let mut url = self.options.remote.join("api/v1/sbom/changes")?;
+        url.query_pairs_mut()
+            .append_pair("since", &since.to_string())
+            .append_pair("limit", &self.options.page_size.to_string());
+        Ok(self
+            .options
+            .client
+            .get(url)
+            .inject_token(self.options.provider.as_ref())
+            .await?
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    async fn mirror(&mut self, change: &Change) -> anyhow::Result<Outcome> {
+        if self.state.is_unchanged(change) {
+            log::debug!("Skipping unchanged SBOM {}", change.id);
+            return Ok(Outcome::Unchanged);
+        }
+        if let Some(tombstone) = self.storage.get_tombstone((&change.id).into()).await? {
+            log::info!("Not mirroring SBOM {tombstone}");
+            return Ok(Outcome::Deleted);
+        }
+        match self
+            .storage
+            .get_provenance(&S3Path::from_key((&change.id).into()))
+            .await
+        {
+            Ok(provenance) if !mirrored_from(provenance.as_ref(), &self.options.remote) => {
+                log::info!("Not mirroring SBOM {}, which is stored locally already", change.id);
+                return Ok(Outcome::Local);
+            }
+            Ok(_) | Err(StorageError::NotFound) => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        let mut url = self.options.remote.join("api/v1/sbom")?;
+        url.query_pairs_mut().append_pair("id", &change.id);
+        let response = self
+            .options
+            .client
+            .get(url.clone())
+            .inject_token(self.options.provider.as_ref())
+            .await?
+            .send()
+            .await?;
+        if matches!(response.status(), StatusCode::NOT_FOUND | StatusCode::GONE) {
+            log::info!("SBOM {} was deleted from the remote", change.id);
+            return Ok(Outcome::Gone);
+        }
+        let response = response.error_for_status()?;
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("application/json")
+            .to_string();
+        let data = response
+            .bytes_stream()
+            .map_err(|e| StorageError::Io(io::Error::new(io::ErrorKind::Other, e)));
+
+        let provenance = Provenance::new(Source::Federation, None, Some(url.as_str()));
+        match self
+            .storage
+            .replace_stream(
+                (&change.id).into(),
+                &content_type,
+                None,
+                &provenance,
+                data,
+                self.options.max_revisions,
+            )
+            .await
+        {
+            Ok(_) => {}
+            Err(err @ (StorageError::ExceedsMaxSize(_) | StorageError::InvalidContent)) => {
+                log::warn!("Skipping SBOM {} refused by the storage: {err}", change.id);
+                return Ok(Outcome::Refused);
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        if let Some(events) = &self.events {
+            let event = serde_json::to_vec(&self.storage.put_event(&change.id))?;
+            events.bus.send(&events.topic, &event).await?;
+        }
+        self.state.record(change);
+        log::debug!("Mirrored SBOM {}", change.id);
+        Ok(Outcome::Mirrored)
+    }
+}
+
+/// Whether a stored SBOM was mirrored from the remote, so that mirroring it again doesn't replace an SBOM of another
+/// source having the same id.
+fn mirrored_from(provenance: Option<&Provenance>, remote: &Url) -> bool {
+    provenance.is_some_and(|provenance| {
+        provenance.source == Source::Federation
+            && provenance
+                .source_url
+                .as_deref()
+                .is_some_and(|url| url.starts_with(remote.as_str()))
+    })
+}
+
+/// The URL of the remote as base of its endpoints, which are joined relative to it to keep any path prefix.
+fn base_url(mut remote: Url) -> Url {
+    if !remote.path().ends_with('/') {
+        let path = format!("{}/", remote.path());
+        remote.set_path(&path);
+    }
+    remote
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn endpoints() {
+        for remote in ["https://sbom.example.com", "https://sbom.example.com/"] {
+            let url = base_url(Url::parse(remote).unwrap()).join("api/v1/sbom").unwrap();
+            assert_eq!(url.as_str(), "https://sbom.example.com/api/v1/sbom");
+        }
+        for remote in ["https://example.com/bombastic", "https://example.com/bombastic/"] {
+            let url = base_url(Url::parse(remote).unwrap()).join("api/v1/sbom").unwrap();
+            assert_eq!(url.as_str(), "https://example.com/bombastic/api/v1/sbom");
+        }
+    }
+
+    #[test]
+    fn mirrored() {
+        let remote = base_url(Url::parse("https://sbom.example.com/bombastic").unwrap());
+        let mirrored = Provenance::new(
+            Source::Federation,
+            None,
+            Some("https://sbom.example.com/bombastic/api/v1/sbom?id=quarkus"),
+        );
+        assert!(mirrored_from(Some(&mirrored), &remote));
+
+        // SBOMs of other remotes, other sources, or stored without provenance are kept
+        let other = Provenance::new(
+            Source::Federation,
+            None,
+            Some("https://sbom.example.org/api/v1/sbom?id=quarkus"),
+        );
+        assert!(!mirrored_from(Some(&other), &remote));
+        let uploaded = Provenance::new(Source::Api, Some("alice"), None);
+        assert!(!mirrored_from(Some(&uploaded), &remote));
+        assert!(!mirrored_from(None, &remote));
+    }
+}
@@ -0,0 +1,81 @@
+//! The state of the federation: the cursor of the changes feed of the remote, and the digests of the mirrored SBOMs.
+//!
+//! SBOMs show up in the changes feed again when the remote reindexes them, so the digests keep SBOMs whose content
+//! didn't change from being retrieved again.
+
+use anyhow::Context;
+use bombastic_model::changes::Change;
+use std::{collections::BTreeMap, path::Path};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct FederationState {
+    /// Cursor of the changes feed, the time the last processed SBOM was indexed by the remote
+    pub since: i64,
+    /// SHA-256 digests of the mirrored SBOMs, by their identifier
+    sboms: BTreeMap<String, String>,
+}
+
+impl FederationState {
+    /// Load the state from a file, starting from scratch if there's none yet.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        match std::fs::read(path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .with_context(|| format!("failed to parse federation state: {}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).with_context(|| format!("failed to read federation state: {}", path.display())),
+        }
+    }
+
+    /// Store the state, replacing the file atomically so that an interrupted sync doesn't lose it.
+    pub fn store(&self, path: &Path) -> anyhow::Result<()> {
+        let temp = path.with_extension("tmp");
+        std::fs::write(&temp, serde_json::to_vec(self)?)
+            .with_context(|| format!("failed to write federation state: {}", temp.display()))?;
+        std::fs::rename(&temp, path)
+            .with_context(|| format!("failed to store federation state: {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Whether an SBOM was mirrored with the content of a change.
+    pub fn is_unchanged(&self, change: &Change) -> bool {
+        self.sboms
+            .get(&change.id)
+            .is_some_and(|sha256| *sha256 == change.sha256)
+    }
+
+    /// Record a mirrored SBOM.
+    pub fn record(&mut self, change: &Change) {
+        self.sboms.insert(change.id.clone(), change.sha256.clone());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn state() {
+        let change = |sha256: &str| Change {
+            id: "ubi9-sbom".to_string(),
+            sha256: sha256.to_string(),
+            indexed_timestamp: 1_700_000_000_000_000_000,
+            labels: vec![],
+        };
+
+        let mut state = FederationState::default();
+        assert!(!state.is_unchanged(&change("abc")));
+        state.record(&change("abc"));
+        state.since = 1_700_000_000_000_000_000;
+
+        assert!(state.is_unchanged(&change("abc")));
+        assert!(!state.is_unchanged(&change("def")));
+
+        let dir = std::env::temp_dir().join(format!("bombastic-federation-state-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.json");
+        assert_eq!(FederationState::load(&path).unwrap(), FederationState::default());
+        state.store(&path).unwrap();
+        assert_eq!(FederationState::load(&path).unwrap(), state);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,157 @@
+//! Components, correlating the same package across SBOMs.
+//!
+//! A component is identified by its canonical package URL: the type, namespace and name of a package URL, without
+//! version, qualifiers and subpath. Its id is derived from the canonical package URL alone, so it's the same in every
+//! SBOM, and across re-indexing.
+
+use core::str::FromStr;
+use std::cmp::Ordering;
+
+use packageurl::PackageUrl;
+
+/// Number of hex digits of the SHA256 digest of the canonical package URL used as id.
+const ID_LEN: usize = 16;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Component {
+    /// Stable identifier of the component
+    pub id: String,
+    /// Canonical package URL of the component
+    pub purl: String,
+}
+
+impl Component {
+    /// The component of a package URL, and the version of the package URL.
+    pub fn from_purl(purl: &str) -> Option<(Self, Option<String>)> {
+        let purl = PackageUrl::from_str(purl).ok()?;
+        let mut canonical = PackageUrl::new(purl.ty().to_string(), purl.name().to_string()).ok()?;
+        if let Some(namespace) = purl.namespace() {
+            canonical.with_namespace(namespace.to_string());
+        }
+        let canonical = canonical.to_string();
+        Some((
+            Self {
+                id: component_id(&canonical),
+                purl: canonical,
+            },
+            purl.version().map(ToString::to_string),
+        ))
+    }
+}
+
+/// The id of the component of a canonical package URL.
+pub fn component_id(canonical_purl: &str) -> String {
+    sha256::digest(canonical_purl)[..ID_LEN].to_string()
+}
+
+/// Whether a value has the format of a component id.
+pub fn is_component_id(value: &str) -> bool {
+    value.len() == ID_LEN && value.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// Compare two versions of a package, like `1.10.0` and `1.9.2-1.el9`.
+///
+/// Versions are compared by their alphanumeric segments: numeric segments numerically, and other segments
+/// lexically, a numeric segment being newer than an alphabetic one. This is close enough to the version schemes of
+/// most ecosystems to order versions for reports, but isn't meant to decide which version is affected by a
+/// vulnerability.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a, b) = (segments(a), segments(b));
+    for (a, b) in a.iter().zip(b.iter()) {
+        let ordering = match (is_numeric(a), is_numeric(b)) {
+            (true, true) => {
+                let (a, b) = (a.trim_start_matches('0'), b.trim_start_matches('0'));
+                a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+            }
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => a.cmp(b),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+fn is_numeric(segment: &str) -> bool {
+    segment.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Split a version into runs of digits and runs of letters, dropping separators.
+fn segments(version: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut start = None;
+    let mut numeric = false;
+    for (i, c) in version.char_indices() {
+        if !c.is_alphanumeric() {
+            if let Some(start) = start.take() {
+                segments.push(&version[start..i]);
+            }
+            continue;
+        }
+        match start {
+            Some(s) if numeric != c.is_ascii_digit() => {
+                segments.push(&version[s..i]);
+                start = Some(i);
+            }
+            Some(_) => {}
+            None => start = Some(i),
+        }
+        numeric = c.is_ascii_digit();
+    }
+    if let Some(start) = start {
+        segments.push(&version[start..]);
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_purl() {
+        let (component, version) =
+            Component::from_purl("pkg:rpm/redhat/openssl-libs@3.0.7-18.el9_2?arch=x86_64&epoch=1").unwrap();
+        assert_eq!(component.purl, "pkg:rpm/redhat/openssl-libs");
+        assert_eq!(version.as_deref(), Some("3.0.7-18.el9_2"));
+        assert!(is_component_id(&component.id));
+
+        // the same component, whatever the version and qualifiers
+        let (other, version) = Component::from_purl("pkg:rpm/redhat/openssl-libs@3.0.1-47.el9_1?arch=aarch64").unwrap();
+        assert_eq!(other, component);
+        assert_eq!(version.as_deref(), Some("3.0.1-47.el9_1"));
+
+        let (other, version) = Component::from_purl("pkg:rpm/redhat/openssl-libs").unwrap();
+        assert_eq!(other, component);
+        assert_eq!(version, None);
+
+        let (other, _) = Component::from_purl("pkg:rpm/fedora/openssl-libs@3.0.7").unwrap();
+        assert_ne!(other.id, component.id);
+
+        assert_eq!(Component::from_purl("openssl-libs"), None);
+    }
+
+    #[test]
+    fn stable_id() {
+        assert_eq!(component_id("pkg:cargo/serde"), component_id("pkg:cargo/serde"));
+        assert_eq!(
+            component_id("pkg:cargo/serde"),
+            &sha256::digest("pkg:cargo/serde")[..16]
+        );
+        assert!(!is_component_id("pkg:cargo/serde"));
+        assert!(!is_component_id("0123456789ABCDEF"));
+    }
+
+    #[test]
+    fn versions() {
+        let mut versions = vec!["1.10.0", "1.9.2", "1.9.2-1.el9", "1.9.2-beta", "1.09.1", "2", "1.9"];
+        versions.sort_by(|a, b| compare_versions(a, b));
+        assert_eq!(
+            versions,
+            vec!["1.9", "1.09.1", "1.9.2", "1.9.2-beta", "1.9.2-1.el9", "1.10.0", "2"]
+        );
+        assert_eq!(compare_versions("1.0", "1_0"), Ordering::Equal);
+    }
+}
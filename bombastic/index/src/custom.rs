@@ -0,0 +1,207 @@
+//! Extraction of custom identifiers of vendors.
+//!
+//! Vendors embed identifiers of their own in SBOMs, like internal part numbers, using the properties of CycloneDX
+//! documents, or the annotations and comments of SPDX documents. Rules, configured in a YAML file, extract these
+//! values, which the SBOM index stores in the `custom_id` field, searchable using the `custom:` qualifier.
+//!
+//! A `property` rule takes the values of all CycloneDX properties of that name, the ones of the document as well as
+//! the ones of any component. A `path` rule takes the values selected by a JSONPath expression, working for any format.
+//!
+//! ```yaml
+//! rules:
+//!   - property: "acme:part-number"
+//!   - path: "$.packages[*].annotations[?(@.annotator == 'Tool: acme-scanner')].comment"
+//! ```
+
+use jsonpath_rust::{parser::model::JsonPath, path::json_path_instance, JsonPathValue};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
+use trustification_index::Error as SearchError;
+
+#[derive(Debug, Deserialize)]
+struct Config {
+    rules: Vec<RuleConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RuleConfig {
+    Property { property: String },
+    Path { path: String },
+}
+
+enum Rule {
+    Property(String),
+    Path(JsonPath),
+}
+
+/// The rules extracting custom identifiers from SBOMs. Without rules, nothing is extracted.
+#[derive(Default)]
+pub struct CustomIds {
+    rules: Vec<Rule>,
+}
+
+impl CustomIds {
+    /// Load the rules of the configuration file, if there is one.
+    pub fn load(path: Option<&Path>) -> Result<Self, SearchError> {
+        match path {
+            Some(path) => Self::from_file(path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Load the rules of a configuration file.
+    pub fn from_file(path: &Path) -> Result<Self, SearchError> {
+        let config = std::fs::read_to_string(path).map_err(SearchError::Io)?;
+        let ids = Self::from_yaml(&config)?;
+        log::info!(
+            "Loaded {} rules extracting custom identifiers from {}",
+            ids.rules.len(),
+            path.display()
+        );
+        Ok(ids)
+    }
+
+    /// Create the rules of a YAML configuration.
+    pub fn from_yaml(config: &str) -> Result<Self, SearchError> {
+        let invalid = |e: String| SearchError::Enrichment(format!("invalid custom identifier rules: {e}"));
+        let config: Config = serde_yaml::from_str(config).map_err(|e| invalid(e.to_string()))?;
+        let rules = config
+            .rules
+            .into_iter()
+            .map(|rule| match rule {
+                RuleConfig::Property { property } => Ok(Rule::Property(property)),
+                RuleConfig::Path { path } => jsonpath_rust::parser::parser::parse_json_path(&path)
+                    .map(Rule::Path)
+                    .map_err(|e| invalid(format!("'{path}': {e}"))),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Extract the custom identifiers of an SBOM, sorted and without duplicates.
+    ///
+    /// The data is only parsed if there are rules.
+    pub fn extract(&self, data: &[u8]) -> Result<Vec<String>, SearchError> {
+        if self.rules.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let value: Value = serde_json::from_slice(data).map_err(|e| SearchError::DocParser(e.to_string()))?;
+        let mut ids = BTreeSet::new();
+        for rule in &self.rules {
+            match rule {
+                Rule::Property(name) => collect_properties(&value, name, &mut ids),
+                Rule::Path(path) => {
+                    for found in json_path_instance(path, &value).find(JsonPathValue::from_root(&value)) {
+                        match found {
+                            JsonPathValue::Slice(data, _) => add(data, &mut ids),
+                            JsonPathValue::NewValue(data) => add(&data, &mut ids),
+                            JsonPathValue::NoValue => {}
+                        }
+                    }
+                }
+            }
+        }
+        Ok(ids.into_iter().collect())
+    }
+}
+
+/// Collect the values of the CycloneDX properties of a name, at any depth.
+fn collect_properties(value: &Value, name: &str, ids: &mut BTreeSet<String>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                if let ("properties", Value::Array(properties)) = (key.as_str(), value) {
+                    properties
+                        .iter()
+                        .filter(|property| property.get("name").and_then(Value::as_str) == Some(name))
+                        .filter_map(|property| property.get("value"))
+                        .for_each(|value| add(value, ids));
+                }
+                collect_properties(value, name, ids);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                collect_properties(value, name, ids);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Add a scalar value as an identifier, ignoring empty ones.
+fn add(value: &Value, ids: &mut BTreeSet<String>) {
+    let id = match value {
+        Value::String(value) => value.trim().to_string(),
+        Value::Number(value) => value.to_string(),
+        _ => return,
+    };
+    if !id.is_empty() {
+        ids.insert(id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+rules:
+  - property: "acme:part-number"
+  - path: "$.packages[*].annotations[?(@.annotator == 'Tool: acme-scanner')].comment"
+"#;
+
+    #[test]
+    fn cyclonedx_properties() {
+        let ids = CustomIds::from_yaml(CONFIG).unwrap();
+        let data = br#"{
+            "bomFormat": "CycloneDX",
+            "metadata": {
+                "component": {
+                    "name": "app",
+                    "properties": [{"name": "acme:part-number", "value": "PN-100"}]
+                }
+            },
+            "components": [
+                {"name": "lib", "properties": [
+                    {"name": "acme:part-number", "value": "PN-200"},
+                    {"name": "other", "value": "ignored"}
+                ]},
+                {"name": "nested", "components": [
+                    {"name": "deep", "properties": [{"name": "acme:part-number", "value": "PN-100"}]}
+                ]}
+            ]
+        }"#;
+        assert_eq!(ids.extract(data).unwrap(), vec!["PN-100", "PN-200"]);
+    }
+
+    #[test]
+    fn spdx_annotations() {
+        let ids = CustomIds::from_yaml(CONFIG).unwrap();
+        let data = br#"{
+            "spdxVersion": "SPDX-2.3",
+            "packages": [
+                {"SPDXID": "SPDXRef-a", "annotations": [
+                    {"annotator": "Tool: acme-scanner", "comment": "PN-300"},
+                    {"annotator": "Person: Jane", "comment": "looks good"}
+                ]},
+                {"SPDXID": "SPDXRef-b"}
+            ]
+        }"#;
+        assert_eq!(ids.extract(data).unwrap(), vec!["PN-300"]);
+    }
+
+    #[test]
+    fn rules() {
+        // without rules, documents aren't even parsed
+        assert!(CustomIds::default().extract(b"not json").unwrap().is_empty());
+        assert!(CustomIds::from_yaml(CONFIG).unwrap().extract(b"not json").is_err());
+
+        assert!(CustomIds::from_yaml("rules:\n  - path: not a path\n").is_err());
+        assert!(CustomIds::from_yaml("rules:\n  - unknown: value\n").is_err());
+    }
+}
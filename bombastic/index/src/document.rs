@@ -0,0 +1,398 @@
+//! Parsing of SBOMs for indexing.
+//!
+//! Large SPDX documents are dominated by data which is not indexed, like files, snippets, relationships and the
+//! texts of extracted licenses. Instead of the full SPDX model, SPDX documents are parsed into [`Spdx`], which only
+//! keeps the fields needed for indexing, skipping everything else while parsing. Relationships are processed one at
+//! a time, keeping only those declaring what the document describes, and the dependency and provenance links between
+//! packages. This bounds the memory required per document to (roughly) the size of its raw data, allowing larger SBOMs
+//! to be indexed on small pods.
+//!
+//! Files are only kept when explicitly requested, using [`ParsedSbom::parse_with_files`], keeping just their names and
+//! SHA-256 digests.
+//!
+//! CycloneDX documents are parsed using the full model. Their external references are collected in a second pass,
+//! keeping just their type and URL as [`ExternalReference`].
+
+use bombastic_model::prelude::{EdgeType, SBOM};
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use spdx_rs::models::{Checksum, ExternalPackageReference};
+use std::fmt::Formatter;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use trustification_index::Error as SearchError;
+
+/// An SBOM, as required for indexing.
+#[derive(Debug)]
+pub enum ParsedSbom {
+    Spdx(Spdx),
+    CycloneDX(cyclonedx_bom::prelude::Bom, ExternalReferences),
+}
+
+impl ParsedSbom {
+    /// Parse an SBOM, using the partial SPDX model for SPDX documents.
+    pub fn parse(data: &[u8]) -> Result<Self, SearchError> {
+        match serde_json::from_slice::<Spdx>(data) {
+            Ok(spdx) => return Ok(Self::Spdx(spdx)),
+            Err(err) => log::debug!("Not an SPDX document, or unable to parse it partially: {err}"),
+        }
+
+        // not (partially) parsable as SPDX, the full parse provides the errors of all formats
+        match SBOM::parse(data) {
+            Ok(SBOM::CycloneDX(bom)) => {
+                let references = serde_json::from_slice::<CycloneDxReferences>(data)
+                    .map_err(|err| SearchError::DocParser(err.to_string()))?;
+                Ok(Self::CycloneDX(bom, references.into()))
+            }
+            // the full model accepted what the partial one rejected, which should not happen
+            Ok(SBOM::SPDX(_)) => Err(SearchError::DocParser("unable to parse SPDX document".into())),
+            Err(err) => Err(SearchError::DocParser(err.to_string())),
+        }
+    }
+
+    /// Parse an SBOM, like [`Self::parse`], additionally keeping the files of SPDX documents.
+    ///
+    /// The files are parsed in a second pass over the data, so documents are not parsed any differently.
+    pub fn parse_with_files(data: &[u8]) -> Result<Self, SearchError> {
+        let mut sbom = Self::parse(data)?;
+        if let Self::Spdx(spdx) = &mut sbom {
+            spdx.file_information = serde_json::from_slice::<SpdxFiles>(data)
+                .map_err(|err| SearchError::DocParser(err.to_string()))?
+                .files;
+        }
+        Ok(sbom)
+    }
+}
+
+/// The parts of an SPDX document which are indexed.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Spdx {
+    pub spdx_version: String,
+    #[serde(rename = "name")]
+    pub document_name: String,
+    #[serde(rename = "documentNamespace")]
+    pub spdx_document_namespace: String,
+    pub creation_info: CreationInfo,
+    #[serde(default)]
+    document_describes: Vec<String>,
+    #[serde(default, rename = "packages")]
+    pub package_information: Vec<Package>,
+    /// The relationships which are indexed
+    #[serde(default, deserialize_with = "relationships")]
+    pub relationships: Relationships,
+    /// Files of the document, only present if parsed using [`ParsedSbom::parse_with_files`]
+    #[serde(skip)]
+    pub file_information: Vec<File>,
+}
+
+impl Spdx {
+    /// Whether the package is described by the document, rather than being one of its dependencies.
+    ///
+    /// The described packages are declared by `documentDescribes`, falling back to the `DESCRIBES` relationships of
+    /// the document if it doesn't declare any.
+    pub fn describes(&self, package: &Package) -> bool {
+        let described = match self.document_describes.is_empty() {
+            true => &self.relationships.described,
+            false => &self.document_describes,
+        };
+        described.contains(&package.package_spdx_identifier)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreationInfo {
+    #[serde(default)]
+    pub creators: Vec<String>,
+    #[serde(deserialize_with = "rfc3339")]
+    pub created: OffsetDateTime,
+}
+
+/// The parts of an SPDX package which are indexed.
+#[derive(Debug, Deserialize)]
+pub struct Package {
+    #[serde(rename = "SPDXID")]
+    pub package_spdx_identifier: String,
+    #[serde(rename = "name")]
+    pub package_name: String,
+    #[serde(default, rename = "versionInfo")]
+    pub package_version: Option<String>,
+    #[serde(default, rename = "summary")]
+    pub package_summary_description: Option<String>,
+    #[serde(default, rename = "supplier")]
+    pub package_supplier: Option<String>,
+    #[serde(default, rename = "licenseDeclared")]
+    pub declared_license: Option<String>,
+    #[serde(default, rename = "checksums")]
+    pub package_checksum: Vec<Checksum>,
+    #[serde(default, rename = "externalRefs")]
+    pub external_reference: Vec<ExternalPackageReference>,
+}
+
+/// The files of an SPDX document, skipping everything else.
+#[derive(Deserialize)]
+struct SpdxFiles {
+    #[serde(default)]
+    files: Vec<File>,
+}
+
+/// The parts of an SPDX file which are indexed.
+#[derive(Debug, Deserialize)]
+pub struct File {
+    #[serde(rename = "fileName")]
+    pub file_name: String,
+    #[serde(default, rename = "checksums")]
+    pub file_checksum: Vec<Checksum>,
+}
+
+/// An external reference of a CycloneDX document or component, like the repository of its sources.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct ExternalReference {
+    /// The type of the reference, like `vcs`, `build-system` or `distribution`
+    #[serde(rename = "type")]
+    pub category: String,
+    pub url: String,
+}
+
+/// The external references of a CycloneDX document.
+#[derive(Debug, Default)]
+pub struct ExternalReferences {
+    /// References of the document and the component it describes
+    pub document: Vec<ExternalReference>,
+    /// References of all other components, including nested ones
+    pub components: Vec<ExternalReference>,
+}
+
+impl ExternalReferences {
+    /// All references, of the document and its components.
+    pub fn iter(&self) -> impl Iterator<Item = &ExternalReference> {
+        self.document.iter().chain(self.components.iter())
+    }
+}
+
+/// The external references of a CycloneDX document, skipping everything else.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CycloneDxReferences {
+    #[serde(default)]
+    external_references: Vec<ExternalReference>,
+    #[serde(default)]
+    metadata: Option<CycloneDxMetadata>,
+    #[serde(default)]
+    components: Vec<CycloneDxComponent>,
+}
+
+#[derive(Deserialize)]
+struct CycloneDxMetadata {
+    #[serde(default)]
+    component: Option<CycloneDxComponent>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CycloneDxComponent {
+    #[serde(default)]
+    external_references: Vec<ExternalReference>,
+    #[serde(default)]
+    components: Vec<CycloneDxComponent>,
+}
+
+impl CycloneDxComponent {
+    fn collect(self, references: &mut Vec<ExternalReference>) {
+        references.extend(self.external_references);
+        for component in self.components {
+            component.collect(references);
+        }
+    }
+}
+
+impl From<CycloneDxReferences> for ExternalReferences {
+    fn from(value: CycloneDxReferences) -> Self {
+        let mut document = value.external_references;
+        if let Some(component) = value.metadata.and_then(|metadata| metadata.component) {
+            document.extend(component.external_references);
+        }
+
+        let mut components = Vec::new();
+        for component in value.components {
+            component.collect(&mut components);
+        }
+
+        Self { document, components }
+    }
+}
+
+/// The relationships of an SPDX document which are indexed.
+#[derive(Debug, Default)]
+pub struct Relationships {
+    /// Elements described by `DESCRIBES` relationships of the document
+    described: Vec<String>,
+    /// Provenance links between elements, like `VARIANT_OF` and `GENERATED_FROM`, as source, type and target
+    pub provenance: Vec<(String, EdgeType, String)>,
+    /// Dependency links between elements, like `DEPENDS_ON` and `CONTAINS`, as dependent and dependency
+    pub dependencies: Vec<(String, String)>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Relationship {
+    spdx_element_id: String,
+    relationship_type: String,
+    related_spdx_element: String,
+}
+
+/// Collect the relationships which are indexed, processing one relationship at a time.
+fn relationships<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Relationships, D::Error> {
+    struct Collect;
+
+    impl<'de> Visitor<'de> for Collect {
+        type Value = Relationships;
+
+        fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+            f.write_str("a list of relationships")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut relationships = Relationships::default();
+            while let Some(relationship) = seq.next_element::<Relationship>()? {
+                if relationship.spdx_element_id == "SPDXRef-DOCUMENT" && relationship.relationship_type == "DESCRIBES" {
+                    relationships.described.push(relationship.related_spdx_element);
+                    continue;
+                }
+                let edge = EdgeType::from_spdx(
+                    &relationship.relationship_type,
+                    &relationship.spdx_element_id,
+                    &relationship.related_spdx_element,
+                );
+                match edge {
+                    Some((source, edge_type, target)) if edge_type.is_provenance() => {
+                        relationships
+                            .provenance
+                            .push((source.to_string(), edge_type, target.to_string()));
+                    }
+                    Some((source, _, target)) => {
+                        relationships
+                            .dependencies
+                            .push((source.to_string(), target.to_string()));
+                    }
+                    None => {}
+                }
+            }
+            Ok(relationships)
+        }
+    }
+
+    deserializer.deserialize_seq(Collect)
+}
+
+fn rfc3339<'de, D: Deserializer<'de>>(deserializer: D) -> Result<OffsetDateTime, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    OffsetDateTime::parse(&value, &Rfc3339).map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_spdx() {
+        let data = br#"{
+            "spdxVersion": "SPDX-2.3",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": "example",
+            "documentNamespace": "https://example.com/example",
+            "creationInfo": {"creators": ["Tool: example"], "created": "2023-06-01T10:00:00Z"},
+            "packages": [
+                {"SPDXID": "SPDXRef-main", "name": "main", "versionInfo": "1.0", "licenseDeclared": "Apache-2.0"},
+                {"SPDXID": "SPDXRef-dep", "name": "dep", "filesAnalyzed": false, "externalRefs": [
+                    {"referenceCategory": "PACKAGE_MANAGER", "referenceType": "purl", "referenceLocator": "pkg:npm/dep@1"}
+                ]}
+            ],
+            "files": [{"SPDXID": "SPDXRef-file", "fileName": "./a", "checksums": []}],
+            "relationships": [
+                {"spdxElementId": "SPDXRef-main", "relationshipType": "DEPENDS_ON", "relatedSpdxElement": "SPDXRef-dep"},
+                {"spdxElementId": "SPDXRef-dep", "relationshipType": "GENERATES", "relatedSpdxElement": "SPDXRef-main"},
+                {"spdxElementId": "SPDXRef-DOCUMENT", "relationshipType": "DESCRIBES", "relatedSpdxElement": "SPDXRef-main"}
+            ]
+        }"#;
+
+        let ParsedSbom::Spdx(spdx) = ParsedSbom::parse(data).unwrap() else {
+            panic!("not parsed as SPDX");
+        };
+        assert_eq!(spdx.document_name, "example");
+        assert_eq!(spdx.creation_info.created.year(), 2023);
+        assert_eq!(spdx.package_information.len(), 2);
+        assert_eq!(
+            spdx.package_information[1].external_reference[0].reference_locator,
+            "pkg:npm/dep@1"
+        );
+
+        // described by relationship, as there is no `documentDescribes`
+        assert!(spdx.describes(&spdx.package_information[0]));
+        assert!(!spdx.describes(&spdx.package_information[1]));
+        assert!(spdx.file_information.is_empty());
+        assert_eq!(
+            spdx.relationships.provenance,
+            vec![(
+                "SPDXRef-main".to_string(),
+                EdgeType::GeneratedFrom,
+                "SPDXRef-dep".to_string()
+            )]
+        );
+        assert_eq!(
+            spdx.relationships.dependencies,
+            vec![("SPDXRef-main".to_string(), "SPDXRef-dep".to_string())]
+        );
+
+        let ParsedSbom::Spdx(spdx) = ParsedSbom::parse_with_files(data).unwrap() else {
+            panic!("not parsed as SPDX");
+        };
+        assert_eq!(spdx.file_information.len(), 1);
+        assert_eq!(spdx.file_information[0].file_name, "./a");
+    }
+
+    #[test]
+    fn cyclonedx() {
+        let data = std::fs::read("../testdata/my-sbom.json").unwrap();
+        let Ok(ParsedSbom::CycloneDX(_, references)) = ParsedSbom::parse(&data) else {
+            panic!("not parsed as CycloneDX");
+        };
+        assert!(references.document.is_empty());
+        assert!(references.components.contains(&ExternalReference {
+            category: "vcs".to_string(),
+            url: "https://github.com/quarkusio/quarkus".to_string(),
+        }));
+    }
+
+    #[test]
+    fn cyclonedx_references() {
+        let references: CycloneDxReferences = serde_json::from_value(serde_json::json!({
+            "externalReferences": [{"type": "build-system", "url": "https://ci.example.com/42"}],
+            "metadata": {"component": {"name": "main", "externalReferences": [
+                {"type": "vcs", "url": "https://example.com/main.git", "comment": "sources"}
+            ]}},
+            "components": [{"name": "dep", "components": [
+                {"name": "nested", "externalReferences": [{"type": "distribution", "url": "https://example.com/nested"}]}
+            ]}]
+        }))
+        .unwrap();
+
+        let references = ExternalReferences::from(references);
+        assert_eq!(
+            references
+                .document
+                .iter()
+                .map(|r| r.category.as_str())
+                .collect::<Vec<_>>(),
+            vec!["build-system", "vcs"]
+        );
+        assert_eq!(references.components.len(), 1);
+        assert_eq!(references.components[0].url, "https://example.com/nested");
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(ParsedSbom::parse(br#"{"spdxVersion": "SPDX-2.3"}"#).is_err());
+    }
+}
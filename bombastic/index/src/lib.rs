@@ -1,2 +1,7 @@
+pub mod component;
+pub mod custom;
+pub mod document;
 pub mod packages;
 pub mod sbom;
+pub mod scorecard;
+pub mod supplier;
@@ -1,3 +1,6 @@
+use crate::document::{Package, ParsedSbom, Spdx};
+use crate::scorecard::{self, Scorecards};
+use crate::supplier::{self, create_supplier_query};
 use bombastic_model::prelude::*;
 use core::str::FromStr;
 use cyclonedx_bom::models::{
@@ -7,26 +10,31 @@ use cyclonedx_bom::models::{
 use log::{debug, trace, warn};
 use sikula::{mir::Direction, prelude::*};
 use spdx_rs::models::Algorithm;
+use std::sync::Arc;
 use time::OffsetDateTime;
-use trustification_api::search::SearchOptions;
+use trustification_api::search::{SearchField, SearchOptions};
 use trustification_index::{
-    boost, create_date_query, create_string_query, field2str,
+    boost, create_date_query, create_float_query, create_pattern_query, create_purl_pattern_query, create_string_query,
+    field2str,
     metadata::doc2metadata,
+    search_field,
     tantivy::{
         self,
         collector::TopDocs,
         doc,
         query::{AllQuery, BooleanQuery, Query, TermQuery, TermSetQuery},
-        schema::{Field, Schema, Term, FAST, STORED, STRING, TEXT},
+        schema::{Field, Schema, Term, FAST, INDEXED, STORED, STRING, TEXT},
         store::ZstdCompressor,
         DateTime, DocAddress, DocId, IndexSettings, Order, Score, Searcher, SegmentReader,
     },
-    term2query, Document, Error as SearchError, SearchQuery,
+    try_term2query, Document, Error as SearchError, SearchQuery,
 };
 
 pub struct Index {
     schema: Schema,
     fields: Fields,
+    /// Scorecard results of the source repositories of packages, if they are collected
+    scorecards: Option<Arc<Scorecards>>,
 }
 
 pub struct Fields {
@@ -37,6 +45,7 @@ pub struct Fields {
     purl: Field,
     license: Field,
     supplier: Field,
+    supplier_normalized: Field,
     classifier: Field,
     sha256: Field,
     purl_type: Field,
@@ -45,6 +54,8 @@ pub struct Fields {
     purl_version: Field,
     purl_qualifiers: Field,
     purl_qualifiers_values: Field,
+    repository: Field,
+    scorecard: Field,
 }
 
 impl Default for Index {
@@ -64,6 +75,7 @@ impl Index {
             desc: schema.add_text_field("package_desc", TEXT | STORED),
             license: schema.add_text_field("package_license", TEXT | STORED),
             supplier: schema.add_text_field("package_supplier", STRING | STORED),
+            supplier_normalized: schema.add_text_field("package_supplier_normalized", STRING),
             classifier: schema.add_text_field("package_classifier", STRING | STORED),
             sha256: schema.add_text_field("package_sha256", STRING | STORED),
             purl_type: schema.add_text_field("package_url_type", STRING | STORED),
@@ -72,24 +84,46 @@ impl Index {
             purl_version: schema.add_text_field("package_url_version", STRING | STORED),
             purl_qualifiers: schema.add_text_field("package_url_qualifiers", STRING | STORED),
             purl_qualifiers_values: schema.add_text_field("package_url_qualifiers_values", STRING | STORED),
+            repository: schema.add_text_field("package_repository", STRING | STORED),
+            scorecard: schema.add_f64_field("package_scorecard", FAST | INDEXED | STORED),
         };
         Self {
             schema: schema.build(),
             fields,
+            scorecards: None,
         }
     }
 
-    fn index_spdx(&self, bom: &spdx_rs::models::SPDX, sha256: &str) -> Result<Vec<(String, Document)>, SearchError> {
+    /// Add the OpenSSF Scorecard scores of the source repositories of packages, as far as they are known.
+    pub fn with_scorecards(mut self, scorecards: Arc<Scorecards>) -> Self {
+        self.scorecards = Some(scorecards);
+        self
+    }
+
+    /// Index the source repository of a package, and its score if known.
+    fn index_repository(document: &mut Document, fields: &Fields, scorecards: Option<&Scorecards>, purl: &str) {
+        let Some(repository) = scorecard::repository(purl) else {
+            return;
+        };
+        if let Some(score) = scorecards.and_then(|scorecards| scorecards.score(&repository)) {
+            document.add_f64(fields.scorecard, score);
+        }
+        document.add_text(fields.repository, repository);
+    }
+
+    fn index_spdx(&self, bom: &Spdx, sha256: &str) -> Result<Vec<(String, Document)>, SearchError> {
         debug!("Indexing Package from SPDX document");
         let mut documents: Vec<(String, Document)> = Vec::new();
 
         for package in &bom.package_information {
-            if !bom
-                .document_creation_information
-                .document_describes
-                .contains(&package.package_spdx_identifier)
-            {
-                Self::index_spdx_package(&mut documents, package, &self.fields, sha256);
+            if !bom.describes(package) {
+                Self::index_spdx_package(
+                    &mut documents,
+                    package,
+                    &self.fields,
+                    self.scorecards.as_deref(),
+                    sha256,
+                );
             }
         }
         trace!("Indexed {:?}", documents);
@@ -98,8 +132,9 @@ impl Index {
 
     fn index_spdx_package(
         documents: &mut Vec<(String, Document)>,
-        package: &spdx_rs::models::PackageInformation,
+        package: &Package,
         fields: &Fields,
+        scorecards: Option<&Scorecards>,
         sha256: &str,
     ) {
         for r in package.external_reference.iter() {
@@ -131,6 +166,7 @@ impl Index {
 
                     document.add_text(fields.purl_type, package.ty());
                 }
+                Self::index_repository(&mut document, fields, scorecards, &purl);
                 document.add_text(fields.purl, &package_id);
                 document.add_text(fields.name, &package.package_name);
                 if let Some(version) = &package.package_version {
@@ -144,11 +180,14 @@ impl Index {
                 }
 
                 if let Some(license) = &package.declared_license {
-                    document.add_text(fields.license, license.to_string());
+                    document.add_text(fields.license, license);
                 }
 
                 if let Some(supplier) = &package.package_supplier {
                     document.add_text(fields.supplier, supplier);
+                    if let Some(normalized) = supplier::normalize(supplier) {
+                        document.add_text(fields.supplier_normalized, normalized);
+                    }
                 }
 
                 // Only add packages with purls
@@ -168,7 +207,13 @@ impl Index {
 
         if let Some(components) = &bom.components {
             for component in components.0.iter() {
-                Self::index_cyclonedx_component(&mut documents, component, &self.fields, sha256);
+                Self::index_cyclonedx_component(
+                    &mut documents,
+                    component,
+                    &self.fields,
+                    self.scorecards.as_deref(),
+                    sha256,
+                );
             }
         }
 
@@ -179,6 +224,7 @@ impl Index {
         documents: &mut Vec<(String, Document)>,
         component: &cyclonedx_bom::prelude::Component,
         fields: &Fields,
+        scorecards: Option<&Scorecards>,
         sha256: &str,
     ) {
         let mut document = doc!();
@@ -218,6 +264,7 @@ impl Index {
                 }
                 document.add_text(fields.purl_type, package.ty());
             }
+            Self::index_repository(&mut document, fields, scorecards, &purl);
         }
 
         if let Some(desc) = &component.description {
@@ -244,11 +291,11 @@ impl Index {
         }
     }
 
-    fn resource2query(&self, resource: &PackageInfo) -> Box<dyn Query> {
+    fn resource2query(&self, resource: &PackageInfo) -> Result<Box<dyn Query>, SearchError> {
         // const PACKAGE_WEIGHT: f32 = 1.5;
         const CREATED_WEIGHT: f32 = 1.25;
-        match resource {
-            PackageInfo::Purl(value) => self.create_string_query(&[self.fields.purl], value),
+        Ok(match resource {
+            PackageInfo::Purl(value) => self.create_purl_pattern_query(&[self.fields.purl], value)?,
 
             PackageInfo::Type(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
                 self.fields.purl_type,
@@ -268,14 +315,16 @@ impl Index {
                 value,
             )])),
 
-            PackageInfo::Supplier(primary) => self.create_string_query(&[self.fields.supplier], primary),
+            PackageInfo::Supplier(primary) => {
+                create_supplier_query(self.fields.supplier, self.fields.supplier_normalized, primary)
+            }
 
             PackageInfo::Created(ordered) => boost(
                 create_date_query(&self.schema, self.fields.indexed_timestamp, ordered),
                 CREATED_WEIGHT,
             ),
 
-            PackageInfo::Name(value) => self.create_string_query(&[self.fields.purl_name], value),
+            PackageInfo::Name(value) => self.create_pattern_query(&[self.fields.purl_name], value)?,
             PackageInfo::Namespace(value) => self.create_string_query(&[self.fields.purl_namespace], value),
 
             PackageInfo::Qualifier(value) => {
@@ -285,13 +334,33 @@ impl Index {
                     Default::default(),
                 ))
             }
-        }
+
+            PackageInfo::Repository(value) => self.create_pattern_query(&[self.fields.repository], value)?,
+            PackageInfo::Scorecard(ordered) => create_float_query(&self.schema, [self.fields.scorecard], ordered),
+        })
     }
 
     fn create_string_query(&self, fields: &[Field], value: &Primary<'_>) -> Box<dyn Query> {
         let queries: Vec<Box<dyn Query>> = fields.iter().map(|f| create_string_query(*f, value)).collect();
         Box::new(BooleanQuery::union(queries))
     }
+
+    fn create_pattern_query(&self, fields: &[Field], value: &Primary<'_>) -> Result<Box<dyn Query>, SearchError> {
+        let queries = fields
+            .iter()
+            .map(|f| create_pattern_query(*f, value))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Box::new(BooleanQuery::union(queries)))
+    }
+
+    /// Like [`Self::create_pattern_query`], for values which are Package URLs.
+    fn create_purl_pattern_query(&self, fields: &[Field], value: &Primary<'_>) -> Result<Box<dyn Query>, SearchError> {
+        let queries = fields
+            .iter()
+            .map(|f| create_purl_pattern_query(*f, value))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Box::new(BooleanQuery::union(queries)))
+    }
 }
 
 impl trustification_index::Index for Index {
@@ -320,7 +389,7 @@ impl trustification_index::Index for Index {
         let query = if query.term.is_empty() {
             Box::new(AllQuery)
         } else {
-            term2query(&query.term, &|resource| self.resource2query(resource))
+            try_term2query(&query.term, &|resource| self.resource2query(resource))?
         };
 
         log::trace!("Processed query: {:?}", query);
@@ -437,6 +506,13 @@ impl trustification_index::Index for Index {
             .map(|s| s.as_text().unwrap_or(""))
             .unwrap_or("");
 
+        let repository = doc
+            .get_first(self.fields.repository)
+            .and_then(|s| s.as_text())
+            .map(ToString::to_string);
+
+        let scorecard = doc.get_first(self.fields.scorecard).and_then(|s| s.as_f64());
+
         let document = SearchPackageDocument {
             version: version.to_string(),
             purl: purl.to_string(),
@@ -452,6 +528,8 @@ impl trustification_index::Index for Index {
             purl_qualifiers: purl_qualifiers.to_string(),
             description: description.to_string(),
             purl_qualifiers_values: purl_qualifiers_values.to_string(),
+            repository,
+            scorecard,
         };
 
         let explanation: Option<serde_json::Value> = if options.explain {
@@ -475,20 +553,67 @@ impl trustification_index::Index for Index {
             metadata,
         })
     }
+
+    fn search_fields(&self) -> Vec<SearchField> {
+        let field = |qualifier: &str, fields: &[Field], description: &str| {
+            search_field(&self.schema, qualifier, fields, description, |q| {
+                PackageInfo::parse(q).is_ok()
+            })
+        };
+        let f = &self.fields;
+
+        let mut qualifier = field(
+            "qualifier",
+            &[f.purl_qualifiers],
+            "Package URL qualifier of the package",
+        );
+        qualifier.example = "qualifier:arch:x86_64".to_string();
+
+        vec![
+            field(
+                "purl",
+                &[f.purl],
+                "Package URL of the package, supporting wildcard (*) and regular expression (/regex/) patterns",
+            ),
+            field("type", &[f.purl_type], "Package URL type of the package"),
+            field("version", &[f.version, f.purl_version], "Version of the package"),
+            field(
+                "name",
+                &[f.purl_name],
+                "Package URL name of the package, supporting patterns like purl",
+            ),
+            field("namespace", &[f.purl_namespace], "Package URL namespace of the package"),
+            field("created", &[f.indexed_timestamp], "Time the package was indexed"),
+            field("supplier", &[f.supplier], "Supplier of the package"),
+            field("license", &[f.license], "License of the package"),
+            field("description", &[f.desc], "Description of the package"),
+            field(
+                "repository",
+                &[f.repository],
+                "Source repository of the package, like github.com/ossf/scorecard, supporting patterns like purl",
+            ),
+            field(
+                "scorecard",
+                &[f.scorecard],
+                "OpenSSF Scorecard score of the source repository of the package, from 0 to 10",
+            ),
+            qualifier,
+        ]
+    }
 }
 
 impl trustification_index::WriteIndex for Index {
-    type Document = (SBOM, String);
+    type Document = (ParsedSbom, String, Vec<String>);
 
     fn name(&self) -> &str {
         "package"
     }
 
     #[allow(unused_variables)]
-    fn index_doc(&self, _id: &str, (doc, sha256): &Self::Document) -> Result<Vec<(String, Document)>, SearchError> {
+    fn index_doc(&self, _id: &str, (doc, sha256, _): &Self::Document) -> Result<Vec<(String, Document)>, SearchError> {
         let doc = match doc {
-            SBOM::CycloneDX(bom) => self.index_cyclonedx(bom, sha256)?,
-            SBOM::SPDX(bom) => self.index_spdx(bom, sha256)?,
+            ParsedSbom::CycloneDX(bom, _) => self.index_cyclonedx(bom, sha256)?,
+            ParsedSbom::Spdx(bom) => self.index_spdx(bom, sha256)?,
         };
 
         Ok(doc)
@@ -496,9 +621,8 @@ impl trustification_index::WriteIndex for Index {
 
     fn parse_doc(&self, data: &[u8]) -> Result<Self::Document, SearchError> {
         let sha256 = sha256::digest(data);
-        SBOM::parse(data)
-            .map_err(|e| SearchError::DocParser(e.to_string()))
-            .map(|doc| (doc, sha256))
+        // custom identifiers are only indexed by the SBOM index
+        ParsedSbom::parse(data).map(|doc| (doc, sha256, Vec::new()))
     }
 
     fn schema(&self) -> Schema {
@@ -596,6 +720,11 @@ mod tests {
             let result = search(&index, "supplier:\"Organization: Red Hat\"");
             assert_eq!(result.0.len(), 617);
         });
+
+        assert_search(|index| {
+            let result = search(&index, "supplier:\"Red Hat Inc\"");
+            assert_eq!(result.0.len(), 617);
+        });
     }
 
     #[tokio::test]
@@ -634,4 +763,102 @@ mod tests {
             assert_eq!(result.0.len(), 30);
         });
     }
+
+    #[tokio::test]
+    async fn test_search_packages_by_scorecard() {
+        let scorecards = Arc::new(Scorecards::default());
+        scorecards.insert("github.com/ossf/scorecard".to_string(), Some(8.2));
+
+        let mut store = IndexStore::new_in_memory(Index::new().with_scorecards(scorecards.clone())).unwrap();
+        let mut writer = store.writer().unwrap();
+        let sbom = serde_json::json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.4",
+            "version": 1,
+            "components": [
+                {
+                    "type": "library",
+                    "name": "scorecard",
+                    "version": "v4.13.0",
+                    "purl": "pkg:golang/github.com/ossf/scorecard/v4@v4.13.0"
+                },
+                {
+                    "type": "library",
+                    "name": "net",
+                    "version": "v0.17.0",
+                    "purl": "pkg:golang/github.com/example/net@v0.17.0"
+                }
+            ]
+        });
+        writer
+            .add_document(store.index_as_mut(), "sbom", &serde_json::to_vec(&sbom).unwrap())
+            .unwrap();
+        writer.commit().unwrap();
+
+        let (hits, _) = search(&store, "scorecard:>7");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(
+            hits[0].document.repository.as_deref(),
+            Some("github.com/ossf/scorecard")
+        );
+        assert_eq!(hits[0].document.scorecard, Some(8.2));
+        assert_eq!(search(&store, "scorecard:<7").0.len(), 0);
+        assert_eq!(search(&store, r#"repository:"github.com/example/*""#).0.len(), 1);
+
+        // repositories without a result are queued to be fetched
+        assert_eq!(scorecards.take_pending(), ["github.com/example/net"]);
+    }
+
+    #[tokio::test]
+    async fn test_search_fields() {
+        assert_search(|index| {
+            for field in index.search_fields() {
+                search(&index, &field.example);
+            }
+        });
+    }
+
+    #[test]
+    fn test_search_fields_match_query_language() {
+        // the match is exhaustive, so a qualifier added to the query language fails to compile until it's listed here
+        macro_rules! qualifiers {
+            ($($variant:ident $(($value:pat))? => $qualifier:literal,)*) => {
+                fn qualifier(term: &PackageInfo) -> &'static str {
+                    match term {
+                        $(PackageInfo::$variant $(($value))? => $qualifier,)*
+                    }
+                }
+                const QUALIFIERS: &[&str] = &[$($qualifier),*];
+            };
+        }
+
+        qualifiers! {
+            Purl(_) => "purl",
+            Type(_) => "type",
+            Version(_) => "version",
+            Name(_) => "name",
+            Namespace(_) => "namespace",
+            Created(_) => "created",
+            Supplier(_) => "supplier",
+            License(_) => "license",
+            Description(_) => "description",
+            Qualifier(_) => "qualifier",
+            Repository(_) => "repository",
+            Scorecard(_) => "scorecard",
+        }
+
+        let fields = Index::new().search_fields();
+        for field in &fields {
+            let query = PackageInfo::parse(&field.example).unwrap();
+            match query.term {
+                sikula::prelude::Term::Match(term) => assert_eq!(qualifier(&term), field.qualifier),
+                term => panic!("unexpected example of {}: {term:?}", field.qualifier),
+            }
+        }
+
+        let described: Vec<_> = fields.iter().map(|field| field.qualifier.as_str()).collect();
+        for qualifier in QUALIFIERS {
+            assert!(described.contains(qualifier), "{qualifier} is not described");
+        }
+    }
 }
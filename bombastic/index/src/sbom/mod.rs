@@ -1,5 +1,10 @@
 use core::str::FromStr;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
+use crate::component::{compare_versions, Component};
+use crate::custom::CustomIds;
+use crate::document::{ExternalReference as DocumentReference, ExternalReferences, File, Package, ParsedSbom, Spdx};
+use crate::supplier::{self, create_supplier_query};
 use bombastic_model::prelude::*;
 use cyclonedx_bom::models::{
     component::Classification,
@@ -7,13 +12,17 @@ use cyclonedx_bom::models::{
     license::{LicenseChoice, LicenseIdentifier},
 };
 use log::{debug, warn};
-use sikula::{mir::Direction, prelude::*};
+use sikula::prelude::*;
 use spdx_rs::models::Algorithm;
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
-use trustification_api::search::SearchOptions;
+use trustification_api::search::{SearchField, SearchOptions};
 use trustification_index::{
-    boost, create_boolean_query, create_date_query, create_i64_query, create_string_query, field2str,
+    boost, create_boolean_query, create_date_query, create_i64_query, create_pattern_query, create_purl_pattern_query,
+    create_string_query,
+    export::{Column, ColumnType},
+    field2str, field2str_opt, field2strvec,
     metadata::doc2metadata,
+    search_field, search_predicate, sort_by,
     tantivy::{
         self,
         collector::TopDocs,
@@ -23,14 +32,18 @@ use trustification_index::{
         schema::INDEXED,
         schema::{Field, Schema, Term, FAST, STORED, STRING, TEXT},
         store::ZstdCompressor,
-        DateTime, DocAddress, DocId, IndexSettings, Order, Score, Searcher, SegmentReader, SnippetGenerator,
+        DateTime, DocAddress, DocId, IndexSettings, Score, Searcher, SegmentReader, SnippetGenerator,
     },
-    term2query, Document, Error as SearchError, SearchQuery,
+    try_term2query, Document, Error as SearchError, IndexStore, SearchQuery,
 };
 
 pub struct Index {
     schema: Schema,
     fields: Fields,
+    /// Whether the files of SPDX documents are indexed
+    files: bool,
+    /// The rules extracting custom identifiers of vendors
+    custom_ids: CustomIds,
 }
 
 pub struct PackageFields {
@@ -41,6 +54,7 @@ pub struct PackageFields {
     cpe: Field,
     license: Field,
     supplier: Field,
+    supplier_normalized: Field,
     classifier: Field,
     sha256: Field,
     purl_type: Field,
@@ -55,6 +69,25 @@ pub struct DepFields {
     purl: Field,
 }
 
+pub struct FileFields {
+    name: Field,
+    sha256: Field,
+}
+
+pub struct ReferenceFields {
+    vcs: Field,
+    build_system: Field,
+    distribution: Field,
+}
+
+/// The Package URLs of the packages linked by relationships.
+pub struct RelationshipFields {
+    variant_of: Field,
+    generated_from: Field,
+    /// the packages depending on other ones, once per dependency
+    dependent: Field,
+}
+
 struct Fields {
     indexed_timestamp: Field,
     /// the "storage id"
@@ -66,8 +99,30 @@ struct Fields {
     sbom_created: Field,
     sbom_creators: Field,
     sbom_name: Field,
+    /// the package URL types of all packages in the SBOM
+    sbom_ecosystem: Field,
+    /// the number of packages per package URL type
+    sbom_ecosystem_count: Field,
+    /// how the SBOM was ingested
+    sbom_source: Field,
+    /// labels added by the enrichment pipeline, like `owner:team-a`
+    sbom_labels: Field,
+    /// whether the stored SBOM was archived
+    sbom_archived: Field,
+    /// the external references of the SBOM and its main component
+    sbom_external_refs: Field,
+    /// the ids of the components of all packages in the SBOM
+    sbom_component: Field,
+    /// the versions of the components, as `<id> <canonical purl>@<version>`
+    sbom_component_version: Field,
+    /// custom identifiers of vendors, like internal part numbers
+    custom_id: Field,
     sbom: PackageFields,
     dep: DepFields,
+    file: FileFields,
+    /// the external references of the SBOM and all of its components, by category
+    ext_ref: ReferenceFields,
+    rel: RelationshipFields,
 }
 
 impl Default for Index {
@@ -87,17 +142,27 @@ impl Index {
             sbom_created: schema.add_date_field("sbom_created", INDEXED | FAST | STORED),
             sbom_creators: schema.add_text_field("sbom_creators", STRING | STORED),
             sbom_name: schema.add_text_field("sbom_name", STRING | FAST | STORED),
+            sbom_ecosystem: schema.add_text_field("sbom_ecosystem", STRING | FAST),
+            sbom_ecosystem_count: schema.add_json_field("sbom_ecosystem_count", STORED),
+            sbom_source: schema.add_text_field("sbom_source", STRING | STORED),
+            sbom_labels: schema.add_text_field("sbom_labels", STRING | STORED),
+            sbom_archived: schema.add_bool_field("sbom_archived", INDEXED | STORED),
+            sbom_external_refs: schema.add_json_field("sbom_external_refs", STORED),
+            sbom_component: schema.add_text_field("sbom_component", STRING | FAST),
+            sbom_component_version: schema.add_text_field("sbom_component_version", STORED),
+            custom_id: schema.add_text_field("custom_id", STRING | STORED),
             sbom: PackageFields {
                 name: schema.add_text_field("sbom_pkg_name", STRING | FAST | STORED),
                 version: schema.add_text_field("sbom_pkg_version", STRING | STORED),
                 purl: schema.add_text_field("sbom_pkg_purl", STRING | FAST | STORED),
                 desc: schema.add_text_field("sbom_pkg_desc", TEXT | STORED),
-                license: schema.add_text_field("sbom_pkg_license", TEXT | STORED),
+                license: schema.add_text_field("sbom_pkg_license", TEXT | FAST | STORED),
                 cpe: schema.add_text_field("sbom_pkg_cpe", STRING | FAST | STORED),
-                supplier: schema.add_text_field("sbom_pkg_supplier", STRING | STORED),
-                classifier: schema.add_text_field("sbom_pkg_classifier", STRING),
+                supplier: schema.add_text_field("sbom_pkg_supplier", STRING | FAST | STORED),
+                supplier_normalized: schema.add_text_field("sbom_pkg_supplier_normalized", STRING),
+                classifier: schema.add_text_field("sbom_pkg_classifier", STRING | FAST),
                 sha256: schema.add_text_field("sbom_pkg_sha256", STRING | STORED),
-                purl_type: schema.add_text_field("sbom_pkg_purl_type", STRING),
+                purl_type: schema.add_text_field("sbom_pkg_purl_type", STRING | FAST),
                 purl_name: schema.add_text_field("sbom_pkg_purl_name", FAST | STRING),
                 purl_namespace: schema.add_text_field("sbom_pkg_purl_namespace", STRING),
                 purl_version: schema.add_text_field("sbom_pkg_purl_version", STRING),
@@ -107,63 +172,93 @@ impl Index {
             dep: DepFields {
                 purl: schema.add_text_field("package_purl", FAST | STRING | STORED),
             },
+            file: FileFields {
+                name: schema.add_text_field("file_name", STRING),
+                sha256: schema.add_text_field("file_sha256", STRING),
+            },
+            ext_ref: ReferenceFields {
+                vcs: schema.add_text_field("ext_ref_vcs", STRING),
+                build_system: schema.add_text_field("ext_ref_build_system", STRING),
+                distribution: schema.add_text_field("ext_ref_distribution", STRING),
+            },
+            rel: RelationshipFields {
+                variant_of: schema.add_text_field("rel_variant_of", STRING),
+                generated_from: schema.add_text_field("rel_generated_from", STRING),
+                dependent: schema.add_text_field("rel_dependent", STRING),
+            },
         };
         Self {
             schema: schema.build(),
             fields,
+            files: false,
+            custom_ids: CustomIds::default(),
         }
     }
 
-    fn index_spdx(
-        &self,
-        id: &str,
-        bom: &spdx_rs::models::SPDX,
-        sha256: &str,
-    ) -> Result<Vec<(String, Document)>, SearchError> {
+    /// Index the names and digests of the files of SPDX documents.
+    ///
+    /// SBOMs listing files can be much larger than the ones only listing packages, so this is disabled by default.
+    pub fn with_files(mut self, files: bool) -> Self {
+        self.files = files;
+        self
+    }
+
+    /// Index the custom identifiers of vendors, extracted by the rules.
+    pub fn with_custom_ids(mut self, custom_ids: CustomIds) -> Self {
+        self.custom_ids = custom_ids;
+        self
+    }
+
+    fn index_spdx(&self, id: &str, bom: &Spdx, sha256: &str) -> Result<Vec<(String, Document)>, SearchError> {
         debug!("Indexing SPDX document");
         let mut documents: Vec<(String, Document)> = Vec::new();
         let mut document = doc!();
         document.add_text(self.fields.sbom_sha256, sha256);
         document.add_text(self.fields.sbom_id, id);
-        document.add_text(
-            self.fields.sbom_uid,
-            &bom.document_creation_information.spdx_document_namespace,
-        );
-        document.add_text(self.fields.sbom_name, &bom.document_creation_information.document_name);
+        document.add_text(self.fields.sbom_uid, &bom.spdx_document_namespace);
+        document.add_text(self.fields.sbom_name, &bom.document_name);
 
         let now = OffsetDateTime::now_utc();
         let nanos_since_epoch = now.unix_timestamp_nanos();
         let nanos_since_epoch_i64 = nanos_since_epoch as i64;
         document.add_i64(self.fields.indexed_timestamp, nanos_since_epoch_i64);
 
-        for creators in &bom.document_creation_information.creation_info.creators {
+        for creators in &bom.creation_info.creators {
             document.add_text(self.fields.sbom_creators, creators);
         }
 
-        let created = &bom.document_creation_information.creation_info.created;
-        document.add_date(
-            self.fields.sbom_created,
-            DateTime::from_timestamp_millis(created.timestamp_millis()),
-        );
+        document.add_date(self.fields.sbom_created, DateTime::from_utc(bom.creation_info.created));
 
+        let mut ecosystems = Ecosystems::default();
+        let mut components = Components::default();
         for package in &bom.package_information {
-            if bom
-                .document_creation_information
-                .document_describes
-                .contains(&package.package_spdx_identifier)
-            {
+            if let Some(purl) = package.external_reference.iter().find(|r| r.reference_type == "purl") {
+                ecosystems.add(&purl.reference_locator);
+                components.add(&purl.reference_locator);
+            }
+
+            if bom.describes(package) {
                 debug!("Indexing SBOM {} with name {}", id, package.package_name);
                 Self::index_spdx_package(&mut document, package, &self.fields.sbom);
             } else {
                 Self::index_spdx_dep(&mut document, package, &self.fields.dep);
             }
         }
+        ecosystems.index(&mut document, &self.fields);
+        components.index(&mut document, &self.fields);
+        Self::index_spdx_relationships(&mut document, bom, &self.fields.rel);
+
+        if self.files {
+            for file in &bom.file_information {
+                Self::index_spdx_file(&mut document, file, &self.fields.file);
+            }
+        }
         debug!("Indexed {:?}", document);
         documents.push((id.to_string(), document));
         Ok(documents)
     }
 
-    fn index_spdx_dep(document: &mut Document, package: &spdx_rs::models::PackageInformation, fields: &DepFields) {
+    fn index_spdx_dep(document: &mut Document, package: &Package, fields: &DepFields) {
         for r in package.external_reference.iter() {
             if r.reference_type == "purl" {
                 let purl = r.reference_locator.clone();
@@ -172,11 +267,77 @@ impl Index {
         }
     }
 
-    fn index_spdx_package(
-        document: &mut Document,
-        package: &spdx_rs::models::PackageInformation,
-        fields: &PackageFields,
-    ) {
+    /// Index the Package URLs of the packages which others are variants of, or generated from, and of the packages
+    /// depending on others.
+    ///
+    /// Dependencies are taken from the `DEPENDS_ON` and `CONTAINS` relationships, and their inverse. Packages which
+    /// no relationship links to are taken as dependencies of the described packages, so that the chain from the
+    /// described package down to nested packages is complete.
+    fn index_spdx_relationships(document: &mut Document, bom: &Spdx, fields: &RelationshipFields) {
+        let purls: HashMap<&str, &str> = bom
+            .package_information
+            .iter()
+            .filter_map(|package| {
+                let purl = package.external_reference.iter().find(|r| r.reference_type == "purl")?;
+                Some((
+                    package.package_spdx_identifier.as_str(),
+                    purl.reference_locator.as_str(),
+                ))
+            })
+            .collect();
+
+        for (_, edge_type, target) in &bom.relationships.provenance {
+            let Some(purl) = purls.get(target.as_str()) else {
+                continue;
+            };
+            match edge_type {
+                EdgeType::VariantOf => document.add_text(fields.variant_of, purl),
+                EdgeType::GeneratedFrom => document.add_text(fields.generated_from, purl),
+                EdgeType::DependsOn | EdgeType::Contains => {}
+            }
+        }
+
+        let mut linked = HashSet::new();
+        for (dependent, dependency) in &bom.relationships.dependencies {
+            linked.insert(dependency.as_str());
+            if let (Some(purl), true) = (purls.get(dependent.as_str()), purls.contains_key(dependency.as_str())) {
+                document.add_text(fields.dependent, purl);
+            }
+        }
+
+        let (described, others): (Vec<_>, Vec<_>) = bom
+            .package_information
+            .iter()
+            .partition(|package| bom.describes(package));
+        for package in others {
+            let id = package.package_spdx_identifier.as_str();
+            if linked.contains(id) || !purls.contains_key(id) {
+                continue;
+            }
+            for described in &described {
+                if let Some(purl) = purls.get(described.package_spdx_identifier.as_str()) {
+                    document.add_text(fields.dependent, purl);
+                }
+            }
+        }
+    }
+
+    fn index_spdx_file(document: &mut Document, file: &File, fields: &FileFields) {
+        let path = file.file_name.trim_start_matches("./");
+        document.add_text(fields.name, path);
+        // allow finding files by their name alone, like `libssl.so.3`
+        if let Some((_, name)) = path.rsplit_once('/').filter(|(_, name)| !name.is_empty()) {
+            document.add_text(fields.name, name);
+        }
+
+        for sum in file.file_checksum.iter() {
+            if sum.algorithm == Algorithm::SHA256 {
+                document.add_text(fields.sha256, sum.value.to_lowercase());
+            }
+        }
+    }
+
+    fn index_spdx_package(document: &mut Document, package: &Package, fields: &PackageFields) {
         if let Some(comment) = &package.package_summary_description {
             document.add_text(fields.desc, comment);
         }
@@ -220,11 +381,14 @@ impl Index {
         }
 
         if let Some(license) = &package.declared_license {
-            document.add_text(fields.license, license.to_string());
+            document.add_text(fields.license, license);
         }
 
         if let Some(supplier) = &package.package_supplier {
             document.add_text(fields.supplier, supplier);
+            if let Some(normalized) = supplier::normalize(supplier) {
+                document.add_text(fields.supplier_normalized, normalized);
+            }
         }
     }
 
@@ -232,6 +396,7 @@ impl Index {
         &self,
         id: &str,
         bom: &cyclonedx_bom::prelude::Bom,
+        references: &ExternalReferences,
         sha256: &str,
     ) -> Result<Vec<(String, Document)>, SearchError> {
         let mut documents: Vec<(String, Document)> = Vec::new();
@@ -246,6 +411,8 @@ impl Index {
         let nanos_since_epoch_i64 = nanos_since_epoch as i64;
         document.add_i64(self.fields.indexed_timestamp, nanos_since_epoch_i64);
 
+        let mut ecosystems = Ecosystems::default();
+        let mut components = Components::default();
         if let Some(metadata) = &bom.metadata {
             if let Some(timestamp) = &metadata.timestamp {
                 let timestamp = timestamp.to_string();
@@ -260,18 +427,97 @@ impl Index {
             if let Some(component) = &metadata.component {
                 document.add_text(self.fields.sbom_name, component.name.to_string());
                 Self::index_cyclonedx_component(&mut document, component, &self.fields.sbom);
+                if let Some(purl) = &component.purl {
+                    ecosystems.add(&purl.to_string());
+                    components.add(&purl.to_string());
+                }
             }
         }
 
         if let Some(components) = &bom.components {
             for component in components.0.iter() {
                 Self::index_cyclonedx_dep(&mut document, component, &self.fields.dep);
+                if let Some(purl) = &component.purl {
+                    ecosystems.add(&purl.to_string());
+                    components.add(&purl.to_string());
+                }
             }
         }
+        ecosystems.index(&mut document, &self.fields);
+        components.index(&mut document, &self.fields);
+        Self::index_cyclonedx_dependencies(&mut document, bom, &self.fields.rel);
+
+        for reference in &references.document {
+            let mut object = serde_json::Map::new();
+            object.insert("category".to_string(), reference.category.clone().into());
+            object.insert("url".to_string(), reference.url.clone().into());
+            document.add_json_object(self.fields.sbom_external_refs, object);
+        }
+        for reference in references.iter() {
+            Self::index_external_reference(&mut document, reference, &self.fields.ext_ref);
+        }
+
         documents.push((id.to_string(), document));
         Ok(documents)
     }
 
+    fn index_external_reference(document: &mut Document, reference: &DocumentReference, fields: &ReferenceFields) {
+        let field = match reference.category.as_str() {
+            "vcs" => fields.vcs,
+            "build-system" => fields.build_system,
+            "distribution" => fields.distribution,
+            _ => return,
+        };
+        document.add_text(field, &reference.url);
+
+        // allow finding repositories by their plain URL, like `https://github.com/org/repo`
+        if field == fields.vcs {
+            let normalized = normalize_vcs_url(&reference.url);
+            if normalized != reference.url {
+                document.add_text(field, normalized);
+            }
+        }
+    }
+
+    /// Index the Package URL of the dependent of each edge of the `dependencies` section.
+    fn index_cyclonedx_dependencies(
+        document: &mut Document,
+        bom: &cyclonedx_bom::prelude::Bom,
+        fields: &RelationshipFields,
+    ) {
+        fn collect<'a>(component: &'a cyclonedx_bom::prelude::Component, purls: &mut HashMap<&'a str, String>) {
+            if let (Some(bom_ref), Some(purl)) = (&component.bom_ref, &component.purl) {
+                purls.insert(bom_ref.as_str(), purl.to_string());
+            }
+            for component in component.components.iter().flat_map(|c| c.0.iter()) {
+                collect(component, purls);
+            }
+        }
+
+        let Some(dependencies) = &bom.dependencies else {
+            return;
+        };
+
+        let mut purls = HashMap::new();
+        if let Some(component) = bom.metadata.as_ref().and_then(|m| m.component.as_ref()) {
+            collect(component, &mut purls);
+        }
+        for component in bom.components.iter().flat_map(|c| c.0.iter()) {
+            collect(component, &mut purls);
+        }
+
+        for dependency in &dependencies.0 {
+            let Some(dependent) = purls.get(dependency.dependency_ref.as_str()) else {
+                continue;
+            };
+            for target in &dependency.dependencies {
+                if purls.contains_key(target.as_str()) {
+                    document.add_text(fields.dependent, dependent);
+                }
+            }
+        }
+    }
+
     fn index_cyclonedx_dep(document: &mut Document, component: &cyclonedx_bom::prelude::Component, fields: &DepFields) {
         if let Some(purl) = &component.purl {
             let purl = purl.to_string();
@@ -341,10 +587,10 @@ impl Index {
         document.add_text(fields.classifier, component.component_type.to_string());
     }
 
-    fn resource2query(&self, resource: &Packages) -> Box<dyn Query> {
+    fn resource2query(&self, resource: &Packages) -> Result<Box<dyn Query>, SearchError> {
         const PACKAGE_WEIGHT: f32 = 1.5;
         const CREATED_WEIGHT: f32 = 1.25;
-        match resource {
+        Ok(match resource {
             Packages::Id(value) => Box::new(TermQuery::new(
                 Term::from_field_text(self.fields.sbom_id, value),
                 Default::default(),
@@ -354,7 +600,7 @@ impl Index {
                 Default::default(),
             )),
             Packages::Package(primary) => boost(
-                self.create_string_query(
+                self.create_purl_pattern_query(
                     &[
                         self.fields.sbom_name,
                         self.fields.sbom.name,
@@ -363,20 +609,36 @@ impl Index {
                         self.fields.sbom.purl_name,
                     ],
                     primary,
-                ),
+                )?,
                 PACKAGE_WEIGHT,
             ),
 
+            Packages::Purl(primary) => self.create_purl_pattern_query(&[self.fields.sbom.purl], primary)?,
+
+            Packages::Cpe(primary) => self.create_pattern_query(&[self.fields.sbom.cpe], primary)?,
+
+            Packages::Name(primary) => self.create_pattern_query(&[self.fields.sbom.purl_name], primary)?,
+
             Packages::Type(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
                 self.fields.sbom.purl_type,
                 value,
             )])),
 
+            Packages::Ecosystem(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                self.fields.sbom_ecosystem,
+                value,
+            )])),
+
             Packages::Namespace(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
                 self.fields.sbom.purl_namespace,
                 value,
             )])),
 
+            Packages::Component(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                self.fields.sbom_component,
+                value,
+            )])),
+
             Packages::Created(ordered) => boost(
                 create_date_query(&self.schema, self.fields.sbom_created, ordered),
                 CREATED_WEIGHT,
@@ -401,7 +663,24 @@ impl Index {
                 value,
             )])),
 
-            Packages::Supplier(primary) => self.create_string_query(&[self.fields.sbom.supplier], primary),
+            Packages::Supplier(primary) => {
+                create_supplier_query(self.fields.sbom.supplier, self.fields.sbom.supplier_normalized, primary)
+            }
+
+            Packages::Source(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                self.fields.sbom_source,
+                &value.to_ascii_lowercase(),
+            )])),
+
+            Packages::Label(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                self.fields.sbom_labels,
+                value,
+            )])),
+
+            Packages::Custom(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                self.fields.custom_id,
+                value,
+            )])),
 
             Packages::Qualifier(qualified) => {
                 let mut qs = Vec::new();
@@ -413,7 +692,32 @@ impl Index {
                 Box::new(BooleanQuery::union(qs))
             }
 
-            Packages::Dependency(primary) => self.create_string_query(&[self.fields.dep.purl], primary),
+            Packages::Dependency(primary) => self.create_purl_pattern_query(&[self.fields.dep.purl], primary)?,
+
+            Packages::Filename(primary) => self.create_pattern_query(&[self.fields.file.name], primary)?,
+
+            Packages::Vcs(primary) => self.create_pattern_query(&[self.fields.ext_ref.vcs], primary)?,
+
+            Packages::BuildSystem(primary) => {
+                self.create_pattern_query(&[self.fields.ext_ref.build_system], primary)?
+            }
+
+            Packages::Distribution(primary) => {
+                self.create_pattern_query(&[self.fields.ext_ref.distribution], primary)?
+            }
+
+            Packages::VariantOf(primary) => self.create_purl_pattern_query(&[self.fields.rel.variant_of], primary)?,
+
+            Packages::GeneratedFrom(primary) => {
+                self.create_purl_pattern_query(&[self.fields.rel.generated_from], primary)?
+            }
+
+            Packages::Dependent(primary) => self.create_purl_pattern_query(&[self.fields.rel.dependent], primary)?,
+
+            Packages::Filedigest(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                self.fields.file.sha256,
+                &value.to_ascii_lowercase(),
+            )])),
 
             Packages::Application => self.match_classifiers(Classification::Application),
             Packages::Library => self.match_classifiers(Classification::Library),
@@ -423,11 +727,15 @@ impl Index {
             Packages::Device => self.match_classifiers(Classification::Device),
             Packages::Firmware => self.match_classifiers(Classification::Firmware),
             Packages::File => self.match_classifiers(Classification::File),
+            Packages::Archived => Box::new(TermQuery::new(
+                Term::from_field_bool(self.fields.sbom_archived, true),
+                Default::default(),
+            )),
             Packages::IndexedTimestamp(ordered) => boost(
                 create_i64_query(&self.schema, self.fields.indexed_timestamp, ordered),
                 CREATED_WEIGHT,
             ),
-        }
+        })
     }
 
     fn create_string_query(&self, fields: &[Field], value: &Primary<'_>) -> Box<dyn Query> {
@@ -435,6 +743,23 @@ impl Index {
         Box::new(BooleanQuery::union(queries))
     }
 
+    fn create_pattern_query(&self, fields: &[Field], value: &Primary<'_>) -> Result<Box<dyn Query>, SearchError> {
+        let queries = fields
+            .iter()
+            .map(|f| create_pattern_query(*f, value))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Box::new(BooleanQuery::union(queries)))
+    }
+
+    /// Like [`Self::create_pattern_query`], for values which are Package URLs.
+    fn create_purl_pattern_query(&self, fields: &[Field], value: &Primary<'_>) -> Result<Box<dyn Query>, SearchError> {
+        let queries = fields
+            .iter()
+            .map(|f| create_purl_pattern_query(*f, value))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Box::new(BooleanQuery::union(queries)))
+    }
+
     fn match_classifiers(&self, classification: Classification) -> Box<dyn Query> {
         Box::new(BooleanQuery::union(vec![create_boolean_query(
             Occur::Should,
@@ -452,32 +777,17 @@ impl trustification_index::Index for Index {
 
         debug!("Query: {:?}", query.term);
 
-        let mut sort_by = None;
-        if let Some(f) = query.sorting.first() {
-            match f.qualifier {
-                PackagesSortable::Created => match f.direction {
-                    Direction::Descending => {
-                        sort_by.replace((self.fields.sbom_created, Order::Desc));
-                    }
-                    Direction::Ascending => {
-                        sort_by.replace((self.fields.sbom_created, Order::Asc));
-                    }
-                },
-                PackagesSortable::IndexedTimestamp => match f.direction {
-                    Direction::Descending => {
-                        sort_by.replace((self.fields.indexed_timestamp, Order::Desc));
-                    }
-                    Direction::Ascending => {
-                        sort_by.replace((self.fields.indexed_timestamp, Order::Asc));
-                    }
-                },
-            }
-        }
+        let sort_by = query.sorting.first().map(|f| match f.qualifier {
+            PackagesSortable::Name => sort_by(f.direction, self.fields.sbom_name),
+            PackagesSortable::Supplier => sort_by(f.direction, self.fields.sbom.supplier),
+            PackagesSortable::Created => sort_by(f.direction, self.fields.sbom_created),
+            PackagesSortable::IndexedTimestamp => sort_by(f.direction, self.fields.indexed_timestamp),
+        });
 
         let query = if query.term.is_empty() {
             Box::new(AllQuery)
         } else {
-            term2query(&query.term, &|resource| self.resource2query(resource))
+            try_term2query(&query.term, &|resource| self.resource2query(resource))?
         };
 
         log::trace!("Processed query: {:?}", query);
@@ -597,6 +907,31 @@ impl trustification_index::Index for Index {
 
         let dependencies: u64 = doc.get_all(self.fields.dep.purl).count() as u64;
 
+        let mut ecosystems = HashMap::new();
+        if let Some(Some(data)) = doc.get_first(self.fields.sbom_ecosystem_count).map(|d| d.as_json()) {
+            for (key, value) in data.iter() {
+                if let Some(value) = value.as_u64() {
+                    ecosystems.insert(key.clone(), value);
+                }
+            }
+        }
+
+        let labels = field2strvec(&doc, self.fields.sbom_labels)?
+            .into_iter()
+            .map(ToString::to_string)
+            .collect();
+
+        let external_references = doc
+            .get_all(self.fields.sbom_external_refs)
+            .filter_map(|value| value.as_json())
+            .filter_map(|object| serde_json::from_value(serde_json::Value::Object(object.clone())).ok())
+            .collect();
+
+        let archived = doc
+            .get_first(self.fields.sbom_archived)
+            .and_then(|value| value.as_bool())
+            .unwrap_or_default();
+
         let indexed_timestamp = doc
             .get_first(self.fields.indexed_timestamp)
             .map(|s| {
@@ -621,6 +956,10 @@ impl trustification_index::Index for Index {
             created,
             description: description.to_string(),
             dependencies,
+            ecosystems,
+            external_references,
+            labels,
+            archived,
             indexed_timestamp,
         };
 
@@ -645,35 +984,365 @@ impl trustification_index::Index for Index {
             metadata,
         })
     }
+
+    fn search_fields(&self) -> Vec<SearchField> {
+        let field = |qualifier: &str, fields: &[Field], description: &str| {
+            search_field(&self.schema, qualifier, fields, description, |q| {
+                Packages::parse(q).is_ok()
+            })
+        };
+        let f = &self.fields;
+
+        let mut qualifier = field(
+            "qualifier",
+            &[f.sbom.purl_qualifiers],
+            "Package URL qualifier of a package",
+        );
+        qualifier.example = "qualifier:arch:x86_64".to_string();
+
+        let mut purl = field(
+            "purl",
+            &[f.sbom.purl],
+            "Package URL of a package, supporting wildcard (*) and regular expression (/regex/) patterns",
+        );
+        purl.example = r#"purl:"pkg:rpm/redhat/openssl*""#.to_string();
+
+        vec![
+            field("id", &[f.sbom_id], "SBOM (storage) identifier"),
+            field("uid", &[f.sbom_uid], "SBOM unique identifier"),
+            field(
+                "package",
+                &[f.sbom_name, f.sbom.name, f.sbom.purl, f.sbom.cpe, f.sbom.purl_name],
+                "Name, package URL or CPE of the SBOM or its packages",
+            ),
+            field("type", &[f.sbom.purl_type], "Package URL type of a package"),
+            purl,
+            field("cpe", &[f.sbom.cpe], "CPE of a package, supporting patterns like purl"),
+            field(
+                "name",
+                &[f.sbom.purl_name],
+                "Package URL name of a package, supporting patterns like purl",
+            ),
+            field(
+                "namespace",
+                &[f.sbom.purl_namespace],
+                "Package URL namespace of a package",
+            ),
+            field(
+                "ecosystem",
+                &[f.sbom_ecosystem],
+                "Package URL type of any package contained in the SBOM",
+            ),
+            field(
+                "version",
+                &[f.sbom.version, f.sbom.purl_version],
+                "Version of a package",
+            ),
+            field("description", &[f.sbom.desc], "Description of a package"),
+            field("created", &[f.sbom_created], "Creation date of the SBOM"),
+            field("indexedTimestamp", &[f.indexed_timestamp], "Time the SBOM was indexed"),
+            field("digest", &[f.sbom.sha256], "SHA256 digest of a package"),
+            field("license", &[f.sbom.license], "License of a package"),
+            field("supplier", &[f.sbom.supplier], "Supplier of a package"),
+            qualifier,
+            field("dependency", &[f.dep.purl], "Package URL of a dependency"),
+            field(
+                "component",
+                &[f.sbom_component],
+                "Component id of any package contained in the SBOM",
+            ),
+            field(
+                "filename",
+                &[f.file.name],
+                "Name or path of a file contained in the SBOM, supporting patterns like purl",
+            ),
+            field(
+                "filedigest",
+                &[f.file.sha256],
+                "SHA256 digest of a file contained in the SBOM",
+            ),
+            field(
+                "vcs",
+                &[f.ext_ref.vcs],
+                "Version control repository of the SBOM or a component, supporting patterns like purl",
+            ),
+            field(
+                "buildSystem",
+                &[f.ext_ref.build_system],
+                "Build system URL of the SBOM or a component, supporting patterns like purl",
+            ),
+            field(
+                "distribution",
+                &[f.ext_ref.distribution],
+                "Distribution URL of the SBOM or a component, supporting patterns like purl",
+            ),
+            field(
+                "variantOf",
+                &[f.rel.variant_of],
+                "Package URL of a package another one is a variant of, supporting patterns like purl",
+            ),
+            field(
+                "generatedFrom",
+                &[f.rel.generated_from],
+                "Package URL of a package another one was generated from, supporting patterns like purl",
+            ),
+            field(
+                "dependent",
+                &[f.rel.dependent],
+                "Package URL of a package depending on another one, supporting patterns like purl",
+            ),
+            field(
+                "source",
+                &[f.sbom_source],
+                "How the SBOM was ingested: api, walker or federation",
+            ),
+            field(
+                "label",
+                &[f.sbom_labels],
+                "Label added when indexing the SBOM, like \"owner:team-a\"",
+            ),
+            field(
+                "custom",
+                &[f.custom_id],
+                "Custom identifier of a vendor, like an internal part number",
+            ),
+            search_predicate("application", "Packages classified as application"),
+            search_predicate("library", "Packages classified as library"),
+            search_predicate("framework", "Packages classified as framework"),
+            search_predicate("container", "Packages classified as container"),
+            search_predicate("operatingSystem", "Packages classified as operating system"),
+            search_predicate("device", "Packages classified as device"),
+            search_predicate("firmware", "Packages classified as firmware"),
+            search_predicate("file", "Packages classified as file"),
+            search_predicate("archived", "SBOMs whose stored document was archived"),
+        ]
+    }
+
+    fn facets(&self) -> Vec<(&'static str, Field)> {
+        let f = &self.fields;
+        vec![
+            ("ecosystem", f.sbom_ecosystem),
+            ("license", f.sbom.license),
+            ("supplier", f.sbom.supplier),
+            ("type", f.sbom.purl_type),
+            ("classifier", f.sbom.classifier),
+        ]
+    }
+    fn export_columns(&self) -> Vec<Column> {
+        let f = &self.fields;
+        vec![
+            Column::new("id", f.sbom_id, ColumnType::Text).key(),
+            Column::new("uid", f.sbom_uid, ColumnType::Text),
+            Column::new("sha256", f.sbom_sha256, ColumnType::Text),
+            Column::new("name", f.sbom_name, ColumnType::Text),
+            Column::new("created", f.sbom_created, ColumnType::Timestamp),
+            Column::new("creators", f.sbom_creators, ColumnType::TextList),
+            Column::new("source", f.sbom_source, ColumnType::Text),
+            Column::new("labels", f.sbom_labels, ColumnType::TextList),
+            Column::new("archived", f.sbom_archived, ColumnType::Bool),
+            Column::new("ecosystems", f.sbom_ecosystem_count, ColumnType::Json),
+            Column::new("external_refs", f.sbom_external_refs, ColumnType::Json),
+            Column::new("package_name", f.sbom.name, ColumnType::Text),
+            Column::new("package_version", f.sbom.version, ColumnType::Text),
+            Column::new("package_purl", f.sbom.purl, ColumnType::Text),
+            Column::new("package_cpe", f.sbom.cpe, ColumnType::Text),
+            Column::new("package_license", f.sbom.license, ColumnType::Text),
+            Column::new("package_supplier", f.sbom.supplier, ColumnType::Text),
+            Column::new("package_sha256", f.sbom.sha256, ColumnType::Text),
+            Column::new("package_description", f.sbom.desc, ColumnType::Text),
+            Column::new("dependencies", f.dep.purl, ColumnType::TextList),
+        ]
+    }
+}
+
+/// Normalize the URL of a repository, dropping the `git+` prefix, the `.git` suffix and trailing slashes.
+fn normalize_vcs_url(url: &str) -> &str {
+    let url = url.strip_prefix("git+").unwrap_or(url).trim_end_matches('/');
+    url.strip_suffix(".git").unwrap_or(url)
+}
+
+/// Rollup of the package URL types of all packages of an SBOM.
+#[derive(Default)]
+struct Ecosystems(HashMap<String, u64>);
+
+impl Ecosystems {
+    fn add(&mut self, purl: &str) {
+        if let Ok(purl) = packageurl::PackageUrl::from_str(purl) {
+            *self.0.entry(purl.ty().to_string()).or_default() += 1;
+        }
+    }
+
+    fn index(self, document: &mut Document, fields: &Fields) {
+        let mut counts = serde_json::Map::new();
+        for (ty, count) in self.0 {
+            document.add_text(fields.sbom_ecosystem, &ty);
+            counts.insert(ty, count.into());
+        }
+        document.add_json_object(fields.sbom_ecosystem_count, counts);
+    }
+}
+
+/// The components of all packages of an SBOM, and their versions.
+#[derive(Default)]
+struct Components(HashMap<Component, BTreeSet<String>>);
+
+impl Components {
+    fn add(&mut self, purl: &str) {
+        if let Some((component, version)) = Component::from_purl(purl) {
+            self.0.entry(component).or_default().extend(version);
+        }
+    }
+
+    fn index(self, document: &mut Document, fields: &Fields) {
+        for (component, versions) in self.0 {
+            document.add_text(fields.sbom_component, &component.id);
+            if versions.is_empty() {
+                document.add_text(
+                    fields.sbom_component_version,
+                    format!("{} {}", component.id, component.purl),
+                );
+            }
+            for version in versions {
+                document.add_text(
+                    fields.sbom_component_version,
+                    format!("{} {}@{}", component.id, component.purl, version),
+                );
+            }
+        }
+    }
+}
+
+/// Report where a component appears, from the SBOMs containing it.
+///
+/// All SBOMs are counted, but only `limit` of them are listed, after skipping `offset`.
+pub fn component_usage(
+    store: &IndexStore<Index>,
+    id: &str,
+    offset: usize,
+    limit: usize,
+) -> Result<ComponentUsage, SearchError> {
+    let fields = &store.index().fields;
+    let documents = store.matching_documents(&format!("component:{id}"))?;
+
+    let prefix = format!("{id} ");
+    let mut purl = None;
+    let mut versions = BTreeMap::<String, usize>::new();
+    let mut trend = BTreeMap::<String, usize>::new();
+    let mut sboms = Vec::new();
+    for doc in &documents {
+        let mut sbom_versions = Vec::new();
+        for value in field2strvec(doc, fields.sbom_component_version)? {
+            let Some(value) = value.strip_prefix(&prefix) else {
+                continue;
+            };
+            let (canonical, version) = match value.split_once('@') {
+                Some((canonical, version)) => (canonical, Some(version)),
+                None => (value, None),
+            };
+            purl.get_or_insert_with(|| canonical.to_string());
+            if let Some(version) = version {
+                *versions.entry(version.to_string()).or_default() += 1;
+                sbom_versions.push(version.to_string());
+            }
+        }
+        sbom_versions.sort_by(|a, b| compare_versions(a, b));
+
+        let created = doc
+            .get_first(fields.sbom_created)
+            .and_then(|value| value.as_date())
+            .map(|date| date.into_utc());
+        if let Some(created) = created {
+            let month = format!("{:04}-{:02}", created.year(), u8::from(created.month()));
+            *trend.entry(month).or_default() += 1;
+        }
+
+        sboms.push(ComponentSbom {
+            id: field2str_opt(doc, fields.sbom_id).unwrap_or_default().to_string(),
+            name: field2str_opt(doc, fields.sbom_name).unwrap_or_default().to_string(),
+            created,
+            versions: sbom_versions,
+        });
+    }
+
+    let mut versions: Vec<_> = versions
+        .into_iter()
+        .map(|(version, sboms)| ComponentVersion { version, sboms })
+        .collect();
+    versions.sort_by(|a, b| compare_versions(&a.version, &b.version));
+
+    // most recent first, SBOMs without a creation date last
+    sboms.sort_by(|a, b| b.created.cmp(&a.created).then_with(|| a.id.cmp(&b.id)));
+
+    Ok(ComponentUsage {
+        id: id.to_string(),
+        purl,
+        total: documents.len(),
+        min_version: versions.first().map(|v| v.version.clone()),
+        max_version: versions.last().map(|v| v.version.clone()),
+        versions,
+        trend: trend
+            .into_iter()
+            .map(|(month, sboms)| ComponentTrend { month, sboms })
+            .collect(),
+        sboms: sboms.into_iter().skip(offset).take(limit).collect(),
+    })
 }
 
 impl trustification_index::WriteIndex for Index {
-    type Document = (SBOM, String);
+    type Document = (ParsedSbom, String, Vec<String>);
 
     fn name(&self) -> &str {
         "sbom"
     }
 
-    fn index_doc(&self, id: &str, (doc, sha256): &Self::Document) -> Result<Vec<(String, Document)>, SearchError> {
-        let doc = match doc {
-            SBOM::CycloneDX(bom) => self.index_cyclonedx(id, bom, sha256)?,
-            SBOM::SPDX(bom) => self.index_spdx(id, bom, sha256)?,
+    fn index_doc(
+        &self,
+        id: &str,
+        (doc, sha256, custom_ids): &Self::Document,
+    ) -> Result<Vec<(String, Document)>, SearchError> {
+        let mut doc = match doc {
+            ParsedSbom::CycloneDX(bom, references) => self.index_cyclonedx(id, bom, references, sha256)?,
+            ParsedSbom::Spdx(bom) => self.index_spdx(id, bom, sha256)?,
         };
+        for (_, document) in &mut doc {
+            for custom_id in custom_ids {
+                document.add_text(self.fields.custom_id, custom_id);
+            }
+        }
 
         Ok(doc)
     }
 
     fn parse_doc(&self, data: &[u8]) -> Result<Self::Document, SearchError> {
         let sha256 = sha256::digest(data);
-        SBOM::parse(data)
-            .map_err(|e| SearchError::DocParser(e.to_string()))
-            .map(|doc| (doc, sha256))
+        let custom_ids = self.custom_ids.extract(data)?;
+        match self.files {
+            true => ParsedSbom::parse_with_files(data),
+            false => ParsedSbom::parse(data),
+        }
+        .map(|doc| (doc, sha256, custom_ids))
     }
 
     fn schema(&self) -> Schema {
         self.schema.clone()
     }
 
+    fn source_field(&self) -> Option<Field> {
+        Some(self.fields.sbom_source)
+    }
+
+    fn label_field(&self) -> Option<Field> {
+        Some(self.fields.sbom_labels)
+    }
+
+    fn archived_field(&self) -> Option<Field> {
+        Some(self.fields.sbom_archived)
+    }
+
+    fn digest_field(&self) -> Option<Field> {
+        Some(self.fields.sbom_sha256)
+    }
+
     fn settings(&self) -> IndexSettings {
         IndexSettings {
             docstore_compression: tantivy::store::Compressor::Zstd(ZstdCompressor::default()),
@@ -769,6 +1438,23 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_search_indexed_after() {
+        assert_search(|index| {
+            // the query of the changes feed
+            let (all, _) = search(&index, "indexedTimestamp:>0 sort:indexedTimestamp");
+            assert_eq!(all.len(), 3);
+            assert!(all
+                .windows(2)
+                .all(|w| w[0].document.indexed_timestamp <= w[1].document.indexed_timestamp));
+
+            let since = all[0].document.indexed_timestamp;
+            let (after, total) = search(&index, &format!("indexedTimestamp:>{since} sort:indexedTimestamp"));
+            assert_eq!(total, 2);
+            assert_eq!(after[0].document.id, all[1].document.id);
+        });
+    }
+
     #[tokio::test]
     async fn test_total_num() {
         assert_search(|index| {
@@ -808,6 +1494,29 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_search_patterns() {
+        assert_search(|index| {
+            let result = search(&index, r#"purl:"pkg:rpm/redhat/openssl*""#);
+            assert_eq!(result.0.len(), 2);
+
+            let result = search(&index, r#"purl:"pkg:rpm/redhat/openssl-libs@3.*""#);
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, r#"purl:"/pkg:rpm/redhat/openssl-libs@1.1.*/""#);
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, r#"name:"openssl-lib?""#);
+            assert_eq!(result.0.len(), 2);
+
+            let result = search(&index, r#"cpe:"cpe:/a:redhat:kernel_module_management*""#);
+            assert_eq!(result.0.len(), 1);
+
+            // without a literal prefix, patterns would have to scan all terms
+            assert!(index.search(r#"purl:"*openssl*""#, 0, 10, Default::default()).is_err());
+        });
+    }
+
     #[tokio::test]
     async fn test_search_namespace() {
         assert_search(|index| {
@@ -816,6 +1525,116 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_search_ecosystem() {
+        assert_search(|index| {
+            let result = search(&index, "ecosystem:rpm");
+            assert_eq!(result.0.len(), 2);
+
+            let result = search(&index, "ecosystem:maven");
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.ecosystems.get("maven"), Some(&95));
+        });
+    }
+
+    #[tokio::test]
+    async fn test_facets() {
+        assert_search(|index| {
+            let facets = index.facets("", &["ecosystem".to_string()]).unwrap();
+            let ecosystems = &facets["ecosystem"];
+            assert_eq!(ecosystems.get("rpm"), Some(&2));
+            assert_eq!(ecosystems.get("oci"), Some(&2));
+            assert_eq!(ecosystems.get("maven"), Some(&1));
+
+            let facets = index.facets("ecosystem:maven", &["ecosystem".to_string()]).unwrap();
+            assert_eq!(facets["ecosystem"].len(), 1);
+
+            assert!(index.facets("", &["unknown".to_string()]).is_err());
+
+            let names = ["license", "supplier", "type", "classifier"].map(ToString::to_string);
+            let facets = index.facets("", &names).unwrap();
+            assert_eq!(facets.len(), 4);
+            let types = &facets["type"];
+            assert!(!types.is_empty());
+            assert!(types.values().sum::<u64>() <= 3);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_component_usage() {
+        assert_search(|index| {
+            let (component, _) = Component::from_purl("pkg:rpm/redhat/bash@5.1.8-6.el9_1?arch=x86_64").unwrap();
+
+            let result = search(&index, &format!("component:{}", component.id));
+            assert_eq!(result.1, 2);
+
+            let usage = component_usage(&index, &component.id, 0, 10).unwrap();
+            assert_eq!(usage.purl.as_deref(), Some("pkg:rpm/redhat/bash"));
+            assert_eq!(usage.total, 2);
+            assert_eq!(usage.min_version.as_deref(), Some("4.4.20-4.el8_6"));
+            assert_eq!(usage.max_version.as_deref(), Some("5.1.8-6.el9_1"));
+            assert_eq!(
+                usage.versions,
+                vec![
+                    ComponentVersion {
+                        version: "4.4.20-4.el8_6".to_string(),
+                        sboms: 1
+                    },
+                    ComponentVersion {
+                        version: "5.1.8-6.el9_1".to_string(),
+                        sboms: 1
+                    },
+                ]
+            );
+            assert_eq!(
+                usage.trend,
+                vec![
+                    ComponentTrend {
+                        month: "2023-03".to_string(),
+                        sboms: 1
+                    },
+                    ComponentTrend {
+                        month: "2023-06".to_string(),
+                        sboms: 1
+                    },
+                ]
+            );
+            assert_eq!(
+                usage.sboms.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(),
+                vec!["kmm-1", "ubi9-sbom"]
+            );
+            assert_eq!(usage.sboms[0].versions, vec!["4.4.20-4.el8_6"]);
+
+            // all SBOMs are counted, even when not listed
+            let usage = component_usage(&index, &component.id, 1, 10).unwrap();
+            assert_eq!(usage.total, 2);
+            assert_eq!(usage.sboms.len(), 1);
+            assert_eq!(usage.sboms[0].id, "ubi9-sbom");
+
+            let usage =
+                component_usage(&index, &crate::component::component_id("pkg:rpm/redhat/unknown"), 0, 10).unwrap();
+            assert_eq!(usage.total, 0);
+            assert_eq!(usage.purl, None);
+            assert!(usage.versions.is_empty());
+        });
+    }
+
+    #[tokio::test]
+    async fn test_search_content_id() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        let data = std::fs::read("../testdata/my-sbom.json").unwrap();
+        let id = format!("sha256:{}", sha256::digest(data.as_slice()));
+        writer.add_document(store.index_as_mut(), &id, &data).unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, &format!(r#"id:"{id}""#));
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.id, id);
+    }
+
     #[tokio::test]
     async fn test_search_created() {
         assert_search(|index| {
@@ -883,6 +1702,38 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_sorting_text() {
+        assert_search(|index| {
+            let names = |query| -> Vec<String> {
+                search(&index, query)
+                    .0
+                    .into_iter()
+                    .map(|hit| hit.document.name)
+                    .collect()
+            };
+
+            let ascending = names("sort:name");
+            assert_eq!(ascending.len(), 3);
+            let mut expected = ascending.clone();
+            expected.sort();
+            assert_eq!(ascending, expected);
+
+            let mut descending = names("-sort:name");
+            descending.reverse();
+            assert_eq!(descending, ascending);
+
+            let suppliers: Vec<String> = search(&index, "sort:supplier")
+                .0
+                .into_iter()
+                .map(|hit| hit.document.supplier)
+                .collect();
+            let mut expected = suppliers.clone();
+            expected.sort_by_key(|supplier| (supplier.is_empty(), supplier.clone()));
+            assert_eq!(suppliers, expected);
+        });
+    }
+
     #[tokio::test]
     async fn test_sorting() {
         assert_search(|index| {
@@ -919,6 +1770,351 @@ mod tests {
             let result = search(&index, "\"Red Hat\" in:supplier");
             assert_eq!(result.0.len(), 2);
         });
+
+        // variants of the supplier name
+        assert_search(|index| {
+            let result = search(&index, "supplier:\"Red Hat, Inc.\"");
+            assert_eq!(result.0.len(), 2);
+        });
+
+        assert_search(|index| {
+            let result = search(&index, "supplier:redhat");
+            assert_eq!(result.0.len(), 2);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_source() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        for (file, source) in [("my-sbom", Some("walker")), ("ubi9-sbom", None)] {
+            let data = std::fs::read(format!("../testdata/{file}.json")).unwrap();
+            writer
+                .add_document_with_source(store.index_as_mut(), file, &data, source)
+                .unwrap();
+        }
+        writer.commit().unwrap();
+
+        let result = search(&store, "source:walker");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.id, "my-sbom");
+
+        let result = search(&store, "source:api");
+        assert_eq!(result.0.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_archived() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        for (file, archived) in [("my-sbom", true), ("ubi9-sbom", false)] {
+            let data = std::fs::read(format!("../testdata/{file}.json")).unwrap();
+            writer
+                .add_stored_document(store.index_as_mut(), file, &data, None, archived, &[])
+                .unwrap();
+        }
+        writer.commit().unwrap();
+
+        let result = search(&store, "is:archived");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.id, "my-sbom");
+        assert!(result.0[0].document.archived);
+    }
+
+    #[tokio::test]
+    async fn test_stored_labels() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        let signed = ["signature:verified".to_string(), "signer:release".to_string()];
+        for (file, labels) in [("my-sbom", &signed[..]), ("ubi9-sbom", &[])] {
+            let data = std::fs::read(format!("../testdata/{file}.json")).unwrap();
+            writer
+                .add_stored_document(store.index_as_mut(), file, &data, None, false, labels)
+                .unwrap();
+        }
+        writer.commit().unwrap();
+
+        let result = search(&store, "label:\"signature:verified\"");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.id, "my-sbom");
+        assert_eq!(result.0[0].document.labels, signed);
+    }
+
+    #[tokio::test]
+    async fn test_files() {
+        let _ = env_logger::try_init();
+
+        let data = br#"{
+            "spdxVersion": "SPDX-2.3",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": "with-files",
+            "documentNamespace": "https://example.com/with-files",
+            "creationInfo": {"creators": ["Tool: example"], "created": "2023-06-01T10:00:00Z"},
+            "documentDescribes": ["SPDXRef-main"],
+            "packages": [{"SPDXID": "SPDXRef-main", "name": "main", "versionInfo": "1.0"}],
+            "files": [{
+                "SPDXID": "SPDXRef-file",
+                "fileName": "./usr/lib64/libssl.so.3",
+                "checksums": [{"algorithm": "SHA256", "checksumValue": "ABCDEF0123"}]
+            }]
+        }"#;
+
+        for files in [true, false] {
+            let mut store = IndexStore::new_in_memory(Index::new().with_files(files)).unwrap();
+            let mut writer = store.writer().unwrap();
+            writer.add_document(store.index_as_mut(), "with-files", data).unwrap();
+            writer.commit().unwrap();
+
+            let expected = if files { 1 } else { 0 };
+            for query in [
+                "filename:libssl.so.3",
+                "filename:usr/lib64/libssl.so.3",
+                r#"filename:"usr/lib64/libssl*""#,
+                "filedigest:abcdef0123",
+            ] {
+                assert_eq!(search(&store, query).0.len(), expected, "{query} (files: {files})");
+            }
+            assert_eq!(search(&store, "filename:libcrypto.so.3").0.len(), 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_ids() {
+        let _ = env_logger::try_init();
+
+        let data = br#"{
+            "spdxVersion": "SPDX-2.3",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": "with-part-number",
+            "documentNamespace": "https://example.com/with-part-number",
+            "creationInfo": {"creators": ["Tool: example"], "created": "2023-06-01T10:00:00Z"},
+            "documentDescribes": ["SPDXRef-main"],
+            "packages": [{"SPDXID": "SPDXRef-main", "name": "main", "versionInfo": "1.0", "annotations": [
+                {"annotator": "Tool: acme", "annotationType": "OTHER", "annotationDate": "2023-06-01T10:00:00Z", "comment": "PN-100"}
+            ]}]
+        }"#;
+        let rules = "rules:\n  - path: \"$.packages[*].annotations[?(@.annotator == 'Tool: acme')].comment\"\n";
+
+        let index = Index::new().with_custom_ids(CustomIds::from_yaml(rules).unwrap());
+        let mut store = IndexStore::new_in_memory(index).unwrap();
+        let mut writer = store.writer().unwrap();
+        writer
+            .add_document(store.index_as_mut(), "with-part-number", data)
+            .unwrap();
+        writer.commit().unwrap();
+
+        assert_eq!(search(&store, "custom:PN-100").0.len(), 1);
+        assert_eq!(search(&store, "custom:PN-200").0.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_provenance_relationships() {
+        let _ = env_logger::try_init();
+
+        let data = br#"{
+            "spdxVersion": "SPDX-2.3",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": "image",
+            "documentNamespace": "https://example.com/image",
+            "creationInfo": {"creators": ["Tool: example"], "created": "2023-06-01T10:00:00Z"},
+            "documentDescribes": ["SPDXRef-image"],
+            "packages": [
+                {"SPDXID": "SPDXRef-image", "name": "image", "externalRefs": [
+                    {"referenceCategory": "PACKAGE_MANAGER", "referenceType": "purl", "referenceLocator": "pkg:oci/image@sha256:abc"}
+                ]},
+                {"SPDXID": "SPDXRef-image-amd64", "name": "image", "externalRefs": [
+                    {"referenceCategory": "PACKAGE_MANAGER", "referenceType": "purl", "referenceLocator": "pkg:oci/image@sha256:def?arch=amd64"}
+                ]},
+                {"SPDXID": "SPDXRef-src", "name": "openssl", "externalRefs": [
+                    {"referenceCategory": "PACKAGE_MANAGER", "referenceType": "purl", "referenceLocator": "pkg:rpm/redhat/openssl@3.0.7?arch=src"}
+                ]}
+            ],
+            "relationships": [
+                {"spdxElementId": "SPDXRef-image-amd64", "relationshipType": "VARIANT_OF", "relatedSpdxElement": "SPDXRef-image"},
+                {"spdxElementId": "SPDXRef-image-amd64", "relationshipType": "GENERATED_FROM", "relatedSpdxElement": "SPDXRef-src"}
+            ]
+        }"#;
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        writer.add_document(store.index_as_mut(), "image", data).unwrap();
+        writer.commit().unwrap();
+
+        for query in [
+            r#"variantOf:"pkg:oci/image@sha256:abc""#,
+            r#"generatedFrom:"pkg:rpm/redhat/openssl@3.0.7?arch=src""#,
+            r#"generatedFrom:"pkg:rpm/redhat/openssl*""#,
+            r#"generatedFrom:"pkg:rpm/redhat/openssl@3.0.7?arch=*""#,
+        ] {
+            assert_eq!(search(&store, query).0.len(), 1, "{query}");
+        }
+        // the qualifiers of a Package URL start with a `?`, which isn't a wildcard
+        let result = search(&store, r#"generatedFrom:"pkg:rpm/redhat/openssl@3.0.7?arch=sr?""#);
+        assert_eq!(result.0.len(), 0);
+        assert_eq!(search(&store, r#"variantOf:"pkg:rpm/redhat/openssl*""#).0.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_dependents() {
+        assert_search(|index| {
+            for query in [
+                r#"dependent:"pkg:maven/io.seedwing/seedwing-java-example@1.0.0-SNAPSHOT?type=jar""#,
+                r#"dependent:"pkg:maven/io.quarkus/quarkus-arc@2.16.2.Final?type=jar""#,
+                r#"dependent:"pkg:maven/io.quarkus/quarkus-arc*""#,
+            ] {
+                let result = search(&index, query);
+                assert_eq!(result.0.len(), 1, "{query}");
+                assert_eq!(result.0[0].document.id, "my-sbom");
+            }
+
+            // packages without dependencies
+            let result = search(
+                &index,
+                r#"dependent:"pkg:maven/jakarta.inject/jakarta.inject-api@1.0?type=jar""#,
+            );
+            assert_eq!(result.0.len(), 0);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_spdx_dependents() {
+        let _ = env_logger::try_init();
+
+        let data = br#"{
+            "spdxVersion": "SPDX-2.3",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": "image",
+            "documentNamespace": "https://example.com/image",
+            "creationInfo": {"creators": ["Tool: example"], "created": "2023-06-01T10:00:00Z"},
+            "packages": [
+                {"SPDXID": "SPDXRef-image", "name": "image", "externalRefs": [
+                    {"referenceCategory": "PACKAGE_MANAGER", "referenceType": "purl", "referenceLocator": "pkg:oci/image@sha256:abc"}
+                ]},
+                {"SPDXID": "SPDXRef-app", "name": "app", "externalRefs": [
+                    {"referenceCategory": "PACKAGE_MANAGER", "referenceType": "purl", "referenceLocator": "pkg:maven/org.example/app@1"}
+                ]},
+                {"SPDXID": "SPDXRef-lib", "name": "lib", "externalRefs": [
+                    {"referenceCategory": "PACKAGE_MANAGER", "referenceType": "purl", "referenceLocator": "pkg:maven/org.example/lib@2"}
+                ]},
+                {"SPDXID": "SPDXRef-bash", "name": "bash", "externalRefs": [
+                    {"referenceCategory": "PACKAGE_MANAGER", "referenceType": "purl", "referenceLocator": "pkg:rpm/redhat/bash@5.1"}
+                ]}
+            ],
+            "relationships": [
+                {"spdxElementId": "SPDXRef-DOCUMENT", "relationshipType": "DESCRIBES", "relatedSpdxElement": "SPDXRef-image"},
+                {"spdxElementId": "SPDXRef-app", "relationshipType": "CONTAINED_BY", "relatedSpdxElement": "SPDXRef-image"},
+                {"spdxElementId": "SPDXRef-app", "relationshipType": "DEPENDS_ON", "relatedSpdxElement": "SPDXRef-lib"}
+            ]
+        }"#;
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        writer.add_document(store.index_as_mut(), "image", data).unwrap();
+        writer.commit().unwrap();
+
+        for query in [
+            r#"dependent:"pkg:oci/image@sha256:abc""#,
+            r#"dependent:"pkg:maven/org.example/app@1""#,
+        ] {
+            assert_eq!(search(&store, query).0.len(), 1, "{query}");
+        }
+        // neither depends on other packages, `bash` is a dependency of the image, as no relationship links to it
+        assert_eq!(search(&store, r#"dependent:"pkg:maven/org.example/lib@2""#).0.len(), 0);
+        assert_eq!(search(&store, r#"dependent:"pkg:rpm/redhat/bash@5.1""#).0.len(), 0);
+
+        assert_search(|index| {
+            let result = search(&index, r#"dependent:"pkg:oci/ubi9*""#);
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.id, "ubi9-sbom");
+        });
+    }
+
+    #[tokio::test]
+    async fn test_external_references() {
+        assert_search(|index| {
+            for query in [
+                r#"vcs:"https://github.com/quarkusio/quarkus""#,
+                r#"vcs:"https://github.com/netty/*""#,
+                r#"distribution:"https://*""#,
+            ] {
+                let result = search(&index, query);
+                assert_eq!(result.0.len(), 1, "{query}");
+                assert_eq!(result.0[0].document.id, "my-sbom", "{query}");
+            }
+            assert_eq!(search(&index, r#"vcs:"https://github.com/example/*""#).0.len(), 0);
+        });
+
+        let data = br#"{
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.4",
+            "version": 1,
+            "metadata": {
+                "component": {
+                    "type": "application",
+                    "name": "main",
+                    "externalReferences": [
+                        {"type": "vcs", "url": "git+https://example.com/org/main.git"},
+                        {"type": "build-system", "url": "https://ci.example.com/main/42"},
+                        {"type": "website", "url": "https://example.com"}
+                    ]
+                }
+            }
+        }"#;
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        writer.add_document(store.index_as_mut(), "main", data).unwrap();
+        writer.commit().unwrap();
+
+        for query in [
+            r#"vcs:"https://example.com/org/main""#,
+            r#"vcs:"git+https://example.com/org/main.git""#,
+            r#"buildSystem:"https://ci.example.com/main/*""#,
+        ] {
+            assert_eq!(search(&store, query).0.len(), 1, "{query}");
+        }
+        // only typed references are indexed
+        assert_eq!(search(&store, r#"distribution:"https://example.com""#).0.len(), 0);
+
+        let (hits, _) = search(&store, "main");
+        assert_eq!(
+            hits[0].document.external_references,
+            vec![
+                ExternalReference {
+                    category: "vcs".to_string(),
+                    url: "git+https://example.com/org/main.git".to_string()
+                },
+                ExternalReference {
+                    category: "build-system".to_string(),
+                    url: "https://ci.example.com/main/42".to_string()
+                },
+                ExternalReference {
+                    category: "website".to_string(),
+                    url: "https://example.com".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn vcs_url() {
+        assert_eq!(
+            normalize_vcs_url("git+https://example.com/repo.git"),
+            "https://example.com/repo"
+        );
+        assert_eq!(
+            normalize_vcs_url("https://example.com/repo/"),
+            "https://example.com/repo"
+        );
+        assert_eq!(
+            normalize_vcs_url("https://example.com/repo"),
+            "https://example.com/repo"
+        );
     }
 
     #[tokio::test]
@@ -971,4 +2167,91 @@ mod tests {
             );
         });
     }
+
+    #[tokio::test]
+    async fn test_search_fields() {
+        assert_search(|index| {
+            let fields = index.search_fields();
+            for field in &fields {
+                search(&index, &field.example);
+            }
+
+            let created = fields.iter().find(|f| f.qualifier == "created").unwrap();
+            assert!(created.sortable);
+            let license = fields.iter().find(|f| f.qualifier == "license").unwrap();
+            assert!(license.scope);
+            assert!(!license.sortable);
+        });
+    }
+
+    #[test]
+    fn test_search_fields_match_query_language() {
+        // the match is exhaustive, so a qualifier added to the query language fails to compile until it's listed here
+        macro_rules! qualifiers {
+            ($($variant:ident $(($value:pat))? => $qualifier:literal,)*) => {
+                fn qualifier(term: &Packages) -> &'static str {
+                    match term {
+                        $(Packages::$variant $(($value))? => $qualifier,)*
+                    }
+                }
+                const QUALIFIERS: &[&str] = &[$($qualifier),*];
+            };
+        }
+
+        qualifiers! {
+            Id(_) => "id",
+            Uid(_) => "uid",
+            Package(_) => "package",
+            Type(_) => "type",
+            Purl(_) => "purl",
+            Cpe(_) => "cpe",
+            Name(_) => "name",
+            Ecosystem(_) => "ecosystem",
+            Namespace(_) => "namespace",
+            Version(_) => "version",
+            Description(_) => "description",
+            Created(_) => "created",
+            IndexedTimestamp(_) => "indexedTimestamp",
+            Digest(_) => "digest",
+            License(_) => "license",
+            Supplier(_) => "supplier",
+            Qualifier(_) => "qualifier",
+            Dependency(_) => "dependency",
+            Component(_) => "component",
+            Source(_) => "source",
+            Label(_) => "label",
+            Custom(_) => "custom",
+            Filename(_) => "filename",
+            Filedigest(_) => "filedigest",
+            Vcs(_) => "vcs",
+            BuildSystem(_) => "buildSystem",
+            Distribution(_) => "distribution",
+            VariantOf(_) => "variantOf",
+            GeneratedFrom(_) => "generatedFrom",
+            Dependent(_) => "dependent",
+            Application => "application",
+            Library => "library",
+            Framework => "framework",
+            Container => "container",
+            OperatingSystem => "operatingSystem",
+            Device => "device",
+            Firmware => "firmware",
+            File => "file",
+            Archived => "archived",
+        }
+
+        let fields = Index::new().search_fields();
+        for field in &fields {
+            let query = Packages::parse(&field.example).unwrap();
+            match query.term {
+                sikula::prelude::Term::Match(term) => assert_eq!(qualifier(&term), field.qualifier),
+                term => panic!("unexpected example of {}: {term:?}", field.qualifier),
+            }
+        }
+
+        let described: Vec<_> = fields.iter().map(|field| field.qualifier.as_str()).collect();
+        for qualifier in QUALIFIERS {
+            assert!(described.contains(qualifier), "{qualifier} is not described");
+        }
+    }
 }
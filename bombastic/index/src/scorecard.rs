@@ -0,0 +1,216 @@
+//! OpenSSF Scorecard results of the source repositories of packages.
+//!
+//! The source repository of a package is derived from its Package URL: the repository of `github` and `gitlab`
+//! packages and of Go modules hosted there, or the `vcs_url` and `repository_url` qualifiers. Scorecards are only
+//! published for repositories hosted on GitHub and GitLab.
+//!
+//! The package index looks up the scores of repositories in [`Scorecards`] when indexing packages. Repositories
+//! without a known result are queued, for a collector to fetch their results. Results apply to the packages indexed
+//! after they were fetched.
+
+use core::str::FromStr;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Mutex, RwLock},
+    time::Duration,
+};
+use time::OffsetDateTime;
+
+/// The hosts Scorecard results are published for.
+const HOSTS: &[&str] = &["github.com", "gitlab.com"];
+
+/// The Package URL qualifiers pointing to the source repository of a package.
+const QUALIFIERS: &[&str] = &["vcs_url", "repository_url"];
+
+/// The source repository of a package, like `github.com/ossf/scorecard`, if it's hosted where Scorecard results are
+/// published.
+pub fn repository(purl: &str) -> Option<String> {
+    let purl = packageurl::PackageUrl::from_str(purl).ok()?;
+    let repository = match purl.ty() {
+        "github" | "gitlab" => purl
+            .namespace()
+            .and_then(|namespace| repository_of_path(&format!("{}.com/{namespace}/{}", purl.ty(), purl.name()))),
+        "golang" => purl
+            .namespace()
+            .and_then(|namespace| repository_of_path(&format!("{namespace}/{}", purl.name()))),
+        _ => None,
+    };
+    repository.or_else(|| {
+        QUALIFIERS
+            .iter()
+            .find_map(|qualifier| purl.qualifiers().get(*qualifier))
+            .and_then(|url| repository_of_url(url))
+    })
+}
+
+/// The repository of a URL, like `git+https://github.com/ossf/scorecard.git` or `git@github.com:ossf/scorecard`.
+fn repository_of_url(url: &str) -> Option<String> {
+    let url = url.strip_prefix("git+").unwrap_or(url);
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.rsplit_once('@').map_or(rest, |(_, host)| host).to_string(),
+        // scp like syntax of SSH URLs
+        None => url.split_once('@').map_or(url, |(_, host)| host).replacen(':', "/", 1),
+    };
+    repository_of_path(&path)
+}
+
+/// The repository of a path starting with the host, like `github.com/ossf/scorecard/v4`.
+fn repository_of_path(path: &str) -> Option<String> {
+    let mut segments = path.trim_end_matches('/').split('/');
+    let (host, owner, name) = (segments.next()?, segments.next()?, segments.next()?);
+    let name = name.strip_suffix(".git").unwrap_or(name);
+    if !HOSTS.contains(&host) || owner.is_empty() || name.is_empty() {
+        return None;
+    }
+    Some(format!("{host}/{owner}/{name}").to_lowercase())
+}
+
+/// The result of a repository.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Scorecard {
+    /// The aggregate score, from 0 to 10. `None` if no result is published for the repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// When the result was fetched, in seconds since the epoch
+    pub fetched: i64,
+}
+
+/// Scorecard results by repository, shared by the package index and the collector fetching them.
+#[derive(Debug, Default)]
+pub struct Scorecards {
+    results: RwLock<HashMap<String, Scorecard>>,
+    /// Repositories without a result, to be fetched
+    pending: Mutex<BTreeSet<String>>,
+}
+
+impl Scorecards {
+    /// The score of a repository, queueing the repository to be fetched if there's no result yet.
+    pub fn score(&self, repository: &str) -> Option<f64> {
+        if let Some(result) = self.results.read().expect("lock is not poisoned").get(repository) {
+            return result.score;
+        }
+        self.pending
+            .lock()
+            .expect("lock is not poisoned")
+            .insert(repository.to_string());
+        None
+    }
+
+    /// Take the repositories queued to be fetched.
+    pub fn take_pending(&self) -> Vec<String> {
+        std::mem::take(&mut *self.pending.lock().expect("lock is not poisoned"))
+            .into_iter()
+            .collect()
+    }
+
+    /// The repositories whose result was fetched longer than `max_age` ago.
+    pub fn stale(&self, max_age: Duration) -> Vec<String> {
+        let oldest = (OffsetDateTime::now_utc() - max_age).unix_timestamp();
+        self.results
+            .read()
+            .expect("lock is not poisoned")
+            .iter()
+            .filter(|(_, result)| result.fetched < oldest)
+            .map(|(repository, _)| repository.clone())
+            .collect()
+    }
+
+    /// Record the score of a repository, `None` if no result is published for it.
+    pub fn insert(&self, repository: String, score: Option<f64>) {
+        let result = Scorecard {
+            score,
+            fetched: OffsetDateTime::now_utc().unix_timestamp(),
+        };
+        self.results
+            .write()
+            .expect("lock is not poisoned")
+            .insert(repository, result);
+    }
+
+    /// Number of repositories with a result.
+    pub fn len(&self) -> usize {
+        self.results.read().expect("lock is not poisoned").len()
+    }
+
+    /// Whether there are no results.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Serialize the results, to persist them.
+    pub fn to_json(&self) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec(&*self.results.read().expect("lock is not poisoned"))
+    }
+
+    /// Restore persisted results, keeping the ones fetched meanwhile.
+    pub fn restore(&self, data: &[u8]) -> Result<(), serde_json::Error> {
+        let restored: HashMap<String, Scorecard> = serde_json::from_slice(data)?;
+        let mut results = self.results.write().expect("lock is not poisoned");
+        for (repository, result) in restored {
+            results.entry(repository).or_insert(result);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repositories() {
+        for (purl, expected) in [
+            ("pkg:github/ossf/Scorecard@v4.13.0", Some("github.com/ossf/scorecard")),
+            (
+                "pkg:gitlab/gitlab-org/gitaly@v16.0.0",
+                Some("gitlab.com/gitlab-org/gitaly"),
+            ),
+            (
+                "pkg:golang/github.com/ossf/scorecard/v4@v4.13.0",
+                Some("github.com/ossf/scorecard"),
+            ),
+            ("pkg:golang/golang.org/x/net@v0.17.0", None),
+            (
+                "pkg:maven/io.vertx/vertx-core@4.4.4?vcs_url=git%2Bhttps://github.com/eclipse-vertx/vert.x.git",
+                Some("github.com/eclipse-vertx/vert.x"),
+            ),
+            (
+                "pkg:npm/left-pad@1.3.0?repository_url=git@github.com:left-pad/left-pad.git",
+                Some("github.com/left-pad/left-pad"),
+            ),
+            ("pkg:rpm/redhat/openssl@3.0.7-18.el9_2?arch=x86_64", None),
+            (
+                "pkg:npm/internal@1.0.0?vcs_url=https://git.example.com/org/internal",
+                None,
+            ),
+        ] {
+            assert_eq!(repository(purl).as_deref(), expected, "{purl}");
+        }
+    }
+
+    #[test]
+    fn scorecards() {
+        let scorecards = Scorecards::default();
+        assert_eq!(scorecards.score("github.com/ossf/scorecard"), None);
+        assert_eq!(scorecards.score("github.com/left-pad/left-pad"), None);
+        assert_eq!(
+            scorecards.take_pending(),
+            ["github.com/left-pad/left-pad", "github.com/ossf/scorecard"]
+        );
+        assert!(scorecards.take_pending().is_empty());
+
+        scorecards.insert("github.com/ossf/scorecard".to_string(), Some(8.2));
+        scorecards.insert("github.com/left-pad/left-pad".to_string(), None);
+        assert_eq!(scorecards.score("github.com/ossf/scorecard"), Some(8.2));
+        // repositories without a result are not queued again
+        assert_eq!(scorecards.score("github.com/left-pad/left-pad"), None);
+        assert!(scorecards.take_pending().is_empty());
+        assert!(scorecards.stale(Duration::from_secs(3600)).is_empty());
+
+        let restored = Scorecards::default();
+        restored.restore(&scorecards.to_json().unwrap()).unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(restored.score("github.com/ossf/scorecard"), Some(8.2));
+    }
+}
@@ -0,0 +1,134 @@
+//! Normalization of supplier names.
+//!
+//! Suppliers are declared in many variants, like `Red Hat, Inc.`, `Red Hat Inc` or `Organization: Red Hat`. The
+//! normalized name drops the SPDX entity prefix, punctuation and legal form, and is lowercase, so all of these
+//! variants become `red hat`. Well known alternative names are mapped to a canonical one. Supplier queries match
+//! the raw value as well as the normalized one, while only the raw value is stored.
+
+use sikula::prelude::*;
+use trustification_index::{
+    create_string_query,
+    tantivy::{
+        query::{BooleanQuery, Query},
+        schema::Field,
+    },
+};
+
+/// Prefixes of SPDX supplier values, declaring the type of the entity.
+const ENTITY_PREFIXES: &[&str] = &["organization:", "person:", "tool:"];
+
+/// Legal forms, which are dropped from the end of names.
+const LEGAL_FORMS: &[&str] = &[
+    "inc",
+    "incorporated",
+    "llc",
+    "ltd",
+    "limited",
+    "corp",
+    "corporation",
+    "co",
+    "company",
+    "gmbh",
+    "ag",
+    "sa",
+    "bv",
+    "plc",
+];
+
+/// Alternative names of suppliers, and the (normalized) name they are aliases of.
+const ALIASES: &[(&str, &str)] = &[
+    ("redhat", "red hat"),
+    ("international business machines", "ibm"),
+    ("apache software foundation", "apache"),
+    ("the apache software foundation", "apache"),
+    ("eclipse foundation", "eclipse"),
+];
+
+/// Normalize a supplier name, `None` if the name doesn't declare a supplier.
+pub fn normalize(supplier: &str) -> Option<String> {
+    let mut name = supplier.trim().to_lowercase();
+    if let Some(prefix) = ENTITY_PREFIXES.iter().find(|prefix| name.starts_with(*prefix)) {
+        name = name[prefix.len()..].to_string();
+    }
+
+    // drop contact details, like in `Person: Jane Doe (jane@example.com)`
+    if let Some(index) = name.find(['(', '<']) {
+        name.truncate(index);
+    }
+
+    let mut words: Vec<&str> = name
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | '.' | ';'))
+        .filter(|word| !word.is_empty())
+        .collect();
+    while words.len() > 1 && words.last().is_some_and(|word| LEGAL_FORMS.contains(word)) {
+        words.pop();
+    }
+
+    let name = words.join(" ");
+    if name.is_empty() || name == "noassertion" || name == "none" {
+        return None;
+    }
+
+    Some(
+        ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == name)
+            .map(|(_, canonical)| canonical.to_string())
+            .unwrap_or(name),
+    )
+}
+
+/// Query suppliers by their raw or their normalized name.
+pub(crate) fn create_supplier_query(raw: Field, normalized: Field, value: &Primary<'_>) -> Box<dyn Query> {
+    let mut queries = vec![create_string_query(raw, value)];
+    match value {
+        Primary::Equal(value) => {
+            if let Some(value) = normalize(value) {
+                queries.push(create_string_query(normalized, &Primary::Equal(&value)));
+            }
+        }
+        Primary::Partial(value) => {
+            if let Some(value) = normalize(value) {
+                queries.push(create_string_query(normalized, &Primary::Partial(&value)));
+            }
+        }
+    }
+    Box::new(BooleanQuery::union(queries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variants() {
+        for variant in [
+            "Red Hat, Inc.",
+            "Red Hat Inc",
+            "Organization: Red Hat",
+            "Organization: Red Hat, Inc.",
+            "RED HAT",
+            "Redhat",
+        ] {
+            assert_eq!(normalize(variant).as_deref(), Some("red hat"), "{variant}");
+        }
+    }
+
+    #[test]
+    fn entities() {
+        assert_eq!(
+            normalize("Person: Jane Doe (jane@example.com)").as_deref(),
+            Some("jane doe")
+        );
+        assert_eq!(normalize("The Apache Software Foundation").as_deref(), Some("apache"));
+        // a legal form alone is a name
+        assert_eq!(normalize("Inc.").as_deref(), Some("inc"));
+    }
+
+    #[test]
+    fn no_supplier() {
+        assert_eq!(normalize("NOASSERTION"), None);
+        assert_eq!(normalize("Organization: "), None);
+        assert_eq!(normalize(""), None);
+    }
+}
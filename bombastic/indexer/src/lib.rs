@@ -1,18 +1,33 @@
+use std::path::PathBuf;
 use std::process::ExitCode;
 
-use bombastic_index::{packages, sbom};
-use bombastic_model::prelude::SBOM;
+use bombastic_index::{custom::CustomIds, document::ParsedSbom, packages, sbom, scorecard::Scorecards};
+use prometheus::Registry;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{mpsc, Mutex};
 use tokio::task::block_in_place;
+use trustification_auth::{
+    auth::AuthConfigArguments, authenticator::Authenticator, authorizer::Authorizer, Permission,
+};
 use trustification_event_bus::EventBusConfig;
 use trustification_index::{IndexConfig, IndexStore, WriteIndex};
-use trustification_indexer::{actix::configure, Indexer, IndexerStatus, ReindexMode};
-use trustification_infrastructure::health::checks::FailureRate;
+use trustification_indexer::{
+    actix::{configure, AdminAuth},
+    audit::Auditor,
+    batch::{BatchConfig, Batching},
+    failures::{FailuresCommand, Topics},
+    stats::IngestionStats,
+    Indexer, IndexerStatus, ReindexMode,
+};
+use trustification_infrastructure::health::checks::{FailureRate, Probe};
 use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
 use trustification_storage::{Storage, StorageConfig};
 
+mod scorecard;
+
+pub use scorecard::ScorecardConfig;
+
 #[derive(clap::Args, Debug)]
 #[command(about = "Run the indexer", args_conflicts_with_subcommands = true)]
 pub struct Run {
@@ -25,12 +40,24 @@ pub struct Run {
     #[arg(long = "failed-topic", default_value = "sbom-failed")]
     pub failed_topic: String,
 
+    /// Topic to send the events of deleted SBOMs to, once they are removed from the index
+    #[arg(long = "deleted-topic", default_value = "sbom-deleted")]
+    pub deleted_topic: String,
+
     #[arg(long = "devmode", default_value_t = false)]
     pub devmode: bool,
 
     #[arg(long = "reindex", default_value_t = ReindexMode::OnFailure)]
     pub reindex: ReindexMode,
 
+    /// Index the names and SHA256 digests of the files of SPDX documents, which may grow the index considerably
+    #[arg(long = "index-files", env = "INDEX_FILES", default_value_t = false)]
+    pub index_files: bool,
+
+    /// YAML file configuring the rules extracting custom identifiers of vendors, searchable using `custom:`
+    #[arg(long = "custom-id-config", env = "CUSTOM_ID_CONFIG")]
+    pub custom_id_config: Option<PathBuf>,
+
     #[command(flatten)]
     pub bus: EventBusConfig,
 
@@ -42,13 +69,54 @@ pub struct Run {
 
     #[command(flatten)]
     pub index: IndexConfig,
+
+    #[command(flatten)]
+    pub batch: BatchConfig,
+
+    #[command(flatten)]
+    pub scorecard: ScorecardConfig,
+
+    /// Authentication of the administrative endpoints, like `POST /admin/reindex`
+    #[command(flatten)]
+    pub auth: AuthConfigArguments,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum Command {
+    #[command(subcommand)]
+    Failures(FailuresCommand),
 }
 
 impl Run {
     pub async fn run(self) -> anyhow::Result<ExitCode> {
+        if let Some(Command::Failures(command)) = self.command {
+            let topics = Topics {
+                stored: &self.stored_topic,
+                failed: &self.failed_topic,
+            };
+            return command.run("bombastic", topics).await;
+        }
+
+        let (authn, authz) = self.auth.split(self.devmode)?.unzip();
+        let admin = AdminAuth {
+            authenticator: Authenticator::from_config(authn).await?.map(Arc::new),
+            authorizer: Authorizer::new(authz),
+            permission: Permission::UpdateSbom,
+        };
+        if admin.authenticator.is_none() {
+            log::warn!("Authentication is disabled");
+        }
+
         let (command_sender, command_receiver) = mpsc::channel(1);
         let status = Arc::new(Mutex::new(IndexerStatus::Running));
         let s = status.clone();
+        let ingestion = Arc::new(Mutex::new(IngestionStats::default()));
+        let i = ingestion.clone();
+        let audit = Arc::new(Mutex::new(None));
+        let a = audit.clone();
         let c = command_sender.clone();
         let storage = self.storage.clone();
         Infrastructure::from(self.infra)
@@ -56,13 +124,29 @@ impl Run {
                 "bombastic-indexer",
                 |_context| async { Ok(()) },
                 |context| async move {
-                    let sbom_index: Box<dyn WriteIndex<Document = (SBOM, String)>> = Box::new(sbom::Index::new());
+                    let custom_ids = CustomIds::load(self.custom_id_config.as_deref())?;
+                    let sbom_index: Box<dyn WriteIndex<Document = (ParsedSbom, String, Vec<String>)>> = Box::new(
+                        sbom::Index::new()
+                            .with_files(self.index_files)
+                            .with_custom_ids(custom_ids),
+                    );
                     let sbom_store = block_in_place(|| {
                         IndexStore::new(&self.storage, &self.index, sbom_index, context.metrics.registry())
                     })?;
 
-                    let package_index: Box<dyn WriteIndex<Document = (SBOM, String)>> =
-                        Box::new(packages::Index::new());
+                    let mut package_index = packages::Index::new();
+                    if self.scorecard.enabled {
+                        let scorecards = Arc::new(Scorecards::default());
+                        package_index = package_index.with_scorecards(scorecards.clone());
+                        let storage = Storage::new(
+                            self.storage.clone().process("bombastic", self.devmode),
+                            &Registry::new(),
+                        )?;
+                        let collector = scorecard::Collector::new(&self.scorecard, scorecards, storage);
+                        tokio::spawn(collector.run());
+                    }
+                    let package_index: Box<dyn WriteIndex<Document = (ParsedSbom, String, Vec<String>)>> =
+                        Box::new(package_index);
                     let package_store = block_in_place(|| {
                         IndexStore::new(&self.storage, &self.index, package_index, context.metrics.registry())
                     })?;
@@ -77,6 +161,12 @@ impl Run {
                     let state = check.handle();
                     context.health.liveness.register("index_state", check).await;
 
+                    let batching =
+                        Batching::new(&self.batch, self.index.sync_interval.into(), context.metrics.registry())?;
+
+                    let (ready, ready_check) = Probe::new("Indexer paused");
+                    context.health.readiness.register("indexer.paused", ready_check).await;
+
                     let mut indexer = Indexer {
                         indexes: vec![sbom_store, package_store],
                         storage,
@@ -84,17 +174,22 @@ impl Run {
                         stored_topic: self.stored_topic.as_str(),
                         indexed_topic: self.indexed_topic.as_str(),
                         failed_topic: self.failed_topic.as_str(),
+                        deleted_topic: Some(self.deleted_topic.as_str()),
                         sync_interval: self.index.sync_interval.into(),
                         status: s.clone(),
                         commands: command_receiver,
                         command_sender: c,
                         reindex: self.reindex,
                         state,
+                        ingestion: i,
+                        batching,
+                        ready,
+                        audit: Auditor::new(a, context.metrics.registry())?,
                     };
                     indexer.run().await
                 },
                 move |config| {
-                    configure(status, command_sender, config);
+                    configure(status, command_sender, ingestion, audit, Some(admin), config);
                 },
             )
             .await?;
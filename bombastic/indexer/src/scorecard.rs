@@ -0,0 +1,145 @@
+//! Collection of the OpenSSF Scorecard results of the source repositories of indexed packages.
+//!
+//! The package index queues the repositories it has no result for. The collector fetches their results from the
+//! Scorecard API, and refreshes results once they are older than the maximum age. Results are persisted with the
+//! statistics of the storage, so they survive restarts of the indexer.
+
+use bombastic_index::scorecard::Scorecards;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::{sync::Arc, time::Duration};
+use tokio::time::MissedTickBehavior;
+use trustification_storage::Storage;
+use url::Url;
+
+/// Name of the persisted results, in the statistics of the storage.
+const STATS_NAME: &str = "scorecards.json";
+
+/// Configuration of the collection of Scorecard results.
+#[derive(Clone, Debug, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "OpenSSF Scorecard")]
+pub struct ScorecardConfig {
+    /// Collect the OpenSSF Scorecard results of the source repositories of packages, adding their scores to the
+    /// package index.
+    #[arg(env = "SCORECARD_ENABLED", long = "scorecard-enabled", default_value_t = false)]
+    pub enabled: bool,
+
+    /// URL of the Scorecard API.
+    #[arg(
+        env = "SCORECARD_API_URL",
+        long = "scorecard-api-url",
+        default_value = "https://api.securityscorecards.dev"
+    )]
+    pub api_url: Url,
+
+    /// Interval of fetching the results of newly seen repositories.
+    #[arg(env = "SCORECARD_INTERVAL", long = "scorecard-interval", default_value = "1m")]
+    pub interval: humantime::Duration,
+
+    /// Age after which results are fetched again.
+    #[arg(env = "SCORECARD_MAX_AGE", long = "scorecard-max-age", default_value = "7d")]
+    pub max_age: humantime::Duration,
+}
+
+impl Default for ScorecardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_url: Url::parse("https://api.securityscorecards.dev").expect("default URL is valid"),
+            interval: Duration::from_secs(60).into(),
+            max_age: Duration::from_secs(7 * 24 * 60 * 60).into(),
+        }
+    }
+}
+
+/// The parts of a result of the Scorecard API which are used.
+#[derive(Deserialize)]
+struct ScorecardResult {
+    score: f64,
+}
+
+/// Fetches the results of the repositories queued by the package index.
+pub struct Collector {
+    client: reqwest::Client,
+    api_url: Url,
+    interval: Duration,
+    max_age: Duration,
+    scorecards: Arc<Scorecards>,
+    storage: Storage,
+}
+
+impl Collector {
+    pub fn new(config: &ScorecardConfig, scorecards: Arc<Scorecards>, storage: Storage) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            api_url: config.api_url.clone(),
+            interval: config.interval.into(),
+            max_age: config.max_age.into(),
+            scorecards,
+            storage,
+        }
+    }
+
+    pub async fn run(self) {
+        match self.storage.get_stats(STATS_NAME).await {
+            Ok(Some(data)) => match self.scorecards.restore(&data) {
+                Ok(()) => log::info!("Restored Scorecard results of {} repositories", self.scorecards.len()),
+                Err(e) => log::warn!("Unable to restore Scorecard results: {e}"),
+            },
+            Ok(None) => {}
+            Err(e) => log::warn!("Unable to load Scorecard results: {e}"),
+        }
+
+        let mut interval = tokio::time::interval(self.interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            interval.tick().await;
+            self.collect().await;
+        }
+    }
+
+    /// Fetch the results of the queued repositories, and refresh the stale ones.
+    async fn collect(&self) {
+        let mut repositories = self.scorecards.take_pending();
+        repositories.extend(self.scorecards.stale(self.max_age));
+        if repositories.is_empty() {
+            return;
+        }
+
+        let mut fetched = 0;
+        for repository in repositories {
+            match self.fetch(&repository).await {
+                Ok(score) => {
+                    self.scorecards.insert(repository, score);
+                    fetched += 1;
+                }
+                // queued again once a package of the repository is indexed
+                Err(e) => log::warn!("Unable to fetch the Scorecard result of {repository}: {e}"),
+            }
+        }
+        log::info!("Fetched the Scorecard results of {fetched} repositories");
+
+        let stored = match self.scorecards.to_json() {
+            Ok(data) => self
+                .storage
+                .put_stats(STATS_NAME, &data)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = stored {
+            log::warn!("Unable to store Scorecard results: {e}");
+        }
+    }
+
+    /// Fetch the score of a repository, `None` if there's no result for it.
+    async fn fetch(&self, repository: &str) -> Result<Option<f64>, reqwest::Error> {
+        let url = format!("{}/projects/{repository}", self.api_url.as_str().trim_end_matches('/'));
+        let response = self.client.get(url).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let result: ScorecardResult = response.error_for_status()?.json().await?;
+        Ok(Some(result.score))
+    }
+}
@@ -0,0 +1,69 @@
+//! The changes feed of SBOMs, listing the SBOMs in the order they were indexed.
+//!
+//! Clients, like the federation of another instance, page through the feed using the cursor of the last page, and
+//! retrieve the SBOMs which changed. The feed is derived from the search index, so SBOMs appear once they are indexed,
+//! and again when they are stored again or reindexed. Deleted SBOMs aren't part of the feed.
+
+/// An SBOM which was indexed.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct Change {
+    /// SBOM (storage) identifier
+    pub id: String,
+    /// SHA256 digest of the SBOM, as it was indexed
+    pub sha256: String,
+    /// The time the SBOM was indexed, in nanoseconds since the epoch
+    pub indexed_timestamp: i64,
+    /// The labels of the SBOM, as indexed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+}
+
+/// A page of the changes feed.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct Changes {
+    /// The SBOMs indexed after the requested cursor, oldest first
+    pub changes: Vec<Change>,
+    /// The cursor to request the next page with, which is the requested one if there were no changes
+    pub next: i64,
+    /// Whether there are more changes after this page
+    pub more: bool,
+}
+
+impl Changes {
+    /// Create the page of the changes after the cursor `since`, out of `total` changes.
+    pub fn new(since: i64, changes: Vec<Change>, total: usize) -> Self {
+        let next = changes.last().map(|change| change.indexed_timestamp).unwrap_or(since);
+        let more = total > changes.len();
+        Self { changes, next, more }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn change(id: &str, indexed_timestamp: i64) -> Change {
+        Change {
+            id: id.to_string(),
+            sha256: "e3b0c442".to_string(),
+            indexed_timestamp,
+            labels: vec![],
+        }
+    }
+
+    #[test]
+    fn cursor() {
+        let page = Changes::new(10, vec![change("a", 20), change("b", 30)], 3);
+        assert_eq!(page.next, 30);
+        assert!(page.more);
+
+        let page = Changes::new(10, vec![change("a", 20)], 1);
+        assert_eq!(page.next, 20);
+        assert!(!page.more);
+
+        // without changes, the client keeps its cursor
+        let page = Changes::new(10, vec![], 0);
+        assert_eq!(page.next, 10);
+        assert!(!page.more);
+    }
+}
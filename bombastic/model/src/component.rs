@@ -0,0 +1,55 @@
+use time::OffsetDateTime;
+
+/// Where a component appears across all SBOMs.
+///
+/// A component is a package identified by its canonical package URL, the package URL without version, qualifiers
+/// and subpath, so the same component is found in every SBOM containing any of its versions.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
+pub struct ComponentUsage {
+    /// Stable identifier of the component, derived from its canonical package URL
+    pub id: String,
+    /// Canonical package URL of the component, unknown if no SBOM contains it
+    pub purl: Option<String>,
+    /// Number of SBOMs containing the component
+    pub total: usize,
+    /// Lowest version of the component found in any SBOM
+    pub min_version: Option<String>,
+    /// Highest version of the component found in any SBOM
+    pub max_version: Option<String>,
+    /// Versions of the component, ordered from lowest to highest, and the number of SBOMs containing them
+    pub versions: Vec<ComponentVersion>,
+    /// Number of SBOMs containing the component, by month of their creation
+    pub trend: Vec<ComponentTrend>,
+    /// SBOMs containing the component, most recently created first (within offset and limit requested)
+    pub sboms: Vec<ComponentSbom>,
+}
+
+/// A version of a component and the number of SBOMs containing it.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, Eq, utoipa::ToSchema)]
+pub struct ComponentVersion {
+    pub version: String,
+    pub sboms: usize,
+}
+
+/// The number of SBOMs containing a component, created in a month.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, Eq, utoipa::ToSchema)]
+pub struct ComponentTrend {
+    /// Month of the creation of the SBOMs, like `2023-05`
+    pub month: String,
+    pub sboms: usize,
+}
+
+/// An SBOM containing a component.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
+pub struct ComponentSbom {
+    /// SBOM (storage) identifier
+    pub id: String,
+    /// SBOM name
+    pub name: String,
+    /// SBOM creation time in RFC3339 format
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    #[schema(value_type = Option<String>)]
+    pub created: Option<OffsetDateTime>,
+    /// Versions of the component contained in the SBOM
+    pub versions: Vec<String>,
+}
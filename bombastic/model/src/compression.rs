@@ -0,0 +1,124 @@
+//! Transparent decompression of SBOMs, which are often published compressed, like `.json.bz2` or `.json.gz` files.
+
+use std::{borrow::Cow, io::Read};
+
+/// Maximum size of a decompressed SBOM, unless a different one is given.
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// A compression format, detected from the magic number of the data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Bzip2,
+    Zstd,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("decompressed data exceeds the limit of {0} bytes")]
+    TooLarge(u64),
+    #[error("invalid {0} data: {1}")]
+    Invalid(&'static str, std::io::Error),
+}
+
+impl Compression {
+    /// Detect the compression of data from its first bytes, `None` if it isn't compressed.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::Gzip)
+        } else if data.starts_with(b"BZh") {
+            Some(Self::Bzip2)
+        } else if data.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Self::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// The name of the compression as content encoding.
+    pub fn content_encoding(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Bzip2 => "bzip2",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// Decompress data, failing once the decompressed data exceeds `max` bytes.
+    pub fn decompress(&self, data: &[u8], max: u64) -> Result<Vec<u8>, Error> {
+        let invalid = |e| Error::Invalid(self.content_encoding(), e);
+        let reader: Box<dyn Read + '_> = match self {
+            Self::Gzip => Box::new(flate2::read::MultiGzDecoder::new(data)),
+            Self::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(data)),
+            Self::Zstd => Box::new(zstd::stream::read::Decoder::new(data).map_err(invalid)?),
+        };
+        // reading a byte more than allowed tells whether the limit was exceeded, without decompressing everything
+        let mut out = Vec::new();
+        reader
+            .take(max.saturating_add(1))
+            .read_to_end(&mut out)
+            .map_err(invalid)?;
+        if out.len() as u64 > max {
+            return Err(Error::TooLarge(max));
+        }
+        Ok(out)
+    }
+}
+
+/// Decompress data if it's compressed, returning it as it is otherwise.
+pub fn decompress(data: &[u8], max: u64) -> Result<Cow<'_, [u8]>, Error> {
+    match Compression::detect(data) {
+        Some(compression) => compression.decompress(data, max).map(Cow::Owned),
+        None => Ok(Cow::Borrowed(data)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Write;
+
+    const DATA: &[u8] = br#"{"spdxVersion": "SPDX-2.3"}"#;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        enc.write_all(data).unwrap();
+        enc.finish().unwrap()
+    }
+
+    fn bzip2(data: &[u8]) -> Vec<u8> {
+        let mut enc = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        enc.write_all(data).unwrap();
+        enc.finish().unwrap()
+    }
+
+    #[test]
+    fn test_decompress() {
+        for (compression, data) in [
+            (Compression::Gzip, gzip(DATA)),
+            (Compression::Bzip2, bzip2(DATA)),
+            (Compression::Zstd, zstd::encode_all(DATA, 3).unwrap()),
+        ] {
+            assert_eq!(Compression::detect(&data), Some(compression));
+            assert_eq!(decompress(&data, DATA.len() as u64).unwrap(), DATA);
+        }
+
+        assert_eq!(Compression::detect(DATA), None);
+        assert!(matches!(decompress(DATA, 0).unwrap(), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_limit() {
+        // highly compressible, like a zip bomb
+        let data = gzip(&vec![b' '; 1024 * 1024]);
+        assert!(data.len() < 4096);
+        assert!(matches!(decompress(&data, 4096), Err(Error::TooLarge(4096))));
+
+        // truncated
+        let data = bzip2(DATA);
+        assert!(matches!(
+            decompress(&data[..data.len() / 2], DEFAULT_MAX_DECOMPRESSED_SIZE),
+            Err(Error::Invalid("bzip2", _))
+        ));
+    }
+}
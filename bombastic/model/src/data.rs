@@ -15,8 +15,12 @@ pub enum SBOM {
 
 #[derive(Debug, Default)]
 pub struct Error {
+    #[cfg(feature = "compression")]
+    compression: Option<crate::compression::Error>,
     #[cfg(feature = "cyclonedx-bom")]
     cyclonedx: Option<cyclonedx_bom::errors::JsonReadError>,
+    #[cfg(feature = "cyclonedx-bom")]
+    cyclonedx_xml: Option<crate::xml::Error>,
     #[cfg(feature = "spdx-rs")]
     spdx: Option<serde_json::Error>,
 }
@@ -25,12 +29,23 @@ impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "Error parsing SBOM (")?;
         let mut first = true;
+        #[cfg(feature = "compression")]
+        {
+            if let Some(err) = &self.compression {
+                write!(f, "compression: {}", err)?;
+                first = false;
+            }
+        }
         #[cfg(feature = "cyclonedx-bom")]
         {
             if let Some(err) = &self.cyclonedx {
                 write!(f, "CycloneDX: {}", err)?;
                 first = false;
             }
+            if let Some(err) = &self.cyclonedx_xml {
+                write!(f, "CycloneDX XML: {}", err)?;
+                first = false;
+            }
         }
         #[cfg(feature = "spdx-rs")]
         {
@@ -49,10 +64,50 @@ impl std::fmt::Display for Error {
 impl std::error::Error for Error {}
 
 impl SBOM {
-    #[instrument(skip_all, fields(data_len={data.len()}), err)]
+    /// Parse an SBOM, decompressing it first if it's compressed.
+    #[cfg(feature = "compression")]
+    pub fn parse(data: &[u8]) -> Result<Self, Error> {
+        Self::parse_with_max_size(data, crate::compression::DEFAULT_MAX_DECOMPRESSED_SIZE)
+    }
+
+    #[cfg(not(feature = "compression"))]
     pub fn parse(data: &[u8]) -> Result<Self, Error> {
+        Self::parse_decompressed(data)
+    }
+
+    /// Parse an SBOM, which may be compressed, failing if it exceeds `max` bytes once decompressed.
+    #[cfg(feature = "compression")]
+    #[instrument(skip_all, fields(data_len={data.len()}), err)]
+    pub fn parse_with_max_size(data: &[u8], max: u64) -> Result<Self, Error> {
+        match crate::compression::decompress(data, max) {
+            Ok(data) => Self::parse_decompressed(&data),
+            Err(e) => {
+                log::error!("Error decompressing SBOM: {:?}", e);
+                Err(Error {
+                    compression: Some(e),
+                    ..Default::default()
+                })
+            }
+        }
+    }
+
+    #[instrument(skip_all, fields(data_len={data.len()}), err)]
+    fn parse_decompressed(data: &[u8]) -> Result<Self, Error> {
         let mut err: Error = Default::default();
 
+        #[cfg(feature = "cyclonedx-bom")]
+        if crate::xml::is_xml(data) {
+            let result = info_span!("parse cyclonedx xml").in_scope(|| crate::xml::parse(data));
+            match result.and_then(|bom| Self::validate_cyclonedx(bom).map_err(crate::xml::Error::Invalid)) {
+                Ok(bom) => return Ok(SBOM::CycloneDX(bom)),
+                Err(e) => {
+                    log::error!("Error parsing CycloneDX XML: {:?}", e);
+                    err.cyclonedx_xml = Some(e);
+                    return Err(err);
+                }
+            }
+        }
+
         #[cfg(feature = "spdx-rs")]
         {
             let result = info_span!("parse spdx").in_scope(|| serde_json::from_slice::<spdx_rs::models::SPDX>(data));
@@ -69,43 +124,11 @@ impl SBOM {
         {
             let result = info_span!("parse cyclonedx").in_scope(|| cyclonedx_bom::prelude::Bom::parse_from_json(data));
             match result {
-                // check the serial number has a value
-                Ok(bom) => match bom.serial_number {
-                    // then validate the SBOM itself
-                    // having checked the serial number is available before validating is mandatory
-                    // because it's an optional field in specs and the validation will succeed if
-                    // the serial number is missing and this isn't what we want because
-                    // serial number is mandatory for trustification to correlate properly
-                    Some(_) => {
-                        let result = bom.validate();
-                        match result.passed() {
-                            true => return Ok(SBOM::CycloneDX(bom)),
-                            false => {
-                                let all_reasons = Self::get_validation_error_messages(result.clone())
-                                    .into_iter()
-                                    // Ignore normalizedstring errors
-                                    // until https://github.com/CycloneDX/cyclonedx-rust-cargo/issues/737 is fixed
-                                    .filter(|reason| {
-                                        reason != "NormalizedString contains invalid characters \\r \\n \\t or \\r\\n"
-                                    })
-                                    .collect::<Vec<String>>()
-                                    .join(", ");
-                                if all_reasons.is_empty() {
-                                    return Ok(SBOM::CycloneDX(bom));
-                                } else {
-                                    log::error!("Error validating CycloneDX: {}", all_reasons);
-                                    let validation_failed: serde_json::Error = serde::de::Error::custom(all_reasons);
-                                    err.cyclonedx = Some(JsonReadError::from(validation_failed));
-                                }
-                            }
-                        }
-                    }
-                    None => {
-                        let serial_number_error_message = "Error validating CycloneDX: In order for a CycloneDX SBOM to be successfully ingested the 'serialNumber' field must be populated.";
-                        log::error!("{}", serial_number_error_message);
-                        let serial_number_error: serde_json::Error =
-                            serde::de::Error::custom(serial_number_error_message);
-                        err.cyclonedx = Some(JsonReadError::from(serial_number_error));
+                Ok(bom) => match Self::validate_cyclonedx(bom) {
+                    Ok(bom) => return Ok(SBOM::CycloneDX(bom)),
+                    Err(message) => {
+                        let validation_failed: serde_json::Error = serde::de::Error::custom(message);
+                        err.cyclonedx = Some(JsonReadError::from(validation_failed));
                     }
                 },
                 Err(e) => {
@@ -118,6 +141,40 @@ impl SBOM {
         Err(err)
     }
 
+    /// Check a CycloneDX document has a serial number and is valid, returning the reasons if it isn't.
+    #[cfg(feature = "cyclonedx-bom")]
+    fn validate_cyclonedx(bom: cyclonedx_bom::prelude::Bom) -> Result<cyclonedx_bom::prelude::Bom, String> {
+        // check the serial number has a value
+        // then validate the SBOM itself
+        // having checked the serial number is available before validating is mandatory
+        // because it's an optional field in specs and the validation will succeed if
+        // the serial number is missing and this isn't what we want because
+        // serial number is mandatory for trustification to correlate properly
+        if bom.serial_number.is_none() {
+            let serial_number_error_message = "Error validating CycloneDX: In order for a CycloneDX SBOM to be successfully ingested the 'serialNumber' field must be populated.";
+            log::error!("{}", serial_number_error_message);
+            return Err(serial_number_error_message.to_string());
+        }
+
+        let result = bom.validate();
+        if result.passed() {
+            return Ok(bom);
+        }
+        let all_reasons = Self::get_validation_error_messages(result)
+            .into_iter()
+            // Ignore normalizedstring errors
+            // until https://github.com/CycloneDX/cyclonedx-rust-cargo/issues/737 is fixed
+            .filter(|reason| reason != "NormalizedString contains invalid characters \\r \\n \\t or \\r\\n")
+            .collect::<Vec<String>>()
+            .join(", ");
+        if all_reasons.is_empty() {
+            Ok(bom)
+        } else {
+            log::error!("Error validating CycloneDX: {}", all_reasons);
+            Err(all_reasons)
+        }
+    }
+
     fn get_validation_error_messages(validation_result: ValidationResult) -> HashSet<String> {
         let mut result = HashSet::<String>::new();
         validation_result.errors().for_each(|(_, error_kind)| match error_kind {
@@ -149,7 +206,7 @@ impl SBOM {
             #[cfg(feature = "spdx-rs")]
             Self::SPDX(sbom) => format!("SPDX/{}", sbom.document_creation_information.spdx_version),
             #[cfg(feature = "cyclonedx-bom")]
-            Self::CycloneDX(_) => "CycloneDX/1.3".to_string(),
+            Self::CycloneDX(bom) => format!("CycloneDX/{}", bom.spec_version),
         }
     }
 }
@@ -165,6 +222,21 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[cfg(feature = "compression")]
+    #[test]
+    fn parse_compressed() {
+        for data in [
+            &include_bytes!("../../testdata/ubi8-valid.json.bz2")[..],
+            &include_bytes!("../../testdata/ubi8-valid.json.zst")[..],
+        ] {
+            assert!(SBOM::parse(data).is_ok());
+        }
+
+        let data = include_bytes!("../../testdata/bigjunk.bz2");
+        let e = SBOM::parse_with_max_size(data, 1024).unwrap_err();
+        assert!(e.to_string().contains("exceeds the limit of 1024 bytes"), "{e}");
+    }
+
     #[test]
     fn parse_cyclonedx_valid_14() {
         let data = include_bytes!("../../testdata/syft.cyclonedx.json");
@@ -179,6 +251,21 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn cyclonedx_spec_version() {
+        let versions: [(&[u8], &str); 3] = [
+            (include_bytes!("../../testdata/my-sbom.json"), "CycloneDX/1.3"),
+            (include_bytes!("../../testdata/syft.cyclonedx.json"), "CycloneDX/1.4"),
+            (
+                include_bytes!("../../testdata/syft.cyclonedx-1.5.json"),
+                "CycloneDX/1.5",
+            ),
+        ];
+        for (data, expected) in versions {
+            assert_eq!(SBOM::parse(data).unwrap().type_str(), expected);
+        }
+    }
+
     #[test]
     fn parse_cdx_valid_15_license_id() {
         let data = include_bytes!("../../testdata/cdx-1.5-valid-license-id.json");
@@ -0,0 +1,346 @@
+//! The graph of the packages of an SBOM, linked by typed edges.
+//!
+//! Besides dependencies, product SBOMs link their packages to the ones they were built from, like an image to its
+//! sources, using the SPDX `VARIANT_OF` and `GENERATED_FROM` relationships. The graph keeps those apart, so that
+//! consumers can trace binaries back to their sources.
+
+use serde_json::Value;
+use std::collections::{HashSet, VecDeque};
+
+/// The packages of an SBOM and the edges between them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct SbomGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// A package of an SBOM.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct GraphNode {
+    /// Identifier of the package in the SBOM: its SPDX identifier or CycloneDX `bom-ref`
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purl: Option<String>,
+}
+
+/// An edge from a package to another one.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    #[serde(rename = "type")]
+    pub edge_type: EdgeType,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EdgeType {
+    /// The source depends on the target
+    DependsOn,
+    /// The source contains the target, like an image its packages
+    Contains,
+    /// The source is a variant of the target, like an image of a product for one architecture
+    VariantOf,
+    /// The source was generated from the target, like a binary from its sources
+    GeneratedFrom,
+}
+
+/// The packages depending on a package of an SBOM.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct Dependents {
+    pub purl: String,
+    /// Packages depending on the package itself
+    pub direct: Vec<GraphNode>,
+    /// Packages depending on the package through other ones
+    pub transitive: Vec<GraphNode>,
+}
+
+impl EdgeType {
+    /// The edge of an SPDX relationship between an element and a related one, as source, type and target.
+    ///
+    /// Inverse relationships, like `DEPENDENCY_OF`, are turned around. Relationships which are not part of the graph
+    /// return `None`.
+    pub fn from_spdx<'a>(
+        relationship_type: &str,
+        element: &'a str,
+        related: &'a str,
+    ) -> Option<(&'a str, Self, &'a str)> {
+        match relationship_type {
+            "DEPENDS_ON" => Some((element, Self::DependsOn, related)),
+            "DEPENDENCY_OF" => Some((related, Self::DependsOn, element)),
+            "CONTAINS" => Some((element, Self::Contains, related)),
+            "CONTAINED_BY" => Some((related, Self::Contains, element)),
+            "VARIANT_OF" => Some((element, Self::VariantOf, related)),
+            "GENERATED_FROM" => Some((element, Self::GeneratedFrom, related)),
+            "GENERATES" => Some((related, Self::GeneratedFrom, element)),
+            _ => None,
+        }
+    }
+
+    /// Whether the edge traces a package back to the one it was built from.
+    pub fn is_provenance(&self) -> bool {
+        matches!(self, Self::VariantOf | Self::GeneratedFrom)
+    }
+
+    /// Whether the source requires the target, by depending on it or containing it.
+    pub fn is_dependency(&self) -> bool {
+        matches!(self, Self::DependsOn | Self::Contains)
+    }
+}
+
+impl SbomGraph {
+    /// Build the graph of an SPDX or CycloneDX document.
+    ///
+    /// Edges whose ends are not packages of the document, like references to other documents, are dropped.
+    pub fn from_json(document: &Value) -> Self {
+        let mut graph = if document.get("spdxVersion").is_some() {
+            Self::from_spdx(document)
+        } else {
+            Self::from_cyclonedx(document)
+        };
+
+        let ids: HashSet<&str> = graph.nodes.iter().map(|node| node.id.as_str()).collect();
+        let edges = std::mem::take(&mut graph.edges);
+        graph.edges = edges
+            .into_iter()
+            .filter(|edge| ids.contains(edge.source.as_str()) && ids.contains(edge.target.as_str()))
+            .collect();
+        graph
+    }
+
+    /// The packages depending on, or containing, the packages with a Package URL, directly or transitively.
+    ///
+    /// Returns `None` if no package of the graph has the Package URL.
+    pub fn dependents(&self, purl: &str) -> Option<Dependents> {
+        let targets: Vec<&str> = self
+            .nodes
+            .iter()
+            .filter(|node| node.purl.as_deref() == Some(purl))
+            .map(|node| node.id.as_str())
+            .collect();
+        if targets.is_empty() {
+            return None;
+        }
+
+        let mut seen: HashSet<&str> = targets.iter().copied().collect();
+        let mut queue: VecDeque<(&str, usize)> = targets.into_iter().map(|id| (id, 0)).collect();
+        let mut direct = HashSet::new();
+        let mut transitive = HashSet::new();
+        while let Some((target, depth)) = queue.pop_front() {
+            for edge in &self.edges {
+                if !edge.edge_type.is_dependency() || edge.target != target || !seen.insert(edge.source.as_str()) {
+                    continue;
+                }
+                if depth == 0 {
+                    direct.insert(edge.source.as_str());
+                } else {
+                    transitive.insert(edge.source.as_str());
+                }
+                queue.push_back((edge.source.as_str(), depth + 1));
+            }
+        }
+
+        let nodes = |ids: HashSet<&str>| {
+            self.nodes
+                .iter()
+                .filter(|node| ids.contains(node.id.as_str()))
+                .cloned()
+                .collect()
+        };
+        Some(Dependents {
+            purl: purl.to_string(),
+            direct: nodes(direct),
+            transitive: nodes(transitive),
+        })
+    }
+
+    fn from_spdx(document: &Value) -> Self {
+        let nodes = array(document, "packages")
+            .filter_map(|package| {
+                let purl = array(package, "externalRefs")
+                    .find(|r| r["referenceType"] == "purl")
+                    .and_then(|r| text(r, "referenceLocator"));
+                Some(GraphNode {
+                    id: text(package, "SPDXID")?,
+                    name: text(package, "name").unwrap_or_default(),
+                    version: text(package, "versionInfo"),
+                    purl,
+                })
+            })
+            .collect();
+
+        let edges = array(document, "relationships")
+            .filter_map(|relationship| {
+                let (source, edge_type, target) = EdgeType::from_spdx(
+                    relationship["relationshipType"].as_str()?,
+                    relationship["spdxElementId"].as_str()?,
+                    relationship["relatedSpdxElement"].as_str()?,
+                )?;
+                Some(GraphEdge {
+                    source: source.to_string(),
+                    target: target.to_string(),
+                    edge_type,
+                })
+            })
+            .collect();
+
+        Self { nodes, edges }
+    }
+
+    fn from_cyclonedx(document: &Value) -> Self {
+        fn collect(component: &Value, nodes: &mut Vec<GraphNode>) {
+            if let Some(id) = text(component, "bom-ref") {
+                nodes.push(GraphNode {
+                    id,
+                    name: text(component, "name").unwrap_or_default(),
+                    version: text(component, "version"),
+                    purl: text(component, "purl"),
+                });
+            }
+            for component in array(component, "components") {
+                collect(component, nodes);
+            }
+        }
+
+        let mut nodes = Vec::new();
+        collect(&document["metadata"]["component"], &mut nodes);
+        for component in array(document, "components") {
+            collect(component, &mut nodes);
+        }
+
+        let mut edges = Vec::new();
+        for dependency in array(document, "dependencies") {
+            let Some(source) = text(dependency, "ref") else {
+                continue;
+            };
+            for target in array(dependency, "dependsOn").filter_map(Value::as_str) {
+                edges.push(GraphEdge {
+                    source: source.clone(),
+                    target: target.to_string(),
+                    edge_type: EdgeType::DependsOn,
+                });
+            }
+        }
+
+        Self { nodes, edges }
+    }
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> impl Iterator<Item = &'a Value> {
+    value[key].as_array().into_iter().flatten()
+}
+
+fn text(value: &Value, key: &str) -> Option<String> {
+    value[key].as_str().map(ToString::to_string)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn spdx() {
+        let document = json!({
+            "spdxVersion": "SPDX-2.3",
+            "packages": [
+                {"SPDXID": "SPDXRef-image", "name": "image", "versionInfo": "1.0", "externalRefs": [
+                    {"referenceCategory": "PACKAGE_MANAGER", "referenceType": "purl", "referenceLocator": "pkg:oci/image@sha256:abc"}
+                ]},
+                {"SPDXID": "SPDXRef-image-amd64", "name": "image"},
+                {"SPDXID": "SPDXRef-src", "name": "image-sources"},
+                {"SPDXID": "SPDXRef-dep", "name": "dep"}
+            ],
+            "relationships": [
+                {"spdxElementId": "SPDXRef-DOCUMENT", "relationshipType": "DESCRIBES", "relatedSpdxElement": "SPDXRef-image"},
+                {"spdxElementId": "SPDXRef-image-amd64", "relationshipType": "VARIANT_OF", "relatedSpdxElement": "SPDXRef-image"},
+                {"spdxElementId": "SPDXRef-src", "relationshipType": "GENERATES", "relatedSpdxElement": "SPDXRef-image-amd64"},
+                {"spdxElementId": "SPDXRef-dep", "relationshipType": "DEPENDENCY_OF", "relatedSpdxElement": "SPDXRef-image"},
+                {"spdxElementId": "SPDXRef-dep", "relationshipType": "CONTAINED_BY", "relatedSpdxElement": "SPDXRef-image-amd64"},
+                {"spdxElementId": "SPDXRef-image", "relationshipType": "GENERATED_FROM", "relatedSpdxElement": "DocumentRef-other:SPDXRef-src"}
+            ]
+        });
+
+        let graph = SbomGraph::from_json(&document);
+        assert_eq!(graph.nodes.len(), 4);
+        assert_eq!(graph.nodes[0].purl.as_deref(), Some("pkg:oci/image@sha256:abc"));
+        assert_eq!(graph.nodes[0].version.as_deref(), Some("1.0"));
+
+        let edges: Vec<_> = graph
+            .edges
+            .iter()
+            .map(|edge| (edge.source.as_str(), edge.edge_type, edge.target.as_str()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("SPDXRef-image-amd64", EdgeType::VariantOf, "SPDXRef-image"),
+                ("SPDXRef-image-amd64", EdgeType::GeneratedFrom, "SPDXRef-src"),
+                ("SPDXRef-image", EdgeType::DependsOn, "SPDXRef-dep"),
+                ("SPDXRef-image-amd64", EdgeType::Contains, "SPDXRef-dep"),
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&graph.edges[1]).unwrap(),
+            json!({"source": "SPDXRef-image-amd64", "target": "SPDXRef-src", "type": "GENERATED_FROM"})
+        );
+    }
+
+    #[test]
+    fn cyclonedx() {
+        let document = json!({
+            "bomFormat": "CycloneDX",
+            "metadata": {"component": {"bom-ref": "app", "name": "app", "version": "1.0"}},
+            "components": [
+                {"bom-ref": "lib", "name": "lib", "purl": "pkg:maven/org.example/lib@2", "components": [
+                    {"bom-ref": "nested", "name": "nested"}
+                ]}
+            ],
+            "dependencies": [
+                {"ref": "app", "dependsOn": ["lib", "unknown"]},
+                {"ref": "lib", "dependsOn": ["nested"]}
+            ]
+        });
+
+        let graph = SbomGraph::from_json(&document);
+        assert_eq!(
+            graph.nodes.iter().map(|node| node.id.as_str()).collect::<Vec<_>>(),
+            vec!["app", "lib", "nested"]
+        );
+        assert_eq!(graph.edges.len(), 2);
+        assert!(graph.edges.iter().all(|edge| edge.edge_type == EdgeType::DependsOn));
+    }
+
+    #[test]
+    fn dependents() {
+        let document = json!({
+            "bomFormat": "CycloneDX",
+            "metadata": {"component": {"bom-ref": "app", "name": "app", "purl": "pkg:maven/org.example/app@1"}},
+            "components": [
+                {"bom-ref": "lib", "name": "lib", "purl": "pkg:maven/org.example/lib@2"},
+                {"bom-ref": "other", "name": "other", "purl": "pkg:maven/org.example/other@1"},
+                {"bom-ref": "log", "name": "log", "purl": "pkg:maven/org.example/log@3"}
+            ],
+            "dependencies": [
+                {"ref": "app", "dependsOn": ["lib", "other"]},
+                {"ref": "lib", "dependsOn": ["log"]},
+                {"ref": "other", "dependsOn": ["lib", "log"]}
+            ]
+        });
+        let graph = SbomGraph::from_json(&document);
+
+        let ids = |nodes: &[GraphNode]| nodes.iter().map(|node| node.id.clone()).collect::<Vec<_>>();
+        let dependents = graph.dependents("pkg:maven/org.example/log@3").unwrap();
+        assert_eq!(ids(&dependents.direct), vec!["lib", "other"]);
+        assert_eq!(ids(&dependents.transitive), vec!["app"]);
+
+        let dependents = graph.dependents("pkg:maven/org.example/app@1").unwrap();
+        assert!(dependents.direct.is_empty());
+        assert!(dependents.transitive.is_empty());
+
+        assert!(graph.dependents("pkg:maven/org.example/unknown@1").is_none());
+    }
+}
@@ -1,9 +1,23 @@
+pub mod changes;
+pub mod component;
+#[cfg(feature = "compression")]
+pub mod compression;
 pub mod data;
+pub mod graph;
+pub mod lint;
 pub mod packages;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
 pub mod search;
+#[cfg(feature = "cyclonedx")]
+pub mod xml;
 
 pub mod prelude {
+    pub use crate::changes::*;
+    pub use crate::component::*;
     pub use crate::data::*;
+    pub use crate::graph::*;
+    pub use crate::lint::*;
     pub use crate::packages::*;
     pub use crate::search::*;
 }
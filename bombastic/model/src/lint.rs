@@ -0,0 +1,315 @@
+//! Linting of SBOM documents before publishing them.
+
+use crate::data::SBOM;
+use packageurl::PackageUrl;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+/// The severity of a lint finding.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Deserialize, serde::Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    /// The document is usable, but should be improved
+    Warning,
+    /// The document would be rejected, or is unusable
+    Error,
+}
+
+/// The rule a lint finding was reported by.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintRule {
+    /// The document can't be parsed as a supported SBOM format
+    Schema,
+    /// A minimum element required by the NTIA is missing
+    Ntia,
+    /// A package URL can't be parsed
+    Purl,
+    /// An identifier is used more than once
+    DuplicateIdentifier,
+}
+
+/// A single finding of the linter.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, ToSchema)]
+pub struct LintFinding {
+    /// Severity of the finding
+    pub severity: LintSeverity,
+    /// The rule reporting the finding
+    pub rule: LintRule,
+    /// Human readable description of the finding
+    pub message: String,
+    /// JSON pointer to the location of the finding, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+}
+
+/// The result of linting an SBOM.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize, ToSchema)]
+pub struct LintReport {
+    /// The detected format of the document, if it could be parsed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// All findings
+    pub findings: Vec<LintFinding>,
+}
+
+impl LintReport {
+    /// Whether the document passed, meaning there are no findings of severity error.
+    pub fn is_valid(&self) -> bool {
+        !self.findings.iter().any(|f| f.severity == LintSeverity::Error)
+    }
+
+    /// Iterate over the findings of a given severity.
+    pub fn findings(&self, severity: LintSeverity) -> impl Iterator<Item = &LintFinding> {
+        self.findings.iter().filter(move |f| f.severity == severity)
+    }
+
+    fn add(&mut self, severity: LintSeverity, rule: LintRule, message: impl Into<String>, location: Option<String>) {
+        self.findings.push(LintFinding {
+            severity,
+            rule,
+            message: message.into(),
+            location,
+        });
+    }
+
+    fn warn(&mut self, message: impl Into<String>, location: impl Into<String>) {
+        self.add(LintSeverity::Warning, LintRule::Ntia, message, Some(location.into()));
+    }
+}
+
+/// Lint an SBOM document, without storing it.
+pub fn lint(data: &[u8]) -> LintReport {
+    let mut report = LintReport::default();
+
+    let sbom = match SBOM::parse(data) {
+        Ok(sbom) => sbom,
+        Err(err) => {
+            report.add(LintSeverity::Error, LintRule::Schema, err.to_string(), None);
+            return report;
+        }
+    };
+    report.format = Some(sbom.type_str());
+
+    // the typed models are lossy, so we check the raw document
+    let json = match serde_json::from_slice::<Value>(data) {
+        Ok(json) => json,
+        Err(err) => {
+            report.add(LintSeverity::Error, LintRule::Schema, err.to_string(), None);
+            return report;
+        }
+    };
+
+    match sbom {
+        #[cfg(feature = "spdx-rs")]
+        SBOM::SPDX(_) => lint_spdx(&json, &mut report),
+        #[cfg(feature = "cyclonedx-bom")]
+        SBOM::CycloneDX(_) => lint_cyclonedx(&json, &mut report),
+    }
+
+    report
+}
+
+fn is_missing(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(s) => s.trim().is_empty() || s == "NOASSERTION" || s == "NONE",
+        Value::Array(a) => a.is_empty(),
+        _ => false,
+    }
+}
+
+fn check_purl(report: &mut LintReport, purl: &str, location: String) {
+    if let Err(err) = PackageUrl::from_str(purl) {
+        report.add(
+            LintSeverity::Error,
+            LintRule::Purl,
+            format!("Invalid package URL '{purl}': {err}"),
+            Some(location),
+        );
+    }
+}
+
+/// Report all identifiers which are used more than once.
+fn check_duplicates<'a>(report: &mut LintReport, ids: impl IntoIterator<Item = (&'a str, String)>) {
+    let mut seen = HashMap::<&str, String>::new();
+    for (id, location) in ids {
+        if let Some(first) = seen.get(id) {
+            report.add(
+                LintSeverity::Error,
+                LintRule::DuplicateIdentifier,
+                format!("Identifier '{id}' is already used at {first}"),
+                Some(location),
+            );
+        } else {
+            seen.insert(id, location);
+        }
+    }
+}
+
+fn lint_spdx(json: &Value, report: &mut LintReport) {
+    let info = &json["creationInfo"];
+    if is_missing(&info["creators"]) {
+        report.warn("Missing author of the SBOM data", "/creationInfo/creators");
+    }
+    if is_missing(&info["created"]) {
+        report.warn("Missing timestamp", "/creationInfo/created");
+    }
+    if is_missing(&json["relationships"]) {
+        report.warn("Missing dependency relationships", "/relationships");
+    }
+
+    let packages = json["packages"].as_array().map(Vec::as_slice).unwrap_or_default();
+    for (n, package) in packages.iter().enumerate() {
+        let location = format!("/packages/{n}");
+
+        if is_missing(&package["name"]) {
+            report.warn("Missing component name", format!("{location}/name"));
+        }
+        if is_missing(&package["versionInfo"]) {
+            report.warn("Missing component version", format!("{location}/versionInfo"));
+        }
+        if is_missing(&package["supplier"]) {
+            report.warn("Missing supplier name", format!("{location}/supplier"));
+        }
+
+        let refs = package["externalRefs"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let mut identified = false;
+        for (r, external) in refs.iter().enumerate() {
+            let locator = external["referenceLocator"].as_str().unwrap_or_default();
+            match external["referenceType"].as_str() {
+                Some("purl") => {
+                    identified = true;
+                    check_purl(report, locator, format!("{location}/externalRefs/{r}/referenceLocator"));
+                }
+                Some("cpe22Type") | Some("cpe23Type") => identified = true,
+                _ => {}
+            }
+        }
+        if !identified {
+            report.warn(
+                "Missing unique identifier (purl or CPE)",
+                format!("{location}/externalRefs"),
+            );
+        }
+    }
+
+    check_duplicates(
+        report,
+        packages
+            .iter()
+            .enumerate()
+            .filter_map(|(n, p)| p["SPDXID"].as_str().map(|id| (id, format!("/packages/{n}/SPDXID")))),
+    );
+}
+
+fn lint_cyclonedx(json: &Value, report: &mut LintReport) {
+    let metadata = &json["metadata"];
+    if is_missing(&metadata["authors"]) && is_missing(&metadata["tools"]) {
+        report.warn("Missing author of the SBOM data", "/metadata/authors");
+    }
+    if is_missing(&metadata["timestamp"]) {
+        report.warn("Missing timestamp", "/metadata/timestamp");
+    }
+    if is_missing(&json["dependencies"]) {
+        report.warn("Missing dependency relationships", "/dependencies");
+    }
+
+    // collect all components, including nested ones, along with their location
+    let mut components = Vec::new();
+    let mut pending = vec![("/components".to_string(), &json["components"])];
+    if metadata["component"].is_object() {
+        components.push(("/metadata/component".to_string(), &metadata["component"]));
+    }
+    while let Some((location, list)) = pending.pop() {
+        for (n, component) in list
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+        {
+            let location = format!("{location}/{n}");
+            pending.push((format!("{location}/components"), &component["components"]));
+            components.push((location, component));
+        }
+    }
+
+    for (location, component) in &components {
+        if is_missing(&component["name"]) {
+            report.warn("Missing component name", format!("{location}/name"));
+        }
+        if is_missing(&component["version"]) {
+            report.warn("Missing component version", format!("{location}/version"));
+        }
+        if is_missing(&component["supplier"]["name"]) && is_missing(&component["publisher"]) {
+            report.warn("Missing supplier name", format!("{location}/supplier"));
+        }
+
+        match component["purl"].as_str() {
+            Some(purl) => check_purl(report, purl, format!("{location}/purl")),
+            None if component["cpe"].is_string() => {}
+            None => report.warn("Missing unique identifier (purl or CPE)", format!("{location}/purl")),
+        }
+    }
+
+    check_duplicates(
+        report,
+        components
+            .iter()
+            .filter_map(|(location, c)| c["bom-ref"].as_str().map(|id| (id, format!("{location}/bom-ref")))),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rules(report: &LintReport, severity: LintSeverity) -> Vec<LintRule> {
+        report.findings(severity).map(|f| f.rule).collect()
+    }
+
+    #[test]
+    fn lint_invalid() {
+        let report = lint(b"{}");
+        assert!(!report.is_valid());
+        assert_eq!(rules(&report, LintSeverity::Error), vec![LintRule::Schema]);
+        assert!(report.format.is_none());
+    }
+
+    #[test]
+    fn lint_spdx_valid() {
+        let report = lint(include_bytes!("../../testdata/ubi9-sbom.json"));
+        assert!(report.is_valid(), "{report:#?}");
+        assert_eq!(report.format.as_deref(), Some("SPDX/SPDX-2.2"));
+    }
+
+    #[test]
+    fn lint_cyclonedx_valid() {
+        let report = lint(include_bytes!("../../testdata/syft.cyclonedx.json"));
+        assert!(report.is_valid(), "{report:#?}");
+    }
+
+    #[test]
+    fn lint_cyclonedx_findings() {
+        let mut bom: Value = serde_json::from_slice(include_bytes!("../../testdata/my-sbom.json")).unwrap();
+        let components = bom["components"].as_array_mut().unwrap();
+        // duplicate the first component, and break its purl
+        let mut duplicate = components[0].clone();
+        duplicate["purl"] = json!("pkg:");
+        components.push(duplicate);
+
+        let report = lint(&serde_json::to_vec(&bom).unwrap());
+        let errors = rules(&report, LintSeverity::Error);
+
+        assert!(errors.contains(&LintRule::DuplicateIdentifier), "{report:#?}");
+        assert!(errors.contains(&LintRule::Purl), "{report:#?}");
+        assert!(!report.is_valid());
+    }
+}
@@ -28,10 +28,22 @@ pub enum PackageInfo<'a> {
     #[search(default)]
     Description(&'a str),
     Qualifier(Qualified<'a, &'a str>),
+    /// Search packages by their source repository, like `github.com/ossf/scorecard`, supporting the same patterns as
+    /// `purl`.
+    #[search(scope)]
+    Repository(Primary<'a>),
+    /// Search packages by the OpenSSF Scorecard score of their source repository, from 0 to 10.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// scorecard:<7
+    /// ```
+    Scorecard(PartialOrdered<f64>),
 }
 
 /// A document returned from the search index for every match.
-#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
 pub struct SearchPackageDocument {
     /// Package name
     pub name: String,
@@ -55,10 +67,16 @@ pub struct SearchPackageDocument {
     pub purl_version: String,
     pub purl_qualifiers: String,
     pub purl_qualifiers_values: String,
+    /// The source repository of the package, like `github.com/ossf/scorecard`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    /// The OpenSSF Scorecard score of the source repository, from 0 to 10
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scorecard: Option<f64>,
 }
 
 /// The hit describes the document, its score and optionally an explanation of why that score was given.
-#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
 pub struct SearchPackageHit {
     /// The document that was matched.
     pub document: SearchPackageDocument,
@@ -79,4 +97,8 @@ pub struct SearchPackageResult {
     pub total: usize,
     /// Documents matched up to max requested
     pub result: Vec<SearchPackageHit>,
+    /// Time of the last commit of the searched index, in RFC3339 format
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    #[schema(value_type = Option<String>)]
+    pub index_updated_at: Option<time::OffsetDateTime>,
 }
@@ -0,0 +1,458 @@
+//! Support for the protobuf encoding of CycloneDX.
+//!
+//! Only the parts of the [CycloneDX protobuf schema](https://github.com/CycloneDX/specification/tree/master/schema)
+//! that are relevant for indexing are decoded. Documents are converted to their JSON encoding, so that they can be
+//! processed like any other CycloneDX SBOM.
+
+use prost::Message;
+use serde_json::{json, Map, Value};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+/// Media type of protobuf encoded CycloneDX documents.
+pub const CYCLONEDX_PROTOBUF: &str = "application/x.cyclonedx+protobuf";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("failed to decode protobuf: {0}")]
+    Decode(#[from] prost::DecodeError),
+    #[error("failed to encode JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Convert a protobuf encoded CycloneDX document to its JSON encoding.
+pub fn cyclonedx_to_json(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let bom = Bom::decode(data)?;
+    Ok(serde_json::to_vec(&bom.to_json())?)
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Bom {
+    #[prost(string, tag = "1")]
+    pub spec_version: String,
+    #[prost(int32, optional, tag = "2")]
+    pub version: Option<i32>,
+    #[prost(string, optional, tag = "3")]
+    pub serial_number: Option<String>,
+    #[prost(message, optional, tag = "4")]
+    pub metadata: Option<Metadata>,
+    #[prost(message, repeated, tag = "5")]
+    pub components: Vec<Component>,
+    #[prost(message, repeated, tag = "8")]
+    pub dependencies: Vec<Dependency>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Metadata {
+    #[prost(message, optional, tag = "1")]
+    pub timestamp: Option<Timestamp>,
+    #[prost(message, optional, tag = "4")]
+    pub component: Option<Component>,
+    #[prost(message, optional, tag = "6")]
+    pub supplier: Option<OrganizationalEntity>,
+    #[prost(message, repeated, tag = "7")]
+    pub licenses: Vec<LicenseChoice>,
+    #[prost(message, repeated, tag = "8")]
+    pub properties: Vec<Property>,
+}
+
+/// Well known `google.protobuf.Timestamp`.
+#[derive(Clone, PartialEq, Message)]
+pub struct Timestamp {
+    #[prost(int64, tag = "1")]
+    pub seconds: i64,
+    #[prost(int32, tag = "2")]
+    pub nanos: i32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Component {
+    #[prost(enumeration = "Classification", tag = "1")]
+    pub r#type: i32,
+    #[prost(string, optional, tag = "2")]
+    pub mime_type: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub bom_ref: Option<String>,
+    #[prost(message, optional, tag = "4")]
+    pub supplier: Option<OrganizationalEntity>,
+    #[prost(string, optional, tag = "5")]
+    pub author: Option<String>,
+    #[prost(string, optional, tag = "6")]
+    pub publisher: Option<String>,
+    #[prost(string, optional, tag = "7")]
+    pub group: Option<String>,
+    #[prost(string, tag = "8")]
+    pub name: String,
+    #[prost(string, tag = "9")]
+    pub version: String,
+    #[prost(string, optional, tag = "10")]
+    pub description: Option<String>,
+    #[prost(message, repeated, tag = "12")]
+    pub hashes: Vec<Hash>,
+    #[prost(message, repeated, tag = "13")]
+    pub licenses: Vec<LicenseChoice>,
+    #[prost(string, optional, tag = "14")]
+    pub copyright: Option<String>,
+    #[prost(string, optional, tag = "15")]
+    pub cpe: Option<String>,
+    #[prost(string, optional, tag = "16")]
+    pub purl: Option<String>,
+    #[prost(message, repeated, tag = "21")]
+    pub components: Vec<Component>,
+    #[prost(message, repeated, tag = "22")]
+    pub properties: Vec<Property>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum Classification {
+    Null = 0,
+    Application = 1,
+    Framework = 2,
+    Library = 3,
+    OperatingSystem = 4,
+    Device = 5,
+    File = 6,
+    Container = 7,
+    Firmware = 8,
+    DeviceDriver = 9,
+    Platform = 10,
+    MachineLearningModel = 11,
+    Data = 12,
+}
+
+impl Classification {
+    fn as_json(&self) -> &'static str {
+        match self {
+            // "type" is mandatory in JSON
+            Self::Null | Self::Library => "library",
+            Self::Application => "application",
+            Self::Framework => "framework",
+            Self::OperatingSystem => "operating-system",
+            Self::Device => "device",
+            Self::File => "file",
+            Self::Container => "container",
+            Self::Firmware => "firmware",
+            Self::DeviceDriver => "device-driver",
+            Self::Platform => "platform",
+            Self::MachineLearningModel => "machine-learning-model",
+            Self::Data => "data",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct OrganizationalEntity {
+    #[prost(string, optional, tag = "1")]
+    pub name: Option<String>,
+    #[prost(string, repeated, tag = "2")]
+    pub url: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Hash {
+    #[prost(enumeration = "HashAlg", tag = "1")]
+    pub alg: i32,
+    #[prost(string, tag = "2")]
+    pub value: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum HashAlg {
+    Null = 0,
+    Md5 = 1,
+    Sha1 = 2,
+    Sha256 = 3,
+    Sha384 = 4,
+    Sha512 = 5,
+    Sha3_256 = 6,
+    Sha3_384 = 7,
+    Sha3_512 = 8,
+    Blake2b256 = 9,
+    Blake2b384 = 10,
+    Blake2b512 = 11,
+    Blake3 = 12,
+}
+
+impl HashAlg {
+    fn as_json(&self) -> Option<&'static str> {
+        Some(match self {
+            Self::Null => return None,
+            Self::Md5 => "MD5",
+            Self::Sha1 => "SHA-1",
+            Self::Sha256 => "SHA-256",
+            Self::Sha384 => "SHA-384",
+            Self::Sha512 => "SHA-512",
+            Self::Sha3_256 => "SHA3-256",
+            Self::Sha3_384 => "SHA3-384",
+            Self::Sha3_512 => "SHA3-512",
+            Self::Blake2b256 => "BLAKE2b-256",
+            Self::Blake2b384 => "BLAKE2b-384",
+            Self::Blake2b512 => "BLAKE2b-512",
+            Self::Blake3 => "BLAKE3",
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct LicenseChoice {
+    #[prost(oneof = "license_choice::Choice", tags = "1, 2")]
+    pub choice: Option<license_choice::Choice>,
+}
+
+pub mod license_choice {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Choice {
+        #[prost(message, tag = "1")]
+        License(super::License),
+        #[prost(string, tag = "2")]
+        Expression(String),
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct License {
+    #[prost(oneof = "license::License", tags = "1, 2")]
+    pub license: Option<license::License>,
+    #[prost(string, optional, tag = "4")]
+    pub url: Option<String>,
+}
+
+pub mod license {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum License {
+        #[prost(string, tag = "1")]
+        Id(String),
+        #[prost(string, tag = "2")]
+        Name(String),
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Property {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, optional, tag = "2")]
+    pub value: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Dependency {
+    #[prost(string, tag = "1")]
+    pub r#ref: String,
+    #[prost(message, repeated, tag = "2")]
+    pub dependencies: Vec<Dependency>,
+}
+
+/// Insert a value, unless it is missing.
+fn insert(object: &mut Map<String, Value>, key: &str, value: Option<impl Into<Value>>) {
+    if let Some(value) = value {
+        object.insert(key.to_string(), value.into());
+    }
+}
+
+/// Insert an array, unless it is empty.
+fn insert_all<T>(object: &mut Map<String, Value>, key: &str, values: &[T], f: impl Fn(&T) -> Option<Value>) {
+    let values: Vec<Value> = values.iter().filter_map(f).collect();
+    if !values.is_empty() {
+        object.insert(key.to_string(), Value::Array(values));
+    }
+}
+
+impl Bom {
+    pub fn to_json(&self) -> Value {
+        let mut bom = Map::new();
+        bom.insert("bomFormat".into(), "CycloneDX".into());
+        bom.insert("specVersion".into(), self.spec_version.clone().into());
+        bom.insert("version".into(), self.version.unwrap_or(1).into());
+        insert(&mut bom, "serialNumber", self.serial_number.clone());
+        insert(&mut bom, "metadata", self.metadata.as_ref().map(Metadata::to_json));
+        insert_all(&mut bom, "components", &self.components, |c| Some(c.to_json()));
+        insert_all(&mut bom, "dependencies", &self.dependencies, |d| Some(d.to_json()));
+        Value::Object(bom)
+    }
+}
+
+impl Metadata {
+    fn to_json(&self) -> Value {
+        let mut metadata = Map::new();
+        insert(
+            &mut metadata,
+            "timestamp",
+            self.timestamp.as_ref().and_then(Timestamp::to_rfc3339),
+        );
+        insert(
+            &mut metadata,
+            "component",
+            self.component.as_ref().map(Component::to_json),
+        );
+        insert(
+            &mut metadata,
+            "supplier",
+            self.supplier.as_ref().map(OrganizationalEntity::to_json),
+        );
+        insert_all(&mut metadata, "licenses", &self.licenses, LicenseChoice::to_json);
+        insert_all(&mut metadata, "properties", &self.properties, |p| Some(p.to_json()));
+        Value::Object(metadata)
+    }
+}
+
+impl Timestamp {
+    fn to_rfc3339(&self) -> Option<String> {
+        let timestamp = OffsetDateTime::from_unix_timestamp(self.seconds).ok()?;
+        let timestamp = timestamp.replace_nanosecond(self.nanos.try_into().ok()?).ok()?;
+        timestamp.format(&Rfc3339).ok()
+    }
+}
+
+impl Component {
+    fn to_json(&self) -> Value {
+        let mut component = Map::new();
+        let classification = Classification::try_from(self.r#type).unwrap_or(Classification::Null);
+        component.insert("type".into(), classification.as_json().into());
+        insert(&mut component, "mime-type", self.mime_type.clone());
+        insert(&mut component, "bom-ref", self.bom_ref.clone());
+        insert(
+            &mut component,
+            "supplier",
+            self.supplier.as_ref().map(OrganizationalEntity::to_json),
+        );
+        insert(&mut component, "author", self.author.clone());
+        insert(&mut component, "publisher", self.publisher.clone());
+        insert(&mut component, "group", self.group.clone());
+        component.insert("name".into(), self.name.clone().into());
+        insert(
+            &mut component,
+            "version",
+            Some(&self.version).filter(|v| !v.is_empty()).cloned(),
+        );
+        insert(&mut component, "description", self.description.clone());
+        insert_all(&mut component, "hashes", &self.hashes, Hash::to_json);
+        insert_all(&mut component, "licenses", &self.licenses, LicenseChoice::to_json);
+        insert(&mut component, "copyright", self.copyright.clone());
+        insert(&mut component, "cpe", self.cpe.clone());
+        insert(&mut component, "purl", self.purl.clone());
+        insert_all(&mut component, "components", &self.components, |c| Some(c.to_json()));
+        insert_all(&mut component, "properties", &self.properties, |p| Some(p.to_json()));
+        Value::Object(component)
+    }
+}
+
+impl OrganizationalEntity {
+    fn to_json(&self) -> Value {
+        let mut entity = Map::new();
+        insert(&mut entity, "name", self.name.clone());
+        insert_all(&mut entity, "url", &self.url, |url| Some(url.clone().into()));
+        Value::Object(entity)
+    }
+}
+
+impl Hash {
+    fn to_json(&self) -> Option<Value> {
+        let alg = HashAlg::try_from(self.alg).ok()?.as_json()?;
+        Some(json!({ "alg": alg, "content": self.value }))
+    }
+}
+
+impl LicenseChoice {
+    fn to_json(&self) -> Option<Value> {
+        Some(match self.choice.as_ref()? {
+            license_choice::Choice::License(license) => json!({ "license": license.to_json()? }),
+            license_choice::Choice::Expression(expression) => json!({ "expression": expression }),
+        })
+    }
+}
+
+impl License {
+    fn to_json(&self) -> Option<Value> {
+        let mut license = Map::new();
+        match self.license.as_ref()? {
+            license::License::Id(id) => license.insert("id".into(), id.clone().into()),
+            license::License::Name(name) => license.insert("name".into(), name.clone().into()),
+        };
+        insert(&mut license, "url", self.url.clone());
+        Some(Value::Object(license))
+    }
+}
+
+impl Property {
+    fn to_json(&self) -> Value {
+        let mut property = Map::new();
+        property.insert("name".into(), self.name.clone().into());
+        insert(&mut property, "value", self.value.clone());
+        Value::Object(property)
+    }
+}
+
+impl Dependency {
+    fn to_json(&self) -> Value {
+        let depends_on: Vec<Value> = self.dependencies.iter().map(|d| d.r#ref.clone().into()).collect();
+        json!({ "ref": self.r#ref, "dependsOn": depends_on })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::SBOM;
+
+    fn component(name: &str, purl: &str) -> Component {
+        Component {
+            r#type: Classification::Library as i32,
+            bom_ref: Some(purl.to_string()),
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            purl: Some(purl.to_string()),
+            licenses: vec![LicenseChoice {
+                choice: Some(license_choice::Choice::License(License {
+                    license: Some(license::License::Id("Apache-2.0".to_string())),
+                    url: None,
+                })),
+            }],
+            hashes: vec![Hash {
+                alg: HashAlg::Sha256 as i32,
+                value: "c3ab8ff13720e8ad9047dd39466b3c8974e592c2fa383d4a3960714caef0c4f2".to_string(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn convert_cyclonedx() {
+        let bom = Bom {
+            spec_version: "1.5".to_string(),
+            version: Some(1),
+            serial_number: Some("urn:uuid:3e671687-395b-41f5-a30f-a58921a69b79".to_string()),
+            metadata: Some(Metadata {
+                timestamp: Some(Timestamp {
+                    seconds: 1_700_000_000,
+                    nanos: 0,
+                }),
+                component: Some(component("app", "pkg:cargo/app@1.0.0")),
+                ..Default::default()
+            }),
+            components: vec![component("lib", "pkg:cargo/lib@1.0.0")],
+            dependencies: vec![Dependency {
+                r#ref: "pkg:cargo/app@1.0.0".to_string(),
+                dependencies: vec![Dependency {
+                    r#ref: "pkg:cargo/lib@1.0.0".to_string(),
+                    dependencies: vec![],
+                }],
+            }],
+        };
+
+        let json = cyclonedx_to_json(&bom.encode_to_vec()).unwrap();
+        let value: Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value["metadata"]["timestamp"], "2023-11-14T22:13:20Z");
+        assert_eq!(value["components"][0]["purl"], "pkg:cargo/lib@1.0.0");
+        assert_eq!(value["dependencies"][0]["dependsOn"][0], "pkg:cargo/lib@1.0.0");
+
+        assert!(matches!(SBOM::parse(&json).unwrap(), SBOM::CycloneDX(_)));
+    }
+
+    #[test]
+    fn invalid() {
+        // truncated length delimited field
+        assert!(cyclonedx_to_json(&[0x0a, 0xff]).is_err());
+    }
+}
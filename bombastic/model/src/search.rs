@@ -1,5 +1,6 @@
 use serde_json::Value;
 use sikula::prelude::*;
+use std::collections::HashMap;
 use time::OffsetDateTime;
 
 #[derive(Clone, Debug, PartialEq, Search)]
@@ -27,6 +28,32 @@ pub enum Packages<'a> {
     /// type:oci
     /// ```
     Type(&'a str),
+    /// Search packages by Package URL, supporting wildcard (`*`, `?`) and regular expression (`/regex/`) patterns.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// purl:"pkg:rpm/redhat/openssl*"
+    /// purl:"/pkg:rpm/redhat/openssl@3.*/"
+    /// ```
+    #[search(scope)]
+    Purl(Primary<'a>),
+    /// Search packages by CPE, supporting the same patterns as `purl`.
+    #[search(scope)]
+    Cpe(Primary<'a>),
+    /// Search packages by the name of their Package URL, supporting the same patterns as `purl`.
+    ///
+    /// Sorting by name orders the SBOMs by their document name.
+    #[search(scope, sort)]
+    Name(Primary<'a>),
+    /// Search SBOMs containing packages of a Package URL type.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// ecosystem:npm
+    /// ```
+    Ecosystem(&'a str),
     #[search]
     Namespace(&'a str),
     #[search(default)]
@@ -40,11 +67,109 @@ pub enum Packages<'a> {
     Digest(&'a str),
     #[search(scope)]
     License(&'a str),
-    #[search(scope)]
+    /// Search packages by their supplier.
+    ///
+    /// Sorting by supplier orders the SBOMs by the supplier of the package they describe.
+    #[search(scope, sort)]
     Supplier(Primary<'a>),
     Qualifier(Qualified<'a, &'a str>),
     #[search(scope)]
     Dependency(Primary<'a>),
+    /// Search SBOMs containing any version of a component, by the id derived from its canonical Package URL.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// component:8f4e4fb1d3d4c2a7
+    /// ```
+    Component(&'a str),
+    /// Search SBOMs by how they were ingested.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// source:walker
+    /// ```
+    Source(&'a str),
+    /// Search SBOMs by a label added when indexing them, as `key:value`.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// label:"owner:team-a"
+    /// ```
+    Label(&'a str),
+    /// Search SBOMs by a custom identifier of a vendor, like an internal part number. Only available if the indexer
+    /// is configured with rules extracting them.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// custom:PN-100
+    /// ```
+    Custom(&'a str),
+    /// Search SBOMs containing a file, by its name or path. Only available if the indexer indexes files.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// filename:libssl.so.3
+    /// filename:"usr/bin/openssl*"
+    /// ```
+    #[search(scope)]
+    Filename(Primary<'a>),
+    /// Search SBOMs containing a file, by its SHA256 digest. Only available if the indexer indexes files.
+    Filedigest(&'a str),
+    /// Search SBOMs by the version control repository of the SBOM or one of its components, supporting the same
+    /// patterns as `purl`. Repositories can also be found by their URL without a `git+` prefix or `.git` suffix.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// vcs:"https://github.com/quarkusio/quarkus"
+    /// vcs:"https://github.com/netty/*"
+    /// ```
+    #[search(scope)]
+    Vcs(Primary<'a>),
+    /// Search SBOMs by the build system URL of the SBOM or one of its components, supporting the same patterns as
+    /// `purl`.
+    #[search(scope)]
+    BuildSystem(Primary<'a>),
+    /// Search SBOMs by the distribution URL of the SBOM or one of its components, supporting the same patterns as
+    /// `purl`.
+    #[search(scope)]
+    Distribution(Primary<'a>),
+    /// Search SBOMs containing a package which is a variant of another one, by the Package URL of the other package,
+    /// supporting the same patterns as `purl`.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// variantOf:"pkg:oci/ubi9*"
+    /// ```
+    #[search(scope)]
+    VariantOf(Primary<'a>),
+    /// Search SBOMs containing a package which was generated from another one, like a binary from its sources, by the
+    /// Package URL of the other package, supporting the same patterns as `purl`.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// generatedFrom:"pkg:generic/openssl@3.0.7?arch=src"
+    /// ```
+    #[search(scope)]
+    GeneratedFrom(Primary<'a>),
+    /// Search SBOMs containing a package which depends on another one, by its Package URL, supporting the same
+    /// patterns as `purl`. Dependencies are taken from the `dependencies` section of CycloneDX SBOMs, and the
+    /// `DEPENDS_ON` and `CONTAINS` relationships of SPDX SBOMs.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// dependent:"pkg:maven/io.quarkus/quarkus-arc*"
+    /// ```
+    #[search(scope)]
+    Dependent(Primary<'a>),
     Application,
     Library,
     Framework,
@@ -53,10 +178,18 @@ pub enum Packages<'a> {
     Device,
     Firmware,
     File,
+    /// SBOMs whose stored document was moved to the archive storage class.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// is:archived
+    /// ```
+    Archived,
 }
 
 /// A document returned from the search index for every match.
-#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
 pub struct SearchDocument {
     /// SBOM (storage) identifier
     pub id: String,
@@ -92,10 +225,30 @@ pub struct SearchDocument {
     pub created: time::OffsetDateTime,
     /// Number of dependencies with package names that matched
     pub dependencies: u64,
+    /// Number of packages per Package URL type (e.g. rpm, npm, golang)
+    #[serde(default)]
+    pub ecosystems: HashMap<String, u64>,
+    /// External references of the SBOM and the component it describes, like its source repository
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub external_references: Vec<ExternalReference>,
+    /// Labels added when indexing the SBOM, like `owner:team-a`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// Whether the stored SBOM was archived, retrieving it may be slower
+    #[serde(default)]
+    pub archived: bool,
+}
+
+/// An external reference, like the version control repository or the build system of a component.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, Eq, utoipa::ToSchema)]
+pub struct ExternalReference {
+    /// The category of the reference, like `vcs`, `build-system` or `distribution`
+    pub category: String,
+    pub url: String,
 }
 
 /// The hit describes the document, its score and optionally an explanation of why that score was given.
-#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
 pub struct SearchHit {
     /// The document that was matched.
     pub document: SearchDocument,
@@ -116,6 +269,13 @@ pub struct SearchResult {
     pub total: usize,
     /// Documents matched up to max requested
     pub result: Vec<SearchHit>,
+    /// Number of matching documents per value, for each requested facet
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub facets: HashMap<String, HashMap<String, u64>>,
+    /// Time of the last commit of the searched index, in RFC3339 format
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    #[schema(value_type = Option<String>)]
+    pub index_updated_at: Option<OffsetDateTime>,
 }
 
 /// This payload returns the total number of docs and the last updated doc.
@@ -0,0 +1,131 @@
+//! Support for the XML encoding of CycloneDX.
+//!
+//! The spec version is detected from the namespace of the document, supporting CycloneDX 1.3, 1.4 and 1.5. Documents
+//! are converted to their JSON encoding of the same spec version, so that they can be processed like any other
+//! CycloneDX SBOM.
+
+use cyclonedx_bom::{
+    errors::{JsonWriteError, XmlReadError},
+    prelude::Bom,
+};
+
+/// Media type of XML encoded CycloneDX documents.
+pub const CYCLONEDX_XML: &str = "application/vnd.cyclonedx+xml";
+
+/// Namespace of the CycloneDX XML schema, without the spec version.
+const NAMESPACE: &str = "http://cyclonedx.org/schema/bom/";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("missing the CycloneDX namespace")]
+    MissingNamespace,
+    #[error("unsupported CycloneDX version {0}")]
+    UnsupportedVersion(String),
+    #[error("failed to parse XML: {0}")]
+    Parse(#[from] XmlReadError),
+    #[error("failed to encode JSON: {0}")]
+    Json(#[from] JsonWriteError),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+/// Whether the data is an XML document, rather than JSON.
+pub fn is_xml(data: &[u8]) -> bool {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    data.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'<')
+}
+
+/// The spec version of a CycloneDX XML document, taken from its namespace.
+pub fn spec_version(data: &[u8]) -> Result<&str, Error> {
+    let data = std::str::from_utf8(data).map_err(|_| Error::MissingNamespace)?;
+    let start = data.find(NAMESPACE).ok_or(Error::MissingNamespace)? + NAMESPACE.len();
+    let version = &data[start..];
+    let end = version
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(version.len());
+    Ok(&version[..end])
+}
+
+/// Parse a CycloneDX XML document, of any supported spec version.
+pub fn parse(data: &[u8]) -> Result<Bom, Error> {
+    Ok(match spec_version(data)? {
+        "1.3" => Bom::parse_from_xml_v1_3(data)?,
+        "1.4" => Bom::parse_from_xml_v1_4(data)?,
+        "1.5" => Bom::parse_from_xml_v1_5(data)?,
+        version => return Err(Error::UnsupportedVersion(version.to_string())),
+    })
+}
+
+/// Convert a CycloneDX XML document to its JSON encoding, keeping the spec version.
+pub fn cyclonedx_to_json(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let bom = parse(data)?;
+    let mut json = Vec::new();
+    match spec_version(data)? {
+        "1.3" => bom.output_as_json_v1_3(&mut json)?,
+        "1.4" => bom.output_as_json_v1_4(&mut json)?,
+        _ => bom.output_as_json_v1_5(&mut json)?,
+    }
+    Ok(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::SBOM;
+    use serde_json::Value;
+
+    fn document(version: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<bom xmlns="http://cyclonedx.org/schema/bom/{version}" serialNumber="urn:uuid:3e671687-395b-41f5-a30f-a58921a69b79" version="1">
+  <metadata>
+    <timestamp>2023-11-14T22:13:20Z</timestamp>
+    <component type="application" bom-ref="pkg:cargo/app@1.0.0">
+      <name>app</name>
+      <version>1.0.0</version>
+      <purl>pkg:cargo/app@1.0.0</purl>
+    </component>
+  </metadata>
+  <components>
+    <component type="library" bom-ref="pkg:cargo/lib@1.0.0">
+      <name>lib</name>
+      <version>1.0.0</version>
+      <purl>pkg:cargo/lib@1.0.0</purl>
+    </component>
+  </components>
+</bom>
+"#
+        )
+    }
+
+    #[test]
+    fn detect() {
+        assert!(is_xml(b"\n  <bom/>"));
+        assert!(is_xml(b"\xEF\xBB\xBF<bom/>"));
+        assert!(!is_xml(b"{\"bomFormat\": \"CycloneDX\"}"));
+
+        assert_eq!(spec_version(document("1.4").as_bytes()).unwrap(), "1.4");
+        assert!(matches!(spec_version(b"<bom/>"), Err(Error::MissingNamespace)));
+        assert!(matches!(
+            parse(document("2.0").as_bytes()),
+            Err(Error::UnsupportedVersion(version)) if version == "2.0"
+        ));
+    }
+
+    #[test]
+    fn convert_cyclonedx() {
+        for version in ["1.3", "1.4", "1.5"] {
+            let json = cyclonedx_to_json(document(version).as_bytes()).unwrap();
+            let value: Value = serde_json::from_slice(&json).unwrap();
+            assert_eq!(value["specVersion"], version);
+            assert_eq!(value["metadata"]["component"]["purl"], "pkg:cargo/app@1.0.0");
+            assert_eq!(value["components"][0]["purl"], "pkg:cargo/lib@1.0.0");
+
+            // the converted document is parsed like the original one
+            let sbom = SBOM::parse(&json).unwrap();
+            assert_eq!(sbom.type_str(), format!("CycloneDX/{version}"));
+            let sbom = SBOM::parse(document(version).as_bytes()).unwrap();
+            assert_eq!(sbom.type_str(), format!("CycloneDX/{version}"));
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use parking_lot::Mutex;
+use sbom_walker::discover::{DiscoveredContext, DiscoveredSbom, DiscoveredVisitor};
+use std::sync::Arc;
+use trustification_common_walker::{filter::DocumentFilter, report::ReportBuilder};
+
+/// Skips discovered SBOMs not matching the filter, before they get retrieved.
+pub struct FilteringVisitor<V> {
+    pub filter: DocumentFilter,
+    pub next: V,
+    /// the report to count discovered and skipped SBOMs in
+    pub report: Arc<Mutex<ReportBuilder>>,
+}
+
+impl<V: DiscoveredVisitor> DiscoveredVisitor for FilteringVisitor<V> {
+    type Error = V::Error;
+    type Context = V::Context;
+
+    async fn visit_context(&self, context: &DiscoveredContext<'_>) -> Result<Self::Context, Self::Error> {
+        self.next.visit_context(context).await
+    }
+
+    async fn visit_sbom(&self, context: &Self::Context, sbom: DiscoveredSbom) -> Result<(), Self::Error> {
+        self.report.lock().discover();
+        if !self.filter.matches(&sbom.url, sbom.modified) {
+            log::debug!("Skipping filtered SBOM: {}", sbom.url);
+            self.report.lock().skip();
+            return Ok(());
+        }
+
+        self.next.visit_sbom(context, sbom).await
+    }
+}
@@ -0,0 +1,595 @@
+//! Generic sources of SBOMs, which don't follow the layout of the SBOM walker: an HTTPS directory listing, crawled
+//! recursively, or an `index.json` manifest listing the documents.
+//!
+//! Documents are validated using their digests and detached OpenPGP signatures, when available, before being
+//! published to bombastic.
+
+use anyhow::{anyhow, bail, Context};
+use bytes::Bytes;
+use parking_lot::Mutex;
+use sequoia_openpgp::{
+    parse::{
+        stream::{DetachedVerifierBuilder, MessageLayer, MessageStructure, VerificationHelper},
+        Parse,
+    },
+    policy::StandardPolicy,
+    Cert, KeyHandle,
+};
+use sha2::{Digest, Sha256, Sha512};
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use time::OffsetDateTime;
+use trustification_auth::client::{TokenInjector, TokenProvider};
+use trustification_common_walker::{
+    filter::DocumentFilter,
+    report::{Phase, ReportBuilder, Severity},
+};
+use url::Url;
+
+/// The file name of the manifest, if the source of the [`Layout::Manifest`] is a directory.
+const MANIFEST: &str = "index.json";
+
+/// How the documents of a source are discovered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Layout {
+    /// The layout of the SBOM walker: a `changes.csv` file listing the documents, next to their digests and signatures
+    #[default]
+    Changes,
+    /// A directory listing, like an Apache or nginx autoindex, crawled recursively
+    Listing,
+    /// An `index.json` manifest, listing the documents with their digests and signatures
+    Manifest,
+}
+
+/// The manifest of the [`Layout::Manifest`].
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize)]
+pub struct Manifest {
+    pub documents: Vec<ManifestEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize)]
+pub struct ManifestEntry {
+    /// URL of the document, relative to the manifest
+    pub url: String,
+    /// When the document was last modified, the document being processed on every run if missing
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub modified: Option<OffsetDateTime>,
+    /// Hex encoded SHA-256 digest of the document
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Hex encoded SHA-512 digest of the document
+    #[serde(default)]
+    pub sha512: Option<String>,
+    /// URL of the detached, armored OpenPGP signature of the document, relative to the manifest
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+/// A digest, known upfront or to be fetched from a file next to the document, like `<document>.sha256`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expected {
+    Inline(String),
+    Remote(Url),
+}
+
+/// A discovered document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Document {
+    pub url: Url,
+    pub modified: Option<SystemTime>,
+    pub sha256: Option<Expected>,
+    pub sha512: Option<Expected>,
+    pub signature: Option<Url>,
+}
+
+/// Discovers, validates and publishes the documents of a generic source.
+pub struct GenericWalker {
+    pub layout: Layout,
+    pub source: String,
+    pub target: Url,
+    pub provider: Arc<dyn TokenProvider>,
+    /// URLs of the keys signatures are validated with, using the fragment as fingerprint
+    pub keys: Vec<Url>,
+    pub validation_date: Option<SystemTime>,
+    pub fix_licenses: bool,
+    pub retries: usize,
+    pub retry_delay: Option<Duration>,
+    pub additional_root_certificates: Vec<PathBuf>,
+}
+
+impl GenericWalker {
+    /// Walk the source, failing if a document couldn't be uploaded.
+    ///
+    /// Documents which fail to be retrieved or validated are reported and skipped, like with the SBOM walker layout,
+    /// so that they get processed again once they are updated.
+    pub async fn walk(
+        &self,
+        filter: &DocumentFilter,
+        since: Option<SystemTime>,
+        report: &Arc<Mutex<ReportBuilder>>,
+    ) -> anyhow::Result<()> {
+        let client = reqwest::Client::new();
+        let upload_client = {
+            let mut client = reqwest::Client::builder();
+            for cert in &self.additional_root_certificates {
+                let pem =
+                    std::fs::read(cert).with_context(|| format!("failed to read certificate: {}", cert.display()))?;
+                client = client.add_root_certificate(reqwest::Certificate::from_pem(&pem)?);
+            }
+            client.build()?
+        };
+
+        let certs = load_keys(&client, &self.keys).await?;
+        let documents = match self.layout {
+            Layout::Changes => bail!("the SBOM walker layout isn't a generic one"),
+            Layout::Listing => crawl(&client, &directory(&self.source)?).await?,
+            Layout::Manifest => load_manifest(&client, &self.source).await?,
+        };
+
+        let mut failed = 0;
+        for document in documents {
+            report.lock().discover();
+            let modified = document.modified.unwrap_or_else(SystemTime::now);
+            if !filter.matches(&document.url, modified) || matches!(since, Some(since) if modified < since) {
+                log::debug!("Skipping filtered SBOM: {}", document.url);
+                report.lock().skip();
+                continue;
+            }
+
+            report.lock().tick();
+            let file = document.url.to_string();
+
+            let data = match retrieve(&client, &document.url).await {
+                Ok(data) => data,
+                Err(err) => {
+                    report.lock().add_error(
+                        Phase::Retrieval,
+                        file,
+                        Severity::Error,
+                        format!("retrieval of document failed: {err}"),
+                    );
+                    continue;
+                }
+            };
+
+            if let Err(err) = self.validate(&client, &certs, &document, &data).await {
+                report
+                    .lock()
+                    .add_error(Phase::Validation, file, Severity::Error, err.to_string());
+                continue;
+            }
+
+            let data = self.process(&file, data, report).await;
+            if let Err(err) = self.upload(&upload_client, &document.url, data).await {
+                log::warn!("Failed to upload {file}: {err}");
+                report
+                    .lock()
+                    .add_error(Phase::Upload, file, Severity::Error, format!("upload failed: {err}"));
+                failed += 1;
+            }
+        }
+
+        match failed {
+            0 => Ok(()),
+            n => Err(anyhow!("failed to upload {n} document(s)")),
+        }
+    }
+
+    async fn validate(
+        &self,
+        client: &reqwest::Client,
+        certs: &[Cert],
+        document: &Document,
+        data: &[u8],
+    ) -> anyhow::Result<()> {
+        if let Some(expected) = &document.sha256 {
+            check_digest(client, expected, &format!("{:x}", Sha256::digest(data))).await?;
+        }
+        if let Some(expected) = &document.sha512 {
+            check_digest(client, expected, &format!("{:x}", Sha512::digest(data))).await?;
+        }
+
+        if certs.is_empty() {
+            return Ok(());
+        }
+        let Some(signature) = &document.signature else {
+            bail!("missing signature");
+        };
+        let signature = retrieve(client, signature)
+            .await
+            .context("failed to retrieve signature")?;
+        verify_signature(certs, self.validation_date, &signature, data)
+            .map_err(|err| anyhow!("unable to verify signature: {err}"))
+    }
+
+    /// Fix invalid license expressions, if enabled, keeping the document as is if it can't be fixed.
+    async fn process(&self, file: &str, data: Bytes, report: &Arc<Mutex<ReportBuilder>>) -> Bytes {
+        if !self.fix_licenses {
+            return data;
+        }
+
+        let reporter = {
+            let report = report.clone();
+            let file = file.to_string();
+            move |msg| {
+                report
+                    .lock()
+                    .add_error(Phase::Validation, file.clone(), Severity::Warning, msg);
+            }
+        };
+        let name = file.to_string();
+        let original = data.clone();
+        let outcome = tokio::task::spawn_blocking(move || crate::processing::process(data, &name, reporter)).await;
+
+        match outcome {
+            Ok(Ok(Some(data))) => data,
+            Ok(Ok(None)) => original,
+            Ok(Err(err)) => {
+                log::warn!("Failed processing, moving on: {err}");
+                original
+            }
+            Err(err) => {
+                log::warn!("Failed processing, moving on: {err}");
+                original
+            }
+        }
+    }
+
+    /// Upload a document, identified by its file name, retrying on server and network errors.
+    async fn upload(&self, client: &reqwest::Client, url: &Url, data: Bytes) -> anyhow::Result<()> {
+        let name = file_name(url);
+        let (name, encoding) = match name.strip_suffix(".bz2") {
+            Some(name) => (name, Some("bzip2")),
+            None => (name, None),
+        };
+        let (id, content_type) = match name.strip_suffix(".xml") {
+            Some(id) => (id, "application/vnd.cyclonedx+xml"),
+            None => (name.strip_suffix(".json").unwrap_or(name), "application/json"),
+        };
+
+        let mut attempt = 0;
+        loop {
+            let mut request = client
+                .post(self.target.clone())
+                .query(&[("id", id)])
+                .header(reqwest::header::CONTENT_TYPE, content_type)
+                .body(data.clone());
+            if let Some(encoding) = encoding {
+                request = request.header(reqwest::header::CONTENT_ENCODING, encoding);
+            }
+
+            let result = match request.inject_token(self.provider.as_ref()).await?.send().await {
+                Ok(response) if response.status().is_client_error() => {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    bail!("{status}: {body}");
+                }
+                Ok(response) => response.error_for_status().map(|_| ()),
+                Err(err) => Err(err),
+            };
+
+            match result {
+                Ok(()) => return Ok(()),
+                Err(err) if attempt >= self.retries => return Err(err.into()),
+                Err(err) => {
+                    attempt += 1;
+                    log::info!("Failed to upload {url}, retrying ({attempt}/{}): {err}", self.retries);
+                    tokio::time::sleep(self.retry_delay.unwrap_or(Duration::from_secs(5))).await;
+                }
+            }
+        }
+    }
+}
+
+/// The URL of a directory, which needs to end with a slash for relative links to resolve below it.
+fn directory(source: &str) -> anyhow::Result<Url> {
+    let mut url = Url::parse(source).with_context(|| format!("invalid source URL: {source}"))?;
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+    Ok(url)
+}
+
+async fn retrieve(client: &reqwest::Client, url: &Url) -> anyhow::Result<Bytes> {
+    Ok(client
+        .get(url.clone())
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?)
+}
+
+async fn load_manifest(client: &reqwest::Client, source: &str) -> anyhow::Result<Vec<Document>> {
+    let mut url = Url::parse(source).with_context(|| format!("invalid source URL: {source}"))?;
+    if url.path().ends_with('/') {
+        url = url.join(MANIFEST)?;
+    }
+
+    log::info!("Loading manifest: {url}");
+    let manifest: Manifest = serde_json::from_slice(&retrieve(client, &url).await?)
+        .with_context(|| format!("failed to parse manifest: {url}"))?;
+    manifest_documents(&url, manifest)
+}
+
+fn manifest_documents(url: &Url, manifest: Manifest) -> anyhow::Result<Vec<Document>> {
+    manifest
+        .documents
+        .into_iter()
+        .map(|entry| {
+            Ok(Document {
+                url: url
+                    .join(&entry.url)
+                    .with_context(|| format!("invalid document URL: {}", entry.url))?,
+                modified: entry.modified.map(Into::into),
+                sha256: entry.sha256.map(Expected::Inline),
+                sha512: entry.sha512.map(Expected::Inline),
+                signature: entry
+                    .signature
+                    .map(|signature| {
+                        url.join(&signature)
+                            .with_context(|| format!("invalid signature URL: {signature}"))
+                    })
+                    .transpose()?,
+            })
+        })
+        .collect()
+}
+
+/// Crawl a directory listing and its subdirectories, never leaving the base directory.
+async fn crawl(client: &reqwest::Client, base: &Url) -> anyhow::Result<Vec<Document>> {
+    let mut pending = vec![base.clone()];
+    let mut visited = HashSet::new();
+    let mut files = BTreeSet::new();
+
+    while let Some(dir) = pending.pop() {
+        if !visited.insert(dir.clone()) {
+            continue;
+        }
+
+        log::info!("Crawling directory: {dir}");
+        let listing = retrieve(client, &dir).await?;
+        let (dirs, found) = listing_entries(base, &dir, &String::from_utf8_lossy(&listing));
+        pending.extend(dirs);
+        files.extend(found);
+    }
+
+    Ok(listing_documents(&files))
+}
+
+/// The subdirectories and files a directory listing links to, ignoring links to parent directories, links leaving the
+/// base directory and links with a query, like the ones sorting the listing.
+fn listing_entries(base: &Url, dir: &Url, listing: &str) -> (Vec<Url>, Vec<Url>) {
+    let mut dirs = Vec::new();
+    let mut files = Vec::new();
+
+    for link in links(listing) {
+        let Ok(mut url) = dir.join(link) else {
+            continue;
+        };
+        url.set_fragment(None);
+        if url.query().is_some() || !url.as_str().starts_with(base.as_str()) || dir.as_str().starts_with(url.as_str()) {
+            continue;
+        }
+        match url.path().ends_with('/') {
+            true => dirs.push(url),
+            false => files.push(url),
+        }
+    }
+
+    (dirs, files)
+}
+
+/// The targets of the `href` attributes of an HTML document.
+fn links(html: &str) -> impl Iterator<Item = &str> {
+    html.split("href=")
+        .skip(1)
+        .filter_map(|rest| match rest.chars().next()? {
+            quote @ ('"' | '\'') => rest[1..].split(quote).next(),
+            _ => rest.split(|c: char| c.is_whitespace() || c == '>').next(),
+        })
+}
+
+/// The documents among the files of a listing, with the digests and signatures found next to them.
+fn listing_documents(files: &BTreeSet<Url>) -> Vec<Document> {
+    let sidecar = |url: &Url, extension: &str| {
+        Url::parse(&format!("{url}{extension}"))
+            .ok()
+            .filter(|url| files.contains(url))
+    };
+
+    files
+        .iter()
+        .filter(|url| is_document(file_name(url)))
+        .map(|url| Document {
+            url: url.clone(),
+            modified: None,
+            sha256: sidecar(url, ".sha256").map(Expected::Remote),
+            sha512: sidecar(url, ".sha512").map(Expected::Remote),
+            signature: sidecar(url, ".asc"),
+        })
+        .collect()
+}
+
+fn file_name(url: &Url) -> &str {
+    url.path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .unwrap_or_default()
+}
+
+/// Whether a file is an SPDX or CycloneDX document, possibly compressed.
+fn is_document(name: &str) -> bool {
+    let name = name.strip_suffix(".bz2").unwrap_or(name);
+    name.ends_with(".json") || name.ends_with(".xml")
+}
+
+async fn check_digest(client: &reqwest::Client, expected: &Expected, actual: &str) -> anyhow::Result<()> {
+    let expected = match expected {
+        Expected::Inline(digest) => digest.clone(),
+        Expected::Remote(url) => {
+            let data = retrieve(client, url).await.context("failed to retrieve digest")?;
+            // like the output of `sha256sum`: the digest, followed by the file name
+            String::from_utf8_lossy(&data)
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        }
+    };
+
+    match expected.eq_ignore_ascii_case(actual) {
+        true => Ok(()),
+        false => bail!("digest mismatch - expected: {expected}, actual: {actual}"),
+    }
+}
+
+/// Load the signing keys, checking their fingerprint if the URL has one as fragment.
+async fn load_keys(client: &reqwest::Client, keys: &[Url]) -> anyhow::Result<Vec<Cert>> {
+    let mut certs = Vec::with_capacity(keys.len());
+    for key in keys {
+        let mut url = key.clone();
+        url.set_fragment(None);
+        let data = retrieve(client, &url)
+            .await
+            .with_context(|| format!("failed to retrieve signing key: {key}"))?;
+        let cert = Cert::from_bytes(&data).with_context(|| format!("failed to parse signing key: {key}"))?;
+
+        if let Some(fingerprint) = key.fragment() {
+            if !cert.fingerprint().to_hex().eq_ignore_ascii_case(fingerprint) {
+                bail!(
+                    "fingerprint mismatch of signing key {key}: {}",
+                    cert.fingerprint().to_hex()
+                );
+            }
+        }
+        certs.push(cert);
+    }
+    Ok(certs)
+}
+
+struct Helper<'a> {
+    certs: &'a [Cert],
+}
+
+impl VerificationHelper for Helper<'_> {
+    fn get_certs(&mut self, _ids: &[KeyHandle]) -> sequoia_openpgp::Result<Vec<Cert>> {
+        Ok(self.certs.to_vec())
+    }
+
+    fn check(&mut self, structure: MessageStructure) -> sequoia_openpgp::Result<()> {
+        for layer in structure.into_iter() {
+            if let MessageLayer::SignatureGroup { results } = layer {
+                if results.iter().any(|result| result.is_ok()) {
+                    return Ok(());
+                }
+            }
+        }
+        Err(anyhow!("no valid signature of a signing key"))
+    }
+}
+
+/// Verify a detached signature of the data, made by one of the certificates.
+fn verify_signature(
+    certs: &[Cert],
+    validation_date: Option<SystemTime>,
+    signature: &[u8],
+    data: &[u8],
+) -> anyhow::Result<()> {
+    let policy = match validation_date {
+        Some(date) => StandardPolicy::at(date),
+        None => StandardPolicy::new(),
+    };
+    let mut verifier = DetachedVerifierBuilder::from_bytes(signature)?.with_policy(&policy, None, Helper { certs })?;
+    verifier.verify_bytes(data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn listing() {
+        let base = Url::parse("https://example.com/sboms/").unwrap();
+        let dir = base.join("2024/").unwrap();
+        let listing = r#"<html><body>
+            <a href="?C=M;O=A">Last modified</a>
+            <a href="/">Parent Directory</a>
+            <a href="../">../</a>
+            <a href="01/">01/</a>
+            <a href='app.json'>app.json</a>
+            <a href=app.json.sha256>app.json.sha256</a>
+            <a href="app.json.asc">app.json.asc</a>
+            <a href="lib.xml.bz2">lib.xml.bz2</a>
+            <a href="README.txt">README.txt</a>
+            <a href="https://example.org/other.json">elsewhere</a>
+        </body></html>"#;
+
+        let (dirs, files) = listing_entries(&base, &dir, listing);
+        assert_eq!(dirs, vec![base.join("2024/01/").unwrap()]);
+        assert_eq!(files.len(), 5);
+
+        let documents = listing_documents(&files.into_iter().collect());
+        assert_eq!(
+            documents,
+            vec![
+                Document {
+                    url: dir.join("app.json").unwrap(),
+                    modified: None,
+                    sha256: Some(Expected::Remote(dir.join("app.json.sha256").unwrap())),
+                    sha512: None,
+                    signature: Some(dir.join("app.json.asc").unwrap()),
+                },
+                Document {
+                    url: dir.join("lib.xml.bz2").unwrap(),
+                    modified: None,
+                    sha256: None,
+                    sha512: None,
+                    signature: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn manifest() {
+        let url = Url::parse("https://example.com/sboms/index.json").unwrap();
+        let manifest: Manifest = serde_json::from_str(
+            r#"{"documents": [
+                {"url": "app.json", "modified": "2024-01-01T00:00:00Z", "sha256": "abc", "signature": "app.json.asc"},
+                {"url": "https://cdn.example.com/lib.json"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let documents = manifest_documents(&url, manifest).unwrap();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[0].url.as_str(), "https://example.com/sboms/app.json");
+        assert_eq!(
+            documents[0].modified,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_704_067_200))
+        );
+        assert_eq!(documents[0].sha256, Some(Expected::Inline("abc".into())));
+        assert_eq!(
+            documents[0].signature.as_ref().map(Url::as_str),
+            Some("https://example.com/sboms/app.json.asc")
+        );
+        assert_eq!(documents[1].url.as_str(), "https://cdn.example.com/lib.json");
+        assert_eq!(documents[1].modified, None);
+    }
+
+    #[test]
+    fn directories() {
+        assert_eq!(
+            directory("https://example.com/sboms").unwrap().as_str(),
+            "https://example.com/sboms/"
+        );
+        assert_eq!(
+            directory("https://example.com/sboms/").unwrap().as_str(),
+            "https://example.com/sboms/"
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use crate::scanner::{Options, Scanner};
+use crate::{
+    generic::Layout,
+    scanner::{Options, Scanner},
+};
 use anyhow::{anyhow, Context};
 use clap::{arg, command, ArgAction, Args};
 use std::path::PathBuf;
@@ -7,7 +10,11 @@ use std::sync::Arc;
 use std::time::SystemTime;
 use time::{Date, Month, UtcOffset};
 use trustification_auth::client::{OpenIdTokenProviderConfig, OpenIdTokenProviderConfigArguments};
-use trustification_common_walker::report::{handle_report, ReportGenerateOption, SplitScannerError};
+use trustification_common_walker::{
+    filter::FilterArgs,
+    report::{handle_report, ReportGenerateOption, SplitScannerError},
+    run::{RunMetrics, RunPublisher},
+};
 use trustification_infrastructure::{
     endpoint::{self, Endpoint},
     Infrastructure, InfrastructureConfig,
@@ -15,6 +22,8 @@ use trustification_infrastructure::{
 use url::Url;
 use walker_common::sender::provider::TokenProvider;
 
+mod filter;
+mod generic;
 mod processing;
 mod report;
 mod scanner;
@@ -62,6 +71,11 @@ pub struct Run {
     #[arg(long, env)]
     pub source: Option<String>,
 
+    /// How the SBOMs of the source are discovered: the `changes.csv` layout of the SBOM walker, an HTTPS directory
+    /// listing crawled recursively, or an `index.json` manifest.
+    #[arg(long, env, value_enum, default_value_t = Layout::Changes)]
+    pub source_layout: Layout,
+
     /// OIDC client
     #[command(flatten)]
     pub oidc: OpenIdTokenProviderConfigArguments,
@@ -89,9 +103,16 @@ pub struct Run {
     #[arg(long, env, default_value_t = false, action = ArgAction::Set)]
     pub report_enable: bool,
 
+    /// Publish a report of each run to the API service, see `/api/v1/walker/runs`.
+    #[arg(long, env, default_value_t = true, action = ArgAction::Set)]
+    pub publish_runs: bool,
+
     /// Define report output path
     #[arg(long, env, default_value = "/tmp/share/reports")]
     pub report_path: String,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
 }
 
 impl Run {
@@ -100,13 +121,13 @@ impl Run {
             .run(
                 "bombastic-walker",
                 |_context| async { Ok(()) },
-                |_| async move {
+                |context| async move {
                     let source = self
                         .source
                         .or_else(|| self.devmode.then(|| DEVMODE_SOURCE.to_string()))
                         .ok_or_else(|| anyhow!("Missing source. Provider either --source <url> or --devmode"))?;
 
-                    let keys = self
+                    let key_urls: Vec<Url> = self
                         .signing_key
                         .into_iter()
                         .chain(
@@ -114,8 +135,8 @@ impl Run {
                                 .then(|| Url::parse(DEVMODE_KEY).context("failed to parse devmode key"))
                                 .transpose()?,
                         )
-                        .map(|key| key.into())
                         .collect();
+                    let keys = key_urls.iter().cloned().map(|key| key.into()).collect();
 
                     let validation_date: Option<SystemTime> = match (self.policy_date, self.v3_signatures) {
                         (_, true) => Some(SystemTime::from(
@@ -130,7 +151,19 @@ impl Run {
 
                     log::debug!("Policy date: {validation_date:?}");
 
-                    let provider = match OpenIdTokenProviderConfig::from_args_or_devmode(self.oidc, self.devmode) {
+                    let oidc = OpenIdTokenProviderConfig::from_args_or_devmode(self.oidc, self.devmode);
+                    let token_provider = OpenIdTokenProviderConfig::new_provider(oidc.clone()).await?;
+                    let runs = match self.publish_runs {
+                        true => Some(RunPublisher::new(
+                            &self.sink,
+                            &source,
+                            token_provider.clone(),
+                            &self.additional_root_certificates,
+                        )?),
+                        false => None,
+                    };
+
+                    let provider = match oidc {
                         Some(OpenIdTokenProviderConfig {
                             issuer_url,
                             client_id,
@@ -153,17 +186,35 @@ impl Run {
                         None => Arc::new(()),
                     };
 
+                    // record where the documents came from
+                    let mut target = self.sink.join("/api/v1/sbom")?;
+                    target
+                        .query_pairs_mut()
+                        .append_pair("source", "walker")
+                        .append_pair("source_url", &source);
+
+                    let filter = self.filter.into_config()?;
+                    if !filter.product_families.is_empty() {
+                        anyhow::bail!("Product families can only be filtered by the vexination walker");
+                    }
+
                     let scanner = Scanner::new(Options {
+                        layout: self.source_layout,
                         source,
-                        target: self.sink.join("/api/v1/sbom")?,
+                        target,
                         keys,
+                        key_urls,
                         provider,
+                        token_provider,
                         validation_date,
                         fix_licenses: self.fix_licenses,
                         since_file: self.since_file,
                         retries: self.retries,
                         retry_delay: self.retry_delay.map(|d| d.into()),
                         additional_root_certificates: self.additional_root_certificates,
+                        runs,
+                        metrics: RunMetrics::register(context.metrics.registry())?,
+                        filter,
                     });
 
                     if let Some(interval) = self.scan_interval {
@@ -87,7 +87,7 @@ where
     }
 }
 
-fn process(data: Bytes, name: &str, reporter: impl Fn(String)) -> anyhow::Result<Option<Bytes>> {
+pub(crate) fn process(data: Bytes, name: &str, reporter: impl Fn(String)) -> anyhow::Result<Option<Bytes>> {
     let (data, compressed) = match decompress_opt(&data, name).transpose()? {
         Some(data) => (data, true),
         None => (data, false),
@@ -1,4 +1,9 @@
-use crate::{processing::ProcessVisitor, report::SbomReportVisitor};
+use crate::{
+    filter::FilteringVisitor,
+    generic::{GenericWalker, Layout},
+    processing::ProcessVisitor,
+    report::SbomReportVisitor,
+};
 use parking_lot::Mutex;
 use sbom_walker::{
     discover::DiscoverConfig, model::metadata::Key, retrieve::RetrievingVisitor, source::new_source,
@@ -9,7 +14,11 @@ use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use tokio::time::MissedTickBehavior;
 use tracing::{instrument, log};
-use trustification_common_walker::report::{Report, ReportBuilder, ReportVisitor, ScannerError};
+use trustification_common_walker::{
+    filter::{DocumentFilter, FilterConfig},
+    report::{Report, ReportBuilder, ReportVisitor, ScannerError},
+    run::{walker_run, RunMetrics, RunPublisher},
+};
 use url::Url;
 use walker_common::{
     fetcher::FetcherOptions,
@@ -19,16 +28,27 @@ use walker_common::{
 };
 
 pub struct Options {
+    /// How the documents of the source are discovered
+    pub layout: Layout,
     pub source: String,
     pub target: Url,
     pub keys: Vec<Key>,
+    /// URLs of the signing keys, which the generic layouts validate signatures with
+    pub key_urls: Vec<Url>,
     pub provider: Arc<dyn TokenProvider>,
+    /// Token provider for uploading the documents of the generic layouts
+    pub token_provider: Arc<dyn trustification_auth::client::TokenProvider>,
     pub validation_date: Option<SystemTime>,
     pub fix_licenses: bool,
     pub since_file: Option<PathBuf>,
     pub retries: usize,
     pub retry_delay: Option<Duration>,
     pub additional_root_certificates: Vec<PathBuf>,
+    /// Where to publish the reports of runs, if at all
+    pub runs: Option<RunPublisher>,
+    /// Metrics of the latest run
+    pub metrics: RunMetrics,
+    pub filter: FilterConfig,
 }
 
 pub struct Scanner {
@@ -52,16 +72,43 @@ impl Scanner {
         }
     }
 
-    #[instrument(skip(self))]
     pub async fn run_once(&self) -> Result<Report, ScannerError> {
+        let result = self.scan().await;
+        self.options.metrics.observe(&walker_run(&self.options.source, &result));
+        if let Some(runs) = &self.options.runs {
+            runs.publish(&result).await;
+        }
+        result
+    }
+
+    #[instrument(skip(self))]
+    async fn scan(&self) -> Result<Report, ScannerError> {
         let report = Arc::new(Mutex::new(ReportBuilder::new()));
 
         let since = Since::new(None::<SystemTime>, self.options.since_file.clone(), Default::default())?;
 
+        match self.options.layout {
+            Layout::Changes => self.walk(&report, *since).await?,
+            layout => self.walk_generic(layout, &report, *since).await?,
+        }
+
+        // if we fail to store the marker, we fail altogether
+        since.store()?;
+
+        // we're done and return the report
+        Ok(match Arc::try_unwrap(report) {
+            Ok(report) => report.into_inner(),
+            Err(report) => report.lock().clone(),
+        }
+        .build())
+    }
+
+    /// Walk a source using the layout of the SBOM walker.
+    async fn walk(&self, report: &Arc<Mutex<ReportBuilder>>, since: Option<SystemTime>) -> Result<(), ScannerError> {
         let source = new_source(
             DiscoverConfig {
                 source: self.options.source.clone(),
-                since: *since,
+                since,
                 keys: self.options.keys.clone(),
             },
             FetcherOptions::default(),
@@ -87,25 +134,52 @@ impl Scanner {
         let validation = ValidationVisitor::new(process)
             .with_options(ValidationOptions::new().validation_date(self.options.validation_date));
 
+        let filtered = FilteringVisitor {
+            filter: DocumentFilter::new(&self.options.filter)?,
+            next: RetrievingVisitor::new(source.clone(), validation),
+            report: report.clone(),
+        };
+
         let walker = Walker::new(source.clone());
         walker
-            .walk(RetrievingVisitor::new(source.clone(), validation))
+            .walk(filtered)
             .await
             // if the walker fails, we record the outcome as part of the report, but skip any
             // further processing, like storing the marker
             .map_err(|err| ScannerError::Normal {
                 err: err.into(),
                 report: report.lock().clone().build(),
-            })?;
+            })
+    }
 
-        // if we fail to store the marker, we fail altogether
-        since.store()?;
+    /// Walk a source using one of the generic layouts.
+    async fn walk_generic(
+        &self,
+        layout: Layout,
+        report: &Arc<Mutex<ReportBuilder>>,
+        since: Option<SystemTime>,
+    ) -> Result<(), ScannerError> {
+        let walker = GenericWalker {
+            layout,
+            source: self.options.source.clone(),
+            target: self.options.target.clone(),
+            provider: self.options.token_provider.clone(),
+            keys: self.options.key_urls.clone(),
+            validation_date: self.options.validation_date,
+            fix_licenses: self.options.fix_licenses,
+            retries: self.options.retries,
+            retry_delay: self.options.retry_delay,
+            additional_root_certificates: self.options.additional_root_certificates.clone(),
+        };
+        let filter = DocumentFilter::new(&self.options.filter)?;
 
-        // we're done and return the report
-        Ok(match Arc::try_unwrap(report) {
-            Ok(report) => report.into_inner(),
-            Err(report) => report.lock().clone(),
-        }
-        .build())
+        walker
+            .walk(&filter, since, report)
+            .await
+            // like with the SBOM walker layout, failing documents are part of the report
+            .map_err(|err| ScannerError::Normal {
+                err,
+                report: report.lock().clone().build(),
+            })
     }
 }
@@ -0,0 +1,131 @@
+use crate::{Client, Error};
+use bombastic_model::prelude::{LintReport, SearchPackageResult, SearchResult, StatusResult};
+use bytes::Bytes;
+use reqwest::{header, Url};
+use tracing::instrument;
+use trustification_api::{search::SearchOptions, Apply};
+
+/// Client for the bombastic (SBOM) API.
+pub struct BombasticClient<'a> {
+    client: &'a Client,
+    url: &'a Url,
+}
+
+impl<'a> BombasticClient<'a> {
+    pub(crate) fn new(client: &'a Client, url: &'a Url) -> Self {
+        Self { client, url }
+    }
+
+    /// Fetch an SBOM, returns `None` if it does not exist.
+    #[instrument(skip(self), err)]
+    pub async fn get_sbom(&self, id: &str) -> Result<Option<Bytes>, Error> {
+        let request = self
+            .client
+            .http()
+            .get(self.url.join("/api/v1/sbom")?)
+            .query(&[("id", id)]);
+        match self.client.send_opt(request).await? {
+            Some(response) => Ok(Some(response.bytes().await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Upload an SBOM in JSON format, returning its identifier.
+    ///
+    /// If no identifier is provided, the server derives it from the content (if enabled).
+    #[instrument(skip(self, data), err)]
+    pub async fn upload_sbom(&self, id: Option<&str>, data: Bytes) -> Result<String, Error> {
+        let mut request = self
+            .client
+            .http()
+            .put(self.url.join("/api/v1/sbom")?)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(data);
+        if let Some(id) = id {
+            request = request.query(&[("id", id)]);
+        }
+        let response = self.client.send(request).await?;
+
+        // the location carries the assigned identifier
+        let location = response
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| self.url.join(location).ok());
+        Ok(location
+            .and_then(|location| {
+                location
+                    .query_pairs()
+                    .find_map(|(key, value)| (key == "id").then(|| value.into_owned()))
+            })
+            .or_else(|| id.map(ToString::to_string))
+            .unwrap_or_default())
+    }
+
+    /// Validate an SBOM without storing it.
+    #[instrument(skip(self, data), err)]
+    pub async fn validate_sbom(&self, data: Bytes) -> Result<LintReport, Error> {
+        let request = self
+            .client
+            .http()
+            .post(self.url.join("/api/v1/sbom/validate")?)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(data);
+        Ok(self.client.send(request).await?.json().await?)
+    }
+
+    /// Delete an SBOM, succeeds if it does not exist.
+    #[instrument(skip(self), err)]
+    pub async fn delete_sbom(&self, id: &str) -> Result<(), Error> {
+        let request = self
+            .client
+            .http()
+            .delete(self.url.join("/api/v1/sbom")?)
+            .query(&[("id", id)]);
+        self.client.send(request).await?;
+        Ok(())
+    }
+
+    #[instrument(skip(self), err)]
+    pub async fn search_sbom(
+        &self,
+        q: &str,
+        offset: usize,
+        limit: usize,
+        options: &SearchOptions,
+    ) -> Result<SearchResult, Error> {
+        let request = self
+            .client
+            .http()
+            .get(self.url.join("/api/v1/sbom/search")?)
+            .query(&[("q", q)])
+            .query(&[("offset", offset), ("limit", limit)])
+            .apply(options);
+        Ok(self.client.send(request).await?.json().await?)
+    }
+
+    #[instrument(skip(self), err)]
+    pub async fn search_package(
+        &self,
+        q: &str,
+        offset: usize,
+        limit: usize,
+        options: &SearchOptions,
+    ) -> Result<SearchPackageResult, Error> {
+        let request = self
+            .client
+            .http()
+            .get(self.url.join("/api/v1/package/search")?)
+            .query(&[("q", q)])
+            .query(&[("offset", offset), ("limit", limit)])
+            .apply(options);
+        Ok(self.client.send(request).await?.json().await?)
+    }
+
+    /// Total number of SBOMs and the last updated one.
+    #[instrument(skip(self), err)]
+    pub async fn status(&self) -> Result<StatusResult, Error> {
+        let request = self.client.http().get(self.url.join("/api/v1/sbom/status")?);
+        Ok(self.client.send(request).await?.json().await?)
+    }
+}
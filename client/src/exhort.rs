@@ -0,0 +1,46 @@
+use crate::{Client, Error};
+use exhort_model::{AnalyzeRequest, AnalyzeResponse, RecommendResponse, VulnerabilitiesResponse};
+use reqwest::Url;
+use tracing::instrument;
+
+/// Client for the exhort (dependency analysis) API.
+pub struct ExhortClient<'a> {
+    client: &'a Client,
+    url: &'a Url,
+}
+
+impl<'a> ExhortClient<'a> {
+    pub(crate) fn new(client: &'a Client, url: &'a Url) -> Self {
+        Self { client, url }
+    }
+
+    /// Analyze packages for known vulnerabilities.
+    #[instrument(skip(self), err)]
+    pub async fn analyze(&self, purls: Vec<String>) -> Result<AnalyzeResponse, Error> {
+        self.post("/api/v1/analyze", purls).await
+    }
+
+    /// Recommend remediated versions of packages.
+    #[instrument(skip(self), err)]
+    pub async fn recommend(&self, purls: Vec<String>) -> Result<RecommendResponse, Error> {
+        self.post("/api/v1/recommend", purls).await
+    }
+
+    /// Find the vulnerabilities affecting packages.
+    #[instrument(skip(self), err)]
+    pub async fn vulnerabilities(&self, purls: Vec<String>) -> Result<VulnerabilitiesResponse, Error> {
+        self.post("/api/v1/vulnerabilities", purls).await
+    }
+
+    async fn post<T>(&self, path: &str, purls: Vec<String>) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let request = self
+            .client
+            .http()
+            .post(self.url.join(path)?)
+            .json(&AnalyzeRequest { purls });
+        Ok(self.client.send(request).await?.json().await?)
+    }
+}
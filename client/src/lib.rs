@@ -0,0 +1,271 @@
+//! Trustification Client
+//!
+//! This crate provides a typed, async client for the APIs of bombastic, vexination, v11y, exhort and spog. All
+//! services share the same HTTP client, token provider and retry policy.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), trustification_client::Error> {
+//! use trustification_client::{Client, Endpoints, NoTokenProvider, SearchOptions};
+//!
+//! let endpoints = Endpoints {
+//!     bombastic: Some("http://localhost:8082".parse().unwrap()),
+//!     ..Default::default()
+//! };
+//! let client = Client::new(reqwest::Client::new(), endpoints, NoTokenProvider);
+//! let result = client
+//!     .bombastic()?
+//!     .search_sbom("openssl", 0, 10, &SearchOptions::default())
+//!     .await?;
+//! println!("Found {} SBOMs", result.total);
+//! # Ok(())
+//! # }
+//! ```
+
+mod bombastic;
+mod exhort;
+mod spog;
+mod v11y;
+mod vexination;
+
+pub use bombastic::BombasticClient;
+pub use exhort::ExhortClient;
+pub use spog::SpogClient;
+pub use v11y::V11yClient;
+pub use vexination::VexinationClient;
+
+// Re-export to align versions
+pub use bombastic_model;
+pub use exhort_model;
+pub use reqwest;
+pub use spog_model;
+pub use trustification_api::search::{SearchOptions, SearchResult};
+pub use trustification_auth::client::{NoTokenProvider, OpenIdTokenProviderConfig, TokenProvider};
+pub use v11y_model;
+pub use vexination_model;
+
+use reqwest::{RequestBuilder, Response, StatusCode, Url};
+use std::{sync::Arc, time::Duration};
+use trustification_auth::client::TokenInjector;
+use trustification_infrastructure::tracing::PropagateCurrentContext;
+
+/// Base URLs of the services, a client can only be obtained for configured services.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Endpoints {
+    pub bombastic: Option<Url>,
+    pub vexination: Option<Url>,
+    pub v11y: Option<Url>,
+    pub exhort: Option<Url>,
+    pub spog: Option<Url>,
+}
+
+/// Retrying of requests failing with connection errors or temporarily unavailable services.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of retries after the initial attempt.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for every further retry.
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between retries.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy which never retries.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+/// Errors returned by the client.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("http error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("auth error: {0}")]
+    Auth(#[from] trustification_auth::client::Error),
+    #[error("URL error: {0}")]
+    Url(#[from] url::ParseError),
+    #[error("unexpected response {status}: {message}")]
+    Response { status: StatusCode, message: String },
+    #[error("no endpoint configured for {0}")]
+    NotConfigured(&'static str),
+}
+
+/// A client for all Trustification services.
+#[derive(Clone)]
+pub struct Client {
+    client: reqwest::Client,
+    endpoints: Endpoints,
+    provider: Arc<dyn TokenProvider>,
+    retry: RetryPolicy,
+}
+
+impl Client {
+    pub fn new<P>(client: reqwest::Client, endpoints: Endpoints, provider: P) -> Self
+    where
+        P: TokenProvider + 'static,
+    {
+        Self {
+            client,
+            endpoints,
+            provider: Arc::new(provider),
+            retry: Default::default(),
+        }
+    }
+
+    /// Replace the default retry policy.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn bombastic(&self) -> Result<BombasticClient<'_>, Error> {
+        Ok(BombasticClient::new(
+            self,
+            endpoint(&self.endpoints.bombastic, "bombastic")?,
+        ))
+    }
+
+    pub fn vexination(&self) -> Result<VexinationClient<'_>, Error> {
+        Ok(VexinationClient::new(
+            self,
+            endpoint(&self.endpoints.vexination, "vexination")?,
+        ))
+    }
+
+    pub fn v11y(&self) -> Result<V11yClient<'_>, Error> {
+        Ok(V11yClient::new(self, endpoint(&self.endpoints.v11y, "v11y")?))
+    }
+
+    pub fn exhort(&self) -> Result<ExhortClient<'_>, Error> {
+        Ok(ExhortClient::new(self, endpoint(&self.endpoints.exhort, "exhort")?))
+    }
+
+    pub fn spog(&self) -> Result<SpogClient<'_>, Error> {
+        Ok(SpogClient::new(self, endpoint(&self.endpoints.spog, "spog")?))
+    }
+
+    pub(crate) fn http(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Send a request, retrying it on connection errors and temporarily unavailable services.
+    ///
+    /// The token is injected for every attempt, so that an expired token gets refreshed. Responses with a status other
+    /// than success are returned as [`Error::Response`].
+    pub(crate) async fn send(&self, request: RequestBuilder) -> Result<Response, Error> {
+        let mut request = Some(request);
+        let mut attempt = 0;
+        loop {
+            let (current, retryable) = match request.as_ref().and_then(|r| r.try_clone()) {
+                Some(current) if attempt < self.retry.max_retries => (current, true),
+                // last attempt, or a streaming body which can only be sent once
+                _ => (
+                    request.take().expect("request is only taken by the last attempt"),
+                    false,
+                ),
+            };
+
+            let result = current
+                .propagate_current_context()
+                .inject_token(self.provider.as_ref())
+                .await?
+                .send()
+                .await;
+
+            let retry = match &result {
+                Ok(response) => matches!(
+                    response.status(),
+                    StatusCode::TOO_MANY_REQUESTS
+                        | StatusCode::BAD_GATEWAY
+                        | StatusCode::SERVICE_UNAVAILABLE
+                        | StatusCode::GATEWAY_TIMEOUT
+                ),
+                Err(e) => e.is_connect() || e.is_timeout(),
+            };
+
+            if retryable && retry {
+                let delay = self.retry.backoff(attempt);
+                log::info!("Request failed (attempt {}), retrying in {delay:?}", attempt + 1);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+                continue;
+            }
+
+            return or_status_error(result?).await;
+        }
+    }
+
+    /// Send a request, returning `None` if the resource was not found.
+    pub(crate) async fn send_opt(&self, request: RequestBuilder) -> Result<Option<Response>, Error> {
+        match self.send(request).await {
+            Ok(response) => Ok(Some(response)),
+            Err(Error::Response {
+                status: StatusCode::NOT_FOUND,
+                ..
+            }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+fn endpoint<'a>(url: &'a Option<Url>, service: &'static str) -> Result<&'a Url, Error> {
+    url.as_ref().ok_or(Error::NotConfigured(service))
+}
+
+async fn or_status_error(response: Response) -> Result<Response, Error> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        Err(Error::Response {
+            status,
+            message: response.text().await?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(800));
+        assert_eq!(policy.backoff(4), Duration::from_secs(1));
+        assert_eq!(policy.backoff(40), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn not_configured() {
+        let client = Client::new(reqwest::Client::new(), Endpoints::default(), NoTokenProvider);
+        assert!(matches!(client.bombastic(), Err(Error::NotConfigured("bombastic"))));
+        assert!(matches!(client.spog(), Err(Error::NotConfigured("spog"))));
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{Client, Error};
+use reqwest::Url;
+use spog_model::{
+    dashboard::DashboardStatus,
+    search::{AdvisorySummary, SbomSummary},
+    vuln::SbomReport,
+};
+use tracing::instrument;
+use trustification_api::{
+    search::{SearchOptions, SearchResult},
+    Apply,
+};
+
+/// Client for the spog API, aggregating the information of the other services.
+pub struct SpogClient<'a> {
+    client: &'a Client,
+    url: &'a Url,
+}
+
+impl<'a> SpogClient<'a> {
+    pub(crate) fn new(client: &'a Client, url: &'a Url) -> Self {
+        Self { client, url }
+    }
+
+    #[instrument(skip(self), err)]
+    pub async fn search_sbom(
+        &self,
+        q: &str,
+        offset: usize,
+        limit: usize,
+        options: &SearchOptions,
+    ) -> Result<SearchResult<Vec<SbomSummary>>, Error> {
+        self.search("/api/v1/sbom/search", q, offset, limit, options).await
+    }
+
+    #[instrument(skip(self), err)]
+    pub async fn search_advisories(
+        &self,
+        q: &str,
+        offset: usize,
+        limit: usize,
+        options: &SearchOptions,
+    ) -> Result<SearchResult<Vec<AdvisorySummary>>, Error> {
+        self.search("/api/v1/advisory/search", q, offset, limit, options).await
+    }
+
+    /// The vulnerabilities affecting an SBOM, returns `None` if the SBOM does not exist.
+    #[instrument(skip(self), err)]
+    pub async fn sbom_vulnerabilities(&self, id: &str) -> Result<Option<SbomReport>, Error> {
+        let request = self
+            .client
+            .http()
+            .get(self.url.join("/api/v1/sbom/vulnerabilities")?)
+            .query(&[("id", id)]);
+        match self.client.send_opt(request).await? {
+            Some(response) => Ok(Some(response.json().await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Number of documents in all services, and the last updated ones.
+    #[instrument(skip(self), err)]
+    pub async fn status(&self) -> Result<DashboardStatus, Error> {
+        let request = self.client.http().get(self.url.join("/api/v1/dashboard/status")?);
+        Ok(self.client.send(request).await?.json().await?)
+    }
+
+    async fn search<T>(
+        &self,
+        path: &str,
+        q: &str,
+        offset: usize,
+        limit: usize,
+        options: &SearchOptions,
+    ) -> Result<SearchResult<T>, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let request = self
+            .client
+            .http()
+            .get(self.url.join(path)?)
+            .query(&[("q", q)])
+            .query(&[("offset", offset), ("limit", limit)])
+            .apply(options);
+        Ok(self.client.send(request).await?.json().await?)
+    }
+}
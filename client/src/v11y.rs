@@ -0,0 +1,71 @@
+use crate::{Client, Error};
+use bytes::Bytes;
+use reqwest::Url;
+use tracing::instrument;
+use trustification_api::search::SearchResult;
+use v11y_model::{
+    search::{SearchDocument, SearchHit, StatusResult},
+    Vulnerability,
+};
+
+/// Client for the v11y (vulnerability) API.
+pub struct V11yClient<'a> {
+    client: &'a Client,
+    url: &'a Url,
+}
+
+impl<'a> V11yClient<'a> {
+    pub(crate) fn new(client: &'a Client, url: &'a Url) -> Self {
+        Self { client, url }
+    }
+
+    /// Fetch the CVE record of a vulnerability, returns `None` if it does not exist.
+    #[instrument(skip(self), err)]
+    pub async fn get_cve(&self, id: &str) -> Result<Option<Bytes>, Error> {
+        let mut url = self.url.join("/api/v1/cve")?;
+        url.path_segments_mut()
+            .map_err(|()| url::ParseError::RelativeUrlWithCannotBeABaseBase)?
+            .push(id);
+        match self.client.send_opt(self.client.http().get(url)).await? {
+            Some(response) => Ok(Some(response.bytes().await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Fetch a vulnerability, as reported by all known origins.
+    #[instrument(skip(self), err)]
+    pub async fn get_vulnerability(&self, id: &str) -> Result<Vec<Vulnerability>, Error> {
+        let url = self.url.join("/api/v1/vulnerability/")?.join(id)?;
+        Ok(self.client.send(self.client.http().get(url)).await?.json().await?)
+    }
+
+    /// Fetch vulnerabilities by one of their aliases.
+    #[instrument(skip(self), err)]
+    pub async fn get_vulnerability_by_alias(&self, alias: &str) -> Result<Vec<Vulnerability>, Error> {
+        let url = self.url.join("/api/v1/vulnerability/by-alias/")?.join(alias)?;
+        Ok(self.client.send(self.client.http().get(url)).await?.json().await?)
+    }
+
+    #[instrument(skip(self), err)]
+    pub async fn search(
+        &self,
+        q: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<SearchResult<Vec<SearchHit<SearchDocument>>>, Error> {
+        let request = self
+            .client
+            .http()
+            .get(self.url.join("/api/v1/search")?)
+            .query(&[("q", q)])
+            .query(&[("offset", offset), ("limit", limit)]);
+        Ok(self.client.send(request).await?.json().await?)
+    }
+
+    /// Total number of vulnerabilities and the last updated one.
+    #[instrument(skip(self), err)]
+    pub async fn status(&self) -> Result<StatusResult, Error> {
+        let request = self.client.http().get(self.url.join("/api/v1/status")?);
+        Ok(self.client.send(request).await?.json().await?)
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{Client, Error};
+use bytes::Bytes;
+use reqwest::{header, Url};
+use tracing::instrument;
+use trustification_api::{search::SearchOptions, Apply};
+use vexination_model::prelude::{CveTimeline, SearchResult, StatusResult};
+
+/// Client for the vexination (CSAF advisory) API.
+pub struct VexinationClient<'a> {
+    client: &'a Client,
+    url: &'a Url,
+}
+
+impl<'a> VexinationClient<'a> {
+    pub(crate) fn new(client: &'a Client, url: &'a Url) -> Self {
+        Self { client, url }
+    }
+
+    /// Fetch an advisory, returns `None` if it does not exist.
+    #[instrument(skip(self), err)]
+    pub async fn get_vex(&self, advisory: &str) -> Result<Option<Bytes>, Error> {
+        let request = self
+            .client
+            .http()
+            .get(self.url.join("/api/v1/vex")?)
+            .query(&[("advisory", advisory)]);
+        match self.client.send_opt(request).await? {
+            Some(response) => Ok(Some(response.bytes().await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Upload a CSAF document, using the identifier of the document unless overridden.
+    #[instrument(skip(self, data), err)]
+    pub async fn upload_vex(&self, advisory: Option<&str>, data: Bytes) -> Result<(), Error> {
+        let mut request = self
+            .client
+            .http()
+            .put(self.url.join("/api/v1/vex")?)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(data);
+        if let Some(advisory) = advisory {
+            request = request.query(&[("advisory", advisory)]);
+        }
+        self.client.send(request).await?;
+        Ok(())
+    }
+
+    /// Delete an advisory, succeeds if it does not exist.
+    #[instrument(skip(self), err)]
+    pub async fn delete_vex(&self, advisory: &str) -> Result<(), Error> {
+        let request = self
+            .client
+            .http()
+            .delete(self.url.join("/api/v1/vex")?)
+            .query(&[("advisory", advisory)]);
+        self.client.send(request).await?;
+        Ok(())
+    }
+
+    #[instrument(skip(self), err)]
+    pub async fn search_vex(
+        &self,
+        q: &str,
+        offset: usize,
+        limit: usize,
+        options: &SearchOptions,
+    ) -> Result<SearchResult, Error> {
+        let request = self
+            .client
+            .http()
+            .get(self.url.join("/api/v1/vex/search")?)
+            .query(&[("q", q)])
+            .query(&[("offset", offset), ("limit", limit)])
+            .apply(options);
+        Ok(self.client.send(request).await?.json().await?)
+    }
+
+    /// Timeline of the advisory events related to a CVE.
+    #[instrument(skip(self), err)]
+    pub async fn timeline(&self, cve: &str) -> Result<CveTimeline, Error> {
+        let request = self
+            .client
+            .http()
+            .get(self.url.join("/api/v1/vex/timeline")?)
+            .query(&[("cve", cve)]);
+        Ok(self.client.send(request).await?.json().await?)
+    }
+
+    /// Total number of advisories and the last updated one.
+    #[instrument(skip(self), err)]
+    pub async fn status(&self) -> Result<StatusResult, Error> {
+        let request = self.client.http().get(self.url.join("/api/v1/vex/status")?);
+        Ok(self.client.send(request).await?.json().await?)
+    }
+}
@@ -0,0 +1,212 @@
+//! Filtering of discovered documents, before they get retrieved.
+
+use anyhow::Context;
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+use std::{path::PathBuf, time::SystemTime};
+use time::OffsetDateTime;
+use url::Url;
+
+/// Command line arguments for filtering documents.
+#[derive(Clone, Debug, Default, clap::Args)]
+#[command(next_help_heading = "Filter")]
+pub struct FilterArgs {
+    /// Only ingest documents with a file name matching any of these patterns. Patterns are globs,
+    /// unless prefixed with `re:`, in which case they are regular expressions.
+    #[arg(long = "include", env = "FILTER_INCLUDE", value_delimiter = ',')]
+    pub include: Vec<String>,
+
+    /// Skip documents with a file name matching any of these patterns (same syntax as `--include`).
+    #[arg(long = "exclude", env = "FILTER_EXCLUDE", value_delimiter = ',')]
+    pub exclude: Vec<String>,
+
+    /// Only ingest documents modified at or after this date.
+    #[arg(long = "modified-after", env = "FILTER_MODIFIED_AFTER")]
+    pub modified_after: Option<humantime::Timestamp>,
+
+    /// Only ingest documents modified before this date.
+    #[arg(long = "modified-before", env = "FILTER_MODIFIED_BEFORE")]
+    pub modified_before: Option<humantime::Timestamp>,
+
+    /// A YAML file containing additional filter configuration.
+    #[arg(long = "filter-config", env = "FILTER_CONFIG")]
+    pub filter_config: Option<PathBuf>,
+}
+
+/// Filter configuration, as read from a configuration file.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterConfig {
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub modified_after: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub modified_before: Option<OffsetDateTime>,
+    /// Product families of advisories, only supported by the vexination walker
+    #[serde(default)]
+    pub product_families: Vec<String>,
+}
+
+impl FilterArgs {
+    /// Merge the command line arguments with the content of the configuration file, if any.
+    pub fn into_config(self) -> anyhow::Result<FilterConfig> {
+        let mut config = match &self.filter_config {
+            Some(path) => {
+                let data = std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+                serde_yaml::from_slice(&data).with_context(|| format!("failed to parse {}", path.display()))?
+            }
+            None => FilterConfig::default(),
+        };
+
+        config.include.extend(self.include);
+        config.exclude.extend(self.exclude);
+        if let Some(after) = self.modified_after {
+            config.modified_after = Some(SystemTime::from(after).into());
+        }
+        if let Some(before) = self.modified_before {
+            config.modified_before = Some(SystemTime::from(before).into());
+        }
+
+        Ok(config)
+    }
+}
+
+enum Pattern {
+    Glob(GlobMatcher),
+    Regex(Regex),
+}
+
+impl Pattern {
+    fn new(pattern: &str) -> anyhow::Result<Self> {
+        Ok(match pattern.strip_prefix("re:") {
+            Some(regex) => Self::Regex(Regex::new(regex).with_context(|| format!("invalid regex: {regex}"))?),
+            None => Self::Glob(
+                Glob::new(pattern)
+                    .with_context(|| format!("invalid glob: {pattern}"))?
+                    .compile_matcher(),
+            ),
+        })
+    }
+
+    fn is_match(&self, name: &str) -> bool {
+        match self {
+            Self::Glob(glob) => glob.is_match(name),
+            Self::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// Decides if a discovered document should be ingested, based on its name and modification time.
+pub struct DocumentFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    modified_after: Option<SystemTime>,
+    modified_before: Option<SystemTime>,
+}
+
+impl DocumentFilter {
+    pub fn new(config: &FilterConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            include: config
+                .include
+                .iter()
+                .map(|p| Pattern::new(p))
+                .collect::<Result<_, _>>()?,
+            exclude: config
+                .exclude
+                .iter()
+                .map(|p| Pattern::new(p))
+                .collect::<Result<_, _>>()?,
+            modified_after: config.modified_after.map(Into::into),
+            modified_before: config.modified_before.map(Into::into),
+        })
+    }
+
+    /// Check if the document at this URL, modified at the provided time, should be ingested.
+    ///
+    /// Patterns are matched against the file name of the document (the last segment of the URL path).
+    pub fn matches(&self, url: &Url, modified: SystemTime) -> bool {
+        let name = url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .unwrap_or_default();
+
+        if !self.include.is_empty() && !self.include.iter().any(|p| p.is_match(name)) {
+            return false;
+        }
+        if self.exclude.iter().any(|p| p.is_match(name)) {
+            return false;
+        }
+        if matches!(self.modified_after, Some(after) if modified < after) {
+            return false;
+        }
+        if matches!(self.modified_before, Some(before) if modified >= before) {
+            return false;
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn url(name: &str) -> Url {
+        Url::parse("https://example.com/data/")
+            .and_then(|base| base.join(name))
+            .unwrap()
+    }
+
+    #[test]
+    fn patterns() {
+        let filter = DocumentFilter::new(&FilterConfig {
+            include: vec!["rhsa-2023_*.json".into(), "re:^cve-\\d+".into()],
+            exclude: vec!["*_1441.json".into()],
+            ..Default::default()
+        })
+        .unwrap();
+
+        let now = SystemTime::now();
+        assert!(filter.matches(&url("rhsa-2023_3408.json"), now));
+        assert!(filter.matches(&url("cve-2023.json"), now));
+        assert!(!filter.matches(&url("rhsa-2023_1441.json"), now));
+        assert!(!filter.matches(&url("rhsa-2021_3029.json"), now));
+    }
+
+    #[test]
+    fn modified() {
+        let now = SystemTime::now();
+        let filter = DocumentFilter::new(&FilterConfig {
+            modified_after: Some((now - Duration::from_secs(60)).into()),
+            modified_before: Some(now.into()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert!(filter.matches(&url("a.json"), now - Duration::from_secs(30)));
+        assert!(!filter.matches(&url("a.json"), now - Duration::from_secs(120)));
+        assert!(!filter.matches(&url("a.json"), now));
+    }
+
+    #[test]
+    fn config_file() {
+        let config: FilterConfig = serde_yaml::from_str(
+            r#"
+include:
+  - "*.json"
+modifiedAfter: 2023-01-01T00:00:00Z
+productFamilies:
+  - Red Hat Enterprise Linux
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.include, vec!["*.json"]);
+        assert!(config.modified_after.is_some());
+        assert_eq!(config.product_families, vec!["Red Hat Enterprise Linux"]);
+    }
+}
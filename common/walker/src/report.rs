@@ -27,6 +27,12 @@ pub struct Report {
 
     #[serde(default)]
     pub numer_of_items: usize,
+    /// Number of documents discovered
+    #[serde(default)]
+    pub discovered: usize,
+    /// Number of discovered documents skipped by filters
+    #[serde(default)]
+    pub skipped: usize,
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub messages: BTreeMap<Phase, BTreeMap<String, Vec<Message>>>,
 }
@@ -49,6 +55,8 @@ impl ReportBuilder {
                 start_date: OffsetDateTime::now_utc(),
                 end_date: OffsetDateTime::now_utc(),
                 numer_of_items: 0,
+                discovered: 0,
+                skipped: 0,
                 messages: Default::default(),
             },
         }
@@ -58,6 +66,16 @@ impl ReportBuilder {
         self.report.numer_of_items += 1;
     }
 
+    /// Record a discovered document.
+    pub fn discover(&mut self) {
+        self.report.discovered += 1;
+    }
+
+    /// Record a document skipped by a filter.
+    pub fn skip(&mut self) {
+        self.report.skipped += 1;
+    }
+
     pub fn add_error(&mut self, phase: Phase, file: impl Into<String>, severity: Severity, message: impl Into<String>) {
         let file = file.into();
         let message = message.into();
@@ -0,0 +1,246 @@
+//! Publishing the reports of walker runs to the API service the walker uploads its documents to, and exposing them
+//! as metrics.
+
+use crate::report::{Phase, Report, ScannerError, Severity};
+use anyhow::Context;
+use prometheus::{opts, register_gauge_with_registry, register_int_gauge_with_registry, Gauge, IntGauge, Registry};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    sync::Arc,
+};
+use time::OffsetDateTime;
+use trustification_auth::client::{TokenInjector, TokenProvider};
+use trustification_storage::{RunStatus, WalkerRun};
+use url::Url;
+
+pub struct RunPublisher {
+    client: reqwest::Client,
+    url: Url,
+    source: String,
+    provider: Arc<dyn TokenProvider>,
+}
+
+impl RunPublisher {
+    /// Create a publisher for the API service at `sink`, trusting the additional root certificates.
+    pub fn new(
+        sink: &Url,
+        source: impl Into<String>,
+        provider: Arc<dyn TokenProvider>,
+        additional_root_certificates: &[PathBuf],
+    ) -> anyhow::Result<Self> {
+        let mut client = reqwest::Client::builder();
+        for cert in additional_root_certificates {
+            let pem = std::fs::read(cert).with_context(|| format!("failed to read certificate: {}", cert.display()))?;
+            client = client.add_root_certificate(reqwest::Certificate::from_pem(&pem)?);
+        }
+
+        Ok(Self {
+            client: client.build()?,
+            url: sink.join("/api/v1/walker/runs")?,
+            source: source.into(),
+            provider,
+        })
+    }
+
+    /// Publish the outcome of a run.
+    ///
+    /// Failing to publish the report is logged, but doesn't fail the run.
+    pub async fn publish(&self, result: &Result<Report, ScannerError>) {
+        let run = walker_run(&self.source, result);
+        if let Err(err) = self.send(&run).await {
+            log::warn!("Failed to publish walker run report: {err}");
+        }
+    }
+
+    async fn send(&self, run: &WalkerRun) -> anyhow::Result<()> {
+        self.client
+            .post(self.url.clone())
+            .json(run)
+            .inject_token(self.provider.as_ref())
+            .await?
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Metrics of the latest run, so that runs are observable even if the walker exits after a single run, when pushed to
+/// a Pushgateway.
+pub struct RunMetrics {
+    timestamp: IntGauge,
+    duration: Gauge,
+    success: IntGauge,
+    discovered: IntGauge,
+    skipped: IntGauge,
+    downloaded: IntGauge,
+    failed: IntGauge,
+}
+
+impl RunMetrics {
+    pub fn register(registry: &Registry) -> Result<Self, prometheus::Error> {
+        let gauge = |name: &str, help: &str| register_int_gauge_with_registry!(opts!(name, help), registry);
+        Ok(Self {
+            timestamp: gauge(
+                "walker_last_run_timestamp_seconds",
+                "Time the latest run ended, in seconds since the epoch",
+            )?,
+            duration: register_gauge_with_registry!(
+                opts!("walker_last_run_duration_seconds", "Duration of the latest run"),
+                registry
+            )?,
+            success: gauge(
+                "walker_last_run_success",
+                "Whether the latest run succeeded (1) or failed (0)",
+            )?,
+            discovered: gauge(
+                "walker_last_run_documents_discovered",
+                "Number of documents discovered by the latest run",
+            )?,
+            skipped: gauge(
+                "walker_last_run_documents_skipped",
+                "Number of documents skipped by filters in the latest run",
+            )?,
+            downloaded: gauge(
+                "walker_last_run_documents_downloaded",
+                "Number of documents retrieved by the latest run",
+            )?,
+            failed: gauge(
+                "walker_last_run_documents_failed",
+                "Number of documents which failed to be retrieved, validated or uploaded in the latest run",
+            )?,
+        })
+    }
+
+    /// Record the outcome of a run.
+    pub fn observe(&self, run: &WalkerRun) {
+        self.timestamp.set(run.end_date.unix_timestamp());
+        self.duration.set(run.duration_ms as f64 / 1000.0);
+        self.success.set((run.status == RunStatus::Succeeded) as i64);
+        self.discovered.set(run.discovered as i64);
+        self.skipped.set(run.skipped as i64);
+        self.downloaded.set(run.downloaded as i64);
+        self.failed.set(run.failed as i64);
+    }
+}
+
+/// Summarize the outcome of a run.
+pub fn walker_run(source: &str, result: &Result<Report, ScannerError>) -> WalkerRun {
+    let (report, error) = match result {
+        Ok(report) => (report, None),
+        Err(ScannerError::Normal { err, report }) => (report, Some(err)),
+        Err(ScannerError::Critical(err)) => {
+            // there is no report of a critical failure, it failed before discovering anything
+            let now = OffsetDateTime::now_utc();
+            return WalkerRun {
+                source: source.to_string(),
+                start_date: now,
+                end_date: now,
+                duration_ms: 0,
+                status: RunStatus::Failed,
+                error: Some(err.to_string()),
+                discovered: 0,
+                skipped: 0,
+                downloaded: 0,
+                failed: 0,
+                failures: Default::default(),
+            };
+        }
+    };
+
+    let mut failures = BTreeMap::<String, Vec<String>>::new();
+    let mut failed = BTreeSet::new();
+    let mut not_retrieved = BTreeSet::new();
+    for (phase, files) in &report.messages {
+        for (file, messages) in files {
+            for message in messages {
+                failures
+                    .entry(file.clone())
+                    .or_default()
+                    .push(format!("{phase:?}: {}", message.message));
+                if message.severity == Severity::Error {
+                    failed.insert(file);
+                    if *phase == Phase::Retrieval {
+                        not_retrieved.insert(file);
+                    }
+                }
+            }
+        }
+    }
+
+    WalkerRun {
+        source: source.to_string(),
+        start_date: report.start_date,
+        end_date: report.end_date,
+        duration_ms: (report.end_date - report.start_date).whole_milliseconds().max(0) as u64,
+        status: match error {
+            None => RunStatus::Succeeded,
+            Some(_) => RunStatus::Failed,
+        },
+        error: error.map(|err| err.to_string()),
+        discovered: report.discovered,
+        skipped: report.skipped,
+        downloaded: report.numer_of_items.saturating_sub(not_retrieved.len()),
+        failed: failed.len(),
+        failures,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::ReportBuilder;
+
+    #[test]
+    fn summarize() {
+        let mut report = ReportBuilder::new();
+        for _ in 0..4 {
+            report.discover();
+        }
+        report.skip();
+        for _ in 0..3 {
+            report.tick();
+        }
+        report.add_error(
+            Phase::Retrieval,
+            "a.json",
+            Severity::Error,
+            "retrieval of document failed: 404",
+        );
+        report.add_error(Phase::Upload, "b.json", Severity::Error, "upload failed");
+        report.add_error(Phase::Validation, "c.json", Severity::Warning, "fixed license");
+
+        let run = walker_run("https://example.com", &Ok(report.build()));
+        assert_eq!(run.status, RunStatus::Succeeded);
+        assert_eq!(run.discovered, 4);
+        assert_eq!(run.skipped, 1);
+        assert_eq!(run.downloaded, 2);
+        assert_eq!(run.failed, 2);
+        assert_eq!(run.failures["b.json"], vec!["Upload: upload failed".to_string()]);
+        assert_eq!(run.failures.len(), 3);
+    }
+
+    #[test]
+    fn metrics() {
+        let registry = Registry::new();
+        let metrics = RunMetrics::register(&registry).unwrap();
+        metrics.observe(&walker_run(
+            "https://example.com",
+            &Err(ScannerError::Critical(anyhow::anyhow!("boom"))),
+        ));
+        assert_eq!(metrics.success.get(), 0);
+        assert_eq!(metrics.discovered.get(), 0);
+        assert_eq!(registry.gather().len(), 7);
+    }
+
+    #[test]
+    fn critical() {
+        let run = walker_run(
+            "https://example.com",
+            &Err(ScannerError::Critical(anyhow::anyhow!("boom"))),
+        );
+        assert_eq!(run.status, RunStatus::Failed);
+        assert_eq!(run.error.as_deref(), Some("boom"));
+    }
+}
@@ -126,11 +126,13 @@ authentication:
           - "read.sbom"
           - "update.sbom"
           - "delete.sbom"
+          - "ingest.document"
         "trustification/vexination":
           - "create.vex"
           - "read.vex"
           - "update.vex"
           - "delete.vex"
+          - "ingest.document"
         "trustification/v11y":
           - "read.cve"
 
@@ -145,6 +147,7 @@ authentication:
         "read:document": [ "read.sbom", "read.vex" ]
         "update:document": [ "update.sbom", "update.vex" ]
         "delete:document": [ "delete.sbom", "delete.vex" ]
+        "ingest:document": [ "ingest.document" ]
       {{- with .root.Values.tls.additionalTrustAnchor }}
       tlsCaCertificates:
         - {{ . | quote }}
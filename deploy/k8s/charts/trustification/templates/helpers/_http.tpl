@@ -16,6 +16,9 @@ Arguments (dict):
   value: "/etc/tls/tls.key"
 - name: HTTP_SERVER_TLS_CERTIFICATE_FILE
   value: "/etc/tls/tls.crt"
+# the service CA renews the certificate before it expires
+- name: HTTP_SERVER_TLS_RELOAD_INTERVAL
+  value: "1m"
 {{ end }}
 
 {{- with .module.requestLimit }}
@@ -28,6 +31,40 @@ Arguments (dict):
   value: {{ include "trustification.common.byteSizeValue" . }}
 {{- end }}
 
+{{- with .module.concurrencyLimits }}
+{{- $limits := list }}
+{{- range $path, $limit := . }}
+{{- $limits = append $limits (printf "%s=%v" $path $limit) }}
+{{- end }}
+- name: HTTP_SERVER_CONCURRENCY_LIMIT
+  value: {{ join "," $limits | quote }}
+{{- end }}
+
+{{- with .module.retryAfter }}
+- name: HTTP_SERVER_RETRY_AFTER
+  value: {{ . | quote }}
+{{- end }}
+
+{{- with .module.anonymousRateLimit }}
+- name: HTTP_SERVER_ANONYMOUS_RATE_LIMIT
+  value: {{ . | quote }}
+{{- end }}
+
+{{- with .module.trustedProxies }}
+- name: HTTP_SERVER_TRUSTED_PROXIES
+  value: {{ join "," . | quote }}
+{{- end }}
+
+{{- with .module.rateLimit }}
+- name: HTTP_SERVER_RATE_LIMIT
+  value: {{ . | quote }}
+{{- end }}
+
+{{- with .module.rateLimitBurst }}
+- name: HTTP_SERVER_RATE_LIMIT_BURST
+  value: {{ . | quote }}
+{{- end }}
+
 {{- end }}
 
 {{/*
@@ -8,14 +8,19 @@ use rdkafka::{
     consumer::{stream_consumer::StreamConsumer, Consumer},
     error::KafkaError,
     message::BorrowedMessage,
-    producer::{FutureProducer, FutureRecord},
-    Message,
+    producer::{FutureProducer, FutureRecord, Producer},
+    Message, TopicPartitionList,
 };
 
+/// Timeout of transaction operations.
+const TRANSACTION_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[allow(unused)]
 pub struct KafkaEventBus {
     config: ClientConfig,
     producer: FutureProducer,
+    /// Producer sending events along with the offsets of consumed events, if transactions are enabled
+    transactional: Option<FutureProducer>,
 }
 
 impl From<KafkaError> for Error {
@@ -28,7 +33,11 @@ impl From<KafkaError> for Error {
 }
 
 impl KafkaEventBus {
-    pub(crate) fn new(brokers: String, properties: Vec<(String, String)>) -> Result<Self, Error> {
+    pub(crate) fn new(
+        brokers: String,
+        properties: Vec<(String, String)>,
+        transactional_id: Option<String>,
+    ) -> Result<Self, Error> {
         let mut config = ClientConfig::new();
         config.set("bootstrap.servers", &brokers);
 
@@ -40,7 +49,24 @@ impl KafkaEventBus {
         producer_config.set("message.timeout.ms", "5000");
         let producer: FutureProducer = producer_config.create()?;
 
-        Ok(Self { config, producer })
+        let transactional = match transactional_id {
+            Some(id) => {
+                // sending events outside of a transaction is not possible with a transactional producer, so it
+                // requires a producer of its own
+                let mut transactional_config = producer_config.clone();
+                transactional_config.set("transactional.id", id);
+                let producer: FutureProducer = transactional_config.create()?;
+                producer.init_transactions(TRANSACTION_TIMEOUT)?;
+                Some(producer)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            config,
+            producer,
+            transactional,
+        })
     }
 
     pub(crate) async fn create(&self, topics: &[&str]) -> Result<(), Error> {
@@ -76,6 +102,60 @@ impl KafkaEventBus {
             .map_err(|(err, _)| err)?;
         Ok(())
     }
+
+    /// Send events and commit the offsets of the consumed events in a single transaction.
+    ///
+    /// Without transactions, the events are sent before committing the offsets, so a failure may lead to the events
+    /// being sent again, but never to events being lost.
+    pub(crate) async fn send_and_commit<'m>(
+        &self,
+        consumer: &'m KafkaConsumer,
+        messages: &[(&str, &[u8])],
+        events: &[Event<'m>],
+    ) -> Result<(), Error> {
+        let Some(producer) = &self.transactional else {
+            for (topic, data) in messages {
+                self.send(topic, data).await?;
+            }
+            return consumer.commit(events).await;
+        };
+
+        producer.begin_transaction()?;
+        match Self::transaction(producer, consumer, messages, events).await {
+            Ok(()) => Ok(producer.commit_transaction(TRANSACTION_TIMEOUT)?),
+            Err(err) => {
+                if let Err(e) = producer.abort_transaction(TRANSACTION_TIMEOUT) {
+                    log::warn!("Error aborting transaction: {e}");
+                }
+                Err(err)
+            }
+        }
+    }
+
+    async fn transaction<'m>(
+        producer: &FutureProducer,
+        consumer: &'m KafkaConsumer,
+        messages: &[(&str, &[u8])],
+        events: &[Event<'m>],
+    ) -> Result<(), Error> {
+        for (topic, data) in messages {
+            let record = FutureRecord::to(topic).payload(*data);
+            producer
+                .send::<(), _, _>(record, Duration::from_secs(10))
+                .await
+                .map_err(|(err, _)| err)?;
+        }
+
+        if let Some(offsets) = consumer.offsets(events)? {
+            let metadata = consumer
+                .consumer
+                .group_metadata()
+                .ok_or_else(|| Error::Transient("missing consumer group metadata".into()))?;
+            producer.send_offsets_to_transaction(&offsets, &metadata, TRANSACTION_TIMEOUT)?;
+        }
+
+        Ok(())
+    }
 }
 
 pub struct KafkaConsumer {
@@ -89,6 +169,15 @@ impl KafkaConsumer {
     }
 
     pub(crate) async fn commit<'m>(&'m self, events: &[Event<'m>]) -> Result<(), Error> {
+        if let Some(position) = self.offsets(events)? {
+            Consumer::commit(&self.consumer, &position, rdkafka::consumer::CommitMode::Sync)?;
+        }
+
+        Ok(())
+    }
+
+    /// The offsets to commit after processing the events, `None` if there are no Kafka events.
+    fn offsets<'m>(&'m self, events: &[Event<'m>]) -> Result<Option<TopicPartitionList>, Error> {
         let mut position = self.consumer.position()?;
         let mut events_found = false;
         for event in events {
@@ -101,11 +190,7 @@ impl KafkaConsumer {
             }
         }
 
-        if events_found {
-            Consumer::commit(&self.consumer, &position, rdkafka::consumer::CommitMode::Sync)?;
-        }
-
-        Ok(())
+        Ok(events_found.then_some(position))
     }
 }
 
@@ -124,6 +124,43 @@ impl EventBus {
         self.metrics.sent_total.with_label_values(&[topic]).inc();
         Ok(())
     }
+
+    /// Send messages to topics and commit the events the consumer received.
+    ///
+    /// For Kafka, with a transactional id configured, sending and committing is atomic, so consumers of the sent
+    /// messages (reading committed messages only) observe neither duplicates nor gaps. Otherwise, and for SQS, the
+    /// messages are sent before the events are committed, so a failure in between leads to the events being processed,
+    /// and the messages being sent, again.
+    pub async fn send_and_commit<'m>(
+        &self,
+        consumer: &'m EventConsumer,
+        messages: &[(&str, &[u8])],
+        events: &[Event<'m>],
+    ) -> Result<(), Error> {
+        match (&self.inner, &consumer.inner) {
+            (InnerBus::Kafka(bus), InnerConsumer::Kafka(kafka)) => bus.send_and_commit(kafka, messages, events).await?,
+            _ => {
+                for (topic, data) in messages {
+                    match &self.inner {
+                        InnerBus::Kafka(bus) => bus.send(topic, data).await?,
+                        InnerBus::Sqs(bus) => bus.send(topic, data).await?,
+                    }
+                }
+                match &consumer.inner {
+                    InnerConsumer::Kafka(consumer) => consumer.commit(events).await?,
+                    InnerConsumer::Sqs(consumer) => consumer.commit(events).await?,
+                }
+            }
+        }
+
+        for (topic, _) in messages {
+            self.metrics.sent_total.with_label_values(&[topic]).inc();
+        }
+        for event in events {
+            self.metrics.committed_total.with_label_values(&[event.topic()]).inc();
+        }
+        Ok(())
+    }
 }
 
 /// An event consumer belongs to a group and consumes events from multiple topics.
@@ -214,6 +251,12 @@ pub struct EventBusConfig {
     /// Add all env-vars having this prefix as key/value entry
     #[arg(env = "KAFKA_PROPERTIES_ENV_PREFIX", long = "kafka-properties-env-prefix")]
     pub kafka_properties_env_prefix: Option<String>,
+
+    /// Kafka transactional id, enables sending events and committing consumed events atomically.
+    ///
+    /// The id must be unique for each instance of a service.
+    #[arg(env = "KAFKA_TRANSACTIONAL_ID", long = "kafka-transactional-id")]
+    pub kafka_transactional_id: Option<String>,
 }
 
 impl EventBusConfig {
@@ -232,7 +275,11 @@ impl EventBusConfig {
                     }
                 }
 
-                let bus = kafka::KafkaEventBus::new(bootstrap, properties)?;
+                if let Some(id) = &self.kafka_transactional_id {
+                    log::info!("Kafka transactional id: {id}");
+                }
+
+                let bus = kafka::KafkaEventBus::new(bootstrap, properties, self.kafka_transactional_id.clone())?;
 
                 Ok(EventBus {
                     metrics: Metrics::register(registry)?,
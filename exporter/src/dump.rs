@@ -0,0 +1,400 @@
+//! Periodic dumps of all stored SBOMs and VEX documents.
+//!
+//! Consumers bootstrapping a mirror can download a dump, instead of fetching each document from the APIs. Each dump is
+//! written to `<path>/<name>/`, named by the time it was taken, as a gzipped tarball of each kind of document, along
+//! with a manifest listing the documents of each tarball with their size and digest. The manifest is written last, and
+//! copied to `<path>/latest.json`, so consumers only ever find complete dumps. Removing old dumps is left to the
+//! lifecycle rules of the bucket.
+
+use crate::DocumentType;
+use flate2::{write::GzEncoder, Compression};
+use futures::{pin_mut, StreamExt};
+use prometheus::{
+    opts, register_int_counter_with_registry, register_int_gauge_with_registry, IntCounter, IntGauge, Registry,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+use time::{macros::format_description, OffsetDateTime};
+use tokio::io::AsyncWriteExt;
+use tokio::time::MissedTickBehavior;
+use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
+use trustification_storage::{ContinuationToken, Storage, StorageConfig};
+
+/// Name of the manifest, stored next to the tarballs of a dump.
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// Name of the copy of the manifest of the latest dump.
+pub const LATEST_NAME: &str = "latest.json";
+
+/// Version of the manifest format.
+pub const MANIFEST_VERSION: u32 = 1;
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Periodically dump all SBOMs and VEX documents to a bucket",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Dump {
+    /// Location to write the dumps to, like <bucket>/<path>
+    #[arg(long = "to", env = "DUMP_TO")]
+    pub to: DumpLocation,
+
+    /// The kinds of documents to dump
+    #[arg(long = "document-type", value_enum, default_values_t = [DocumentType::SBOM, DocumentType::VEX])]
+    pub document_types: Vec<DocumentType>,
+
+    /// Bucket the SBOMs are stored in
+    #[arg(long = "sbom-bucket", default_value = "bombastic")]
+    pub sbom_bucket: String,
+
+    /// Bucket the VEX documents are stored in
+    #[arg(long = "vex-bucket", default_value = "vexination")]
+    pub vex_bucket: String,
+
+    /// Interval of taking dumps, a single dump is taken if missing
+    #[arg(long = "interval", env = "DUMP_INTERVAL")]
+    pub interval: Option<humantime::Duration>,
+
+    /// Directory the tarballs are written to, before being uploaded
+    #[arg(long = "work-dir", env = "DUMP_WORK_DIR", default_value_os_t = std::env::temp_dir())]
+    pub work_dir: PathBuf,
+
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    #[command(flatten)]
+    pub storage: StorageConfig,
+
+    #[command(flatten)]
+    pub infra: InfrastructureConfig,
+}
+
+impl Dump {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        Infrastructure::from(self.infra.clone())
+            .run(
+                "exporter-dump",
+                |_context| async { Ok(()) },
+                |context| async move {
+                    // the storages would register the same metrics, so they don't register any
+                    let mut sources = Vec::new();
+                    for kind in &self.document_types {
+                        let bucket = match kind {
+                            DocumentType::SBOM => &self.sbom_bucket,
+                            DocumentType::VEX => &self.vex_bucket,
+                        };
+                        sources.push((
+                            kind.clone(),
+                            Storage::new(self.storage_config_for(bucket), &Registry::new())?,
+                        ));
+                    }
+                    let target = Storage::new(self.storage_config_for(&self.to.bucket), &Registry::new())?;
+                    let dumper = Dumper {
+                        to: self.to.clone(),
+                        target,
+                        sources,
+                        work_dir: self.work_dir.clone(),
+                        metrics: Metrics::register(context.metrics.registry())?,
+                    };
+
+                    match self.interval {
+                        Some(interval) => dumper.run(interval.into()).await,
+                        None => dumper.dump().await.map(|_| ()),
+                    }
+                },
+            )
+            .await?;
+        Ok(ExitCode::SUCCESS)
+    }
+
+    fn storage_config_for(&self, bucket: &str) -> StorageConfig {
+        let mut config = self.storage.clone();
+        config.bucket = Some(bucket.to_string());
+        config.process(bucket, self.devmode)
+    }
+}
+
+/// Location of the dumps, in the form of `<bucket>/<path>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DumpLocation {
+    pub bucket: String,
+    pub path: String,
+}
+
+impl DumpLocation {
+    fn object(&self, name: &str) -> String {
+        match self.path.is_empty() {
+            true => name.to_string(),
+            false => format!("{}/{}", self.path, name),
+        }
+    }
+}
+
+impl std::str::FromStr for DumpLocation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim_start_matches("s3://").trim_matches('/');
+        let (bucket, path) = s.split_once('/').unwrap_or((s, ""));
+        if bucket.is_empty() {
+            return Err("the location requires a bucket, like <bucket>/<path>".to_string());
+        }
+        Ok(Self {
+            bucket: bucket.to_string(),
+            path: path.trim_matches('/').to_string(),
+        })
+    }
+}
+
+/// The manifest of a dump, listing the tarballs and the documents they contain.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    /// Name of the dump, which is the path of its tarballs relative to the location of the dumps
+    pub name: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created: OffsetDateTime,
+    pub archives: Vec<ArchiveEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    pub document_type: DocumentType,
+    /// Name of the tarball, relative to the dump
+    pub name: String,
+    pub size: usize,
+    /// SHA-256 digest of the tarball, hex encoded
+    pub sha256: String,
+    pub documents: Vec<DocumentEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentEntry {
+    /// Key of the document, as it is stored
+    pub key: String,
+    /// Path of the document in the tarball, which is its URL encoded key
+    pub path: String,
+    pub size: usize,
+    /// SHA-256 digest of the document, hex encoded
+    pub sha256: String,
+}
+
+impl DocumentEntry {
+    fn new(key: String, data: &[u8]) -> Self {
+        Self {
+            path: format!("{}.json", urlencoding::encode(&key)),
+            key,
+            size: data.len(),
+            sha256: format!("{:x}", Sha256::digest(data)),
+        }
+    }
+}
+
+struct Metrics {
+    dumps_total: IntCounter,
+    dumps_failed_total: IntCounter,
+    last_dump: IntGauge,
+}
+
+impl Metrics {
+    fn register(registry: &Registry) -> anyhow::Result<Self> {
+        let dumps_total = register_int_counter_with_registry!(
+            opts!("exporter_dumps_total", "Total number of dumps taken"),
+            registry
+        )?;
+
+        let dumps_failed_total = register_int_counter_with_registry!(
+            opts!("exporter_dumps_failed_total", "Total number of dumps which failed"),
+            registry
+        )?;
+
+        let last_dump = register_int_gauge_with_registry!(
+            opts!(
+                "exporter_last_dump_timestamp_seconds",
+                "Time the latest complete dump was taken, as Unix timestamp"
+            ),
+            registry
+        )?;
+
+        Ok(Self {
+            dumps_total,
+            dumps_failed_total,
+            last_dump,
+        })
+    }
+}
+
+type Archive = tar::Builder<GzEncoder<Vec<u8>>>;
+
+/// Takes the dumps of the stored documents.
+struct Dumper {
+    to: DumpLocation,
+    target: Storage,
+    sources: Vec<(DocumentType, Storage)>,
+    work_dir: PathBuf,
+    metrics: Metrics,
+}
+
+impl Dumper {
+    async fn run(&self, interval: Duration) -> anyhow::Result<()> {
+        let mut interval = tokio::time::interval(interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.dump().await {
+                log::warn!("Failed to dump the stored documents: {err}");
+            }
+        }
+    }
+
+    async fn dump(&self) -> anyhow::Result<Manifest> {
+        self.metrics.dumps_total.inc();
+        let res = self.take().await;
+        match &res {
+            Ok(manifest) => self.metrics.last_dump.set(manifest.created.unix_timestamp()),
+            Err(_) => self.metrics.dumps_failed_total.inc(),
+        }
+        res
+    }
+
+    async fn take(&self) -> anyhow::Result<Manifest> {
+        let created = OffsetDateTime::now_utc();
+        let name = created.format(format_description!("[year][month][day]T[hour][minute][second]Z"))?;
+        log::info!("Taking dump {name} to {}/{}", self.to.bucket, self.to.path);
+
+        let mut archives = Vec::new();
+        for (document_type, source) in &self.sources {
+            let archive_name = format!("{document_type}.tar.gz");
+            let file = self.work_dir.join(format!("{name}-{archive_name}"));
+            let res = self
+                .upload(source, &file, &self.to.object(&format!("{name}/{archive_name}")))
+                .await;
+            // the tarball is removed whether or not it was uploaded
+            if let Err(err) = tokio::fs::remove_file(&file).await {
+                log::warn!("Failed to remove {}: {err}", file.display());
+            }
+            let (size, sha256, documents) = res?;
+            log::info!("Dumped {} {document_type} documents ({size} bytes)", documents.len());
+            archives.push(ArchiveEntry {
+                document_type: document_type.clone(),
+                name: archive_name,
+                size,
+                sha256,
+                documents,
+            });
+        }
+
+        let manifest = Manifest {
+            version: MANIFEST_VERSION,
+            name: name.clone(),
+            created,
+            archives,
+        };
+        let data = serde_json::to_vec_pretty(&manifest)?;
+        self.target
+            .put_object(&self.to.object(&format!("{name}/{MANIFEST_NAME}")), &data)
+            .await?;
+        self.target.put_object(&self.to.object(LATEST_NAME), &data).await?;
+        Ok(manifest)
+    }
+
+    /// Write the documents of a storage to a tarball, and upload it.
+    ///
+    /// Returns the size and digest of the tarball, as well as its documents.
+    async fn upload(
+        &self,
+        source: &Storage,
+        file: &Path,
+        path: &str,
+    ) -> anyhow::Result<(usize, String, Vec<DocumentEntry>)> {
+        let (sha256, documents) = write_archive(source, file).await?;
+        let mut reader = tokio::fs::File::open(file).await?;
+        let size = self
+            .target
+            .put_object_stream(path, "application/gzip", &mut reader)
+            .await?;
+        Ok((size, sha256, documents))
+    }
+}
+
+/// Write all documents of a storage to a gzipped tarball.
+///
+/// Returns the digest of the tarball, and its documents.
+async fn write_archive(source: &Storage, path: &Path) -> anyhow::Result<(String, Vec<DocumentEntry>)> {
+    let mut file = tokio::fs::File::create(path).await?;
+    let mut hasher = Sha256::new();
+    let mut archive = Archive::new(GzEncoder::new(Vec::new(), Compression::default()));
+    let mut documents = Vec::new();
+
+    let objects = source.list_objects_from(ContinuationToken::default());
+    pin_mut!(objects);
+    while let Some(next) = objects.next().await {
+        let (path, data) = next.map_err(|(e, _)| e)?;
+        let entry = DocumentEntry::new(path.key().to_string(), &data);
+        append(&mut archive, &entry.path, &data)?;
+        let chunk = std::mem::take(archive.get_mut().get_mut());
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+        documents.push(entry);
+    }
+
+    let chunk = archive.into_inner()?.finish()?;
+    hasher.update(&chunk);
+    file.write_all(&chunk).await?;
+    file.flush().await?;
+
+    Ok((format!("{:x}", hasher.finalize()), documents))
+}
+
+fn append(archive: &mut Archive, name: &str, data: &[u8]) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    archive.append_data(&mut header, name, data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn location() {
+        let location: DumpLocation = "s3://dumps/trustification/".parse().unwrap();
+        assert_eq!(location.bucket, "dumps");
+        assert_eq!(location.object(LATEST_NAME), "trustification/latest.json");
+
+        let location: DumpLocation = "dumps".parse().unwrap();
+        assert_eq!(location.object(LATEST_NAME), "latest.json");
+
+        assert!("/".parse::<DumpLocation>().is_err());
+    }
+
+    #[test]
+    fn archive() {
+        let entry = DocumentEntry::new("quarkus/3.2.6".to_string(), b"{}");
+        assert_eq!(entry.path, "quarkus%2F3.2.6.json");
+        assert_eq!(
+            entry.sha256,
+            "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+        );
+
+        let mut archive = Archive::new(GzEncoder::new(Vec::new(), Compression::default()));
+        append(&mut archive, &entry.path, b"{}").unwrap();
+        let data = archive.into_inner().unwrap().finish().unwrap();
+
+        let mut archive = tar::Archive::new(GzDecoder::new(data.as_slice()));
+        let mut entries = archive.entries().unwrap();
+        let mut file = entries.next().unwrap().unwrap();
+        assert_eq!(file.path().unwrap().to_str(), Some("quarkus%2F3.2.6.json"));
+        let mut content = String::new();
+        file.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "{}");
+        assert!(entries.next().is_none());
+    }
+}
@@ -1,11 +1,13 @@
 use std::process::ExitCode;
 
 use guac::collector::emitter::NatsEmitter;
+use serde::{Deserialize, Serialize};
 use strum_macros::Display;
 use trustification_event_bus::EventBusConfig;
 use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
 use trustification_storage::{Storage, StorageConfig};
 
+pub mod dump;
 pub mod exporter;
 
 #[derive(clap::ValueEnum, Debug, Clone)]
@@ -16,8 +18,9 @@ pub enum Events {
     Sqs,
 }
 
-#[derive(clap::ValueEnum, Debug, Clone, Display)]
+#[derive(clap::ValueEnum, Debug, Clone, PartialEq, Eq, Display, Serialize, Deserialize)]
 #[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum DocumentType {
     #[clap(name = "sbom")]
     SBOM,
@@ -0,0 +1,264 @@
+//! Caching of search results.
+//!
+//! Results are cached per query, pagination and options. The cache is bound to the generation of an index, see
+//! [`crate::IndexStore::generation`], and is cleared as a whole once the generation changes, so that a reloaded index
+//! never serves results computed for the previous one.
+
+use crate::Error;
+use lru::LruCache;
+use parking_lot::Mutex;
+use prometheus::{
+    opts, register_int_counter_with_registry, register_int_gauge_with_registry, IntCounter, IntGauge, Registry,
+};
+use std::num::NonZeroUsize;
+use trustification_api::search::SearchOptions;
+
+/// Configuration of the search result cache.
+#[derive(Clone, Debug, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Search cache")]
+pub struct SearchCacheConfig {
+    /// Number of search results to cache per index, caching is disabled if set to zero.
+    #[arg(env = "SEARCH_CACHE_ENTRIES", long = "search-cache-entries", default_value_t = 0)]
+    pub entries: usize,
+
+    /// Only cache results of requests with a limit up to this number of documents.
+    #[arg(
+        env = "SEARCH_CACHE_MAX_LIMIT",
+        long = "search-cache-max-limit",
+        default_value_t = 100
+    )]
+    pub max_limit: usize,
+}
+
+impl Default for SearchCacheConfig {
+    fn default() -> Self {
+        Self {
+            entries: 0,
+            max_limit: 100,
+        }
+    }
+}
+
+/// The parameters a search result is cached for.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SearchKey {
+    pub q: String,
+    pub offset: usize,
+    pub limit: usize,
+    pub options: SearchOptions,
+    /// The requested facets, if any
+    pub facets: Vec<String>,
+    /// Identifies the filter restricting the results, if any
+    pub filter: Option<String>,
+}
+
+impl SearchKey {
+    pub fn new(q: impl Into<String>, offset: usize, limit: usize, options: SearchOptions) -> Self {
+        Self {
+            q: q.into(),
+            offset,
+            limit,
+            options,
+            facets: vec![],
+            filter: None,
+        }
+    }
+
+    pub fn with_facets(mut self, facets: Vec<String>) -> Self {
+        self.facets = facets;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: Option<String>) -> Self {
+        self.filter = filter;
+        self
+    }
+}
+
+#[derive(Clone)]
+struct Metrics {
+    hits_total: IntCounter,
+    misses_total: IntCounter,
+    entries: IntGauge,
+}
+
+impl Metrics {
+    fn register(registry: &Registry, prefix: &str) -> Result<Self, Error> {
+        let prefix = prefix.replace('-', "_");
+        let hits_total = register_int_counter_with_registry!(
+            opts!(
+                format!("{}_search_cache_hits_total", prefix),
+                "Total number of searches answered from the cache"
+            ),
+            registry
+        )?;
+
+        let misses_total = register_int_counter_with_registry!(
+            opts!(
+                format!("{}_search_cache_misses_total", prefix),
+                "Total number of cacheable searches not found in the cache"
+            ),
+            registry
+        )?;
+
+        let entries = register_int_gauge_with_registry!(
+            opts!(
+                format!("{}_search_cache_entries", prefix),
+                "Number of search results in the cache"
+            ),
+            registry
+        )?;
+
+        Ok(Self {
+            hits_total,
+            misses_total,
+            entries,
+        })
+    }
+}
+
+struct State<V> {
+    generation: u64,
+    entries: LruCache<SearchKey, V>,
+}
+
+/// A bounded cache of search results, evicting the least recently used results.
+pub struct SearchCache<V> {
+    state: Option<Mutex<State<V>>>,
+    max_limit: usize,
+    metrics: Metrics,
+}
+
+impl<V: Clone> SearchCache<V> {
+    /// Create a cache, registering its metrics using the name of the index as prefix.
+    pub fn new(config: &SearchCacheConfig, registry: &Registry, name: &str) -> Result<Self, Error> {
+        let state = NonZeroUsize::new(config.entries).map(|entries| {
+            Mutex::new(State {
+                generation: 0,
+                entries: LruCache::new(entries),
+            })
+        });
+
+        Ok(Self {
+            state,
+            max_limit: config.max_limit,
+            metrics: Metrics::register(registry, name)?,
+        })
+    }
+
+    /// Whether results are cached at all.
+    pub fn is_enabled(&self) -> bool {
+        self.state.is_some()
+    }
+
+    /// Get the cached result for the key, or run the search and cache its result.
+    ///
+    /// The generation must be read from the index before searching it. Results of failed searches, and of pages
+    /// larger than the configured maximum, are not cached.
+    pub fn get_or_search<E>(
+        &self,
+        generation: u64,
+        key: SearchKey,
+        search: impl FnOnce(&SearchKey) -> Result<V, E>,
+    ) -> Result<V, E> {
+        let Some(state) = self.state.as_ref().filter(|_| key.limit <= self.max_limit) else {
+            return search(&key);
+        };
+
+        {
+            let mut state = state.lock();
+            self.invalidate(&mut state, generation);
+            if let Some(value) = state.entries.get(&key) {
+                self.metrics.hits_total.inc();
+                return Ok(value.clone());
+            }
+        }
+
+        self.metrics.misses_total.inc();
+        // search without holding the lock, concurrent misses of the same key simply search twice
+        let value = search(&key)?;
+
+        let mut state = state.lock();
+        self.invalidate(&mut state, generation);
+        // the index may have been reloaded while searching, keep the result out of the newer generation
+        if state.generation == generation {
+            state.entries.put(key, value.clone());
+            self.metrics.entries.set(state.entries.len() as i64);
+        }
+
+        Ok(value)
+    }
+
+    fn invalidate(&self, state: &mut State<V>, generation: u64) {
+        if generation > state.generation {
+            log::debug!("Index generation changed to {generation}, dropping cached search results");
+            state.entries.clear();
+            state.generation = generation;
+            self.metrics.entries.set(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(q: &str, limit: usize) -> SearchKey {
+        SearchKey::new(q, 0, limit, Default::default())
+    }
+
+    fn cache(entries: usize) -> SearchCache<usize> {
+        let config = SearchCacheConfig { entries, max_limit: 10 };
+        SearchCache::new(&config, &Registry::new(), "test").unwrap()
+    }
+
+    fn search(cache: &SearchCache<usize>, generation: u64, key: SearchKey, result: usize) -> usize {
+        cache
+            .get_or_search(generation, key, |_| Ok::<_, Error>(result))
+            .unwrap()
+    }
+
+    #[test]
+    fn cached_until_reload() {
+        let cache = cache(2);
+
+        assert_eq!(search(&cache, 0, key("foo", 10), 1), 1);
+        assert_eq!(search(&cache, 0, key("foo", 10), 2), 1);
+        assert_eq!(search(&cache, 0, key("foo", 5), 3), 3);
+        assert_eq!(cache.metrics.hits_total.get(), 1);
+        assert_eq!(cache.metrics.misses_total.get(), 2);
+
+        // the index was reloaded
+        assert_eq!(search(&cache, 1, key("foo", 10), 4), 4);
+        assert_eq!(cache.metrics.entries.get(), 1);
+
+        // results of an older generation are not cached
+        assert_eq!(search(&cache, 0, key("bar", 10), 5), 5);
+        assert_eq!(search(&cache, 1, key("bar", 10), 6), 6);
+    }
+
+    #[test]
+    fn bounded() {
+        let cache = cache(2);
+
+        search(&cache, 0, key("a", 10), 1);
+        search(&cache, 0, key("b", 10), 2);
+        search(&cache, 0, key("c", 10), 3);
+        assert_eq!(cache.metrics.entries.get(), 2);
+        assert_eq!(search(&cache, 0, key("a", 10), 4), 4);
+
+        // pages larger than the maximum limit are not cached
+        search(&cache, 0, key("d", 11), 5);
+        assert_eq!(search(&cache, 0, key("d", 11), 6), 6);
+    }
+
+    #[test]
+    fn disabled() {
+        let cache = cache(0);
+        assert!(!cache.is_enabled());
+
+        search(&cache, 0, key("a", 10), 1);
+        assert_eq!(search(&cache, 0, key("a", 10), 2), 2);
+        assert_eq!(cache.metrics.misses_total.get(), 0);
+    }
+}
@@ -0,0 +1,174 @@
+//! Capping the disk usage of an index.
+//!
+//! The disk usage of an index, including its partitions and the directory of the previous snapshot, is measured
+//! whenever a snapshot is taken. Beyond a share of the cap, warnings are logged. Once the cap is reached, documents are
+//! refused until the usage drops below it again, either by evicting documents, if an eviction is configured, or by an
+//! operator freeing space.
+
+use crate::Error;
+use bytesize::ByteSize;
+use std::{
+    path::Path,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+};
+
+/// Configuration for capping the disk usage of an index.
+#[derive(Clone, Debug, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Index disk usage")]
+pub struct DiskCapConfig {
+    /// Disk usage of the index, including its partitions, beyond which no more documents are indexed. Unlimited if not
+    /// set.
+    #[arg(env = "INDEX_DISK_CAP", long = "index-disk-cap")]
+    pub cap: Option<ByteSize>,
+
+    /// Share of the cap beyond which warnings are logged.
+    #[arg(
+        env = "INDEX_DISK_WARNING_RATIO",
+        long = "index-disk-warning-ratio",
+        default_value_t = 0.8
+    )]
+    pub warning_ratio: f64,
+
+    /// How to reclaim disk space once the cap is reached.
+    #[arg(env = "INDEX_DISK_EVICTION", long = "index-disk-eviction", value_enum, default_value_t = Eviction::None)]
+    pub eviction: Eviction,
+}
+
+impl Default for DiskCapConfig {
+    fn default() -> Self {
+        Self {
+            cap: None,
+            warning_ratio: 0.8,
+            eviction: Eviction::None,
+        }
+    }
+}
+
+/// How to reclaim disk space once the cap of an index is reached.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Eviction {
+    /// Refuse documents until space is freed.
+    #[default]
+    None,
+    /// Remove the documents of the oldest yearly partition which has any from the index.
+    OldestPartition,
+    /// Remove the documents whose stored document was archived from the index.
+    Archived,
+}
+
+/// The disk usage of an index, compared to its cap.
+#[derive(Debug, Default)]
+pub(crate) struct Capacity {
+    cap: Option<u64>,
+    warning: Option<u64>,
+    pub(crate) eviction: Eviction,
+    usage: AtomicU64,
+    exceeded: AtomicBool,
+}
+
+impl Capacity {
+    pub(crate) fn new(config: &DiskCapConfig) -> Self {
+        let cap = config.cap.map(|cap| cap.as_u64());
+        Self {
+            cap,
+            warning: cap.map(|cap| (cap as f64 * config.warning_ratio.clamp(0.0, 1.0)) as u64),
+            eviction: config.eviction,
+            ..Default::default()
+        }
+    }
+
+    /// Record the measured disk usage of an index, returning whether it exceeds the cap.
+    pub(crate) fn update(&self, name: &str, usage: u64) -> bool {
+        self.usage.store(usage, Ordering::Relaxed);
+        let (Some(cap), Some(warning)) = (self.cap, self.warning) else {
+            return false;
+        };
+
+        let exceeded = usage >= cap;
+        let was_exceeded = self.exceeded.swap(exceeded, Ordering::Relaxed);
+        if exceeded {
+            log::warn!(
+                "Disk usage of index {name} ({}) reached its cap ({}), refusing documents",
+                ByteSize(usage),
+                ByteSize(cap)
+            );
+        } else if was_exceeded {
+            log::info!(
+                "Disk usage of index {name} ({}) dropped below its cap, accepting documents again",
+                ByteSize(usage)
+            );
+        } else if usage >= warning {
+            log::warn!(
+                "Disk usage of index {name} ({}) is at {:.0}% of its cap ({})",
+                ByteSize(usage),
+                usage as f64 * 100.0 / cap as f64,
+                ByteSize(cap)
+            );
+        }
+        exceeded
+    }
+
+    /// Check that documents may be indexed.
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        match self.cap {
+            Some(cap) if self.exceeded.load(Ordering::Relaxed) => Err(Error::DiskCapExceeded {
+                usage: self.usage.load(Ordering::Relaxed),
+                cap,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The size of the files of a directory and its subdirectories, `0` if it doesn't exist.
+pub(crate) fn disk_usage(path: &Path) -> Result<u64, Error> {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(Error::Io(e)),
+    };
+    let mut usage = 0;
+    for entry in entries {
+        let entry = entry.map_err(Error::Io)?;
+        let metadata = entry.metadata().map_err(Error::Io)?;
+        if metadata.is_dir() {
+            usage += disk_usage(&entry.path())?;
+        } else {
+            usage += metadata.len();
+        }
+    }
+    Ok(usage)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_capacity() {
+        let capacity = Capacity::new(&DiskCapConfig {
+            cap: Some(ByteSize::kb(10)),
+            ..Default::default()
+        });
+        assert!(!capacity.update("sbom", 9_000));
+        assert!(capacity.check().is_ok());
+
+        assert!(capacity.update("sbom", 10_000));
+        assert!(matches!(
+            capacity.check(),
+            Err(Error::DiskCapExceeded {
+                usage: 10_000,
+                cap: 10_000
+            })
+        ));
+
+        // space was freed
+        assert!(!capacity.update("sbom", 5_000));
+        assert!(capacity.check().is_ok());
+
+        // unlimited
+        let capacity = Capacity::new(&Default::default());
+        assert!(!capacity.update("sbom", u64::MAX));
+        assert!(capacity.check().is_ok());
+    }
+}
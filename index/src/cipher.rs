@@ -0,0 +1,111 @@
+//! Encryption of index snapshots.
+//!
+//! Tantivy does not allow plugging a custom compressor into the doc store, so confidentiality of the
+//! stored fields is provided by encrypting the packed index snapshot before it leaves the process, and
+//! decrypting it when syncing it back.
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use std::{fmt::Debug, str::FromStr};
+
+/// Length of the nonce, prepended to the encrypted payload.
+const NONCE_LEN: usize = 12;
+
+/// A 256 bit key used to encrypt index snapshots.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; 32]);
+
+impl Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EncryptionKey(***)")
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeyError {
+    #[error("key must be hex encoded: {0}")]
+    Hex(#[from] hex::FromHexError),
+    #[error("key must be 32 bytes long, was {0}")]
+    Length(usize),
+}
+
+impl FromStr for EncryptionKey {
+    type Err = KeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let data = hex::decode(s.trim())?;
+        let len = data.len();
+        Ok(Self(data.try_into().map_err(|_| KeyError::Length(len))?))
+    }
+}
+
+/// Encrypts and decrypts index snapshots using AES-256-GCM.
+#[derive(Clone)]
+pub(crate) struct Cipher {
+    cipher: Aes256Gcm,
+}
+
+impl Cipher {
+    pub fn new(key: &EncryptionKey) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key.0)),
+        }
+    }
+
+    /// Encrypt the data, returning the nonce followed by the cipher text.
+    pub fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>, crate::Error> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let encrypted = self
+            .cipher
+            .encrypt(&nonce, data)
+            .map_err(|_| crate::Error::Encryption("failed to encrypt snapshot".into()))?;
+
+        let mut out = Vec::with_capacity(NONCE_LEN + encrypted.len());
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&encrypted);
+        Ok(out)
+    }
+
+    /// Decrypt data previously produced by [`Cipher::encrypt`].
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, crate::Error> {
+        if data.len() < NONCE_LEN {
+            return Err(crate::Error::Encryption("snapshot too short".into()));
+        }
+        let (nonce, encrypted) = data.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), encrypted)
+            .map_err(|_| crate::Error::Encryption("failed to decrypt snapshot, wrong key?".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    #[test]
+    fn roundtrip() {
+        let cipher = Cipher::new(&KEY.parse().unwrap());
+        let encrypted = cipher.encrypt(b"some index data").unwrap();
+        assert_ne!(&encrypted[NONCE_LEN..], b"some index data");
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), b"some index data");
+    }
+
+    #[test]
+    fn wrong_key() {
+        let cipher = Cipher::new(&KEY.parse().unwrap());
+        let encrypted = cipher.encrypt(b"some index data").unwrap();
+
+        let other = Cipher::new(&KEY.replace("00", "ff").parse().unwrap());
+        assert!(other.decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn invalid_keys() {
+        assert!(matches!("0001".parse::<EncryptionKey>(), Err(KeyError::Length(2))));
+        assert!(matches!("zz".parse::<EncryptionKey>(), Err(KeyError::Hex(_))));
+    }
+}
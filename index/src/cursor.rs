@@ -0,0 +1,73 @@
+//! Paging through all the documents matching a query, like for exports.
+//!
+//! A cursor keeps the searchers of the index when it was created, so that documents indexed or deleted while paging
+//! don't shift the pages, and every document is returned exactly once.
+
+use crate::{Error, Index, IndexStore, SearchQuery};
+use tantivy::{
+    query::{BooleanQuery, Query},
+    Searcher,
+};
+use trustification_api::search::SearchOptions;
+
+/// A cursor over the documents matching a query, on a snapshot of the index.
+pub struct SearchCursor {
+    query: SearchQuery,
+    searchers: Vec<(Searcher, SearchQuery)>,
+    offset: usize,
+    total: usize,
+}
+
+impl SearchCursor {
+    /// Number of matching documents.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Whether all matching documents were returned.
+    pub fn is_done(&self) -> bool {
+        self.offset >= self.total
+    }
+}
+
+impl<INDEX: Index> IndexStore<INDEX> {
+    /// Open a cursor over the documents matching both a query and a filter.
+    pub fn cursor(&self, q: &str, filter: Option<Box<dyn Query>>) -> Result<SearchCursor, Error> {
+        let mut query = self.index.prepare_query(q)?;
+        if let Some(filter) = filter {
+            query.query = Box::new(BooleanQuery::intersection(vec![query.query, filter]));
+        }
+
+        let searchers = self.searchers_for(&query)?;
+        let mut total = 0;
+        for (searcher, query) in &searchers {
+            total += query.query.count(searcher)?;
+        }
+
+        Ok(SearchCursor {
+            query,
+            searchers,
+            offset: 0,
+            total,
+        })
+    }
+
+    /// Return the next page of matching documents, which is empty once all of them were returned.
+    pub fn next_page(
+        &self,
+        cursor: &mut SearchCursor,
+        limit: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<INDEX::MatchedDocument>, Error> {
+        if limit == 0 {
+            return Err(Error::InvalidLimitParameter(limit));
+        }
+        if cursor.is_done() {
+            return Ok(Vec::new());
+        }
+
+        let (hits, _) = self.search_in(&cursor.searchers, &cursor.query, cursor.offset, limit, options)?;
+        cursor.offset += limit;
+        Ok(hits)
+    }
+}
@@ -0,0 +1,185 @@
+//! Delta snapshots of an index.
+//!
+//! Publishing the whole index on every sync interval is costly for large indexes. As tantivy never modifies the files
+//! of a segment once written, a snapshot can be published as a delta instead: an archive of the files added since the
+//! previous snapshot, along with the few files rewritten by every commit. A manifest describes the latest snapshot: the
+//! full snapshot it starts from, identified by its digest, the number of deltas published on top of it, and the files
+//! of the index.
+//!
+//! Syncing applies the deltas published since the snapshot being served. When the chain of deltas is broken, like when
+//! a full snapshot was published meanwhile or a delta is missing, the full snapshot is synced instead.
+
+use crate::{version::SCHEMA_VERSION_FILE, Error};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// Files rewritten by commits, which are part of every delta.
+const MUTABLE_FILES: &[&str] = &["meta.json", ".managed.json", SCHEMA_VERSION_FILE];
+
+/// Name of the directory deltas are unpacked to, before moving their files into the index directory.
+const STAGING_DIR: &str = "delta";
+
+/// The manifest of the latest snapshot of an index.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Manifest {
+    /// SHA-256 digest of the full snapshot the deltas apply to, hex encoded
+    pub base: String,
+    /// Number of deltas published on top of the full snapshot
+    pub sequence: u64,
+    /// Files of the index as of the latest snapshot, with their size
+    pub files: BTreeMap<String, u64>,
+}
+
+impl Manifest {
+    /// The manifest of a full snapshot, given the SHA-256 digest of its (unencrypted) data.
+    pub fn full(digest: &[u8], files: BTreeMap<String, u64>) -> Self {
+        Self {
+            base: hex::encode(digest),
+            sequence: 0,
+            files,
+        }
+    }
+
+    /// The manifest of the delta following this snapshot.
+    pub fn next(&self, files: BTreeMap<String, u64>) -> Self {
+        Self {
+            base: self.base.clone(),
+            sequence: self.sequence + 1,
+            files,
+        }
+    }
+
+    /// The files which are new or changed compared to the snapshot of this manifest.
+    pub fn changed<'a>(&self, files: &'a BTreeMap<String, u64>) -> Vec<&'a str> {
+        files
+            .iter()
+            .filter(|(name, size)| MUTABLE_FILES.contains(&name.as_str()) || self.files.get(*name) != Some(*size))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
+
+/// The files of an index directory, with their size, except for locks.
+pub(crate) fn list(path: &Path) -> Result<BTreeMap<String, u64>, Error> {
+    let mut files = BTreeMap::new();
+    for entry in std::fs::read_dir(path).map_err(Error::Io)? {
+        let entry = entry.map_err(Error::Io)?;
+        let metadata = entry.metadata().map_err(Error::Io)?;
+        match entry.file_name().to_str() {
+            // locks of the index writer aren't part of the index
+            Some(name) if metadata.is_file() && !name.ends_with(".lock") => {
+                files.insert(name.to_string(), metadata.len());
+            }
+            _ => {}
+        }
+    }
+    Ok(files)
+}
+
+/// Pack some files of an index directory into a delta.
+pub(crate) fn pack(path: &Path, files: &[&str]) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    let enc = zstd::stream::Encoder::new(&mut out, 3).map_err(Error::Io)?;
+    let mut archive = tar::Builder::new(enc.auto_finish());
+    for file in files {
+        archive
+            .append_path_with_name(path.join(file), file)
+            .map_err(Error::Io)?;
+    }
+    drop(archive);
+    Ok(out)
+}
+
+/// Build the index directory `next` from the one of the snapshot being served, `current`, by applying deltas.
+///
+/// The files of the current snapshot are linked, as they are never modified, while the files of the deltas replace
+/// them. Files which aren't part of the manifest are removed, and the result is checked against it.
+pub(crate) fn apply(current: &Path, next: &Path, deltas: &[Vec<u8>], manifest: &Manifest) -> Result<(), Error> {
+    if next.exists() {
+        std::fs::remove_dir_all(next).map_err(Error::Io)?;
+    }
+    std::fs::create_dir_all(next).map_err(Error::Io)?;
+
+    for name in list(current)?.keys().filter(|name| manifest.files.contains_key(*name)) {
+        let (from, to) = (current.join(name), next.join(name));
+        if std::fs::hard_link(&from, &to).is_err() {
+            std::fs::copy(&from, &to).map_err(Error::Io)?;
+        }
+    }
+
+    let staging: PathBuf = next.join(STAGING_DIR);
+    for delta in deltas {
+        std::fs::create_dir_all(&staging).map_err(Error::Io)?;
+        let dec = zstd::stream::Decoder::new(&delta[..]).map_err(Error::Io)?;
+        tar::Archive::new(dec).unpack(&staging).map_err(Error::Io)?;
+        // renaming replaces linked files, instead of writing to the files of the current snapshot
+        for name in list(&staging)?.keys() {
+            std::fs::rename(staging.join(name), next.join(name)).map_err(Error::Io)?;
+        }
+        std::fs::remove_dir_all(&staging).map_err(Error::Io)?;
+    }
+
+    let files = list(next)?;
+    for name in files.keys().filter(|name| !manifest.files.contains_key(*name)) {
+        std::fs::remove_file(next.join(name)).map_err(Error::Io)?;
+    }
+    for (name, size) in &manifest.files {
+        if files.get(name) != Some(size) {
+            return Err(Error::Open(format!(
+                "delta snapshot {} of {} doesn't match its manifest: {name}",
+                manifest.sequence, manifest.base
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write(dir: &Path, files: &[(&str, &str)]) {
+        for (name, content) in files {
+            std::fs::write(dir.join(name), content).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let root = std::env::temp_dir().join(format!("delta.{}", rand::random::<u32>()));
+        let (published, current, next) = (root.join("published"), root.join("current"), root.join("next"));
+        for dir in [&published, &current] {
+            std::fs::create_dir_all(dir).unwrap();
+            write(
+                dir,
+                &[("meta.json", "{1}"), ("a.idx", "segment a"), ("b.idx", "segment b")],
+            );
+        }
+        let base = Manifest::full(b"snapshot", list(&published).unwrap());
+
+        // a segment was added, one merged away, and the metadata rewritten
+        write(&published, &[("meta.json", "{2}"), ("c.idx", "segment c")]);
+        std::fs::remove_file(published.join("b.idx")).unwrap();
+        let files = list(&published).unwrap();
+        assert_eq!(base.changed(&files), vec!["c.idx", "meta.json"]);
+        let manifest = base.next(files);
+        let delta = pack(&published, &base.changed(&manifest.files)).unwrap();
+
+        apply(&current, &next, &[delta.clone()], &manifest).unwrap();
+        assert_eq!(list(&next).unwrap(), manifest.files);
+        assert_eq!(std::fs::read_to_string(next.join("meta.json")).unwrap(), "{2}");
+        // the current snapshot is left as it is
+        assert_eq!(std::fs::read_to_string(current.join("meta.json")).unwrap(), "{1}");
+        assert!(current.join("b.idx").exists());
+
+        // deltas missing from the chain
+        let mut broken = manifest.next(manifest.files.clone());
+        broken.files.insert("d.idx".to_string(), 9);
+        assert!(apply(&current, &next, &[delta], &broken).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,427 @@
+//! Enrichment of documents before they are written to the index.
+//!
+//! Installations can attach their own metadata to documents, like the team owning a package or the business
+//! criticality of a product. An ordered chain of processors, configured in a YAML file, runs on every document after
+//! the index created it. Processors add labels (`key:value`), which indexes providing a [`WriteIndex::label_field`]
+//! store and make searchable, or modify the document directly.
+//!
+//! The `ownership` and `labels` processors are built in. Custom processors implement [`Processor`] and are registered
+//! in the [`Registry`], the `custom-processors` feature compiles in the ones of the [`custom`] module.
+//!
+//! [`WriteIndex::label_field`]: crate::WriteIndex::label_field
+
+use crate::Error;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+use tantivy::schema::{Document, Field, Schema};
+
+#[cfg(feature = "custom-processors")]
+pub mod custom;
+
+/// Configuration of the enrichment pipeline.
+#[derive(Clone, Debug, Default, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Index enrichment")]
+pub struct EnrichmentConfig {
+    /// YAML file configuring the processors enriching documents before they are indexed.
+    #[arg(env = "INDEX_ENRICHMENT_CONFIG", long = "index-enrichment-config")]
+    pub config: Option<PathBuf>,
+}
+
+/// A step of the enrichment pipeline.
+pub trait Processor: Send + Sync {
+    /// Name of the processor, used when reporting errors.
+    fn name(&self) -> &str;
+
+    /// Enrich a document, failing the indexing of the document on errors.
+    fn process(&self, document: &mut Enrichment<'_>) -> Result<(), Error>;
+}
+
+/// Creates a processor from its configuration.
+pub type Factory = fn(serde_yaml::Value) -> Result<Box<dyn Processor>, Error>;
+
+/// The processors available to the pipeline, by the type used in the configuration.
+pub struct Registry {
+    factories: HashMap<&'static str, Factory>,
+}
+
+impl Default for Registry {
+    /// The built-in processors, and the custom ones if the `custom-processors` feature is enabled.
+    fn default() -> Self {
+        let mut registry = Self {
+            factories: HashMap::new(),
+        };
+        registry.register("ownership", |config| Ok(Box::new(Ownership::new(config)?)));
+        registry.register("labels", |config| Ok(Box::new(Labels::new(config)?)));
+        #[cfg(feature = "custom-processors")]
+        custom::register(&mut registry);
+        registry
+    }
+}
+
+impl Registry {
+    /// Register a processor type, replacing any processor of the same type.
+    pub fn register(&mut self, kind: &'static str, factory: Factory) {
+        self.factories.insert(kind, factory);
+    }
+
+    fn create(&self, kind: &str, config: serde_yaml::Value) -> Result<Box<dyn Processor>, Error> {
+        let factory = self
+            .factories
+            .get(kind)
+            .ok_or_else(|| Error::Enrichment(format!("unknown processor type '{kind}'")))?;
+        factory(config).map_err(|e| Error::Enrichment(format!("invalid configuration of processor '{kind}': {e}")))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PipelineConfig {
+    processors: Vec<ProcessorConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProcessorConfig {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(flatten)]
+    config: serde_yaml::Value,
+}
+
+/// An ordered chain of processors.
+#[derive(Default)]
+pub struct Pipeline {
+    processors: Vec<Box<dyn Processor>>,
+}
+
+impl Pipeline {
+    /// Load the pipeline of the configuration file, if there is one.
+    pub fn load(config: &EnrichmentConfig, registry: &Registry) -> Result<Option<Self>, Error> {
+        match &config.config {
+            Some(path) => Self::from_file(path, registry).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn from_file(path: &Path, registry: &Registry) -> Result<Self, Error> {
+        let config = std::fs::read_to_string(path).map_err(Error::Io)?;
+        let pipeline = Self::from_yaml(&config, registry)?;
+        log::info!(
+            "Loaded {} enrichment processors from {}",
+            pipeline.processors.len(),
+            path.display()
+        );
+        Ok(pipeline)
+    }
+
+    /// Create the pipeline of a YAML configuration, using the processor types of the registry.
+    pub fn from_yaml(config: &str, registry: &Registry) -> Result<Self, Error> {
+        let config: PipelineConfig =
+            serde_yaml::from_str(config).map_err(|e| Error::Enrichment(format!("invalid configuration: {e}")))?;
+        let processors = config
+            .processors
+            .into_iter()
+            .map(|processor| registry.create(&processor.kind, processor.config))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { processors })
+    }
+
+    /// Append a processor to the pipeline.
+    pub fn push(&mut self, processor: Box<dyn Processor>) {
+        self.processors.push(processor);
+    }
+
+    /// Run all processors on a document, in order, storing their labels in the label field.
+    ///
+    /// Labels are dropped if the index has no label field.
+    pub fn process(
+        &self,
+        schema: &Schema,
+        label_field: Option<Field>,
+        id: &str,
+        document: &mut Document,
+    ) -> Result<(), Error> {
+        let mut enrichment = Enrichment {
+            id,
+            schema,
+            document,
+            labels: BTreeSet::new(),
+        };
+        for processor in &self.processors {
+            processor.process(&mut enrichment).map_err(|e| {
+                Error::Enrichment(format!(
+                    "processor '{}' failed on document '{id}': {e}",
+                    processor.name()
+                ))
+            })?;
+        }
+
+        let Enrichment { document, labels, .. } = enrichment;
+        if let Some(field) = label_field {
+            for label in labels {
+                document.add_text(field, label);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A document being enriched.
+pub struct Enrichment<'a> {
+    id: &'a str,
+    schema: &'a Schema,
+    document: &'a mut Document,
+    labels: BTreeSet<String>,
+}
+
+impl<'a> Enrichment<'a> {
+    /// Identifier of the document.
+    pub fn id(&self) -> &str {
+        self.id
+    }
+
+    /// Text values of a field, by its name in the schema of the index. Empty if the index has no such field.
+    pub fn values(&self, name: &str) -> Vec<&str> {
+        match self.schema.get_field(name) {
+            Ok(field) => self
+                .document
+                .get_all(field)
+                .filter_map(|value| value.as_text())
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Label the document, with a key like `owner` and a value like `team-a`.
+    pub fn label(&mut self, key: &str, value: &str) {
+        self.labels.insert(format!("{key}:{value}"));
+    }
+
+    /// Labels added by the processors so far.
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.labels.iter().map(String::as_str)
+    }
+
+    /// The schema of the index.
+    pub fn schema(&self) -> &Schema {
+        self.schema
+    }
+
+    /// The document, for processors modifying fields other than the labels.
+    pub fn document(&mut self) -> &mut Document {
+        self.document
+    }
+}
+
+/// Assigns owners to documents by the prefix of a field, like the Package URL of the packages of an SBOM.
+///
+/// Owners are assigned by the longest matching prefix, so more specific entries take precedence over broader ones.
+/// Every value of the field is matched, a document containing packages of several teams gets several owners.
+///
+/// ```yaml
+/// type: ownership
+/// field: sbom_pkg_purl
+/// owners:
+///   - prefix: pkg:maven/org.example/
+///     owner: team-a
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct Ownership {
+    field: String,
+    owners: Vec<Owner>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Owner {
+    prefix: String,
+    owner: String,
+}
+
+impl Ownership {
+    pub fn new(config: serde_yaml::Value) -> Result<Self, Error> {
+        let mut ownership: Self = serde_yaml::from_value(config).map_err(|e| Error::Enrichment(e.to_string()))?;
+        ownership.owners.sort_by(|a, b| b.prefix.len().cmp(&a.prefix.len()));
+        Ok(ownership)
+    }
+}
+
+impl Processor for Ownership {
+    fn name(&self) -> &str {
+        "ownership"
+    }
+
+    fn process(&self, document: &mut Enrichment<'_>) -> Result<(), Error> {
+        let owners: BTreeSet<String> = document
+            .values(&self.field)
+            .into_iter()
+            .filter_map(|value| self.owners.iter().find(|owner| value.starts_with(&owner.prefix)))
+            .map(|owner| owner.owner.clone())
+            .collect();
+        for owner in owners {
+            document.label("owner", &owner);
+        }
+        Ok(())
+    }
+}
+
+/// Adds static labels to documents matching rules, like tagging the business criticality of products.
+///
+/// A rule matches if a value of its field starts with the prefix, or the document identifier if it has no field. All
+/// matching rules apply.
+///
+/// ```yaml
+/// type: labels
+/// rules:
+///   - prefix: ubi9
+///     labels:
+///       criticality: high
+///   - field: sbom_pkg_supplier
+///     prefix: "Organization: Example"
+///     labels:
+///       vendor: example
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct Labels {
+    rules: Vec<LabelRule>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LabelRule {
+    #[serde(default)]
+    field: Option<String>,
+    prefix: String,
+    labels: BTreeMap<String, String>,
+}
+
+impl Labels {
+    pub fn new(config: serde_yaml::Value) -> Result<Self, Error> {
+        serde_yaml::from_value(config).map_err(|e| Error::Enrichment(e.to_string()))
+    }
+}
+
+impl Processor for Labels {
+    fn name(&self) -> &str {
+        "labels"
+    }
+
+    fn process(&self, document: &mut Enrichment<'_>) -> Result<(), Error> {
+        for rule in &self.rules {
+            let matches = match &rule.field {
+                Some(field) => document
+                    .values(field)
+                    .into_iter()
+                    .any(|value| value.starts_with(&rule.prefix)),
+                None => document.id().starts_with(&rule.prefix),
+            };
+            if matches {
+                for (key, value) in &rule.labels {
+                    document.label(key, value);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tantivy::schema::{STORED, STRING};
+
+    const CONFIG: &str = r#"
+processors:
+  - type: ownership
+    field: purl
+    owners:
+      - prefix: pkg:maven/
+        owner: java
+      - prefix: pkg:maven/org.example/
+        owner: team-a
+      - prefix: pkg:npm/
+        owner: team-b
+  - type: labels
+    rules:
+      - prefix: ubi9
+        labels:
+          criticality: high
+      - field: purl
+        prefix: pkg:npm/left-pad
+        labels:
+          risk: trivial
+"#;
+
+    fn schema() -> (Schema, Field, Field) {
+        let mut builder = Schema::builder();
+        let purl = builder.add_text_field("purl", STRING | STORED);
+        let labels = builder.add_text_field("labels", STRING | STORED);
+        (builder.build(), purl, labels)
+    }
+
+    fn labels(document: &Document, field: Field) -> Vec<&str> {
+        document.get_all(field).filter_map(|value| value.as_text()).collect()
+    }
+
+    #[test]
+    fn builtin_processors() {
+        let (schema, purl, field) = schema();
+        let pipeline = Pipeline::from_yaml(CONFIG, &Registry::default()).unwrap();
+
+        let mut document = Document::new();
+        document.add_text(purl, "pkg:maven/org.example/app@1.0");
+        document.add_text(purl, "pkg:maven/org.other/lib@2.0");
+        pipeline
+            .process(&schema, Some(field), "ubi9-container", &mut document)
+            .unwrap();
+        assert_eq!(
+            labels(&document, field),
+            vec!["criticality:high", "owner:java", "owner:team-a"]
+        );
+
+        let mut document = Document::new();
+        document.add_text(purl, "pkg:npm/left-pad@1.3.0");
+        pipeline.process(&schema, Some(field), "other", &mut document).unwrap();
+        assert_eq!(labels(&document, field), vec!["owner:team-b", "risk:trivial"]);
+
+        // indexes without a label field don't store labels
+        let mut document = Document::new();
+        document.add_text(purl, "pkg:npm/left-pad@1.3.0");
+        pipeline.process(&schema, None, "other", &mut document).unwrap();
+        assert!(labels(&document, field).is_empty());
+    }
+
+    struct Failing;
+
+    impl Processor for Failing {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn process(&self, document: &mut Enrichment<'_>) -> Result<(), Error> {
+            // processors run in order, seeing the labels of previous ones
+            match document.labels().next() {
+                Some(_) => Err(Error::Enrichment("already labeled".to_string())),
+                None => Ok(()),
+            }
+        }
+    }
+
+    #[test]
+    fn custom_processor() {
+        let (schema, _, field) = schema();
+        let mut registry = Registry::default();
+        registry.register("failing", |_| Ok(Box::new(Failing)));
+
+        let mut pipeline = Pipeline::from_yaml(CONFIG, &registry).unwrap();
+        pipeline.push(Box::new(Failing));
+
+        let mut document = Document::new();
+        assert!(pipeline.process(&schema, Some(field), "other", &mut document).is_ok());
+        let mut document = Document::new();
+        assert!(pipeline.process(&schema, Some(field), "ubi9", &mut document).is_err());
+
+        assert!(Pipeline::from_yaml("processors:\n  - type: failing\n", &registry).is_ok());
+        assert!(Pipeline::from_yaml("processors:\n  - type: unknown\n", &registry).is_err());
+        assert!(Pipeline::from_yaml("processors:\n  - type: ownership\n", &registry).is_err());
+    }
+}
@@ -0,0 +1,46 @@
+//! Custom processors of an installation, compiled in with the `custom-processors` feature.
+//!
+//! Add processors to this module and register them in [`register`], using the type name under which they are
+//! configured. The `field-labels` processor serves as an example.
+
+use super::{Enrichment, Processor, Registry};
+use crate::Error;
+use serde::Deserialize;
+
+/// Register the custom processors.
+pub fn register(registry: &mut Registry) {
+    registry.register("field-labels", |config| Ok(Box::new(FieldLabels::new(config)?)));
+}
+
+/// Labels documents with the values of a field, like the suppliers of the packages of an SBOM.
+///
+/// ```yaml
+/// type: field-labels
+/// field: sbom_pkg_supplier
+/// key: supplier
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct FieldLabels {
+    field: String,
+    key: String,
+}
+
+impl FieldLabels {
+    pub fn new(config: serde_yaml::Value) -> Result<Self, Error> {
+        serde_yaml::from_value(config).map_err(|e| Error::Enrichment(e.to_string()))
+    }
+}
+
+impl Processor for FieldLabels {
+    fn name(&self) -> &str {
+        "field-labels"
+    }
+
+    fn process(&self, document: &mut Enrichment<'_>) -> Result<(), Error> {
+        let values: Vec<String> = document.values(&self.field).into_iter().map(String::from).collect();
+        for value in values {
+            document.label(&self.key, &value);
+        }
+        Ok(())
+    }
+}
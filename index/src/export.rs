@@ -0,0 +1,250 @@
+//! Export of the stored fields of an index as Parquet, for offline analytics.
+//!
+//! The columns are declared by the index, see [`Index::export_columns`], and form the documented schema of the
+//! export. The output is deterministic: rows are ordered by the key columns, and no timestamps or other volatile
+//! metadata are written, so exporting the same documents twice yields the same file.
+
+use crate::{Error, Index, IndexStore};
+use parquet::{
+    basic::{Compression, ZstdLevel},
+    column::writer::ColumnWriterImpl,
+    data_type::{BoolType, ByteArray, ByteArrayType, DataType, DoubleType, Int64Type},
+    errors::ParquetError,
+    file::{
+        properties::WriterProperties,
+        writer::{SerializedColumnWriter, SerializedFileWriter},
+    },
+    schema::parser::parse_message_type,
+};
+use std::{io::Write, sync::Arc};
+use tantivy::{schema::Field, DocAddress, Document};
+
+/// Number of rows written per row group.
+const ROW_GROUP_SIZE: usize = 10_000;
+
+/// Written as the `created_by` of the file, instead of the version of the Parquet library.
+const CREATED_BY: &str = "trustification";
+
+/// Type of an exported column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    /// The first value of a text field, as optional `STRING`.
+    Text,
+    /// All values of a text field, as `LIST` of `STRING`, which is empty if the field has no value.
+    TextList,
+    /// The first value of an `i64` field, as optional `INT64`.
+    Int64,
+    /// The first value of an `f64` field, as optional `DOUBLE`.
+    Float64,
+    /// The first value of a `bool` field, as optional `BOOLEAN`.
+    Bool,
+    /// The first value of a date field, as optional `TIMESTAMP` in microseconds (UTC).
+    Timestamp,
+    /// The first value of a JSON field, as optional `JSON`.
+    Json,
+}
+
+/// A column of an export, filled from a stored field.
+#[derive(Clone, Debug)]
+pub struct Column {
+    pub name: &'static str,
+    pub field: Field,
+    pub r#type: ColumnType,
+    /// Rows are ordered by the text of key columns, in the order they are declared.
+    pub key: bool,
+}
+
+impl Column {
+    pub fn new(name: &'static str, field: Field, r#type: ColumnType) -> Self {
+        Self {
+            name,
+            field,
+            r#type,
+            key: false,
+        }
+    }
+
+    /// Order rows by this column, which must be of type [`ColumnType::Text`].
+    pub fn key(mut self) -> Self {
+        self.key = true;
+        self
+    }
+
+    fn schema(&self) -> String {
+        let name = self.name;
+        match self.r#type {
+            ColumnType::Text => format!("optional binary {name} (STRING);"),
+            ColumnType::TextList => {
+                format!(
+                    "required group {name} (LIST) {{ repeated group list {{ required binary element (STRING); }} }}"
+                )
+            }
+            ColumnType::Int64 => format!("optional int64 {name};"),
+            ColumnType::Float64 => format!("optional double {name};"),
+            ColumnType::Bool => format!("optional boolean {name};"),
+            ColumnType::Timestamp => format!("optional int64 {name} (TIMESTAMP(MICROS,true));"),
+            ColumnType::Json => format!("optional binary {name} (JSON);"),
+        }
+    }
+}
+
+impl From<ParquetError> for Error {
+    fn from(e: ParquetError) -> Self {
+        Self::Export(e.to_string())
+    }
+}
+
+impl<INDEX: Index> IndexStore<INDEX> {
+    /// Export the stored fields of all documents, including the ones of partitions, as Parquet.
+    ///
+    /// Returns the number of exported rows, one for each document.
+    pub fn export_parquet<W: Write + Send>(&self, out: W) -> Result<usize, Error> {
+        let columns = self.index.export_columns();
+        if columns.is_empty() {
+            return Err(Error::Export(format!("index {} can't be exported", self.index.name())));
+        }
+        if let Some(column) = columns.iter().find(|c| c.key && c.r#type != ColumnType::Text) {
+            return Err(Error::Export(format!(
+                "key column {} must be of type text",
+                column.name
+            )));
+        }
+
+        let schema = format!(
+            "message {} {{ {} }}",
+            self.index.name(),
+            columns.iter().map(Column::schema).collect::<Vec<_>>().join(" ")
+        );
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .set_created_by(CREATED_BY.to_string())
+            .build();
+        let mut writer = SerializedFileWriter::new(out, Arc::new(parse_message_type(&schema)?), Arc::new(properties))?;
+
+        // order the documents first, only loading the documents of one row group at a time afterwards
+        let searchers = self.searchers()?;
+        let mut rows = Vec::new();
+        for (n, searcher) in searchers.iter().enumerate() {
+            for (segment_ord, segment) in searcher.segment_readers().iter().enumerate() {
+                for doc_id in segment.doc_ids_alive() {
+                    let address = DocAddress::new(segment_ord as u32, doc_id);
+                    let doc = searcher.doc(address)?;
+                    let key: Vec<Option<String>> = columns
+                        .iter()
+                        .filter(|c| c.key)
+                        .map(|c| {
+                            doc.get_first(c.field)
+                                .and_then(|v| v.as_text())
+                                .map(ToString::to_string)
+                        })
+                        .collect();
+                    rows.push((key, n, address));
+                }
+            }
+        }
+        rows.sort();
+
+        for chunk in rows.chunks(ROW_GROUP_SIZE) {
+            let docs = chunk
+                .iter()
+                .map(|(_, n, address)| searchers[*n].doc(*address))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut row_group = writer.next_row_group()?;
+            for column in &columns {
+                let mut writer = row_group
+                    .next_column()?
+                    .ok_or_else(|| Error::Export(format!("missing column {}", column.name)))?;
+                write_column(&mut writer, column, &docs)?;
+                writer.close()?;
+            }
+            row_group.close()?;
+        }
+
+        writer.close()?;
+        Ok(rows.len())
+    }
+}
+
+fn write_column(
+    writer: &mut SerializedColumnWriter<'_>,
+    column: &Column,
+    docs: &[Document],
+) -> Result<(), ParquetError> {
+    let field = column.field;
+    match column.r#type {
+        ColumnType::Text => write_optional(
+            writer.typed::<ByteArrayType>(),
+            docs.iter()
+                .map(|doc| doc.get_first(field).and_then(|v| v.as_text()).map(ByteArray::from)),
+        ),
+        ColumnType::TextList => {
+            let (mut values, mut definitions, mut repetitions) = (Vec::new(), Vec::new(), Vec::new());
+            for doc in docs {
+                let mut empty = true;
+                for value in doc.get_all(field).filter_map(|v| v.as_text()) {
+                    values.push(ByteArray::from(value));
+                    definitions.push(1);
+                    repetitions.push(if empty { 0 } else { 1 });
+                    empty = false;
+                }
+                if empty {
+                    definitions.push(0);
+                    repetitions.push(0);
+                }
+            }
+            writer
+                .typed::<ByteArrayType>()
+                .write_batch(&values, Some(&definitions), Some(&repetitions))?;
+            Ok(())
+        }
+        ColumnType::Int64 => write_optional(
+            writer.typed::<Int64Type>(),
+            docs.iter().map(|doc| doc.get_first(field).and_then(|v| v.as_i64())),
+        ),
+        ColumnType::Float64 => write_optional(
+            writer.typed::<DoubleType>(),
+            docs.iter().map(|doc| doc.get_first(field).and_then(|v| v.as_f64())),
+        ),
+        ColumnType::Bool => write_optional(
+            writer.typed::<BoolType>(),
+            docs.iter().map(|doc| doc.get_first(field).and_then(|v| v.as_bool())),
+        ),
+        ColumnType::Timestamp => write_optional(
+            writer.typed::<Int64Type>(),
+            docs.iter().map(|doc| {
+                doc.get_first(field)
+                    .and_then(|v| v.as_date())
+                    .map(|d| d.into_timestamp_micros())
+            }),
+        ),
+        ColumnType::Json => write_optional(
+            writer.typed::<ByteArrayType>(),
+            docs.iter().map(|doc| {
+                doc.get_first(field)
+                    .and_then(|v| v.as_json())
+                    .and_then(|json| serde_json::to_vec(json).ok())
+                    .map(ByteArray::from)
+            }),
+        ),
+    }
+}
+
+/// Write the values of an optional column, `None` being written as null.
+fn write_optional<T: DataType>(
+    writer: &mut ColumnWriterImpl<'_, T>,
+    values: impl Iterator<Item = Option<T::T>>,
+) -> Result<(), ParquetError> {
+    let (mut data, mut definitions) = (Vec::new(), Vec::new());
+    for value in values {
+        match value {
+            Some(value) => {
+                data.push(value);
+                definitions.push(1);
+            }
+            None => definitions.push(0),
+        }
+    }
+    writer.write_batch(&data, Some(&definitions), None)?;
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use crate::{Error, Index, IndexStore};
+use std::collections::HashMap;
+use tantivy::{
+    aggregation::{
+        agg_req::{Aggregation, AggregationVariants, Aggregations},
+        agg_result::{AggregationResult, BucketResult},
+        bucket::TermsAggregation,
+        AggregationCollector, AggregationLimits, Key,
+    },
+    query::{BooleanQuery, Query},
+};
+
+/// Number of matching documents per value, for each requested facet.
+pub type Facets = HashMap<String, HashMap<String, u64>>;
+
+/// Maximum number of values reported per facet.
+const FACET_SIZE: u32 = 100;
+
+impl<INDEX: Index> IndexStore<INDEX> {
+    /// Count the documents matching a query, grouped by the values of the requested facets.
+    pub fn facets(&self, q: &str, names: &[String]) -> Result<Facets, Error> {
+        self.facets_filtered(q, None, names)
+    }
+
+    /// Count the documents matching a query and a filter, like the documents the user may read, grouped by the values
+    /// of the requested facets.
+    pub fn facets_filtered(&self, q: &str, filter: Option<Box<dyn Query>>, names: &[String]) -> Result<Facets, Error> {
+        if names.is_empty() {
+            return Ok(Facets::new());
+        }
+
+        let supported = self.index.facets();
+        let schema = self.index.schema();
+
+        let mut aggregations = Aggregations::new();
+        for name in names {
+            let field = supported
+                .iter()
+                .find_map(|(n, field)| (n == name).then_some(*field))
+                .ok_or_else(|| Error::InvalidFacet(name.clone()))?;
+
+            aggregations.insert(
+                name.clone(),
+                Aggregation {
+                    agg: AggregationVariants::Terms(TermsAggregation {
+                        field: schema.get_field_name(field).to_string(),
+                        size: Some(FACET_SIZE),
+                        ..Default::default()
+                    }),
+                    sub_aggregation: Default::default(),
+                },
+            );
+        }
+
+        let mut query = self.index.prepare_query(q)?;
+        if let Some(filter) = filter {
+            query.query = Box::new(BooleanQuery::intersection(vec![query.query, filter]));
+        }
+
+        // counts of partitioned indexes are summed up
+        let mut facets = Facets::new();
+        for (searcher, query) in self.searchers_for(&query)? {
+            let collector = AggregationCollector::from_aggs(aggregations.clone(), AggregationLimits::default());
+            let results = searcher.search(&query.query, &collector)?;
+
+            for (name, result) in results.0 {
+                let counts = facets.entry(name).or_default();
+                if let AggregationResult::BucketResult(BucketResult::Terms { buckets, .. }) = result {
+                    for bucket in buckets {
+                        let key = match bucket.key {
+                            Key::Str(key) => key,
+                            Key::F64(key) => key.to_string(),
+                        };
+                        *counts.entry(key).or_default() += bucket.doc_count;
+                    }
+                }
+            }
+        }
+
+        Ok(facets)
+    }
+}
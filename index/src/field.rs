@@ -0,0 +1,95 @@
+use tantivy::schema::{Field, FieldType, Schema};
+use trustification_api::search::{SearchField, SearchFieldType};
+
+/// Describe a qualifier of the query language, deriving its type from the schema of the fields it is
+/// mapped to.
+///
+/// The `parses` function is used to check if the qualifier can be used for sorting or as a scope, and
+/// should try to parse the provided query with the query language of the index.
+pub fn search_field<F>(schema: &Schema, qualifier: &str, fields: &[Field], description: &str, parses: F) -> SearchField
+where
+    F: Fn(&str) -> bool,
+{
+    let r#type = fields
+        .first()
+        .map(|field| field_type(schema.get_field_entry(*field).field_type()))
+        .unwrap_or(SearchFieldType::String);
+
+    let example = match r#type {
+        SearchFieldType::String | SearchFieldType::Text | SearchFieldType::Predicate => format!("{qualifier}:value"),
+        SearchFieldType::Integer => format!("{qualifier}:>1"),
+        SearchFieldType::Number => format!("{qualifier}:>7.5"),
+        SearchFieldType::Date => format!("{qualifier}:>2023-01-01"),
+        SearchFieldType::Boolean => format!("{qualifier}:true"),
+    };
+
+    SearchField {
+        qualifier: qualifier.to_string(),
+        description: description.to_string(),
+        r#type,
+        sortable: parses(&format!("sort:{qualifier}")),
+        scope: parses(&format!("value in:{qualifier}")),
+        example,
+    }
+}
+
+/// Describe a predicate of the query language (`is:qualifier`).
+pub fn search_predicate(qualifier: &str, description: &str) -> SearchField {
+    SearchField {
+        qualifier: qualifier.to_string(),
+        description: description.to_string(),
+        r#type: SearchFieldType::Predicate,
+        sortable: false,
+        scope: false,
+        example: format!("is:{qualifier}"),
+    }
+}
+
+fn field_type(field_type: &FieldType) -> SearchFieldType {
+    match field_type {
+        FieldType::Str(options) => match options.get_indexing_options() {
+            Some(indexing) if indexing.tokenizer() != "raw" => SearchFieldType::Text,
+            _ => SearchFieldType::String,
+        },
+        FieldType::I64(_) | FieldType::U64(_) => SearchFieldType::Integer,
+        FieldType::F64(_) => SearchFieldType::Number,
+        FieldType::Date(_) => SearchFieldType::Date,
+        FieldType::Bool(_) => SearchFieldType::Boolean,
+        _ => SearchFieldType::String,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tantivy::schema::{FAST, INDEXED, STRING, TEXT};
+
+    #[test]
+    fn field_types() {
+        let mut schema = Schema::builder();
+        let id = schema.add_text_field("id", STRING);
+        let desc = schema.add_text_field("desc", TEXT);
+        let score = schema.add_f64_field("score", INDEXED | FAST);
+        let created = schema.add_date_field("created", INDEXED | FAST);
+        let schema = schema.build();
+
+        let sortable = |q: &str| q == "sort:created";
+
+        let field = search_field(&schema, "id", &[id], "Identifier", sortable);
+        assert_eq!(field.r#type, SearchFieldType::String);
+        assert_eq!(field.example, "id:value");
+        assert!(!field.sortable);
+
+        let field = search_field(&schema, "description", &[desc, id], "Description", sortable);
+        assert_eq!(field.r#type, SearchFieldType::Text);
+
+        let field = search_field(&schema, "score", &[score], "Score", sortable);
+        assert_eq!(field.r#type, SearchFieldType::Number);
+        assert_eq!(field.example, "score:>7.5");
+
+        let field = search_field(&schema, "created", &[created], "Created", sortable);
+        assert_eq!(field.r#type, SearchFieldType::Date);
+        assert!(field.sortable);
+        assert!(!field.scope);
+    }
+}
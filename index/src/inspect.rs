@@ -0,0 +1,129 @@
+//! Inspection of an index, useful for debugging indexing issues.
+
+use crate::{field2str_opt, Error, IndexStore, WriteIndex};
+use std::collections::BTreeMap;
+use tantivy::{
+    collector::DocSetCollector,
+    query::AllQuery,
+    schema::{IndexRecordOption, Type},
+    DocSet, TERMINATED,
+};
+
+/// Statistics of a searchable segment.
+#[derive(Clone, Debug)]
+pub struct SegmentStats {
+    pub id: String,
+    pub num_docs: u32,
+    pub num_deleted_docs: u32,
+}
+
+/// Statistics of a field of the schema.
+#[derive(Clone, Debug)]
+pub struct FieldStats {
+    pub name: String,
+    pub r#type: Type,
+    pub indexed: bool,
+    pub stored: bool,
+    pub fast: bool,
+    /// Number of distinct terms, only available for indexed fields.
+    pub num_terms: Option<u64>,
+    /// Number of live documents having at least one term, only available for indexed fields.
+    pub num_docs: Option<u64>,
+}
+
+impl<INDEX> IndexStore<INDEX>
+where
+    INDEX: WriteIndex + 'static,
+{
+    /// Statistics of the searchable segments of the index.
+    pub fn segment_stats(&self) -> Result<Vec<SegmentStats>, Error> {
+        let inner = self.inner.read();
+        Ok(inner
+            .searchable_segment_metas()?
+            .iter()
+            .map(|meta| SegmentStats {
+                id: meta.id().uuid_string(),
+                num_docs: meta.num_docs(),
+                num_deleted_docs: meta.num_deleted_docs(),
+            })
+            .collect())
+    }
+
+    /// Statistics of every field of the schema.
+    ///
+    /// NOTE: This walks all postings of all indexed fields, and is expensive for large indexes.
+    pub fn field_stats(&self) -> Result<Vec<FieldStats>, Error> {
+        let inner = self.inner.read();
+        let searcher = inner.reader()?.searcher();
+        let schema = inner.schema();
+
+        let mut result = Vec::new();
+        for (field, entry) in schema.fields() {
+            let (num_terms, num_docs) = if entry.is_indexed() {
+                let mut num_terms = 0;
+                let mut num_docs = 0;
+                for segment in searcher.segment_readers() {
+                    let inverted = segment.inverted_index(field)?;
+                    num_terms += inverted.terms().num_terms() as u64;
+
+                    let mut seen = vec![false; segment.max_doc() as usize];
+                    let mut terms = inverted.terms().stream().map_err(Error::Io)?;
+                    while terms.advance() {
+                        let mut postings = inverted
+                            .read_postings_from_terminfo(terms.value(), IndexRecordOption::Basic)
+                            .map_err(Error::Io)?;
+                        let mut doc = postings.doc();
+                        while doc != TERMINATED {
+                            seen[doc as usize] = true;
+                            doc = postings.advance();
+                        }
+                    }
+
+                    num_docs += seen
+                        .iter()
+                        .enumerate()
+                        .filter(|(doc, seen)| **seen && !segment.is_deleted(*doc as u32))
+                        .count() as u64;
+                }
+                (Some(num_terms), Some(num_docs))
+            } else {
+                (None, None)
+            };
+
+            result.push(FieldStats {
+                name: entry.name().to_string(),
+                r#type: entry.field_type().value_type(),
+                indexed: entry.is_indexed(),
+                stored: entry.is_stored(),
+                fast: entry.is_fast(),
+                num_terms,
+                num_docs,
+            });
+        }
+
+        Ok(result)
+    }
+
+    /// The identifiers of the indexed documents, along with their digest if the index provides a
+    /// [`WriteIndex::digest_field`]. Includes the documents of partitions.
+    ///
+    /// Requires the field of [`WriteIndex::doc_id_to_term`] to be stored, documents without it are skipped.
+    ///
+    /// NOTE: This loads the stored fields of all documents, and is expensive for large indexes.
+    pub fn document_digests(&self) -> Result<BTreeMap<String, Option<String>>, Error> {
+        let id_field = self.index.doc_id_to_term("").field();
+        let digest_field = self.index.digest_field();
+
+        let mut result = BTreeMap::new();
+        for searcher in self.searchers()? {
+            for address in searcher.search(&AllQuery, &DocSetCollector)? {
+                let doc = searcher.doc(address)?;
+                if let Some(id) = field2str_opt(&doc, id_field) {
+                    let digest = digest_field.and_then(|field| field2str_opt(&doc, field));
+                    result.insert(id.to_string(), digest.map(ToString::to_string));
+                }
+            }
+        }
+        Ok(result)
+    }
+}
@@ -3,24 +3,55 @@
 //! This crate provides a wrapper around the tantivy index for the trustification project.
 //!
 
+pub mod cache;
+pub mod enrich;
+pub mod export;
+pub mod inspect;
 pub mod metadata;
 
+pub use capacity::{DiskCapConfig, Eviction};
+pub use cipher::{EncryptionKey, KeyError};
+pub use cursor::SearchCursor;
+pub use enrich::EnrichmentConfig;
+pub use facet::*;
+pub use field::*;
+pub use partition::PartitionConfig;
+pub use pattern::*;
+pub use realtime::RealtimeConfig;
 pub use sort::*;
-
+pub use version::SchemaVersion;
+
+mod capacity;
+mod cipher;
+mod cursor;
+mod delta;
+mod facet;
+mod field;
+mod partition;
+mod pattern;
+mod realtime;
 mod s3dir;
+mod scoring;
 mod sort;
+mod version;
 
 // Re-export to align versions
 pub use tantivy;
 pub use tantivy::schema::Document;
 
 use bytesize::ByteSize;
+use capacity::Capacity;
+use cipher::Cipher;
+use delta::Manifest;
+use enrich::Pipeline;
 use parking_lot::RwLock;
+use partition::{PartitionWriter, Partitions, Routing};
 use prometheus::{
     histogram_opts, opts, register_histogram_with_registry, register_int_counter_with_registry,
     register_int_gauge_with_registry, Histogram, IntCounter, IntGauge, Registry,
 };
 use s3dir::S3Directory;
+use scoring::{CombinedScoring, CombinedStatistics};
 use sha2::{Digest, Sha256};
 use sikula::{
     lir::PartialOrdered,
@@ -31,20 +62,24 @@ use std::{
     fmt::{Debug, Display},
     ops::Bound,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     time::Duration,
 };
 use tantivy::{
     collector::TopDocs,
     directory::{MmapDirectory, INDEX_WRITER_LOCK},
+    fastfield::FastValue,
     query::{AllQuery, BooleanQuery, BoostQuery, FuzzyTermQuery, Occur, Query, RangeQuery, RegexQuery, TermQuery},
     schema::*,
     tokenizer::TokenizerManager,
-    DateTime, Directory, DocAddress, Index as SearchIndex, IndexSettings, Order, Searcher,
+    DateTime, Directory, DocAddress, DocId, Index as SearchIndex, IndexSettings, Order, Searcher, SegmentReader,
 };
 use time::{OffsetDateTime, UtcOffset};
 use tokio::{spawn, sync::oneshot};
-use trustification_api::search::SearchOptions;
+use trustification_api::search::{SearchField, SearchOptions};
 use trustification_storage::{Storage, StorageConfig};
 
 /// Configuration for the index.
@@ -66,6 +101,25 @@ pub struct IndexConfig {
     /// Synchronization interval for index persistence.
     #[arg(env = "INDEX_MODE", long = "index-mode", default_value_t = IndexMode::File)]
     pub mode: IndexMode,
+
+    /// Key used to encrypt index snapshots (hex encoded, 32 bytes). Snapshots are stored unencrypted if not set. Only
+    /// supported for file indices.
+    #[arg(env = "INDEX_ENCRYPTION_KEY", long = "index-encryption-key", hide_env_values = true)]
+    pub encryption_key: Option<EncryptionKey>,
+
+    /// Number of delta snapshots, containing only the files changed since the previous snapshot, published before
+    /// publishing a full snapshot again. Full snapshots are always published if `0`.
+    #[arg(env = "INDEX_MAX_DELTAS", long = "index-max-deltas", default_value_t = 0)]
+    pub max_deltas: usize,
+
+    #[command(flatten)]
+    pub partitions: PartitionConfig,
+
+    #[command(flatten)]
+    pub disk: DiskCapConfig,
+
+    #[command(flatten)]
+    pub enrichment: EnrichmentConfig,
 }
 
 #[derive(Clone, Debug, clap::ValueEnum)]
@@ -94,6 +148,11 @@ struct Metrics {
     indexed_total: IntCounter,
     failed_total: IntCounter,
     snapshots_total: IntCounter,
+    delta_snapshots_total: IntCounter,
+    delta_syncs_total: IntCounter,
+    disk_usage_bytes: IntGauge,
+    disk_cap_refused_total: IntCounter,
+    disk_evictions_total: IntCounter,
     queries_total: IntCounter,
     index_size_disk_bytes: IntGauge,
     indexing_latency_seconds: Histogram,
@@ -101,6 +160,7 @@ struct Metrics {
     documents: IntGauge,
     count_errors: IntCounter,
     count_latency_seconds: Histogram,
+    updated_timestamp_seconds: IntGauge,
 }
 
 impl Metrics {
@@ -138,6 +198,46 @@ impl Metrics {
             registry
         )?;
 
+        let delta_snapshots_total = register_int_counter_with_registry!(
+            opts!(
+                format!("{}_index_delta_snapshots_total", prefix),
+                "Total number of delta snapshots published"
+            ),
+            registry
+        )?;
+
+        let delta_syncs_total = register_int_counter_with_registry!(
+            opts!(
+                format!("{}_index_delta_syncs_total", prefix),
+                "Total number of syncs applying delta snapshots"
+            ),
+            registry
+        )?;
+
+        let disk_usage_bytes = register_int_gauge_with_registry!(
+            opts!(
+                format!("{}_index_disk_usage_bytes", prefix),
+                "Amount of bytes consumed on disk by the index, its partitions and previous snapshots"
+            ),
+            registry
+        )?;
+
+        let disk_cap_refused_total = register_int_counter_with_registry!(
+            opts!(
+                format!("{}_index_disk_cap_refused_total", prefix),
+                "Total number of documents refused as the disk usage reached its cap"
+            ),
+            registry
+        )?;
+
+        let disk_evictions_total = register_int_counter_with_registry!(
+            opts!(
+                format!("{}_index_disk_evictions_total", prefix),
+                "Total number of evictions reclaiming disk space"
+            ),
+            registry
+        )?;
+
         let index_size_disk_bytes = register_int_gauge_with_registry!(
             opts!(
                 format!("{}_index_size_disk_bytes", prefix),
@@ -189,10 +289,23 @@ impl Metrics {
             registry
         )?;
 
+        let updated_timestamp_seconds = register_int_gauge_with_registry!(
+            opts!(
+                format!("{}_index_updated_timestamp_seconds", prefix),
+                "Time of the last commit of the index, in seconds since the epoch"
+            ),
+            registry
+        )?;
+
         Ok(Self {
             indexed_total,
             failed_total,
             snapshots_total,
+            delta_snapshots_total,
+            delta_syncs_total,
+            disk_usage_bytes,
+            disk_cap_refused_total,
+            disk_evictions_total,
             queries_total,
             index_size_disk_bytes,
             indexing_latency_seconds,
@@ -200,6 +313,7 @@ impl Metrics {
             documents,
             count_errors,
             count_latency_seconds,
+            updated_timestamp_seconds,
         })
     }
 }
@@ -213,9 +327,41 @@ pub struct IndexStore<INDEX> {
     index: INDEX,
     index_writer_memory_bytes: usize,
     metrics: Metrics,
+    cipher: Option<Cipher>,
+    /// delta snapshots published before publishing a full snapshot again
+    max_deltas: usize,
+    /// disk usage compared to its cap
+    capacity: Arc<Capacity>,
+    /// yearly partitions of older documents, if enabled
+    partitions: Option<Partitions>,
+    /// processors enriching documents before they are written, if configured
+    enrichment: Option<Arc<Pipeline>>,
 
     /// the handle running the counter for the metrics. We need to hold on to this handle.
     shutdown_counter: Option<oneshot::Sender<()>>,
+
+    freshness: RwLock<Freshness>,
+
+    /// incremented whenever the served documents may have changed, see [`Self::generation`]
+    generation: AtomicU64,
+
+    /// documents stored since the last snapshot, if real-time updates are enabled
+    realtime: Option<realtime::Realtime>,
+}
+
+/// How current the index being served is.
+#[derive(Clone, Copy, Debug, Default)]
+struct Freshness {
+    /// time of the last commit, stored as commit payload
+    updated_at: Option<OffsetDateTime>,
+    /// time of the last successful sync from storage
+    synced_at: Option<OffsetDateTime>,
+}
+
+/// Read the commit time from the payload of the last commit, see [`IndexWriter::commit`].
+fn commit_timestamp(index: &SearchIndex) -> Option<OffsetDateTime> {
+    let payload = index.load_metas().ok()?.payload?;
+    OffsetDateTime::from_unix_timestamp(payload.parse().ok()?).ok()
 }
 
 impl<INDEX> Drop for IndexStore<INDEX> {
@@ -255,6 +401,26 @@ impl<DOC> WriteIndex for Box<dyn WriteIndex<Document = DOC>> {
     fn tokenizers(&self) -> Result<TokenizerManager, Error> {
         self.as_ref().tokenizers()
     }
+
+    fn partition_year(&self, document: &Self::Document) -> Option<i32> {
+        self.as_ref().partition_year(document)
+    }
+
+    fn source_field(&self) -> Option<Field> {
+        self.as_ref().source_field()
+    }
+
+    fn label_field(&self) -> Option<Field> {
+        self.as_ref().label_field()
+    }
+
+    fn archived_field(&self) -> Option<Field> {
+        self.as_ref().archived_field()
+    }
+
+    fn schema_version(&self) -> u32 {
+        self.as_ref().schema_version()
+    }
 }
 
 /// Defines the interface for an index that can be written to.
@@ -277,6 +443,37 @@ pub trait WriteIndex {
     fn index_doc(&self, id: &str, document: &Self::Document) -> Result<Vec<(String, Document)>, Error>;
     /// Convert a document id to a term for referencing that document.
     fn doc_id_to_term(&self, id: &str) -> Term;
+    /// The year used to assign a document to a partition, if the index is partitioned.
+    ///
+    /// Documents without a year are kept in the main index.
+    fn partition_year(&self, _document: &Self::Document) -> Option<i32> {
+        None
+    }
+    /// Field storing the source a document was ingested from (e.g. `api` or `walker`), if supported.
+    fn source_field(&self) -> Option<Field> {
+        None
+    }
+    /// Field storing the labels added by the enrichment pipeline (`key:value`), if supported.
+    fn label_field(&self) -> Option<Field> {
+        None
+    }
+    /// Field flagging documents whose stored document was archived, if supported.
+    fn archived_field(&self) -> Option<Field> {
+        None
+    }
+    /// Field storing the SHA256 digest of the stored document, if supported.
+    ///
+    /// Used to audit the index, finding documents which changed since they were indexed.
+    fn digest_field(&self) -> Option<Field> {
+        None
+    }
+    /// Version of the indexing, recorded with the index next to a fingerprint of the schema.
+    ///
+    /// Must be increased when the way documents are indexed changes without changing the schema, so that indexes
+    /// built by previous versions are rejected and rebuilt.
+    fn schema_version(&self) -> u32 {
+        1
+    }
 }
 
 /// Defines the interface for an index that can be searched.
@@ -303,6 +500,18 @@ pub trait Index: WriteIndex {
         query: &dyn Query,
         options: &SearchOptions,
     ) -> Result<Self::MatchedDocument, Error>;
+    /// Qualifiers supported by the query language of the index.
+    fn search_fields(&self) -> Vec<SearchField> {
+        Vec::new()
+    }
+    /// Facets supported by the index, by name and the (fast) field providing their values.
+    fn facets(&self) -> Vec<(&'static str, Field)> {
+        Vec::new()
+    }
+    /// Columns of the Parquet export of the index, which can't be exported if there are none.
+    fn export_columns(&self) -> Vec<export::Column> {
+        Vec::new()
+    }
 }
 
 /// Errors returned by the index.
@@ -332,6 +541,22 @@ pub enum Error {
     Prometheus(prometheus::Error),
     #[error("I/O error {0}")]
     Io(std::io::Error),
+    #[error("encryption error {0}")]
+    Encryption(String),
+    #[error("unknown facet {0}")]
+    InvalidFacet(String),
+    #[error("invalid pattern: {0}")]
+    InvalidPattern(String),
+    #[error("enrichment error: {0}")]
+    Enrichment(String),
+    #[error("export error: {0}")]
+    Export(String),
+    #[error("incompatible index schema {found}, expected {expected}")]
+    IncompatibleSchema { found: String, expected: String },
+    #[error("index disk usage of {usage} bytes reached its cap of {cap} bytes")]
+    DiskCapExceeded { usage: u64, cap: u64 },
+    #[error("invalid index configuration: {0}")]
+    Configuration(String),
 }
 
 impl From<prometheus::Error> for Error {
@@ -367,6 +592,12 @@ pub struct SearchQuery {
 pub struct IndexWriter {
     writer: tantivy::IndexWriter,
     metrics: Metrics,
+    routing: Option<Routing>,
+    partitions: Vec<PartitionWriter>,
+    enrichment: Option<Arc<Pipeline>>,
+    /// ids of the added index documents, if tracked
+    tracked: Option<Vec<String>>,
+    capacity: Arc<Capacity>,
 }
 
 impl IndexWriter {
@@ -380,6 +611,36 @@ impl IndexWriter {
         self.add_document_with_id(index, data, id, |_| id.to_string())
     }
 
+    /// Add a document to the batch, recording the source it was ingested from.
+    ///
+    /// The source is only stored by indexes providing a [`WriteIndex::source_field`].
+    pub fn add_document_with_source<DOC>(
+        &mut self,
+        index: &dyn WriteIndex<Document = DOC>,
+        id: &str,
+        data: &[u8],
+        source: Option<&str>,
+    ) -> Result<(), Error> {
+        self.add(index, data, id, |_| id.to_string(), source, false, &[])
+    }
+
+    /// Add a stored document to the batch, recording the source it was ingested from, whether it was archived and the
+    /// labels derived from its stored metadata, like the outcome of verifying its signature.
+    ///
+    /// The archived status is only stored by indexes providing a [`WriteIndex::archived_field`], the labels by indexes
+    /// providing a [`WriteIndex::label_field`].
+    pub fn add_stored_document<DOC>(
+        &mut self,
+        index: &dyn WriteIndex<Document = DOC>,
+        id: &str,
+        data: &[u8],
+        source: Option<&str>,
+        archived: bool,
+        labels: &[String],
+    ) -> Result<(), Error> {
+        self.add(index, data, id, |_| id.to_string(), source, archived, labels)
+    }
+
     /// Add a document with a given identifier to the batch.
     pub fn add_document_with_id<DOC, F>(
         &mut self,
@@ -391,20 +652,74 @@ impl IndexWriter {
     where
         F: FnOnce(&DOC) -> String,
     {
+        self.add(index, data, name, id, None, false, &[])
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn add<DOC, F>(
+        &mut self,
+        index: &dyn WriteIndex<Document = DOC>,
+        data: &[u8],
+        name: &str,
+        id: F,
+        source: Option<&str>,
+        archived: bool,
+        labels: &[String],
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(&DOC) -> String,
+    {
+        if let Err(e) = self.capacity.check() {
+            self.metrics.disk_cap_refused_total.inc();
+            return Err(e);
+        }
         let indexing_latency = self.metrics.indexing_latency_seconds.start_timer();
         match index.parse_doc(data) {
             Ok(doc) => {
                 let id = &id(&doc);
+                let partition = self.routing.and_then(|r| r.route(index.partition_year(&doc)));
                 let docs = index.index_doc(id, &doc).map_err(|e| {
                     self.metrics.failed_total.inc();
                     e
                 })?;
-                for (i, doc) in docs {
+                let source = index.source_field().zip(source);
+                let archived = index.archived_field().filter(|_| archived);
+                let schema = self.enrichment.as_ref().map(|_| index.schema());
+                for (i, mut doc) in docs {
+                    if let Some((field, source)) = source {
+                        doc.add_text(field, source);
+                    }
+                    if let Some(field) = archived {
+                        doc.add_bool(field, true);
+                    }
+                    if let Some(field) = index.label_field() {
+                        for label in labels {
+                            doc.add_text(field, label);
+                        }
+                    }
+                    if let Some((pipeline, schema)) = self.enrichment.as_ref().zip(schema.as_ref()) {
+                        pipeline
+                            .process(schema, index.label_field(), &i, &mut doc)
+                            .map_err(|e| {
+                                self.metrics.failed_total.inc();
+                                e
+                            })?;
+                    }
                     self.delete_document(index, &i);
-                    self.writer.add_document(doc).map_err(|e| {
+                    let result = match partition.and_then(|p| self.partitions.get_mut(p)) {
+                        Some(partition) => {
+                            partition.delete_term_always(index.doc_id_to_term(&i));
+                            partition.add_document(doc)
+                        }
+                        None => self.writer.add_document(doc).map(|_| ()).map_err(Error::from),
+                    };
+                    result.map_err(|e| {
                         self.metrics.failed_total.inc();
                         e
                     })?;
+                    if let Some(tracked) = &mut self.tracked {
+                        tracked.push(i);
+                    }
                 }
 
                 self.metrics.indexed_total.inc();
@@ -420,41 +735,72 @@ impl IndexWriter {
     }
 
     /// Commit the batch and consume the writer. May merge index segments.
+    ///
+    /// The time of the commit is recorded as payload, in seconds since the epoch.
     pub fn commit(mut self) -> Result<(), Error> {
-        self.writer.commit()?;
+        let timestamp = OffsetDateTime::now_utc().unix_timestamp().to_string();
+        let mut commit = self.writer.prepare_commit()?;
+        commit.set_payload(&timestamp);
+        commit.commit()?;
         self.writer.wait_merging_threads()?;
+        for mut partition in self.partitions {
+            let mut commit = partition.writer.prepare_commit()?;
+            commit.set_payload(&timestamp);
+            commit.commit()?;
+            partition.writer.wait_merging_threads()?;
+        }
         Ok(())
     }
 
     /// Add a delete operation to the batch.
     pub fn delete_document<DOC>(&self, index: &dyn WriteIndex<Document = DOC>, key: &str) {
         let term = index.doc_id_to_term(key);
+        for partition in &self.partitions {
+            partition.delete_term(term.clone());
+        }
         self.writer.delete_term(term);
     }
 }
 
+/// Open the index of a directory, failing if it holds none instead of creating an empty one.
+fn open_existing(path: &Path, tokenizers: TokenizerManager) -> Result<SearchIndex, Error> {
+    let mut index = SearchIndex::open_in_dir(path).map_err(|e| Error::Open(format!("{}: {e}", path.display())))?;
+    index.set_tokenizers(tokenizers);
+    Ok(index)
+}
+
 /// Represents state of the index on disk and managing index swaps.
 #[derive(Debug)]
 struct IndexDirectory {
     path: PathBuf,
     state: IndexState,
     digest: Vec<u8>,
+    version: SchemaVersion,
+    /// the published snapshot the active directory matches, if known
+    chain: Option<Manifest>,
+}
+
+/// A snapshot of an index, either full or a delta.
+struct Snapshot {
+    data: Vec<u8>,
+    manifest: Manifest,
+}
+
+impl Snapshot {
+    fn is_delta(&self) -> bool {
+        self.manifest.sequence > 0
+    }
 }
 
 impl IndexDirectory {
     /// Attempt to build a new index from the serialized zstd data
-    pub fn sync(
-        &mut self,
-        schema: Schema,
-        settings: IndexSettings,
-        tokenizers: TokenizerManager,
-        data: &[u8],
-    ) -> Result<Option<SearchIndex>, Error> {
+    pub fn sync(&mut self, tokenizers: TokenizerManager, data: &[u8]) -> Result<Option<SearchIndex>, Error> {
         let digest = Sha256::digest(data).to_vec();
         if self.digest != digest {
             let next = self.state.next();
             let path = next.directory(&self.path);
-            let index = self.unpack(schema, settings, tokenizers, data, &path)?;
+            let index = self.unpack(tokenizers, data, &path)?;
+            self.chain = Some(Manifest::full(&digest, delta::list(&path)?));
             self.state = next;
             self.digest = digest;
             Ok(Some(index))
@@ -463,7 +809,47 @@ impl IndexDirectory {
         }
     }
 
-    pub fn new(path: &PathBuf) -> Result<IndexDirectory, Error> {
+    /// Attempt to build a new index by applying delta snapshots to the active directory.
+    fn apply(
+        &mut self,
+        tokenizers: TokenizerManager,
+        deltas: &[Vec<u8>],
+        manifest: &Manifest,
+    ) -> Result<SearchIndex, Error> {
+        let next = self.state.next();
+        let path = next.directory(&self.path);
+        delta::apply(&self.state.directory(&self.path), &path, deltas, manifest)?;
+        log::trace!("Applied {} delta snapshots into {:?}", deltas.len(), path);
+        self.version.check(&path)?;
+
+        let inner = open_existing(&path, tokenizers)?;
+        self.state = next;
+        self.chain = Some(manifest.clone());
+        Ok(inner)
+    }
+
+    /// Pack the active directory into a snapshot: a delta on top of the last published snapshot, unless `max_deltas`
+    /// were published since the last full snapshot.
+    fn snapshot(&mut self, max_deltas: usize) -> Result<Snapshot, Error> {
+        let path = self.state.directory(&self.path);
+        let files = delta::list(&path)?;
+        match &self.chain {
+            Some(chain) if chain.sequence < max_deltas as u64 => {
+                let data = delta::pack(&path, &chain.changed(&files))?;
+                Ok(Snapshot {
+                    data,
+                    manifest: chain.next(files),
+                })
+            }
+            _ => {
+                let data = self.pack()?;
+                let manifest = Manifest::full(&Sha256::digest(&data), files);
+                Ok(Snapshot { data, manifest })
+            }
+        }
+    }
+
+    pub fn new(path: &PathBuf, version: SchemaVersion) -> Result<IndexDirectory, Error> {
         if path.exists() {
             std::fs::remove_dir_all(path).map_err(|e| Error::Open(e.to_string()))?;
         }
@@ -473,6 +859,8 @@ impl IndexDirectory {
             digest: Vec::new(),
             path: path.clone(),
             state,
+            version,
+            chain: None,
         })
     }
 
@@ -490,9 +878,24 @@ impl IndexDirectory {
         std::fs::create_dir_all(&path).map_err(|e| Error::Open(e.to_string()))?;
         let index = self.build_new(settings, schema, tokenizers, &path)?;
         self.state = next;
+        self.chain = None;
         Ok(index)
     }
 
+    /// The size of the files of the index, including the ones of the previous snapshot.
+    fn disk_usage(&self) -> Result<u64, Error> {
+        capacity::disk_usage(&self.path)
+    }
+
+    /// Remove the directory of the previous snapshot, to reclaim its disk space.
+    fn clear_inactive(&self) -> Result<(), Error> {
+        let path = self.state.next().directory(&self.path);
+        if path.exists() {
+            std::fs::remove_dir_all(&path).map_err(Error::Io)?;
+        }
+        Ok(())
+    }
+
     fn build_new(
         &self,
         settings: IndexSettings,
@@ -507,6 +910,7 @@ impl IndexDirectory {
             .settings(settings)
             .tokenizers(tokenizers);
         let index = builder.open_or_create(dir).map_err(|e| Error::Open(e.to_string()))?;
+        self.version.write(path)?;
         Ok(index)
     }
 
@@ -520,14 +924,7 @@ impl IndexDirectory {
         self.build_new(settings, schema, tokenizers, &path)
     }
 
-    fn unpack(
-        &mut self,
-        schema: Schema,
-        settings: IndexSettings,
-        tokenizers: TokenizerManager,
-        data: &[u8],
-        path: &Path,
-    ) -> Result<SearchIndex, Error> {
+    fn unpack(&mut self, tokenizers: TokenizerManager, data: &[u8], path: &Path) -> Result<SearchIndex, Error> {
         if path.exists() {
             std::fs::remove_dir_all(path).map_err(|e| Error::Open(e.to_string()))?;
         }
@@ -537,14 +934,9 @@ impl IndexDirectory {
         let mut archive = tar::Archive::new(dec);
         archive.unpack(path).map_err(Error::Io)?;
         log::trace!("Unpacked into {:?}", path);
+        self.version.check(path)?;
 
-        let dir = MmapDirectory::open(path).map_err(|e| Error::Open(e.to_string()))?;
-        let builder = SearchIndex::builder()
-            .schema(schema)
-            .settings(settings)
-            .tokenizers(tokenizers);
-        let inner = builder.open_or_create(dir).map_err(|e| Error::Open(e.to_string()))?;
-        Ok(inner)
+        open_existing(path, tokenizers)
     }
 
     pub fn pack(&mut self) -> Result<Vec<u8>, Error> {
@@ -586,9 +978,24 @@ where
     INDEX: WriteIndex + 'static,
 {
     pub fn new_in_memory(index: INDEX) -> Result<Self, Error> {
+        Self::new_in_memory_with_partitions(index, &Default::default())
+    }
+
+    /// Create an index in memory, partitioned as configured.
+    pub fn new_in_memory_with_partitions(index: INDEX, partitions: &PartitionConfig) -> Result<Self, Error> {
         let schema = index.schema();
         let settings = index.settings();
         let tokenizers = index.tokenizers()?;
+        let version = SchemaVersion::new(index.schema_version(), &schema);
+        let partitions = Partitions::new(
+            partitions,
+            None,
+            index.name(),
+            &version,
+            schema.clone(),
+            settings.clone(),
+            tokenizers.clone(),
+        )?;
         let builder = SearchIndex::builder()
             .schema(schema)
             .settings(settings)
@@ -601,8 +1008,41 @@ where
             index_writer_memory_bytes: 32 * 1024 * 1024,
             index_dir: None,
             metrics: Metrics::register(&Default::default(), &name)?,
+            cipher: None,
+            max_deltas: 0,
+            capacity: Default::default(),
+            partitions,
+            enrichment: None,
             shutdown_counter: None,
-        })
+            freshness: Default::default(),
+            generation: Default::default(),
+            realtime: None,
+        }
+        .with_freshness())
+    }
+
+    /// Open an existing index directory, such as an unpacked snapshot.
+    ///
+    /// The directory is used as is, syncing and snapshots are not applicable.
+    pub fn open_dir(path: &Path, index: INDEX) -> Result<Self, Error> {
+        SchemaVersion::new(index.schema_version(), &index.schema()).check(path)?;
+        let inner = open_existing(path, index.tokenizers()?)?;
+        let name = index.name().to_string();
+        Ok(Self {
+            inner: Arc::new(RwLock::new(inner)),
+            index,
+            index_writer_memory_bytes: 32 * 1024 * 1024,
+            index_dir: None,
+            metrics: Metrics::register(&Default::default(), &name)?,
+            cipher: None,
+            partitions: None,
+            enrichment: None,
+            shutdown_counter: None,
+            freshness: Default::default(),
+            generation: Default::default(),
+            realtime: None,
+        }
+        .with_freshness())
     }
 
     /// runs an internal loop, counting documents and syncing that to the metrics
@@ -647,23 +1087,32 @@ where
         index: INDEX,
         metrics_registry: &Registry,
     ) -> Result<Self, Error> {
+        let enrichment = Pipeline::load(&config.enrichment, &Default::default())?.map(Arc::new);
         match config.mode {
             IndexMode::File => {
-                let path = config
-                    .index_dir
-                    .clone()
-                    .unwrap_or_else(|| {
-                        use rand::RngCore;
-                        let r = rand::thread_rng().next_u32();
-                        std::env::temp_dir().join(format!("index.{}", r))
-                    })
-                    .join(index.name());
+                let root = config.index_dir.clone().unwrap_or_else(|| {
+                    use rand::RngCore;
+                    let r = rand::thread_rng().next_u32();
+                    std::env::temp_dir().join(format!("index.{}", r))
+                });
+                let path = root.join(index.name());
 
                 let schema = index.schema();
                 let settings = index.settings();
                 let tokenizers = index.tokenizers()?;
 
-                let index_dir = IndexDirectory::new(&path)?;
+                let version = SchemaVersion::new(index.schema_version(), &schema);
+
+                let partitions = Partitions::new(
+                    &config.partitions,
+                    Some(&root),
+                    index.name(),
+                    &version,
+                    schema.clone(),
+                    settings.clone(),
+                    tokenizers.clone(),
+                )?;
+                let index_dir = IndexDirectory::new(&path, version)?;
                 let inner = index_dir.build(settings, schema, tokenizers)?;
                 let name = index.name().to_string();
                 let inner = Arc::new(RwLock::new(inner));
@@ -682,10 +1131,28 @@ where
                     index_dir: Some(RwLock::new(index_dir)),
                     index,
                     metrics,
+                    cipher: config.encryption_key.as_ref().map(Cipher::new),
+                    max_deltas: config.max_deltas,
+                    capacity: Arc::new(Capacity::new(&config.disk)),
+                    partitions,
+                    enrichment,
                     shutdown_counter: Some(shutdown_counter),
-                })
+                    freshness: Default::default(),
+                    generation: Default::default(),
+                    realtime: None,
+                }
+                .with_freshness())
             }
             IndexMode::S3 => {
+                if config.encryption_key.is_some() {
+                    // refused, rather than silently storing the index in plaintext
+                    return Err(Error::Configuration(
+                        "index encryption is only supported for file indices".to_string(),
+                    ));
+                }
+                if config.partitions.enabled {
+                    log::warn!("Index partitioning is only supported for file indices, ignoring partitions");
+                }
                 let bucket = storage.clone().try_into()?;
                 let schema = index.schema();
                 let settings = index.settings();
@@ -713,12 +1180,28 @@ where
                     index_dir: None,
                     index,
                     metrics,
+                    cipher: None,
+                    max_deltas: 0,
+                    capacity: Default::default(),
+                    partitions: None,
+                    enrichment,
                     shutdown_counter: Some(shutdown_counter),
-                })
+                    freshness: Default::default(),
+                    generation: Default::default(),
+                    realtime: None,
+                }
+                .with_freshness())
             }
         }
     }
 
+    /// Enrich documents with a pipeline, replacing the one of the configuration. Useful for pipelines with custom
+    /// processors which are not part of the [`enrich::Registry`].
+    pub fn with_enrichment(mut self, pipeline: Pipeline) -> Self {
+        self.enrichment = Some(Arc::new(pipeline));
+        self
+    }
+
     pub fn index(&self) -> &INDEX {
         &self.index
     }
@@ -727,34 +1210,191 @@ where
         &mut self.index
     }
 
-    /// Sync the index from a snapshot.
+    /// Version of the schema the index is built with.
+    pub fn schema_version(&self) -> SchemaVersion {
+        SchemaVersion::new(self.index.schema_version(), &self.index.schema())
+    }
+
+    /// Sync the index from a snapshot, including yearly partitions when they are due.
     ///
     /// NOTE: Only applicable for file indices.
     pub async fn sync(&self, storage: &Storage) -> Result<(), Error> {
+        self.sync_hot(storage).await?;
+        self.sync_partitions(storage).await
+    }
+
+    /// Sync the main index from a snapshot, leaving yearly partitions as they are.
+    ///
+    /// This allows serving recent documents quickly, loading partitions with a later call to [`Self::sync`].
+    ///
+    /// NOTE: Only applicable for file indices.
+    pub async fn sync_hot(&self, storage: &Storage) -> Result<(), Error> {
         if let Some(index_dir) = &self.index_dir {
-            let data = storage.get_index(self.index.name()).await?;
-            let mut index_dir = index_dir.write();
-            match index_dir.sync(
-                self.index.schema(),
-                self.index.settings(),
-                self.index.tokenizers()?,
-                &data,
-            ) {
-                Ok(Some(index)) => {
-                    *self.inner.write() = index;
-                    log::debug!("Index replaced");
-                }
-                Ok(None) => {
-                    // No index change
-                    log::debug!("No index change");
-                }
+            let synced = match self.sync_deltas(storage, index_dir).await {
+                Ok(synced) => synced,
                 Err(e) => {
-                    log::warn!("Error syncing index: {:?}, keeping old", e);
-                    return Err(e);
+                    log::warn!("Error applying delta snapshots: {:?}, syncing the full snapshot", e);
+                    false
+                }
+            };
+            if !synced {
+                self.sync_full(storage, index_dir).await?;
+                // catch up with the deltas published on top of the full snapshot
+                if let Err(e) = self.sync_deltas(storage, index_dir).await {
+                    log::warn!("(Ignored) Error applying delta snapshots: {:?}", e);
                 }
             }
             log::debug!("Index reloaded");
         }
+        self.refresh_updated_at();
+        self.freshness.write().synced_at = Some(OffsetDateTime::now_utc());
+        if let Err(e) = self.reconcile_realtime() {
+            log::warn!("Error reconciling real-time updates: {e:?}");
+        }
+        Ok(())
+    }
+
+    async fn sync_full(&self, storage: &Storage, index_dir: &RwLock<IndexDirectory>) -> Result<(), Error> {
+        let data = storage.get_index(self.index.name()).await?;
+        let data = match &self.cipher {
+            Some(cipher) => cipher.decrypt(&data)?,
+            None => data,
+        };
+        let mut index_dir = index_dir.write();
+        match index_dir.sync(self.index.tokenizers()?, &data) {
+            Ok(Some(index)) => {
+                *self.inner.write() = index;
+                self.invalidate();
+                log::debug!("Index replaced");
+            }
+            Ok(None) => {
+                // No index change
+                log::debug!("No index change");
+            }
+            Err(e) => {
+                log::warn!("Error syncing index: {:?}, keeping old", e);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the delta snapshots published since the snapshot being served, see [`delta`].
+    ///
+    /// Returns `false` if the full snapshot needs to be synced instead, like when there is no manifest or the chain of
+    /// deltas is broken.
+    async fn sync_deltas(&self, storage: &Storage, index_dir: &RwLock<IndexDirectory>) -> Result<bool, Error> {
+        let Some(data) = storage.get_index_manifest(self.index.name()).await? else {
+            return Ok(false);
+        };
+        let manifest: Manifest =
+            serde_json::from_slice(&data).map_err(|e| Error::Open(format!("invalid snapshot manifest: {e}")))?;
+
+        let current = index_dir
+            .read()
+            .chain
+            .as_ref()
+            .filter(|chain| chain.base == manifest.base && chain.sequence <= manifest.sequence)
+            .map(|chain| chain.sequence);
+        let Some(current) = current else {
+            return Ok(false);
+        };
+        if current == manifest.sequence {
+            log::debug!("No index change");
+            return Ok(true);
+        }
+
+        let mut deltas = Vec::new();
+        for sequence in current + 1..=manifest.sequence {
+            let data = match storage
+                .get_index_delta(self.index.name(), &manifest.base, sequence)
+                .await
+            {
+                Ok(data) => data,
+                Err(trustification_storage::Error::NotFound) => {
+                    log::info!("Delta snapshot {sequence} of {} is missing", manifest.base);
+                    return Ok(false);
+                }
+                Err(e) => return Err(e.into()),
+            };
+            deltas.push(match &self.cipher {
+                Some(cipher) => cipher.decrypt(&data)?,
+                None => data,
+            });
+        }
+
+        let index = index_dir.write().apply(self.index.tokenizers()?, &deltas, &manifest)?;
+        *self.inner.write() = index;
+        self.invalidate();
+        self.metrics.delta_syncs_total.inc();
+        log::debug!("Index updated by {} delta snapshots", deltas.len());
+        Ok(true)
+    }
+
+    fn with_freshness(self) -> Self {
+        self.refresh_updated_at();
+        self
+    }
+
+    fn refresh_updated_at(&self) {
+        let updated_at = commit_timestamp(&self.inner.read());
+        if let Some(updated_at) = updated_at {
+            self.metrics.updated_timestamp_seconds.set(updated_at.unix_timestamp());
+        }
+        self.freshness.write().updated_at = updated_at;
+    }
+
+    /// Time of the last commit of the (main) index being served, `None` if unknown.
+    pub fn updated_at(&self) -> Option<OffsetDateTime> {
+        self.freshness.read().updated_at
+    }
+
+    /// Time of the last successful sync of the index from storage, `None` if it has not been synced yet.
+    pub fn synced_at(&self) -> Option<OffsetDateTime> {
+        self.freshness.read().synced_at
+    }
+
+    /// The generation of the served documents, which changes whenever the index or one of its partitions is
+    /// reloaded, reset or committed to.
+    ///
+    /// Search results computed for one generation may be reused for as long as the generation stays the same. Indices
+    /// stored in S3 are read directly from the bucket, their generation only changes with local commits.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    async fn sync_partitions(&self, storage: &Storage) -> Result<(), Error> {
+        let Some(partitions) = self.partitions.as_ref().filter(|p| p.sync_due()) else {
+            return Ok(());
+        };
+
+        for partition in &partitions.entries {
+            let Some(index_dir) = &partition.index_dir else {
+                continue;
+            };
+            let data = match storage.get_index(&partition.name).await {
+                Ok(data) => data,
+                Err(trustification_storage::Error::NotFound) => {
+                    log::debug!("No snapshot of partition {} yet", partition.name);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let data = match &self.cipher {
+                Some(cipher) => cipher.decrypt(&data)?,
+                None => data,
+            };
+            let index = index_dir.write().sync(self.index.tokenizers()?, &data)?;
+            if let Some(index) = index {
+                *partition.inner.write() = index;
+                self.invalidate();
+                log::debug!("Partition {} replaced", partition.name);
+            }
+        }
         Ok(())
     }
 
@@ -767,22 +1407,149 @@ where
             let mut inner = self.inner.write();
             *inner = index;
         }
+        for partition in self.partitions.iter().flat_map(|p| &p.entries) {
+            if let Some(index_dir) = &partition.index_dir {
+                let index =
+                    index_dir
+                        .write()
+                        .reset(self.index.settings(), self.index.schema(), self.index.tokenizers()?)?;
+                *partition.inner.write() = index;
+            }
+            // publish the empty partition, replacing any previous snapshot
+            partition.dirty.store(true, Ordering::Relaxed);
+        }
+        self.invalidate();
         Ok(())
     }
 
     pub fn commit(&self, writer: IndexWriter) -> Result<(), Error> {
         writer.commit()?;
+        self.invalidate();
+        self.refresh_updated_at();
         Ok(())
     }
 
     /// Take a snapshot of the index and push to object storage.
     ///
-    /// NOTE: Only applicable for file indices.
+    /// Yearly partitions are only published when they have changed, independent of `force`.
     ///
+    /// NOTE: Only applicable for file indices.
+    pub async fn snapshot(&mut self, writer: IndexWriter, storage: &Storage, force: bool) -> Result<(), Error> {
+        let result = self.snapshot_main(writer, storage, force).await;
+        self.invalidate();
+        result?;
+        self.refresh_updated_at();
+        let result = self.snapshot_partitions(storage).await;
+        self.check_disk_usage()?;
+        result
+    }
+
+    /// Measure the disk usage of the index, evicting documents if it reached its cap and an eviction is configured.
+    fn check_disk_usage(&mut self) -> Result<(), Error> {
+        let Some(mut usage) = self.disk_usage()? else {
+            return Ok(());
+        };
+        if self.capacity.update(self.index.name(), usage) && self.evict()? {
+            usage = self.disk_usage()?.unwrap_or_default();
+            self.capacity.update(self.index.name(), usage);
+        }
+        self.metrics.disk_usage_bytes.set(usage as i64);
+        Ok(())
+    }
+
+    /// The disk usage of the index and its partitions, `None` if it isn't stored on disk.
+    fn disk_usage(&self) -> Result<Option<u64>, Error> {
+        let Some(index_dir) = &self.index_dir else {
+            return Ok(None);
+        };
+        let mut usage = index_dir.read().disk_usage()?;
+        for index_dir in self
+            .partitions
+            .iter()
+            .flat_map(|p| &p.entries)
+            .flat_map(|p| &p.index_dir)
+        {
+            usage += index_dir.read().disk_usage()?;
+        }
+        Ok(Some(usage))
+    }
+
+    /// Evict documents to reclaim disk space, as configured, returning whether any were evicted.
+    fn evict(&mut self) -> Result<bool, Error> {
+        let evicted = match self.capacity.eviction {
+            Eviction::None => false,
+            Eviction::OldestPartition => self.evict_oldest_partition()?,
+            Eviction::Archived => self.evict_archived()?,
+        };
+        if evicted {
+            self.metrics.disk_evictions_total.inc();
+            self.invalidate();
+        }
+        Ok(evicted)
+    }
+
+    /// Empty the oldest partition which has documents, publishing it with the next snapshot.
+    fn evict_oldest_partition(&self) -> Result<bool, Error> {
+        for partition in self.partitions.iter().flat_map(|p| &p.entries) {
+            let Some(index_dir) = &partition.index_dir else {
+                continue;
+            };
+            if partition.inner.read().reader()?.searcher().num_docs() == 0 {
+                continue;
+            }
+
+            log::warn!("Evicting partition {} to reclaim disk space", partition.name);
+            let mut index_dir = index_dir.write();
+            let index = index_dir.reset(self.index.settings(), self.index.schema(), self.index.tokenizers()?)?;
+            *partition.inner.write() = index;
+            index_dir.clear_inactive()?;
+            partition.dirty.store(true, Ordering::Relaxed);
+            return Ok(true);
+        }
+        log::warn!("No partition of index {} left to evict", self.index.name());
+        Ok(false)
+    }
+
+    /// Delete the documents flagged as archived from the index and its partitions.
     ///
+    /// Their disk space is reclaimed once the segments containing them are merged.
+    fn evict_archived(&mut self) -> Result<bool, Error> {
+        let Some(field) = self.index.archived_field() else {
+            log::warn!("Index {} doesn't flag archived documents to evict", self.index.name());
+            return Ok(false);
+        };
+        let query = || -> Box<dyn Query> {
+            Box::new(TermQuery::new(
+                Term::from_field_bool(field, true),
+                IndexRecordOption::Basic,
+            ))
+        };
+
+        let mut archived = 0;
+        for searcher in self.searchers()? {
+            archived += searcher.search(&query(), &tantivy::collector::Count)?;
+        }
+        if archived == 0 {
+            log::warn!("No archived documents of index {} left to evict", self.index.name());
+            return Ok(false);
+        }
+
+        log::warn!(
+            "Evicting {archived} archived documents of index {} to reclaim disk space",
+            self.index.name()
+        );
+        let writer = self.writer()?;
+        writer.writer.delete_query(query())?;
+        for partition in &writer.partitions {
+            partition.delete_query(query())?;
+        }
+        self.commit(writer)?;
+        Ok(true)
+    }
+
     // Disable the lint due to a [bug in clippy](https://github.com/rust-lang/rust-clippy/issues/6446).
     #[allow(clippy::await_holding_lock)]
-    pub async fn snapshot(&mut self, writer: IndexWriter, storage: &Storage, force: bool) -> Result<(), Error> {
+    async fn snapshot_main(&mut self, writer: IndexWriter, storage: &Storage, force: bool) -> Result<(), Error> {
         if let Some(index_dir) = &self.index_dir {
             writer.commit()?;
 
@@ -812,18 +1579,19 @@ where
             inner.directory_mut().sync_directory().map_err(Error::Io)?;
             if force || changed {
                 log::info!("Index has changed, publishing new snapshot");
-                let out = dir.pack()?;
+                let snapshot = dir.snapshot(self.max_deltas)?;
                 drop(lock);
                 drop(inner);
                 drop(dir);
-                match storage.put_index(self.index.name(), &out).await {
+                match self.publish(storage, &snapshot).await {
                     Ok(_) => {
                         log::trace!("Snapshot published successfully");
+                        index_dir.write().chain = Some(snapshot.manifest);
                         Ok(())
                     }
                     Err(e) => {
                         log::warn!("Error updating index: {:?}", e);
-                        Err(e.into())
+                        Err(e)
                     }
                 }
             } else {
@@ -837,22 +1605,107 @@ where
         }
     }
 
+    /// Publish a snapshot of the main index, followed by its manifest.
+    async fn publish(&self, storage: &Storage, snapshot: &Snapshot) -> Result<(), Error> {
+        let manifest = &snapshot.manifest;
+        let out = match &self.cipher {
+            Some(cipher) => cipher.encrypt(&snapshot.data)?,
+            None => snapshot.data.clone(),
+        };
+        if snapshot.is_delta() {
+            log::debug!("Publishing delta snapshot {} of {}", manifest.sequence, manifest.base);
+            storage
+                .put_index_delta(self.index.name(), &manifest.base, manifest.sequence, &out)
+                .await?;
+            self.metrics.delta_snapshots_total.inc();
+        } else {
+            storage.put_index(self.index.name(), &out).await?;
+        }
+
+        let data = serde_json::to_vec(manifest).map_err(|e| Error::Open(e.to_string()))?;
+        storage.put_index_manifest(self.index.name(), &data).await?;
+
+        if !snapshot.is_delta() && self.max_deltas > 0 {
+            // the deltas of the previous full snapshot are replaced by this one
+            if let Err(e) = storage.delete_index_deltas(self.index.name()).await {
+                log::warn!("(Ignored) Error removing delta snapshots: {:?}", e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn snapshot_partitions(&self, storage: &Storage) -> Result<(), Error> {
+        for partition in self.partitions.iter().flat_map(|p| &p.entries) {
+            let Some(index_dir) = &partition.index_dir else {
+                continue;
+            };
+            if !partition.dirty.swap(false, std::sync::atomic::Ordering::Relaxed) {
+                continue;
+            }
+
+            let out = {
+                let mut dir = index_dir.write();
+                let mut inner = partition.inner.write();
+                inner.directory_mut().sync_directory().map_err(Error::Io)?;
+                let _lock = inner.directory_mut().acquire_lock(&INDEX_WRITER_LOCK);
+                let managed_files = inner.directory().list_managed_files();
+                inner.directory_mut().garbage_collect(|| managed_files)?;
+                dir.pack()?
+            };
+            let out = match &self.cipher {
+                Some(cipher) => cipher.encrypt(&out)?,
+                None => out,
+            };
+
+            log::info!("Partition {} has changed, publishing new snapshot", partition.name);
+            if let Err(e) = storage.put_index(&partition.name, &out).await {
+                // try again with the next snapshot
+                partition.dirty.store(true, std::sync::atomic::Ordering::Relaxed);
+                return Err(e.into());
+            }
+            self.metrics.snapshots_total.inc();
+        }
+        Ok(())
+    }
+
     pub fn writer(&mut self) -> Result<IndexWriter, Error> {
         let writer = self.inner.write().writer(self.index_writer_memory_bytes)?;
+        let (routing, partitions) = match &self.partitions {
+            Some(partitions) => (Some(partitions.routing), partitions.writers()?),
+            None => (None, Vec::new()),
+        };
         Ok(IndexWriter {
             writer,
             metrics: self.metrics.clone(),
+            routing,
+            partitions,
+            enrichment: self.enrichment.clone(),
+            tracked: None,
+            capacity: self.capacity.clone(),
         })
     }
 }
 
+impl<INDEX> IndexStore<INDEX> {
+    /// Searchers of the main index and all partitions, the main index being first.
+    pub(crate) fn searchers(&self) -> Result<Vec<Searcher>, Error> {
+        let mut searchers = vec![self.inner.read().reader()?.searcher()];
+        if let Some(partitions) = &self.partitions {
+            searchers.extend(partitions.searchers()?);
+        }
+        Ok(searchers)
+    }
+}
+
 impl<INDEX: Index> IndexStore<INDEX> {
+    /// Qualifiers supported by the query language of the index.
+    pub fn search_fields(&self) -> Vec<SearchField> {
+        self.index.search_fields()
+    }
+
     /// To obtain the total number of docs.
     pub fn get_total_docs(&self) -> Result<u64, Error> {
-        let inner = self.inner.read();
-        let reader = inner.reader()?;
-        let searcher = reader.searcher();
-        Ok(searcher.num_docs())
+        Ok(self.searchers()?.iter().map(|searcher| searcher.num_docs()).sum())
     }
 
     /// Search the index for a given query and return matching documents.
@@ -862,118 +1715,86 @@ impl<INDEX: Index> IndexStore<INDEX> {
         offset: usize,
         limit: usize,
         options: SearchOptions,
+    ) -> Result<(Vec<INDEX::MatchedDocument>, usize), Error> {
+        self.search_filtered(q, None, offset, limit, options)
+    }
+
+    /// Search documents matching both a query and a filter.
+    ///
+    /// Unlike the query, the filter isn't provided by users, so it can restrict the results to the documents a user
+    /// may access.
+    pub fn search_filtered(
+        &self,
+        q: &str,
+        filter: Option<Box<dyn Query>>,
+        offset: usize,
+        limit: usize,
+        options: SearchOptions,
     ) -> Result<(Vec<INDEX::MatchedDocument>, usize), Error> {
         let latency = self.metrics.query_latency_seconds.start_timer();
 
-        if limit == 0 {
+        if limit == 0 || offset.checked_add(limit).is_none() {
             return Err(Error::InvalidLimitParameter(limit));
         }
 
-        let inner = self.inner.read();
-        let reader = inner.reader()?;
-        let searcher = reader.searcher();
-
-        let query = self.index.prepare_query(q)?;
+        let mut query = self.index.prepare_query(q)?;
+        if let Some(filter) = filter {
+            query.query = Box::new(BooleanQuery::intersection(vec![query.query, filter]));
+        }
 
         log::trace!("Processed query: {:?}", query);
 
-        let (top_docs, count) = if let Some(sort_by) = query.sort_by {
-            let field = sort_by.0;
-            let order = sort_by.1;
-            let order_by_str = self.index.schema().get_field_name(field).to_string();
-            let vtype = self.index.schema().get_field_entry(field).field_type().value_type();
-            let mut hits = Vec::new();
-            let total = match vtype {
-                Type::U64 => {
-                    let result = searcher.search(
-                        &query.query,
-                        &(
-                            TopDocs::with_limit(limit)
-                                .and_offset(offset)
-                                .order_by_fast_field::<u64>(&order_by_str, order.clone()),
-                            tantivy::collector::Count,
-                        ),
-                    )?;
-                    for r in result.0 {
-                        hits.push((1.0, r.1));
-                    }
-                    result.1
-                }
-                Type::I64 => {
-                    let result = searcher.search(
-                        &query.query,
-                        &(
-                            TopDocs::with_limit(limit)
-                                .and_offset(offset)
-                                .order_by_fast_field::<i64>(&order_by_str, order.clone()),
-                            tantivy::collector::Count,
-                        ),
-                    )?;
-                    for r in result.0 {
-                        hits.push((1.0, r.1));
-                    }
-                    result.1
-                }
-                Type::F64 => {
-                    let result = searcher.search(
-                        &query.query,
-                        &(
-                            TopDocs::with_limit(limit)
-                                .and_offset(offset)
-                                .order_by_fast_field::<f64>(&order_by_str, order.clone()),
-                            tantivy::collector::Count,
-                        ),
-                    )?;
-                    for r in result.0 {
-                        hits.push((1.0, r.1));
-                    }
-                    result.1
-                }
-                Type::Bool => {
-                    let result = searcher.search(
-                        &query.query,
-                        &(
-                            TopDocs::with_limit(limit)
-                                .and_offset(offset)
-                                .order_by_fast_field::<bool>(&order_by_str, order.clone()),
-                            tantivy::collector::Count,
-                        ),
-                    )?;
-                    for r in result.0 {
-                        hits.push((1.0, r.1));
-                    }
-                    result.1
-                }
-                Type::Date => {
-                    let result = searcher.search(
-                        &query.query,
-                        &(
-                            TopDocs::with_limit(limit)
-                                .and_offset(offset)
-                                .order_by_fast_field::<DateTime>(&order_by_str, order.clone()),
-                            tantivy::collector::Count,
-                        ),
-                    )?;
-                    for r in result.0 {
-                        hits.push((1.0, r.1));
-                    }
-                    result.1
-                }
-                _ => return Err(Error::NotSortable(order_by_str)),
-            };
-            (hits, total)
-        } else {
-            self.index.search(&searcher, &query.query, offset, limit)?
-        };
+        let searchers = self.searchers_for(&query)?;
+        let (hits, count) = self.search_in(&searchers, &query, offset, limit, &options)?;
 
         self.metrics.queries_total.inc();
-
         log::info!("#matches={count} for query '{q}'");
 
+        latency.observe_duration();
+        Ok((hits, count))
+    }
+
+    /// Search a page of the documents matching a query, in the given searchers.
+    pub(crate) fn search_in(
+        &self,
+        searchers: &[(Searcher, SearchQuery)],
+        query: &SearchQuery,
+        offset: usize,
+        limit: usize,
+        options: &SearchOptions,
+    ) -> Result<(Vec<INDEX::MatchedDocument>, usize), Error> {
+        let (top_docs, count) = if let [(searcher, query)] = searchers {
+            let (hits, count) = self.collect(searcher, query, offset, limit)?;
+            (hits.into_iter().map(|(rank, doc)| (0, rank, doc)).collect(), count)
+        } else {
+            // every partition has to provide enough hits to fill the requested page
+            let end = offset.checked_add(limit).ok_or(Error::InvalidLimitParameter(limit))?;
+            // scores are only comparable when using the same statistics for all partitions
+            let statistics = CombinedStatistics::new(searchers.iter().map(|(searcher, _)| searcher.clone()).collect());
+            let mut hits = Vec::new();
+            let mut count = 0;
+            for (n, (searcher, query)) in searchers.iter().enumerate() {
+                let query = SearchQuery {
+                    query: Box::new(CombinedScoring::new(query.query.box_clone(), statistics.clone())),
+                    sort_by: query.sort_by.clone(),
+                };
+                let (partition_hits, partition_count) = self.collect(searcher, &query, 0, end)?;
+                hits.extend(partition_hits.into_iter().map(|(rank, doc)| (n, rank, doc)));
+                count += partition_count;
+            }
+            let ascending = matches!(query.sort_by, Some((_, Order::Asc)));
+            hits.sort_by(|a: &(usize, Rank, DocAddress), b| b.1.cmp_position(&a.1, ascending));
+            (hits.into_iter().skip(offset).take(limit).collect::<Vec<_>>(), count)
+        };
+
         if options.summaries {
             let mut hits = Vec::new();
             for hit in top_docs {
-                match self.index.process_hit(hit.1, hit.0, &searcher, &query.query, &options) {
+                let (n, rank, address) = hit;
+                match self
+                    .index
+                    .process_hit(address, rank.score(), &searchers[n].0, &query.query, options)
+                {
                     Ok(value) => {
                         log::debug!("HIT: {:?}", value);
                         hits.push(value);
@@ -985,14 +1806,170 @@ impl<INDEX: Index> IndexStore<INDEX> {
             }
 
             log::debug!("Filtered to {}", hits.len());
-
-            latency.observe_duration();
             Ok((hits, count))
         } else {
-            latency.observe_duration();
             Ok((Vec::new(), count))
         }
     }
+
+    /// Load the stored fields of all documents matching a query, including the ones of partitions.
+    ///
+    /// Unlike [`Self::search`], documents are neither scored nor paged, so the query should only match a bounded
+    /// number of documents.
+    pub fn matching_documents(&self, q: &str) -> Result<Vec<Document>, Error> {
+        let query = self.index.prepare_query(q)?;
+        let mut documents = Vec::new();
+        for searcher in self.searchers()? {
+            for address in searcher.search(&query.query, &tantivy::collector::DocSetCollector)? {
+                documents.push(searcher.doc(address)?);
+            }
+        }
+        Ok(documents)
+    }
+
+    /// Collect the top documents of a single searcher.
+    fn collect(
+        &self,
+        searcher: &Searcher,
+        query: &SearchQuery,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<(Rank, DocAddress)>, usize), Error> {
+        fn sorted<T: FastValue>(
+            searcher: &Searcher,
+            query: &SearchQuery,
+            field: &str,
+            order: Order,
+            offset: usize,
+            limit: usize,
+            rank: fn(T) -> Rank,
+        ) -> Result<(Vec<(Rank, DocAddress)>, usize), Error> {
+            let (hits, count) = searcher.search(
+                &query.query,
+                &(
+                    TopDocs::with_limit(limit)
+                        .and_offset(offset)
+                        .order_by_fast_field::<T>(field, order),
+                    tantivy::collector::Count,
+                ),
+            )?;
+            Ok((hits.into_iter().map(|(value, doc)| (rank(value), doc)).collect(), count))
+        }
+
+        // text fast fields can't be sorted by tantivy, and their term ordinals differ between segments
+        fn sorted_text(
+            searcher: &Searcher,
+            query: &SearchQuery,
+            field: String,
+            order: Order,
+            offset: usize,
+            limit: usize,
+        ) -> Result<(Vec<(Rank, DocAddress)>, usize), Error> {
+            let ascending = matches!(order, Order::Asc);
+            let key = move |segment_reader: &SegmentReader| {
+                let column = segment_reader.fast_fields().str(&field).ok().flatten();
+                move |doc: DocId| {
+                    let value = column.as_ref().and_then(|column| {
+                        let ord = column.term_ords(doc).next()?;
+                        let mut value = String::new();
+                        column.ord_to_str(ord, &mut value).ok()?.then_some(value)
+                    });
+                    TextKey { value, ascending }
+                }
+            };
+            let (hits, count) = searcher.search(
+                &query.query,
+                &(
+                    TopDocs::with_limit(limit).and_offset(offset).custom_score(key),
+                    tantivy::collector::Count,
+                ),
+            )?;
+            let hits = hits.into_iter().map(|(key, doc)| (Rank::Text(key), doc)).collect();
+            Ok((hits, count))
+        }
+
+        match &query.sort_by {
+            Some((field, order)) => {
+                let field = *field;
+                let order_by_str = self.index.schema().get_field_name(field).to_string();
+                let vtype = self.index.schema().get_field_entry(field).field_type().value_type();
+                let order = order.clone();
+                match vtype {
+                    Type::U64 => sorted(searcher, query, &order_by_str, order, offset, limit, Rank::U64),
+                    Type::I64 => sorted(searcher, query, &order_by_str, order, offset, limit, Rank::I64),
+                    Type::F64 => sorted(searcher, query, &order_by_str, order, offset, limit, Rank::F64),
+                    Type::Bool => sorted(searcher, query, &order_by_str, order, offset, limit, Rank::Bool),
+                    Type::Date => sorted(searcher, query, &order_by_str, order, offset, limit, Rank::Date),
+                    Type::Str => sorted_text(searcher, query, order_by_str, order, offset, limit),
+                    _ => Err(Error::NotSortable(order_by_str)),
+                }
+            }
+            None => {
+                let (hits, count) = self.index.search(searcher, &query.query, offset, limit)?;
+                Ok((
+                    hits.into_iter().map(|(score, doc)| (Rank::Score(score), doc)).collect(),
+                    count,
+                ))
+            }
+        }
+    }
+}
+
+/// The position of a hit in the results, either its score or the value it was sorted by.
+///
+/// Used to merge the results of several partitions.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+enum Rank {
+    Score(f32),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    Bool(bool),
+    Date(DateTime),
+    Text(TextKey),
+}
+
+impl Rank {
+    fn score(&self) -> f32 {
+        match self {
+            Self::Score(score) => *score,
+            // sorted results are not scored
+            _ => 1.0,
+        }
+    }
+
+    /// Compare the positions of two hits in the results, the greater one coming first.
+    fn cmp_position(&self, other: &Self, ascending: bool) -> std::cmp::Ordering {
+        let ordering = self.partial_cmp(other).unwrap_or(std::cmp::Ordering::Equal);
+        match self {
+            // text keys already take the order into account
+            Self::Text(_) => ordering,
+            _ if ascending => ordering.reverse(),
+            _ => ordering,
+        }
+    }
+}
+
+/// The value of a text field a hit is sorted by, the greater key coming first in the results.
+///
+/// Hits without a value come last, whatever the order.
+#[derive(Clone, Debug, PartialEq)]
+struct TextKey {
+    value: Option<String>,
+    ascending: bool,
+}
+
+impl PartialOrd for TextKey {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        use std::cmp::Ordering::*;
+        Some(match (&self.value, &other.value) {
+            (Some(a), Some(b)) if self.ascending => b.cmp(a),
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => Greater,
+            (None, Some(_)) => Less,
+            (None, None) => Equal,
+        })
+    }
 }
 
 /// Convert a sikula term to a query
@@ -1025,6 +2002,44 @@ pub fn term2query<'m, R: Search, F: Fn(&R::Parsed<'m>) -> Box<dyn Query>>(
     }
 }
 
+/// Check if any resource of a sikula term, including negated ones, matches a predicate.
+pub fn term_any<'m, R: Search, F: Fn(&R::Parsed<'m>) -> bool>(term: &sikula::prelude::Term<'m, R>, f: &F) -> bool {
+    match term {
+        sikula::prelude::Term::Match(resource) => f(resource),
+        sikula::prelude::Term::Not(term) => term_any(term, f),
+        sikula::prelude::Term::And(terms) | sikula::prelude::Term::Or(terms) => {
+            terms.iter().any(|term| term_any(term, f))
+        }
+    }
+}
+
+/// Convert a sikula term to a tantivy query, using a fallible conversion of its resources.
+pub fn try_term2query<'m, R: Search, F: Fn(&R::Parsed<'m>) -> Result<Box<dyn Query>, Error>>(
+    term: &sikula::prelude::Term<'m, R>,
+    f: &F,
+) -> Result<Box<dyn Query>, Error> {
+    Ok(match term {
+        sikula::prelude::Term::Match(resource) => f(resource)?,
+        sikula::prelude::Term::Not(term) => {
+            let all: Box<dyn Query> = Box::new(AllQuery);
+            let query_terms = vec![(Occur::Should, all), (Occur::MustNot, try_term2query(term, f)?)];
+            Box::new(BooleanQuery::new(query_terms))
+        }
+        sikula::prelude::Term::And(terms) => Box::new(BooleanQuery::intersection(
+            terms
+                .iter()
+                .map(|term| try_term2query(term, f))
+                .collect::<Result<_, _>>()?,
+        )),
+        sikula::prelude::Term::Or(terms) => Box::new(BooleanQuery::union(
+            terms
+                .iter()
+                .map(|term| try_term2query(term, f))
+                .collect::<Result<_, _>>()?,
+        )),
+    })
+}
+
 /// Crate a i64 query based on an ordered value
 pub fn create_i64_query(schema: &Schema, field: Field, value: &Ordered<i64>) -> Box<dyn Query> {
     let field_name = schema.get_field_name(field).to_string();
@@ -1178,6 +2193,41 @@ pub fn create_text_query(field: Field, primary: &Primary<'_>) -> Box<dyn Query>
     }
 }
 
+/// Create a query for a tokenized text field, matching all tokens of the value produced by the default tokenizer.
+///
+/// Unlike [`create_text_query`], values containing separators like `kernel-rt` or `9.2` match the individual tokens
+/// of the field. For partial values, the last token is matched as a prefix.
+pub fn create_tokenized_query(field: Field, primary: &Primary<'_>) -> Box<dyn Query> {
+    use tantivy::tokenizer::TokenStream;
+
+    let (value, partial) = match primary {
+        Primary::Equal(value) => (*value, false),
+        Primary::Partial(value) => (*value, true),
+    };
+
+    let mut tokens = Vec::new();
+    if let Some(mut analyzer) = TokenizerManager::default().get("default") {
+        analyzer
+            .token_stream(value)
+            .process(&mut |token| tokens.push(token.text.clone()));
+    }
+
+    let last = tokens.len().saturating_sub(1);
+    let queries = tokens
+        .into_iter()
+        .enumerate()
+        .map(|(i, token)| {
+            let term = Term::from_field_text(field, &token);
+            let query: Box<dyn Query> = match partial && i == last {
+                true => Box::new(FuzzyTermQuery::new_prefix(term, 0, true)),
+                false => Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs)),
+            };
+            (Occur::Must, query)
+        })
+        .collect();
+    Box::new(BooleanQuery::new(queries))
+}
+
 /// Boost score of a term
 pub fn boost(q: Box<dyn Query>, weight: f32) -> Box<dyn Query> {
     Box::new(BoostQuery::new(q, weight))
@@ -1350,6 +2400,10 @@ mod tests {
                 &(TopDocs::with_limit(limit).and_offset(offset), tantivy::collector::Count),
             )?)
         }
+
+        fn export_columns(&self) -> Vec<export::Column> {
+            vec![export::Column::new("id", self.id, export::ColumnType::Text).key()]
+        }
     }
 
     impl WriteIndex for TestIndex {
@@ -1388,6 +2442,147 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_realtime() {
+        let _ = env_logger::try_init();
+        let config = RealtimeConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        let mut store = IndexStore::new_in_memory(TestIndex::new())
+            .unwrap()
+            .with_realtime(&config)
+            .unwrap();
+        let mut writer = store.writer().unwrap();
+        writer
+            .add_document(store.index_as_mut(), "old", b"existing document")
+            .unwrap();
+        store.commit(writer).unwrap();
+        let count = |store: &IndexStore<TestIndex>, q: &str| store.search(q, 0, 10, Default::default()).unwrap().1;
+        assert_eq!(count(&store, "existing"), 1);
+
+        store.index_realtime("new", b"new document", None).unwrap();
+        assert_eq!(count(&store, "new"), 1);
+        assert_eq!(count(&store, "document"), 2);
+
+        // real-time updates shadow the documents of the snapshot
+        store.index_realtime("old", b"updated document", None).unwrap();
+        assert_eq!(count(&store, "existing"), 0);
+        assert_eq!(count(&store, "updated"), 1);
+        assert_eq!(count(&store, "document"), 2);
+
+        store.delete_realtime("new").unwrap();
+        assert_eq!(count(&store, "new"), 0);
+        assert_eq!(count(&store, "document"), 1);
+
+        // expired updates are dropped, the snapshot being served again
+        let config = RealtimeConfig {
+            enabled: true,
+            max_age: Duration::ZERO.into(),
+        };
+        let store = IndexStore::new_in_memory(TestIndex::new())
+            .unwrap()
+            .with_realtime(&config)
+            .unwrap();
+        store.index_realtime("new", b"new document", None).unwrap();
+        assert_eq!(count(&store, "new"), 1);
+        store.reconcile_realtime().unwrap();
+        assert_eq!(count(&store, "new"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_inspect_stats() {
+        let _ = env_logger::try_init();
+        let mut store = IndexStore::new_in_memory(TestIndex::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        writer
+            .add_document(store.index_as_mut(), "foo", b"Foo is great")
+            .unwrap();
+        writer
+            .add_document(store.index_as_mut(), "bar", b"Bar is fine")
+            .unwrap();
+        writer.commit().unwrap();
+
+        let segments = store.segment_stats().unwrap();
+        assert_eq!(segments.iter().map(|s| s.num_docs).sum::<u32>(), 2);
+
+        let fields = store.field_stats().unwrap();
+        assert_eq!(fields.len(), 2);
+        for field in fields {
+            assert!(field.indexed);
+            assert_eq!(field.num_docs, Some(2));
+        }
+
+        // the test index has no digest field
+        let digests = store.document_digests().unwrap();
+        assert_eq!(
+            digests.into_iter().collect::<Vec<_>>(),
+            vec![("bar".to_string(), None), ("foo".to_string(), None)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_parquet() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+        use parquet::record::RowAccessor;
+
+        let _ = env_logger::try_init();
+        let mut exports = Vec::new();
+        for ids in [["foo", "bar", "baz"], ["baz", "foo", "bar"]] {
+            let mut store = IndexStore::new_in_memory(TestIndex::new()).unwrap();
+            let mut writer = store.writer().unwrap();
+            for id in ids {
+                writer.add_document(store.index_as_mut(), id, b"Some text").unwrap();
+            }
+            writer.commit().unwrap();
+
+            let mut data = Vec::new();
+            assert_eq!(store.export_parquet(&mut data).unwrap(), 3);
+            exports.push(data);
+        }
+
+        // the same documents result in the same file, regardless of the order they were indexed in
+        assert_eq!(exports[0], exports[1]);
+
+        let reader = SerializedFileReader::new(bytes::Bytes::from(exports.remove(0))).unwrap();
+        let ids: Vec<String> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().get_string(0).unwrap().clone())
+            .collect();
+        assert_eq!(ids, ["bar", "baz", "foo"]);
+    }
+
+    #[tokio::test]
+    async fn test_cursor() {
+        let _ = env_logger::try_init();
+        let mut store = IndexStore::new_in_memory(TestIndex::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        for id in ["foo", "bar", "baz"] {
+            writer.add_document(store.index_as_mut(), id, b"Some text").unwrap();
+        }
+        writer.commit().unwrap();
+
+        let options = SearchOptions::default();
+        let mut cursor = store.cursor("text", None).unwrap();
+        assert_eq!(cursor.total(), 3);
+        let mut ids = store.next_page(&mut cursor, 2, &options).unwrap();
+        assert_eq!(ids.len(), 2);
+
+        // documents indexed in the meantime don't shift the pages
+        let mut writer = store.writer().unwrap();
+        writer.add_document(store.index_as_mut(), "qux", b"Some text").unwrap();
+        writer.commit().unwrap();
+
+        ids.extend(store.next_page(&mut cursor, 2, &options).unwrap());
+        assert!(cursor.is_done());
+        assert!(store.next_page(&mut cursor, 2, &options).unwrap().is_empty());
+        ids.sort();
+        assert_eq!(ids, ["bar", "baz", "foo"]);
+
+        assert!(store.next_page(&mut cursor, 0, &options).is_err());
+    }
+
     #[tokio::test]
     async fn test_basic_index() {
         let _ = env_logger::try_init();
@@ -1424,6 +2619,25 @@ mod tests {
         assert_eq!(store.search("is", 0, 10, SearchOptions::default()).unwrap().1, 0);
     }
 
+    #[tokio::test]
+    async fn test_freshness() {
+        let _ = env_logger::try_init();
+        let mut store = IndexStore::new_in_memory(TestIndex::new()).unwrap();
+        assert_eq!(store.updated_at(), None);
+        assert_eq!(store.synced_at(), None);
+
+        let before = OffsetDateTime::now_utc().replace_nanosecond(0).unwrap();
+        let mut writer = store.writer().unwrap();
+        writer
+            .add_document(store.index_as_mut(), "foo", b"Foo is great")
+            .unwrap();
+        store.commit(writer).unwrap();
+
+        let updated_at = store.updated_at().unwrap();
+        assert!(updated_at >= before);
+        assert!(updated_at <= OffsetDateTime::now_utc());
+    }
+
     #[tokio::test]
     async fn test_zero_limit() {
         let _ = env_logger::try_init();
@@ -1478,8 +2692,8 @@ mod tests {
         let r = rand::thread_rng().next_u32();
         let dir = std::env::temp_dir().join(format!("index.{}", r));
 
-        let mut good = IndexDirectory::new(&dir.join("good")).unwrap();
-        let mut bad = IndexDirectory::new(&dir.join("bad")).unwrap();
+        let mut good = IndexDirectory::new(&dir.join("good"), SchemaVersion::new(1, &old_schema)).unwrap();
+        let mut bad = IndexDirectory::new(&dir.join("bad"), SchemaVersion::new(1, &new_schema)).unwrap();
 
         let store = good.build(Default::default(), old_schema, Default::default()).unwrap();
 
@@ -1490,16 +2704,66 @@ mod tests {
 
         let snapshot = good.pack().unwrap();
         let store = bad.build(Default::default(), new_schema, Default::default()).unwrap();
-        let schema = store.schema();
-        let settings = store.settings();
         let tokenizers = store.tokenizers();
 
         assert_eq!(bad.state, IndexState::A);
-        let result = bad.sync(schema, settings.clone(), tokenizers.clone(), &snapshot);
+        let result = bad.sync(tokenizers.clone(), &snapshot);
         assert!(result.is_err());
         assert_eq!(bad.state, IndexState::A);
     }
 
+    #[tokio::test]
+    async fn test_directory_sync_version_mismatch() {
+        let _ = env_logger::try_init();
+
+        let mut schema = Schema::builder();
+        let id = schema.add_text_field("id", STRING | FAST | STORED);
+        let schema = schema.build();
+
+        let r = rand::thread_rng().next_u32();
+        let dir = std::env::temp_dir().join(format!("index.{}", r));
+
+        let mut old = IndexDirectory::new(&dir.join("old"), SchemaVersion::new(1, &schema)).unwrap();
+        let mut new = IndexDirectory::new(&dir.join("new"), SchemaVersion::new(2, &schema)).unwrap();
+
+        let store = old
+            .build(Default::default(), schema.clone(), Default::default())
+            .unwrap();
+        let mut w = store.writer(15_000_000).unwrap();
+        w.add_document(doc!(id => "foo")).unwrap();
+        w.commit().unwrap();
+        w.wait_merging_threads().unwrap();
+
+        // same schema, but indexed by another version
+        let snapshot = old.pack().unwrap();
+        new.build(Default::default(), schema.clone(), Default::default())
+            .unwrap();
+        let result = new.sync(Default::default(), &snapshot);
+        assert!(matches!(result, Err(Error::IncompatibleSchema { .. })));
+        assert_eq!(new.state, IndexState::A);
+
+        // snapshots of the same version are accepted
+        let mut other = IndexDirectory::new(&dir.join("other"), SchemaVersion::new(1, &schema)).unwrap();
+        other
+            .build(Default::default(), schema.clone(), Default::default())
+            .unwrap();
+        let index = other.sync(Default::default(), &snapshot).unwrap().unwrap();
+        assert_eq!(index.reader().unwrap().searcher().num_docs(), 1);
+        assert_eq!(other.state, IndexState::B);
+    }
+
+    #[test]
+    fn test_open_dir_missing() {
+        let r = rand::thread_rng().next_u32();
+        let dir = std::env::temp_dir().join(format!("index.{}", r));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let result = IndexStore::open_dir(&dir, TestIndex::new());
+        assert!(matches!(result, Err(Error::Open(_))));
+        // the directory is left as it is
+        assert!(!dir.join("meta.json").exists());
+    }
+
     #[tokio::test]
     async fn test_index_dir_reset() {
         let _ = env_logger::try_init();
@@ -1511,7 +2775,7 @@ mod tests {
         let r = rand::thread_rng().next_u32();
         let dir = std::env::temp_dir().join(format!("index.{}", r));
 
-        let mut good = IndexDirectory::new(&dir.join("good")).unwrap();
+        let mut good = IndexDirectory::new(&dir.join("good"), SchemaVersion::new(1, &schema)).unwrap();
 
         let store = good
             .build(Default::default(), schema, TokenizerManager::default())
@@ -0,0 +1,311 @@
+//! Time based partitioning of an index.
+//!
+//! When enabled, documents of recent years stay in the main index, while older documents are moved into one index
+//! per year. All partitions are searched together, but older partitions are only synchronized occasionally, keeping
+//! the size and synchronization time of the main index bounded.
+//!
+//! Partitions are created when the index is opened, including the ones of years which are still hot, so that
+//! documents of a year turning cold while running can be routed to its partition. Documents indexed before that stay
+//! in the main index until they are indexed again.
+
+use crate::{Error, IndexDirectory, SchemaVersion};
+use parking_lot::{Mutex, RwLock};
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tantivy::{schema::Schema, tokenizer::TokenizerManager, Index as SearchIndex, IndexSettings, Searcher, Term};
+use time::OffsetDateTime;
+
+/// Memory used by the writer of a partition, partitions are expected to receive few updates.
+const PARTITION_WRITER_MEMORY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Configuration for partitioning an index by year.
+#[derive(Clone, Debug, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Index partitions")]
+pub struct PartitionConfig {
+    /// Split documents older than the hot years into yearly partitions, if supported by the index.
+    #[arg(env = "INDEX_PARTITIONED", long = "index-partitioned", default_value_t = false)]
+    pub enabled: bool,
+
+    /// Number of recent years kept in the main index.
+    #[arg(env = "INDEX_HOT_YEARS", long = "index-hot-years", default_value_t = 2)]
+    pub hot_years: u16,
+
+    /// Oldest year having its own partition, documents of earlier years are added to this partition.
+    #[arg(env = "INDEX_FIRST_YEAR", long = "index-first-year", default_value_t = 2000)]
+    pub first_year: i32,
+
+    /// Synchronization interval of the yearly partitions.
+    #[arg(
+        env = "INDEX_COLD_SYNC_INTERVAL",
+        long = "index-cold-sync-interval",
+        default_value = "1h"
+    )]
+    pub cold_sync_interval: humantime::Duration,
+}
+
+impl Default for PartitionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hot_years: 2,
+            first_year: 2000,
+            cold_sync_interval: Duration::from_secs(60 * 60).into(),
+        }
+    }
+}
+
+/// Assignment of documents to partitions, based on their year.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Routing {
+    first_year: i32,
+    hot_years: u16,
+    /// Partitions exist for the years before this one
+    end: i32,
+}
+
+impl Routing {
+    fn new(config: &PartitionConfig, current: i32) -> Self {
+        let hot_years = config.hot_years.max(1);
+        let hot_from = current - i32::from(hot_years) + 1;
+        let first_year = config.first_year.min(hot_from);
+        Self {
+            first_year,
+            hot_years,
+            // no partitions at all if they would start with the hot years
+            end: if first_year < hot_from { current + 1 } else { first_year },
+        }
+    }
+
+    fn years(&self) -> std::ops::Range<i32> {
+        self.first_year..self.end
+    }
+
+    /// Documents of this year or later stay in the main index.
+    fn hot_from(&self, current: i32) -> i32 {
+        (current - i32::from(self.hot_years) + 1).min(self.end)
+    }
+
+    /// The partition of a document, or `None` if it belongs to the main index.
+    pub(crate) fn route(&self, year: Option<i32>) -> Option<usize> {
+        self.route_at(year, OffsetDateTime::now_utc().year())
+    }
+
+    fn route_at(&self, year: Option<i32>, current: i32) -> Option<usize> {
+        let hot_from = self.hot_from(current);
+        match year {
+            Some(year) if year < hot_from && self.first_year < hot_from => {
+                Some((year.max(self.first_year) - self.first_year) as usize)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A single yearly partition.
+pub(crate) struct Partition {
+    pub(crate) name: String,
+    pub(crate) inner: RwLock<SearchIndex>,
+    pub(crate) index_dir: Option<RwLock<IndexDirectory>>,
+    /// If the partition was changed since its last snapshot
+    pub(crate) dirty: Arc<AtomicBool>,
+}
+
+/// The yearly partitions of an index.
+pub(crate) struct Partitions {
+    pub(crate) routing: Routing,
+    pub(crate) entries: Vec<Partition>,
+    cold_sync_interval: Duration,
+    next_sync: Mutex<Option<Instant>>,
+}
+
+impl Partitions {
+    /// Create the partitions, stored next to the main index if a root directory is provided, or in memory otherwise.
+    pub(crate) fn new(
+        config: &PartitionConfig,
+        root: Option<&Path>,
+        name: &str,
+        version: &SchemaVersion,
+        schema: Schema,
+        settings: IndexSettings,
+        tokenizers: TokenizerManager,
+    ) -> Result<Option<Self>, Error> {
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let current = OffsetDateTime::now_utc().year();
+        let routing = Routing::new(config, current);
+        let mut entries = Vec::new();
+        for year in routing.years() {
+            let name = format!("{name}_{year}");
+            let (inner, index_dir) = match root {
+                Some(root) => {
+                    let index_dir = IndexDirectory::new(&root.join(&name), version.clone())?;
+                    let inner = index_dir.build(settings.clone(), schema.clone(), tokenizers.clone())?;
+                    (inner, Some(RwLock::new(index_dir)))
+                }
+                None => {
+                    let inner = SearchIndex::builder()
+                        .schema(schema.clone())
+                        .settings(settings.clone())
+                        .tokenizers(tokenizers.clone())
+                        .create_in_ram()?;
+                    (inner, None)
+                }
+            };
+            entries.push(Partition {
+                name,
+                inner: RwLock::new(inner),
+                index_dir,
+                dirty: Default::default(),
+            });
+        }
+
+        log::info!(
+            "Partitioning index {name} by year, keeping documents since {} in the main index",
+            routing.hot_from(current)
+        );
+
+        Ok(Some(Self {
+            routing,
+            entries,
+            cold_sync_interval: config.cold_sync_interval.into(),
+            next_sync: Default::default(),
+        }))
+    }
+
+    /// Check if the partitions should be synchronized, scheduling the next synchronization if so.
+    pub(crate) fn sync_due(&self) -> bool {
+        let now = Instant::now();
+        let mut next = self.next_sync.lock();
+        match *next {
+            Some(at) if at > now => false,
+            _ => {
+                *next = Some(now + self.cold_sync_interval);
+                true
+            }
+        }
+    }
+
+    pub(crate) fn searchers(&self) -> Result<Vec<Searcher>, Error> {
+        self.entries
+            .iter()
+            .map(|p| Ok(p.inner.read().reader()?.searcher()))
+            .collect()
+    }
+
+    pub(crate) fn writers(&self) -> Result<Vec<PartitionWriter>, Error> {
+        self.entries
+            .iter()
+            .map(|p| {
+                let inner = p.inner.read();
+                Ok(PartitionWriter {
+                    writer: inner.writer_with_num_threads(1, PARTITION_WRITER_MEMORY_BYTES)?,
+                    searcher: inner.reader()?.searcher(),
+                    dirty: p.dirty.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Writer of a single partition.
+pub(crate) struct PartitionWriter {
+    pub(crate) writer: tantivy::IndexWriter,
+    /// Searcher of the last commit, to only delete documents actually stored in the partition
+    searcher: Searcher,
+    dirty: Arc<AtomicBool>,
+}
+
+impl PartitionWriter {
+    pub(crate) fn add_document(&mut self, document: tantivy::Document) -> Result<(), Error> {
+        self.dirty.store(true, Ordering::Relaxed);
+        self.writer.add_document(document)?;
+        Ok(())
+    }
+
+    /// Delete a document, only if it is contained in the partition.
+    pub(crate) fn delete_term(&self, term: Term) {
+        if self.searcher.doc_freq(&term).map(|n| n > 0).unwrap_or(true) {
+            self.dirty.store(true, Ordering::Relaxed);
+            self.writer.delete_term(term);
+        }
+    }
+
+    /// Delete the documents matching a query.
+    pub(crate) fn delete_query(&self, query: Box<dyn tantivy::query::Query>) -> Result<(), Error> {
+        self.dirty.store(true, Ordering::Relaxed);
+        self.writer.delete_query(query)?;
+        Ok(())
+    }
+
+    /// Delete a document, even if it was only added in the current batch.
+    pub(crate) fn delete_term_always(&self, term: Term) {
+        self.writer.delete_term(term);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routing() {
+        let routing = Routing::new(
+            &PartitionConfig {
+                enabled: true,
+                hot_years: 2,
+                first_year: 2010,
+                ..Default::default()
+            },
+            2024,
+        );
+        assert_eq!(routing.route_at(None, 2024), None);
+        assert_eq!(routing.route_at(Some(2024), 2024), None);
+        assert_eq!(routing.route_at(Some(2023), 2024), None);
+        assert_eq!(routing.route_at(Some(2022), 2024), Some(12));
+        assert_eq!(routing.route_at(Some(2010), 2024), Some(0));
+        assert_eq!(routing.route_at(Some(1999), 2024), Some(0));
+        assert_eq!(routing.years(), 2010..2025);
+    }
+
+    #[test]
+    fn routing_over_time() {
+        let routing = Routing::new(
+            &PartitionConfig {
+                enabled: true,
+                hot_years: 2,
+                first_year: 2010,
+                ..Default::default()
+            },
+            2024,
+        );
+        // years turning cold are routed to the partitions created upfront
+        assert_eq!(routing.route_at(Some(2023), 2025), Some(13));
+        assert_eq!(routing.route_at(Some(2024), 2026), Some(14));
+        // until there are no more partitions
+        assert_eq!(routing.route_at(Some(2025), 2027), None);
+    }
+
+    #[test]
+    fn routing_from_config() {
+        let routing = Routing::new(
+            &PartitionConfig {
+                enabled: true,
+                hot_years: 1,
+                first_year: 3000,
+                ..Default::default()
+            },
+            OffsetDateTime::now_utc().year(),
+        );
+        // the first year must not overlap with the main index
+        assert!(routing.years().is_empty());
+        assert_eq!(routing.route(Some(2000)), None);
+    }
+}
@@ -0,0 +1,275 @@
+//! Wildcard and regular expression patterns for string fields.
+//!
+//! Patterns are evaluated by walking the term dictionary of a field. A literal prefix lets tantivy skip all terms not
+//! sharing it, so patterns are required to start with one, and are capped in length and number of wildcards.
+//!
+//! In Package URLs, a `?` starts the qualifiers, so fields holding them only use `*` as a wildcard.
+
+use crate::Error;
+use sikula::prelude::Primary;
+use tantivy::{
+    query::{Query, RegexQuery, TermQuery},
+    schema::{Field, Term},
+};
+
+/// Maximum length of a pattern.
+pub const MAX_PATTERN_LENGTH: usize = 256;
+/// Maximum number of wildcards in a wildcard pattern.
+pub const MAX_PATTERN_WILDCARDS: usize = 8;
+/// Minimum length of the literal prefix of a pattern.
+pub const MIN_PATTERN_PREFIX: usize = 3;
+
+/// A pattern matching terms of a string field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Pattern<'a> {
+    /// A wildcard pattern, `*` matching any number and `?` matching a single character.
+    Wildcard(&'a str),
+    /// A wildcard pattern for Package URLs, `*` matching any number of characters, `?` matching itself.
+    PurlWildcard(&'a str),
+    /// A regular expression, which must match the full term (`/regex/`).
+    Regex(&'a str),
+}
+
+impl<'a> Pattern<'a> {
+    /// Detect a pattern in a query value, returns `None` for plain values.
+    pub fn detect(value: &'a str) -> Option<Self> {
+        if value.len() > 2 && value.starts_with('/') && value.ends_with('/') {
+            Some(Self::Regex(&value[1..value.len() - 1]))
+        } else if value.contains(['*', '?']) {
+            Some(Self::Wildcard(value))
+        } else {
+            None
+        }
+    }
+
+    /// Detect a pattern in a Package URL, returns `None` for plain values.
+    pub fn detect_purl(value: &'a str) -> Option<Self> {
+        match Self::detect(value)? {
+            Self::Wildcard(value) if !value.contains('*') => None,
+            Self::Wildcard(value) => Some(Self::PurlWildcard(value)),
+            pattern => Some(pattern),
+        }
+    }
+
+    fn wildcards(&self) -> &'static [char] {
+        match self {
+            Self::Wildcard(_) => &['*', '?'],
+            Self::PurlWildcard(_) => &['*'],
+            Self::Regex(_) => &[],
+        }
+    }
+
+    /// Check the limits of the pattern, and convert it into a regular expression.
+    pub fn to_regex(&self) -> Result<String, Error> {
+        let wildcards = self.wildcards();
+        let (pattern, prefix) = match self {
+            Self::Wildcard(pattern) | Self::PurlWildcard(pattern) => {
+                let count = pattern.matches(wildcards).count();
+                if count > MAX_PATTERN_WILDCARDS {
+                    return Err(Error::InvalidPattern(format!(
+                        "pattern has {count} wildcards, at most {MAX_PATTERN_WILDCARDS} are allowed"
+                    )));
+                }
+                (pattern, pattern.find(wildcards).unwrap_or(pattern.len()))
+            }
+            Self::Regex(pattern) => (pattern, regex_prefix(pattern)),
+        };
+
+        if pattern.len() > MAX_PATTERN_LENGTH {
+            return Err(Error::InvalidPattern(format!(
+                "pattern is longer than {MAX_PATTERN_LENGTH} characters"
+            )));
+        }
+        if prefix < MIN_PATTERN_PREFIX {
+            return Err(Error::InvalidPattern(format!(
+                "pattern must start with at least {MIN_PATTERN_PREFIX} literal characters"
+            )));
+        }
+
+        Ok(match self {
+            Self::Wildcard(pattern) | Self::PurlWildcard(pattern) => {
+                let mut regex = String::with_capacity(pattern.len() * 2);
+                for c in pattern.chars() {
+                    match c {
+                        '*' => regex.push_str(".*"),
+                        '?' if wildcards.contains(&'?') => regex.push('.'),
+                        c => {
+                            if is_meta(c) {
+                                regex.push('\\');
+                            }
+                            regex.push(c);
+                        }
+                    }
+                }
+                regex
+            }
+            Self::Regex(pattern) => pattern.to_string(),
+        })
+    }
+}
+
+fn is_meta(c: char) -> bool {
+    matches!(
+        c,
+        '\\' | '.' | '+' | '*' | '?' | '(' | ')' | '|' | '[' | ']' | '{' | '}' | '^' | '$' | '#' | '&' | '-' | '~'
+    )
+}
+
+/// Number of literal characters a regular expression starts with.
+///
+/// Escaped meta characters are literals, while a character followed by a quantifier isn't, as it may be left out or
+/// repeated. An alternation at the top level means there is no common prefix at all.
+fn regex_prefix(pattern: &str) -> usize {
+    if has_top_level_alternation(pattern) {
+        return 0;
+    }
+
+    let mut prefix = 0;
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c) if is_meta(c) => {}
+                _ => break,
+            },
+            c if is_meta(c) => break,
+            _ => {}
+        }
+        if matches!(chars.peek(), Some('*' | '?' | '+' | '{')) {
+            break;
+        }
+        prefix += 1;
+    }
+    prefix
+}
+
+/// Whether a regular expression has an alternation outside of any group.
+fn has_top_level_alternation(pattern: &str) -> bool {
+    let mut depth = 0usize;
+    let mut class = false;
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            ']' if class => class = false,
+            _ if class => {}
+            '[' => class = true,
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            '|' if depth == 0 => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Convert a sikula primary to a tantivy query for string fields, supporting patterns for equality.
+///
+/// Partial matches are not affected by patterns, as they already match any part of a term.
+pub fn create_pattern_query(field: Field, primary: &Primary<'_>) -> Result<Box<dyn Query>, Error> {
+    create_query(field, primary, Pattern::detect)
+}
+
+/// Convert a sikula primary to a tantivy query for fields holding Package URLs, see [`Pattern::detect_purl`].
+pub fn create_purl_pattern_query(field: Field, primary: &Primary<'_>) -> Result<Box<dyn Query>, Error> {
+    create_query(field, primary, Pattern::detect_purl)
+}
+
+fn create_query<'a>(
+    field: Field,
+    primary: &Primary<'a>,
+    detect: fn(&'a str) -> Option<Pattern<'a>>,
+) -> Result<Box<dyn Query>, Error> {
+    match primary {
+        Primary::Equal(value) => match detect(*value) {
+            Some(pattern) => {
+                let regex = pattern.to_regex()?;
+                let query =
+                    RegexQuery::from_pattern(&regex, field).map_err(|e| Error::InvalidPattern(e.to_string()))?;
+                Ok(Box::new(query))
+            }
+            None => Ok(Box::new(TermQuery::new(
+                Term::from_field_text(field, value),
+                Default::default(),
+            ))),
+        },
+        Primary::Partial(_) => Ok(crate::create_string_query(field, primary)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect() {
+        assert_eq!(Pattern::detect("openssl"), None);
+        assert_eq!(
+            Pattern::detect("pkg:rpm/redhat/openssl*"),
+            Some(Pattern::Wildcard("pkg:rpm/redhat/openssl*"))
+        );
+        assert_eq!(
+            Pattern::detect("/pkg:rpm/.*/openssl/"),
+            Some(Pattern::Regex("pkg:rpm/.*/openssl"))
+        );
+        assert_eq!(Pattern::detect("/"), None);
+    }
+
+    #[test]
+    fn detect_purl() {
+        assert_eq!(Pattern::detect_purl("pkg:rpm/redhat/openssl@3.0.7?arch=src"), None);
+        assert_eq!(
+            Pattern::detect_purl("pkg:rpm/redhat/openssl@3.0.7?arch=*"),
+            Some(Pattern::PurlWildcard("pkg:rpm/redhat/openssl@3.0.7?arch=*"))
+        );
+        assert_eq!(
+            Pattern::PurlWildcard("pkg:rpm/redhat/openssl@3.0.7?arch=*")
+                .to_regex()
+                .unwrap(),
+            r"pkg:rpm/redhat/openssl@3\.0\.7\?arch=.*"
+        );
+        assert_eq!(
+            Pattern::detect_purl("/pkg:rpm/.*/openssl/"),
+            Some(Pattern::Regex("pkg:rpm/.*/openssl"))
+        );
+    }
+
+    #[test]
+    fn prefix() {
+        assert_eq!(regex_prefix("pkg:rpm/.*"), 8);
+        assert_eq!(regex_prefix(r"pkg\.rpm.*"), 7);
+        assert_eq!(regex_prefix("pkgs?:rpm"), 3);
+        assert_eq!(regex_prefix("pk+g"), 1);
+        assert_eq!(regex_prefix("pkg:a{2}"), 4);
+        assert_eq!(regex_prefix(r"pkg\d"), 3);
+        assert_eq!(regex_prefix("pkg:(rpm|oci)/.*"), 4);
+        assert_eq!(regex_prefix("pkg:rpm/.*|.*"), 0);
+        assert_eq!(regex_prefix("pkg:[|]"), 4);
+    }
+
+    #[test]
+    fn wildcard() {
+        assert_eq!(
+            Pattern::Wildcard("pkg:rpm/redhat/openssl@1.1?*").to_regex().unwrap(),
+            r"pkg:rpm/redhat/openssl@1\.1..*"
+        );
+    }
+
+    #[test]
+    fn limits() {
+        assert!(Pattern::Wildcard("*openssl").to_regex().is_err());
+        assert!(Pattern::Wildcard("pk*").to_regex().is_err());
+        assert!(Pattern::Wildcard("pkg*").to_regex().is_ok());
+        assert!(Pattern::Wildcard("pkg*a*b*c*d*e*f*g*h*i").to_regex().is_err());
+        assert!(Pattern::Regex(".*openssl").to_regex().is_err());
+        assert!(Pattern::Regex("pkg:.*openssl").to_regex().is_ok());
+        assert!(Pattern::Regex("pkg:.*|.*openssl").to_regex().is_err());
+        assert!(Pattern::Regex("pkg?:.*").to_regex().is_err());
+        assert!(Pattern::PurlWildcard("pkg:rpm/a?b*c*d*e*f*g*h*i").to_regex().is_ok());
+        assert!(Pattern::Wildcard(&format!("pkg{}", "a".repeat(MAX_PATTERN_LENGTH)))
+            .to_regex()
+            .is_err());
+    }
+}
@@ -0,0 +1,278 @@
+//! Real-time updates of an index served from snapshots.
+//!
+//! Documents are only searchable once the indexer processed them and the next snapshot was synced, which can take up to
+//! the sync interval. When enabled, the service storing a document also indexes it right away, into a small in-memory
+//! index which is searched together with the snapshot. Its documents shadow the ones of the snapshot having the same id,
+//! and are dropped once a snapshot committed after they were indexed includes them.
+
+use crate::{Error, IndexStore, IndexWriter, SearchQuery, WriteIndex};
+use parking_lot::Mutex;
+use std::{collections::BTreeMap, time::Duration};
+use tantivy::{
+    collector::Count,
+    query::{AllQuery, BooleanQuery, Occur, Query, TermQuery},
+    schema::IndexRecordOption,
+    Index as SearchIndex, Searcher,
+};
+use time::OffsetDateTime;
+
+/// Memory used by the writer of the real-time index, which only receives single documents.
+const REALTIME_WRITER_MEMORY_BYTES: usize = 15_000_000;
+
+/// Configuration of real-time updates.
+#[derive(Clone, Debug, clap::Args)]
+#[command(
+    rename_all_env = "SCREAMING_SNAKE_CASE",
+    next_help_heading = "Index real-time updates"
+)]
+pub struct RealtimeConfig {
+    /// Index stored documents right away, instead of waiting for the next snapshot of the indexer.
+    #[arg(env = "INDEX_REALTIME", long = "index-realtime", default_value_t = false)]
+    pub enabled: bool,
+
+    /// Maximum time documents are kept in the real-time index, in case no snapshot ever includes them.
+    #[arg(
+        env = "INDEX_REALTIME_MAX_AGE",
+        long = "index-realtime-max-age",
+        default_value = "1h"
+    )]
+    pub max_age: humantime::Duration,
+}
+
+impl Default for RealtimeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_age: Duration::from_secs(60 * 60).into(),
+        }
+    }
+}
+
+/// The in-memory index of the documents stored since the last snapshot.
+pub(crate) struct Realtime {
+    index: SearchIndex,
+    entries: Mutex<BTreeMap<String, Entry>>,
+    max_age: Duration,
+    /// held while writing, as there may only be a single writer
+    writing: Mutex<()>,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    /// ids of the index documents of the stored document, shadowing the ones of the snapshot
+    ids: Vec<String>,
+    /// when the document was indexed or deleted
+    at: OffsetDateTime,
+    /// whether the document was deleted, only shadowing the snapshot
+    deleted: bool,
+}
+
+impl<INDEX> IndexStore<INDEX>
+where
+    INDEX: WriteIndex + 'static,
+{
+    /// Enable real-time updates, if configured.
+    pub fn with_realtime(mut self, config: &RealtimeConfig) -> Result<Self, Error> {
+        if config.enabled {
+            let index = SearchIndex::builder()
+                .schema(self.index.schema())
+                .settings(self.index.settings())
+                .tokenizers(self.index.tokenizers()?)
+                .create_in_ram()?;
+            self.realtime = Some(Realtime {
+                index,
+                entries: Default::default(),
+                max_age: config.max_age.into(),
+                writing: Default::default(),
+            });
+        }
+        Ok(self)
+    }
+
+    /// Whether real-time updates are enabled.
+    pub fn realtime_enabled(&self) -> bool {
+        self.realtime.is_some()
+    }
+
+    fn realtime_writer(&self, realtime: &Realtime) -> Result<IndexWriter, Error> {
+        Ok(IndexWriter {
+            writer: realtime
+                .index
+                .writer_with_num_threads(1, REALTIME_WRITER_MEMORY_BYTES)?,
+            metrics: self.metrics.clone(),
+            routing: None,
+            partitions: Vec::new(),
+            enrichment: self.enrichment.clone(),
+            tracked: None,
+            // the real-time index is kept in memory
+            capacity: Default::default(),
+        })
+    }
+
+    /// Index a stored document right away, so that it can be searched before a snapshot includes it.
+    ///
+    /// Does nothing unless real-time updates are enabled.
+    pub fn index_realtime(&self, id: &str, data: &[u8], source: Option<&str>) -> Result<(), Error> {
+        let Some(realtime) = &self.realtime else {
+            return Ok(());
+        };
+
+        let _writing = realtime.writing.lock();
+        let mut writer = self.realtime_writer(realtime)?;
+        if let Some(previous) = realtime.entries.lock().get(id) {
+            for i in &previous.ids {
+                writer.writer.delete_term(self.index.doc_id_to_term(i));
+            }
+        }
+        writer.tracked = Some(Vec::new());
+        writer.add_document_with_source(&self.index, id, data, source)?;
+        let ids = writer.tracked.take().unwrap_or_default();
+        writer.commit()?;
+
+        if ids.is_empty() {
+            // the document couldn't be parsed, which the indexer will fail on as well
+            realtime.entries.lock().remove(id);
+        } else {
+            let entry = Entry {
+                ids,
+                at: OffsetDateTime::now_utc(),
+                deleted: false,
+            };
+            realtime.entries.lock().insert(id.to_string(), entry);
+        }
+        self.invalidate();
+        Ok(())
+    }
+
+    /// Hide a deleted document right away, until a snapshot doesn't include it anymore.
+    ///
+    /// Does nothing unless real-time updates are enabled.
+    pub fn delete_realtime(&self, id: &str) -> Result<(), Error> {
+        let Some(realtime) = &self.realtime else {
+            return Ok(());
+        };
+
+        let _writing = realtime.writing.lock();
+        let ids = match realtime.entries.lock().get(id) {
+            Some(previous) => previous.ids.clone(),
+            None => vec![id.to_string()],
+        };
+        let writer = self.realtime_writer(realtime)?;
+        for i in &ids {
+            writer.writer.delete_term(self.index.doc_id_to_term(i));
+        }
+        writer.commit()?;
+
+        let entry = Entry {
+            ids,
+            at: OffsetDateTime::now_utc(),
+            deleted: true,
+        };
+        realtime.entries.lock().insert(id.to_string(), entry);
+        self.invalidate();
+        Ok(())
+    }
+
+    /// Drop the documents which the synced snapshot caught up with, or which are too old.
+    pub(crate) fn reconcile_realtime(&self) -> Result<(), Error> {
+        let Some(realtime) = &self.realtime else {
+            return Ok(());
+        };
+
+        let _writing = realtime.writing.lock();
+        let searchers = self.searchers()?;
+        let contains = |id: &str| -> Result<bool, Error> {
+            let query = TermQuery::new(self.index.doc_id_to_term(id), IndexRecordOption::Basic);
+            for searcher in &searchers {
+                if searcher.search(&query, &Count)? > 0 {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        };
+
+        let now = OffsetDateTime::now_utc();
+        let updated_at = self.updated_at();
+        let entries = realtime.entries.lock().clone();
+        let mut reconciled = Vec::new();
+        for (id, entry) in entries {
+            let expired = now - entry.at >= realtime.max_age;
+            let caught_up = match updated_at {
+                Some(updated_at) if updated_at > entry.at => {
+                    let mut included = true;
+                    for i in &entry.ids {
+                        included &= contains(i)? != entry.deleted;
+                    }
+                    included
+                }
+                _ => false,
+            };
+            if expired || caught_up {
+                reconciled.push((id, entry));
+            }
+        }
+        if reconciled.is_empty() {
+            return Ok(());
+        }
+
+        log::debug!("Dropping {} real-time updates", reconciled.len());
+        let writer = self.realtime_writer(realtime)?;
+        for (_, entry) in &reconciled {
+            for i in &entry.ids {
+                writer.writer.delete_term(self.index.doc_id_to_term(i));
+            }
+        }
+        writer.commit()?;
+
+        let mut entries = realtime.entries.lock();
+        for (id, _) in reconciled {
+            entries.remove(&id);
+        }
+        drop(entries);
+        self.invalidate();
+        Ok(())
+    }
+}
+
+impl<INDEX: WriteIndex> IndexStore<INDEX> {
+    /// The searchers of the index, each with the query to run: the snapshot excludes the documents shadowed by
+    /// real-time updates, whose searcher comes last.
+    pub(crate) fn searchers_for(&self, query: &SearchQuery) -> Result<Vec<(Searcher, SearchQuery)>, Error> {
+        let same = |query: &SearchQuery| SearchQuery {
+            query: query.query.box_clone(),
+            sort_by: query.sort_by.clone(),
+        };
+
+        let searchers = self.searchers()?;
+        let Some(realtime) = &self.realtime else {
+            return Ok(searchers.into_iter().map(|searcher| (searcher, same(query))).collect());
+        };
+
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Must, Box::new(AllQuery))];
+        for entry in realtime.entries.lock().values() {
+            for i in &entry.ids {
+                let term = TermQuery::new(self.index.doc_id_to_term(i), IndexRecordOption::Basic);
+                clauses.push((Occur::MustNot, Box::new(term)));
+            }
+        }
+
+        let mut result = Vec::with_capacity(searchers.len() + 1);
+        if clauses.len() == 1 {
+            result.extend(searchers.into_iter().map(|searcher| (searcher, same(query))));
+        } else {
+            let unshadowed = BooleanQuery::new(clauses);
+            for searcher in searchers {
+                let query = SearchQuery {
+                    query: Box::new(BooleanQuery::intersection(vec![
+                        query.query.box_clone(),
+                        unshadowed.box_clone(),
+                    ])),
+                    sort_by: query.sort_by.clone(),
+                };
+                result.push((searcher, query));
+            }
+        }
+        result.push((realtime.index.reader()?.searcher(), same(query)));
+        Ok(result)
+    }
+}
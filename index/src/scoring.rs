@@ -0,0 +1,146 @@
+//! Scoring documents consistently across several indexes.
+//!
+//! BM25 weighs the terms of a query by their frequency among all documents of an index. When searching the main index
+//! together with its partitions, or its real-time updates, each index would score its documents using its own
+//! statistics, making scores of different indexes incomparable when merging the results. Using the statistics of all
+//! indexes, a document gets the same score as it would get in a single index containing all of them.
+//!
+//! Documents shadowed by real-time updates are still counted, which only skews the statistics slightly.
+
+use std::{
+    fmt::{Debug, Formatter},
+    sync::Arc,
+};
+use tantivy::{
+    query::{Bm25StatisticsProvider, EnableScoring, Query, Weight},
+    schema::Field,
+    Searcher, Term,
+};
+
+/// The statistics of several indexes, combined.
+#[derive(Clone)]
+pub(crate) struct CombinedStatistics(Arc<Vec<Searcher>>);
+
+impl CombinedStatistics {
+    pub(crate) fn new(searchers: Vec<Searcher>) -> Self {
+        Self(Arc::new(searchers))
+    }
+}
+
+impl Debug for CombinedStatistics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CombinedStatistics")
+            .field("indexes", &self.0.len())
+            .finish()
+    }
+}
+
+impl Bm25StatisticsProvider for CombinedStatistics {
+    fn total_num_tokens(&self, field: Field) -> tantivy::Result<u64> {
+        self.0
+            .iter()
+            .map(|searcher| Bm25StatisticsProvider::total_num_tokens(searcher, field))
+            .sum()
+    }
+
+    fn total_num_docs(&self) -> tantivy::Result<u64> {
+        self.0.iter().map(Bm25StatisticsProvider::total_num_docs).sum()
+    }
+
+    fn doc_freq(&self, term: &Term) -> tantivy::Result<u64> {
+        self.0
+            .iter()
+            .map(|searcher| Bm25StatisticsProvider::doc_freq(searcher, term))
+            .sum()
+    }
+}
+
+/// A query scoring its matches using combined statistics.
+#[derive(Clone, Debug)]
+pub(crate) struct CombinedScoring {
+    query: Box<dyn Query>,
+    statistics: CombinedStatistics,
+}
+
+impl CombinedScoring {
+    pub(crate) fn new(query: Box<dyn Query>, statistics: CombinedStatistics) -> Self {
+        Self { query, statistics }
+    }
+}
+
+impl Query for CombinedScoring {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        if !enable_scoring.is_scoring_enabled() {
+            return self.query.weight(enable_scoring);
+        }
+        match enable_scoring.searcher() {
+            Some(searcher) => self.query.weight(EnableScoring::enabled_from_statistics_provider(
+                &self.statistics,
+                searcher,
+            )),
+            None => self.query.weight(enable_scoring),
+        }
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.query.query_terms(visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tantivy::{
+        collector::TopDocs,
+        doc,
+        query::TermQuery,
+        schema::{IndexRecordOption, Schema, TEXT},
+        Index,
+    };
+
+    fn searcher(field: Field, schema: &Schema, docs: &[&str]) -> Searcher {
+        let index = Index::create_in_ram(schema.clone());
+        let mut writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for text in docs {
+            writer.add_document(doc!(field => *text)).unwrap();
+        }
+        writer.commit().unwrap();
+        index.reader().unwrap().searcher()
+    }
+
+    fn scores(searcher: &Searcher, query: &dyn Query) -> Vec<f32> {
+        let hits = searcher.search(query, &TopDocs::with_limit(10)).unwrap();
+        hits.into_iter().map(|(score, _)| score).collect()
+    }
+
+    #[test]
+    fn same_as_single_index() {
+        let mut builder = Schema::builder();
+        let field = builder.add_text_field("text", TEXT);
+        let schema = builder.build();
+
+        let first = searcher(field, &schema, &["a b"]);
+        let second = searcher(field, &schema, &["a", "b c", "c d e", "d"]);
+        let all = searcher(field, &schema, &["a b", "a", "b c", "c d e", "d"]);
+
+        let query = TermQuery::new(Term::from_field_text(field, "b"), IndexRecordOption::WithFreqs);
+        let expected = scores(&all, &query);
+        assert_eq!(expected.len(), 2);
+
+        // the separate index would score the document differently
+        assert_ne!(scores(&first, &query)[0], expected[0]);
+
+        let combined = CombinedScoring::new(
+            Box::new(query),
+            CombinedStatistics::new(vec![first.clone(), second.clone()]),
+        );
+        let mut merged = scores(&first, &combined);
+        merged.extend(scores(&second, &combined));
+        merged.sort_by(|a, b| b.total_cmp(a));
+
+        assert_eq!(merged.len(), expected.len());
+        for (merged, expected) in merged.iter().zip(&expected) {
+            assert!((merged - expected).abs() < 1e-6, "{merged} != {expected}");
+        }
+    }
+}
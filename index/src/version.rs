@@ -0,0 +1,119 @@
+//! Versioning of the schema of an index.
+//!
+//! Each index directory records the version of the schema it was built with in a marker file, which is packed into
+//! its snapshots. Loading a snapshot whose marker doesn't match the current schema fails, keeping the index which is
+//! being served, instead of serving documents indexed for another schema. The index must then be rebuilt from the
+//! stored documents.
+//!
+//! The version is made of a number, to be increased when the way documents are indexed changes without changing the
+//! schema, and a fingerprint of the schema itself, which changes with any field.
+
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fmt, path::Path};
+use tantivy::schema::Schema;
+
+/// Name of the marker file, in the index directory.
+pub const SCHEMA_VERSION_FILE: &str = "schema-version.json";
+
+/// The version of the schema of an index.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaVersion {
+    /// Version of the indexing, see [`crate::WriteIndex::schema_version`]
+    pub version: u32,
+    /// SHA-256 digest of the serialized schema, hex encoded
+    pub fingerprint: String,
+}
+
+impl SchemaVersion {
+    pub fn new(version: u32, schema: &Schema) -> Self {
+        let schema = serde_json::to_vec(schema).unwrap_or_default();
+        Self {
+            version,
+            fingerprint: hex::encode(Sha256::digest(schema)),
+        }
+    }
+
+    /// Read the marker of an index directory, `None` if there is none, like in snapshots predating the marker.
+    pub fn read(dir: &Path) -> Result<Option<Self>, Error> {
+        match std::fs::read(dir.join(SCHEMA_VERSION_FILE)) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map(Some)
+                .map_err(|e| Error::Open(format!("invalid schema version marker: {e}"))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::Io(e)),
+        }
+    }
+
+    /// Write the marker into an index directory.
+    pub fn write(&self, dir: &Path) -> Result<(), Error> {
+        let data = serde_json::to_vec(self).map_err(|e| Error::Open(e.to_string()))?;
+        std::fs::write(dir.join(SCHEMA_VERSION_FILE), data).map_err(Error::Io)
+    }
+
+    /// Check that an index directory was built with this version.
+    ///
+    /// Directories without a marker are accepted, leaving it to tantivy to check their schema when opening them.
+    pub fn check(&self, dir: &Path) -> Result<(), Error> {
+        match Self::read(dir)? {
+            Some(found) if found != *self => Err(Error::IncompatibleSchema {
+                found: found.to_string(),
+                expected: self.to_string(),
+            }),
+            Some(_) => Ok(()),
+            None => {
+                log::warn!("Index in {} has no schema version, expecting {self}", dir.display());
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fingerprint = self.fingerprint.get(..12).unwrap_or(&self.fingerprint);
+        write!(f, "{} ({fingerprint})", self.version)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tantivy::schema::{STORED, STRING};
+
+    fn schema(fields: &[&str]) -> Schema {
+        let mut schema = Schema::builder();
+        for field in fields {
+            schema.add_text_field(field, STRING | STORED);
+        }
+        schema.build()
+    }
+
+    #[test]
+    fn fingerprint() {
+        let version = SchemaVersion::new(1, &schema(&["id"]));
+        assert_eq!(version, SchemaVersion::new(1, &schema(&["id"])));
+        assert_ne!(version, SchemaVersion::new(2, &schema(&["id"])));
+        assert_ne!(version, SchemaVersion::new(1, &schema(&["id", "name"])));
+    }
+
+    #[test]
+    fn check() {
+        use rand::RngCore;
+        let r = rand::thread_rng().next_u32();
+        let dir = std::env::temp_dir().join(format!("schema-version.{}", r));
+        std::fs::create_dir_all(&dir).unwrap();
+        let version = SchemaVersion::new(1, &schema(&["id"]));
+
+        // no marker yet
+        assert!(version.check(&dir).is_ok());
+
+        version.write(&dir).unwrap();
+        assert_eq!(SchemaVersion::read(&dir).unwrap(), Some(version.clone()));
+        assert!(version.check(&dir).is_ok());
+
+        let next = SchemaVersion::new(1, &schema(&["id", "name"]));
+        assert!(matches!(next.check(&dir), Err(Error::IncompatibleSchema { .. })));
+    }
+}
@@ -1,7 +1,27 @@
-use crate::{IndexerCommand, IndexerStatus};
+use crate::{audit::AuditReport, pause::Pause, stats::IngestionStats, IndexerCommand, IndexerStatus};
 use actix_web::{get, post, web, web::ServiceConfig, HttpResponse};
+use serde::Deserialize;
 use std::sync::Arc;
-use tokio::sync::{mpsc::Sender, Mutex};
+use time::OffsetDateTime;
+use tokio::sync::{
+    mpsc::{error::TrySendError, Sender},
+    Mutex,
+};
+use trustification_auth::{
+    authenticator::{error::AuthorizationError, user::UserInformation, Authenticator},
+    authorizer::Authorizer,
+    Permission,
+};
+use trustification_infrastructure::new_auth;
+
+/// Authorization of the administrative endpoints of an indexer.
+#[derive(Clone)]
+pub struct AdminAuth {
+    pub authenticator: Option<Arc<Authenticator>>,
+    pub authorizer: Authorizer,
+    /// Permission required to administrate the indexer
+    pub permission: Permission,
+}
 
 #[post("/reindex")]
 async fn post_command(sender: web::Data<Sender<IndexerCommand>>) -> HttpResponse {
@@ -14,25 +34,225 @@ async fn post_command(sender: web::Data<Sender<IndexerCommand>>) -> HttpResponse
 
 #[get("/reindex")]
 async fn get_status(status: web::Data<Arc<Mutex<IndexerStatus>>>) -> HttpResponse {
+    status_response(&status).await
+}
+
+async fn status_response(status: &Mutex<IndexerStatus>) -> HttpResponse {
     let status = status.lock().await.clone();
     let status = match status {
         IndexerStatus::Running => "running".to_string(),
         IndexerStatus::Reindexing { progress } => {
             format!("reindexing ({} objects)", progress)
         }
+        IndexerStatus::Auditing { progress } => {
+            format!("auditing ({} objects)", progress)
+        }
         IndexerStatus::Failed { error } => {
             format!("indexer failed: {:?}", error)
         }
+        IndexerStatus::Paused(pause) => pause.to_string(),
     };
     HttpResponse::Ok().json(serde_json::json!({
         "status": status,
     }))
 }
 
-pub fn configure(status: Arc<Mutex<IndexerStatus>>, sender: Sender<IndexerCommand>, config: &mut ServiceConfig) {
+/// Number of ingested documents by source, over the last hour, day, week and month.
+#[get("/admin/ingestion/stats")]
+async fn get_ingestion_stats(stats: web::Data<Arc<Mutex<IngestionStats>>>) -> HttpResponse {
+    let summary = stats.lock().await.summary(OffsetDateTime::now_utc());
+    HttpResponse::Ok().json(summary)
+}
+
+/// Rebuild the index from all documents in storage, without restarting the indexer.
+///
+/// The reindexing runs in the background, its progress is reported by `GET /admin/reindex`.
+async fn post_admin_reindex(
+    sender: web::Data<Sender<IndexerCommand>>,
+    status: web::Data<Arc<Mutex<IndexerStatus>>>,
+    auth: web::Data<AdminAuth>,
+    user: UserInformation,
+) -> Result<HttpResponse, AuthorizationError> {
+    auth.authorizer.require(&user, auth.permission)?;
+
+    match &*status.lock().await {
+        IndexerStatus::Reindexing { progress } => {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "status": format!("already reindexing ({} objects)", progress),
+            })));
+        }
+        IndexerStatus::Auditing { progress } => {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "status": format!("auditing ({} objects)", progress),
+            })));
+        }
+        IndexerStatus::Paused(pause) => {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "status": pause.to_string(),
+            })));
+        }
+        _ => {}
+    }
+
+    log::info!("Reindexing requested by {}", user.id().unwrap_or("anonymous"));
+    match sender.try_send(IndexerCommand::Reindex) {
+        Ok(()) => Ok(HttpResponse::Accepted().finish()),
+        // a reindexing is already queued
+        Err(TrySendError::Full(_)) => Ok(HttpResponse::Accepted().finish()),
+        Err(e) => Ok(HttpResponse::InternalServerError().body(e.to_string())),
+    }
+}
+
+async fn get_admin_reindex(
+    status: web::Data<Arc<Mutex<IndexerStatus>>>,
+    auth: web::Data<AdminAuth>,
+    user: UserInformation,
+) -> Result<HttpResponse, AuthorizationError> {
+    auth.authorizer.require(&user, auth.permission)?;
+    Ok(status_response(&status).await)
+}
+
+/// Parameters to pause requests.
+#[derive(Debug, Default, Deserialize)]
+struct PauseRequest {
+    /// Why the indexer is paused, like `storage migration`
+    reason: Option<String>,
+}
+
+/// Stop consuming events, until the indexer is resumed, even if it's restarted meanwhile.
+///
+/// The events consumed so far are committed first. While paused, the readiness of the indexer is down.
+async fn post_admin_pause(
+    sender: web::Data<Sender<IndexerCommand>>,
+    auth: web::Data<AdminAuth>,
+    user: UserInformation,
+    request: Option<web::Json<PauseRequest>>,
+) -> Result<HttpResponse, AuthorizationError> {
+    auth.authorizer.require(&user, auth.permission)?;
+
+    let pause = Pause {
+        since: OffsetDateTime::now_utc(),
+        by: user.id().map(ToString::to_string),
+        reason: request.map(|request| request.into_inner()).unwrap_or_default().reason,
+    };
+    log::info!("Pausing requested by {}", user.id().unwrap_or("anonymous"));
+    Ok(send_command(&sender, IndexerCommand::Pause(pause)))
+}
+
+/// Consume events again, after the indexer was paused.
+async fn post_admin_resume(
+    sender: web::Data<Sender<IndexerCommand>>,
+    auth: web::Data<AdminAuth>,
+    user: UserInformation,
+) -> Result<HttpResponse, AuthorizationError> {
+    auth.authorizer.require(&user, auth.permission)?;
+
+    log::info!("Resuming requested by {}", user.id().unwrap_or("anonymous"));
+    Ok(send_command(&sender, IndexerCommand::Resume))
+}
+
+/// Parameters to audit requests.
+#[derive(Debug, Default, Deserialize)]
+struct AuditRequest {
+    /// Send the events repairing the discrepancies, instead of only reporting them
+    #[serde(default)]
+    repair: bool,
+}
+
+/// Audit the consistency of the stored documents and the index.
+///
+/// The audit runs in the background, blocking the consumption of events meanwhile. Its report is returned by
+/// `GET /admin/audit` once it finished.
+async fn post_admin_audit(
+    sender: web::Data<Sender<IndexerCommand>>,
+    auth: web::Data<AdminAuth>,
+    user: UserInformation,
+    request: Option<web::Json<AuditRequest>>,
+) -> Result<HttpResponse, AuthorizationError> {
+    auth.authorizer.require(&user, auth.permission)?;
+
+    let repair = request.map(|request| request.into_inner()).unwrap_or_default().repair;
+    log::info!(
+        "Audit (repair: {repair}) requested by {}",
+        user.id().unwrap_or("anonymous")
+    );
+    Ok(send_command(&sender, IndexerCommand::Audit { repair }))
+}
+
+/// The report of the latest audit, listing the discrepancies and how to repair them.
+async fn get_admin_audit(
+    report: web::Data<Arc<Mutex<Option<AuditReport>>>>,
+    auth: web::Data<AdminAuth>,
+    user: UserInformation,
+) -> Result<HttpResponse, AuthorizationError> {
+    auth.authorizer.require(&user, auth.permission)?;
+
+    Ok(match &*report.lock().await {
+        Some(report) => HttpResponse::Ok().json(report),
+        // no audit finished since the indexer started
+        None => HttpResponse::NoContent().finish(),
+    })
+}
+
+fn send_command(sender: &Sender<IndexerCommand>, command: IndexerCommand) -> HttpResponse {
+    match sender.try_send(command) {
+        Ok(()) => HttpResponse::Accepted().finish(),
+        // like while a reindexing is running
+        Err(TrySendError::Full(_)) => HttpResponse::Conflict().json(serde_json::json!({
+            "status": "busy with a previous command, try again later",
+        })),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// Configure the endpoints of the indexer.
+///
+/// The administrative endpoints under `/admin/reindex`, `/admin/pause`, `/admin/resume` and `/admin/audit` are only
+/// registered if `admin` is provided.
+pub fn configure(
+    status: Arc<Mutex<IndexerStatus>>,
+    sender: Sender<IndexerCommand>,
+    ingestion: Arc<Mutex<IngestionStats>>,
+    audit: Arc<Mutex<Option<AuditReport>>>,
+    admin: Option<AdminAuth>,
+    config: &mut ServiceConfig,
+) {
     config
         .app_data(web::Data::new(sender))
         .app_data(web::Data::new(status))
+        .app_data(web::Data::new(ingestion))
+        .app_data(web::Data::new(audit))
         .service(post_command)
-        .service(get_status);
+        .service(get_status)
+        .service(get_ingestion_stats);
+
+    if let Some(admin) = admin {
+        let authenticator = admin.authenticator.clone();
+        config.service(
+            web::resource("/admin/reindex")
+                .app_data(web::Data::new(admin.clone()))
+                .wrap(new_auth!(authenticator.clone()))
+                .route(web::post().to(post_admin_reindex))
+                .route(web::get().to(get_admin_reindex)),
+        );
+        config.service(
+            web::resource("/admin/pause")
+                .app_data(web::Data::new(admin.clone()))
+                .wrap(new_auth!(authenticator.clone()))
+                .route(web::post().to(post_admin_pause)),
+        );
+        config.service(
+            web::resource("/admin/resume")
+                .app_data(web::Data::new(admin.clone()))
+                .wrap(new_auth!(authenticator.clone()))
+                .route(web::post().to(post_admin_resume)),
+        );
+        config.service(
+            web::resource("/admin/audit")
+                .app_data(web::Data::new(admin))
+                .wrap(new_auth!(authenticator))
+                .route(web::post().to(post_admin_audit))
+                .route(web::get().to(get_admin_audit)),
+        );
+    }
 }
@@ -0,0 +1,192 @@
+//! Audit of the consistency between the stored documents and the index.
+//!
+//! Events may get lost, like when the bus dropped them or an indexer failed to commit, leaving the index out of sync
+//! with the storage. The audit compares the stored documents with the documents of the primary index of an indexer,
+//! finding documents missing from the index, documents of the index whose stored document is gone, and documents which
+//! changed since they were indexed, if the index records their digest.
+//!
+//! The discrepancies form a repair plan. Repairing sends the events of storing or deleting the documents to the topic
+//! of stored documents, so the indexer processes them like any other event.
+
+use prometheus::{
+    opts, register_int_counter_with_registry, register_int_gauge_vec_with_registry, IntCounter, IntGaugeVec, Registry,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
+
+/// What's inconsistent about a document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiscrepancyKind {
+    /// The document is stored, but not indexed
+    Missing,
+    /// The document is indexed, but not stored anymore
+    Orphaned,
+    /// The stored document differs from the indexed one
+    Mismatched,
+}
+
+impl DiscrepancyKind {
+    pub const ALL: [Self; 3] = [Self::Missing, Self::Orphaned, Self::Mismatched];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Missing => "missing",
+            Self::Orphaned => "orphaned",
+            Self::Mismatched => "mismatched",
+        }
+    }
+
+    /// The action repairing the discrepancy.
+    pub fn repair(&self) -> RepairAction {
+        match self {
+            Self::Missing | Self::Mismatched => RepairAction::Reindex,
+            Self::Orphaned => RepairAction::Remove,
+        }
+    }
+}
+
+/// How a discrepancy is repaired.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RepairAction {
+    /// Index the stored document again
+    Reindex,
+    /// Remove the document from the index
+    Remove,
+}
+
+/// An inconsistent document, and how to repair it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Discrepancy {
+    pub key: String,
+    pub kind: DiscrepancyKind,
+    pub action: RepairAction,
+}
+
+/// The outcome of an audit.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AuditReport {
+    #[serde(with = "time::serde::rfc3339")]
+    pub started: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub finished: OffsetDateTime,
+    /// Number of stored documents
+    pub stored: usize,
+    /// Number of indexed documents
+    pub indexed: usize,
+    /// The repair plan
+    pub discrepancies: Vec<Discrepancy>,
+    /// Whether the events repairing the discrepancies were sent
+    pub repaired: bool,
+}
+
+/// Compare the digests of the stored documents with the ones of the indexed documents, by their key.
+///
+/// Indexed documents without a digest are only checked for being stored.
+pub fn compare(stored: &BTreeMap<String, String>, indexed: &BTreeMap<String, Option<String>>) -> Vec<Discrepancy> {
+    let discrepancy = |key: &str, kind: DiscrepancyKind| Discrepancy {
+        key: key.to_string(),
+        kind,
+        action: kind.repair(),
+    };
+
+    let mut result = Vec::new();
+    for (key, digest) in stored {
+        match indexed.get(key) {
+            None => result.push(discrepancy(key, DiscrepancyKind::Missing)),
+            Some(Some(indexed)) if indexed != digest => result.push(discrepancy(key, DiscrepancyKind::Mismatched)),
+            Some(_) => {}
+        }
+    }
+    result.extend(
+        indexed
+            .keys()
+            .filter(|key| !stored.contains_key(*key))
+            .map(|key| discrepancy(key, DiscrepancyKind::Orphaned)),
+    );
+    result
+}
+
+/// Keeps the report of the latest audit, and reports its outcome as metrics.
+pub struct Auditor {
+    report: Arc<Mutex<Option<AuditReport>>>,
+    discrepancies: IntGaugeVec,
+    repairs_total: IntCounter,
+}
+
+impl Auditor {
+    pub fn new(report: Arc<Mutex<Option<AuditReport>>>, registry: &Registry) -> anyhow::Result<Self> {
+        let discrepancies = register_int_gauge_vec_with_registry!(
+            opts!(
+                "indexer_audit_discrepancies",
+                "Number of inconsistent documents found by the latest audit, by kind"
+            ),
+            &["kind"],
+            registry
+        )?;
+
+        let repairs_total = register_int_counter_with_registry!(
+            opts!(
+                "indexer_audit_repairs_total",
+                "Total number of events sent to repair inconsistent documents"
+            ),
+            registry
+        )?;
+
+        Ok(Self {
+            report,
+            discrepancies,
+            repairs_total,
+        })
+    }
+
+    /// Record the report of a finished audit.
+    pub async fn record(&self, report: AuditReport) {
+        for kind in DiscrepancyKind::ALL {
+            let count = report.discrepancies.iter().filter(|d| d.kind == kind).count();
+            self.discrepancies.with_label_values(&[kind.as_str()]).set(count as i64);
+        }
+        if report.repaired {
+            self.repairs_total.inc_by(report.discrepancies.len() as u64);
+        }
+        *self.report.lock().await = Some(report);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn discrepancies() {
+        let stored = BTreeMap::from([
+            ("consistent".to_string(), "aaa".to_string()),
+            ("changed".to_string(), "bbb".to_string()),
+            ("unindexed".to_string(), "ccc".to_string()),
+            ("undigested".to_string(), "ddd".to_string()),
+        ]);
+        let indexed = BTreeMap::from([
+            ("consistent".to_string(), Some("aaa".to_string())),
+            ("changed".to_string(), Some("old".to_string())),
+            ("undigested".to_string(), None),
+            ("deleted".to_string(), Some("eee".to_string())),
+        ]);
+
+        let result = compare(&stored, &indexed);
+        assert_eq!(
+            result
+                .iter()
+                .map(|d| (d.key.as_str(), d.kind, d.action))
+                .collect::<Vec<_>>(),
+            vec![
+                ("changed", DiscrepancyKind::Mismatched, RepairAction::Reindex),
+                ("unindexed", DiscrepancyKind::Missing, RepairAction::Reindex),
+                ("deleted", DiscrepancyKind::Orphaned, RepairAction::Remove),
+            ]
+        );
+    }
+}
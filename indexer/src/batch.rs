@@ -0,0 +1,345 @@
+use prometheus::{
+    histogram_opts, opts, register_histogram_with_registry, register_int_counter_vec_with_registry,
+    register_int_counter_with_registry, register_int_gauge_with_registry, Histogram, IntCounter, IntCounterVec,
+    IntGauge, Registry,
+};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::time::Instant;
+use trustification_event_bus::Event;
+
+/// Batching of index commits.
+///
+/// The indexer commits its indexes, publishing their snapshots and acknowledging the processed events, on the index sync
+/// interval. Batching commits sooner: once enough documents were indexed, or once the first of them waited long enough.
+#[derive(Clone, Debug, clap::Args)]
+#[command(next_help_heading = "Commit batching")]
+pub struct BatchConfig {
+    /// Commit once this many documents were indexed or deleted, `0` only commits on the latency and sync interval
+    #[arg(env = "COMMIT_BATCH_SIZE", long = "commit-batch-size", default_value_t = 0)]
+    pub commit_batch_size: usize,
+
+    /// Commit once the first document indexed since the last commit waited this long, defaults to the sync interval
+    #[arg(env = "COMMIT_MAX_LATENCY", long = "commit-max-latency")]
+    pub commit_max_latency: Option<humantime::Duration>,
+
+    /// Stop consuming events while this many are waiting to be committed, like when committing fails, `0` for no limit
+    #[arg(env = "COMMIT_MAX_PENDING", long = "commit-max-pending", default_value_t = 10000)]
+    pub commit_max_pending: usize,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            commit_batch_size: 0,
+            commit_max_latency: None,
+            commit_max_pending: 10000,
+        }
+    }
+}
+
+/// What made the indexer commit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Trigger {
+    /// The batch reached its size
+    Size,
+    /// The batch reached its latency
+    Latency,
+    /// The sync interval elapsed
+    Interval,
+    /// The indexer is being paused
+    Pause,
+    /// The index is being audited
+    Audit,
+}
+
+impl Trigger {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Size => "size",
+            Self::Latency => "latency",
+            Self::Interval => "interval",
+            Self::Pause => "pause",
+            Self::Audit => "audit",
+        }
+    }
+}
+
+/// The batching applied by an indexer.
+pub struct Batching {
+    size: usize,
+    max_latency: Duration,
+    max_pending: usize,
+    metrics: Metrics,
+}
+
+impl Batching {
+    pub fn new(config: &BatchConfig, sync_interval: Duration, registry: &Registry) -> anyhow::Result<Self> {
+        Ok(Self {
+            size: config.commit_batch_size,
+            max_latency: config.commit_max_latency.map(Into::into).unwrap_or(sync_interval),
+            max_pending: config.commit_max_pending,
+            metrics: Metrics::register(registry)?,
+        })
+    }
+
+    /// Whether the batch reached its size.
+    pub(crate) fn is_complete(&self, batch: &Batch) -> bool {
+        self.size > 0 && batch.documents >= self.size
+    }
+
+    /// When the batch reaches its latency, `None` if it's empty.
+    pub(crate) fn deadline(&self, batch: &Batch) -> Option<Instant> {
+        batch.started.map(|started| started + self.max_latency)
+    }
+
+    /// Whether consuming events must pause until the batch is committed, recording when it starts to.
+    pub(crate) fn is_full(&self, batch: &mut Batch) -> bool {
+        let full = self.max_pending > 0 && batch.processed.len() >= self.max_pending;
+        if full && !batch.paused {
+            log::warn!(
+                "{} events waiting to be committed, pausing consumption",
+                batch.processed.len()
+            );
+            self.metrics.backpressure_total.inc();
+        }
+        batch.paused = full;
+        full
+    }
+
+    pub(crate) fn start_commit(&self) -> prometheus::HistogramTimer {
+        self.metrics.commit_duration_seconds.start_timer()
+    }
+
+    /// Record a successful commit of the batch, which made its indexed documents searchable.
+    pub(crate) fn committed(&self, batch: &Batch, trigger: Trigger) {
+        if batch.processed.is_empty() {
+            return;
+        }
+        self.metrics.commits_total.with_label_values(&[trigger.as_str()]).inc();
+        self.metrics.batch_documents.observe(batch.documents as f64);
+        if let Some(started) = batch.started {
+            self.metrics
+                .batch_latency_seconds
+                .observe(started.elapsed().as_secs_f64());
+        }
+        let now = OffsetDateTime::now_utc();
+        for stored in &batch.stored {
+            self.metrics
+                .ingestion_latency_seconds
+                .observe(ingestion_latency(*stored, now));
+        }
+    }
+
+    /// Record the number of events waiting to be committed.
+    pub(crate) fn pending(&self, batch: &Batch) {
+        self.metrics.pending_events.set(batch.processed.len() as i64);
+    }
+}
+
+/// The events processed since the last commit.
+pub(crate) struct Batch<'c> {
+    pub(crate) processed: Vec<Event<'c>>,
+    /// Payloads of the events of the indexed documents
+    pub(crate) indexed: Vec<Vec<u8>>,
+    /// Payloads of the events of the deleted documents
+    pub(crate) deleted: Vec<Vec<u8>>,
+    /// Number of documents indexed or deleted
+    pub(crate) documents: usize,
+    /// When the indexed documents were stored, as reported by their events
+    pub(crate) stored: Vec<OffsetDateTime>,
+    /// When the first event of the batch was processed
+    started: Option<Instant>,
+    paused: bool,
+}
+
+impl<'c> Batch<'c> {
+    pub(crate) fn new() -> Self {
+        Self {
+            processed: Vec::new(),
+            indexed: Vec::new(),
+            deleted: Vec::new(),
+            documents: 0,
+            stored: Vec::new(),
+            started: None,
+            paused: false,
+        }
+    }
+
+    pub(crate) fn push(&mut self, event: Event<'c>) {
+        self.started.get_or_insert_with(Instant::now);
+        self.processed.push(event);
+    }
+
+    /// Start over, once the batch was committed.
+    pub(crate) fn clear(&mut self) {
+        self.processed.clear();
+        self.indexed.clear();
+        self.deleted.clear();
+        self.documents = 0;
+        self.stored.clear();
+        self.started = None;
+    }
+
+    /// Wait for the latency again before the next commit, once committing the batch failed.
+    pub(crate) fn retry_later(&mut self) {
+        if self.started.is_some() {
+            self.started = Some(Instant::now());
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Metrics {
+    commits_total: IntCounterVec,
+    batch_documents: Histogram,
+    batch_latency_seconds: Histogram,
+    commit_duration_seconds: Histogram,
+    pending_events: IntGauge,
+    backpressure_total: IntCounter,
+    ingestion_latency_seconds: Histogram,
+}
+
+impl Metrics {
+    fn register(registry: &Registry) -> anyhow::Result<Self> {
+        let commits_total = register_int_counter_vec_with_registry!(
+            opts!(
+                "indexer_commits_total",
+                "Total number of commits, by what triggered them"
+            ),
+            &["trigger"],
+            registry
+        )?;
+
+        let batch_documents = register_histogram_with_registry!(
+            histogram_opts!(
+                "indexer_commit_batch_documents",
+                "Number of documents indexed or deleted per commit",
+                vec![1.0, 10.0, 50.0, 100.0, 500.0, 1000.0, 5000.0, 10000.0]
+            ),
+            registry
+        )?;
+
+        let batch_latency_seconds = register_histogram_with_registry!(
+            histogram_opts!(
+                "indexer_commit_batch_latency_seconds",
+                "Time from processing the first event of a batch to committing it",
+                vec![0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0, 1800.0]
+            ),
+            registry
+        )?;
+
+        let commit_duration_seconds = register_histogram_with_registry!(
+            histogram_opts!(
+                "indexer_commit_duration_seconds",
+                "Duration of publishing the index snapshots and committing the events",
+                vec![0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0]
+            ),
+            registry
+        )?;
+
+        let pending_events = register_int_gauge_with_registry!(
+            opts!(
+                "indexer_pending_events",
+                "Number of processed events waiting to be committed"
+            ),
+            registry
+        )?;
+
+        let backpressure_total = register_int_counter_with_registry!(
+            opts!(
+                "indexer_backpressure_total",
+                "Total number of times consuming events paused, as too many were waiting to be committed"
+            ),
+            registry
+        )?;
+
+        let ingestion_latency_seconds = register_histogram_with_registry!(
+            histogram_opts!(
+                "indexer_ingestion_latency_seconds",
+                "Time from storing a document to committing the index containing it, making it searchable",
+                vec![1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0, 7200.0]
+            ),
+            registry
+        )?;
+
+        Ok(Self {
+            commits_total,
+            batch_documents,
+            batch_latency_seconds,
+            commit_duration_seconds,
+            pending_events,
+            backpressure_total,
+            ingestion_latency_seconds,
+        })
+    }
+}
+
+/// Seconds from storing a document to committing it, `0` if the clocks of the storage and the indexer disagree.
+fn ingestion_latency(stored: OffsetDateTime, committed: OffsetDateTime) -> f64 {
+    (committed - stored).as_seconds_f64().max(0.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn batching(size: usize, max_pending: usize) -> Batching {
+        let config = BatchConfig {
+            commit_batch_size: size,
+            commit_max_latency: Some(Duration::from_secs(5).into()),
+            commit_max_pending: max_pending,
+        };
+        Batching::new(&config, Duration::from_secs(30), &Registry::new()).unwrap()
+    }
+
+    #[test]
+    fn test_size() {
+        let batching = batching(2, 0);
+        let mut batch = Batch::new();
+        assert!(!batching.is_complete(&batch));
+        batch.documents = 2;
+        assert!(batching.is_complete(&batch));
+
+        // only commits on the latency and interval
+        assert!(!batching(0, 0).is_complete(&batch));
+        assert!(!batching.is_full(&mut batch));
+    }
+
+    #[test]
+    fn test_deadline() {
+        let batching = batching(0, 0);
+        let mut batch = Batch::new();
+        assert_eq!(batching.deadline(&batch), None);
+
+        batch.started = Some(Instant::now());
+        let deadline = batching.deadline(&batch).unwrap();
+        assert!(deadline > Instant::now());
+
+        std::thread::sleep(Duration::from_millis(10));
+        batch.retry_later();
+        assert!(batching.deadline(&batch).unwrap() > deadline);
+
+        batch.clear();
+        assert_eq!(batching.deadline(&batch), None);
+    }
+
+    #[test]
+    fn test_default_latency() {
+        let config = BatchConfig {
+            commit_batch_size: 0,
+            commit_max_latency: None,
+            commit_max_pending: 0,
+        };
+        let batching = Batching::new(&config, Duration::from_secs(30), &Registry::new()).unwrap();
+        assert_eq!(batching.max_latency, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_ingestion_latency() {
+        let stored = OffsetDateTime::now_utc();
+        assert_eq!(ingestion_latency(stored, stored + Duration::from_secs(90)), 90.0);
+        // the clock of the storage is ahead
+        assert_eq!(ingestion_latency(stored + Duration::from_secs(1), stored), 0.0);
+    }
+}
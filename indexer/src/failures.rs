@@ -0,0 +1,242 @@
+//! Inspecting and retrying the documents which failed to be indexed.
+//!
+//! The indexers publish a [`Failure`] to their failed topic for each document they fail to index. The failures are
+//! read from the topic like a dead letter queue: listing them doesn't acknowledge them, while retrying them publishes an
+//! event of storing the document again to the stored topic, which makes the indexer process it again.
+
+use prometheus::Registry;
+use std::{process::ExitCode, time::Duration};
+use trustification_event_bus::{Event, EventBus, EventBusConfig, EventConsumer};
+use trustification_storage::{Storage, StorageConfig};
+
+/// Consumer group reading the failed topic, which only advances when failures are retried.
+const GROUP: &str = "indexer-failures";
+
+/// A document the indexer failed to index, as published to the failed topic.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Failure {
+    /// Key of the document
+    pub key: String,
+    /// Why the document couldn't be indexed, like a parse error
+    pub error: String,
+}
+
+/// Outcome of retrying failures.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Retried {
+    /// Failures published to the stored topic
+    pub retried: Vec<Failure>,
+    /// Failures which weren't selected, published to the failed topic again
+    pub kept: usize,
+}
+
+/// The failures of an indexer.
+pub struct Failures<'a> {
+    bus: &'a EventBus,
+    failed_topic: &'a str,
+    wait: Duration,
+}
+
+impl<'a> Failures<'a> {
+    /// Read the failures of `failed_topic`, until no failure arrived for `wait`.
+    pub fn new(bus: &'a EventBus, failed_topic: &'a str, wait: Duration) -> Self {
+        Self {
+            bus,
+            failed_topic,
+            wait,
+        }
+    }
+
+    /// List the pending failures, without acknowledging them.
+    pub async fn list(&self) -> anyhow::Result<Vec<Failure>> {
+        let consumer = self.bus.subscribe(GROUP, &[self.failed_topic]).await?;
+        let events = self.read(&consumer).await?;
+        Ok(events.iter().filter_map(|event| decode(event.payload())).collect())
+    }
+
+    /// Retry the pending failures matching `select`, by publishing events of storing their documents again to
+    /// `stored_topic`.
+    ///
+    /// All pending failures are acknowledged: the ones which weren't selected are published to the failed topic again,
+    /// so that they can still be retried later.
+    pub async fn retry(
+        &self,
+        storage: &Storage,
+        stored_topic: &str,
+        select: impl Fn(&Failure) -> bool,
+    ) -> anyhow::Result<Retried> {
+        let consumer = self.bus.subscribe(GROUP, &[self.failed_topic]).await?;
+        let events = self.read(&consumer).await?;
+
+        let mut retried = Retried::default();
+        let mut messages = Vec::new();
+        for event in &events {
+            match decode(event.payload()) {
+                Some(failure) if select(&failure) => {
+                    let event = serde_json::to_vec(&storage.put_event(&failure.key))?;
+                    messages.push((stored_topic, event));
+                    retried.retried.push(failure);
+                }
+                _ => {
+                    let payload = event.payload().unwrap_or_default().to_vec();
+                    messages.push((self.failed_topic, payload));
+                    retried.kept += 1;
+                }
+            }
+        }
+
+        let messages: Vec<(&str, &[u8])> = messages.iter().map(|(topic, data)| (*topic, &data[..])).collect();
+        self.bus.send_and_commit(&consumer, &messages, &events).await?;
+        Ok(retried)
+    }
+
+    /// Read the events of the failed topic, until none arrived for the wait duration.
+    async fn read<'c>(&self, consumer: &'c EventConsumer) -> anyhow::Result<Vec<Event<'c>>> {
+        let mut events = Vec::new();
+        while let Ok(next) = tokio::time::timeout(self.wait, consumer.next()).await {
+            if let Some(event) = next? {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+}
+
+fn decode(payload: Option<&[u8]>) -> Option<Failure> {
+    match serde_json::from_slice(payload?) {
+        Ok(failure) => Some(failure),
+        Err(e) => {
+            log::warn!("Ignoring undecodable failure: {e}");
+            None
+        }
+    }
+}
+
+/// Inspect and retry the documents the indexer failed to index
+#[derive(clap::Subcommand, Debug)]
+pub enum FailuresCommand {
+    List(ListFailures),
+    Retry(RetryFailures),
+}
+
+/// The topics of an indexer, used when they aren't provided.
+pub struct Topics<'a> {
+    pub stored: &'a str,
+    pub failed: &'a str,
+}
+
+impl FailuresCommand {
+    /// Run the command, for the documents of the storage `bucket`.
+    pub async fn run(self, bucket: &str, topics: Topics<'_>) -> anyhow::Result<ExitCode> {
+        match self {
+            Self::List(run) => run.run(topics).await,
+            Self::Retry(run) => run.run(bucket, topics).await,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub struct FailuresConfig {
+    /// Topic the indexer publishes its failures to, defaults to the failed topic of the indexer
+    #[arg(long = "failed-topic")]
+    pub failed_topic: Option<String>,
+
+    /// How long to wait for further failures, before considering all of them read
+    #[arg(long = "wait", default_value = "10s")]
+    pub wait: humantime::Duration,
+
+    #[command(flatten)]
+    pub bus: EventBusConfig,
+}
+
+impl FailuresConfig {
+    async fn bus(&self) -> anyhow::Result<EventBus> {
+        self.bus.create(&Registry::new()).await
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[command(about = "List the documents which failed to be indexed")]
+pub struct ListFailures {
+    #[command(flatten)]
+    pub config: FailuresConfig,
+}
+
+impl ListFailures {
+    async fn run(self, topics: Topics<'_>) -> anyhow::Result<ExitCode> {
+        let bus = self.config.bus().await?;
+        let failed_topic = self.config.failed_topic.as_deref().unwrap_or(topics.failed);
+        let failures = Failures::new(&bus, failed_topic, self.config.wait.into())
+            .list()
+            .await?;
+
+        for failure in &failures {
+            println!("{}\t{}", failure.key, failure.error);
+        }
+        println!("{} failures", failures.len());
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[command(about = "Index the documents which failed to be indexed again")]
+pub struct RetryFailures {
+    /// Key of a document to retry, may be repeated
+    #[arg(long = "key", required_unless_present = "all")]
+    pub keys: Vec<String>,
+
+    /// Retry all failures
+    #[arg(long = "all", conflicts_with = "keys", default_value_t = false)]
+    pub all: bool,
+
+    /// Topic the indexer reads the events of stored documents from, defaults to the stored topic of the indexer
+    #[arg(long = "stored-topic")]
+    pub stored_topic: Option<String>,
+
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    #[command(flatten)]
+    pub config: FailuresConfig,
+
+    #[command(flatten)]
+    pub storage: StorageConfig,
+}
+
+impl RetryFailures {
+    async fn run(self, bucket: &str, topics: Topics<'_>) -> anyhow::Result<ExitCode> {
+        let bus = self.config.bus().await?;
+        let storage = Storage::new(self.storage.process(bucket, self.devmode), &Registry::new())?;
+        let failed_topic = self.config.failed_topic.as_deref().unwrap_or(topics.failed);
+        let stored_topic = self.stored_topic.as_deref().unwrap_or(topics.stored);
+
+        let retried = Failures::new(&bus, failed_topic, self.config.wait.into())
+            .retry(&storage, stored_topic, |failure| {
+                self.all || self.keys.iter().any(|key| *key == failure.key)
+            })
+            .await?;
+
+        for failure in &retried.retried {
+            println!("Retrying {}", failure.key);
+        }
+        println!("{} failures retried, {} kept", retried.retried.len(), retried.kept);
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let failure = Failure {
+            key: "mysbom".to_string(),
+            error: "Error parsing document".to_string(),
+        };
+        let payload = serde_json::to_vec(&failure).unwrap();
+        assert_eq!(decode(Some(&payload)), Some(failure));
+        assert_eq!(decode(Some(b"not json")), None);
+        assert_eq!(decode(None), None);
+    }
+}
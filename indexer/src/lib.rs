@@ -1,4 +1,5 @@
 use core::fmt;
+use std::collections::BTreeMap;
 use std::time::Duration;
 
 use futures::pin_mut;
@@ -7,25 +8,49 @@ use std::sync::Arc;
 use tokio::sync::mpsc::Receiver;
 use tokio::sync::mpsc::Sender;
 use tokio::task::block_in_place;
-use tokio::time::Instant;
+use tokio::time::{sleep_until, Instant};
 use tokio::{select, sync::Mutex};
-use trustification_event_bus::{Error as BusError, EventBus};
+use trustification_event_bus::{Error as BusError, EventBus, EventConsumer};
 use trustification_index::{IndexStore, IndexWriter, WriteIndex};
-use trustification_infrastructure::health::checks::FailureRateHandle;
+use trustification_infrastructure::health::checks::{FailureRateHandle, Probe};
 use trustification_storage::ContinuationToken;
-use trustification_storage::{EventType, Storage};
+use trustification_storage::{EventType, Provenance, Storage};
+
+use crate::audit::{AuditReport, Auditor, RepairAction};
+use crate::batch::{Batch, Batching, Trigger};
+use crate::failures::Failure;
+use crate::pause::{Pause, PAUSE_NAME};
+use crate::stats::{IngestionStats, STATS_NAME};
+use time::OffsetDateTime;
 
 pub mod actix;
+pub mod audit;
+pub mod batch;
+pub mod failures;
+pub mod pause;
+pub mod stats;
+
+/// How often documents due to be archived are moved to the archive storage class, if archiving is configured.
+const ARCHIVE_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
 #[derive(Clone, Debug)]
 pub enum IndexerStatus {
     Running,
     Reindexing { progress: usize },
+    Auditing { progress: usize },
     Failed { error: String },
+    Paused(Pause),
 }
 
 pub enum IndexerCommand {
     Reindex,
+    /// Stop consuming events, once the events consumed so far are committed
+    Pause(Pause),
+    Resume,
+    /// Audit the consistency of the stored documents and the index, repairing the discrepancies if requested
+    Audit {
+        repair: bool,
+    },
 }
 
 #[derive(clap::ValueEnum, Default, Clone, Debug, PartialEq)]
@@ -53,6 +78,8 @@ pub struct Indexer<'a, DOC> {
     pub stored_topic: &'a str,
     pub indexed_topic: &'a str,
     pub failed_topic: &'a str,
+    /// Topic to send the events of deleted documents to, once they are removed from the index
+    pub deleted_topic: Option<&'a str>,
     pub sync_interval: Duration,
     pub indexes: Vec<IndexStore<Box<dyn WriteIndex<Document = DOC>>>>,
     pub storage: Storage,
@@ -62,6 +89,14 @@ pub struct Indexer<'a, DOC> {
     pub command_sender: Sender<IndexerCommand>,
     pub reindex: ReindexMode,
     pub state: FailureRateHandle,
+    /// Statistics of the ingested documents, persisted along with the index
+    pub ingestion: Arc<Mutex<IngestionStats>>,
+    /// When to commit the indexes, besides the sync interval
+    pub batching: Batching,
+    /// Readiness of the indexer, down while it's paused
+    pub ready: Probe,
+    /// Keeps the report of the latest audit, which compares the storage with the first of the indexes
+    pub audit: Auditor,
 }
 
 impl<'a, DOC> Indexer<'a, DOC>
@@ -88,46 +123,97 @@ where
             }
         }
 
+        self.load_ingestion_stats().await;
+        let mut paused = self.load_pause().await;
+
         let mut interval = tokio::time::interval(self.sync_interval);
+        let mut archive_interval = tokio::time::interval(ARCHIVE_INTERVAL);
         let mut writers = Vec::new();
         for index in &mut self.indexes {
             writers.push(block_in_place(|| index.writer())?);
         }
         let consumer = self.bus.subscribe("indexer", &[self.stored_topic]).await?;
-        let mut processed_events = Vec::new();
-        let mut indexed_events = Vec::new();
-        let mut events = 0;
+        let mut batch = Batch::new();
 
-        *self.status.lock().await = IndexerStatus::Running;
+        self.set_status(paused.as_ref()).await;
         loop {
             let tick = interval.tick();
             pin_mut!(tick);
+            let deadline = self.batching.deadline(&batch);
+            let full = self.batching.is_full(&mut batch);
             select! {
-                command = self.commands.recv() => {
-                    if let Some(IndexerCommand::Reindex) = command {
+                command = self.commands.recv() => match command {
+                    Some(IndexerCommand::Reindex) if paused.is_some() => {
+                        log::warn!("Not reindexing, as the indexer is paused");
+                    }
+                    Some(IndexerCommand::Reindex) => {
                         self.handle_reindex(&mut writers).await?;
                     }
-                }
-                event = consumer.next() => match event {
+                    Some(IndexerCommand::Pause(pause)) => {
+                        // nothing is left pending while paused
+                        self.commit(&mut writers, &consumer, &mut batch, Trigger::Pause).await?;
+                        log::info!("Indexer {pause}");
+                        self.store_pause(Some(&pause)).await;
+                        self.set_status(Some(&pause)).await;
+                        paused = Some(pause);
+                    }
+                    Some(IndexerCommand::Resume) => {
+                        if paused.take().is_some() {
+                            log::info!("Indexer resumed");
+                            self.store_pause(None).await;
+                            self.set_status(None).await;
+                        }
+                    }
+                    Some(IndexerCommand::Audit { repair }) => {
+                        // the index has to include the events consumed so far
+                        self.commit(&mut writers, &consumer, &mut batch, Trigger::Audit).await?;
+                        self.handle_audit(repair).await;
+                        self.set_status(paused.as_ref()).await;
+                    }
+                    None => {}
+                },
+                event = consumer.next(), if !full && paused.is_none() => match event {
                     Ok(Some(event)) => {
                         if let Some(payload) = event.payload() {
                             if let Ok(data) = self.storage.decode_event(payload) {
                                 log::debug!("Received {} records", data.records.len());
                                 let mut indexed = 0;
+                                let mut deleted = 0;
                                 for data in data.records {
                                     if self.storage.is_index(data.key()) {
                                         log::trace!("It's an index event, ignoring");
+                                    } else if self.storage.is_original(data.key()) {
+                                        log::trace!("It's an original document event, ignoring");
+                                    } else if self.storage.is_walker_run(data.key()) {
+                                        log::trace!("It's a walker run event, ignoring");
+                                    } else if self.storage.is_stats(data.key()) {
+                                        log::trace!("It's a statistics event, ignoring");
+                                    } else if self.storage.is_upload(data.key()) {
+                                        log::trace!("It's an upload event, ignoring");
+                                    } else if self.storage.is_tombstone(data.key()) {
+                                        log::trace!("It's a tombstone event, ignoring");
+                                    } else if self.storage.is_revision(data.key()) {
+                                        log::trace!("It's a prior revision event, ignoring");
+                                    } else if self.storage.is_conflict(data.key()) {
+                                        log::trace!("It's a conflict event, ignoring");
+                                    } else if self.storage.is_blob(data.key()) {
+                                        log::trace!("It's a content addressed blob event, ignoring");
                                     } else {
                                         match data.event_type() {
                                             EventType::Put => {
                                                 match self.storage.get_for_event(&data, true).await {
                                                     Ok(res) => {
+                                                        let source = res.provenance.as_ref().map(|p| p.source.as_str());
                                                         for (index, writer) in self.indexes.iter().zip(writers.iter_mut()) {
-                                                            if let Err(e) = self.index_doc(index.index(), writer, &res.key, &res.data).await {
+                                                            if let Err(e) = self.index_doc(index.index(), writer, &res.key, &res.data, res.provenance.as_ref(), res.archived).await {
                                                                 log::warn!("(Ignored) Internal error when indexing {}: {:?}", res.key, e);
                                                             }
                                                         }
-                                                        events += 1;
+                                                        self.ingestion.lock().await.record(source, OffsetDateTime::now_utc());
+                                                        if let Some(stored) = data.event_time() {
+                                                            batch.stored.push(stored);
+                                                        }
+                                                        batch.documents += 1;
                                                         indexed += 1;
                                                     }
                                                     Err(e) => {
@@ -141,7 +227,8 @@ where
                                                     block_in_place(|| writer.delete_document(index.index(), key.as_str()));
                                                 }
                                                 log::info!("Deleted entry '{key}' from index");
-                                                events += 1;
+                                                batch.documents += 1;
+                                                deleted += 1;
                                             }
                                             _ => log::debug!("Non (PUT | DELETE)  event ({:?}), skipping", data),
                                         }
@@ -149,7 +236,12 @@ where
                                 }
                                 if indexed > 0 {
                                     if let Some(payload) = event.payload() {
-                                        indexed_events.push(payload.to_vec());
+                                        batch.indexed.push(payload.to_vec());
+                                    }
+                                }
+                                if deleted > 0 {
+                                    if let (Some(payload), Some(_)) = (event.payload(), self.deleted_topic) {
+                                        batch.deleted.push(payload.to_vec());
                                     }
                                 }
                             } else {
@@ -158,7 +250,11 @@ where
                         } else {
                             log::warn!("No event for payload, skipping");
                         }
-                        processed_events.push(event);
+                        batch.push(event);
+                        self.batching.pending(&batch);
+                        if self.batching.is_complete(&batch) {
+                            self.commit(&mut writers, &consumer, &mut batch, Trigger::Size).await?;
+                        }
                     }
                     Ok(None) => {
                         log::debug!("Polling returned no events, retrying");
@@ -171,51 +267,171 @@ where
                         log::warn!("Error polling for event: {:?}", e);
                     }
                 },
+                _ = archive_interval.tick(), if paused.is_none() => {
+                    match self.storage.archive().await {
+                        Ok(0) => {}
+                        Ok(archived) => log::info!("Archived {archived} documents"),
+                        Err(e) => log::warn!("(Ignored) Error archiving documents: {:?}", e),
+                    }
+                }
+                _ = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    self.commit(&mut writers, &consumer, &mut batch, Trigger::Latency).await?;
+                }
                 _ = tick => {
-                    log::trace!("{} new events added, pushing new index to storage", events);
-                    let mut result = Ok(());
-                    for (index, writer) in self.indexes.iter_mut().zip(writers.drain(..)) {
-                        if let Err(e) = index.snapshot(writer, &self.storage, events > 0).await {
-                            result = Err(e);
-                            break;
-                        }
+                    self.commit(&mut writers, &consumer, &mut batch, Trigger::Interval).await?;
+                }
+            }
+        }
+    }
+
+    /// Publish the snapshots of the indexes and commit the processed events of the batch, sending the events of the
+    /// indexed and deleted documents along. If either fails, the batch is kept and committed again later.
+    async fn commit<'c>(
+        &mut self,
+        writers: &mut Vec<IndexWriter>,
+        consumer: &'c EventConsumer,
+        batch: &mut Batch<'c>,
+        trigger: Trigger,
+    ) -> anyhow::Result<()> {
+        log::trace!(
+            "{} new events added, pushing new index to storage ({:?})",
+            batch.documents,
+            trigger
+        );
+        let timer = self.batching.start_commit();
+        let mut result = Ok(());
+        for (index, writer) in self.indexes.iter_mut().zip(writers.drain(..)) {
+            if let Err(e) = index.snapshot(writer, &self.storage, batch.documents > 0).await {
+                result = Err(e);
+                break;
+            }
+        }
+
+        match result {
+            Ok(_) => {
+                log::trace!("Index updated successfully");
+                // events of indexed and deleted documents are sent along with committing the processed events
+                let mut messages: Vec<(&str, &[u8])> = batch
+                    .indexed
+                    .iter()
+                    .map(|payload| (self.indexed_topic, &payload[..]))
+                    .collect();
+                if let Some(deleted_topic) = self.deleted_topic {
+                    messages.extend(batch.deleted.iter().map(|payload| (deleted_topic, &payload[..])));
+                }
+                match self
+                    .bus
+                    .send_and_commit(consumer, &messages[..], &batch.processed[..])
+                    .await
+                {
+                    Ok(_) => {
+                        log::trace!("Event committed successfully");
+                        self.batching.committed(batch, trigger);
+                        batch.clear();
                     }
+                    Err(e) => {
+                        // the events are processed again if they aren't committed, keep them for the next commit
+                        self.state.increment();
+                        log::warn!("Error committing event: {:?}", e);
+                        batch.retry_later();
+                    }
+                }
+            }
+            Err(e) => {
+                self.state.increment();
+                log::warn!("Error taking index snapshot: {:?}", e);
+                batch.retry_later();
+            }
+        }
+        timer.observe_duration();
+        self.batching.pending(batch);
 
-                    match result {
-                        Ok(_) => {
-                            log::trace!("Index updated successfully");
-                            match consumer.commit(&processed_events[..]).await {
-                                Ok(_) => {
-                                    log::trace!("Event committed successfully");
-                                }
-                                Err(e) => {
-                                    log::warn!("Error committing event: {:?}", e)
-                                }
-                            }
-                            processed_events.clear();
-                            events = 0;
+        for index in self.indexes.iter_mut() {
+            writers.push(block_in_place(|| index.writer())?);
+        }
 
-                            for payload in indexed_events.drain(..) {
-                                // Filter events not related to documents
-                                if let Err(e) = self.bus.send(self.indexed_topic, &payload).await {
-                                    log::warn!("(Ignored) Error sending event to indexed topic {}: {:?}", self.indexed_topic, e);
-                                }
-                            }
+        self.store_ingestion_stats().await;
+        Ok(())
+    }
 
-                        }
-                        Err(e) => {
-                            self.state.increment();
-                            log::warn!("Error taking index snapshot: {:?}", e);
-                        }
-                    }
-                    for index in self.indexes.iter_mut() {
-                        writers.push(block_in_place(|| index.writer())?);
+    /// The pause persisted by a previous run, if the indexer wasn't resumed since.
+    async fn load_pause(&self) -> Option<Pause> {
+        match self.storage.get_stats(PAUSE_NAME).await {
+            Ok(Some(data)) => match pause::parse(&data) {
+                Ok(pause) => {
+                    if let Some(pause) = &pause {
+                        log::warn!("Indexer {pause}, waiting to be resumed");
                     }
+                    pause
+                }
+                Err(e) => {
+                    log::warn!("(Ignored) Invalid pause: {:?}", e);
+                    None
                 }
+            },
+            Ok(None) => None,
+            Err(e) => {
+                log::warn!("(Ignored) Error loading pause: {:?}", e);
+                None
             }
         }
     }
 
+    async fn store_pause(&self, pause: Option<&Pause>) {
+        let result = match serde_json::to_vec(&pause) {
+            Ok(data) => self
+                .storage
+                .put_stats(PAUSE_NAME, &data)
+                .await
+                .map_err(anyhow::Error::from),
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            log::warn!("(Ignored) Error storing pause, it won't survive a restart: {:?}", e);
+        }
+    }
+
+    async fn set_status(&self, pause: Option<&Pause>) {
+        *self.status.lock().await = match pause {
+            Some(pause) => IndexerStatus::Paused(pause.clone()),
+            None => IndexerStatus::Running,
+        };
+        self.ready.set(pause.is_none());
+    }
+
+    /// Add the statistics persisted by a previous run.
+    async fn load_ingestion_stats(&self) {
+        match self.storage.get_stats(STATS_NAME).await {
+            Ok(Some(data)) => match serde_json::from_slice(&data) {
+                Ok(stats) => self.ingestion.lock().await.merge(stats),
+                Err(e) => log::warn!("(Ignored) Invalid ingestion statistics: {:?}", e),
+            },
+            Ok(None) => {}
+            Err(e) => log::warn!("(Ignored) Error loading ingestion statistics: {:?}", e),
+        }
+    }
+
+    async fn store_ingestion_stats(&self) {
+        let data = {
+            let mut stats = self.ingestion.lock().await;
+            match stats.take_dirty(OffsetDateTime::now_utc()) {
+                Some(stats) => serde_json::to_vec(stats),
+                None => return,
+            }
+        };
+        let result = match data {
+            Ok(data) => self
+                .storage
+                .put_stats(STATS_NAME, &data)
+                .await
+                .map_err(anyhow::Error::from),
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            log::warn!("(Ignored) Error storing ingestion statistics: {:?}", e);
+        }
+    }
+
     async fn handle_reindex(&mut self, writers: &mut Vec<IndexWriter>) -> anyhow::Result<()> {
         log::info!("Reindexing all documents");
 
@@ -271,6 +487,63 @@ where
         Ok(())
     }
 
+    async fn handle_audit(&self, repair: bool) {
+        log::info!("Auditing the consistency of storage and index (repair: {repair})");
+        let started = OffsetDateTime::now_utc();
+        match self.audit(started, repair).await {
+            Ok(report) => {
+                log::info!(
+                    "Audit finished, {} stored and {} indexed documents, {} discrepancies",
+                    report.stored,
+                    report.indexed,
+                    report.discrepancies.len()
+                );
+                self.audit.record(report).await;
+            }
+            Err(e) => log::warn!("Audit failed: {:?}", e),
+        }
+    }
+
+    async fn audit(&self, started: OffsetDateTime, repair: bool) -> anyhow::Result<AuditReport> {
+        let index = self
+            .indexes
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("The indexer has no index to audit"))?;
+        // the index doesn't change while auditing, as no events are consumed meanwhile
+        let indexed = block_in_place(|| index.document_digests())?;
+
+        let mut stored = BTreeMap::new();
+        let objects = self.storage.list_objects_from(ContinuationToken::default());
+        pin_mut!(objects);
+        while let Some(next) = objects.next().await {
+            let (path, data) = next.map_err(|(e, _)| e)?;
+            stored.insert(path.key().to_string(), sha256::digest(data.as_slice()));
+            *self.status.lock().await = IndexerStatus::Auditing { progress: stored.len() };
+        }
+
+        let discrepancies = audit::compare(&stored, &indexed);
+        let repaired = repair && !discrepancies.is_empty();
+        if repaired {
+            for discrepancy in &discrepancies {
+                let event = match discrepancy.action {
+                    RepairAction::Reindex => self.storage.put_event(&discrepancy.key),
+                    RepairAction::Remove => self.storage.delete_event(&discrepancy.key),
+                };
+                self.bus.send(self.stored_topic, &serde_json::to_vec(&event)?).await?;
+            }
+            log::info!("Sent events repairing {} documents", discrepancies.len());
+        }
+
+        Ok(AuditReport {
+            started,
+            finished: OffsetDateTime::now_utc(),
+            stored: stored.len(),
+            indexed: indexed.len(),
+            discrepancies,
+            repaired,
+        })
+    }
+
     async fn reindex(
         &mut self,
         writers: &mut Vec<IndexWriter>,
@@ -292,9 +565,16 @@ where
                         Some(Ok((path, obj))) => {
                             let key = path.key();
                             log::info!("Reindexing {:?}", key);
+                            let (provenance, archived) = match self.storage.get_head(path.clone()).await {
+                                Ok(head) => (head.provenance, head.archived),
+                                Err(e) => {
+                                    log::warn!("(Ignored) Unable to read provenance of {}: {:?}", key, e);
+                                    (None, false)
+                                }
+                            };
                             // Not sending notifications for reindexing
                             for (index, writer) in self.indexes.iter().zip(writers.iter_mut()) {
-                                if let Err(e) = self.index_doc(index.index(), writer, &key, &obj).await {
+                                if let Err(e) = self.index_doc(index.index(), writer, &key, &obj, provenance.as_ref(), archived).await {
                                     log::warn!("(Ignored) Internal error when indexing {}: {:?}", key, e);
                                 }
                             }
@@ -338,18 +618,21 @@ where
         writer: &mut IndexWriter,
         key: &str,
         data: &[u8],
+        provenance: Option<&Provenance>,
+        archived: bool,
     ) -> Result<(), anyhow::Error> {
-        match block_in_place(|| writer.add_document(index, key, data)) {
+        let source = provenance.map(|p| p.source.as_str());
+        let labels = provenance.map(Provenance::labels).unwrap_or_default();
+        match block_in_place(|| writer.add_stored_document(index, key, data, source, archived, &labels)) {
             Ok(_) => {
                 log::debug!("Inserted entry '{key}' into index");
             }
             Err(e) => {
-                let failure = serde_json::json!( {
-                    "key": key,
-                    "error": e.to_string(),
-                })
-                .to_string();
-                self.bus.send(self.failed_topic, failure.as_bytes()).await?;
+                let failure = serde_json::to_vec(&Failure {
+                    key: key.to_string(),
+                    error: e.to_string(),
+                })?;
+                self.bus.send(self.failed_topic, &failure).await?;
             }
         }
         Ok(())
@@ -0,0 +1,68 @@
+//! Pausing the consumption of events.
+//!
+//! During maintenance, like migrating the storage, operators pause the indexer instead of scaling it down. The pause
+//! is persisted to storage, next to the ingestion statistics, so that a restarted indexer stays paused until it's
+//! resumed.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+/// Name of the stored pause.
+pub const PAUSE_NAME: &str = "pause.json";
+
+/// Why and since when the indexer is paused.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pause {
+    /// When the indexer was paused
+    #[serde(with = "time::serde::rfc3339")]
+    pub since: OffsetDateTime,
+    /// Who paused the indexer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub by: Option<String>,
+    /// Why the indexer was paused
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl fmt::Display for Pause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let since = self.since.format(&Rfc3339).map_err(|_| fmt::Error)?;
+        write!(f, "paused since {since}")?;
+        if let Some(by) = &self.by {
+            write!(f, " by {by}")?;
+        }
+        if let Some(reason) = &self.reason {
+            write!(f, ": {reason}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a stored pause, `None` if the indexer was resumed.
+pub fn parse(data: &[u8]) -> serde_json::Result<Option<Pause>> {
+    serde_json::from_slice(data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pause() {
+        let pause = Pause {
+            since: OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap(),
+            by: Some("admin".to_string()),
+            reason: Some("storage migration".to_string()),
+        };
+        assert_eq!(
+            pause.to_string(),
+            "paused since 2023-11-14T22:13:20Z by admin: storage migration"
+        );
+
+        let data = serde_json::to_vec(&Some(&pause)).unwrap();
+        assert_eq!(parse(&data).unwrap(), Some(pause));
+        // resumed
+        assert_eq!(parse(b"null").unwrap(), None);
+    }
+}
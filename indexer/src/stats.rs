@@ -0,0 +1,178 @@
+//! Statistics of ingested documents.
+//!
+//! The indexer counts the documents it indexes by source (like `api` or `walker`) and hour, and periodically
+//! persists the counters to storage, so they survive restarts. Counters older than the longest window are dropped.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use time::{Duration, OffsetDateTime};
+
+/// Name of the stored statistics.
+pub const STATS_NAME: &str = "ingestion.json";
+
+/// Source of documents stored without provenance.
+pub const UNKNOWN_SOURCE: &str = "unknown";
+
+/// The windows statistics are summarized over.
+const WINDOWS: &[(&str, Duration)] = &[
+    ("1h", Duration::HOUR),
+    ("24h", Duration::DAY),
+    ("7d", Duration::WEEK),
+    ("30d", Duration::days(30)),
+];
+
+const HOUR_SECONDS: i64 = 3600;
+
+/// Number of ingested documents, by hour and source.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IngestionStats {
+    /// Counters by the start of the hour (as unix timestamp) and source
+    #[serde(default)]
+    hours: BTreeMap<i64, BTreeMap<String, u64>>,
+    /// Whether there are counters which were not yet persisted
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl IngestionStats {
+    /// Count an ingested document.
+    pub fn record(&mut self, source: Option<&str>, at: OffsetDateTime) {
+        let hour = at.unix_timestamp().div_euclid(HOUR_SECONDS) * HOUR_SECONDS;
+        *self
+            .hours
+            .entry(hour)
+            .or_default()
+            .entry(source.unwrap_or(UNKNOWN_SOURCE).to_string())
+            .or_default() += 1;
+        self.dirty = true;
+    }
+
+    /// Add the counters of previously persisted statistics.
+    pub fn merge(&mut self, other: IngestionStats) {
+        for (hour, sources) in other.hours {
+            let counters = self.hours.entry(hour).or_default();
+            for (source, count) in sources {
+                *counters.entry(source).or_default() += count;
+            }
+        }
+    }
+
+    /// Take the statistics to persist, if anything changed since they were last taken.
+    pub fn take_dirty(&mut self, now: OffsetDateTime) -> Option<&Self> {
+        if !self.dirty {
+            return None;
+        }
+        self.dirty = false;
+        self.prune(now);
+        Some(self)
+    }
+
+    /// Drop the counters which are older than the longest window.
+    fn prune(&mut self, now: OffsetDateTime) {
+        let retention = WINDOWS.iter().map(|(_, duration)| *duration).max().unwrap_or_default();
+        let oldest = (now - retention).unix_timestamp();
+        self.hours.retain(|hour, _| *hour + HOUR_SECONDS > oldest);
+    }
+
+    /// Summarize the statistics over the windows, ending now.
+    pub fn summary(&self, now: OffsetDateTime) -> IngestionSummary {
+        let windows = WINDOWS
+            .iter()
+            .map(|(name, duration)| {
+                let since = now - *duration;
+                let mut sources = BTreeMap::<String, u64>::new();
+                // hours which started within the window, so the window is rounded to full hours
+                for (_, counters) in self
+                    .hours
+                    .range(since.unix_timestamp().div_euclid(HOUR_SECONDS) * HOUR_SECONDS..)
+                {
+                    for (source, count) in counters {
+                        *sources.entry(source.clone()).or_default() += count;
+                    }
+                }
+                IngestionWindow {
+                    window: name.to_string(),
+                    since,
+                    total: sources.values().sum(),
+                    sources,
+                }
+            })
+            .collect();
+
+        IngestionSummary {
+            generated: now,
+            windows,
+        }
+    }
+}
+
+/// Summary of the ingested documents.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IngestionSummary {
+    #[serde(with = "time::serde::rfc3339")]
+    pub generated: OffsetDateTime,
+    pub windows: Vec<IngestionWindow>,
+}
+
+/// Number of documents ingested within a window.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IngestionWindow {
+    /// Name of the window, like `24h`
+    pub window: String,
+    /// Start of the window
+    #[serde(with = "time::serde::rfc3339")]
+    pub since: OffsetDateTime,
+    /// Number of documents ingested within the window
+    pub total: u64,
+    /// Number of documents ingested within the window, by source
+    pub sources: BTreeMap<String, u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hours_ago: i64, now: OffsetDateTime) -> OffsetDateTime {
+        now - Duration::hours(hours_ago)
+    }
+
+    #[test]
+    fn windows() {
+        let now = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let mut stats = IngestionStats::default();
+        stats.record(Some("api"), now);
+        stats.record(Some("walker"), at(2, now));
+        stats.record(Some("walker"), at(30, now));
+        stats.record(None, at(24 * 20, now));
+
+        let summary = stats.summary(now);
+        let window = |name: &str| summary.windows.iter().find(|w| w.window == name).unwrap();
+        assert_eq!(window("1h").total, 1);
+        assert_eq!(window("24h").total, 2);
+        assert_eq!(window("24h").sources["walker"], 1);
+        assert_eq!(window("7d").sources["walker"], 2);
+        assert_eq!(window("30d").sources[UNKNOWN_SOURCE], 1);
+        assert_eq!(window("30d").total, 4);
+    }
+
+    #[test]
+    fn persist() {
+        let now = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let mut stats = IngestionStats::default();
+        assert!(stats.take_dirty(now).is_none());
+
+        stats.record(Some("api"), now);
+        stats.record(Some("api"), at(24 * 40, now));
+        let persisted: IngestionStats =
+            serde_json::from_slice(&serde_json::to_vec(stats.take_dirty(now).unwrap()).unwrap()).unwrap();
+        assert!(stats.take_dirty(now).is_none());
+        // expired counters are not persisted
+        assert_eq!(persisted.summary(now).windows[3].total, 1);
+
+        // counters of a previous run are added
+        let mut restarted = IngestionStats::default();
+        restarted.record(Some("api"), now);
+        restarted.merge(persisted);
+        assert_eq!(restarted.summary(now).windows[0].sources["api"], 2);
+    }
+}
@@ -0,0 +1,277 @@
+use crate::app::client::TrustedProxies;
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{ContentType, RETRY_AFTER},
+    HttpResponse,
+};
+use futures::future::{ready, LocalBoxFuture, Ready};
+use prometheus::{IntCounter, Opts, Registry};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use trustification_auth::authenticator::actix::is_anonymous_read;
+
+/// The window the rate limit of anonymous requests applies to.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// The number of clients tracked in a window, further clients share a single budget until the next window.
+const MAX_CLIENTS: usize = 10_000;
+
+/// The client the requests of untracked clients are counted for, which can't be a valid address.
+const OTHER_CLIENTS: &str = "*";
+
+/// Limits of requests made without authentication, when anonymous reads are enabled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Anonymous limits")]
+pub struct AnonymousLimits {
+    /// The maximum number of anonymous requests per minute and client address. Requests exceeding the limit are
+    /// rejected with `429 Too Many Requests`.
+    #[arg(
+        id = "http-server-anonymous-rate-limit",
+        long,
+        env = "HTTP_SERVER_ANONYMOUS_RATE_LIMIT",
+        default_value_t = default::rate_limit()
+    )]
+    pub rate_limit: u32,
+}
+
+mod default {
+    pub const fn rate_limit() -> u32 {
+        60
+    }
+}
+
+impl Default for AnonymousLimits {
+    fn default() -> Self {
+        Self {
+            rate_limit: default::rate_limit(),
+        }
+    }
+}
+
+/// Requests per client address, in the current window.
+struct Window {
+    start: Instant,
+    requests: HashMap<String, u32>,
+}
+
+impl Window {
+    /// Count a request of a client, returning the number of seconds until the next window if it exceeds the limit.
+    fn count(&mut self, client: &str, limit: u32, now: Instant) -> Result<(), u64> {
+        let elapsed = now.duration_since(self.start);
+        if elapsed >= WINDOW {
+            // starting a new window also drops the clients of the previous one
+            self.start = now;
+            self.requests.clear();
+        }
+
+        let client = match self.requests.len() < MAX_CLIENTS || self.requests.contains_key(client) {
+            true => client,
+            false => OTHER_CLIENTS,
+        };
+        let requests = self.requests.entry(client.to_string()).or_default();
+        if *requests >= limit {
+            return Err(WINDOW.saturating_sub(now.duration_since(self.start)).as_secs().max(1));
+        }
+        *requests += 1;
+        Ok(())
+    }
+}
+
+struct Inner {
+    limit: u32,
+    proxies: TrustedProxies,
+    window: Mutex<Window>,
+    rejected: Option<IntCounter>,
+}
+
+/// Middleware enforcing the rate limit of [`AnonymousLimits`], shared by all workers of a server.
+///
+/// Requests qualifying as anonymous read are counted per client address. When running behind a proxy, it must be
+/// trusted to report the address of the client, see [`TrustedProxies`].
+#[derive(Clone)]
+pub struct AnonymousRateLimiter(Arc<Inner>);
+
+impl AnonymousRateLimiter {
+    /// Create a new limiter, registering its metrics if a registry and namespace are provided.
+    pub fn new(
+        limits: AnonymousLimits,
+        proxies: TrustedProxies,
+        metrics: Option<(&Registry, &str)>,
+    ) -> Result<Self, prometheus::Error> {
+        log::info!("Limiting anonymous requests to {} per minute", limits.rate_limit);
+
+        let rejected = metrics
+            .map(|(registry, namespace)| {
+                let rejected = IntCounter::with_opts(
+                    Opts::new(
+                        "http_requests_anonymous_rejected_total",
+                        "Total number of anonymous requests rejected because of the rate limit",
+                    )
+                    .namespace(namespace),
+                )?;
+                registry.register(Box::new(rejected.clone()))?;
+                Ok::<_, prometheus::Error>(rejected)
+            })
+            .transpose()?;
+
+        Ok(Self(Arc::new(Inner {
+            limit: limits.rate_limit,
+            proxies,
+            window: Mutex::new(Window {
+                start: Instant::now(),
+                requests: HashMap::new(),
+            }),
+            rejected,
+        })))
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for AnonymousRateLimiter
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = AnonymousRateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AnonymousRateLimitMiddleware {
+            service,
+            limiter: self.clone(),
+        }))
+    }
+}
+
+pub struct AnonymousRateLimitMiddleware<S> {
+    service: S,
+    limiter: AnonymousRateLimiter,
+}
+
+impl<S, B> Service<ServiceRequest> for AnonymousRateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if is_anonymous_read(&req) {
+            let inner = &self.limiter.0;
+            let client = inner.proxies.client_address(&req);
+
+            let result = {
+                let mut window = inner.window.lock().expect("window lock must not be poisoned");
+                window.count(&client, inner.limit, Instant::now())
+            };
+
+            if let Err(retry_after) = result {
+                log::debug!("Rejecting anonymous request of '{client}', rate limit reached");
+                if let Some(rejected) = &inner.rejected {
+                    rejected.inc();
+                }
+                let response = HttpResponse::TooManyRequests()
+                    .insert_header((RETRY_AFTER, retry_after))
+                    .insert_header(ContentType::plaintext())
+                    .body("Too many anonymous requests, retry later or authenticate");
+                return Box::pin(ready(Ok(req.into_response(response).map_into_right_body())));
+            }
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{http::header::AUTHORIZATION, http::StatusCode, test, web, App};
+
+    #[test]
+    fn window() {
+        let start = Instant::now();
+        let mut window = Window {
+            start,
+            requests: HashMap::new(),
+        };
+
+        assert_eq!(window.count("a", 2, start), Ok(()));
+        assert_eq!(window.count("a", 2, start), Ok(()));
+        assert_eq!(window.count("a", 2, start + Duration::from_secs(15)), Err(45));
+        // other clients have their own budget
+        assert_eq!(window.count("b", 2, start), Ok(()));
+        // a new window starts over
+        assert_eq!(window.count("a", 2, start + WINDOW), Ok(()));
+    }
+
+    #[test]
+    fn max_clients() {
+        let start = Instant::now();
+        let mut window = Window {
+            start,
+            requests: HashMap::new(),
+        };
+
+        for client in 0..MAX_CLIENTS {
+            assert_eq!(window.count(&client.to_string(), 1, start), Ok(()));
+        }
+        // tracked clients keep their budget, others share one
+        assert!(window.count("0", 1, start).is_err());
+        assert_eq!(window.count("a", 1, start), Ok(()));
+        assert!(window.count("b", 1, start).is_err());
+        assert_eq!(window.requests.len(), MAX_CLIENTS + 1);
+    }
+
+    #[actix_web::test]
+    async fn limits_anonymous() {
+        let registry = Registry::new();
+        let limiter = AnonymousRateLimiter::new(
+            AnonymousLimits { rate_limit: 1 },
+            TrustedProxies::default(),
+            Some((&registry, "test")),
+        )
+        .unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .wrap(limiter)
+                .route("/", web::get().to(HttpResponse::Ok))
+                .route("/", web::post().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(resp.headers().contains_key(RETRY_AFTER));
+
+        // authenticated requests and writes are not limited
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((AUTHORIZATION, "Bearer token"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+        let resp = test::call_service(&app, test::TestRequest::post().uri("/").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let rejected = registry
+            .gather()
+            .into_iter()
+            .find(|family| family.get_name() == "test_http_requests_anonymous_rejected_total")
+            .unwrap();
+        assert_eq!(rejected.get_metric()[0].get_counter().get_value(), 1.0);
+    }
+}
@@ -0,0 +1,222 @@
+//! Identifying the client of a request by its address, for the limits applying per client.
+//!
+//! The peer address of the connection is used, unless it belongs to a trusted proxy. Only then the forwarded headers
+//! are considered, as any client can set them.
+
+use actix_web::{dev::ServiceRequest, http::header::HeaderMap};
+use std::{
+    fmt::{Display, Formatter},
+    net::IpAddr,
+    str::FromStr,
+};
+
+/// The proxies in front of a server, which are trusted to report the address of the client.
+#[derive(Clone, Debug, Default, PartialEq, Eq, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Trusted proxies")]
+pub struct TrustedProxies {
+    /// Addresses or networks (like `10.0.0.0/8`) of proxies, which are trusted to report the address of the client in
+    /// the `Forwarded` or `X-Forwarded-For` header. Without any, the peer address is used to identify clients.
+    #[arg(
+        id = "http-server-trusted-proxies",
+        long,
+        env = "HTTP_SERVER_TRUSTED_PROXIES",
+        value_delimiter = ','
+    )]
+    pub proxies: Vec<Network>,
+}
+
+impl TrustedProxies {
+    fn is_trusted(&self, addr: IpAddr) -> bool {
+        self.proxies.iter().any(|network| network.contains(addr))
+    }
+
+    /// The address of the client of a request.
+    ///
+    /// If the request comes from a trusted proxy, the hops reported by the proxies are followed back until the first
+    /// one which isn't trusted, as everything before it may have been made up by the client.
+    pub fn client_address(&self, req: &ServiceRequest) -> String {
+        let peer = match req.peer_addr() {
+            Some(peer) => peer.ip(),
+            None => return String::new(),
+        };
+        if !self.is_trusted(peer) {
+            return peer.to_string();
+        }
+
+        let mut client = peer.to_string();
+        for hop in forwarded_hops(req.headers()).iter().rev() {
+            match parse_hop(hop) {
+                Some(addr) if self.is_trusted(addr) => client = addr.to_string(),
+                Some(addr) => return addr.to_string(),
+                // obfuscated or unknown, but not a proxy we trust
+                None => return hop.to_string(),
+            }
+        }
+        client
+    }
+}
+
+/// An address, or a network of addresses in CIDR notation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Network {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Network {
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        match (self.addr, addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(addr) & mask
+            }
+            (IpAddr::V6(_), IpAddr::V4(addr)) => self.contains(IpAddr::V6(addr.to_ipv6_mapped())),
+            (IpAddr::V4(_), IpAddr::V6(addr)) => addr
+                .to_ipv4_mapped()
+                .is_some_and(|addr| self.contains(IpAddr::V4(addr))),
+        }
+    }
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr = IpAddr::from_str(addr.trim()).map_err(|err| format!("invalid address '{addr}': {err}"))?;
+        let max = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        let prefix = match prefix {
+            Some(prefix) => match prefix.trim().parse::<u8>() {
+                Ok(prefix) if prefix <= max => prefix,
+                _ => return Err(format!("invalid prefix length '{prefix}', must be at most {max}")),
+            },
+            None => max,
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl Display for Network {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// The hops reported by the `Forwarded` header, or the `X-Forwarded-For` header if there is none, from the client to
+/// the last proxy.
+fn forwarded_hops(headers: &HeaderMap) -> Vec<String> {
+    let forwarded: Vec<String> = headers
+        .get_all("forwarded")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (name, value) = pair.trim().split_once('=')?;
+                name.eq_ignore_ascii_case("for")
+                    .then(|| value.trim().trim_matches('"').to_string())
+            })
+        })
+        .collect();
+    if !forwarded.is_empty() {
+        return forwarded;
+    }
+
+    headers
+        .get_all("x-forwarded-for")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|hop| hop.trim().to_string())
+        .filter(|hop| !hop.is_empty())
+        .collect()
+}
+
+/// Parse the address of a hop, which may be bracketed and carry a port.
+fn parse_hop(hop: &str) -> Option<IpAddr> {
+    if let Ok(addr) = IpAddr::from_str(hop) {
+        return Some(addr);
+    }
+    let addr = match hop.strip_prefix('[') {
+        Some(rest) => rest.split_once(']')?.0,
+        None => hop.split_once(':')?.0,
+    };
+    IpAddr::from_str(addr).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn proxies(networks: &[&str]) -> TrustedProxies {
+        TrustedProxies {
+            proxies: networks.iter().map(|network| network.parse().unwrap()).collect(),
+        }
+    }
+
+    #[test]
+    fn networks() {
+        let network: Network = "10.1.0.0/16".parse().unwrap();
+        assert!(network.contains("10.1.2.3".parse().unwrap()));
+        assert!(!network.contains("10.2.0.1".parse().unwrap()));
+        assert!(network.contains("::ffff:10.1.2.3".parse().unwrap()));
+
+        let network: Network = "fd00::/8".parse().unwrap();
+        assert!(network.contains("fd12::1".parse().unwrap()));
+        assert!(!network.contains("fe80::1".parse().unwrap()));
+
+        let network: Network = "192.168.1.1".parse().unwrap();
+        assert!(network.contains("192.168.1.1".parse().unwrap()));
+        assert!(!network.contains("192.168.1.2".parse().unwrap()));
+
+        assert!("0.0.0.0/0"
+            .parse::<Network>()
+            .unwrap()
+            .contains("1.2.3.4".parse().unwrap()));
+        assert!("10.0.0.0/33".parse::<Network>().is_err());
+        assert!("proxy".parse::<Network>().is_err());
+    }
+
+    #[test]
+    fn untrusted_peer() {
+        let req = TestRequest::default()
+            .peer_addr("192.0.2.1:1234".parse().unwrap())
+            .insert_header(("x-forwarded-for", "198.51.100.1"))
+            .to_srv_request();
+        // without trusted proxies, the header is ignored
+        assert_eq!(proxies(&[]).client_address(&req), "192.0.2.1");
+        assert_eq!(proxies(&["10.0.0.0/8"]).client_address(&req), "192.0.2.1");
+    }
+
+    #[test]
+    fn trusted_peer() {
+        let proxies = proxies(&["10.0.0.0/8"]);
+
+        // the client may prepend anything, only the hop added by the proxy counts
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .insert_header(("x-forwarded-for", "198.51.100.1, 192.0.2.1, 10.0.0.2"))
+            .to_srv_request();
+        assert_eq!(proxies.client_address(&req), "192.0.2.1");
+
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .insert_header(("forwarded", r#"for=198.51.100.1, for="[2001:db8::1]:4711";proto=https"#))
+            .to_srv_request();
+        assert_eq!(proxies.client_address(&req), "2001:db8::1");
+
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .to_srv_request();
+        assert_eq!(proxies.client_address(&req), "10.0.0.1");
+    }
+}
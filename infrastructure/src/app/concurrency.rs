@@ -0,0 +1,326 @@
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{ContentType, RETRY_AFTER},
+    HttpResponse,
+};
+use futures::future::{ready, LocalBoxFuture, Ready};
+use prometheus::{IntCounterVec, IntGaugeVec, Opts, Registry};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// The maximum number of concurrent requests to a route.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RouteLimit {
+    /// The path of the route. When ending with `*`, all paths starting with it are matched.
+    pub path: String,
+    pub limit: usize,
+}
+
+impl RouteLimit {
+    fn matches(&self, path: &str) -> bool {
+        match self.path.strip_suffix('*') {
+            Some(prefix) => path.starts_with(prefix),
+            None => path == self.path,
+        }
+    }
+}
+
+impl FromStr for RouteLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (path, limit) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected <path>=<limit>, got '{s}'"))?;
+        let limit: usize = limit.parse().map_err(|err| format!("invalid limit '{limit}': {err}"))?;
+        if limit == 0 {
+            return Err(format!("limit of '{path}' must be greater than zero"));
+        }
+        Ok(Self {
+            path: path.to_string(),
+            limit,
+        })
+    }
+}
+
+/// Concurrency limits of routes, shedding load instead of queuing requests.
+#[derive(Clone, Debug, PartialEq, Eq, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Concurrency limits")]
+pub struct ConcurrencyLimits {
+    /// The maximum number of concurrent requests to a route, as `<path>=<limit>`. Requests exceeding the limit are
+    /// rejected with `503 Service Unavailable`. A path ending with `*` matches all paths starting with it.
+    #[arg(
+        id = "http-server-concurrency-limit",
+        long,
+        env = "HTTP_SERVER_CONCURRENCY_LIMIT",
+        value_delimiter = ','
+    )]
+    pub routes: Vec<RouteLimit>,
+
+    /// The number of seconds clients should wait before retrying a rejected request
+    #[arg(
+        id = "http-server-retry-after",
+        long,
+        env = "HTTP_SERVER_RETRY_AFTER",
+        default_value_t = default::retry_after()
+    )]
+    pub retry_after: u64,
+}
+
+mod default {
+    pub const fn retry_after() -> u64 {
+        1
+    }
+}
+
+impl Default for ConcurrencyLimits {
+    fn default() -> Self {
+        Self {
+            routes: vec![],
+            retry_after: default::retry_after(),
+        }
+    }
+}
+
+struct Route {
+    limit: RouteLimit,
+    permits: Arc<Semaphore>,
+}
+
+struct Metrics {
+    in_flight: IntGaugeVec,
+    shed: IntCounterVec,
+}
+
+impl Metrics {
+    fn register(registry: &Registry, namespace: &str) -> Result<Self, prometheus::Error> {
+        let in_flight = IntGaugeVec::new(
+            Opts::new(
+                "http_requests_in_flight",
+                "Number of requests being processed by routes with a concurrency limit",
+            )
+            .namespace(namespace),
+            &["route"],
+        )?;
+        let shed = IntCounterVec::new(
+            Opts::new(
+                "http_requests_shed_total",
+                "Total number of requests rejected because of the concurrency limit of a route",
+            )
+            .namespace(namespace),
+            &["route"],
+        )?;
+        registry.register(Box::new(in_flight.clone()))?;
+        registry.register(Box::new(shed.clone()))?;
+        Ok(Self { in_flight, shed })
+    }
+}
+
+struct Inner {
+    routes: Vec<Route>,
+    retry_after: u64,
+    metrics: Option<Metrics>,
+}
+
+/// Middleware enforcing [`ConcurrencyLimits`], shared by all workers of a server.
+///
+/// A permit is held until the handler returned its response, the streaming of the response body is not limited.
+#[derive(Clone)]
+pub struct ConcurrencyLimiter(Arc<Inner>);
+
+impl ConcurrencyLimiter {
+    /// Create a new limiter, registering its metrics if a registry and namespace are provided.
+    pub fn new(limits: ConcurrencyLimits, metrics: Option<(&Registry, &str)>) -> Result<Self, prometheus::Error> {
+        let routes = limits
+            .routes
+            .into_iter()
+            .map(|limit| {
+                log::info!("Limiting concurrent requests of '{}' to {}", limit.path, limit.limit);
+                Route {
+                    permits: Arc::new(Semaphore::new(limit.limit)),
+                    limit,
+                }
+            })
+            .collect();
+        let metrics = metrics
+            .map(|(registry, namespace)| Metrics::register(registry, namespace))
+            .transpose()?;
+
+        Ok(Self(Arc::new(Inner {
+            routes,
+            retry_after: limits.retry_after,
+            metrics,
+        })))
+    }
+
+    /// The first route matching the path.
+    fn route(&self, path: &str) -> Option<&Route> {
+        self.0.routes.iter().find(|route| route.limit.matches(path))
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ConcurrencyLimiter
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = ConcurrencyLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ConcurrencyLimitMiddleware {
+            service,
+            limiter: self.clone(),
+        }))
+    }
+}
+
+pub struct ConcurrencyLimitMiddleware<S> {
+    service: S,
+    limiter: ConcurrencyLimiter,
+}
+
+impl<S, B> Service<ServiceRequest> for ConcurrencyLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let Some(route) = self.limiter.route(req.path()) else {
+            let fut = self.service.call(req);
+            return Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) });
+        };
+
+        let metrics = self.limiter.0.metrics.as_ref();
+        match route.permits.clone().try_acquire_owned() {
+            Ok(permit) => {
+                let in_flight = metrics.map(|m| m.in_flight.with_label_values(&[route.limit.path.as_str()]));
+                if let Some(in_flight) = &in_flight {
+                    in_flight.inc();
+                }
+                let fut = self.service.call(req);
+                Box::pin(async move {
+                    let res = fut.await;
+                    drop(permit);
+                    if let Some(in_flight) = in_flight {
+                        in_flight.dec();
+                    }
+                    res.map(ServiceResponse::map_into_left_body)
+                })
+            }
+            Err(_) => {
+                log::debug!("Shedding request to '{}', limit reached", req.path());
+                if let Some(metrics) = metrics {
+                    metrics.shed.with_label_values(&[route.limit.path.as_str()]).inc();
+                }
+                let response = HttpResponse::ServiceUnavailable()
+                    .insert_header((RETRY_AFTER, self.limiter.0.retry_after))
+                    .insert_header(ContentType::plaintext())
+                    .body("Too many concurrent requests, retry later");
+                Box::pin(ready(Ok(req.into_response(response).map_into_right_body())))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{http::StatusCode, test, web, App};
+    use tokio::sync::Notify;
+
+    #[test]
+    fn parse_route_limit() {
+        assert_eq!(
+            "/api/v1/sbom/search=4".parse::<RouteLimit>(),
+            Ok(RouteLimit {
+                path: "/api/v1/sbom/search".into(),
+                limit: 4
+            })
+        );
+        assert!("/api/v1/sbom/search".parse::<RouteLimit>().is_err());
+        assert!("/api/v1/sbom/search=0".parse::<RouteLimit>().is_err());
+    }
+
+    #[test]
+    fn route_matching() {
+        let exact: RouteLimit = "/api/v1/sbom=1".parse().unwrap();
+        assert!(exact.matches("/api/v1/sbom"));
+        assert!(!exact.matches("/api/v1/sbom/search"));
+
+        let prefix: RouteLimit = "/api/v1/sbom*=1".parse().unwrap();
+        assert!(prefix.matches("/api/v1/sbom"));
+        assert!(prefix.matches("/api/v1/sbom/search"));
+        assert!(!prefix.matches("/api/v1/vex"));
+    }
+
+    #[actix_web::test]
+    async fn sheds_load() {
+        let registry = Registry::new();
+        let limiter = ConcurrencyLimiter::new(
+            ConcurrencyLimits {
+                routes: vec!["/slow=1".parse().unwrap()],
+                retry_after: 5,
+            },
+            Some((&registry, "test")),
+        )
+        .unwrap();
+
+        let release = Arc::new(Notify::new());
+        let app = test::init_service(
+            App::new()
+                .wrap(limiter)
+                .app_data(web::Data::from(release.clone()))
+                .route(
+                    "/slow",
+                    web::get().to(|release: web::Data<Notify>| async move {
+                        release.notified().await;
+                        HttpResponse::Ok().finish()
+                    }),
+                )
+                .route("/fast", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        // occupy the only permit
+        let pending = test::call_service(&app, test::TestRequest::get().uri("/slow").to_request());
+        futures::pin_mut!(pending);
+        assert!(futures::poll!(pending.as_mut()).is_pending());
+
+        let shed = test::call_service(&app, test::TestRequest::get().uri("/slow").to_request()).await;
+        assert_eq!(shed.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(shed.headers().get(RETRY_AFTER).unwrap(), "5");
+
+        // other routes are not limited
+        let fast = test::call_service(&app, test::TestRequest::get().uri("/fast").to_request()).await;
+        assert_eq!(fast.status(), StatusCode::OK);
+
+        release.notify_one();
+        assert_eq!(pending.await.status(), StatusCode::OK);
+
+        // the permit was returned
+        release.notify_one();
+        let again = test::call_service(&app, test::TestRequest::get().uri("/slow").to_request()).await;
+        assert_eq!(again.status(), StatusCode::OK);
+
+        let shed_total = registry
+            .gather()
+            .into_iter()
+            .find(|family| family.get_name() == "test_http_requests_shed_total")
+            .unwrap();
+        assert_eq!(shed_total.get_metric()[0].get_counter().get_value(), 1.0);
+    }
+}
@@ -0,0 +1,125 @@
+use actix_web::{
+    http::header::{EntityTag, Header, HttpDate, IfModifiedSince, IfNoneMatch, ETAG, LAST_MODIFIED},
+    HttpRequest, HttpResponse, HttpResponseBuilder,
+};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+/// The validators of a stored document, answering conditional requests of clients polling for changes.
+///
+/// The entity tag is weak, as the document may be served with or without the content encoding it is stored with.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Validators {
+    pub etag: Option<EntityTag>,
+    pub last_modified: Option<HttpDate>,
+}
+
+impl Validators {
+    /// Create the validators from the entity tag and last modification date reported by the object storage. Values
+    /// which can't be used in HTTP headers are ignored.
+    pub fn new(etag: Option<&str>, last_modified: Option<&str>) -> Self {
+        let etag = etag
+            .map(|etag| etag.trim_start_matches("W/").trim_matches('"'))
+            .filter(|tag| !tag.is_empty() && tag.bytes().all(|c| c == 0x21 || (0x23..=0x7e).contains(&c)))
+            .map(|tag| EntityTag::new_weak(tag.to_string()));
+        let last_modified = last_modified.and_then(|date| HttpDate::from_str(date).ok());
+        Self { etag, last_modified }
+    }
+
+    /// Whether the client has the current document already, according to the `If-None-Match` and
+    /// `If-Modified-Since` headers of its request. The latter is only evaluated without the former.
+    pub fn is_not_modified(&self, req: &HttpRequest) -> bool {
+        if req.headers().contains_key(IfNoneMatch::name()) {
+            return match (IfNoneMatch::parse(req), &self.etag) {
+                (Ok(IfNoneMatch::Any), Some(_)) => true,
+                (Ok(IfNoneMatch::Items(tags)), Some(etag)) => tags.iter().any(|tag| tag.weak_eq(etag)),
+                _ => false,
+            };
+        }
+
+        match (IfModifiedSince::parse(req), self.last_modified) {
+            (Ok(IfModifiedSince(since)), Some(last_modified)) => {
+                SystemTime::from(last_modified) <= SystemTime::from(since)
+            }
+            _ => false,
+        }
+    }
+
+    /// Add the validators to a response.
+    pub fn apply(&self, response: &mut HttpResponseBuilder) {
+        if let Some(etag) = &self.etag {
+            response.insert_header((ETAG, etag.to_string()));
+        }
+        if let Some(last_modified) = self.last_modified {
+            response.insert_header((LAST_MODIFIED, last_modified.to_string()));
+        }
+    }
+
+    /// The response telling the client the document didn't change.
+    pub fn not_modified(&self) -> HttpResponse {
+        let mut response = HttpResponse::NotModified();
+        self.apply(&mut response);
+        response.finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::http::header::{IF_MODIFIED_SINCE, IF_NONE_MATCH};
+    use actix_web::test::TestRequest;
+
+    const DATE: &str = "Wed, 21 Oct 2015 07:28:00 GMT";
+
+    #[test]
+    fn parse() {
+        let validators = Validators::new(Some("\"d41d8cd98f00b204e9800998ecf8427e\""), Some(DATE));
+        assert_eq!(
+            validators.etag,
+            Some(EntityTag::new_weak("d41d8cd98f00b204e9800998ecf8427e".to_string()))
+        );
+        assert!(validators.last_modified.is_some());
+
+        assert_eq!(Validators::new(Some("\"\""), Some("yesterday")), Validators::default());
+    }
+
+    #[test]
+    fn entity_tags() {
+        let validators = Validators::new(Some("\"abc\""), Some(DATE));
+
+        let req = TestRequest::get()
+            .insert_header((IF_NONE_MATCH, "\"abc\""))
+            .to_http_request();
+        assert!(validators.is_not_modified(&req));
+        let req = TestRequest::get()
+            .insert_header((IF_NONE_MATCH, "W/\"xyz\", W/\"abc\""))
+            .to_http_request();
+        assert!(validators.is_not_modified(&req));
+        let req = TestRequest::get().insert_header((IF_NONE_MATCH, "*")).to_http_request();
+        assert!(validators.is_not_modified(&req));
+
+        // a mismatching entity tag takes precedence over the date
+        let req = TestRequest::get()
+            .insert_header((IF_NONE_MATCH, "\"xyz\""))
+            .insert_header((IF_MODIFIED_SINCE, DATE))
+            .to_http_request();
+        assert!(!validators.is_not_modified(&req));
+
+        assert!(!Validators::default().is_not_modified(&req));
+    }
+
+    #[test]
+    fn dates() {
+        let validators = Validators::new(None, Some(DATE));
+
+        let req = TestRequest::get()
+            .insert_header((IF_MODIFIED_SINCE, DATE))
+            .to_http_request();
+        assert!(validators.is_not_modified(&req));
+        let req = TestRequest::get()
+            .insert_header((IF_MODIFIED_SINCE, "Tue, 20 Oct 2015 07:28:00 GMT"))
+            .to_http_request();
+        assert!(!validators.is_not_modified(&req));
+        assert!(!validators.is_not_modified(&TestRequest::get().to_http_request()));
+    }
+}
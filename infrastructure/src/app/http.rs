@@ -1,8 +1,15 @@
-use crate::app::{new_app, AppOptions};
+use crate::app::{
+    anonymous::{AnonymousLimits, AnonymousRateLimiter},
+    client::TrustedProxies,
+    concurrency::{ConcurrencyLimiter, ConcurrencyLimits},
+    new_app,
+    ratelimit::{RateLimiter, RateLimits},
+    search::SearchLimits,
+    AppOptions,
+};
 use crate::endpoint::Endpoint;
 use crate::tracing::Tracing;
 use actix_cors::Cors;
-use actix_tls::{accept::openssl::reexports::SslAcceptor, connect::openssl::reexports::SslMethod};
 use actix_web::{
     web::{self, JsonConfig, ServiceConfig},
     HttpServer,
@@ -12,7 +19,6 @@ use actix_web_prom::{PrometheusMetrics, PrometheusMetricsBuilder};
 use anyhow::{anyhow, Context};
 use bytesize::ByteSize;
 use clap::{value_parser, Arg, ArgMatches, Args, Command, Error, FromArgMatches};
-use openssl::ssl::SslFiletype;
 use prometheus::Registry;
 use std::fmt::{Debug, Display, Formatter};
 use std::marker::PhantomData;
@@ -21,8 +27,11 @@ use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 use trustification_auth::{authenticator::Authenticator, authorizer::Authorizer};
 
+pub use crate::app::tls::TlsConfiguration;
+
 const DEFAULT_ADDR: SocketAddr = SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 8080);
 
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Default)]
@@ -198,6 +207,30 @@ where
     )]
     pub tls_certificate_file: Option<PathBuf>,
 
+    /// Check the TLS certificate and key files for changes in this interval, reloading them without a restart when
+    /// they change. Disabled if not set.
+    #[arg(
+        id = "http-server-tls-reload-interval",
+        long,
+        env = "HTTP_SERVER_TLS_RELOAD_INTERVAL"
+    )]
+    pub tls_reload_interval: Option<humantime::Duration>,
+
+    #[command(flatten)]
+    pub search_limits: SearchLimits,
+
+    #[command(flatten)]
+    pub concurrency_limits: ConcurrencyLimits,
+
+    #[command(flatten)]
+    pub anonymous_limits: AnonymousLimits,
+
+    #[command(flatten)]
+    pub rate_limits: RateLimits,
+
+    #[command(flatten)]
+    pub trusted_proxies: TrustedProxies,
+
     #[arg(skip)]
     _marker: Marker<E>,
 }
@@ -232,6 +265,12 @@ where
             tls_enabled: false,
             tls_key_file: None,
             tls_certificate_file: None,
+            tls_reload_interval: None,
+            search_limits: Default::default(),
+            concurrency_limits: Default::default(),
+            anonymous_limits: Default::default(),
+            rate_limits: Default::default(),
+            trusted_proxies: Default::default(),
             _marker: Default::default(),
         }
     }
@@ -268,17 +307,23 @@ where
             .workers(value.workers)
             .bind(addr)
             .request_limit(value.request_limit.0 .0 as _)
-            .json_limit(value.json_limit.0 .0 as _);
+            .json_limit(value.json_limit.0 .0 as _)
+            .search_limits(value.search_limits)
+            .concurrency_limits(value.concurrency_limits)
+            .anonymous_limits(value.anonymous_limits)
+            .rate_limits(value.rate_limits)
+            .trusted_proxies(value.trusted_proxies);
 
         if value.tls_enabled {
-            result = result.tls(TlsConfiguration {
-                key: value.tls_key_file.ok_or_else(|| {
-                    anyhow!("TLS enabled but no key file configured (use --http-server-tls-key-file)")
-                })?,
-                certificate: value.tls_certificate_file.ok_or_else(|| {
-                    anyhow!("TLS enabled but no certificate file configured (use --http-server-tls-certificate-file)")
-                })?,
-            });
+            let key = value
+                .tls_key_file
+                .ok_or_else(|| anyhow!("TLS enabled but no key file configured (use --http-server-tls-key-file)"))?;
+            let certificate = value.tls_certificate_file.ok_or_else(|| {
+                anyhow!("TLS enabled but no certificate file configured (use --http-server-tls-certificate-file)")
+            })?;
+            result = result.tls(
+                TlsConfiguration::new(certificate, key).reload_interval(value.tls_reload_interval.map(Duration::from)),
+            );
         }
 
         Ok(result)
@@ -293,6 +338,8 @@ pub struct HttpServerBuilder {
     tls: Option<TlsConfiguration>,
 
     metrics_factory: Option<Arc<dyn Fn() -> anyhow::Result<PrometheusMetrics> + Send + Sync>>,
+    /// Registry and namespace for additional metrics
+    metrics_registry: Option<(Registry, String)>,
     cors_factory: Option<Arc<dyn Fn() -> Cors + Send + Sync>>,
     authenticator: Option<Arc<Authenticator>>,
     authorizer: Option<Authorizer>,
@@ -300,14 +347,14 @@ pub struct HttpServerBuilder {
     workers: usize,
     json_limit: Option<usize>,
     request_limit: Option<usize>,
+    search_limits: SearchLimits,
+    concurrency_limits: ConcurrencyLimits,
+    anonymous_limits: AnonymousLimits,
+    rate_limits: RateLimits,
+    trusted_proxies: TrustedProxies,
     tracing: Tracing,
 }
 
-pub struct TlsConfiguration {
-    certificate: PathBuf,
-    key: PathBuf,
-}
-
 pub enum Bind {
     /// Use the provided listener
     Listener(TcpListener),
@@ -328,12 +375,18 @@ impl HttpServerBuilder {
             bind: Bind::Address(DEFAULT_ADDR),
             tls: None,
             metrics_factory: None,
+            metrics_registry: None,
             cors_factory: Some(Arc::new(Cors::permissive)),
             authenticator: None,
             authorizer: None,
             workers: 0,
             json_limit: None,
             request_limit: None,
+            search_limits: SearchLimits::default(),
+            concurrency_limits: ConcurrencyLimits::default(),
+            anonymous_limits: AnonymousLimits::default(),
+            rate_limits: RateLimits::default(),
+            trusted_proxies: TrustedProxies::default(),
             tracing: Tracing::default(),
         }
     }
@@ -379,8 +432,10 @@ impl HttpServerBuilder {
 
     #[allow(clippy::useless_asref)]
     pub fn metrics(mut self, registry: impl Into<Registry>, namespace: impl AsRef<str>) -> Self {
+        let registry = registry.into();
+        self.metrics_registry = Some((registry.clone(), namespace.as_ref().to_string()));
         let metrics = PrometheusMetricsBuilder::new(namespace.as_ref())
-            .registry(registry.into())
+            .registry(registry)
             .build();
 
         self.metrics_factory = Some(Arc::new(move || {
@@ -430,6 +485,34 @@ impl HttpServerBuilder {
         self
     }
 
+    pub fn search_limits(mut self, search_limits: SearchLimits) -> Self {
+        self.search_limits = search_limits;
+        self
+    }
+
+    pub fn concurrency_limits(mut self, concurrency_limits: ConcurrencyLimits) -> Self {
+        self.concurrency_limits = concurrency_limits;
+        self
+    }
+
+    /// Set the limits of anonymous requests, enforced if the authorizer allows anonymous reads.
+    pub fn anonymous_limits(mut self, anonymous_limits: AnonymousLimits) -> Self {
+        self.anonymous_limits = anonymous_limits;
+        self
+    }
+
+    /// Set the limits of requests per client.
+    pub fn rate_limits(mut self, rate_limits: RateLimits) -> Self {
+        self.rate_limits = rate_limits;
+        self
+    }
+
+    /// Set the proxies trusted to report the address of the client, for the limits applying per client address.
+    pub fn trusted_proxies(mut self, trusted_proxies: TrustedProxies) -> Self {
+        self.trusted_proxies = trusted_proxies;
+        self
+    }
+
     pub async fn run(self) -> anyhow::Result<()> {
         let metrics = self.metrics_factory.as_ref().map(|factory| (factory)()).transpose()?;
 
@@ -440,6 +523,39 @@ impl HttpServerBuilder {
             log::info!("Payload limit: {}", BinaryByteSize::from(limit));
         }
 
+        // shared by all workers
+        let concurrency = match self.concurrency_limits.routes.is_empty() {
+            true => None,
+            false => Some(ConcurrencyLimiter::new(
+                self.concurrency_limits.clone(),
+                self.metrics_registry
+                    .as_ref()
+                    .map(|(registry, namespace)| (registry, namespace.as_str())),
+            )?),
+        };
+
+        let anonymous = match self.authorizer.as_ref().is_some_and(Authorizer::anonymous_read) {
+            false => None,
+            true => Some(AnonymousRateLimiter::new(
+                self.anonymous_limits,
+                self.trusted_proxies.clone(),
+                self.metrics_registry
+                    .as_ref()
+                    .map(|(registry, namespace)| (registry, namespace.as_str())),
+            )?),
+        };
+
+        let rate_limit = match self.rate_limits.is_enabled() {
+            false => None,
+            true => Some(RateLimiter::new(
+                self.rate_limits,
+                self.trusted_proxies.clone(),
+                self.metrics_registry
+                    .as_ref()
+                    .map(|(registry, namespace)| (registry, namespace.as_str())),
+            )?),
+        };
+
         let mut http = HttpServer::new(move || {
             let config = self.configurator.clone();
 
@@ -469,6 +585,9 @@ impl HttpServerBuilder {
                 authorizer: self.authorizer.clone().unwrap_or_else(|| Authorizer::new(None)),
                 logger,
                 tracing_logger,
+                concurrency: concurrency.clone(),
+                anonymous: anonymous.clone(),
+                rate_limit: rate_limit.clone(),
             });
 
             // configure payload limit
@@ -477,11 +596,13 @@ impl HttpServerBuilder {
                 app = app.app_data(web::PayloadConfig::new(limit));
             }
 
-            app.app_data(json).configure(|svc| {
-                if let Some(config) = config {
-                    config(svc);
-                }
-            })
+            app.app_data(json)
+                .app_data(web::Data::new(self.search_limits))
+                .configure(|svc| {
+                    if let Some(config) = config {
+                        config(svc);
+                    }
+                })
         });
 
         if self.workers > 0 {
@@ -492,14 +613,7 @@ impl HttpServerBuilder {
         let tls = match self.tls {
             Some(tls) => {
                 log::info!("Enabling TLS support");
-                let mut acceptor = SslAcceptor::mozilla_modern_v5(SslMethod::tls_server())?;
-                acceptor
-                    .set_certificate_chain_file(tls.certificate)
-                    .context("setting certificate chain")?;
-                acceptor
-                    .set_private_key_file(tls.key, SslFiletype::PEM)
-                    .context("setting private key")?;
-                Some(acceptor)
+                Some(tls.acceptor()?)
             }
             None => None,
         };
@@ -1,4 +1,15 @@
+pub mod anonymous;
+pub mod client;
+pub mod concurrency;
+pub mod conditional;
 pub mod http;
+pub mod multi_get;
+pub mod ndjson;
+pub mod range;
+pub mod ratelimit;
+pub mod search;
+pub mod tls;
+pub mod version;
 
 use actix_cors::Cors;
 use actix_web::{
@@ -11,6 +22,9 @@ use actix_web::{
 use actix_web_extras::middleware::Condition;
 use actix_web_opentelemetry::RequestTracing;
 use actix_web_prom::PrometheusMetrics;
+use anonymous::AnonymousRateLimiter;
+use concurrency::ConcurrencyLimiter;
+use ratelimit::RateLimiter;
 use std::sync::Arc;
 use trustification_auth::authenticator::Authenticator;
 use trustification_auth::authorizer::Authorizer;
@@ -23,16 +37,17 @@ pub struct AppOptions {
     pub authorizer: Authorizer,
     pub logger: Option<Logger>,
     pub tracing_logger: Option<RequestTracing>,
+    pub concurrency: Option<ConcurrencyLimiter>,
+    pub anonymous: Option<AnonymousRateLimiter>,
+    pub rate_limit: Option<RateLimiter>,
 }
 
 #[macro_export]
 macro_rules! new_auth {
     ($auth:expr) => {
-        $crate::extras::middleware::Condition::from_option($auth.map(move |authenticator| {
-            $crate::httpauth::middleware::HttpAuthentication::bearer(move |req, auth| {
-                trustification_auth::authenticator::actix::openid_validator(req, auth, authenticator.clone())
-            })
-        }))
+        $crate::extras::middleware::Condition::from_option(
+            $auth.map(trustification_auth::authenticator::actix::Authentication::new),
+        )
     };
 }
 
@@ -56,10 +71,16 @@ pub fn new_app(
     // following lines, read them from end to start! Middleware for services will be executed after
     // the middleware here.
     App::new()
+        // Rate limit requests per client, after authenticating them to tell the clients apart
+        .wrap(Condition::from_option(options.rate_limit))
         // Handle authentication, might fail and return early
         .wrap(new_auth!(options.authenticator))
         // Handle authorization
         .app_data(actix_web::web::Data::new(options.authorizer))
+        // Shed load of routes with a concurrency limit, before doing any expensive work
+        .wrap(Condition::from_option(options.concurrency))
+        // Rate limit anonymous requests, before they take a share of the concurrency limits
+        .wrap(Condition::from_option(options.anonymous))
         // Handle CORS requests, this might finish early and not pass requests to the next entry
         .wrap(Condition::from_option(options.cors))
         // Next, record metrics for the request (should never fail)
@@ -0,0 +1,181 @@
+//! Fetching multiple documents with a single request, shared by the APIs serving documents.
+//!
+//! The entries are streamed as newline delimited JSON, in the order of the request, while the number of concurrent
+//! storage reads is limited across all requests.
+
+use crate::app::ndjson::NDJSON;
+use actix_web::{
+    http::{header::ContentType, StatusCode},
+    web::Bytes,
+    HttpResponse, ResponseError,
+};
+use futures::{Future, StreamExt};
+use std::{
+    fmt::{Display, Formatter},
+    sync::Arc,
+};
+use tokio::sync::Semaphore;
+use trustification_api::multi_get::{MultiGetEntry, MultiGetRequest};
+
+/// Server side limits for multi-get requests.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Multi-get limits")]
+pub struct MultiGetConfig {
+    /// Maximum number of concurrent storage reads for multi-get requests
+    #[arg(id = "mget-concurrency", long, env = "MGET_CONCURRENCY", default_value_t = default::concurrency())]
+    pub concurrency: usize,
+
+    /// Maximum number of identifiers of a multi-get request
+    #[arg(id = "mget-max-ids", long, env = "MGET_MAX_IDS", default_value_t = default::max_ids())]
+    pub max_ids: usize,
+}
+
+mod default {
+    pub const fn concurrency() -> usize {
+        8
+    }
+
+    pub const fn max_ids() -> usize {
+        1000
+    }
+}
+
+impl Default for MultiGetConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: default::concurrency(),
+            max_ids: default::max_ids(),
+        }
+    }
+}
+
+/// Serves multi-get requests.
+#[derive(Debug)]
+pub struct MultiGet {
+    config: MultiGetConfig,
+    permits: Arc<Semaphore>,
+}
+
+impl MultiGet {
+    pub fn new(config: MultiGetConfig) -> Self {
+        Self {
+            config,
+            permits: Arc::new(Semaphore::new(config.concurrency.max(1))),
+        }
+    }
+
+    /// The number of documents read concurrently.
+    pub fn concurrency(&self) -> usize {
+        self.config.concurrency.max(1)
+    }
+
+    /// Stream the entries of the requested documents, fetched by `fetch`, failing if the request has too many ids.
+    pub fn respond<F, Fut>(&self, request: MultiGetRequest, fetch: F) -> Result<HttpResponse, MultiGetError>
+    where
+        F: Fn(String) -> Fut + 'static,
+        Fut: Future<Output = MultiGetEntry> + 'static,
+    {
+        let ids = request.ids;
+        if ids.len() > self.config.max_ids {
+            return Err(MultiGetError::TooManyIds {
+                count: ids.len(),
+                max: self.config.max_ids,
+            });
+        }
+
+        let permits = self.permits.clone();
+        let entries = futures::stream::iter(ids)
+            .map(move |id| {
+                let permits = permits.clone();
+                let entry = fetch(id);
+                async move {
+                    let _permit = permits.acquire().await;
+                    entry.await
+                }
+            })
+            .buffered(self.concurrency())
+            .map(|entry| {
+                let mut line = serde_json::to_vec(&entry)?;
+                line.push(b'\n');
+                Ok::<_, serde_json::Error>(Bytes::from(line))
+            });
+
+        Ok(HttpResponse::Ok().content_type(NDJSON).streaming(entries))
+    }
+}
+
+/// A multi-get request exceeding the configured limits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultiGetError {
+    TooManyIds { count: usize, max: usize },
+}
+
+impl Display for MultiGetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooManyIds { count, max } => write!(f, "at most {max} ids can be fetched at once, got {count}"),
+        }
+    }
+}
+
+impl std::error::Error for MultiGetError {}
+
+impl ResponseError for MultiGetError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .insert_header(ContentType::plaintext())
+            .body(self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::body::to_bytes;
+
+    fn request(ids: &[&str]) -> MultiGetRequest {
+        MultiGetRequest {
+            ids: ids.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn entries() {
+        let mget = MultiGet::new(MultiGetConfig::default());
+        let response = mget
+            .respond(request(&["a", "b"]), |id| async move {
+                match id.as_str() {
+                    "a" => MultiGetEntry::found(id, serde_json::json!({"name": "a"})),
+                    _ => MultiGetEntry::failed(id, 404, "Not found"),
+                }
+            })
+            .unwrap();
+
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let lines: Vec<MultiGetEntry> = body
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].status, 200);
+        assert_eq!(lines[1].id, "b");
+        assert_eq!(lines[1].status, 404);
+    }
+
+    #[test]
+    fn too_many_ids() {
+        let mget = MultiGet::new(MultiGetConfig {
+            max_ids: 1,
+            ..Default::default()
+        });
+        let result = mget.respond(request(&["a", "b"]), |id| async move {
+            MultiGetEntry::failed(id, 404, "Not found")
+        });
+        assert_eq!(result.unwrap_err(), MultiGetError::TooManyIds { count: 2, max: 1 });
+    }
+}
@@ -0,0 +1,110 @@
+//! Chunked streaming of newline delimited JSON (NDJSON) responses.
+//!
+//! Large results are sent progressively, one JSON value per line, instead of being built in memory first. While the
+//! next value takes longer than the heartbeat interval, a comment line is sent to keep proxies and clients from
+//! closing an idle connection. Comment lines start with `#` and must be skipped by clients.
+
+use actix_web::{web::Bytes, HttpResponse};
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+use std::time::Duration;
+
+/// The content type of NDJSON responses.
+pub const NDJSON: &str = "application/x-ndjson";
+
+/// Sent when no value was ready within the heartbeat interval.
+pub const HEARTBEAT: &[u8] = b"# heartbeat\n";
+
+/// Default interval of heartbeats.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Encode a stream of values as NDJSON lines, interleaved with heartbeats while the next value isn't ready.
+///
+/// A failing value ends the stream with its error, which aborts the response, as its status was sent already.
+pub fn ndjson_stream<S, T, E>(values: S, heartbeat: Duration) -> impl Stream<Item = Result<Bytes, actix_web::Error>>
+where
+    S: Stream<Item = Result<T, E>> + 'static,
+    T: Serialize,
+    E: Into<actix_web::Error>,
+{
+    futures::stream::unfold(Some(Box::pin(values)), move |values| async move {
+        let mut values = values?;
+        let line = match tokio::time::timeout(heartbeat, values.next()).await {
+            Ok(Some(Ok(value))) => serde_json::to_vec(&value).map_err(actix_web::Error::from),
+            Ok(Some(Err(err))) => Err(err.into()),
+            Ok(None) => return None,
+            Err(_) => return Some((Ok(Bytes::from_static(HEARTBEAT)), Some(values))),
+        };
+        match line {
+            Ok(mut line) => {
+                line.push(b'\n');
+                Some((Ok(Bytes::from(line)), Some(values)))
+            }
+            Err(err) => {
+                log::warn!("Aborting NDJSON stream: {err}");
+                Some((Err(err), None))
+            }
+        }
+    })
+}
+
+/// Create a chunked NDJSON response, streaming the values as they are produced.
+pub fn ndjson_response<S, T, E>(values: S) -> HttpResponse
+where
+    S: Stream<Item = Result<T, E>> + 'static,
+    T: Serialize,
+    E: Into<actix_web::Error>,
+{
+    HttpResponse::Ok()
+        .content_type(NDJSON)
+        .streaming(ndjson_stream(values, HEARTBEAT_INTERVAL))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::error::ErrorBadRequest;
+
+    async fn collect<S: Stream<Item = Result<Bytes, actix_web::Error>>>(stream: S) -> (Vec<u8>, bool) {
+        let mut data = Vec::new();
+        let mut failed = false;
+        futures::pin_mut!(stream);
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(chunk) => data.extend_from_slice(&chunk),
+                Err(_) => failed = true,
+            }
+        }
+        (data, failed)
+    }
+
+    #[tokio::test]
+    async fn lines() {
+        let values = futures::stream::iter([Ok::<_, actix_web::Error>(1), Ok(2)]);
+        let (data, failed) = collect(ndjson_stream(values, HEARTBEAT_INTERVAL)).await;
+        assert_eq!(data, b"1\n2\n");
+        assert!(!failed);
+    }
+
+    #[tokio::test]
+    async fn heartbeat() {
+        let values = futures::stream::iter([1, 2]).then(|value| async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok::<_, actix_web::Error>(serde_json::json!({ "value": value }))
+        });
+        let (data, _) = collect(ndjson_stream(values, Duration::from_millis(20))).await;
+        let data = String::from_utf8(data).unwrap();
+
+        let values: Vec<_> = data.lines().filter(|line| !line.starts_with('#')).collect();
+        assert_eq!(values, vec![r#"{"value":1}"#, r#"{"value":2}"#]);
+        assert!(data.starts_with("# heartbeat\n"));
+    }
+
+    #[tokio::test]
+    async fn error() {
+        let values = futures::stream::iter([Ok(1), Err(ErrorBadRequest("failed")), Ok(3)]);
+        let (data, failed) = collect(ndjson_stream(values, HEARTBEAT_INTERVAL)).await;
+        assert_eq!(data, b"1\n");
+        assert!(failed);
+    }
+}
@@ -0,0 +1,122 @@
+use super::conditional::Validators;
+use actix_web::{
+    http::{
+        header::{
+            AcceptRanges, ContentRange, ContentRangeSpec, Header, IfRange, Range, RangeUnit, CONTENT_RANGE, IF_RANGE,
+            RANGE,
+        },
+        StatusCode,
+    },
+    HttpRequest, HttpResponse, HttpResponseBuilder,
+};
+
+/// The part of a stored document requested by a client, using the `Range` header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Requested {
+    /// The complete document
+    Full,
+    /// The bytes from the first to the last position, both inclusive
+    Partial(u64, u64),
+    /// A range outside of the document
+    Unsatisfiable,
+}
+
+impl Requested {
+    /// Evaluate the `Range` and `If-Range` headers of a request, for a document of the given length.
+    ///
+    /// Only single byte ranges are served, requests for multiple ranges receive the complete document. As are requests
+    /// with an `If-Range` header not matching the document. Entity tags are compared strongly, so only the date of the
+    /// last modification can match the weak entity tags of stored documents.
+    pub fn new(req: &HttpRequest, length: u64, validators: &Validators) -> Self {
+        if !req.headers().contains_key(RANGE) {
+            return Self::Full;
+        }
+
+        if req.headers().contains_key(IF_RANGE) {
+            let current = match (IfRange::parse(req), &validators.etag, validators.last_modified) {
+                (Ok(IfRange::EntityTag(tag)), Some(etag), _) => tag.strong_eq(etag),
+                (Ok(IfRange::Date(date)), _, Some(last_modified)) => date == last_modified,
+                _ => false,
+            };
+            if !current {
+                return Self::Full;
+            }
+        }
+
+        match Range::parse(req) {
+            Ok(Range::Bytes(ranges)) if ranges.len() == 1 => match ranges[0].to_satisfiable_range(length) {
+                Some((start, end)) => Self::Partial(start, end),
+                None => Self::Unsatisfiable,
+            },
+            // invalid range headers are ignored
+            _ => Self::Full,
+        }
+    }
+}
+
+/// Tell the client it may request ranges of the document.
+pub fn accept_ranges(response: &mut HttpResponseBuilder) {
+    response.insert_header(AcceptRanges(vec![RangeUnit::Bytes]));
+}
+
+/// Turn a response into the one of a part of a document.
+pub fn partial(response: &mut HttpResponseBuilder, start: u64, end: u64, length: u64) {
+    response.status(StatusCode::PARTIAL_CONTENT);
+    response.insert_header(ContentRange(ContentRangeSpec::Bytes {
+        range: Some((start, end)),
+        instance_length: Some(length),
+    }));
+}
+
+/// The response telling the client the requested range is outside of the document.
+pub fn unsatisfiable(length: u64) -> HttpResponse {
+    HttpResponse::RangeNotSatisfiable()
+        .insert_header((CONTENT_RANGE, format!("bytes */{length}")))
+        .finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    const DATE: &str = "Wed, 21 Oct 2015 07:28:00 GMT";
+
+    #[test]
+    fn ranges() {
+        let validators = Validators::new(Some("\"abc\""), Some(DATE));
+        let requested = |range: &str| {
+            let req = TestRequest::get().insert_header((RANGE, range)).to_http_request();
+            Requested::new(&req, 100, &validators)
+        };
+
+        assert_eq!(requested("bytes=0-9"), Requested::Partial(0, 9));
+        assert_eq!(requested("bytes=90-"), Requested::Partial(90, 99));
+        assert_eq!(requested("bytes=-10"), Requested::Partial(90, 99));
+        assert_eq!(requested("bytes=50-200"), Requested::Partial(50, 99));
+        assert_eq!(requested("bytes=100-"), Requested::Unsatisfiable);
+        assert_eq!(requested("bytes=0-9,20-29"), Requested::Full);
+        assert_eq!(requested("lines=0-9"), Requested::Full);
+        assert_eq!(
+            Requested::new(&TestRequest::get().to_http_request(), 100, &validators),
+            Requested::Full
+        );
+    }
+
+    #[test]
+    fn if_range() {
+        let validators = Validators::new(Some("\"abc\""), Some(DATE));
+        let requested = |if_range: &str| {
+            let req = TestRequest::get()
+                .insert_header((RANGE, "bytes=0-9"))
+                .insert_header((IF_RANGE, if_range))
+                .to_http_request();
+            Requested::new(&req, 100, &validators)
+        };
+
+        assert_eq!(requested(DATE), Requested::Partial(0, 9));
+        assert_eq!(requested("Tue, 20 Oct 2015 07:28:00 GMT"), Requested::Full);
+        // the entity tag of the document is weak
+        assert_eq!(requested("\"abc\""), Requested::Full);
+    }
+}
@@ -0,0 +1,342 @@
+use crate::app::client::TrustedProxies;
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{ContentType, RETRY_AFTER},
+    HttpMessage, HttpResponse,
+};
+use futures::future::{ready, LocalBoxFuture, Ready};
+use prometheus::{IntCounterVec, IntGauge, Opts, Registry};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use trustification_auth::authenticator::user::UserInformation;
+
+/// The number of tracked clients, further clients share a single bucket until idle clients are dropped.
+const MAX_CLIENTS: usize = 10_000;
+
+/// The client the requests of untracked clients are counted for, which can't be the key of a client.
+const OTHER_CLIENTS: &str = "*";
+
+/// Minimum time between dropping the buckets of idle clients, so that a full map isn't scanned on every request.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Limits of requests per client, applying to all requests.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Rate limits")]
+pub struct RateLimits {
+    /// The sustained number of requests per second and client, `0` disables the limit. Clients are identified by
+    /// their user id once authenticated, by their address otherwise. Requests exceeding the limit are rejected with
+    /// `429 Too Many Requests`.
+    #[arg(
+        id = "http-server-rate-limit",
+        long,
+        env = "HTTP_SERVER_RATE_LIMIT",
+        default_value_t = 0
+    )]
+    pub rate_limit: u32,
+
+    /// The number of requests a client may make in a burst, on top of the sustained rate. Defaults to the rate limit.
+    #[arg(id = "http-server-rate-limit-burst", long, env = "HTTP_SERVER_RATE_LIMIT_BURST")]
+    pub burst: Option<u32>,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            rate_limit: 0,
+            burst: None,
+        }
+    }
+}
+
+impl RateLimits {
+    pub fn is_enabled(&self) -> bool {
+        self.rate_limit > 0
+    }
+}
+
+/// The tokens of a client, refilled at the rate limit up to the burst size.
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+struct Buckets {
+    rate: f64,
+    burst: f64,
+    clients: HashMap<String, Bucket>,
+    pruned: Instant,
+}
+
+impl Buckets {
+    /// Take a token of a client, returning the number of seconds until a token is available if there is none.
+    fn take(&mut self, client: &str, now: Instant) -> Result<(), u64> {
+        let mut client = client;
+        if self.clients.len() >= MAX_CLIENTS && !self.clients.contains_key(client) {
+            if now.duration_since(self.pruned) >= PRUNE_INTERVAL {
+                self.prune(now);
+            }
+            if self.clients.len() >= MAX_CLIENTS {
+                client = OTHER_CLIENTS;
+            }
+        }
+
+        let (rate, burst) = (self.rate, self.burst);
+        let bucket = self.clients.entry(client.to_string()).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate).min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens < 1.0 {
+            return Err(((1.0 - bucket.tokens) / rate).ceil().max(1.0) as u64);
+        }
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+
+    /// Drop the buckets which are full again, as they are no different from a new one.
+    fn prune(&mut self, now: Instant) {
+        self.pruned = now;
+        let (rate, burst) = (self.rate, self.burst);
+        self.clients
+            .retain(|_, bucket| bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate < burst);
+    }
+}
+
+struct Metrics {
+    throttled: IntCounterVec,
+    clients: IntGauge,
+}
+
+struct Inner {
+    buckets: Mutex<Buckets>,
+    proxies: TrustedProxies,
+    metrics: Option<Metrics>,
+}
+
+/// Middleware enforcing the [`RateLimits`] using a token bucket per client, shared by all workers of a server.
+///
+/// It runs after the authentication, so that authenticated clients are limited by their user id, no matter where
+/// their requests come from. Other requests are limited by the client address, see [`TrustedProxies`].
+#[derive(Clone)]
+pub struct RateLimiter(Arc<Inner>);
+
+impl RateLimiter {
+    /// Create a new limiter, registering its metrics if a registry and namespace are provided.
+    pub fn new(
+        limits: RateLimits,
+        proxies: TrustedProxies,
+        metrics: Option<(&Registry, &str)>,
+    ) -> Result<Self, prometheus::Error> {
+        let burst = limits.burst.unwrap_or(limits.rate_limit).max(1);
+        log::info!(
+            "Limiting requests to {} per second and client, with bursts of {burst}",
+            limits.rate_limit
+        );
+
+        let metrics = metrics
+            .map(|(registry, namespace)| {
+                let throttled = IntCounterVec::new(
+                    Opts::new(
+                        "http_requests_throttled_total",
+                        "Total number of requests rejected because of the rate limit",
+                    )
+                    .namespace(namespace),
+                    &["client"],
+                )?;
+                let clients = IntGauge::with_opts(
+                    Opts::new("http_rate_limit_clients", "Number of clients tracked by the rate limit")
+                        .namespace(namespace),
+                )?;
+                registry.register(Box::new(throttled.clone()))?;
+                registry.register(Box::new(clients.clone()))?;
+                Ok::<_, prometheus::Error>(Metrics { throttled, clients })
+            })
+            .transpose()?;
+
+        Ok(Self(Arc::new(Inner {
+            buckets: Mutex::new(Buckets {
+                rate: limits.rate_limit.max(1) as f64,
+                burst: burst as f64,
+                clients: HashMap::new(),
+                pruned: Instant::now(),
+            }),
+            proxies,
+            metrics,
+        })))
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimiter
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service,
+            limiter: self.clone(),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: S,
+    limiter: RateLimiter,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let inner = &self.limiter.0;
+        let user = match req.extensions().get::<UserInformation>() {
+            Some(UserInformation::Authenticated(details)) => Some(details.id.clone()),
+            _ => None,
+        };
+        let (kind, client) = match user {
+            Some(id) => ("user", format!("user:{id}")),
+            None => ("address", format!("address:{}", inner.proxies.client_address(&req))),
+        };
+
+        let result = {
+            let mut buckets = inner.buckets.lock().expect("buckets lock must not be poisoned");
+            let result = buckets.take(&client, Instant::now());
+            if let Some(metrics) = &inner.metrics {
+                metrics.clients.set(buckets.clients.len() as i64);
+            }
+            result
+        };
+
+        if let Err(retry_after) = result {
+            log::debug!("Rejecting request of '{client}', rate limit reached");
+            if let Some(metrics) = &inner.metrics {
+                metrics.throttled.with_label_values(&[kind]).inc();
+            }
+            let response = HttpResponse::TooManyRequests()
+                .insert_header((RETRY_AFTER, retry_after))
+                .insert_header(ContentType::plaintext())
+                .body("Too many requests, retry later");
+            return Box::pin(ready(Ok(req.into_response(response).map_into_right_body())));
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{http::StatusCode, test, web, App};
+    use trustification_auth::authenticator::user::UserDetails;
+
+    fn buckets(rate: f64, burst: f64) -> Buckets {
+        Buckets {
+            rate,
+            burst,
+            clients: HashMap::new(),
+            pruned: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn token_bucket() {
+        let start = Instant::now();
+        let mut buckets = buckets(2.0, 3.0);
+
+        // a burst, then the sustained rate
+        for _ in 0..3 {
+            assert_eq!(buckets.take("a", start), Ok(()));
+        }
+        assert_eq!(buckets.take("a", start), Err(1));
+        assert_eq!(buckets.take("a", start + Duration::from_millis(500)), Ok(()));
+        assert_eq!(buckets.take("a", start + Duration::from_millis(500)), Err(1));
+        // other clients have their own budget
+        assert_eq!(buckets.take("b", start), Ok(()));
+        // refills are capped by the burst
+        for _ in 0..3 {
+            assert_eq!(buckets.take("a", start + Duration::from_secs(60)), Ok(()));
+        }
+        assert!(buckets.take("a", start + Duration::from_secs(60)).is_err());
+
+        // only the full buckets are dropped
+        buckets.prune(start + Duration::from_secs(60));
+        assert_eq!(buckets.clients.len(), 1);
+        assert!(buckets.clients.contains_key("a"));
+    }
+
+    #[test]
+    fn max_clients() {
+        let start = Instant::now();
+        let mut buckets = buckets(1.0, 1.0);
+
+        for client in 0..MAX_CLIENTS {
+            assert_eq!(buckets.take(&client.to_string(), start), Ok(()));
+        }
+        // tracked clients keep their bucket, others share one
+        assert!(buckets.take("0", start).is_err());
+        assert_eq!(buckets.take("a", start), Ok(()));
+        assert!(buckets.take("b", start).is_err());
+        assert_eq!(buckets.clients.len(), MAX_CLIENTS + 1);
+
+        // once idle clients are dropped, new clients are tracked again
+        let later = start + Duration::from_secs(60);
+        assert_eq!(buckets.take("b", later), Ok(()));
+        assert!(buckets.take("b", later).is_err());
+        assert_eq!(buckets.clients.len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn limits_clients() {
+        let registry = Registry::new();
+        let limits = RateLimits {
+            rate_limit: 1,
+            burst: None,
+        };
+        let limiter = RateLimiter::new(limits, TrustedProxies::default(), Some((&registry, "test"))).unwrap();
+
+        let app = test::init_service(App::new().wrap(limiter).route("/", web::get().to(HttpResponse::Ok))).await;
+
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(resp.headers().contains_key(RETRY_AFTER));
+
+        // authenticated users have their own budget
+        let req = test::TestRequest::get().uri("/").to_request();
+        req.extensions_mut().insert(UserInformation::Authenticated(UserDetails {
+            id: "alice".into(),
+            permissions: vec![],
+        }));
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+        let throttled = registry
+            .gather()
+            .into_iter()
+            .find(|family| family.get_name() == "test_http_requests_throttled_total")
+            .unwrap();
+        assert_eq!(throttled.get_metric()[0].get_counter().get_value(), 1.0);
+    }
+}
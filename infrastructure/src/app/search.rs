@@ -0,0 +1,349 @@
+use crate::app::http::BinaryByteSize;
+use actix_web::{
+    dev::Payload,
+    http::{header::ContentType, StatusCode},
+    web, FromRequest, HttpRequest, HttpResponse, ResponseError,
+};
+use bytesize::ByteSize;
+use futures::future::{ready, Ready};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use trustification_api::search::SearchOptions;
+use trustification_auth::authenticator::user::UserInformation;
+
+/// Server side limits for search requests.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Search limits")]
+pub struct SearchLimits {
+    /// The maximum number of documents returned by a search
+    #[arg(
+        id = "search-max-limit",
+        long,
+        env = "SEARCH_MAX_LIMIT",
+        default_value_t = default::max_limit()
+    )]
+    pub max_limit: usize,
+
+    /// The maximum offset of a search
+    #[arg(
+        id = "search-max-offset",
+        long,
+        env = "SEARCH_MAX_OFFSET",
+        default_value_t = default::max_offset()
+    )]
+    pub max_offset: usize,
+
+    /// The maximum size of a search response
+    #[arg(
+        id = "search-max-response-size",
+        long,
+        env = "SEARCH_MAX_RESPONSE_SIZE",
+        default_value_t = default::max_response_size()
+    )]
+    pub max_response_size: BinaryByteSize,
+
+    /// The maximum number of documents returned by a search of an anonymous user
+    #[arg(
+        id = "search-anonymous-max-limit",
+        long,
+        env = "SEARCH_ANONYMOUS_MAX_LIMIT",
+        default_value_t = default::anonymous_max_limit()
+    )]
+    pub anonymous_max_limit: usize,
+}
+
+mod default {
+    use super::*;
+
+    pub const fn max_limit() -> usize {
+        1000
+    }
+
+    pub const fn max_offset() -> usize {
+        100_000
+    }
+
+    pub const fn max_response_size() -> BinaryByteSize {
+        BinaryByteSize(ByteSize::mib(32))
+    }
+
+    pub const fn anonymous_max_limit() -> usize {
+        50
+    }
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        Self {
+            max_limit: default::max_limit(),
+            max_offset: default::max_offset(),
+            max_response_size: default::max_response_size(),
+            anonymous_max_limit: default::anonymous_max_limit(),
+        }
+    }
+}
+
+impl SearchLimits {
+    /// Check the parameters of a search request against the limits.
+    ///
+    /// The limit applies with or without summaries, as the documents are collected either way. Searches of anonymous
+    /// users have a lower limit, and can't use expensive options like facets.
+    pub fn check(&self, params: &SearchParams, anonymous: bool) -> Result<(), SearchLimitError> {
+        if anonymous {
+            if params.limit == 0 || params.limit > self.anonymous_max_limit.min(self.max_limit) {
+                return Err(SearchLimitError::Limit {
+                    limit: params.limit,
+                    max: self.anonymous_max_limit.min(self.max_limit),
+                });
+            }
+            if !params.facets().is_empty() {
+                return Err(SearchLimitError::Anonymous("facets"));
+            }
+            if params.explain {
+                return Err(SearchLimitError::Anonymous("explain"));
+            }
+        }
+
+        if params.limit == 0 || params.limit > self.max_limit {
+            return Err(SearchLimitError::Limit {
+                limit: params.limit,
+                max: self.max_limit,
+            });
+        }
+
+        if params.offset > self.max_offset {
+            return Err(SearchLimitError::Offset {
+                offset: params.offset,
+                max: self.max_offset,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// A search request exceeding the configured limits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchLimitError {
+    Limit { limit: usize, max: usize },
+    Offset { offset: usize, max: usize },
+    ResponseSize { size: usize, max: BinaryByteSize },
+    Anonymous(&'static str),
+}
+
+impl Display for SearchLimitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Limit { limit, max } => write!(f, "limit must be between 1 and {max}, was {limit}"),
+            Self::Offset { offset, max } => write!(f, "offset must not exceed {max}, was {offset}"),
+            Self::ResponseSize { size, max } => write!(
+                f,
+                "response size of {} exceeds the maximum of {max}, reduce the limit or disable summaries",
+                BinaryByteSize::from(*size)
+            ),
+            Self::Anonymous(option) => write!(f, "searching with {option} requires authentication"),
+        }
+    }
+}
+
+impl std::error::Error for SearchLimitError {}
+
+impl ResponseError for SearchLimitError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Anonymous(_) => StatusCode::FORBIDDEN,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .insert_header(ContentType::plaintext())
+            .body(self.to_string())
+    }
+}
+
+/// Parameters for search query.
+///
+/// When used as an extractor, the parameters are checked against the [`SearchLimits`] registered with the
+/// application, falling back to the default limits.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SearchParams {
+    /// Search query string
+    pub q: String,
+    /// Offset of documents to return (for pagination)
+    #[serde(default = "default_offset")]
+    pub offset: usize,
+    /// Max number of documents to return
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    /// Provide a detailed explanation of query matches
+    #[serde(default = "default_explain")]
+    pub explain: bool,
+    /// Provide additional metadata from the index
+    #[serde(default = "default_metadata")]
+    pub metadata: bool,
+    /// Enable fetching document summaries
+    #[serde(default = "default_summaries")]
+    pub summaries: bool,
+    /// Comma separated list of facets to count matching documents for
+    #[serde(default)]
+    pub facets: String,
+    /// The limits the parameters were checked against
+    #[serde(skip)]
+    pub limits: SearchLimits,
+}
+
+const fn default_offset() -> usize {
+    0
+}
+
+const fn default_limit() -> usize {
+    10
+}
+
+const fn default_explain() -> bool {
+    false
+}
+
+const fn default_metadata() -> bool {
+    false
+}
+
+const fn default_summaries() -> bool {
+    true
+}
+
+impl SearchParams {
+    /// The requested facets.
+    pub fn facets(&self) -> Vec<String> {
+        self.facets
+            .split(',')
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// Create a JSON response for the search result, failing if it exceeds the maximum response size.
+    pub fn response<T: Serialize>(&self, result: &T) -> Result<HttpResponse, actix_web::Error> {
+        let body = serde_json::to_vec(result)?;
+
+        let max = self.limits.max_response_size;
+        if body.len() as u64 > max.0 .0 {
+            return Err(SearchLimitError::ResponseSize { size: body.len(), max }.into());
+        }
+
+        Ok(HttpResponse::Ok().content_type(ContentType::json()).body(body))
+    }
+
+    fn extract(req: &HttpRequest) -> Result<Self, actix_web::Error> {
+        let limits = req
+            .app_data::<web::Data<SearchLimits>>()
+            .map(|limits| **limits)
+            .unwrap_or_default();
+
+        // only set when anonymous reads are enabled, if authentication is disabled there is no user at all
+        let anonymous = matches!(
+            req.extensions().get::<UserInformation>(),
+            Some(UserInformation::Anonymous)
+        );
+
+        let mut params = web::Query::<Self>::from_query(req.query_string())?.into_inner();
+        limits.check(&params, anonymous)?;
+        params.limits = limits;
+
+        Ok(params)
+    }
+}
+
+impl From<&SearchParams> for SearchOptions {
+    fn from(value: &SearchParams) -> Self {
+        Self {
+            explain: value.explain,
+            metadata: value.metadata,
+            summaries: value.summaries,
+        }
+    }
+}
+
+impl FromRequest for SearchParams {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(Self::extract(req))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn extract(limits: SearchLimits, query: &str) -> Result<SearchParams, actix_web::Error> {
+        let req = TestRequest::with_uri(&format!("/search?{query}"))
+            .app_data(web::Data::new(limits))
+            .to_http_request();
+        SearchParams::extract(&req)
+    }
+
+    #[test]
+    fn limits() {
+        let limits = SearchLimits {
+            max_limit: 100,
+            max_offset: 1000,
+            ..Default::default()
+        };
+
+        let params = extract(limits, "q=foo&limit=50&offset=10&facets=a,%20b").unwrap();
+        assert_eq!(params.limit, 50);
+        assert_eq!(params.facets(), vec!["a", "b"]);
+
+        assert!(extract(limits, "q=foo&limit=500").is_err());
+        assert!(extract(limits, "q=foo&limit=0").is_err());
+        assert!(extract(limits, "q=foo&offset=5000").is_err());
+        // the documents are collected, even if their summaries aren't returned
+        assert!(extract(limits, "q=foo&limit=500&summaries=false").is_err());
+    }
+
+    #[test]
+    fn anonymous() {
+        let limits = SearchLimits {
+            anonymous_max_limit: 20,
+            ..Default::default()
+        };
+        let extract_anonymous = |query: &str| {
+            let req = TestRequest::with_uri(&format!("/search?{query}"))
+                .app_data(web::Data::new(limits))
+                .to_http_request();
+            req.extensions_mut().insert(UserInformation::Anonymous);
+            SearchParams::extract(&req)
+        };
+
+        assert!(extract_anonymous("q=foo&limit=20").is_ok());
+        assert!(extract_anonymous("q=foo&limit=50").is_err());
+        assert!(extract_anonymous("q=foo&limit=50&summaries=false").is_err());
+        let err = extract_anonymous("q=foo&facets=a").unwrap_err();
+        assert_eq!(err.as_response_error().status_code(), StatusCode::FORBIDDEN);
+        assert!(extract_anonymous("q=foo&explain=true").is_err());
+
+        // without a user, authentication is disabled
+        assert!(extract(limits, "q=foo&limit=50&facets=a").is_ok());
+    }
+
+    #[test]
+    fn response_size() {
+        let params = extract(
+            SearchLimits {
+                max_response_size: BinaryByteSize::from(16u64),
+                ..Default::default()
+            },
+            "q=foo",
+        )
+        .unwrap();
+
+        assert!(params.response(&"short").is_ok());
+        assert!(params.response(&"a much longer response").is_err());
+    }
+}
@@ -0,0 +1,195 @@
+//! TLS termination of the HTTP server.
+//!
+//! The certificate and key can be reloaded when the files change, like when a certificate is renewed, without
+//! restarting the server. New connections use the reloaded certificate, existing connections keep the one they were
+//! established with.
+
+use anyhow::Context;
+use openssl::ssl::{SniError, SslAcceptor, SslAcceptorBuilder, SslContext, SslFiletype, SslMethod};
+use parking_lot::RwLock;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+
+/// The certificate and key of the server, in PEM format.
+pub struct TlsConfiguration {
+    certificate: PathBuf,
+    key: PathBuf,
+    reload_interval: Option<Duration>,
+}
+
+impl TlsConfiguration {
+    pub fn new(certificate: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        Self {
+            certificate: certificate.into(),
+            key: key.into(),
+            reload_interval: None,
+        }
+    }
+
+    /// Check the files for changes in this interval, reloading them if they changed.
+    pub fn reload_interval(mut self, reload_interval: impl Into<Option<Duration>>) -> Self {
+        self.reload_interval = reload_interval.into();
+        self
+    }
+
+    /// Create the acceptor of TLS connections, spawning a task watching the files if reloading is enabled.
+    pub(crate) fn acceptor(self) -> anyhow::Result<SslAcceptorBuilder> {
+        let mut acceptor = builder(&self.certificate, &self.key)?;
+
+        if let Some(interval) = self.reload_interval {
+            log::info!("Reloading TLS certificate and key on changes, checking every {interval:?}");
+            let files = Files::read(&self.certificate, &self.key);
+            let current = Arc::new(RwLock::new(
+                builder(&self.certificate, &self.key)?.build().into_context(),
+            ));
+
+            // the callback runs for every handshake, switching to the current context
+            acceptor.set_servername_callback({
+                let current = current.clone();
+                move |ssl, _alert| {
+                    ssl.set_ssl_context(&current.read()).map_err(|err| {
+                        log::warn!("Failed to set TLS context: {err}");
+                        SniError::ALERT_FATAL
+                    })
+                }
+            });
+
+            tokio::spawn(watch(self.certificate, self.key, interval, files, current));
+        }
+
+        Ok(acceptor)
+    }
+}
+
+fn builder(certificate: &Path, key: &Path) -> anyhow::Result<SslAcceptorBuilder> {
+    let mut acceptor = SslAcceptor::mozilla_modern_v5(SslMethod::tls_server())?;
+    acceptor
+        .set_certificate_chain_file(certificate)
+        .context("setting certificate chain")?;
+    acceptor
+        .set_private_key_file(key, SslFiletype::PEM)
+        .context("setting private key")?;
+    acceptor.check_private_key().context("checking private key")?;
+    Ok(acceptor)
+}
+
+/// The content of the certificate and key files, `None` if one of them can't be read.
+#[derive(PartialEq, Eq)]
+struct Files(Option<(Vec<u8>, Vec<u8>)>);
+
+impl Files {
+    fn read(certificate: &Path, key: &Path) -> Self {
+        Self(std::fs::read(certificate).ok().zip(std::fs::read(key).ok()))
+    }
+}
+
+/// Reload the context when the files change.
+///
+/// Certificate and key are usually not replaced at the same time, a mismatching pair fails to load and is retried with
+/// the next check, keeping the current context meanwhile.
+async fn watch(
+    certificate: PathBuf,
+    key: PathBuf,
+    interval: Duration,
+    mut files: Files,
+    current: Arc<RwLock<SslContext>>,
+) {
+    let mut interval = tokio::time::interval(interval);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+
+        let changed = Files::read(&certificate, &key);
+        if changed.0.is_none() || changed == files {
+            continue;
+        }
+
+        match builder(&certificate, &key) {
+            Ok(builder) => {
+                *current.write() = builder.build().into_context();
+                files = changed;
+                log::info!("Reloaded TLS certificate and key");
+            }
+            Err(err) => {
+                log::warn!("Failed to reload TLS certificate and key, keeping the current ones: {err:#}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use openssl::{
+        asn1::Asn1Time,
+        hash::MessageDigest,
+        pkey::PKey,
+        rsa::Rsa,
+        x509::{X509Builder, X509NameBuilder},
+    };
+
+    fn self_signed(name: &str) -> (Vec<u8>, Vec<u8>) {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut subject = X509NameBuilder::new().unwrap();
+        subject.append_entry_by_text("CN", name).unwrap();
+        let subject = subject.build();
+
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&subject).unwrap();
+        builder.set_issuer_name(&subject).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+
+        (
+            builder.build().to_pem().unwrap(),
+            key.private_key_to_pem_pkcs8().unwrap(),
+        )
+    }
+
+    fn common_name(context: &SslContext) -> String {
+        let certificate = context.certificate().unwrap();
+        let entry = certificate.subject_name().entries().next().unwrap();
+        entry.data().as_utf8().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn reload() {
+        let dir = std::env::temp_dir().join(format!("tls-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (certificate, key) = (dir.join("tls.crt"), dir.join("tls.key"));
+
+        let (first_certificate, first_key) = self_signed("first");
+        std::fs::write(&certificate, &first_certificate).unwrap();
+        std::fs::write(&key, &first_key).unwrap();
+
+        let files = Files::read(&certificate, &key);
+        let current = Arc::new(RwLock::new(builder(&certificate, &key).unwrap().build().into_context()));
+        let watcher = tokio::spawn(watch(
+            certificate.clone(),
+            key.clone(),
+            Duration::from_millis(10),
+            files,
+            current.clone(),
+        ));
+        assert_eq!(common_name(&current.read()), "first");
+
+        // a certificate not matching the key is not loaded
+        let (second_certificate, second_key) = self_signed("second");
+        std::fs::write(&certificate, &second_certificate).unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(common_name(&current.read()), "first");
+
+        std::fs::write(&key, &second_key).unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(common_name(&current.read()), "second");
+
+        watcher.abort();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
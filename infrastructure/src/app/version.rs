@@ -0,0 +1,76 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceFactory, ServiceRequest, ServiceResponse},
+    http::header::LINK,
+    middleware::DefaultHeaders,
+    web, Error, Scope,
+};
+use trustification_api::version::ApiVersion;
+
+/// The response header carrying the served API version.
+pub const API_VERSION: &str = "API-Version";
+
+/// The response headers announcing the version, and its deprecation.
+pub fn version_headers(version: ApiVersion) -> DefaultHeaders {
+    let headers = DefaultHeaders::new().add((API_VERSION, version.as_str()));
+    if version.is_deprecated() {
+        headers.add(("Deprecation", "true")).add((
+            LINK,
+            format!(r#"<{}>; rel="successor-version""#, ApiVersion::LATEST.prefix()),
+        ))
+    } else {
+        headers
+    }
+}
+
+/// Create the scope of an API version, providing the version to its handlers as `web::Data<ApiVersion>`.
+pub fn versioned_scope(
+    version: ApiVersion,
+) -> Scope<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = Error,
+        InitError = (),
+    >,
+> {
+    web::scope(version.prefix())
+        .app_data(web::Data::new(version))
+        .wrap(version_headers(version))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{test, App, HttpResponse};
+
+    #[actix_web::test]
+    async fn versioned_scopes() {
+        let app =
+            test::init_service(App::new().configure(|cfg| {
+                for version in ApiVersion::ALL {
+                    cfg.service(versioned_scope(version).route(
+                        "/version",
+                        web::get().to(|version: web::Data<ApiVersion>| async move {
+                            HttpResponse::Ok().body(version.as_str())
+                        }),
+                    ));
+                }
+            }))
+            .await;
+
+        for version in ApiVersion::ALL {
+            let req = test::TestRequest::get()
+                .uri(&format!("{}/version", version.prefix()))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.headers().get(API_VERSION).unwrap(), version.as_str());
+            assert_eq!(resp.headers().contains_key("Deprecation"), version.is_deprecated());
+            assert_eq!(test::read_body(resp).await, version.as_str());
+        }
+
+        let req = test::TestRequest::get().uri("/api/v0/version").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+    }
+}
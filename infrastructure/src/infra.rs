@@ -8,6 +8,7 @@ use futures::future::select_all;
 use prometheus::{Registry, TextEncoder};
 use tokio::signal;
 
+use crate::pushgateway::PushgatewayConfig;
 use crate::tracing::{init_tracing, Tracing};
 
 use crate::health::{Checks, HealthChecks};
@@ -32,6 +33,8 @@ pub struct InfrastructureConfig {
     /// Enable tracing
     #[arg(long, env, default_value_t = Tracing::Disabled)]
     pub tracing: Tracing,
+    #[command(flatten)]
+    pub pushgateway: PushgatewayConfig,
 }
 
 impl Default for InfrastructureConfig {
@@ -41,6 +44,7 @@ impl Default for InfrastructureConfig {
             infrastructure_bind: DEFAULT_BIND_ADDR.into(),
             infrastructure_workers: 1,
             tracing: Tracing::Disabled,
+            pushgateway: Default::default(),
         }
     }
 }
@@ -208,6 +212,8 @@ impl Infrastructure {
         .await?;
 
         init_tracing(id, self.config.tracing);
+        let pushgateway = self.config.pushgateway.clone();
+        let metrics = self.metrics.clone();
         let main = Box::pin(main(MainContext {
             init_data,
             metrics: self.metrics.clone(),
@@ -229,6 +235,10 @@ impl Infrastructure {
         }
 
         let (result, _index, _others) = select_all(tasks).await;
+        // push the metrics of the run before exiting, pushing being best effort
+        if let Err(err) = pushgateway.push(id, metrics.registry()).await {
+            log::warn!("{err:#}");
+        }
         result
     }
 
@@ -1,4 +1,5 @@
 mod infra;
+mod pushgateway;
 
 pub mod app;
 pub mod endpoint;
@@ -6,6 +7,7 @@ pub mod health;
 pub mod tracing;
 
 pub use infra::*;
+pub use pushgateway::PushgatewayConfig;
 
 // re-export extras
 pub use actix_web_extras as extras;
@@ -0,0 +1,99 @@
+//! Pushing metrics to a Prometheus Pushgateway.
+//!
+//! Short-lived runs, like walkers running as a Kubernetes Job, exit before Prometheus scrapes their metrics. When a
+//! Pushgateway is configured, the metrics are pushed to it when the application exits, replacing the metrics previously
+//! pushed for the same job and grouping labels.
+
+use anyhow::{anyhow, Context};
+use prometheus::{Encoder, Registry, TextEncoder};
+use url::Url;
+
+#[derive(Clone, Debug, Default, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Pushgateway")]
+pub struct PushgatewayConfig {
+    /// URL of a Prometheus Pushgateway to push the metrics to when the application exits
+    #[arg(long, env)]
+    pub pushgateway_url: Option<Url>,
+    /// Job label of the pushed metrics, defaults to the name of the application, like `bombastic-walker`
+    #[arg(long, env)]
+    pub pushgateway_job: Option<String>,
+    /// Additional grouping labels of the pushed metrics, as `name=value`
+    #[arg(long = "pushgateway-label", env = "PUSHGATEWAY_LABELS", value_delimiter = ',', value_parser = parse_label)]
+    pub pushgateway_labels: Vec<(String, String)>,
+}
+
+fn parse_label(label: &str) -> Result<(String, String), String> {
+    match label.split_once('=') {
+        Some((name, value)) if !name.is_empty() && !value.contains('/') => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!(
+            "invalid label '{label}', expecting 'name=value', the value not containing '/'"
+        )),
+    }
+}
+
+impl PushgatewayConfig {
+    /// The URL grouping the pushed metrics: `<pushgateway>/metrics/job/<job>/<name>/<value>…`.
+    fn grouping_url(&self, url: &Url, id: &str) -> anyhow::Result<Url> {
+        let mut url = url.clone();
+        {
+            let mut segments = url
+                .path_segments_mut()
+                .map_err(|()| anyhow!("invalid Pushgateway URL: {url}"))?;
+            segments
+                .pop_if_empty()
+                .extend(["metrics", "job", self.pushgateway_job.as_deref().unwrap_or(id)]);
+            for (name, value) in &self.pushgateway_labels {
+                segments.extend([name, value]);
+            }
+        }
+        Ok(url)
+    }
+
+    /// Push the metrics of the registry, if a Pushgateway is configured.
+    pub async fn push(&self, id: &str, registry: &Registry) -> anyhow::Result<()> {
+        let Some(url) = &self.pushgateway_url else {
+            return Ok(());
+        };
+        let url = self.grouping_url(url, id)?;
+
+        let encoder = TextEncoder::new();
+        let mut body = Vec::new();
+        encoder.encode(&registry.gather(), &mut body)?;
+
+        log::info!("Pushing metrics to {url}");
+        reqwest::Client::new()
+            .put(url)
+            .header(reqwest::header::CONTENT_TYPE, encoder.format_type())
+            .body(body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("failed to push metrics")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn grouping_url() {
+        let mut config = PushgatewayConfig::default();
+        let url = Url::parse("http://pushgateway:9091/").unwrap();
+        assert_eq!(
+            config.grouping_url(&url, "bombastic-walker").unwrap().as_str(),
+            "http://pushgateway:9091/metrics/job/bombastic-walker"
+        );
+
+        config.pushgateway_job = Some("walker".into());
+        config.pushgateway_labels = vec![parse_label("source=redhat").unwrap()];
+        assert_eq!(
+            config.grouping_url(&url, "bombastic-walker").unwrap().as_str(),
+            "http://pushgateway:9091/metrics/job/walker/source/redhat"
+        );
+
+        assert!(parse_label("source").is_err());
+        assert!(parse_label("source=a/b").is_err());
+    }
+}
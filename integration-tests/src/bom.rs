@@ -163,51 +163,44 @@ where
     wait_for_search_result(context, flags, check, "/api/v1/sbom/search").await
 }
 
-async fn wait_for_search_result<T, F>(context: &BombasticContext, flags: &T, check: F, path: &str) -> Value
-where
-    T: Serialize + ?Sized,
-    F: Fn(&Value) -> bool,
-{
-    let request: RequestFactory<'_, _, Value> = RequestFactory::new()
-        .with_provider_manager()
-        .get(path)
-        .with_query(flags)
-        .expect_status(StatusCode::OK);
-    loop {
-        let payload = request.send(context).await.1.unwrap().try_into().unwrap();
-        if check(&payload) {
-            return payload;
-        }
-        tokio::time::sleep(Duration::from_secs(1)).await;
-    }
-}
-
 // Configuration for the bombastic indexer
 fn bombastic_indexer() -> bombastic_indexer::Run {
     bombastic_indexer::Run {
         stored_topic: "sbom-stored".into(),
         failed_topic: "sbom-failed".into(),
         indexed_topic: "sbom-indexed".into(),
+        deleted_topic: "sbom-deleted".into(),
         devmode: true,
         reindex: Default::default(),
+        index_files: false,
+        custom_id_config: None,
         index: IndexConfig {
             index_dir: None,
             index_writer_memory_bytes: bytesize::ByteSize::mb(64),
             mode: Default::default(),
             sync_interval: Duration::from_secs(2).into(),
+            encryption_key: None,
+            // exercise applying delta snapshots in the APIs
+            max_deltas: 3,
+            disk: Default::default(),
+            partitions: Default::default(),
+            enrichment: Default::default(),
         },
         storage: StorageConfig {
             region: None,
             bucket: Some("bombastic".into()),
-            endpoint: Some(STORAGE_ENDPOINT.into()),
+            endpoint: Some(infrastructure().storage_endpoint.clone()),
             access_key: Some("admin".into()),
             secret_key: Some("password".into()),
             validator: Validator::None,
             max_size: ByteSize::gb(1),
+            archive_after: None,
+            archive_class: None,
+            content_addressable: false,
         },
         bus: EventBusConfig {
             event_bus: EventBusType::Kafka,
-            kafka_bootstrap_servers: KAFKA_BOOTSTRAP_SERVERS.into(),
+            kafka_bootstrap_servers: infrastructure().kafka_bootstrap_servers.clone(),
             ..Default::default()
         },
         infra: InfrastructureConfig {
@@ -215,7 +208,12 @@ fn bombastic_indexer() -> bombastic_indexer::Run {
             infrastructure_bind: "127.0.0.1".into(),
             infrastructure_workers: 1,
             tracing: Default::default(),
+            pushgateway: Default::default(),
         },
+        batch: Default::default(),
+        scorecard: Default::default(),
+        auth: testing_auth(),
+        command: None,
     }
 }
 
@@ -228,28 +226,51 @@ fn bombastic_api() -> bombastic_api::Run {
             index_writer_memory_bytes: bytesize::ByteSize::mb(64),
             mode: Default::default(),
             sync_interval: Duration::from_secs(2).into(),
+            encryption_key: None,
+            max_deltas: 0,
+            disk: Default::default(),
+            partitions: Default::default(),
+            enrichment: Default::default(),
+        },
+        // exercise the invalidation of cached results, tests search until their documents are indexed
+        search_cache: trustification_index::cache::SearchCacheConfig {
+            entries: 100,
+            ..Default::default()
         },
+        realtime: Default::default(),
         storage: StorageConfig {
             region: Some(Region::Custom {
-                endpoint: STORAGE_ENDPOINT.into(),
+                endpoint: infrastructure().storage_endpoint.clone(),
                 region: Region::EuCentral1.to_string(),
             }),
             bucket: Some("bombastic".into()),
-            endpoint: Some(STORAGE_ENDPOINT.into()),
+            endpoint: Some(infrastructure().storage_endpoint.clone()),
             access_key: Some("admin".into()),
             secret_key: Some("password".into()),
             validator: Validator::SBOM,
             max_size: ByteSize::gb(1),
+            archive_after: None,
+            archive_class: None,
+            content_addressable: false,
         },
         infra: InfrastructureConfig {
             infrastructure_enabled: false,
             infrastructure_bind: "127.0.0.1".into(),
             infrastructure_workers: 1,
             tracing: Default::default(),
+            pushgateway: Default::default(),
         },
         auth: testing_auth(),
         swagger_ui_oidc: testing_swagger_ui_oidc(),
         http: Default::default(),
+        redaction: Default::default(),
+        signature: Default::default(),
         publish_limit: ByteSize::mib(64).into(),
+        batch_limit: ByteSize::mib(256).into(),
+        mget: Default::default(),
+        content_ids: true,
+        upload_chunk_limit: ByteSize::mib(64).into(),
+        max_revisions: 10,
+        max_decompressed_size: ByteSize::gib(1).into(),
     }
 }
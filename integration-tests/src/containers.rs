@@ -0,0 +1,279 @@
+//! Provisioning of the infrastructure required by the integration tests.
+//!
+//! Unless disabled using `TRUST_TESTCONTAINERS=false`, MinIO, Kafka and Keycloak are started as containers once per
+//! test binary, using dynamic ports, and are removed when the process exits. When disabled, the services of the
+//! [default compose script](../deploy/compose/compose.yaml) are expected to be running on their fixed ports.
+
+use std::{
+    net::TcpListener,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{Mutex, OnceLock},
+};
+use testcontainers::{clients::Cli, core::WaitFor, Container, GenericImage, RunnableImage};
+use trustification_auth::devmode;
+
+const MINIO_IMAGE: (&str, &str) = ("quay.io/minio/minio", "RELEASE.2023-06-19T19-52-50Z");
+const KAFKA_IMAGE: (&str, &str) = ("docker.io/bitnami/kafka", "3.4");
+const KEYCLOAK_IMAGE: (&str, &str) = ("quay.io/keycloak/keycloak", "20.0.0");
+
+/// Issuer URL used by the static authentication configuration.
+const DEFAULT_ISSUER_URL: &str = "http://localhost:8090/realms/chicken";
+
+/// Buckets to create, with the Kafka notification target, its topic and the events to publish.
+const BUCKETS: &[(&str, &str, &str, &str)] = &[
+    ("bombastic", "BOMBASTIC", "sbom-stored", "put,delete"),
+    ("vexination", "VEXINATION", "vex-stored", "put"),
+    ("v11y", "V11Y", "v11y-stored", "put"),
+];
+
+/// Endpoints of the infrastructure services used by the integration tests.
+#[derive(Clone, Debug)]
+pub struct Infrastructure {
+    pub storage_endpoint: String,
+    pub kafka_bootstrap_servers: String,
+    pub issuer_url: String,
+    /// Authentication configuration, pointing to the issuer.
+    pub auth_config: PathBuf,
+}
+
+impl Infrastructure {
+    /// Services running on the fixed ports of the compose script.
+    fn external() -> Self {
+        Self {
+            storage_endpoint: "http://localhost:9000".into(),
+            kafka_bootstrap_servers: "localhost:9092".into(),
+            issuer_url: devmode::issuer_url(),
+            auth_config: "config/auth.yaml".into(),
+        }
+    }
+}
+
+/// Resources to remove when the process exits.
+static CLEANUP: Mutex<Cleanup> = Mutex::new(Cleanup {
+    containers: Vec::new(),
+    networks: Vec::new(),
+    files: Vec::new(),
+});
+
+struct Cleanup {
+    containers: Vec<String>,
+    networks: Vec<String>,
+    files: Vec<PathBuf>,
+}
+
+#[ctor::dtor]
+fn cleanup() {
+    let Ok(cleanup) = CLEANUP.lock() else {
+        return;
+    };
+    for id in cleanup.containers.iter().rev() {
+        let _ = Command::new("docker").args(["rm", "--force", "--volumes", id]).output();
+    }
+    for network in &cleanup.networks {
+        let _ = Command::new("docker").args(["network", "rm", network]).output();
+    }
+    for file in &cleanup.files {
+        let _ = std::fs::remove_file(file);
+    }
+}
+
+/// Get the infrastructure for this test run, starting it on first use.
+pub fn infrastructure() -> &'static Infrastructure {
+    static INFRASTRUCTURE: OnceLock<Infrastructure> = OnceLock::new();
+    INFRASTRUCTURE.get_or_init(|| match std::env::var("TRUST_TESTCONTAINERS").as_deref() {
+        Ok("false") | Ok("0") => Infrastructure::external(),
+        _ => start(),
+    })
+}
+
+fn start() -> Infrastructure {
+    let _ = env_logger::try_init();
+
+    // the client needs to outlive the containers, which get removed on exit
+    let docker: &'static Cli = Box::leak(Box::default());
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let network = format!("trustification-{id}");
+    CLEANUP.lock().unwrap().networks.push(network.clone());
+
+    let (kafka, kafka_port) = start_kafka(docker, &network, &id);
+    let kafka_bootstrap_servers = format!("localhost:{kafka_port}");
+    let storage_endpoint = start_minio(docker, &network, &id, &kafka);
+    let issuer_url = start_keycloak(docker, &network, &id);
+    let auth_config = write_auth_config(&id, &issuer_url);
+
+    log::info!(
+        "Started infrastructure - storage: {storage_endpoint}, kafka: {kafka_bootstrap_servers}, issuer: {issuer_url}"
+    );
+
+    Infrastructure {
+        storage_endpoint,
+        kafka_bootstrap_servers,
+        issuer_url,
+        auth_config,
+    }
+}
+
+/// Start Kafka, returning the internal address (for other containers) and the port on the host.
+fn start_kafka(docker: &'static Cli, network: &str, id: &str) -> (String, u16) {
+    let name = format!("kafka-{id}");
+    let internal = format!("{name}:9094");
+
+    // the external listener needs to advertise the port on the host, so that one must be known upfront
+    let port = free_port();
+
+    let image = GenericImage::new(KAFKA_IMAGE.0, KAFKA_IMAGE.1)
+        .with_env_var("KAFKA_ENABLE_KRAFT", "yes")
+        .with_env_var("ALLOW_PLAINTEXT_LISTENER", "yes")
+        .with_env_var("KAFKA_CFG_NODE_ID", "1")
+        .with_env_var("KAFKA_CFG_AUTO_CREATE_TOPICS_ENABLE", "true")
+        .with_env_var("KAFKA_CFG_PROCESS_ROLES", "controller,broker")
+        .with_env_var("KAFKA_CFG_CONTROLLER_QUORUM_VOTERS", "1@localhost:9093")
+        .with_env_var("KAFKA_CFG_CONTROLLER_LISTENER_NAMES", "CONTROLLER")
+        .with_env_var(
+            "KAFKA_CFG_LISTENER_SECURITY_PROTOCOL_MAP",
+            "PLAINTEXT:PLAINTEXT,EXTERNAL:PLAINTEXT,CONTROLLER:PLAINTEXT",
+        )
+        .with_env_var(
+            "KAFKA_CFG_LISTENERS",
+            "PLAINTEXT://:9094,CONTROLLER://:9093,EXTERNAL://:9092",
+        )
+        .with_env_var(
+            "KAFKA_CFG_ADVERTISED_LISTENERS",
+            format!("PLAINTEXT://{internal},EXTERNAL://localhost:{port}"),
+        )
+        .with_wait_for(WaitFor::message_on_stdout("Kafka Server started"));
+
+    run(
+        docker,
+        RunnableImage::from(image)
+            .with_network(network)
+            .with_container_name(name)
+            .with_mapped_port((port, 9092)),
+    );
+
+    (internal, port)
+}
+
+/// Start MinIO, publishing bucket notifications to Kafka, returning the endpoint on the host.
+fn start_minio(docker: &'static Cli, network: &str, id: &str, kafka: &str) -> String {
+    let mut image = GenericImage::new(MINIO_IMAGE.0, MINIO_IMAGE.1)
+        .with_env_var("MINIO_ROOT_USER", "admin")
+        .with_env_var("MINIO_ROOT_PASSWORD", "password")
+        .with_exposed_port(9000)
+        .with_wait_for(WaitFor::message_on_stdout("API:"));
+    for (_, target, topic, _) in BUCKETS {
+        image = image
+            .with_env_var(format!("MINIO_NOTIFY_KAFKA_ENABLE_{target}"), "on")
+            .with_env_var(format!("MINIO_NOTIFY_KAFKA_BROKERS_{target}"), kafka)
+            .with_env_var(format!("MINIO_NOTIFY_KAFKA_TOPIC_{target}"), *topic);
+    }
+
+    let container = run(
+        docker,
+        RunnableImage::from((image, vec!["server".to_string(), "/data".to_string()]))
+            .with_network(network)
+            .with_container_name(format!("minio-{id}")),
+    );
+
+    exec(
+        container.id(),
+        &[
+            "mc",
+            "alias",
+            "set",
+            "local",
+            "http://localhost:9000",
+            "admin",
+            "password",
+        ],
+    );
+    for (bucket, target, _, events) in BUCKETS {
+        let bucket = format!("local/{bucket}");
+        let arn = format!("arn:minio:sqs::{target}:kafka");
+        exec(container.id(), &["mc", "mb", "--ignore-existing", &bucket]);
+        exec(
+            container.id(),
+            &["mc", "event", "add", &bucket, &arn, "--event", events],
+        );
+    }
+
+    format!("http://localhost:{}", container.get_host_port_ipv4(9000))
+}
+
+/// Start Keycloak and initialize the realm and clients, returning the issuer URL on the host.
+fn start_keycloak(docker: &'static Cli, network: &str, id: &str) -> String {
+    let name = format!("keycloak-{id}");
+
+    let image = GenericImage::new(KEYCLOAK_IMAGE.0, KEYCLOAK_IMAGE.1)
+        .with_env_var("KEYCLOAK_DB", "dev-file")
+        .with_env_var("KEYCLOAK_ADMIN", "admin")
+        .with_env_var("KEYCLOAK_ADMIN_PASSWORD", "admin123456")
+        .with_exposed_port(8080)
+        .with_wait_for(WaitFor::message_on_stdout("Listening on:"));
+    let container = run(
+        docker,
+        RunnableImage::from((image, vec!["start-dev".to_string()]))
+            .with_network(network)
+            .with_container_name(&name),
+    );
+    let port = container.get_host_port_ipv4(8080);
+
+    // same as the "init-keycloak" service of the compose script
+    let init_sso = Path::new(env!("CARGO_MANIFEST_DIR")).join("../deploy/compose/container_files/init-sso");
+    let init = GenericImage::new(KEYCLOAK_IMAGE.0, KEYCLOAK_IMAGE.1)
+        .with_entrypoint("/usr/bin/bash")
+        .with_volume(init_sso.to_string_lossy(), "/init-sso")
+        .with_env_var("KEYCLOAK_URL", format!("http://{name}:8080"))
+        .with_env_var("KCADM_PATH", "/opt/keycloak/bin/kcadm.sh")
+        .with_env_var("KEYCLOAK_ADMIN", "admin")
+        .with_env_var("KEYCLOAK_ADMIN_PASSWORD", "admin123456")
+        .with_env_var("REALM", "chicken")
+        .with_env_var("INIT_DATA", "/init-sso/data")
+        .with_env_var("CHICKEN_ADMIN", "admin")
+        .with_env_var("CHICKEN_ADMIN_PASSWORD", "admin123456")
+        .with_env_var("REDIRECT_URIS", r#"["http://localhost:*"]"#)
+        .with_env_var("WALKER_SECRET", "ZVzq9AMOVUdMY1lSohpx1jI3aW56QDPS")
+        .with_env_var("SSO_FRONTEND_URL", format!("http://localhost:{port}"))
+        .with_wait_for(WaitFor::message_on_stdout("SSO initialization complete"));
+    run(
+        docker,
+        RunnableImage::from((init, vec!["/init-sso/init.sh".to_string()])).with_network(network),
+    );
+
+    format!("http://localhost:{port}/realms/chicken")
+}
+
+/// Write the authentication configuration, using the issuer of the started Keycloak instance.
+fn write_auth_config(id: &str, issuer_url: &str) -> PathBuf {
+    let config = std::fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("config/auth.yaml")).unwrap();
+    let path = std::env::temp_dir().join(format!("trustification-auth-{id}.yaml"));
+    std::fs::write(&path, config.replace(DEFAULT_ISSUER_URL, issuer_url)).unwrap();
+    CLEANUP.lock().unwrap().files.push(path.clone());
+    path
+}
+
+fn run(docker: &'static Cli, image: RunnableImage<GenericImage>) -> &'static Container<'static, GenericImage> {
+    let container = docker.run(image);
+    CLEANUP.lock().unwrap().containers.push(container.id().to_string());
+    // removed by the cleanup, as statics are never dropped
+    Box::leak(Box::new(container))
+}
+
+fn exec(container: &str, command: &[&str]) {
+    let output = Command::new("docker")
+        .arg("exec")
+        .arg(container)
+        .args(command)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "Failed to run {command:?}: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("localhost:0").unwrap().local_addr().unwrap().port()
+}
@@ -0,0 +1,198 @@
+//! Contract tests: canonical request/response pairs of the public endpoints, replayed against the current code.
+//!
+//! The pairs of each service are recorded in `tests/contracts/<service>.json`. Replaying them fails when a response
+//! breaks the recorded shape: its status changed, a field was removed, or a field changed its type. Additions are
+//! compatible, so that integrators relying on the recorded responses keep working.
+//!
+//! After an intended change, set `TRUST_RECORD_CONTRACTS=true` to record the current responses instead.
+
+use super::*;
+use reqwest::Method;
+use std::path::PathBuf;
+
+/// A recorded request and its response.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Contract {
+    /// Name of the pair, to report breaking changes
+    pub name: String,
+    pub request: ContractRequest,
+    pub response: ContractResponse,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct ContractRequest {
+    pub method: String,
+    /// Path of the endpoint, where `{name}` is replaced by the variable of the test, like the id of an uploaded document
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub query: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+    /// Send the request as a manager, instead of a user
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub manager: bool,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct ContractResponse {
+    pub status: u16,
+    /// The canonical JSON payload, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+fn contracts_path(service: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/contracts")
+        .join(format!("{service}.json"))
+}
+
+fn recording() -> bool {
+    matches!(std::env::var("TRUST_RECORD_CONTRACTS").as_deref(), Ok("true") | Ok("1"))
+}
+
+/// Replay the recorded contracts of a service, failing on breaking changes, or record them.
+pub async fn verify_contracts<C: Urlifier + IntoTokenProvider + Sync>(
+    context: &C,
+    service: &str,
+    variables: &[(&str, &str)],
+) {
+    let path = contracts_path(service);
+    let mut contracts: Vec<Contract> = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    let substitute = |value: &str| {
+        variables.iter().fold(value.to_string(), |value, (name, v)| {
+            value.replace(&format!("{{{name}}}"), v)
+        })
+    };
+
+    let record = recording();
+    let client = reqwest::Client::new();
+    let mut breaks = Vec::new();
+    for contract in &mut contracts {
+        let request = &contract.request;
+        let method = Method::from_bytes(request.method.as_bytes()).unwrap();
+        let query: Vec<_> = request
+            .query
+            .iter()
+            .map(|(name, value)| (name.clone(), substitute(value)))
+            .collect();
+        let mut builder = client
+            .request(method, context.urlify(substitute(&request.path)))
+            .query(&query);
+        if let Some(body) = &request.body {
+            builder = builder.json(&serde_json::from_str::<Value>(&substitute(&body.to_string())).unwrap());
+        }
+        let kind = if request.manager {
+            ProviderKind::Manager
+        } else {
+            ProviderKind::User
+        };
+        let response = builder
+            .inject_token(context.token_provider(kind))
+            .await
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+
+        let status = response.status().as_u16();
+        let body = serde_json::from_slice::<Value>(&response.bytes().await.unwrap()).ok();
+        log::debug!("Contract {}: {status} {body:?}", contract.name);
+
+        if record {
+            contract.response = ContractResponse {
+                status,
+                body: body.map(canonical),
+            };
+            continue;
+        }
+
+        if status != contract.response.status {
+            breaks.push(format!(
+                "{}: status {} became {status}",
+                contract.name, contract.response.status
+            ));
+        }
+        match (&contract.response.body, &body) {
+            (Some(recorded), Some(actual)) => breaks.extend(
+                shape_breaks(recorded, actual)
+                    .into_iter()
+                    .map(|b| format!("{}: {b}", contract.name)),
+            ),
+            (Some(_), None) => breaks.push(format!("{}: the payload is no longer JSON", contract.name)),
+            (None, _) => {}
+        }
+    }
+
+    if record {
+        let mut data = serde_json::to_vec_pretty(&contracts).unwrap();
+        data.push(b'\n');
+        std::fs::write(&path, data).unwrap();
+        log::info!(
+            "Recorded {} contracts of {service}: {}",
+            contracts.len(),
+            path.display()
+        );
+        return;
+    }
+
+    assert!(
+        breaks.is_empty(),
+        "Breaking changes of the {service} API:\n{}",
+        breaks.join("\n")
+    );
+}
+
+/// Keep only the first element of arrays, which is enough to describe their shape.
+pub fn canonical(value: Value) -> Value {
+    match value {
+        Value::Array(values) => Value::Array(values.into_iter().take(1).map(canonical).collect()),
+        Value::Object(values) => Value::Object(values.into_iter().map(|(k, v)| (k, canonical(v))).collect()),
+        value => value,
+    }
+}
+
+/// The changes of an actual payload breaking the shape of the recorded one.
+///
+/// Fields which were recorded as `null` may take any type, the elements of arrays must have the shape of the first
+/// recorded one.
+pub fn shape_breaks(recorded: &Value, actual: &Value) -> Vec<String> {
+    fn kind(value: &Value) -> &'static str {
+        match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        }
+    }
+
+    fn compare(path: &str, recorded: &Value, actual: &Value, breaks: &mut Vec<String>) {
+        match (recorded, actual) {
+            (Value::Null, _) => {}
+            (Value::Array(recorded), Value::Array(actual)) => {
+                if let Some(recorded) = recorded.first() {
+                    for (i, actual) in actual.iter().enumerate() {
+                        compare(&format!("{path}[{i}]"), recorded, actual, breaks);
+                    }
+                }
+            }
+            (Value::Object(recorded), Value::Object(actual)) => {
+                for (name, recorded) in recorded {
+                    let path = format!("{path}.{name}");
+                    match actual.get(name) {
+                        Some(actual) => compare(&path, recorded, actual, breaks),
+                        None => breaks.push(format!("{path} was removed")),
+                    }
+                }
+            }
+            (recorded, actual) if kind(recorded) == kind(actual) => {}
+            (recorded, actual) => breaks.push(format!("{path}: {} became {}", kind(recorded), kind(actual))),
+        }
+    }
+
+    let mut breaks = Vec::new();
+    compare("$", recorded, actual, &mut breaks);
+    breaks
+}
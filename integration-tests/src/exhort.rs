@@ -0,0 +1,46 @@
+use super::*;
+use crate::{
+    config::Config,
+    provider::{IntoTokenProvider, ProviderKind},
+};
+use async_trait::async_trait;
+use test_context::AsyncTestContext;
+use trustification_auth::client::TokenProvider;
+
+/// A remote exhort instance, set using `EXHORT_URL`.
+///
+/// Exhort isn't started by the tests, as it requires GUAC.
+pub struct ExhortContext {
+    pub url: Url,
+    pub provider: ProviderContext,
+}
+
+#[async_trait]
+impl AsyncTestContext for ExhortContext {
+    async fn setup() -> Self {
+        let config = Config::new().await;
+        let url = std::env::var("EXHORT_URL").expect("EXHORT_URL is required");
+        #[allow(clippy::expect_fun_call)]
+        let url = Url::parse(&url).expect(&format!("Invalid EXHORT_URL: '{url}'"));
+        log::debug!("Testing remote exhort: {url}");
+        ExhortContext {
+            url,
+            provider: config.provider().await,
+        }
+    }
+}
+
+impl Urlifier for ExhortContext {
+    fn base_url(&self) -> &Url {
+        &self.url
+    }
+}
+
+impl IntoTokenProvider for ExhortContext {
+    fn token_provider(&self, kind: ProviderKind) -> &dyn TokenProvider {
+        match kind {
+            ProviderKind::User => &self.provider.provider_user,
+            ProviderKind::Manager => &self.provider.provider_manager,
+        }
+    }
+}
@@ -2,6 +2,9 @@
 
 mod bom;
 mod config;
+mod containers;
+mod contract;
+mod exhort;
 mod provider;
 mod spog;
 mod ui;
@@ -10,6 +13,9 @@ mod vex;
 pub mod runner;
 
 pub use bom::*;
+pub use containers::{infrastructure, Infrastructure};
+pub use contract::*;
+pub use exhort::*;
 pub use provider::*;
 pub use spog::*;
 pub use ui::*;
@@ -26,15 +32,12 @@ use tokio::{
     fs::{remove_file, File},
     select,
 };
-use trustification_auth::{auth::AuthConfigArguments, client::TokenInjector, devmode, swagger_ui::SwaggerUiOidcConfig};
+use trustification_auth::{auth::AuthConfigArguments, client::TokenInjector, swagger_ui::SwaggerUiOidcConfig};
 use trustification_event_bus::{EventBusConfig, EventBusType};
 use trustification_index::IndexConfig;
 use trustification_infrastructure::InfrastructureConfig;
 use trustification_storage::StorageConfig;
 
-const STORAGE_ENDPOINT: &str = "http://localhost:9000";
-const KAFKA_BOOTSTRAP_SERVERS: &str = "localhost:9092";
-
 pub fn tcp_connection() -> (TcpListener, u16, Url) {
     let listener = TcpListener::bind("localhost:0").unwrap();
     let port = listener.local_addr().unwrap().port();
@@ -55,6 +58,7 @@ pub enum ValueTester<T: Eq> {
 pub enum RequestKind<'a> {
     Get(&'a str),
     Post(&'a str),
+    Put(&'a str),
     Delete(&'a str),
 }
 
@@ -211,6 +215,11 @@ impl<'a, T: Serialize + ?Sized, U: Serialize + ?Sized> RequestFactory<'a, T, U>
         self
     }
 
+    pub fn put(mut self, endpoint: &'a str) -> Self {
+        self.request = RequestKind::Put(endpoint);
+        self
+    }
+
     pub fn delete(mut self, endpoint: &'a str) -> Self {
         self.request = RequestKind::Delete(endpoint);
         self
@@ -291,6 +300,7 @@ impl<'a, T: Serialize + ?Sized, U: Serialize + ?Sized> RequestFactory<'a, T, U>
         let builder = match self.request {
             RequestKind::Get(endpoint) => client.get(context.urlify(endpoint)),
             RequestKind::Post(endpoint) => client.post(context.urlify(endpoint)),
+            RequestKind::Put(endpoint) => client.put(context.urlify(endpoint)),
             RequestKind::Delete(endpoint) => client.delete(context.urlify(endpoint)),
         };
         let mut builder = builder.try_query(self.query);
@@ -373,6 +383,26 @@ pub async fn wait_on_service<T: Urlifier + IntoTokenProvider + Sync>(context: &T
     }
 }
 
+pub(crate) async fn wait_for_search_result<C, T, F>(context: &C, flags: &T, check: F, path: &str) -> Value
+where
+    C: Urlifier + IntoTokenProvider + Sync,
+    T: Serialize + ?Sized,
+    F: Fn(&Value) -> bool,
+{
+    let request: RequestFactory<'_, _, Value> = RequestFactory::new()
+        .with_provider_manager()
+        .get(path)
+        .with_query(flags)
+        .expect_status(StatusCode::OK);
+    loop {
+        let payload = request.send(context).await.1.unwrap().try_into().unwrap();
+        if check(&payload) {
+            return payload;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
 /// Return a unique ID
 pub fn id(prefix: &str) -> String {
     let uuid = uuid::Uuid::new_v4();
@@ -424,7 +454,7 @@ pub trait Urlifier {
 fn testing_auth() -> AuthConfigArguments {
     AuthConfigArguments {
         disabled: false,
-        config: Some("config/auth.yaml".into()),
+        config: Some(infrastructure().auth_config.clone()),
         clients: Default::default(),
     }
 }
@@ -433,7 +463,7 @@ fn testing_swagger_ui_oidc() -> SwaggerUiOidcConfig {
     SwaggerUiOidcConfig {
         tls_insecure: false,
         ca_certificates: vec![],
-        swagger_ui_oidc_issuer_url: Some(devmode::issuer_url()),
+        swagger_ui_oidc_issuer_url: Some(infrastructure().issuer_url.clone()),
         swagger_ui_oidc_client_id: "frontend".to_string(),
     }
 }
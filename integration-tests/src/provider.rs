@@ -1,3 +1,4 @@
+use crate::infrastructure;
 use std::sync::Arc;
 use trustification_auth::client::{OpenIdTokenProvider, TokenProvider};
 use trustification_auth::devmode;
@@ -19,9 +20,10 @@ pub struct ProviderContext {
 }
 
 pub async fn create_provider_context() -> ProviderContext {
+    let issuer = &infrastructure().issuer_url;
     ProviderContext {
-        provider_user: create_provider("testing-user", devmode::SSO_CLIENT_SECRET, devmode::issuer_url()).await,
-        provider_manager: create_provider("testing-manager", devmode::SSO_CLIENT_SECRET, devmode::issuer_url()).await,
+        provider_user: create_provider("testing-user", devmode::SSO_CLIENT_SECRET, issuer).await,
+        provider_manager: create_provider("testing-manager", devmode::SSO_CLIENT_SECRET, issuer).await,
     }
 }
 
@@ -131,18 +131,29 @@ fn spog_api(
         snyk_token: None,
         collectorist_url,
         v11y_url,
-        oidc: OpenIdTokenProviderConfigArguments::devmode(),
+        oidc: OpenIdTokenProviderConfigArguments {
+            issuer_url: Some(infrastructure().issuer_url.clone()),
+            ..OpenIdTokenProviderConfigArguments::devmode()
+        },
         config: None,
         infra: InfrastructureConfig {
             infrastructure_enabled: false,
             infrastructure_bind: "127.0.0.1".into(),
             infrastructure_workers: 1,
             tracing: Default::default(),
+            pushgateway: Default::default(),
         },
         auth: testing_auth(),
         swagger_ui_oidc: testing_swagger_ui_oidc(),
         analytics: Default::default(),
         http: Default::default(),
+        cache: Default::default(),
+        registry: Default::default(),
+        export: Default::default(),
+        i18n: Default::default(),
+        posture: Default::default(),
+        ownership: Default::default(),
+        subscriptions: Default::default(),
         db_storage_base: None,
     }
 }
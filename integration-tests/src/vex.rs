@@ -149,6 +149,14 @@ impl VexinationContext {
     }
 }
 
+pub async fn wait_for_vex_search_result<T, F>(context: &VexinationContext, flags: &T, check: F) -> Value
+where
+    T: Serialize + ?Sized,
+    F: Fn(&Value) -> bool,
+{
+    wait_for_search_result(context, flags, check, "/api/v1/vex/search").await
+}
+
 // Configuration for the vexination indexer
 fn vexination_indexer() -> vexination_indexer::Run {
     vexination_indexer::Run {
@@ -159,29 +167,42 @@ fn vexination_indexer() -> vexination_indexer::Run {
         reindex: ReindexMode::Always,
         bus: EventBusConfig {
             event_bus: EventBusType::Kafka,
-            kafka_bootstrap_servers: KAFKA_BOOTSTRAP_SERVERS.into(),
+            kafka_bootstrap_servers: infrastructure().kafka_bootstrap_servers.clone(),
             ..Default::default()
         },
         storage: StorageConfig {
             region: None,
             bucket: Some("vexination".into()),
-            endpoint: Some(STORAGE_ENDPOINT.into()),
+            endpoint: Some(infrastructure().storage_endpoint.clone()),
             access_key: Some("admin".into()),
             secret_key: Some("password".into()),
             validator: Validator::None,
             max_size: ByteSize::gb(1),
+            archive_after: None,
+            archive_class: None,
+            content_addressable: false,
         },
         infra: InfrastructureConfig {
             infrastructure_enabled: false,
             infrastructure_bind: "127.0.0.1".into(),
             infrastructure_workers: 1,
             tracing: Default::default(),
+            pushgateway: Default::default(),
         },
+        batch: Default::default(),
+        auth: testing_auth(),
+        command: None,
         index: IndexConfig {
             index_dir: None,
             index_writer_memory_bytes: bytesize::ByteSize::mb(64),
             mode: Default::default(),
             sync_interval: Duration::from_secs(2).into(),
+            encryption_key: None,
+            // exercise applying delta snapshots in the APIs
+            max_deltas: 3,
+            disk: Default::default(),
+            partitions: Default::default(),
+            enrichment: Default::default(),
         },
     }
 }
@@ -196,28 +217,45 @@ fn vexination_api() -> vexination_api::Run {
             index_writer_memory_bytes: bytesize::ByteSize::mb(64),
             mode: Default::default(),
             sync_interval: Duration::from_secs(2).into(),
+            encryption_key: None,
+            max_deltas: 0,
+            disk: Default::default(),
+            partitions: Default::default(),
+            enrichment: Default::default(),
+        },
+        // exercise the invalidation of cached results, tests search until their documents are indexed
+        search_cache: trustification_index::cache::SearchCacheConfig {
+            entries: 100,
+            ..Default::default()
         },
         storage: StorageConfig {
             region: Some(Region::Custom {
-                endpoint: STORAGE_ENDPOINT.into(),
+                endpoint: infrastructure().storage_endpoint.clone(),
                 region: Region::EuCentral1.to_string(),
             }),
             bucket: Some("vexination".into()),
-            endpoint: Some(STORAGE_ENDPOINT.into()),
+            endpoint: Some(infrastructure().storage_endpoint.clone()),
             access_key: Some("admin".into()),
             secret_key: Some("password".into()),
             validator: Validator::VEX,
             max_size: ByteSize::gb(1),
+            archive_after: None,
+            archive_class: None,
+            content_addressable: false,
         },
         infra: InfrastructureConfig {
             infrastructure_enabled: false,
             infrastructure_bind: "127.0.0.1".into(),
             infrastructure_workers: 1,
             tracing: Default::default(),
+            pushgateway: Default::default(),
         },
         auth: testing_auth(),
         swagger_ui_oidc: testing_swagger_ui_oidc(),
         http: Default::default(),
         publish_limit: ByteSize::mib(64).into(),
+        mget: Default::default(),
+        max_revisions: 10,
+        validation: Default::default(),
     }
 }
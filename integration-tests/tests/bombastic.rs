@@ -1,9 +1,12 @@
 #![allow(clippy::unwrap_used)]
 
+use bombastic_model::protobuf::{Bom, Classification, Component, Metadata, CYCLONEDX_PROTOBUF};
+use bombastic_model::xml::CYCLONEDX_XML;
 use integration_tests::{
     get_response, id, wait_for_package_search_result, wait_for_sbom_search_result, BombasticContext, FileUtility,
     FixtureKind, HasPushFixture, RequestFactory,
 };
+use prost::Message;
 use reqwest::StatusCode;
 use serde_json::{json, Value};
 use test_context::test_context;
@@ -30,6 +33,27 @@ async fn upload_happy_sbom(context: &mut BombasticContext) {
     assert_eq!(input, output);
 }
 
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn fetch_sbom_api_v2(context: &mut BombasticContext) {
+    let input = serde_json::from_str(include_str!("../../bombastic/testdata/my-sbom.json")).unwrap();
+    let id = "test-fetch-v2";
+    context.upload_sbom(id, &input).await;
+    let output: Value = RequestFactory::<_, Value>::new()
+        .with_provider_manager()
+        .get("/api/v2/sbom")
+        .with_query(&[("id", id)])
+        .expect_status(StatusCode::OK)
+        .send(context)
+        .await
+        .1
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(input, output);
+}
+
 #[test_context(BombasticContext)]
 #[tokio::test]
 #[ntest::timeout(60_000)]
@@ -57,7 +81,7 @@ async fn delete_happy_sbom(context: &mut BombasticContext) {
     request
         .clone()
         .get(url)
-        .expect_status(StatusCode::NOT_FOUND)
+        .expect_status(StatusCode::GONE)
         .send(context)
         .await;
     request
@@ -68,6 +92,65 @@ async fn delete_happy_sbom(context: &mut BombasticContext) {
         .await;
 }
 
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn sbom_tombstone(context: &mut BombasticContext) {
+    let input: Value = serde_json::from_str(include_str!("../../bombastic/testdata/my-sbom.json")).unwrap();
+    let id = id("test-tombstone");
+    context.upload_sbom(&id, &input).await;
+    context.delete_sbom(&id).await;
+    get_response(context, &format!("/api/v1/sbom?id={id}"), StatusCode::GONE).await;
+
+    // walkers can't bring a deleted SBOM back
+    RequestFactory::<_, Value>::new()
+        .with_provider_manager()
+        .post("/api/v1/sbom")
+        .with_query(&[("id", id.as_str()), ("source", "walker")])
+        .with_json(&input)
+        .expect_status(StatusCode::CONFLICT)
+        .send(context)
+        .await;
+    get_response(context, &format!("/api/v1/sbom?id={id}"), StatusCode::GONE).await;
+
+    // uploading it through the API stores it again
+    context.upload_sbom(&id, &input).await;
+    get_response(context, &format!("/api/v1/sbom?id={id}"), StatusCode::OK).await;
+}
+
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn sbom_versions(context: &mut BombasticContext) {
+    let mut input: Value = serde_json::from_str(include_str!("../../bombastic/testdata/my-sbom.json")).unwrap();
+    let id = id("test-versions");
+    context.upload_sbom(&id, &input).await;
+    input["serialNumber"] = json!("urn:uuid:00000000-0000-0000-0000-000000000002");
+    context.upload_sbom(&id, &input).await;
+
+    let versions = get_response(context, &format!("/api/v1/sbom/{id}/versions"), StatusCode::OK)
+        .await
+        .unwrap();
+    let versions = versions.as_array().unwrap();
+    assert_eq!(versions.len(), 2);
+    assert_eq!(versions[0]["current"], json!(true));
+    assert_eq!(versions[1]["current"], json!(false));
+
+    // the prior revision is kept as it was stored
+    let revision = versions[1]["revision"].as_str().unwrap();
+    let endpoint = format!("/api/v1/sbom/{id}/versions/{revision}");
+    let prior = get_response(context, &endpoint, StatusCode::OK).await.unwrap();
+    assert_ne!(prior["serialNumber"], input["serialNumber"]);
+    let revision = versions[0]["revision"].as_str().unwrap();
+    let endpoint = format!("/api/v1/sbom/{id}/versions/{revision}");
+    let current = get_response(context, &endpoint, StatusCode::OK).await.unwrap();
+    assert_eq!(current["serialNumber"], input["serialNumber"]);
+
+    // revisions are removed with the SBOM
+    context.delete_sbom(&id).await;
+    get_response(context, &format!("/api/v1/sbom/{id}/versions"), StatusCode::GONE).await;
+}
+
 #[test_context(BombasticContext)]
 #[tokio::test]
 #[ntest::timeout(60_000)]
@@ -157,6 +240,36 @@ async fn bombastic_package_search(context: &mut BombasticContext) {
     assert_eq!(response["result"][0]["document"]["name"], json!("libdnf"));
 }
 
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn fetch_sbom_by_digest(context: &mut BombasticContext) {
+    let request = RequestFactory::<_, Value>::new()
+        .with_provider_manager()
+        .get("/api/v1/sbom");
+    // neither or both an id and a digest, or an invalid digest
+    for query in [
+        &[][..],
+        &[("id", "ubi9"), ("digest", "sha256:0")],
+        &[("digest", "0123456789abcdef")],
+        &[("digest", "sha256:../../index/sbom")],
+    ] {
+        request
+            .clone()
+            .with_query(query)
+            .expect_status(StatusCode::BAD_REQUEST)
+            .send(context)
+            .await;
+    }
+    // not stored by digest
+    let digest = format!("sha256:{}", "0".repeat(64));
+    request
+        .with_query(&[("digest", digest.as_str())][..])
+        .expect_status(StatusCode::NOT_FOUND)
+        .send(context)
+        .await;
+}
+
 #[test_context(BombasticContext)]
 #[tokio::test]
 #[ntest::timeout(30_000)]
@@ -173,6 +286,96 @@ async fn bombastic_bad_search_queries(context: &mut BombasticContext) {
     }
 }
 
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn bombastic_search_limits(context: &mut BombasticContext) {
+    let request = RequestFactory::<_, Value>::new()
+        .with_provider_manager()
+        .get("/api/v1/sbom/search")
+        .expect_status(StatusCode::BAD_REQUEST);
+    for (limit, offset) in [("0", "0"), ("100000", "0"), ("10", "100000000")] {
+        request
+            .clone()
+            .with_query(&[("q", ""), ("limit", limit), ("offset", offset)])
+            .send(context)
+            .await;
+    }
+}
+
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(90_000)]
+async fn bombastic_content_id(context: &mut BombasticContext) {
+    let mut input: Value = serde_json::from_str(include_str!("../../bombastic/testdata/ubi9-sbom.json")).unwrap();
+    // make the content unique, so that the id is too
+    let version = id("test-content-id");
+    input["packages"][617]["versionInfo"] = json!(version);
+
+    let publish = RequestFactory::<&[(&str, &str)], _>::new()
+        .with_provider_manager()
+        .post("/api/v1/sbom")
+        .with_json(&input)
+        .expect_status(StatusCode::CREATED)
+        .as_html();
+
+    let message: String = publish.clone().send(context).await.1.unwrap().try_into().unwrap();
+    let id = message
+        .split_once("id=")
+        .and_then(|(_, rest)| rest.split_once(','))
+        .map(|(id, _)| id.to_string())
+        .unwrap();
+    assert!(id.starts_with("sha256:"), "unexpected id: {id}");
+    context.push_fixture(FixtureKind::Id(id.clone()));
+
+    // publishing the same content again results in the same id
+    let again: String = publish.send(context).await.1.unwrap().try_into().unwrap();
+    assert_eq!(message, again);
+
+    let response = wait_for_sbom_search_result(context, &[("q", format!(r#"id:"{id}""#).as_str())], |response| {
+        response["total"].as_u64().unwrap() > 0
+    })
+    .await;
+    assert_eq!(response["result"][0]["document"]["id"], json!(id));
+}
+
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn bombastic_validate(context: &mut BombasticContext) {
+    let input: Value = serde_json::from_str(include_str!("../../bombastic/testdata/ubi9-sbom.json")).unwrap();
+    let payload: Value = RequestFactory::<&[(&str, &str)], _>::new()
+        .with_provider_user()
+        .post("/api/v1/sbom/validate")
+        .with_json(&input)
+        .expect_status(StatusCode::OK)
+        .send(context)
+        .await
+        .1
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(payload["format"], json!("SPDX/SPDX-2.2"));
+    assert!(payload["findings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|f| f["severity"] != json!("error")));
+
+    let payload: Value = RequestFactory::<&[(&str, &str)], _>::new()
+        .with_provider_user()
+        .post("/api/v1/sbom/validate")
+        .with_json(&json!({}))
+        .expect_status(StatusCode::OK)
+        .send(context)
+        .await
+        .1
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(payload["findings"][0]["rule"], json!("schema"));
+}
+
 #[test_context(BombasticContext)]
 #[tokio::test]
 #[ntest::timeout(120_000)]
@@ -240,7 +443,10 @@ async fn sbom_invalid_type(context: &mut BombasticContext) {
         .with_headers(&[("Content-Type", "application/xml")])
         .with_body(b"<foo/>".as_slice())
         .expect_status(StatusCode::BAD_REQUEST)
-        .expect_headers(&[("accept", "application/json")])
+        .expect_headers(&[(
+            "accept",
+            "application/json, application/x.cyclonedx+protobuf, application/vnd.cyclonedx+xml",
+        )])
         .send(context)
         .await;
 }
@@ -263,6 +469,31 @@ async fn valid_bzip2_encoded(context: &mut BombasticContext) {
         .await;
 }
 
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn valid_bzip2_detected(context: &mut BombasticContext) {
+    let sbom = include_bytes!("../../bombastic/testdata/ubi8-valid.json.bz2");
+    let id = "valid_bzip2_detected";
+    context.push_fixture(FixtureKind::Id(String::from(id)));
+    // sent as it is, without content encoding
+    RequestFactory::<_, Value>::new()
+        .with_provider_manager()
+        .post("/api/v1/sbom")
+        .with_query(&[("id", id)])
+        .with_headers(&[("Content-Type", "application/json")])
+        .with_body(sbom.as_slice())
+        .expect_status(StatusCode::CREATED)
+        .send(context)
+        .await;
+
+    let expected: Value = serde_json::from_slice(include_bytes!("../../bombastic/testdata/ubi8-valid.json")).unwrap();
+    let response: Value = get_response(context, &format!("api/v1/sbom?id={id}"), StatusCode::OK)
+        .await
+        .into();
+    assert_eq!(expected, response);
+}
+
 #[test_context(BombasticContext)]
 #[tokio::test]
 #[ntest::timeout(60_000)]
@@ -291,7 +522,7 @@ async fn sbom_invalid_encoding(context: &mut BombasticContext) {
         .with_headers(&[("Content-Type", "application/json"), ("Content-Encoding", "braille")])
         .with_body(b"{}".as_slice())
         .expect_status(StatusCode::BAD_REQUEST)
-        .expect_headers(&[("accept-encoding", "bzip2, zstd")])
+        .expect_headers(&[("accept-encoding", "bzip2, gzip, zstd")])
         .send(context)
         .await;
 }
@@ -447,3 +678,267 @@ async fn get_sbom_with_missing_id(context: &mut BombasticContext) {
     let api_end_point = "api/v1/sbom?ID=test";
     get_response(context, &api_end_point, StatusCode::BAD_REQUEST).await;
 }
+
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn sbom_mget(context: &mut BombasticContext) {
+    let input: Value = serde_json::from_str(include_str!("../../bombastic/testdata/my-sbom.json")).unwrap();
+    let id = "test-mget";
+    context.upload_sbom(id, &input).await;
+
+    let body = json!({"ids": [id, "test-mget-missing"]});
+    let output: String = RequestFactory::<(), _>::new()
+        .with_provider_manager()
+        .post("/api/v1/sbom/_mget")
+        .with_json(&body)
+        .as_html()
+        .expect_status(StatusCode::OK)
+        .send(context)
+        .await
+        .1
+        .unwrap()
+        .try_into()
+        .unwrap();
+
+    let entries: Vec<Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["id"], json!(id));
+    assert_eq!(entries[0]["status"], json!(200));
+    assert_eq!(entries[0]["document"], input);
+    assert_eq!(entries[1]["id"], json!("test-mget-missing"));
+    assert_eq!(entries[1]["status"], json!(404));
+}
+
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn sbom_protobuf(context: &mut BombasticContext) {
+    let id = id("test-protobuf");
+    let purl = format!("pkg:generic/{id}@1.0.0");
+    let bom = Bom {
+        spec_version: "1.5".to_string(),
+        version: Some(1),
+        serial_number: Some(format!("urn:uuid:{}", uuid::Uuid::new_v4())),
+        metadata: Some(Metadata {
+            component: Some(Component {
+                r#type: Classification::Application as i32,
+                bom_ref: Some(purl.clone()),
+                name: id.clone(),
+                version: "1.0.0".to_string(),
+                purl: Some(purl.clone()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+    .encode_to_vec();
+
+    context.push_fixture(FixtureKind::Id(id.clone()));
+    RequestFactory::<_, Value>::new()
+        .with_provider_manager()
+        .post("/api/v1/sbom")
+        .with_query(&[("id", id.as_str())])
+        .with_headers(&[("Content-Type", CYCLONEDX_PROTOBUF)])
+        .with_body(bom.as_slice())
+        .expect_status(StatusCode::CREATED)
+        .send(context)
+        .await;
+
+    // stored as JSON
+    let sbom = get_response(context, &format!("/api/v1/sbom?id={id}"), StatusCode::OK)
+        .await
+        .unwrap();
+    assert_eq!(sbom["metadata"]["component"]["purl"], json!(purl));
+
+    // the original is available when preferred
+    RequestFactory::<_, Value>::new()
+        .with_provider_manager()
+        .get("/api/v1/sbom")
+        .with_query(&[("id", id.as_str())])
+        .with_headers(&[("Accept", CYCLONEDX_PROTOBUF)])
+        .expect_status(StatusCode::OK)
+        .expect_headers(&[("content-type", CYCLONEDX_PROTOBUF)])
+        .as_html()
+        .send(context)
+        .await;
+
+    let response = wait_for_sbom_search_result(context, &[("q", format!(r#"id:"{id}""#).as_str())], |response| {
+        response["total"].as_u64().unwrap() > 0
+    })
+    .await;
+    assert_eq!(response["result"][0]["document"]["id"], json!(id));
+
+    // publishing it again as JSON removes the outdated original
+    context.upload_sbom(&id, &sbom).await;
+    RequestFactory::<_, Value>::new()
+        .with_provider_manager()
+        .get("/api/v1/sbom")
+        .with_query(&[("id", id.as_str())])
+        .with_headers(&[("Accept", CYCLONEDX_PROTOBUF)])
+        .expect_status(StatusCode::OK)
+        .expect_headers(&[("content-type", "application/json")])
+        .send(context)
+        .await;
+}
+
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn sbom_xml(context: &mut BombasticContext) {
+    let id = id("test-xml");
+    let purl = format!("pkg:generic/{id}@1.0.0");
+    let bom = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<bom xmlns="http://cyclonedx.org/schema/bom/1.4" serialNumber="urn:uuid:{}" version="1">
+  <metadata>
+    <component type="application" bom-ref="{purl}">
+      <name>{id}</name>
+      <version>1.0.0</version>
+      <purl>{purl}</purl>
+    </component>
+  </metadata>
+</bom>
+"#,
+        uuid::Uuid::new_v4()
+    );
+
+    context.push_fixture(FixtureKind::Id(id.clone()));
+    RequestFactory::<_, Value>::new()
+        .with_provider_manager()
+        .post("/api/v1/sbom")
+        .with_query(&[("id", id.as_str())])
+        .with_headers(&[("Content-Type", CYCLONEDX_XML)])
+        .with_body(bom.as_bytes())
+        .expect_status(StatusCode::CREATED)
+        .send(context)
+        .await;
+
+    // stored as JSON, of the same spec version
+    let sbom = get_response(context, &format!("/api/v1/sbom?id={id}"), StatusCode::OK)
+        .await
+        .unwrap();
+    assert_eq!(sbom["specVersion"], json!("1.4"));
+    assert_eq!(sbom["metadata"]["component"]["purl"], json!(purl));
+
+    let response = wait_for_sbom_search_result(context, &[("q", format!(r#"id:"{id}""#).as_str())], |response| {
+        response["total"].as_u64().unwrap() > 0
+    })
+    .await;
+    assert_eq!(response["result"][0]["document"]["id"], json!(id));
+}
+
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn sbom_upload_in_parts(context: &mut BombasticContext) {
+    let id = id("test-upload");
+    let data = include_bytes!("../../bombastic/testdata/my-sbom.json");
+
+    context.push_fixture(FixtureKind::Id(id.clone()));
+    let started: Value = RequestFactory::<_, Value>::new()
+        .with_provider_manager()
+        .post("/api/v1/sbom/upload")
+        .with_query(&[("id", id.as_str())])
+        .with_headers(&[("Content-Type", "application/json")])
+        .expect_status(StatusCode::CREATED)
+        .send(context)
+        .await
+        .1
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let token = started["token"].as_str().unwrap();
+    assert!(started["chunk_limit"].as_u64().unwrap() > 0);
+
+    // a part can be uploaded again, the last upload wins
+    let endpoint = format!("/api/v1/sbom/upload/{token}/1");
+    RequestFactory::<(), ()>::new()
+        .with_provider_manager()
+        .put(&endpoint)
+        .with_body(&data[..10])
+        .expect_status(StatusCode::OK)
+        .send(context)
+        .await;
+    let part: Value = RequestFactory::<(), ()>::new()
+        .with_provider_manager()
+        .put(&endpoint)
+        .with_body(&data[..])
+        .expect_status(StatusCode::OK)
+        .send(context)
+        .await
+        .1
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(part["part"], json!(1));
+
+    let completion = json!({ "parts": [part] });
+    RequestFactory::<(), _>::new()
+        .with_provider_manager()
+        .post(&format!("/api/v1/sbom/upload/{token}"))
+        .with_json(&completion)
+        .expect_status(StatusCode::CREATED)
+        .send(context)
+        .await;
+
+    // the upload is gone once completed
+    RequestFactory::<(), _>::new()
+        .with_provider_manager()
+        .post(&format!("/api/v1/sbom/upload/{token}"))
+        .with_json(&completion)
+        .expect_status(StatusCode::NOT_FOUND)
+        .send(context)
+        .await;
+
+    let sbom = get_response(context, &format!("/api/v1/sbom?id={id}"), StatusCode::OK)
+        .await
+        .unwrap();
+    let expected: Value = serde_json::from_slice(data).unwrap();
+    assert_eq!(sbom, expected);
+}
+
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn sbom_upload_aborted(context: &mut BombasticContext) {
+    let id = id("test-upload-aborted");
+
+    let started: Value = RequestFactory::<_, Value>::new()
+        .with_provider_manager()
+        .post("/api/v1/sbom/upload")
+        .with_query(&[("id", id.as_str())])
+        .with_headers(&[("Content-Type", "application/json")])
+        .expect_status(StatusCode::CREATED)
+        .send(context)
+        .await
+        .1
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let endpoint = format!("/api/v1/sbom/upload/{}", started["token"].as_str().unwrap());
+
+    RequestFactory::<(), ()>::new()
+        .with_provider_manager()
+        .put(&format!("{endpoint}/0"))
+        .with_body(&b"{}"[..])
+        .expect_status(StatusCode::BAD_REQUEST)
+        .send(context)
+        .await;
+
+    RequestFactory::<(), ()>::new()
+        .with_provider_manager()
+        .delete(&endpoint)
+        .expect_status(StatusCode::NO_CONTENT)
+        .send(context)
+        .await;
+    RequestFactory::<(), ()>::new()
+        .with_provider_manager()
+        .delete(&endpoint)
+        .expect_status(StatusCode::NOT_FOUND)
+        .send(context)
+        .await;
+
+    get_response(context, &format!("/api/v1/sbom?id={id}"), StatusCode::NOT_FOUND).await;
+}
@@ -0,0 +1,116 @@
+#![allow(clippy::unwrap_used)]
+
+//! Replays the recorded request/response pairs of the public endpoints, failing on breaking changes of their responses.
+//!
+//! Set `TRUST_RECORD_CONTRACTS=true` to record the current responses after an intended change.
+
+use integration_tests::{
+    id, shape_breaks, verify_contracts, wait_for_sbom_search_result, wait_for_vex_search_result, BombasticContext,
+    ExhortContext, SpogContext, VexinationContext,
+};
+use serde_json::{json, Value};
+use test_context::test_context;
+
+fn sbom(key: &str) -> Value {
+    let mut input: Value = serde_json::from_str(include_str!("../../bombastic/testdata/ubi9-sbom.json")).unwrap();
+    // We use the unique id as the SBOM's version, for searching
+    input["packages"][617]["versionInfo"] = json!(key);
+    input
+}
+
+fn vex(key: &str) -> Value {
+    let mut input: Value = serde_json::from_str(include_str!("../../vexination/testdata/rhsa-2023_1441.json")).unwrap();
+    input["document"]["tracking"]["id"] = json!(key);
+    input
+}
+
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(90_000)]
+async fn contracts_bombastic(context: &mut BombasticContext) {
+    let key = id("test-contracts");
+    context.upload_sbom(&key, &sbom(&key)).await;
+    wait_for_sbom_search_result(context, &[("q", &key)], |response| {
+        response["total"].as_u64().unwrap() > 0
+    })
+    .await;
+
+    verify_contracts(context, "bombastic", &[("sbom", &key)]).await;
+}
+
+#[test_context(VexinationContext)]
+#[tokio::test]
+#[ntest::timeout(90_000)]
+async fn contracts_vexination(context: &mut VexinationContext) {
+    let key = id("test-contracts");
+    context.upload_vex(&vex(&key)).await;
+    wait_for_vex_search_result(context, &[("q", format!("\"{key}\""))], |response| {
+        response["total"].as_u64().unwrap() > 0
+    })
+    .await;
+
+    verify_contracts(context, "vexination", &[("advisory", &key)]).await;
+}
+
+#[test_context(SpogContext)]
+#[tokio::test]
+#[ntest::timeout(120_000)]
+async fn contracts_spog(context: &mut SpogContext) {
+    let sbom_key = id("test-contracts");
+    context.bombastic.upload_sbom(&sbom_key, &sbom(&sbom_key)).await;
+    wait_for_sbom_search_result(&context.bombastic, &[("q", &sbom_key)], |response| {
+        response["total"].as_u64().unwrap() > 0
+    })
+    .await;
+
+    let vex_key = id("test-contracts");
+    context.vexination.upload_vex(&vex(&vex_key)).await;
+    wait_for_vex_search_result(&context.vexination, &[("q", format!("\"{vex_key}\""))], |response| {
+        response["total"].as_u64().unwrap() > 0
+    })
+    .await;
+
+    verify_contracts(context, "spog", &[("sbom", &sbom_key), ("advisory", &vex_key)]).await;
+}
+
+/// Exhort requires GUAC, so its contracts are only replayed against a remote instance.
+#[test_with::env(EXHORT_URL)]
+#[test_context(ExhortContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn contracts_exhort(context: &mut ExhortContext) {
+    verify_contracts(context, "exhort", &[]).await;
+}
+
+#[test]
+fn contracts_shape() {
+    let recorded = json!({
+        "total": 1,
+        "result": [{"id": "a", "purl": null, "tags": ["x"]}],
+    });
+
+    let added = json!({
+        "total": 2,
+        "result": [{"id": "a", "purl": "pkg:rpm/a", "tags": [], "new": true}, {"id": "b", "purl": null, "tags": ["y"]}],
+        "facets": {},
+    });
+    assert!(shape_breaks(&recorded, &added).is_empty());
+    assert!(shape_breaks(&recorded, &json!({"total": 0, "result": []})).is_empty());
+
+    let broken = json!({
+        "total": "2",
+        "result": [{"id": "a", "tags": ["x"]}, {"id": 2, "tags": [1]}],
+    });
+    let mut breaks = shape_breaks(&recorded, &broken);
+    breaks.sort();
+    assert_eq!(
+        breaks,
+        vec![
+            "$.result[0].purl was removed",
+            "$.result[1].id: string became number",
+            "$.result[1].purl was removed",
+            "$.result[1].tags[0]: string became number",
+            "$.total: number became string",
+        ]
+    );
+}
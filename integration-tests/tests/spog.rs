@@ -79,6 +79,30 @@ async fn spog_search_forward_vexination(context: &mut SpogContext) {
         .await;
 }
 
+/// The advisory bundle is bounded in the number of advisories it can contain.
+#[test_context(SpogContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn spog_advisory_bundle_limits(context: &mut SpogContext) {
+    let ids = (0..101).map(|i| format!("advisory-{i}")).collect::<Vec<_>>().join(",");
+
+    RequestFactory::<_, Value>::new()
+        .with_provider_user()
+        .get("/api/v1/advisory/bundle")
+        .with_query(&[("ids", ids.as_str())])
+        .expect_status(StatusCode::BAD_REQUEST)
+        .send(context)
+        .await;
+
+    RequestFactory::<_, Value>::new()
+        .with_provider_user()
+        .get("/api/v1/advisory/bundle")
+        .with_query(&[("ids", "")])
+        .expect_status(StatusCode::BAD_REQUEST)
+        .send(context)
+        .await;
+}
+
 #[test_with::env(CRDA_URL)]
 #[test_context(SpogContext)]
 #[tokio::test]
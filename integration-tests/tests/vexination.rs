@@ -227,3 +227,83 @@ async fn get_vex_invalid_advisory(context: &mut VexinationContext) {
     let api_end_point = "api/v1/vex?advisory=invalid_vex";
     get_response(context, &api_end_point, StatusCode::NOT_FOUND).await;
 }
+
+#[test_context(VexinationContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn vex_revisions(context: &mut VexinationContext) {
+    let input: Value = serde_json::from_str(include_str!("../../vexination/testdata/rhsa-2023_1441.json")).unwrap();
+    context.upload_vex(&input).await;
+    let id = input["document"]["tracking"]["id"].as_str().unwrap();
+
+    let payload: Value = RequestFactory::<_, Value>::new()
+        .with_provider_manager()
+        .get("/api/v1/vex/revisions")
+        .with_query(&[("advisory", &id)])
+        .expect_status(StatusCode::OK)
+        .send(context)
+        .await
+        .1
+        .unwrap()
+        .try_into()
+        .unwrap();
+
+    assert_eq!(payload["advisory_id"], json!(id));
+    assert_eq!(payload["version"], json!("1"));
+    assert_eq!(payload["status"], json!("final"));
+    assert_eq!(payload["revisions"].as_array().unwrap().len(), 1);
+
+    let api_end_point = "api/v1/vex/revisions?advisory=invalid_vex";
+    get_response(context, &api_end_point, StatusCode::NOT_FOUND).await;
+}
+
+#[test_context(VexinationContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn vex_timeline(context: &mut VexinationContext) {
+    let mut input: Value = serde_json::from_str(include_str!("../../vexination/testdata/rhsa-2023_1441.json")).unwrap();
+    let key = id("test-timeline-vex");
+    input["document"]["tracking"]["id"] = json!(key);
+    context.upload_vex(&input).await;
+
+    let request = RequestFactory::<_, Value>::new()
+        .with_provider_manager()
+        .get("/api/v1/vex/timeline")
+        .with_query(&[("cve", "cve-2023-0286")])
+        .expect_status(StatusCode::OK);
+
+    // wait for the advisory to be indexed
+    let events = loop {
+        let payload: Value = request.clone().send(context).await.1.unwrap().try_into().unwrap();
+        assert_eq!(payload["cve"], json!("CVE-2023-0286"));
+
+        let events: Vec<Value> = payload["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|event| event["advisory_id"] == json!(key))
+            .cloned()
+            .collect();
+        if !events.is_empty() {
+            break events;
+        }
+        tokio::time::sleep(Duration::from_secs(4)).await;
+    };
+
+    let kinds: Vec<_> = events.iter().map(|event| event["kind"].as_str().unwrap()).collect();
+    assert!(kinds.contains(&"initialRelease"));
+    assert!(kinds.contains(&"revision"));
+    assert!(kinds.contains(&"status"));
+
+    for cve in [r#"CVE-2023-0286" OR "#, "CVE-2023", "2023-0286"] {
+        RequestFactory::<_, Value>::new()
+            .with_provider_manager()
+            .get("/api/v1/vex/timeline")
+            .with_query(&[("cve", cve)])
+            .expect_status(StatusCode::BAD_REQUEST)
+            .send(context)
+            .await;
+    }
+
+    context.delete_vex(&key).await;
+}
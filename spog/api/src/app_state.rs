@@ -2,7 +2,9 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use futures::{Stream, StreamExt, TryStreamExt};
 use http::StatusCode;
+use serde::de::DeserializeOwned;
 use tracing::instrument;
 
 use crate::db::Db;
@@ -181,6 +183,28 @@ impl AppState {
         Ok(response.json::<vexination_model::prelude::StatusResult>().await?)
     }
 
+    #[instrument(skip(self, provider), err)]
+    pub async fn get_vex_timeline(
+        &self,
+        cve: &str,
+        provider: &dyn TokenProvider,
+    ) -> Result<vexination_model::timeline::CveTimeline, Error> {
+        let url = self.vexination.join("/api/v1/vex/timeline")?;
+        let response = self
+            .client
+            .get(url)
+            .query(&[("cve", cve)])
+            .propagate_current_context()
+            .inject_token(provider)
+            .await?
+            .send()
+            .await?
+            .or_status_error()
+            .await?;
+
+        Ok(response.json::<vexination_model::prelude::CveTimeline>().await?)
+    }
+
     #[instrument(skip(self, provider), err)]
     pub async fn get_sbom_status(
         &self,
@@ -203,6 +227,30 @@ impl AppState {
         Ok(response.json::<bombastic_model::prelude::StatusResult>().await?)
     }
 
+    #[instrument(skip(self, provider), err)]
+    pub async fn get_sbom_changes(
+        &self,
+        since: i64,
+        limit: usize,
+        provider: &dyn TokenProvider,
+    ) -> Result<bombastic_model::changes::Changes, Error> {
+        let url = self.bombastic.join("/api/v1/sbom/changes")?;
+        let response = self
+            .client
+            .get(url)
+            .query(&[("since", since)])
+            .query(&[("limit", limit)])
+            .propagate_current_context()
+            .inject_token(provider)
+            .await?
+            .send()
+            .await?
+            .or_status_error()
+            .await?;
+
+        Ok(response.json::<bombastic_model::changes::Changes>().await?)
+    }
+
     #[instrument(skip(self, provider), err)]
     pub async fn search_vex(
         &self,
@@ -229,6 +277,75 @@ impl AppState {
 
         Ok(response.json::<vexination_model::prelude::SearchResult>().await?)
     }
+
+    /// Stream all the packages matching a query, which unlike a search isn't limited to a page.
+    #[instrument(skip(self, provider), err)]
+    pub async fn export_packages(
+        &self,
+        q: &str,
+        provider: &dyn TokenProvider,
+    ) -> Result<impl Stream<Item = Result<bombastic_model::prelude::SearchPackageHit, Error>>, Error> {
+        let url = self.bombastic.join("/api/v1/package/search/export")?;
+        let response = self
+            .client
+            .get(url)
+            .query(&[("q", q)])
+            .propagate_current_context()
+            .inject_token(provider)
+            .await?
+            .send()
+            .await?
+            .or_status_error()
+            .await?;
+
+        Ok(ndjson(response))
+    }
+
+    /// Stream all the advisories matching a query, which unlike a search isn't limited to a page.
+    #[instrument(skip(self, provider), err)]
+    pub async fn export_vex(
+        &self,
+        q: &str,
+        provider: &dyn TokenProvider,
+    ) -> Result<impl Stream<Item = Result<vexination_model::prelude::SearchHit, Error>>, Error> {
+        let url = self.vexination.join("/api/v1/vex/search/export")?;
+        let response = self
+            .client
+            .get(url)
+            .query(&[("q", q)])
+            .propagate_current_context()
+            .inject_token(provider)
+            .await?
+            .send()
+            .await?
+            .or_status_error()
+            .await?;
+
+        Ok(ndjson(response))
+    }
+}
+
+/// The values of a newline delimited JSON response, skipping the heartbeat comments sent while exporting.
+fn ndjson<T: DeserializeOwned>(response: reqwest::Response) -> impl Stream<Item = Result<T, Error>> {
+    let chunks = Box::pin(response.bytes_stream().map_err(Error::from).fuse());
+    futures::stream::try_unfold((chunks, Vec::new()), |(mut chunks, mut buf)| async move {
+        loop {
+            if let Some(end) = buf.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buf.drain(..=end).collect();
+                let line = &line[..end];
+                if line.is_empty() || line.starts_with(b"#") {
+                    continue;
+                }
+                return Ok(Some((serde_json::from_slice(line)?, (chunks, buf))));
+            }
+            match chunks.try_next().await? {
+                Some(chunk) => buf.extend_from_slice(&chunk),
+                None if buf.is_empty() => return Ok(None),
+                // the last line may not be terminated
+                None => buf.push(b'\n'),
+            }
+        }
+    })
 }
 
 #[async_trait]
@@ -266,3 +383,28 @@ impl ResponseError for reqwest::Response {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn ndjson_values() {
+        let body = "{\"id\":1}\n# heartbeat\n\n{\"id\":2}\n{\"id\":3}";
+        let response = reqwest::Response::from(http::Response::new(body));
+        let values: Vec<serde_json::Value> = ndjson(response).try_collect().await.unwrap();
+        assert_eq!(
+            values,
+            vec![
+                serde_json::json!({"id": 1}),
+                serde_json::json!({"id": 2}),
+                serde_json::json!({"id": 3})
+            ]
+        );
+
+        let response = reqwest::Response::from(http::Response::new("{\"id\":1}\nnot json\n"));
+        let values: Vec<Result<serde_json::Value, Error>> = ndjson(response).collect().await;
+        assert_eq!(values.len(), 2);
+        assert!(matches!(values[1], Err(Error::Serde(_))));
+    }
+}
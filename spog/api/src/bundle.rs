@@ -0,0 +1,195 @@
+//! Writing zip archives incrementally.
+//!
+//! As the content of each entry is known when it is added, the local headers can be written upfront and don't
+//! need to be patched afterwards. This allows handing out the archive while it is being written, which the zip
+//! writers we have available can't do, as they require the target to be seekable.
+
+use flate2::{write::DeflateEncoder, Compression};
+use std::io::{self, Write};
+
+const LOCAL_FILE_HEADER: u32 = 0x04034b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+
+/// Version 2.0, required for deflate
+const VERSION_NEEDED: u16 = 20;
+/// Unix, version 2.0
+const VERSION_MADE_BY: u16 = (3 << 8) | VERSION_NEEDED;
+/// Names are encoded as UTF-8
+const FLAGS: u16 = 1 << 11;
+const METHOD_DEFLATE: u16 = 8;
+/// 1980-01-01 00:00, the earliest timestamp zip can represent
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = (1 << 5) | 1;
+/// Regular file, mode 0644
+const EXTERNAL_ATTRIBUTES: u32 = 0o100644 << 16;
+
+struct Entry {
+    name: String,
+    crc: u32,
+    compressed: u32,
+    uncompressed: u32,
+    offset: u32,
+}
+
+/// A zip archive, of which the data written so far can be taken out while adding more entries.
+///
+/// Archives are limited to what fits without the zip64 extensions, which is plenty for a bundle of documents.
+#[derive(Default)]
+pub struct ZipStream {
+    buffer: Vec<u8>,
+    written: u64,
+    entries: Vec<Entry>,
+}
+
+impl ZipStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file to the archive, compressing its data.
+    pub fn append(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        if self.entries.len() >= u16::MAX as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "too many entries"));
+        }
+        let name_len = u16::try_from(name.len()).map_err(|_| too_large("name"))?;
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        let compressed = encoder.finish()?;
+
+        let entry = Entry {
+            name: name.to_string(),
+            crc: crc32fast::hash(data),
+            compressed: u32::try_from(compressed.len()).map_err(|_| too_large("entry"))?,
+            uncompressed: u32::try_from(data.len()).map_err(|_| too_large("entry"))?,
+            offset: self.offset()?,
+        };
+
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend_from_slice(&LOCAL_FILE_HEADER.to_le_bytes());
+        header.extend_from_slice(&VERSION_NEEDED.to_le_bytes());
+        header.extend_from_slice(&FLAGS.to_le_bytes());
+        header.extend_from_slice(&METHOD_DEFLATE.to_le_bytes());
+        header.extend_from_slice(&DOS_TIME.to_le_bytes());
+        header.extend_from_slice(&DOS_DATE.to_le_bytes());
+        header.extend_from_slice(&entry.crc.to_le_bytes());
+        header.extend_from_slice(&entry.compressed.to_le_bytes());
+        header.extend_from_slice(&entry.uncompressed.to_le_bytes());
+        header.extend_from_slice(&name_len.to_le_bytes());
+        // no extra fields
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+
+        self.write(&header);
+        self.write(&compressed);
+        self.entries.push(entry);
+
+        Ok(())
+    }
+
+    /// Take the data written so far, leaving the archive writable.
+    pub fn drain(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.buffer)
+    }
+
+    /// Write the central directory, returning the remaining data of the archive.
+    pub fn finish(mut self) -> io::Result<Vec<u8>> {
+        let start = self.offset()?;
+        // checked when appending the entries
+        let count = self.entries.len() as u16;
+
+        for entry in std::mem::take(&mut self.entries) {
+            let mut header = Vec::with_capacity(46 + entry.name.len());
+            header.extend_from_slice(&CENTRAL_DIRECTORY_HEADER.to_le_bytes());
+            header.extend_from_slice(&VERSION_MADE_BY.to_le_bytes());
+            header.extend_from_slice(&VERSION_NEEDED.to_le_bytes());
+            header.extend_from_slice(&FLAGS.to_le_bytes());
+            header.extend_from_slice(&METHOD_DEFLATE.to_le_bytes());
+            header.extend_from_slice(&DOS_TIME.to_le_bytes());
+            header.extend_from_slice(&DOS_DATE.to_le_bytes());
+            header.extend_from_slice(&entry.crc.to_le_bytes());
+            header.extend_from_slice(&entry.compressed.to_le_bytes());
+            header.extend_from_slice(&entry.uncompressed.to_le_bytes());
+            // checked when appending the entry
+            header.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            // no extra fields, no comment, starting on disk 0, no internal attributes
+            header.extend_from_slice(&[0; 8]);
+            header.extend_from_slice(&EXTERNAL_ATTRIBUTES.to_le_bytes());
+            header.extend_from_slice(&entry.offset.to_le_bytes());
+            header.extend_from_slice(entry.name.as_bytes());
+            self.write(&header);
+        }
+
+        let end = self.offset()?;
+
+        let mut footer = Vec::with_capacity(22);
+        footer.extend_from_slice(&END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+        // single disk
+        footer.extend_from_slice(&[0; 4]);
+        footer.extend_from_slice(&count.to_le_bytes());
+        footer.extend_from_slice(&count.to_le_bytes());
+        footer.extend_from_slice(&(end - start).to_le_bytes());
+        footer.extend_from_slice(&start.to_le_bytes());
+        // no comment
+        footer.extend_from_slice(&0u16.to_le_bytes());
+        self.write(&footer);
+
+        Ok(self.buffer)
+    }
+
+    fn write(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+        self.written += data.len() as u64;
+    }
+
+    fn offset(&self) -> io::Result<u32> {
+        u32::try_from(self.written).map_err(|_| too_large("archive"))
+    }
+}
+
+fn too_large(what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("{what} too large for a zip archive"),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::{Cursor, Read};
+
+    #[test]
+    fn roundtrip() {
+        let mut archive = ZipStream::new();
+        let mut data = Vec::new();
+
+        archive.append("first.json", br#"{"document": 1}"#).unwrap();
+        data.extend(archive.drain());
+        archive.append("second.json", &[b'x'; 10_000]).unwrap();
+        archive.append("errors.txt", b"").unwrap();
+        data.extend(archive.drain());
+        data.extend(archive.finish().unwrap());
+
+        let mut zip = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+        assert_eq!(zip.len(), 3);
+
+        let mut content = String::new();
+        zip.by_name("first.json").unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, r#"{"document": 1}"#);
+
+        let mut content = Vec::new();
+        zip.by_name("second.json").unwrap().read_to_end(&mut content).unwrap();
+        assert_eq!(content, vec![b'x'; 10_000]);
+
+        assert_eq!(zip.by_name("errors.txt").unwrap().size(), 0);
+    }
+
+    #[test]
+    fn empty() {
+        let data = ZipStream::new().finish().unwrap();
+        let zip = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+        assert_eq!(zip.len(), 0);
+    }
+}
@@ -0,0 +1,286 @@
+//! Caching of data derived from documents of other services.
+//!
+//! Cached entries are invalidated when the indexers report changed documents on the event bus, so that entries can
+//! be kept without relying on short expiration times.
+
+use spog_model::prelude::SbomReport;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use trustification_auth::{authenticator::user::UserInformation, authorizer::Authorizer, Permission};
+use trustification_event_bus::{Error as BusError, EventBus, EventBusConfig};
+use trustification_storage::{Storage, StorageEvent};
+use vexination_model::prelude::Tlp;
+
+#[derive(Clone, Debug, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Cache")]
+pub struct CacheConfig {
+    /// Cache derived data, invalidating it using events from the indexers
+    #[arg(long = "cache-enabled", env = "CACHE_ENABLED", default_value_t = false)]
+    pub enabled: bool,
+
+    /// Maximum number of entries of each cache
+    #[arg(long = "cache-max-entries", env = "CACHE_MAX_ENTRIES", default_value_t = 1000)]
+    pub max_entries: usize,
+
+    /// Topic of SBOMs being indexed
+    #[arg(
+        long = "sbom-indexed-topic",
+        env = "SBOM_INDEXED_TOPIC",
+        default_value = "sbom-indexed"
+    )]
+    pub sbom_indexed_topic: String,
+
+    /// Topic of advisories being indexed
+    #[arg(long = "vex-indexed-topic", env = "VEX_INDEXED_TOPIC", default_value = "vex-indexed")]
+    pub vex_indexed_topic: String,
+
+    #[command(flatten)]
+    pub bus: EventBusConfig,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: 1000,
+            sbom_indexed_topic: "sbom-indexed".into(),
+            vex_indexed_topic: "vex-indexed".into(),
+            bus: Default::default(),
+        }
+    }
+}
+
+/// A bounded cache, which doesn't store anything when disabled.
+pub struct Cache<K, V> {
+    max_entries: usize,
+    entries: Mutex<HashMap<K, V>>,
+}
+
+impl<K, V> Cache<K, V>
+where
+    K: Clone + Hash + Eq,
+    V: Clone,
+{
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            entries: Default::default(),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.entries.lock().expect("cache lock poisoned").get(key).cloned()
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        if self.max_entries == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().expect("cache lock poisoned");
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            // evict an arbitrary entry, we don't track usage
+            if let Some(evict) = entries.keys().next().cloned() {
+                entries.remove(&evict);
+            }
+        }
+        entries.insert(key, value);
+    }
+
+    /// Remove all entries matching the predicate.
+    pub fn invalidate(&self, f: impl Fn(&K) -> bool) {
+        self.entries.lock().expect("cache lock poisoned").retain(|k, _| !f(k));
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().expect("cache lock poisoned").clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().expect("cache lock poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The documents a user may read.
+///
+/// Data derived using the credentials of a user depends on what the user is allowed to see, so it is only shared
+/// between users with the same scope.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AccessScope {
+    sbom: bool,
+    vex: bool,
+    amber: bool,
+    red: bool,
+}
+
+impl AccessScope {
+    pub fn of(authorizer: &Authorizer, user: &UserInformation) -> Self {
+        Self {
+            sbom: authorizer.allows(user, Permission::ReadSbom),
+            vex: authorizer.allows(user, Permission::ReadVex),
+            amber: Tlp::Amber.may_read(authorizer, user),
+            red: Tlp::Red.may_read(authorizer, user),
+        }
+    }
+}
+
+/// Key of a vulnerability report, including the parameters it was requested with.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ReportKey {
+    pub id: String,
+    pub offset: Option<i64>,
+    pub limit: Option<i64>,
+    pub retrieve_remediation: Option<bool>,
+    pub scope: AccessScope,
+}
+
+/// Data derived from SBOMs and advisories.
+pub struct DerivedCache {
+    /// Vulnerability reports by SBOM
+    pub reports: Cache<ReportKey, Arc<SbomReport>>,
+    /// Number of advisories correlated to an SBOM, visible within a scope
+    pub sbom_advisories: Cache<(String, AccessScope), u64>,
+    /// Number of advisories related to a CVE
+    pub cve_advisories: Cache<String, usize>,
+}
+
+impl DerivedCache {
+    pub fn new(config: &CacheConfig) -> Self {
+        let max_entries = if config.enabled { config.max_entries } else { 0 };
+        Self {
+            reports: Cache::new(max_entries),
+            sbom_advisories: Cache::new(max_entries),
+            cve_advisories: Cache::new(max_entries),
+        }
+    }
+
+    /// An SBOM was added, changed, or removed.
+    pub fn sbom_changed(&self, id: &str) {
+        log::debug!("Invalidating cached data of SBOM: {id}");
+        self.reports.invalidate(|key| key.id == id);
+        self.sbom_advisories.invalidate(|(key, _)| key == id);
+    }
+
+    /// An advisory was added, changed, or removed.
+    ///
+    /// We can't tell which CVEs or SBOMs the advisory was related to before the change, so all data derived from
+    /// advisories gets dropped.
+    pub fn advisory_changed(&self, id: &str) {
+        log::debug!("Invalidating cached data derived from advisories, caused by: {id}");
+        self.reports.clear();
+        self.sbom_advisories.clear();
+        self.cve_advisories.clear();
+    }
+}
+
+/// Invalidate the cache based on the events from the indexers.
+pub async fn run(cache: Arc<DerivedCache>, bus: Arc<EventBus>, config: CacheConfig) -> anyhow::Result<()> {
+    let topics = [config.sbom_indexed_topic.as_str(), config.vex_indexed_topic.as_str()];
+    let consumer = bus.subscribe("spog-api-cache", &topics).await?;
+    log::info!("Invalidating cached data using events from: {topics:?}");
+
+    loop {
+        match consumer.next().await {
+            Ok(Some(event)) => {
+                let sbom = event.topic() == config.sbom_indexed_topic;
+                match event.payload().map(serde_json::from_slice::<StorageEvent>) {
+                    Some(Ok(data)) => {
+                        for record in data.records {
+                            match Storage::key_from_event(&record) {
+                                Ok((_, key)) if sbom => cache.sbom_changed(&key),
+                                Ok((_, key)) => cache.advisory_changed(&key),
+                                Err(e) => log::warn!("Error decoding event key, skipping: {e:?}"),
+                            }
+                        }
+                    }
+                    Some(Err(e)) => {
+                        // we don't know what changed, so play it safe
+                        log::warn!("Error decoding event, dropping all cached data: {e:?}");
+                        cache.advisory_changed("<unknown>");
+                    }
+                    None => log::warn!("No event for payload, skipping"),
+                }
+
+                if let Err(e) = consumer.commit(&[event]).await {
+                    log::warn!("Error committing event: {e:?}");
+                }
+            }
+            Ok(None) => {
+                log::debug!("Polling returned no events, retrying");
+            }
+            Err(BusError::Critical(s)) => {
+                log::warn!("Critical error while polling, exiting: {s:?}");
+                return Err(anyhow::anyhow!(s));
+            }
+            Err(e) => {
+                log::warn!("Error polling for event: {e:?}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> CacheConfig {
+        CacheConfig {
+            enabled: true,
+            max_entries: 2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn bounded() {
+        let cache = Cache::new(2);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        cache.insert("c", 3);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&"c"), Some(3));
+
+        let disabled = Cache::new(0);
+        disabled.insert("a", 1);
+        assert_eq!(disabled.get(&"a"), None);
+    }
+
+    #[test]
+    fn invalidate_sbom() {
+        let cache = DerivedCache::new(&CacheConfig {
+            max_entries: 3,
+            ..config()
+        });
+        let amber = AccessScope {
+            amber: true,
+            ..Default::default()
+        };
+        cache.sbom_advisories.insert(("a".into(), AccessScope::default()), 1);
+        cache.sbom_advisories.insert(("a".into(), amber), 2);
+        cache.sbom_advisories.insert(("b".into(), AccessScope::default()), 3);
+        cache.cve_advisories.insert("CVE-2023-0001".into(), 1);
+
+        assert_eq!(cache.sbom_advisories.get(&("a".to_string(), amber)), Some(2));
+
+        cache.sbom_changed("a");
+        assert_eq!(
+            cache.sbom_advisories.get(&("a".to_string(), AccessScope::default())),
+            None
+        );
+        assert_eq!(cache.sbom_advisories.get(&("a".to_string(), amber)), None);
+        assert_eq!(
+            cache.sbom_advisories.get(&("b".to_string(), AccessScope::default())),
+            Some(3)
+        );
+        assert_eq!(cache.cve_advisories.get(&"CVE-2023-0001".to_string()), Some(1));
+
+        cache.advisory_changed("RHSA-2023:0001");
+        assert_eq!(cache.sbom_advisories.len(), 0);
+        assert_eq!(cache.cve_advisories.len(), 0);
+    }
+}
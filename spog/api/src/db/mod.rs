@@ -3,15 +3,36 @@ use actix_web::http::header::ContentType;
 use actix_web::{HttpResponse, ResponseError};
 use http::StatusCode;
 use spog_model::dashboard::UserPreferences;
+use spog_model::ownership::{Finding, OwnershipRule, TeamWebhook};
+use spog_model::posture::PostureSnapshot;
+use spog_model::score::ScoreOverride;
+use spog_model::subscription::{Subscription, SubscriptionEvent};
 use sqlx::sqlite::SqliteConnectOptions;
 use sqlx::{Row, SqlitePool};
 use std::path::Path;
 use std::str::FromStr;
+use time::OffsetDateTime;
 use trustification_common::error::ErrorInformation;
 
 #[allow(dead_code)]
 static DB_FILE_NAME: &str = "preferences.db";
 
+/// The delivery state of an event which is yet to be posted to the webhook of its subscription.
+const DELIVERY_PENDING: i64 = 0;
+
+/// The delivery state of an event which failed to be posted to the webhook of its subscription too often.
+const DELIVERY_FAILED: i64 = 2;
+
+/// An event which is yet to be posted to the webhook of its subscription.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingEvent {
+    pub event: SubscriptionEvent,
+    /// The number of failed deliveries
+    pub attempts: u32,
+    /// When the delivery may be retried, in seconds since the epoch
+    pub retry_at: i64,
+}
+
 #[allow(dead_code)]
 pub struct Db {
     pool: SqlitePool,
@@ -72,6 +93,10 @@ impl Db {
     #[allow(dead_code)]
     async fn initialize(&self) -> Result<(), Error> {
         self.create_user_preferences_table().await?;
+        self.create_score_overrides_table().await?;
+        self.create_ownership_tables().await?;
+        self.create_posture_snapshots_table().await?;
+        self.create_subscription_tables().await?;
         Ok(())
     }
 
@@ -134,11 +159,717 @@ impl Db {
             Ok(p)
         }
     }
+
+    pub async fn create_score_overrides_table(&self) -> Result<(), Error> {
+        // overrides for all products use an empty product, as null values are never equal in unique indexes
+        sqlx::query(
+            r#"CREATE TABLE IF NOT EXISTS score_overrides (
+                cve TEXT,
+                product TEXT,
+                modifiers TEXT,
+                comment TEXT
+            )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+                create unique index if not exists cve_product_idx on score_overrides ( cve, product ) ;
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_score_override(&self, score_override: ScoreOverride) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+                    INSERT OR REPLACE INTO score_overrides ( cve, product, modifiers, comment )
+                    VALUES ($1, $2, $3, $4);
+            "#,
+        )
+        .bind(score_override.cve.to_uppercase())
+        .bind(score_override.product.unwrap_or_default())
+        .bind(score_override.modifiers)
+        .bind(score_override.comment)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Select the overrides, of a CVE if given.
+    pub async fn select_score_overrides(&self, cve: Option<&str>) -> Result<Vec<ScoreOverride>, Error> {
+        let result = sqlx::query(
+            r#"
+           select cve, product, modifiers, comment from score_overrides where $1 is null or cve = $1 order by cve, product;
+            "#,
+        )
+        .bind(cve.map(str::to_uppercase))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result
+            .into_iter()
+            .map(|row| ScoreOverride {
+                cve: row.get("cve"),
+                product: Some(row.get::<String, _>("product")).filter(|product| !product.is_empty()),
+                modifiers: row.get("modifiers"),
+                comment: row.get("comment"),
+            })
+            .collect())
+    }
+
+    /// Delete an override, returning whether it existed.
+    pub async fn delete_score_override(&self, cve: &str, product: Option<&str>) -> Result<bool, Error> {
+        let result = sqlx::query(
+            r#"
+           delete from score_overrides where cve = $1 and product = $2;
+            "#,
+        )
+        .bind(cve.to_uppercase())
+        .bind(product.unwrap_or_default())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn create_ownership_tables(&self) -> Result<(), Error> {
+        sqlx::query(
+            r#"CREATE TABLE IF NOT EXISTS ownership_rules (
+                pattern TEXT,
+                team TEXT
+            )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+                create unique index if not exists pattern_idx on ownership_rules ( pattern ) ;
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"CREATE TABLE IF NOT EXISTS team_webhooks (
+                team TEXT,
+                url TEXT,
+                template TEXT,
+                content_type TEXT
+            )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // webhooks stored before they had templates
+        let columns: Vec<String> = sqlx::query("select name from pragma_table_info('team_webhooks');")
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|row| row.get("name"))
+            .collect();
+        for column in ["template", "content_type"] {
+            if !columns.iter().any(|name| name == column) {
+                sqlx::query(&format!("ALTER TABLE team_webhooks ADD COLUMN {column} TEXT;"))
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+
+        sqlx::query(
+            r#"
+                create unique index if not exists team_idx on team_webhooks ( team ) ;
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // findings which were notified already, so that teams only hear about new ones
+        sqlx::query(
+            r#"CREATE TABLE IF NOT EXISTS notified_findings (
+                sbom TEXT,
+                vulnerability TEXT,
+                purl TEXT
+            )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+                create unique index if not exists sbom_finding_idx on notified_findings ( sbom, vulnerability, purl ) ;
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_ownership_rule(&self, rule: OwnershipRule) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+                    INSERT OR REPLACE INTO ownership_rules ( pattern, team )
+                    VALUES ($1, $2);
+            "#,
+        )
+        .bind(rule.pattern)
+        .bind(rule.team)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn select_ownership_rules(&self) -> Result<Vec<OwnershipRule>, Error> {
+        let result = sqlx::query(
+            r#"
+           select pattern, team from ownership_rules order by pattern;
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result
+            .into_iter()
+            .map(|row| OwnershipRule {
+                pattern: row.get("pattern"),
+                team: row.get("team"),
+            })
+            .collect())
+    }
+
+    /// Delete a rule, returning whether it existed.
+    pub async fn delete_ownership_rule(&self, pattern: &str) -> Result<bool, Error> {
+        let result = sqlx::query(
+            r#"
+           delete from ownership_rules where pattern = $1;
+            "#,
+        )
+        .bind(pattern)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn update_team_webhook(&self, webhook: TeamWebhook) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+                    INSERT OR REPLACE INTO team_webhooks ( team, url, template, content_type )
+                    VALUES ($1, $2, $3, $4);
+            "#,
+        )
+        .bind(webhook.team)
+        .bind(webhook.url)
+        .bind(webhook.template)
+        .bind(webhook.content_type)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Select the webhooks, of a team if given.
+    pub async fn select_team_webhooks(&self, team: Option<&str>) -> Result<Vec<TeamWebhook>, Error> {
+        let result = sqlx::query(
+            r#"
+           select team, url, template, content_type from team_webhooks where $1 is null or team = $1 order by team;
+            "#,
+        )
+        .bind(team)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result
+            .into_iter()
+            .map(|row| TeamWebhook {
+                team: row.get("team"),
+                url: row.get("url"),
+                template: row.get("template"),
+                content_type: row.get("content_type"),
+            })
+            .collect())
+    }
+
+    /// Delete the webhook of a team, returning whether it existed.
+    pub async fn delete_team_webhook(&self, team: &str) -> Result<bool, Error> {
+        let result = sqlx::query(
+            r#"
+           delete from team_webhooks where team = $1;
+            "#,
+        )
+        .bind(team)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Select the findings of an SBOM which weren't notified yet.
+    pub async fn select_new_findings(&self, sbom: &str, findings: Vec<Finding>) -> Result<Vec<Finding>, Error> {
+        let mut new = Vec::new();
+        for finding in findings {
+            let notified = sqlx::query(
+                r#"
+                    select 1 from notified_findings where sbom = $1 and vulnerability = $2 and purl = $3;
+                "#,
+            )
+            .bind(sbom)
+            .bind(&finding.vulnerability)
+            .bind(&finding.purl)
+            .fetch_optional(&self.pool)
+            .await?;
+            if notified.is_none() {
+                new.push(finding);
+            }
+        }
+
+        Ok(new)
+    }
+
+    /// Record the findings of an SBOM as notified.
+    pub async fn record_notified_findings(&self, sbom: &str, findings: &[Finding]) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+        for finding in findings {
+            sqlx::query(
+                r#"
+                    INSERT OR IGNORE INTO notified_findings ( sbom, vulnerability, purl )
+                    VALUES ($1, $2, $3);
+                "#,
+            )
+            .bind(sbom)
+            .bind(&finding.vulnerability)
+            .bind(&finding.purl)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn create_subscription_tables(&self) -> Result<(), Error> {
+        sqlx::query(
+            r#"CREATE TABLE IF NOT EXISTS subscriptions (
+                id TEXT,
+                purl TEXT,
+                severity TEXT,
+                label TEXT,
+                webhook TEXT
+            )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+                create unique index if not exists subscription_idx on subscriptions ( id ) ;
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // the queues of the subscriptions, where seq is the cursor of consumers
+        sqlx::query(
+            r#"CREATE TABLE IF NOT EXISTS subscription_events (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                subscription TEXT,
+                sbom TEXT,
+                indexed_timestamp INTEGER,
+                event TEXT,
+                delivered INTEGER,
+                attempts INTEGER DEFAULT 0,
+                retry_at INTEGER DEFAULT 0
+            )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // events queued before failed deliveries were backed off
+        let columns: Vec<String> = sqlx::query("select name from pragma_table_info('subscription_events');")
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|row| row.get("name"))
+            .collect();
+        for column in ["attempts", "retry_at"] {
+            if !columns.iter().any(|name| name == column) {
+                sqlx::query(&format!(
+                    "ALTER TABLE subscription_events ADD COLUMN {column} INTEGER DEFAULT 0;"
+                ))
+                .execute(&self.pool)
+                .await?;
+            }
+        }
+
+        sqlx::query(
+            r#"
+                create unique index if not exists subscription_change_idx on subscription_events ( subscription, sbom, indexed_timestamp ) ;
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // the position in the changes feed, up to which changes were matched
+        sqlx::query(
+            r#"CREATE TABLE IF NOT EXISTS subscription_cursor (
+                id INTEGER PRIMARY KEY,
+                since INTEGER
+            )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_subscription(&self, subscription: Subscription) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+                    INSERT OR REPLACE INTO subscriptions ( id, purl, severity, label, webhook )
+                    VALUES ($1, $2, $3, $4, $5);
+            "#,
+        )
+        .bind(subscription.id)
+        .bind(subscription.purl)
+        .bind(subscription.severity.map(|severity| severity.as_str()))
+        .bind(subscription.label)
+        .bind(subscription.webhook)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Select the subscriptions, or the one with an ID if given.
+    pub async fn select_subscriptions(&self, id: Option<&str>) -> Result<Vec<Subscription>, Error> {
+        let result = sqlx::query(
+            r#"
+           select id, purl, severity, label, webhook from subscriptions where $1 is null or id = $1 order by id;
+            "#,
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result
+            .into_iter()
+            .map(|row| Subscription {
+                id: row.get("id"),
+                purl: row.get("purl"),
+                severity: row
+                    .get::<Option<String>, _>("severity")
+                    .and_then(|severity| cvss::Severity::from_str(&severity).ok()),
+                label: row.get("label"),
+                webhook: row.get("webhook"),
+            })
+            .collect())
+    }
+
+    /// Delete a subscription along with its queue, returning whether it existed.
+    pub async fn delete_subscription(&self, id: &str) -> Result<bool, Error> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"
+           delete from subscriptions where id = $1;
+            "#,
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+           delete from subscription_events where subscription = $1;
+            "#,
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Queue an event of a subscription, keeping the latest `retained` events of the subscription. Events to be posted
+    /// to the webhook of the subscription are pending until they are recorded as delivered.
+    ///
+    /// Returns the event with its position in the queue, or `None` if the change was queued already.
+    pub async fn queue_subscription_event(
+        &self,
+        mut event: SubscriptionEvent,
+        pending: bool,
+        retained: usize,
+    ) -> Result<Option<SubscriptionEvent>, Error> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"
+                    INSERT OR IGNORE INTO subscription_events ( subscription, sbom, indexed_timestamp, delivered )
+                    VALUES ($1, $2, $3, $4);
+            "#,
+        )
+        .bind(&event.subscription)
+        .bind(&event.sbom)
+        .bind(event.indexed_timestamp)
+        .bind(!pending)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        event.seq = result.last_insert_rowid();
+        sqlx::query(
+            r#"
+                    UPDATE subscription_events SET event = $1 WHERE seq = $2;
+            "#,
+        )
+        .bind(serde_json::to_string(&event)?)
+        .bind(event.seq)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+           delete from subscription_events where subscription = $1 and seq in (
+               select seq from subscription_events where subscription = $1 order by seq desc limit -1 offset $2
+           );
+            "#,
+        )
+        .bind(&event.subscription)
+        .bind(retained as i64)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(Some(event))
+    }
+
+    /// Select up to `limit` events of a subscription after the position `since`, oldest first.
+    pub async fn select_subscription_events(
+        &self,
+        subscription: &str,
+        since: i64,
+        limit: usize,
+    ) -> Result<Vec<SubscriptionEvent>, Error> {
+        let result = sqlx::query(
+            r#"
+           select event from subscription_events where subscription = $1 and seq > $2 order by seq limit $3;
+            "#,
+        )
+        .bind(subscription)
+        .bind(since)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        result
+            .into_iter()
+            .map(|row| Ok(serde_json::from_str(row.get("event"))?))
+            .collect()
+    }
+
+    /// Select the pending events, which weren't delivered to the webhooks of their subscriptions yet, oldest first.
+    pub async fn select_undelivered_subscription_events(&self) -> Result<Vec<PendingEvent>, Error> {
+        let result = sqlx::query(
+            r#"
+           select event, attempts, retry_at from subscription_events where delivered = 0 order by seq;
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        result
+            .into_iter()
+            .map(|row| {
+                Ok(PendingEvent {
+                    event: serde_json::from_str(row.get("event"))?,
+                    attempts: row.get::<Option<u32>, _>("attempts").unwrap_or_default(),
+                    retry_at: row.get::<Option<i64>, _>("retry_at").unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+
+    pub async fn select_subscription_cursor(&self) -> Result<Option<i64>, Error> {
+        let result = sqlx::query(
+            r#"
+           select since from subscription_cursor where id = 0;
+            "#,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result.map(|row| row.get("since")))
+    }
+
+    pub async fn update_subscription_cursor(&self, since: i64) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+                    INSERT OR REPLACE INTO subscription_cursor ( id, since )
+                    VALUES (0, $1);
+            "#,
+        )
+        .bind(since)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record an event as delivered to the webhook of its subscription.
+    pub async fn record_delivered_subscription_event(&self, seq: i64) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+                    UPDATE subscription_events SET delivered = 1 WHERE seq = $1;
+            "#,
+        )
+        .bind(seq)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record a failed delivery of an event, which is retried at `retry_at` (in seconds since the epoch), or given up
+    /// on if `retry_at` is `None`. Given up events stay queued, but aren't posted to the webhook anymore.
+    pub async fn record_failed_subscription_event(
+        &self,
+        seq: i64,
+        attempts: u32,
+        retry_at: Option<i64>,
+    ) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+                    UPDATE subscription_events SET delivered = $2, attempts = $3, retry_at = $4 WHERE seq = $1;
+            "#,
+        )
+        .bind(seq)
+        .bind(match retry_at {
+            Some(_) => DELIVERY_PENDING,
+            None => DELIVERY_FAILED,
+        })
+        .bind(attempts)
+        .bind(retry_at.unwrap_or_default())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn create_posture_snapshots_table(&self) -> Result<(), Error> {
+        sqlx::query(
+            r#"CREATE TABLE IF NOT EXISTS posture_snapshots (
+                sbom TEXT,
+                name TEXT,
+                version TEXT,
+                timestamp INTEGER,
+                summary TEXT
+            )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+                create index if not exists posture_sbom_idx on posture_snapshots ( sbom, timestamp ) ;
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+                create index if not exists posture_name_idx on posture_snapshots ( name, timestamp ) ;
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn record_posture_snapshot(&self, snapshot: &PostureSnapshot) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+                    INSERT INTO posture_snapshots ( sbom, name, version, timestamp, summary )
+                    VALUES ($1, $2, $3, $4, $5);
+            "#,
+        )
+        .bind(&snapshot.sbom)
+        .bind(&snapshot.name)
+        .bind(&snapshot.version)
+        .bind(snapshot.timestamp.unix_timestamp())
+        .bind(serde_json::to_string(&snapshot.summary)?)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Select the snapshots of an SBOM, or of all SBOMs with the name, oldest first.
+    pub async fn select_posture_snapshots(
+        &self,
+        sbom: Option<&str>,
+        name: Option<&str>,
+    ) -> Result<Vec<PostureSnapshot>, Error> {
+        let result = sqlx::query(
+            r#"
+           select sbom, name, version, timestamp, summary from posture_snapshots
+           where ($1 is null or sbom = $1) and ($2 is null or name = $2)
+           order by timestamp, sbom;
+            "#,
+        )
+        .bind(sbom)
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await?;
+
+        result
+            .into_iter()
+            .map(|row| {
+                let summary: String = row.get("summary");
+                Ok(PostureSnapshot {
+                    sbom: row.get("sbom"),
+                    name: row.get("name"),
+                    version: row.get("version"),
+                    timestamp: OffsetDateTime::from_unix_timestamp(row.get("timestamp"))
+                        .unwrap_or(OffsetDateTime::UNIX_EPOCH),
+                    summary: serde_json::from_str(&summary)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Delete the snapshots taken before a point in time, returning the number of deleted snapshots.
+    pub async fn delete_posture_snapshots(&self, before: OffsetDateTime) -> Result<u64, Error> {
+        let result = sqlx::query(
+            r#"
+           delete from posture_snapshots where timestamp < $1;
+            "#,
+        )
+        .bind(before.unix_timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
 }
 #[cfg(test)]
 mod test {
     use crate::db::Db;
     use spog_model::dashboard::{Preferences, UserPreferences};
+    use spog_model::ownership::{Finding, OwnershipRule, TeamWebhook};
+    use spog_model::posture::PostureSnapshot;
+    use spog_model::score::ScoreOverride;
+    use spog_model::subscription::{Subscription, SubscriptionEvent};
+    use spog_model::vuln::SummaryEntry;
+    use time::{Duration, OffsetDateTime};
     #[actix_web::test]
     async fn update_user_preferences() -> Result<(), anyhow::Error> {
         let pre_preferences = Preferences {
@@ -191,4 +922,210 @@ mod test {
         );
         Ok(())
     }
+
+    #[actix_web::test]
+    async fn score_overrides() -> Result<(), anyhow::Error> {
+        let score_override = |product: Option<&str>, modifiers: &str| ScoreOverride {
+            cve: "cve-2023-0286".to_string(),
+            product: product.map(ToString::to_string),
+            modifiers: modifiers.to_string(),
+            comment: None,
+        };
+
+        let db = Db::new(".").await?;
+        db.update_score_override(score_override(None, "E:U")).await?;
+        db.update_score_override(score_override(Some("sbom1"), "E:P")).await?;
+        // replaces the previous override of the product
+        db.update_score_override(score_override(Some("sbom1"), "E:F")).await?;
+
+        let result = db.select_score_overrides(Some("CVE-2023-0286")).await?;
+        assert_eq!(2, result.len());
+        assert_eq!(None, result[0].product);
+        assert_eq!("E:F", result[1].modifiers);
+        assert!(db.select_score_overrides(Some("CVE-2023-0001")).await?.is_empty());
+
+        assert!(db.delete_score_override("CVE-2023-0286", None).await?);
+        assert!(!db.delete_score_override("CVE-2023-0286", None).await?);
+        assert_eq!(1, db.select_score_overrides(None).await?.len());
+        Ok(())
+    }
+    #[actix_web::test]
+    async fn ownership() -> Result<(), anyhow::Error> {
+        let db = Db::new(".").await?;
+        for (pattern, team) in [("pkg:maven/*", "java"), ("pkg:maven/io.quarkus/*", "quarkus")] {
+            db.update_ownership_rule(OwnershipRule {
+                pattern: pattern.to_string(),
+                team: team.to_string(),
+            })
+            .await?;
+        }
+        // replaces the team of the pattern
+        db.update_ownership_rule(OwnershipRule {
+            pattern: "pkg:maven/*".to_string(),
+            team: "middleware".to_string(),
+        })
+        .await?;
+
+        let rules = db.select_ownership_rules().await?;
+        assert_eq!(2, rules.len());
+        assert_eq!("middleware", rules[0].team);
+        assert!(db.delete_ownership_rule("pkg:maven/*").await?);
+        assert!(!db.delete_ownership_rule("pkg:maven/*").await?);
+        assert_eq!(1, db.select_ownership_rules().await?.len());
+
+        let webhook = TeamWebhook {
+            team: "quarkus".to_string(),
+            url: "https://hooks.example.com/quarkus".to_string(),
+            template: Some(r#"{"text": "{{ sbom }}"}"#.to_string()),
+            content_type: None,
+        };
+        db.update_team_webhook(webhook.clone()).await?;
+        assert_eq!(vec![webhook], db.select_team_webhooks(Some("quarkus")).await?);
+        assert!(db.select_team_webhooks(Some("java")).await?.is_empty());
+        assert!(db.delete_team_webhook("quarkus").await?);
+        assert!(db.select_team_webhooks(None).await?.is_empty());
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn notified_findings() -> Result<(), anyhow::Error> {
+        let finding = |vulnerability: &str| Finding {
+            vulnerability: vulnerability.to_string(),
+            purl: "pkg:rpm/redhat/openssl@1.1.1k-7.el8_6".to_string(),
+        };
+
+        let db = Db::new(".").await?;
+        let new = db.select_new_findings("sbom1", vec![finding("CVE-2023-0286")]).await?;
+        assert_eq!(vec![finding("CVE-2023-0286")], new);
+
+        // findings are new until they are recorded
+        let new = db.select_new_findings("sbom1", vec![finding("CVE-2023-0286")]).await?;
+        assert_eq!(vec![finding("CVE-2023-0286")], new);
+        db.record_notified_findings("sbom1", &new).await?;
+
+        // only the new finding is returned, and findings of other SBOMs are distinct
+        let new = db
+            .select_new_findings("sbom1", vec![finding("CVE-2023-0286"), finding("CVE-2023-0215")])
+            .await?;
+        assert_eq!(vec![finding("CVE-2023-0215")], new);
+        let new = db.select_new_findings("sbom2", vec![finding("CVE-2023-0286")]).await?;
+        assert_eq!(1, new.len());
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn subscriptions() -> Result<(), anyhow::Error> {
+        let subscription = Subscription {
+            id: "quarkus-critical".to_string(),
+            purl: Some("pkg:maven/io.quarkus/*".to_string()),
+            severity: Some(cvss::Severity::Critical),
+            label: None,
+            webhook: Some("https://hooks.example.com/quarkus".to_string()),
+        };
+        let event = |sbom: &str, indexed_timestamp: i64| SubscriptionEvent {
+            seq: 0,
+            subscription: "quarkus-critical".to_string(),
+            sbom: sbom.to_string(),
+            sha256: "e3b0c442".to_string(),
+            indexed_timestamp,
+            labels: vec![],
+            findings: vec![],
+        };
+
+        let db = Db::new(".").await?;
+        db.update_subscription(subscription.clone()).await?;
+        assert_eq!(
+            vec![subscription],
+            db.select_subscriptions(Some("quarkus-critical")).await?
+        );
+        assert!(db.select_subscriptions(Some("other")).await?.is_empty());
+
+        let first = db.queue_subscription_event(event("sbom1", 10), true, 2).await?.unwrap();
+        // a change is queued once
+        assert!(db
+            .queue_subscription_event(event("sbom1", 10), true, 2)
+            .await?
+            .is_none());
+        db.queue_subscription_event(event("sbom1", 20), false, 2).await?;
+        let pending = db.select_undelivered_subscription_events().await?;
+        assert_eq!(
+            vec![(first.clone(), 0, 0)],
+            pending
+                .into_iter()
+                .map(|p| (p.event, p.attempts, p.retry_at))
+                .collect::<Vec<_>>()
+        );
+
+        // failed deliveries are retried later
+        db.record_failed_subscription_event(first.seq, 1, Some(100)).await?;
+        let pending = db.select_undelivered_subscription_events().await?;
+        assert_eq!((1, 100), (pending[0].attempts, pending[0].retry_at));
+        db.record_delivered_subscription_event(first.seq).await?;
+        assert!(db.select_undelivered_subscription_events().await?.is_empty());
+
+        // only the latest events are retained
+        let last = db.queue_subscription_event(event("sbom2", 30), true, 2).await?.unwrap();
+        // deliveries given up on aren't pending anymore, but the event stays queued
+        db.record_failed_subscription_event(last.seq, 10, None).await?;
+        assert!(db.select_undelivered_subscription_events().await?.is_empty());
+        let events = db.select_subscription_events("quarkus-critical", 0, 10).await?;
+        assert_eq!(
+            vec![20, 30],
+            events.iter().map(|e| e.indexed_timestamp).collect::<Vec<_>>()
+        );
+        let events = db
+            .select_subscription_events("quarkus-critical", events[0].seq, 10)
+            .await?;
+        assert_eq!(1, events.len());
+
+        assert_eq!(None, db.select_subscription_cursor().await?);
+        db.update_subscription_cursor(10).await?;
+        db.update_subscription_cursor(30).await?;
+        assert_eq!(Some(30), db.select_subscription_cursor().await?);
+
+        assert!(db.delete_subscription("quarkus-critical").await?);
+        assert!(!db.delete_subscription("quarkus-critical").await?);
+        assert!(db
+            .select_subscription_events("quarkus-critical", 0, 10)
+            .await?
+            .is_empty());
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn posture_snapshots() -> Result<(), anyhow::Error> {
+        let now = OffsetDateTime::from_unix_timestamp(OffsetDateTime::now_utc().unix_timestamp())?;
+        let snapshot = |sbom: &str, version: &str, timestamp: OffsetDateTime, critical: usize| PostureSnapshot {
+            sbom: sbom.to_string(),
+            name: "ubi9".to_string(),
+            version: Some(version.to_string()),
+            timestamp,
+            summary: vec![SummaryEntry {
+                severity: Some(cvss::Severity::Critical),
+                count: critical,
+            }],
+        };
+
+        let db = Db::new(".").await?;
+        db.record_posture_snapshot(&snapshot("ubi9-1", "9.1", now - Duration::days(2), 3))
+            .await?;
+        db.record_posture_snapshot(&snapshot("ubi9-1", "9.1", now - Duration::days(1), 2))
+            .await?;
+        db.record_posture_snapshot(&snapshot("ubi9-2", "9.2", now, 0)).await?;
+
+        let snapshots = db.select_posture_snapshots(Some("ubi9-1"), None).await?;
+        assert_eq!(
+            vec![
+                snapshot("ubi9-1", "9.1", now - Duration::days(2), 3),
+                snapshot("ubi9-1", "9.1", now - Duration::days(1), 2)
+            ],
+            snapshots
+        );
+        // all releases of the product
+        assert_eq!(3, db.select_posture_snapshots(None, Some("ubi9")).await?.len());
+
+        assert_eq!(1, db.delete_posture_snapshots(now - Duration::hours(36)).await?);
+        assert_eq!(1, db.select_posture_snapshots(Some("ubi9-1"), None).await?.len());
+        Ok(())
+    }
 }
@@ -1,7 +1,9 @@
 use actix_web::{web, web::ServiceConfig, HttpResponse};
 use actix_web_httpauth::extractors::bearer::BearerAuth;
+use bytes::Bytes;
+use futures::{stream, TryStreamExt};
 use http::header;
-use log::trace;
+use log::{trace, warn};
 use spog_model::search::AdvisorySummary;
 use std::sync::Arc;
 use tracing::instrument;
@@ -10,10 +12,13 @@ use trustification_auth::authenticator::Authenticator;
 use trustification_infrastructure::new_auth;
 use utoipa::IntoParams;
 
-use crate::{app_state::AppState, search::QueryParams};
+use crate::{app_state::AppState, bundle::ZipStream, error::Error, search::QueryParams, utils::get_sanitize_filename};
 
 const MAX_LIMIT: usize = 1_000;
 
+/// Maximum number of advisories which can be requested in a single bundle
+const MAX_BUNDLE_SIZE: usize = 100;
+
 pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
     |config: &mut ServiceConfig| {
         config.service(
@@ -23,6 +28,8 @@ pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut Se
         );
         // the get operation doesn't get the authenticator added, as we check this using the access_token query parameter
         config.service(web::resource("/api/v1/advisory").to(get));
+        // same as above, the bundle is downloaded by the browser, using the token query parameter
+        config.service(web::resource("/api/v1/advisory/bundle").to(bundle));
     }
 }
 
@@ -58,6 +65,110 @@ pub async fn get(
         .streaming(stream))
 }
 
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct BundleParams {
+    /// Comma separated list of advisory/VEX IDs to include in the bundle
+    pub ids: String,
+    /// The bearer token
+    pub token: Option<String>,
+}
+
+impl BundleParams {
+    fn ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .ids
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(ToString::to_string)
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/advisory/bundle",
+    responses(
+        (status = OK, description = "Bundle of the requested advisories, as a zip archive", content_type = "application/zip"),
+        (status = BAD_REQUEST, description = "No advisories, or too many advisories requested"),
+    ),
+    params(BundleParams)
+)]
+#[instrument(skip(state, access_token), err)]
+pub async fn bundle(
+    state: web::Data<AppState>,
+    web::Query(params): web::Query<BundleParams>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let ids = params.ids();
+    if ids.is_empty() || ids.len() > MAX_BUNDLE_SIZE {
+        return Err(actix_web::error::ErrorBadRequest(format!(
+            "a bundle must contain between 1 and {MAX_BUNDLE_SIZE} advisories, requested: {}",
+            ids.len()
+        )));
+    }
+
+    let token = params.token.or_else(|| access_token.map(|s| s.token().to_string()));
+    let archive = ZipStream::new();
+
+    // fetch one advisory after the other, handing out the archive as we go
+    let entries = stream::try_unfold(
+        (ids.into_iter(), Some(archive), Vec::new()),
+        move |(mut ids, archive, mut failed)| {
+            let state = state.clone();
+            let token = token.clone();
+            async move {
+                let Some(mut archive) = archive else {
+                    return Ok::<_, Error>(None);
+                };
+
+                match ids.next() {
+                    Some(id) => {
+                        let data: Result<bytes::BytesMut, Error> =
+                            async { Ok(state.get_vex(&id, &token).await?.try_collect().await?) }.await;
+
+                        match data {
+                            Ok(data) => {
+                                let name = format!("{}.json", get_sanitize_filename(id));
+                                archive
+                                    .append(&name, &data)
+                                    .map_err(|err| Error::Generic(err.to_string()))?;
+                            }
+                            Err(err) => {
+                                warn!("Failed to add advisory '{id}' to bundle: {err}");
+                                failed.push(format!("{id}: {err}"));
+                            }
+                        }
+
+                        let chunk = Bytes::from(archive.drain());
+                        Ok(Some((chunk, (ids, Some(archive), failed))))
+                    }
+                    None => {
+                        if !failed.is_empty() {
+                            let mut errors = failed.join("\n");
+                            errors.push('\n');
+                            archive
+                                .append("errors.txt", errors.as_bytes())
+                                .map_err(|err| Error::Generic(err.to_string()))?;
+                        }
+
+                        let chunk = archive.finish().map_err(|err| Error::Generic(err.to_string()))?;
+                        Ok(Some((Bytes::from(chunk), (ids, None, failed))))
+                    }
+                }
+            }
+        },
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/zip")
+        .append_header((header::CONTENT_DISPOSITION, r#"attachment; filename="advisories.zip""#))
+        .streaming(entries))
+}
+
 #[utoipa::path(
     get,
     path = "/api/v1/advisory/search",
@@ -100,6 +211,7 @@ pub async fn search(
             href: format!("/api/v1/advisory?id={}", item.advisory_id),
             cves: item.cves,
             cve_severity_count: item.cve_severity_count,
+            vendors: item.vendors,
             metadata,
         });
     }
@@ -1,6 +1,6 @@
 use crate::{
-    app_state::AppState, error::Error, search, service::collectorist::CollectoristService, service::guac::GuacService,
-    service::v11y::V11yService,
+    app_state::AppState, cache::DerivedCache, endpoints::score::adjust_score, error::Error, search,
+    service::collectorist::CollectoristService, service::guac::GuacService, service::v11y::V11yService,
 };
 use actix_web::{
     web::{self, ServiceConfig},
@@ -34,7 +34,8 @@ pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut Se
                 .wrap(new_auth!(auth))
                 .service(web::resource("").to(cve_search))
                 .service(web::resource("/{id}").to(cve_get))
-                .service(web::resource("/{id}/related-products").to(cve_related_product)),
+                .service(web::resource("/{id}/related-products").to(cve_related_product))
+                .service(web::resource("/{id}/timeline").to(cve_timeline)),
         );
     }
 }
@@ -47,27 +48,41 @@ pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut Se
     ),
     params(search::QueryParams)
 )]
-#[instrument(skip(v11y, state, guac), err)]
+#[instrument(skip(v11y, state, guac, cache), err)]
 async fn cve_search(
     web::Query(params): web::Query<search::QueryParams>,
     v11y: web::Data<V11yService>,
     state: web::Data<AppState>,
     guac: web::Data<GuacService>,
+    cache: web::Data<DerivedCache>,
 ) -> actix_web::Result<HttpResponse> {
     let SearchResult { result, total } = v11y.search(params).await.map_err(Error::V11y)?;
+    // only overrides for all products apply to CVEs
+    let overrides: Arc<[_]> = state
+        .db_storage
+        .select_score_overrides(None)
+        .await?
+        .into_iter()
+        .filter(|o| o.product.is_none())
+        .collect();
 
     // enrich the results with counts of relations
     let result: Vec<_> = stream::iter(result.into_iter().map(Ok::<_, Error>))
         .and_then(move |hit: SearchHit<SearchDocument>| {
             let state = state.clone();
             let guac = guac.clone();
+            let cache = cache.clone();
+            let v11y = v11y.clone();
+            let overrides = overrides.clone();
             async move {
-                let related_advisories = count_related_advisories(&state, &hit.document.id).await?;
+                let related_advisories = count_related_advisories(&state, &cache, &hit.document.id).await?;
                 let related_products = count_related_products(&guac, &hit.document.id).await?;
+                let adjusted_score = adjust_score(&v11y, &overrides, &hit.document.id, None).await?;
                 Ok(hit.map(|document| CveSearchDocument {
                     document,
                     related_advisories,
                     related_products,
+                    adjusted_score,
                 }))
             }
         })
@@ -78,15 +93,20 @@ async fn cve_search(
 }
 
 /// return the number of related advisories for a CVE
-#[instrument(skip(state), err, ret)]
-async fn count_related_advisories(state: &AppState, cve: &str) -> Result<usize, Error> {
+#[instrument(skip(state, cache), err, ret)]
+async fn count_related_advisories(state: &AppState, cache: &DerivedCache, cve: &str) -> Result<usize, Error> {
+    if let Some(count) = cache.cve_advisories.get(&cve.to_string()) {
+        return Ok(count);
+    }
+
     let options = SearchOptions {
         summaries: false,
         ..Default::default()
     };
     let result = state
-        .search_vex(&format!(r#"cve:"{}""#, cve), 0, 1000, options, &*state.provider)
+        .search_vex(&format!(r#"cve:"{}""#, cve), 0, 1, options, &*state.provider)
         .await?;
+    cache.cve_advisories.insert(cve.to_string(), result.total);
     Ok(result.total)
 }
 
@@ -116,6 +136,31 @@ async fn cve_get(id: web::Path<String>, v11y: web::Data<V11yService>) -> actix_w
     Ok(HttpResponseBuilder::new(response.status()).streaming(response.bytes_stream()))
 }
 
+/// Retrieve the timeline of the advisories referencing a CVE.
+#[utoipa::path(
+    get,
+    path = "/api/v1/cve/{id}/timeline",
+    responses(
+        (status = OK, description = "Timeline of the CVE", body = CveTimeline),
+    ),
+    params(
+        ("id" = String, Path, description = "The CVE to retrieve the timeline for"),
+    )
+)]
+#[instrument(skip(state, access_token), err)]
+async fn cve_timeline(
+    id: web::Path<String>,
+    state: web::Data<AppState>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let id = id.into_inner();
+    let token = access_token.map(|s| s.token().to_string());
+
+    let timeline = state.get_vex_timeline(&id, &token).await?;
+
+    Ok(HttpResponse::Ok().json(timeline))
+}
+
 async fn cve_related_product(
     _app_state: web::Data<AppState>,
     guac: web::Data<GuacService>,
@@ -149,6 +194,31 @@ async fn cve_details(
     Ok(HttpResponse::Ok().json(build_cve_details(&app_state, &guac, provider, id, &collectorist, &v11y).await?))
 }
 
+/// Number of advisories requested from vexination at once, within its maximum search limit.
+const ADVISORY_PAGE_SIZE: usize = 1000;
+
+/// The IDs of the advisories mentioning a CVE, requested page by page.
+async fn related_advisory_ids(
+    app: &AppState,
+    cve_id: &str,
+    provider: &dyn TokenProvider,
+) -> Result<BTreeSet<String>, Error> {
+    let q = format!(r#"cve:"{cve_id}""#);
+    let mut ids = BTreeSet::new();
+    let mut offset = 0;
+    loop {
+        let result = app
+            .search_vex(&q, offset, ADVISORY_PAGE_SIZE, Default::default(), provider)
+            .await?;
+        offset += result.result.len();
+        let done = result.result.is_empty() || offset >= result.total;
+        ids.extend(result.result.into_iter().map(|advisory| advisory.document.advisory_id));
+        if done {
+            return Ok(ids);
+        }
+    }
+}
+
 #[instrument(skip_all, fields(cve_id = % cve_id), err)]
 async fn build_cve_details<P>(
     app: &AppState,
@@ -168,13 +238,7 @@ where
 
     // fetch from index
 
-    let q = format!(r#"cve:"{cve_id}""#);
-    let advisories = app.search_vex(&q, 0, 1024, Default::default(), &provider).await?.result;
-
-    let advisory_ids: BTreeSet<String> = advisories
-        .into_iter()
-        .map(|advisory| advisory.document.advisory_id)
-        .collect();
+    let advisory_ids = related_advisory_ids(app, &cve_id, &provider).await?;
 
     let mut products = BTreeMap::<&str, BTreeSet<String>>::new();
     let mut advisories = vec![];
@@ -302,3 +366,78 @@ fn add_product(csaf: &Csaf, product: &ProductIdT, result: &mut HashSet<String>)
         result.insert(product.name.clone());
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::Db;
+    use actix_web::{App, HttpServer};
+    use trustification_auth::client::NoTokenProvider;
+    use trustification_infrastructure::app::search::SearchParams;
+    use vexination_model::search::{SearchDocument as VexDocument, SearchHit as VexHit, SearchResult as VexResult};
+
+    const ADVISORIES: usize = 1500;
+
+    /// Search of vexination, enforcing the default search limits like the real one.
+    async fn search(params: SearchParams) -> HttpResponse {
+        let result = (params.offset..ADVISORIES.min(params.offset + params.limit))
+            .map(|i| VexHit {
+                document: VexDocument {
+                    advisory_id: format!("RHSA-{i}"),
+                    advisory_title: String::new(),
+                    advisory_version: String::new(),
+                    advisory_date: time::OffsetDateTime::UNIX_EPOCH,
+                    advisory_snippet: String::new(),
+                    advisory_desc: String::new(),
+                    advisory_severity: None,
+                    cves: vec!["CVE-2023-44487".to_string()],
+                    cvss_max: None,
+                    cvss3_max: None,
+                    cvss4_max: None,
+                    cve_severity_count: Default::default(),
+                    indexed_timestamp: 0,
+                    withdrawn: false,
+                    superseded_by: vec![],
+                    archived: false,
+                    labels: vec![],
+                    tlp: None,
+                    vendors: vec![],
+                },
+                score: 1.0,
+                explanation: None,
+                metadata: None,
+            })
+            .collect();
+        HttpResponse::Ok().json(VexResult {
+            total: ADVISORIES,
+            result,
+            facets: Default::default(),
+            index_updated_at: None,
+        })
+    }
+
+    #[actix_web::test]
+    async fn related_advisories_within_search_limits() -> Result<(), anyhow::Error> {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let server = HttpServer::new(|| App::new().route("/api/v1/vex/search", web::get().to(search)))
+            .listen(listener)?
+            .run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        let app = AppState {
+            client: reqwest::Client::new(),
+            provider: Arc::new(NoTokenProvider),
+            bombastic: url.parse()?,
+            vexination: url.parse()?,
+            exhort: url.parse()?,
+            db_storage: Db::new(".").await?,
+        };
+        let ids = related_advisory_ids(&app, "CVE-2023-44487", &NoTokenProvider).await?;
+        assert_eq!(ids.len(), ADVISORIES);
+
+        handle.stop(true).await;
+        Ok(())
+    }
+}
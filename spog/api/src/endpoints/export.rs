@@ -0,0 +1,150 @@
+use crate::app_state::AppState;
+use crate::export::Exports;
+use actix_files::NamedFile;
+use actix_web::{
+    http::header::{self, HeaderValue},
+    web::{self, ServiceConfig},
+    HttpRequest, HttpResponse,
+};
+use spog_model::prelude::{ExportJob, ExportKind, ExportRequest};
+use std::sync::Arc;
+use tracing::instrument;
+use trustification_auth::{
+    authenticator::{user::UserInformation, Authenticator},
+    authorizer::Authorizer,
+    Permission,
+};
+use trustification_infrastructure::new_auth;
+use vexination_model::prelude::Tlp;
+
+pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config.service(
+            web::scope("/api/v1/export")
+                .wrap(new_auth!(auth))
+                .service(
+                    web::resource("")
+                        .route(web::get().to(list))
+                        .route(web::post().to(submit)),
+                )
+                .service(web::resource("/{id}").route(web::get().to(get)))
+                .service(web::resource("/{id}/download").route(web::get().to(download))),
+        );
+    }
+}
+
+/// Start an export job.
+///
+/// The job runs in the background, its progress can be polled using the returned ID.
+#[utoipa::path(
+    post,
+    path = "/api/v1/export",
+    tag = "export",
+    request_body = ExportRequest,
+    responses(
+        (status = ACCEPTED, description = "Export job was started", body = ExportJob),
+        (status = TOO_MANY_REQUESTS, description = "The user has too many pending or running export jobs"),
+    ),
+)]
+#[instrument(skip(state, exports, authorizer), err)]
+pub async fn submit(
+    state: web::Data<AppState>,
+    exports: web::Data<Exports>,
+    web::Json(request): web::Json<ExportRequest>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(
+        &user,
+        match request.kind {
+            ExportKind::Packages => Permission::ReadSbom,
+            ExportKind::Advisories => Permission::ReadVex,
+        },
+    )?;
+
+    // exports fetch advisories using the credentials of the service, so the user's access is checked here
+    let hidden = Tlp::hidden(&authorizer, &user);
+
+    let job = exports.submit(Exports::owner(user.id()), request)?;
+    log::info!("Starting export job {} of {:?}", job.id, job.kind);
+
+    let exports = exports.into_inner();
+    let running = job.clone();
+    actix_web::rt::spawn(async move { exports.run(&state, running, hidden).await });
+
+    Ok(HttpResponse::Accepted()
+        .insert_header((header::LOCATION, format!("/api/v1/export/{}", job.id)))
+        .json(job))
+}
+
+/// List the export jobs of the current user.
+#[utoipa::path(
+    get,
+    path = "/api/v1/export",
+    tag = "export",
+    responses(
+        (status = OK, description = "Export jobs of the user", body = Vec<ExportJob>),
+    ),
+)]
+#[instrument(skip(exports), err)]
+pub async fn list(exports: web::Data<Exports>, user: UserInformation) -> actix_web::Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(exports.list(Exports::owner(user.id()))))
+}
+
+/// Get the state and progress of an export job.
+#[utoipa::path(
+    get,
+    path = "/api/v1/export/{id}",
+    tag = "export",
+    responses(
+        (status = OK, description = "State of the export job", body = ExportJob),
+        (status = NOT_FOUND, description = "Export job was not found, or expired"),
+    ),
+    params(
+        ("id" = String, Path, description = "ID of the export job")
+    )
+)]
+#[instrument(skip(exports), err)]
+pub async fn get(
+    exports: web::Data<Exports>,
+    path: web::Path<String>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(exports.get(Exports::owner(user.id()), &path)?))
+}
+
+/// Download the result of a completed export job.
+///
+/// Downloads support range requests, so interrupted downloads can be resumed.
+#[utoipa::path(
+    get,
+    path = "/api/v1/export/{id}/download",
+    tag = "export",
+    responses(
+        (status = OK, description = "Result of the export job"),
+        (status = PARTIAL_CONTENT, description = "Requested range of the result"),
+        (status = NOT_FOUND, description = "Export job was not found, or expired"),
+        (status = CONFLICT, description = "Export job is not completed"),
+    ),
+    params(
+        ("id" = String, Path, description = "ID of the export job")
+    )
+)]
+#[instrument(skip(exports, req), err)]
+pub async fn download(
+    exports: web::Data<Exports>,
+    path: web::Path<String>,
+    user: UserInformation,
+    req: HttpRequest,
+) -> actix_web::Result<HttpResponse> {
+    let (job, file) = exports.result(Exports::owner(user.id()), &path)?;
+
+    let mut response = NamedFile::open_async(file).await?.into_response(&req);
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(job.kind.content_type()));
+    headers.insert(
+        header::CONTENT_DISPOSITION,
+        HeaderValue::from_str(&format!(r#"attachment; filename="{}""#, job.kind.file_name()))?,
+    );
+    Ok(response)
+}
@@ -1,15 +1,28 @@
 pub mod advisory;
 pub mod cve;
 pub mod dashboard;
+pub mod export;
 pub mod index;
 pub mod license;
+pub mod ownership;
 pub mod package;
 pub mod sbom;
+pub mod score;
+pub mod subscription;
 pub mod suggestion;
 pub mod wellknown;
 
 use crate::openapi;
-use utoipa::OpenApi;
+use actix_web::{web, web::ServiceConfig, HttpResponse};
+use std::sync::Arc;
+use trustification_auth::{
+    authenticator::{user::UserInformation, Authenticator},
+    authorizer::Authorizer,
+    openapi::{annotate_permissions, permitted_operations, OperationPermission},
+    Permission,
+};
+use trustification_infrastructure::new_auth;
+use utoipa::{openapi::path::PathItemType, OpenApi};
 
 #[derive(OpenApi)]
 #[openapi(
@@ -21,7 +34,11 @@ use utoipa::OpenApi;
         sbom::get,
         sbom::search,
         sbom::get_vulnerabilities,
+        sbom::get_provenance,
+        sbom::get_ingestion,
+        sbom::get_posture,
         advisory::get,
+        advisory::bundle,
         advisory::search,
 
         package::package_search,
@@ -33,6 +50,29 @@ use utoipa::OpenApi;
 
         cve::cve_get,
         cve::cve_search,
+        cve::cve_timeline,
+
+        score::get_overrides,
+        score::update_override,
+        score::delete_override,
+
+        ownership::get_rules,
+        ownership::update_rule,
+        ownership::delete_rule,
+        ownership::get_webhooks,
+        ownership::update_webhook,
+        ownership::delete_webhook,
+        ownership::test_webhook,
+
+        subscription::get_subscriptions,
+        subscription::update_subscription,
+        subscription::delete_subscription,
+        subscription::get_events,
+
+        export::submit,
+        export::list,
+        export::get,
+        export::download,
     ),
 
     components(
@@ -41,16 +81,44 @@ use utoipa::OpenApi;
             openapi::SearchResultVex,
             openapi::SearchResultCve,
 
+            spog_model::export::ExportJob,
+            spog_model::export::ExportKind,
+            spog_model::export::ExportRequest,
+            spog_model::export::ExportStatus,
+
             spog_model::pkg::PackageRefList,
             spog_model::pkg::PackageRef,
 
+            spog_model::posture::PostureSnapshot,
+
+            spog_model::ingestion::IngestionReport,
+            spog_model::ingestion::DescribedPackage,
+
+            spog_model::ownership::OwnershipRule,
+            spog_model::ownership::TeamWebhook,
+            spog_model::ownership::OwnershipNotification,
+            spog_model::ownership::Finding,
+            spog_model::subscription::Subscription,
+            spog_model::subscription::SubscriptionFinding,
+            spog_model::subscription::SubscriptionEvent,
+            spog_model::subscription::SubscriptionEvents,
+
             spog_model::package_info::PackageInfo,
             spog_model::package_info::PackageProductDetails,
             spog_model::package_info::ProductRelatedToPackage,
             spog_model::package_info::V11yRef,
 
+            spog_model::provenance::ComponentProvenance,
+            spog_model::provenance::ProvenanceReport,
+            spog_model::provenance::ProvenanceStatus,
+            spog_model::provenance::Registry,
+
+            spog_model::score::AdjustedScore,
+            spog_model::score::ScoreOverride,
+
             spog_model::search::AdvisorySummary,
             spog_model::search::SbomSummary,
+            spog_model::search::SbomReference,
 
             spog_model::suggestion::Suggestion,
             spog_model::suggestion::Action,
@@ -67,6 +135,10 @@ use utoipa::OpenApi;
 
             v11y_model::search::SearchHitWithDocument,
             v11y_model::search::SearchDocument,
+
+            vexination_model::timeline::CveTimeline,
+            vexination_model::timeline::TimelineEvent,
+            vexination_model::timeline::TimelineEventKind,
         )
     ),
     tags(
@@ -76,6 +148,60 @@ use utoipa::OpenApi;
         (name = "vulnerability", description = "Vulnerability endpoints"),
         (name = "well-known", description = ".well-known endpoints"),
         (name = "search", description = "Search endpoint"),
+        (name = "score", description = "CVSS score endpoints"),
+        (name = "ownership", description = "Package ownership endpoints"),
+        (name = "export", description = "Export job endpoints"),
     ),
 )]
 pub struct ApiDoc;
+
+/// The permissions required by the operations of [`ApiDoc`], including the ones checked by the backends.
+const PERMISSIONS: &[OperationPermission] = &[
+    ("/api/v1/sbom", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/sbom/search", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/advisory", PathItemType::Get, Permission::ReadVex),
+    ("/api/v1/advisory/bundle", PathItemType::Get, Permission::ReadVex),
+    ("/api/v1/advisory/search", PathItemType::Get, Permission::ReadVex),
+    ("/api/v1/score/overrides", PathItemType::Put, Permission::UpdateVex),
+    ("/api/v1/score/overrides", PathItemType::Delete, Permission::UpdateVex),
+    ("/api/v1/ownership/rules", PathItemType::Put, Permission::UpdateSbom),
+    ("/api/v1/ownership/rules", PathItemType::Delete, Permission::UpdateSbom),
+    ("/api/v1/ownership/webhooks", PathItemType::Get, Permission::UpdateSbom),
+    ("/api/v1/ownership/webhooks", PathItemType::Put, Permission::UpdateSbom),
+    (
+        "/api/v1/ownership/webhooks",
+        PathItemType::Delete,
+        Permission::UpdateSbom,
+    ),
+    (
+        "/api/v1/ownership/webhooks/test",
+        PathItemType::Post,
+        Permission::UpdateSbom,
+    ),
+    ("/api/v1/subscriptions", PathItemType::Get, Permission::UpdateSbom),
+    ("/api/v1/subscriptions", PathItemType::Put, Permission::UpdateSbom),
+    ("/api/v1/subscriptions", PathItemType::Delete, Permission::UpdateSbom),
+    ("/api/v1/subscriptions/events", PathItemType::Get, Permission::ReadSbom),
+];
+
+/// The OpenAPI document of the endpoints, mentioning the permission each operation requires.
+pub fn api_doc() -> utoipa::openapi::OpenApi {
+    let mut openapi = ApiDoc::openapi();
+    annotate_permissions(&mut openapi, PERMISSIONS);
+    openapi
+}
+
+pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config.service(
+            web::resource("/api/v1/openapi.json")
+                .wrap(new_auth!(auth))
+                .route(web::get().to(permitted_openapi)),
+        );
+    }
+}
+
+/// Get the OpenAPI document, only containing the operations the caller is permitted to call.
+async fn permitted_openapi(authorizer: web::Data<Authorizer>, user: UserInformation) -> HttpResponse {
+    HttpResponse::Ok().json(permitted_operations(api_doc(), PERMISSIONS, &authorizer, &user))
+}
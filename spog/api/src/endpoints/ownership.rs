@@ -0,0 +1,511 @@
+//! Ownership of packages by teams, and routing notifications of new vulnerabilities to the owning teams.
+
+use crate::app_state::AppState;
+use actix_web::{web, web::ServiceConfig, HttpResponse};
+use reqwest::header::{HeaderValue, CONTENT_TYPE};
+use spog_model::ownership::{Finding, OwnershipNotification, OwnershipRule, TeamWebhook};
+use spog_model::vuln::SbomReport;
+use std::collections::BTreeMap;
+use std::error::Error as _;
+use std::sync::Arc;
+use tera::{Context, Tera};
+use tracing::instrument;
+use trustification_auth::{
+    authenticator::{user::UserInformation, Authenticator},
+    authorizer::Authorizer,
+    Permission,
+};
+use trustification_common::error::ErrorInformation;
+use trustification_infrastructure::new_auth;
+use utoipa::IntoParams;
+
+pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config.service(
+            web::resource("/api/v1/ownership/rules")
+                .wrap(new_auth!(auth.clone()))
+                .route(web::get().to(get_rules))
+                .route(web::put().to(update_rule))
+                .route(web::delete().to(delete_rule)),
+        );
+        config.service(
+            web::resource("/api/v1/ownership/webhooks")
+                .wrap(new_auth!(auth.clone()))
+                .route(web::get().to(get_webhooks))
+                .route(web::put().to(update_webhook))
+                .route(web::delete().to(delete_webhook)),
+        );
+        config.service(
+            web::resource("/api/v1/ownership/webhooks/test")
+                .wrap(new_auth!(auth))
+                .route(web::post().to(test_webhook)),
+        );
+    }
+}
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct RuleParams {
+    /// The Package URL pattern of the rule
+    pub pattern: String,
+}
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct WebhookParams {
+    /// The team of the webhook
+    pub team: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/ownership/rules",
+    responses(
+        (status = OK, description = "Rules assigning packages to teams", body = Vec<OwnershipRule>),
+    ),
+)]
+#[instrument(skip(state), err)]
+pub async fn get_rules(state: web::Data<AppState>) -> actix_web::Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(state.db_storage.select_ownership_rules().await?))
+}
+
+/// Create or replace the rule of a Package URL pattern.
+///
+/// Rules are managed by those allowed to update SBOMs.
+#[utoipa::path(
+    put,
+    path = "/api/v1/ownership/rules",
+    request_body = OwnershipRule,
+    responses(
+        (status = OK, description = "Rule was stored", body = OwnershipRule),
+        (status = BAD_REQUEST, description = "Invalid pattern or team"),
+    ),
+)]
+#[instrument(skip(state, authorizer), err)]
+pub async fn update_rule(
+    state: web::Data<AppState>,
+    web::Json(rule): web::Json<OwnershipRule>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::UpdateSbom)?;
+
+    if !rule.pattern.starts_with("pkg:") || rule.team.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "InvalidRule".to_string(),
+            message: "The pattern must be a Package URL, owned by a team".to_string(),
+            details: format!("{} → {}", rule.pattern, rule.team),
+        }));
+    }
+
+    state.db_storage.update_ownership_rule(rule.clone()).await?;
+    Ok(HttpResponse::Ok().json(rule))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/ownership/rules",
+    responses(
+        (status = NO_CONTENT, description = "Rule was deleted"),
+        (status = NOT_FOUND, description = "Rule was not found"),
+    ),
+    params(RuleParams)
+)]
+#[instrument(skip(state, authorizer), err)]
+pub async fn delete_rule(
+    state: web::Data<AppState>,
+    web::Query(params): web::Query<RuleParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::UpdateSbom)?;
+
+    match state.db_storage.delete_ownership_rule(&params.pattern).await? {
+        true => Ok(HttpResponse::NoContent().finish()),
+        false => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+/// Get the webhooks of the teams.
+///
+/// As their URLs may contain secrets, they are only listed to those allowed to manage them.
+#[utoipa::path(
+    get,
+    path = "/api/v1/ownership/webhooks",
+    responses(
+        (status = OK, description = "Webhooks of the teams", body = Vec<TeamWebhook>),
+    ),
+    params(WebhookParams)
+)]
+#[instrument(skip(state, authorizer), err)]
+pub async fn get_webhooks(
+    state: web::Data<AppState>,
+    web::Query(params): web::Query<WebhookParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::UpdateSbom)?;
+
+    Ok(HttpResponse::Ok().json(state.db_storage.select_team_webhooks(params.team.as_deref()).await?))
+}
+
+/// Create or replace the webhook of a team.
+///
+/// A template of the payload is checked by rendering an example notification, and the content type must be usable as
+/// the value of a header.
+#[utoipa::path(
+    put,
+    path = "/api/v1/ownership/webhooks",
+    request_body = TeamWebhook,
+    responses(
+        (status = OK, description = "Webhook was stored", body = TeamWebhook),
+        (status = BAD_REQUEST, description = "Invalid URL, template or content type"),
+    ),
+)]
+#[instrument(skip(state, authorizer, webhook), fields(team = %webhook.team), err)]
+pub async fn update_webhook(
+    state: web::Data<AppState>,
+    web::Json(webhook): web::Json<TeamWebhook>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::UpdateSbom)?;
+
+    if let Err(err) = url::Url::parse(&webhook.url) {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "InvalidUrl".to_string(),
+            message: "The URL of the webhook is invalid".to_string(),
+            details: err.to_string(),
+        }));
+    }
+    if let Some(Err(err)) = webhook.content_type.as_deref().map(HeaderValue::from_str) {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "InvalidContentType".to_string(),
+            message: "The content type of the webhook is not a valid header value".to_string(),
+            details: err.to_string(),
+        }));
+    }
+    if let Err(err) = payload(&webhook, &example(&webhook.team)) {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "InvalidTemplate".to_string(),
+            message: "The template of the webhook can't be rendered".to_string(),
+            details: template_error(&err),
+        }));
+    }
+
+    state.db_storage.update_team_webhook(webhook.clone()).await?;
+    Ok(HttpResponse::Ok().json(webhook))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/ownership/webhooks",
+    responses(
+        (status = NO_CONTENT, description = "Webhook was deleted"),
+        (status = NOT_FOUND, description = "Webhook was not found"),
+    ),
+    params(WebhookParams)
+)]
+#[instrument(skip(state, authorizer), err)]
+pub async fn delete_webhook(
+    state: web::Data<AppState>,
+    web::Query(params): web::Query<WebhookParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::UpdateSbom)?;
+
+    let Some(team) = &params.team else {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "MissingTeam".to_string(),
+            message: "The team of the webhook is required".to_string(),
+            details: String::new(),
+        }));
+    };
+
+    match state.db_storage.delete_team_webhook(team).await? {
+        true => Ok(HttpResponse::NoContent().finish()),
+        false => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+/// Post an example notification to the webhook of a team, to try its payload.
+#[utoipa::path(
+    post,
+    path = "/api/v1/ownership/webhooks/test",
+    responses(
+        (status = NO_CONTENT, description = "Webhook accepted the notification"),
+        (status = BAD_REQUEST, description = "Team is missing"),
+        (status = NOT_FOUND, description = "Webhook was not found"),
+        (status = BAD_GATEWAY, description = "Webhook failed"),
+    ),
+    params(WebhookParams)
+)]
+#[instrument(skip(state, authorizer), err)]
+pub async fn test_webhook(
+    state: web::Data<AppState>,
+    web::Query(params): web::Query<WebhookParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::UpdateSbom)?;
+
+    let Some(team) = &params.team else {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "MissingTeam".to_string(),
+            message: "The team of the webhook is required".to_string(),
+            details: String::new(),
+        }));
+    };
+    let Some(webhook) = state.db_storage.select_team_webhooks(Some(team)).await?.pop() else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    match deliver(&state, &webhook, &example(team)).await {
+        Ok(()) => Ok(HttpResponse::NoContent().finish()),
+        Err(err) => Ok(HttpResponse::BadGateway().json(ErrorInformation {
+            error: "WebhookFailed".to_string(),
+            message: "The webhook failed to accept the notification".to_string(),
+            details: format!("{err:#}"),
+        })),
+    }
+}
+
+/// Add the owners of the affected packages to a report.
+///
+/// Like score overrides, owners are applied to cached reports too, so changing the rules doesn't require invalidating
+/// the cache.
+#[instrument(skip(state, report), err)]
+pub(crate) async fn add_owners(state: &AppState, report: Arc<SbomReport>) -> actix_web::Result<Arc<SbomReport>> {
+    let rules = state.db_storage.select_ownership_rules().await?;
+    if rules.is_empty() {
+        return Ok(report);
+    }
+
+    let mut report = (*report).clone();
+    report.owners = owners(&rules, &report);
+    Ok(Arc::new(report))
+}
+
+fn owners(rules: &[OwnershipRule], report: &SbomReport) -> BTreeMap<String, String> {
+    report
+        .details
+        .iter()
+        .flat_map(|vuln| vuln.affected_packages.keys())
+        .filter_map(|purl| OwnershipRule::owner(rules, purl).map(|team| (purl.clone(), team.to_string())))
+        .collect()
+}
+
+/// Notify the owning teams of the vulnerabilities newly found in their packages, through their webhooks.
+///
+/// Each finding is notified once per SBOM, and recorded as notified once its webhook accepted it. Findings of teams
+/// without a webhook, or whose webhook failed, are not recorded, so that they are notified again the next time. Failed
+/// deliveries are reported as error, after notifying the other teams.
+#[instrument(skip(state, report), err)]
+pub(crate) async fn notify_owners(state: &AppState, id: &str, report: &SbomReport) -> Result<(), anyhow::Error> {
+    let rules = state.db_storage.select_ownership_rules().await?;
+    if rules.is_empty() {
+        return Ok(());
+    }
+
+    let mut findings: BTreeMap<&str, Vec<Finding>> = BTreeMap::new();
+    for vuln in &report.details {
+        for purl in vuln.affected_packages.keys() {
+            if let Some(team) = OwnershipRule::owner(&rules, purl) {
+                findings.entry(team).or_default().push(Finding {
+                    vulnerability: vuln.id.clone(),
+                    purl: purl.clone(),
+                });
+            }
+        }
+    }
+
+    let mut failed = Vec::new();
+    for (team, findings) in findings {
+        let Some(webhook) = state.db_storage.select_team_webhooks(Some(team)).await?.pop() else {
+            continue;
+        };
+        let findings = state.db_storage.select_new_findings(id, findings).await?;
+        if findings.is_empty() {
+            continue;
+        }
+
+        log::info!("Notifying {team} of {} new findings in {id}", findings.len());
+        let notification = OwnershipNotification {
+            team: team.to_string(),
+            sbom: id.to_string(),
+            findings,
+        };
+        match deliver(state, &webhook, &notification).await {
+            Ok(()) => {
+                state
+                    .db_storage
+                    .record_notified_findings(id, &notification.findings)
+                    .await?
+            }
+            Err(err) => {
+                log::warn!("Failed to notify {team} through its webhook: {err}");
+                failed.push(team);
+            }
+        }
+    }
+
+    match failed.is_empty() {
+        true => Ok(()),
+        false => Err(anyhow::anyhow!("failed to notify {}", failed.join(", "))),
+    }
+}
+
+/// Post a notification to a webhook, failing unless it answers with a `2xx` status.
+async fn deliver(
+    state: &AppState,
+    webhook: &TeamWebhook,
+    notification: &OwnershipNotification,
+) -> Result<(), anyhow::Error> {
+    let (content_type, body) = payload(webhook, notification).map_err(|err| anyhow::anyhow!(template_error(&err)))?;
+    state
+        .client
+        .post(&webhook.url)
+        .header(CONTENT_TYPE, content_type)
+        .body(body)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// The payload of a notification for a webhook, rendered using its template if it has one, and its content type.
+fn payload(webhook: &TeamWebhook, notification: &OwnershipNotification) -> Result<(String, Vec<u8>), tera::Error> {
+    let content_type = webhook
+        .content_type
+        .clone()
+        .unwrap_or_else(|| "application/json".to_string());
+    let body = match &webhook.template {
+        Some(template) => {
+            let mut tera = Tera::default();
+            tera.add_raw_template("payload", template)?;
+            tera.render("payload", &Context::from_serialize(notification)?)?
+                .into_bytes()
+        }
+        None => serde_json::to_vec(notification)?,
+    };
+    Ok((content_type, body))
+}
+
+/// Describe a template error, which only tells the cause in its sources.
+fn template_error(err: &tera::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        message = format!("{message}: {err}");
+        source = err.source();
+    }
+    message
+}
+
+/// A notification as example, for checking and testing webhooks.
+fn example(team: &str) -> OwnershipNotification {
+    OwnershipNotification {
+        team: team.to_string(),
+        sbom: "example-sbom".to_string(),
+        findings: vec![Finding {
+            vulnerability: "CVE-2023-44487".to_string(),
+            purl: "pkg:maven/io.vertx/vertx-core@4.4.4".to_string(),
+        }],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use spog_model::vuln::SbomReportVulnerability;
+
+    #[test]
+    fn report_owners() {
+        let vuln = |id: &str, purls: &[&str]| SbomReportVulnerability {
+            id: id.to_string(),
+            affected_packages: purls.iter().map(|purl| (purl.to_string(), vec![])).collect(),
+            ..Default::default()
+        };
+        let report = SbomReport {
+            name: "sbom".to_string(),
+            version: None,
+            created: None,
+            summary: vec![],
+            details: vec![
+                vuln("CVE-2023-0286", &["pkg:rpm/redhat/openssl@1.1.1k-7.el8_6"]),
+                vuln(
+                    "CVE-2023-44487",
+                    &["pkg:maven/io.vertx/vertx-core@4.4.4", "pkg:npm/http2@1.0.0"],
+                ),
+            ],
+            backtraces: Default::default(),
+            owners: Default::default(),
+        };
+        let rules = [
+            OwnershipRule {
+                pattern: "pkg:rpm/redhat/openssl@*".to_string(),
+                team: "crypto".to_string(),
+            },
+            OwnershipRule {
+                pattern: "pkg:maven/io.vertx/*".to_string(),
+                team: "vertx".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            owners(&rules, &report),
+            BTreeMap::from([
+                ("pkg:maven/io.vertx/vertx-core@4.4.4".to_string(), "vertx".to_string()),
+                (
+                    "pkg:rpm/redhat/openssl@1.1.1k-7.el8_6".to_string(),
+                    "crypto".to_string()
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn webhook_payload() {
+        let mut webhook = TeamWebhook {
+            team: "vertx".to_string(),
+            url: "https://hooks.example.com/services/vertx".to_string(),
+            template: None,
+            content_type: None,
+        };
+        let notification = example("vertx");
+
+        let (content_type, body) = payload(&webhook, &notification).unwrap();
+        assert_eq!(content_type, "application/json");
+        assert_eq!(
+            serde_json::from_slice::<OwnershipNotification>(&body).unwrap(),
+            notification
+        );
+
+        webhook.template =
+            Some("{% for finding in findings %}{{ finding.vulnerability }} in {{ sbom }}{% endfor %}".to_string());
+        webhook.content_type = Some("text/plain".to_string());
+        let (content_type, body) = payload(&webhook, &notification).unwrap();
+        assert_eq!(content_type, "text/plain");
+        assert_eq!(body, b"CVE-2023-44487 in example-sbom");
+
+        // values are inserted as they are, unless encoded
+        let mut quoted = notification.clone();
+        quoted.sbom = r#"sbom "quoted""#.to_string();
+        webhook.template = Some(r#"{"text": "{{ sbom }}"}"#.to_string());
+        let (_, body) = payload(&webhook, &quoted).unwrap();
+        assert!(serde_json::from_slice::<serde_json::Value>(&body).is_err());
+        webhook.template = Some(
+            r#"{% set count = findings | length %}{% set text = count ~ " new vulnerabilities in " ~ sbom %}{"text": {{ text | json_encode() }}}"#
+                .to_string(),
+        );
+        let (_, body) = payload(&webhook, &quoted).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            serde_json::json!({"text": r#"1 new vulnerabilities in sbom "quoted""#})
+        );
+
+        webhook.template = Some("{{ findings | length".to_string());
+        assert!(payload(&webhook, &notification).is_err());
+        webhook.template = Some("{{ owner }}".to_string());
+        assert!(payload(&webhook, &notification).is_err());
+    }
+}
@@ -71,7 +71,12 @@ pub async fn package_search(
         let item = item.document;
         let purl = item.purl;
         let vulnerabilities = get_vulnerabilities(&guac, &v11y, &purl).await?;
-        m.push(PackageInfo { purl, vulnerabilities });
+        m.push(PackageInfo {
+            purl,
+            vulnerabilities,
+            repository: item.repository,
+            scorecard: item.scorecard,
+        });
     }
 
     let result = SearchResult {
@@ -101,7 +106,12 @@ pub async fn package_get(
 ) -> Result<HttpResponse, Error> {
     let purl = path.into_inner();
     let vulnerabilities = get_vulnerabilities(&guac, &v11y, &purl).await?;
-    let pkg = PackageInfo { purl, vulnerabilities };
+    let pkg = PackageInfo {
+        purl,
+        vulnerabilities,
+        repository: None,
+        scorecard: None,
+    };
     Ok(HttpResponse::Ok().json(&pkg))
 }
 
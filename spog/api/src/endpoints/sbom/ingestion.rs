@@ -0,0 +1,165 @@
+use super::provenance::sample;
+use super::vuln::{find_main, map_purls};
+use crate::app_state::AppState;
+use crate::error::Error;
+use crate::service::guac::GuacService;
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use bombastic_model::data::SBOM;
+use bytes::BytesMut;
+use cyclonedx_bom::prelude::Component as CycloneDxComponent;
+use futures::{stream::iter, StreamExt, TryStreamExt};
+use spog_model::ingestion::{DescribedPackage, IngestionReport};
+use std::collections::BTreeSet;
+use tracing::{info_span, instrument, Instrument};
+use utoipa::IntoParams;
+
+/// Number of packages looked up in GUAC concurrently
+const LOOKUP_CONCURRENCY: usize = 8;
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct IngestionParams {
+    /// ID of the SBOM to verify
+    pub id: String,
+    /// Number of packages to look up, all packages if omitted
+    pub sample: Option<usize>,
+}
+
+/// Verify an SBOM was ingested into GUAC.
+///
+/// Checks if GUAC has the SBOM for the packages it describes, using the SPDX document namespace or CycloneDX serial
+/// number as its URI, and looks up the packages of the SBOM in GUAC. The report lists packages GUAC doesn't know,
+/// which helps with debugging missing vulnerabilities of an SBOM. When sampling, the looked up packages are spread
+/// evenly over all packages.
+#[utoipa::path(
+    get,
+    path = "/api/v1/sbom/ingestion",
+    responses(
+        (status = OK, description = "Ingestion state of the SBOM", body = IngestionReport),
+        (status = NOT_FOUND, description = "SBOM was not found")
+    ),
+    params(IngestionParams)
+)]
+#[instrument(skip(state, guac, access_token), err)]
+pub async fn get_ingestion(
+    state: web::Data<AppState>,
+    guac: web::Data<GuacService>,
+    params: web::Query<IngestionParams>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let sbom: BytesMut = state
+        .get_sbom(&params.id, &access_token)
+        .await?
+        .try_collect()
+        .instrument(info_span!("download SBOM data"))
+        .await
+        .map_err(Error::Request)?;
+    let sbom = SBOM::parse(&sbom).map_err(|err| Error::Generic(format!("Unable to parse SBOM: {err}")))?;
+
+    let Packages { uri, described, all } = packages(&sbom);
+
+    let mut described_packages = Vec::with_capacity(described.len());
+    for purl in described {
+        let sboms = guac.sbom_uris(&purl).await?;
+        described_packages.push(DescribedPackage { purl, sboms });
+    }
+
+    let total = all.len();
+    let size = params.sample.unwrap_or(usize::MAX);
+    let lookups = iter(sample(all.into_iter().collect(), size))
+        .map(|purl| {
+            let guac = guac.clone();
+            async move {
+                let result = guac.has_package(&purl).await.map_err(|err| err.to_string());
+                (purl, result)
+            }
+        })
+        .buffer_unordered(LOOKUP_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+    Ok(HttpResponse::Ok().json(IngestionReport::new(
+        params.into_inner().id,
+        uri,
+        described_packages,
+        total,
+        lookups,
+    )))
+}
+
+/// The Package URLs of an SBOM, and the URI identifying it.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Packages {
+    uri: String,
+    /// Package URLs of the described packages
+    described: Vec<String>,
+    /// Package URLs of all packages, including the described ones
+    all: BTreeSet<String>,
+}
+
+fn packages(sbom: &SBOM) -> Packages {
+    match sbom {
+        SBOM::SPDX(spdx) => Packages {
+            uri: spdx.document_creation_information.spdx_document_namespace.clone(),
+            described: find_main(spdx).into_iter().flat_map(map_purls).collect(),
+            all: spdx.package_information.iter().flat_map(map_purls).collect(),
+        },
+        SBOM::CycloneDX(bom) => {
+            let main = bom.metadata.as_ref().and_then(|metadata| metadata.component.as_ref());
+
+            let mut all = BTreeSet::new();
+            let mut pending: Vec<&CycloneDxComponent> = main
+                .into_iter()
+                .chain(bom.components.iter().flat_map(|components| components.0.iter()))
+                .collect();
+            while let Some(component) = pending.pop() {
+                pending.extend(component.components.iter().flat_map(|components| components.0.iter()));
+                if let Some(purl) = &component.purl {
+                    all.insert(purl.to_string());
+                }
+            }
+
+            Packages {
+                uri: bom
+                    .serial_number
+                    .as_ref()
+                    .map(|serial| serial.to_string())
+                    .unwrap_or_default(),
+                described: main
+                    .and_then(|component| component.purl.as_ref())
+                    .map(|purl| purl.to_string())
+                    .into_iter()
+                    .collect(),
+                all,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spdx_packages() {
+        let sbom = SBOM::parse(include_bytes!("../../../../../bombastic/testdata/ubi9-sbom.json")).unwrap();
+        let packages = packages(&sbom);
+
+        assert!(packages.uri.starts_with("https://"));
+        assert!(!packages.described.is_empty());
+        for purl in &packages.described {
+            assert!(packages.all.contains(purl), "{purl}");
+        }
+        assert!(packages.all.len() > packages.described.len());
+    }
+
+    #[test]
+    fn cyclonedx_packages() {
+        let sbom = SBOM::parse(include_bytes!("../../../../../bombastic/testdata/syft.cyclonedx.json")).unwrap();
+        let packages = packages(&sbom);
+
+        assert!(packages.uri.starts_with("urn:uuid:"));
+        assert!(!packages.all.is_empty());
+        assert!(packages.all.iter().all(|purl| purl.starts_with("pkg:")));
+    }
+}
@@ -1,8 +1,14 @@
 mod get;
+mod ingestion;
+mod posture;
+mod provenance;
 mod search;
 pub(crate) mod vuln;
 
 pub use get::*;
+pub use ingestion::*;
+pub use posture::*;
+pub use provenance::*;
 pub use search::*;
 pub use vuln::*;
 
@@ -23,6 +29,21 @@ pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut Se
                 .wrap(new_auth!(auth.clone()))
                 .to(sboms_with_vulnerability_summary),
         );
+        config.service(
+            web::resource("/api/v1/sbom/provenance")
+                .wrap(new_auth!(auth.clone()))
+                .to(get_provenance),
+        );
+        config.service(
+            web::resource("/api/v1/sbom/ingestion")
+                .wrap(new_auth!(auth.clone()))
+                .to(get_ingestion),
+        );
+        config.service(
+            web::resource("/api/v1/sbom/posture")
+                .wrap(new_auth!(auth.clone()))
+                .to(get_posture),
+        );
         config.service(
             web::resource("/api/v1/sbom/vulnerabilities")
                 .wrap(new_auth!(auth))
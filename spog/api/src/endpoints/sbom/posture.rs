@@ -0,0 +1,43 @@
+use crate::app_state::AppState;
+use actix_web::{web, HttpResponse};
+use spog_model::posture::PostureSnapshot;
+use tracing::instrument;
+use utoipa::IntoParams;
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct PostureParams {
+    /// ID of the SBOM to get the snapshots of
+    pub id: Option<String>,
+    /// Name of the SBOMs to get the snapshots of, covering all releases of a product
+    pub name: Option<String>,
+}
+
+/// Get the vulnerability posture of an SBOM over time.
+///
+/// Returns the snapshots of the number of vulnerabilities by severity, oldest first. Snapshots are taken periodically
+/// by a background task, if enabled. Selecting the snapshots by name instead of ID includes all SBOMs of a product,
+/// telling its releases apart by their version.
+#[utoipa::path(
+    get,
+    path = "/api/v1/sbom/posture",
+    responses(
+        (status = OK, description = "Snapshots of the vulnerability posture", body = Vec<PostureSnapshot>),
+        (status = BAD_REQUEST, description = "Neither an ID nor a name was provided")
+    ),
+    params(PostureParams)
+)]
+#[instrument(skip(state), err)]
+pub async fn get_posture(
+    state: web::Data<AppState>,
+    params: web::Query<PostureParams>,
+) -> actix_web::Result<HttpResponse> {
+    if params.id.is_none() && params.name.is_none() {
+        return Err(actix_web::error::ErrorBadRequest("either an ID or a name is required"));
+    }
+
+    let snapshots = state
+        .db_storage
+        .select_posture_snapshots(params.id.as_deref(), params.name.as_deref())
+        .await?;
+    Ok(HttpResponse::Ok().json(snapshots))
+}
@@ -0,0 +1,254 @@
+use crate::app_state::AppState;
+use crate::error::Error;
+use crate::i18n;
+use crate::service::registry::{Algorithm, Checksum, RegistryService};
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use bombastic_model::data::SBOM;
+use bytes::BytesMut;
+use cyclonedx_bom::models::hash::HashAlgorithm;
+use cyclonedx_bom::prelude::Component as CycloneDxComponent;
+use futures::{stream::iter, StreamExt, TryStreamExt};
+use packageurl::PackageUrl;
+use spdx_rs::models::Algorithm as SpdxAlgorithm;
+use spog_model::provenance::{ComponentProvenance, ProvenanceReport, ProvenanceStatus, Registry};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use tracing::{info_span, instrument, Instrument};
+use utoipa::IntoParams;
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct ProvenanceParams {
+    /// ID of the SBOM to check
+    pub id: String,
+    /// Number of components to check, all components (up to the maximum configured on the server) if omitted
+    pub sample: Option<usize>,
+}
+
+/// Check the components of an SBOM against their upstream package registries.
+///
+/// Components with a versioned Package URL of crates.io (`cargo`), npm, PyPI or Maven Central are looked up in
+/// their registry, comparing the checksums of the SBOM with the published ones. Components which are unknown to
+/// their registry, or have a mismatching checksum, are flagged, as they may have been substituted (e.g. by
+/// dependency confusion). When sampling, the checked components are spread evenly over all supported components.
+#[utoipa::path(
+    get,
+    path = "/api/v1/sbom/provenance",
+    responses(
+        (status = OK, description = "Provenance of the components", body = ProvenanceReport),
+        (status = NOT_FOUND, description = "SBOM was not found")
+    ),
+    params(ProvenanceParams)
+)]
+#[instrument(skip(state, registry, access_token), err)]
+pub async fn get_provenance(
+    state: web::Data<AppState>,
+    registry: web::Data<RegistryService>,
+    params: web::Query<ProvenanceParams>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let sbom: BytesMut = state
+        .get_sbom(&params.id, &access_token)
+        .await?
+        .try_collect()
+        .instrument(info_span!("download SBOM data"))
+        .await
+        .map_err(Error::Request)?;
+    let sbom = SBOM::parse(&sbom).map_err(|err| Error::Generic(format!("Unable to parse SBOM: {err}")))?;
+
+    let components = components(&sbom);
+    let supported = components.len();
+    let size = params.sample.unwrap_or(usize::MAX).min(registry.max_components());
+
+    let checked = iter(sample(components, size))
+        .map(|component| check(&registry, component))
+        .buffer_unordered(registry.concurrency())
+        .collect::<Vec<_>>()
+        .await;
+
+    Ok(HttpResponse::Ok().json(ProvenanceReport::new(supported, checked)))
+}
+
+/// A component of an SBOM, which can be checked against its registry.
+struct Component {
+    purl: PackageUrl<'static>,
+    registry: Registry,
+    checksums: Vec<Checksum>,
+}
+
+/// Collect the components with a versioned Package URL of a supported registry, ordered by their Package URL.
+fn components(sbom: &SBOM) -> Vec<Component> {
+    let mut components = BTreeMap::new();
+    let mut add = |purl: &str, checksums: Vec<Checksum>| {
+        let Ok(purl) = PackageUrl::from_str(purl) else {
+            log::debug!("Ignoring invalid Package URL: {purl}");
+            return;
+        };
+        let Some(registry) = Registry::from_purl_type(purl.ty()) else {
+            return;
+        };
+        if purl.version().is_none() {
+            return;
+        }
+        let component = components.entry(purl.to_string()).or_insert_with(|| Component {
+            purl,
+            registry,
+            checksums: vec![],
+        });
+        for checksum in checksums {
+            if !component.checksums.contains(&checksum) {
+                component.checksums.push(checksum);
+            }
+        }
+    };
+
+    match sbom {
+        SBOM::SPDX(spdx) => {
+            for package in &spdx.package_information {
+                let checksums: Vec<_> = package
+                    .package_checksum
+                    .iter()
+                    .filter_map(|checksum| {
+                        let algorithm = match checksum.algorithm {
+                            SpdxAlgorithm::SHA1 => Algorithm::Sha1,
+                            SpdxAlgorithm::SHA256 => Algorithm::Sha256,
+                            SpdxAlgorithm::SHA512 => Algorithm::Sha512,
+                            _ => return None,
+                        };
+                        Some(Checksum::new(algorithm, &checksum.value))
+                    })
+                    .collect();
+                for reference in &package.external_reference {
+                    if reference.reference_type == "purl" {
+                        add(&reference.reference_locator, checksums.clone());
+                    }
+                }
+            }
+        }
+        SBOM::CycloneDX(bom) => {
+            let mut pending: Vec<&CycloneDxComponent> = bom
+                .metadata
+                .iter()
+                .flat_map(|metadata| metadata.component.iter())
+                .chain(bom.components.iter().flat_map(|components| components.0.iter()))
+                .collect();
+            while let Some(component) = pending.pop() {
+                pending.extend(component.components.iter().flat_map(|components| components.0.iter()));
+                let Some(purl) = &component.purl else {
+                    continue;
+                };
+                let checksums = component
+                    .hashes
+                    .iter()
+                    .flat_map(|hashes| hashes.0.iter())
+                    .filter_map(|hash| {
+                        let algorithm = match hash.alg {
+                            HashAlgorithm::SHA1 => Algorithm::Sha1,
+                            HashAlgorithm::SHA_256 => Algorithm::Sha256,
+                            HashAlgorithm::SHA_512 => Algorithm::Sha512,
+                            _ => return None,
+                        };
+                        Some(Checksum::new(algorithm, &hash.content.0))
+                    })
+                    .collect();
+                add(&purl.to_string(), checksums);
+            }
+        }
+    }
+
+    components.into_values().collect()
+}
+
+/// Pick `size` items, spread evenly over all items.
+pub(super) fn sample<T>(items: Vec<T>, size: usize) -> Vec<T> {
+    let len = items.len();
+    if len <= size {
+        return items;
+    }
+    let mut picks = (0..size).map(|n| n * len / size).peekable();
+    items
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, item)| picks.next_if_eq(&index).map(|_| item))
+        .collect()
+}
+
+async fn check(registry: &RegistryService, component: Component) -> ComponentProvenance {
+    let (status, details) = match registry.lookup(component.registry, &component.purl).await {
+        Ok(Some(published)) => compare(&component.checksums, &published.checksums),
+        Ok(None) => (ProvenanceStatus::Unknown, None),
+        Err(err) => (ProvenanceStatus::Error, Some(err.to_string())),
+    };
+
+    ComponentProvenance {
+        purl: component.purl.to_string(),
+        registry: component.registry,
+        status,
+        details,
+    }
+}
+
+/// Compare the checksums of the SBOM with the published ones, using the algorithms both provide.
+fn compare(expected: &[Checksum], published: &[Checksum]) -> (ProvenanceStatus, Option<String>) {
+    let comparable: Vec<_> = expected
+        .iter()
+        .filter(|checksum| published.iter().any(|p| p.algorithm == checksum.algorithm))
+        .collect();
+
+    match comparable.first() {
+        None => (ProvenanceStatus::Unverified, None),
+        Some(_) if comparable.iter().any(|checksum| published.contains(checksum)) => (ProvenanceStatus::Verified, None),
+        Some(checksum) => (
+            ProvenanceStatus::Mismatch,
+            Some(i18n::message(
+                "provenance.checksum-mismatch",
+                &[
+                    ("algorithm", &format!("{:?}", checksum.algorithm)),
+                    ("value", &checksum.value),
+                ],
+            )),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sampling() {
+        assert_eq!(sample(vec![1, 2, 3], 5), vec![1, 2, 3]);
+        assert_eq!(sample((0..10).collect(), 3), vec![0, 3, 6]);
+        assert_eq!(sample((0..10).collect(), 0), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn checksums() {
+        let sha1 = Checksum::new(Algorithm::Sha1, "ABCD");
+        let sha256 = Checksum::new(Algorithm::Sha256, "1234");
+
+        let (status, _) = compare(&[sha1.clone()], &[Checksum::new(Algorithm::Sha1, "abcd")]);
+        assert_eq!(status, ProvenanceStatus::Verified);
+
+        let (status, details) = compare(&[sha1.clone()], &[Checksum::new(Algorithm::Sha1, "0000")]);
+        assert_eq!(status, ProvenanceStatus::Mismatch);
+        assert!(details.is_some());
+
+        let (status, _) = compare(&[sha256], &[sha1]);
+        assert_eq!(status, ProvenanceStatus::Unverified);
+    }
+
+    #[test]
+    fn cyclonedx_components() {
+        let sbom = SBOM::parse(include_bytes!("../../../../../bombastic/testdata/my-sbom.json")).unwrap();
+        let components = components(&sbom);
+
+        assert!(!components.is_empty());
+        assert!(components.iter().all(|c| c.registry == Registry::Maven));
+        let netty = components
+            .iter()
+            .find(|c| c.purl.name() == "netty-common")
+            .expect("netty component");
+        assert!(netty.checksums.iter().any(|c| c.algorithm == Algorithm::Sha1));
+    }
+}
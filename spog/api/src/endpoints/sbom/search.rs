@@ -1,5 +1,5 @@
 use crate::app_state::AppState;
-
+use crate::cache::{AccessScope, DerivedCache};
 use crate::error::Error;
 use crate::search::QueryParams;
 use crate::service::guac::GuacService;
@@ -12,13 +12,13 @@ use bombastic_model::search::SearchHit;
 use cvss::Severity;
 use futures::future::join_all;
 use spog_model::prelude::{Last10SbomVulnerabilitySummary, Last10SbomVulnerabilitySummaryVulnerabilities};
-use spog_model::search::SbomSummary;
+use spog_model::search::{SbomReference, SbomSummary};
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering::Relaxed;
 use std::sync::Arc;
 use tracing::instrument;
 use trustification_api::search::{SearchOptions, SearchResult};
-use trustification_auth::client::TokenProvider;
+use trustification_auth::{authenticator::user::UserInformation, authorizer::Authorizer, client::TokenProvider};
 
 #[utoipa::path(
     get,
@@ -31,12 +31,15 @@ use trustification_auth::client::TokenProvider;
         SearchOptions,
     )
 )]
-#[instrument(skip(state, access_token), err)]
+#[instrument(skip(state, cache, access_token, authorizer, user), err)]
 pub async fn search(
     state: web::Data<AppState>,
+    cache: web::Data<DerivedCache>,
     params: web::Query<search::QueryParams>,
     options: web::Query<SearchOptions>,
     access_token: Option<BearerAuth>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
 ) -> actix_web::Result<HttpResponse> {
     let params = params.into_inner();
     log::trace!("Querying SBOM using {}", params.q);
@@ -67,6 +70,16 @@ pub async fn search(
             href: format!("/api/v1/sbom?id={}", item.id),
             description: item.description,
             dependencies: item.dependencies,
+            ecosystems: item.ecosystems,
+            external_references: item
+                .external_references
+                .into_iter()
+                .map(|reference| SbomReference {
+                    category: reference.category,
+                    url: reference.url,
+                })
+                .collect(),
+            labels: item.labels,
             vulnerabilities: vec![],
             advisories: None,
             created: item.created,
@@ -80,19 +93,32 @@ pub async fn search(
     };
 
     // TODO: Use guac to lookup advisories for each sbom!
-    search_advisories(state, &mut result.result, &access_token).await;
+    let scope = AccessScope::of(&authorizer, &user);
+    search_advisories(state, &cache, scope, &mut result.result, &access_token).await;
     Ok(HttpResponse::Ok().json(result))
 }
 
 #[instrument(skip_all)]
-async fn search_advisories(state: web::Data<AppState>, sboms: &mut Vec<SbomSummary>, provider: &dyn TokenProvider) {
+async fn search_advisories(
+    state: web::Data<AppState>,
+    cache: &DerivedCache,
+    scope: AccessScope,
+    sboms: &mut Vec<SbomSummary>,
+    provider: &dyn TokenProvider,
+) {
     for sbom in sboms {
+        let key = (sbom.id.clone(), scope);
+        if let Some(advisories) = cache.sbom_advisories.get(&key) {
+            sbom.advisories = Some(advisories);
+            continue;
+        }
         if let Some(q) = sbom.advisories_query() {
+            // only the total is used, which counts all matches regardless of the limit
             if let Ok(result) = state
                 .search_vex(
                     &q,
                     0,
-                    100000,
+                    1,
                     SearchOptions {
                         explain: false,
                         metadata: false,
@@ -103,6 +129,7 @@ async fn search_advisories(state: web::Data<AppState>, sboms: &mut Vec<SbomSumma
                 .await
             {
                 sbom.advisories = Some(result.total as u64);
+                cache.sbom_advisories.insert(key, result.total as u64);
             }
         }
     }
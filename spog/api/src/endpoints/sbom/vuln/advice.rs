@@ -0,0 +1,280 @@
+//! Update advice for the vulnerable packages of an SBOM.
+//!
+//! The affected version ranges of the vulnerabilities are taken from v11y, the published versions of the packages
+//! from their registries. The recommended version of a package is the nearest version above the current one, which
+//! isn't affected by any of the vulnerabilities of the report affecting that package.
+
+use crate::service::{registry::RegistryService, v11y::V11yService};
+use futures::{stream, StreamExt};
+use packageurl::PackageUrl;
+use spog_model::{provenance::Registry, vuln::SbomReportVulnerability};
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+use tracing::instrument;
+use v11y_model::{Affected, Range, Version};
+
+/// Add the recommended versions to the affected packages of the vulnerabilities.
+///
+/// Advice is best effort: failing to look up a vulnerability or the versions of a package only leaves out the
+/// recommendations depending on it.
+#[instrument(skip_all)]
+pub async fn add_advice(v11y: &V11yService, registry: &RegistryService, details: &mut [SbomReportVulnerability]) {
+    let ids = details.iter().map(|vuln| vuln.id.clone()).collect::<BTreeSet<_>>();
+    let affected = stream::iter(ids)
+        .map(|id| async move {
+            match v11y.fetch_by_alias(&id).await {
+                Ok(vulns) => vulns.into_iter().flat_map(|vuln| vuln.affected).collect(),
+                Err(err) => {
+                    log::warn!("Unable to fetch the affected ranges of {id}: {err}");
+                    vec![]
+                }
+            }
+        })
+        .buffer_unordered(registry.concurrency())
+        .collect::<Vec<Vec<Affected>>>()
+        .await
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    if affected.is_empty() {
+        return;
+    }
+
+    let purls = details
+        .iter()
+        .flat_map(|vuln| vuln.affected_packages.keys().cloned())
+        .collect::<BTreeSet<_>>();
+    let affected = &affected;
+    let recommended = stream::iter(purls)
+        .map(|purl| async move {
+            let version = recommend_for(registry, affected, &purl).await;
+            (purl, version)
+        })
+        .buffer_unordered(registry.concurrency())
+        .filter_map(|(purl, version)| async move { version.map(|version| (purl, version)) })
+        .collect::<HashMap<_, _>>()
+        .await;
+
+    for vuln in details {
+        vuln.recommended_version = vuln
+            .affected_packages
+            .keys()
+            .filter_map(|purl| Some((purl.clone(), recommended.get(purl)?.clone())))
+            .collect();
+    }
+}
+
+/// The recommended version of a package, if its registry is supported and there's range data for it.
+async fn recommend_for(registry: &RegistryService, affected: &[Affected], purl: &str) -> Option<String> {
+    let purl = PackageUrl::from_str(purl).ok()?;
+    let current = purl.version()?;
+    let kind = Registry::from_purl_type(purl.ty())?;
+
+    let ranges = affected
+        .iter()
+        .filter(|affected| matches(&affected.package, &purl))
+        .flat_map(|affected| affected.ranges.iter().cloned())
+        .collect::<Vec<_>>();
+    if ranges.is_empty() {
+        return None;
+    }
+
+    match registry.versions(kind, &purl).await {
+        Ok(versions) => recommend(current, &versions?, &ranges),
+        Err(err) => {
+            log::warn!("Unable to fetch the versions of {purl}: {err}");
+            None
+        }
+    }
+}
+
+/// Whether the package of an affected entry, a Package URL without a version or a plain name, is the package of
+/// the Package URL.
+fn matches(package: &str, purl: &PackageUrl) -> bool {
+    match PackageUrl::from_str(package) {
+        Ok(package) => {
+            package.ty() == purl.ty() && package.namespace() == purl.namespace() && package.name() == purl.name()
+        }
+        Err(_) => match purl.namespace() {
+            Some(namespace) => {
+                package == purl.name()
+                    || package == format!("{namespace}/{}", purl.name())
+                    || package == format!("{namespace}:{}", purl.name())
+            }
+            None => package == purl.name(),
+        },
+    }
+}
+
+/// The nearest version above the current one, which isn't a pre-release and isn't affected by any of the ranges.
+fn recommend(current: &str, versions: &[String], ranges: &[Range]) -> Option<String> {
+    versions
+        .iter()
+        .filter(|version| compare(version, current) == Ordering::Greater)
+        .filter(|version| !is_prerelease(version))
+        .filter(|version| !ranges.iter().any(|range| affects(range, version)))
+        .min_by(|a, b| compare(a, b))
+        .cloned()
+}
+
+/// Whether the version is in the range. A range without bounds affects all versions.
+fn affects(range: &Range, version: &str) -> bool {
+    let lower = match &range.lower {
+        Some(Version::Inclusive(lower)) => compare(version, lower) != Ordering::Less,
+        Some(Version::Exclusive(lower)) => compare(version, lower) == Ordering::Greater,
+        None => true,
+    };
+    let upper = match &range.upper {
+        Some(Version::Inclusive(upper)) => compare(version, upper) != Ordering::Greater,
+        Some(Version::Exclusive(upper)) => compare(version, upper) == Ordering::Less,
+        None => true,
+    };
+    lower && upper
+}
+
+/// Qualifiers marking pre-releases, in the syntaxes of the supported registries.
+const PRERELEASES: &[&str] = &[
+    "alpha",
+    "beta",
+    "rc",
+    "cr",
+    "pre",
+    "dev",
+    "snapshot",
+    "milestone",
+    "m",
+    "a",
+    "b",
+];
+
+fn is_prerelease(version: &str) -> bool {
+    segments(version).any(|segment| match segment {
+        Segment::Text(text) => PRERELEASES.contains(&text.to_lowercase().as_str()),
+        Segment::Number(_) => false,
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Segment<'a> {
+    Number(u64),
+    Text(&'a str),
+}
+
+/// Split a version into its numeric and textual segments, like `1.0.0-rc1` into `1`, `0`, `0`, `rc` and `1`.
+fn segments(version: &str) -> impl Iterator<Item = Segment<'_>> {
+    let version = version.trim_start_matches(['v', 'V']);
+    // build metadata doesn't take part in the ordering
+    let version = version.split_once('+').map_or(version, |(version, _)| version);
+
+    let mut segments = vec![];
+    for part in version.split(['.', '-', '_']).filter(|part| !part.is_empty()) {
+        let mut rest = part;
+        while let Some(first) = rest.chars().next() {
+            let numeric = first.is_ascii_digit();
+            let end = rest.find(|c: char| c.is_ascii_digit() != numeric).unwrap_or(rest.len());
+            let (segment, tail) = rest.split_at(end);
+            segments.push(match segment.parse() {
+                Ok(number) if numeric => Segment::Number(number),
+                _ => Segment::Text(segment),
+            });
+            rest = tail;
+        }
+    }
+    segments.into_iter()
+}
+
+/// Compare versions of any of the supported registries, numeric segments by their value.
+///
+/// A version followed by a textual segment, like `1.0-rc1` or `1.0.Final`, sorts before the version without it. As
+/// Maven versions like `1.0.Final` are no pre-release, they are only ordered correctly among each other.
+fn compare(a: &str, b: &str) -> Ordering {
+    let mut a = segments(a);
+    let mut b = segments(b);
+    loop {
+        let ordering = match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (Some(Segment::Text(_)), None) => Ordering::Less,
+            (None, Some(Segment::Text(_))) => Ordering::Greater,
+            (Some(Segment::Number(_)), None) => Ordering::Greater,
+            (None, Some(Segment::Number(_))) => Ordering::Less,
+            (Some(Segment::Number(a)), Some(Segment::Number(b))) => a.cmp(&b),
+            (Some(Segment::Number(_)), Some(Segment::Text(_))) => Ordering::Greater,
+            (Some(Segment::Text(_)), Some(Segment::Number(_))) => Ordering::Less,
+            (Some(Segment::Text(a)), Some(Segment::Text(b))) => a.to_lowercase().cmp(&b.to_lowercase()),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(lower: Option<Version>, upper: Option<Version>) -> Range {
+        Range { lower, upper }
+    }
+
+    #[test]
+    fn ordering() {
+        for (a, b) in [
+            ("1.0.0", "1.0.1"),
+            ("1.2", "1.10"),
+            ("1.0.0-rc1", "1.0.0"),
+            ("1.0.0-alpha", "1.0.0-beta"),
+            ("1.0", "1.0.1"),
+            ("v1.9", "v2.0"),
+            ("2.0.0a1", "2.0.0"),
+            ("4.1.100.Final", "4.1.101.Final"),
+        ] {
+            assert_eq!(compare(a, b), Ordering::Less, "{a} < {b}");
+            assert_eq!(compare(b, a), Ordering::Greater, "{b} > {a}");
+        }
+        assert_eq!(compare("1.0.0+build1", "1.0.0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn prereleases() {
+        assert!(is_prerelease("1.0.0-rc.1"));
+        assert!(is_prerelease("2.0.0b2"));
+        assert!(is_prerelease("3.0.0-SNAPSHOT"));
+        assert!(!is_prerelease("4.1.101.Final"));
+        assert!(!is_prerelease("1.2.3"));
+    }
+
+    #[test]
+    fn recommendations() {
+        let versions = ["1.0.0", "1.0.1", "1.1.0-rc1", "1.1.0", "1.2.0", "2.0.0"].map(String::from);
+        let ranges = [
+            range(
+                Some(Version::Inclusive("1.0.0".into())),
+                Some(Version::Exclusive("1.1.0".into())),
+            ),
+            range(
+                Some(Version::Inclusive("1.1.0".into())),
+                Some(Version::Inclusive("1.1.0".into())),
+            ),
+        ];
+        assert_eq!(recommend("1.0.0", &versions, &ranges).as_deref(), Some("1.2.0"));
+        assert_eq!(recommend("1.2.0", &versions, &ranges).as_deref(), Some("2.0.0"));
+        assert_eq!(recommend("2.0.0", &versions, &ranges), None);
+
+        // fixed in no version
+        let ranges = [range(Some(Version::Inclusive("1.0.0".into())), None)];
+        assert_eq!(recommend("1.0.0", &versions, &ranges), None);
+    }
+
+    #[test]
+    fn packages() {
+        let purl = PackageUrl::from_str("pkg:maven/io.vertx/vertx-core@4.4.4").unwrap();
+        assert!(matches("pkg:maven/io.vertx/vertx-core", &purl));
+        assert!(matches("io.vertx:vertx-core", &purl));
+        assert!(!matches("pkg:npm/vertx-core", &purl));
+
+        let purl = PackageUrl::from_str("pkg:npm/left-pad@1.3.0").unwrap();
+        assert!(matches("left-pad", &purl));
+        assert!(!matches("right-pad", &purl));
+    }
+}
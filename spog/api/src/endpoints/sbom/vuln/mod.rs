@@ -1,12 +1,14 @@
+mod advice;
 mod analyze;
 mod backtrace;
 mod vex;
 
 use crate::app_state::AppState;
-use crate::endpoints::sbom::vuln::analyze::AnalyzeOutcome;
+use crate::cache::{AccessScope, DerivedCache, ReportKey};
+use crate::endpoints::{ownership::add_owners, sbom::vuln::analyze::AnalyzeOutcome, score::adjust_score};
 use crate::error::Error;
 use crate::search::QueryParams;
-use crate::service::{guac::GuacService, v11y::V11yService};
+use crate::service::{guac::GuacService, registry::RegistryService, v11y::V11yService};
 use actix_web::cookie::time;
 use actix_web::{web, HttpResponse};
 use actix_web_httpauth::extractors::bearer::BearerAuth;
@@ -24,11 +26,12 @@ use spog_model::{
 };
 use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
+use std::sync::Arc;
 use time::macros::format_description;
 use time::OffsetDateTime;
 use tracing::{info_span, instrument, Instrument};
 use trustification_api::search::SearchResult;
-use trustification_auth::client::TokenProvider;
+use trustification_auth::{authenticator::user::UserInformation, authorizer::Authorizer, client::TokenProvider};
 use trustification_common::error::ErrorInformation;
 use utoipa::IntoParams;
 use v11y_model::search::SearchDocument;
@@ -56,16 +59,42 @@ pub struct GetParams {
     ),
     params(GetParams)
 )]
-#[instrument(skip(state, v11y, guac, access_token), err)]
+#[instrument(skip(state, v11y, guac, registry, cache, access_token, authorizer, user), err)]
+#[allow(clippy::too_many_arguments)]
 pub async fn get_vulnerabilities(
     state: web::Data<AppState>,
     v11y: web::Data<V11yService>,
     guac: web::Data<GuacService>,
+    registry: web::Data<RegistryService>,
+    cache: web::Data<DerivedCache>,
     params: web::Query<GetParams>,
     access_token: Option<BearerAuth>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
 ) -> actix_web::Result<HttpResponse> {
-    if let Some(result) = process_get_vulnerabilities(&state, &v11y, &guac, &access_token, &params).await? {
-        Ok(HttpResponse::Ok().json(result))
+    // the report is built using the credentials of the user, so it is only shared with users seeing the same documents
+    let key = ReportKey {
+        id: params.id.clone(),
+        offset: params.offset,
+        limit: params.limit,
+        retrieve_remediation: params.retrieve_remediation,
+        scope: AccessScope::of(&authorizer, &user),
+    };
+    // overrides are applied to cached reports too, so changing them doesn't require invalidating the cache
+    if let Some(result) = cache.reports.get(&key) {
+        log::debug!("Using cached report for SBOM: {}", params.id);
+        let result = adjust_scores(&state, &v11y, &params.id, result).await?;
+        let result = add_owners(&state, result).await?;
+        return Ok(HttpResponse::Ok().json(&*result));
+    }
+
+    if let Some(result) = process_get_vulnerabilities(&state, &v11y, &guac, &registry, &access_token, &params).await? {
+        let result = Arc::new(result);
+        cache.reports.insert(key, result.clone());
+
+        let result = adjust_scores(&state, &v11y, &params.id, result).await?;
+        let result = add_owners(&state, result).await?;
+        Ok(HttpResponse::Ok().json(&*result))
     } else {
         Ok(HttpResponse::NotFound().json(ErrorInformation {
             error: "NoPackageInformation".to_string(),
@@ -75,11 +104,32 @@ pub async fn get_vulnerabilities(
     }
 }
 
-#[instrument(skip(state, guac, v11y, access_token), err)]
+/// Adjust the scores of the vulnerabilities of a report, if there are overrides for them.
+#[instrument(skip(state, v11y, report), err)]
+async fn adjust_scores(
+    state: &AppState,
+    v11y: &V11yService,
+    id: &str,
+    report: Arc<SbomReport>,
+) -> actix_web::Result<Arc<SbomReport>> {
+    let overrides = state.db_storage.select_score_overrides(None).await?;
+    if overrides.is_empty() {
+        return Ok(report);
+    }
+
+    let mut report = (*report).clone();
+    for vuln in &mut report.details {
+        vuln.adjusted = adjust_score(v11y, &overrides, &vuln.id, Some(id)).await?;
+    }
+    Ok(Arc::new(report))
+}
+
+#[instrument(skip(state, guac, v11y, registry, access_token), err)]
 pub async fn process_get_vulnerabilities(
     state: &AppState,
     v11y: &V11yService,
     guac: &GuacService,
+    registry: &RegistryService,
     access_token: &dyn TokenProvider,
     params: &GetParams,
 ) -> Result<Option<SbomReport>, Error> {
@@ -169,7 +219,7 @@ pub async fn process_get_vulnerabilities(
 
     // fetch CVE details
 
-    let details = iter(analyze)
+    let mut details = iter(analyze)
         .map(|(id, affected_packages)| async move {
             let q = format!("id:\"{}\"", id.clone());
             log::debug!("querying for {}", q);
@@ -203,6 +253,8 @@ pub async fn process_get_vulnerabilities(
                                 published: cve.document.date_published,
                                 updated: cve.document.date_updated,
                                 affected_packages,
+                                recommended_version: BTreeMap::new(),
+                                adjusted: None,
                             }));
                             log::debug!("result is {:?}", result);
                             result
@@ -217,6 +269,12 @@ pub async fn process_get_vulnerabilities(
         .try_collect::<Vec<_>>()
         .await?;
 
+    // advise on updates, along with the remediations
+
+    if retrieve_remediation == Some(true) {
+        advice::add_advice(v11y, registry, &mut details).await;
+    }
+
     // summarize scores
 
     let summary = summarize_vulns(&details)
@@ -241,6 +299,7 @@ pub async fn process_get_vulnerabilities(
         summary,
         details,
         backtraces,
+        owners: BTreeMap::new(),
     }))
 }
 
@@ -307,6 +366,29 @@ pub(crate) fn get_score(cve: &Cve) -> Option<f32> {
     v3_1.or(v3_0).or(v2_0)
 }
 
+/// get the CVSS v3 vector, preferring CVSS v3.1
+pub(crate) fn get_vector(cve: &Cve) -> Option<String> {
+    let p = match cve {
+        Cve::Published(p) => p,
+        Cve::Rejected(_) => return None,
+    };
+
+    let vector = |value: &Value| value["vectorString"].as_str().map(ToString::to_string);
+
+    let mut v3_1 = None;
+    let mut v3_0 = None;
+
+    for m in &p.containers.cna.metrics {
+        if let Some(m) = m.cvss_v3_1.as_ref().and_then(vector) {
+            v3_1 = Some(m);
+        } else if let Some(m) = m.cvss_v3_0.as_ref().and_then(vector) {
+            v3_0 = Some(m);
+        }
+    }
+
+    v3_1.or(v3_0)
+}
+
 /// Collect a summary of count, based on CVSS v3 severities
 fn summarize_vulns<'a>(
     vulnerabilities: impl IntoIterator<Item = &'a SbomReportVulnerability>,
@@ -326,7 +408,7 @@ fn summarize_vulns<'a>(
 
 /// Extract all purls which are referenced by "document describes"
 #[instrument(skip_all)]
-fn find_main(spdx: &SPDX) -> Vec<&PackageInformation> {
+pub(crate) fn find_main(spdx: &SPDX) -> Vec<&PackageInformation> {
     let mut main = vec![];
     for desc in &spdx.document_creation_information.document_describes {
         for pi in &spdx.package_information {
@@ -347,8 +429,7 @@ fn find_main(spdx: &SPDX) -> Vec<&PackageInformation> {
 }
 
 /// map package information to it's purls
-#[allow(unused)]
-fn map_purls(pi: &PackageInformation) -> impl IntoIterator<Item = String> + '_ {
+pub(crate) fn map_purls(pi: &PackageInformation) -> impl IntoIterator<Item = String> + '_ {
     pi.external_reference.iter().filter_map(|er| {
         if er.reference_type == "purl" {
             Some(er.reference_locator.clone())
@@ -0,0 +1,159 @@
+//! Overrides of CVSS scores, adjusting base scores with temporal and environmental metrics of this deployment.
+
+use crate::app_state::{AppState, ResponseError};
+use crate::endpoints::sbom::vuln::get_vector;
+use crate::error::Error;
+use crate::service::v11y::V11yService;
+use actix_web::{web, web::ServiceConfig, HttpResponse};
+use cve::Cve;
+use spog_model::score::{validate_modifiers, AdjustedScore, ScoreOverride};
+use std::sync::Arc;
+use tracing::instrument;
+use trustification_auth::{
+    authenticator::{user::UserInformation, Authenticator},
+    authorizer::Authorizer,
+    Permission,
+};
+use trustification_common::error::ErrorInformation;
+use trustification_infrastructure::new_auth;
+use utoipa::IntoParams;
+
+pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config.service(
+            web::resource("/api/v1/score/overrides")
+                .wrap(new_auth!(auth))
+                .route(web::get().to(get_overrides))
+                .route(web::put().to(update_override))
+                .route(web::delete().to(delete_override)),
+        );
+    }
+}
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct OverrideParams {
+    /// The CVE of the overrides
+    pub cve: Option<String>,
+    /// The product (SBOM ID) of the override, selecting the override for all products if missing
+    pub product: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/score/overrides",
+    responses(
+        (status = OK, description = "Overrides of CVSS scores", body = Vec<ScoreOverride>),
+    ),
+    params(OverrideParams)
+)]
+#[instrument(skip(state), err)]
+pub async fn get_overrides(
+    state: web::Data<AppState>,
+    web::Query(params): web::Query<OverrideParams>,
+) -> actix_web::Result<HttpResponse> {
+    let mut overrides = state.db_storage.select_score_overrides(params.cve.as_deref()).await?;
+    if let Some(product) = &params.product {
+        overrides.retain(|o| o.product.as_ref() == Some(product));
+    }
+    Ok(HttpResponse::Ok().json(overrides))
+}
+
+/// Create or replace the override of a CVE and product.
+///
+/// Overrides are managed by those allowed to update advisories.
+#[utoipa::path(
+    put,
+    path = "/api/v1/score/overrides",
+    request_body = ScoreOverride,
+    responses(
+        (status = OK, description = "Override was stored", body = ScoreOverride),
+        (status = BAD_REQUEST, description = "Invalid temporal or environmental metrics"),
+    ),
+)]
+#[instrument(skip(state, authorizer), err)]
+pub async fn update_override(
+    state: web::Data<AppState>,
+    web::Json(score_override): web::Json<ScoreOverride>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::UpdateVex)?;
+
+    if let Err(err) = validate_modifiers(&score_override.modifiers) {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "InvalidModifiers".to_string(),
+            message: "Invalid temporal or environmental metrics".to_string(),
+            details: err.to_string(),
+        }));
+    }
+
+    state.db_storage.update_score_override(score_override.clone()).await?;
+    Ok(HttpResponse::Ok().json(score_override))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/score/overrides",
+    responses(
+        (status = NO_CONTENT, description = "Override was deleted"),
+        (status = NOT_FOUND, description = "Override was not found"),
+    ),
+    params(OverrideParams)
+)]
+#[instrument(skip(state, authorizer), err)]
+pub async fn delete_override(
+    state: web::Data<AppState>,
+    web::Query(params): web::Query<OverrideParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::UpdateVex)?;
+
+    let Some(cve) = &params.cve else {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "MissingCve".to_string(),
+            message: "The CVE of the override is required".to_string(),
+            details: String::new(),
+        }));
+    };
+
+    match state
+        .db_storage
+        .delete_score_override(cve, params.product.as_deref())
+        .await?
+    {
+        true => Ok(HttpResponse::NoContent().finish()),
+        false => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+/// Adjust the score of a CVE, if there is an override for it.
+///
+/// The base vector of the CVE is only retrieved if there is an override.
+#[instrument(skip(v11y, overrides), err)]
+pub(crate) async fn adjust_score(
+    v11y: &V11yService,
+    overrides: &[ScoreOverride],
+    cve: &str,
+    product: Option<&str>,
+) -> Result<Option<AdjustedScore>, Error> {
+    let Some(score_override) = ScoreOverride::select(overrides, cve, product) else {
+        return Ok(None);
+    };
+
+    let Some(response) = v11y.fetch_cve(cve).await?.or_status_error_opt().await? else {
+        return Ok(None);
+    };
+    let Some(vector) = get_vector(&response.json::<Cve>().await?) else {
+        log::debug!("No CVSS v3 vector of {cve}, unable to adjust its score");
+        return Ok(None);
+    };
+
+    match score_override.adjust(&vector) {
+        Ok(adjusted) => Ok(Some(adjusted)),
+        Err(err) => {
+            log::warn!("Unable to adjust the score of {cve} ({vector}): {err}");
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Subscriptions to the changes of SBOMs, and the queues of the matching changes.
+
+use crate::app_state::AppState;
+use actix_web::{web, web::ServiceConfig, HttpResponse};
+use spog_model::subscription::{Subscription, SubscriptionEvents};
+use std::sync::Arc;
+use tracing::instrument;
+use trustification_auth::{
+    authenticator::{user::UserInformation, Authenticator},
+    authorizer::Authorizer,
+    Permission,
+};
+use trustification_common::error::ErrorInformation;
+use trustification_infrastructure::new_auth;
+use utoipa::IntoParams;
+
+/// Maximum number of events returned by a page of the queue of a subscription.
+const MAX_EVENTS: usize = 1000;
+
+pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config.service(
+            web::resource("/api/v1/subscriptions")
+                .wrap(new_auth!(auth.clone()))
+                .route(web::get().to(get_subscriptions))
+                .route(web::put().to(update_subscription))
+                .route(web::delete().to(delete_subscription)),
+        );
+        config.service(
+            web::resource("/api/v1/subscriptions/events")
+                .wrap(new_auth!(auth))
+                .route(web::get().to(get_events)),
+        );
+    }
+}
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct SubscriptionParams {
+    /// The ID of the subscription
+    pub id: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct EventsParams {
+    /// The ID of the subscription
+    pub id: String,
+    /// Cursor of the last page, starts from the oldest queued event if omitted
+    #[serde(default)]
+    pub since: i64,
+    /// Maximum number of events to return, defaults to 100, at most 1000
+    #[serde(default = "default_events_limit")]
+    pub limit: usize,
+}
+
+const fn default_events_limit() -> usize {
+    100
+}
+
+/// Get the subscriptions.
+///
+/// As the URLs of their webhooks may contain secrets, they are only listed to those allowed to manage them.
+#[utoipa::path(
+    get,
+    path = "/api/v1/subscriptions",
+    responses(
+        (status = OK, description = "Subscriptions to the changes of SBOMs", body = Vec<Subscription>),
+    ),
+    params(SubscriptionParams)
+)]
+#[instrument(skip(state, authorizer), err)]
+pub async fn get_subscriptions(
+    state: web::Data<AppState>,
+    web::Query(params): web::Query<SubscriptionParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::UpdateSbom)?;
+
+    Ok(HttpResponse::Ok().json(state.db_storage.select_subscriptions(params.id.as_deref()).await?))
+}
+
+/// Create or replace a subscription.
+///
+/// Changing the filters of a subscription keeps the events queued already.
+#[utoipa::path(
+    put,
+    path = "/api/v1/subscriptions",
+    request_body = Subscription,
+    responses(
+        (status = OK, description = "Subscription was stored", body = Subscription),
+        (status = BAD_REQUEST, description = "Invalid subscription"),
+    ),
+)]
+#[instrument(skip(state, authorizer, subscription), fields(id = %subscription.id), err)]
+pub async fn update_subscription(
+    state: web::Data<AppState>,
+    web::Json(subscription): web::Json<Subscription>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::UpdateSbom)?;
+
+    if subscription.id.is_empty() || subscription.purl.as_ref().is_some_and(|purl| !purl.starts_with("pkg:")) {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "InvalidSubscription".to_string(),
+            message: "The subscription needs an ID, and its pattern must be a Package URL".to_string(),
+            details: format!("{}: {}", subscription.id, subscription.purl.unwrap_or_default()),
+        }));
+    }
+    if let Some(Err(err)) = subscription.webhook.as_deref().map(url::Url::parse) {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "InvalidUrl".to_string(),
+            message: "The URL of the webhook is invalid".to_string(),
+            details: err.to_string(),
+        }));
+    }
+
+    state.db_storage.update_subscription(subscription.clone()).await?;
+    Ok(HttpResponse::Ok().json(subscription))
+}
+
+/// Delete a subscription, along with its queue.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/subscriptions",
+    responses(
+        (status = NO_CONTENT, description = "Subscription was deleted"),
+        (status = BAD_REQUEST, description = "ID is missing"),
+        (status = NOT_FOUND, description = "Subscription was not found"),
+    ),
+    params(SubscriptionParams)
+)]
+#[instrument(skip(state, authorizer), err)]
+pub async fn delete_subscription(
+    state: web::Data<AppState>,
+    web::Query(params): web::Query<SubscriptionParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::UpdateSbom)?;
+
+    let Some(id) = &params.id else {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "MissingId".to_string(),
+            message: "The ID of the subscription is required".to_string(),
+            details: String::new(),
+        }));
+    };
+
+    match state.db_storage.delete_subscription(id).await? {
+        true => Ok(HttpResponse::NoContent().finish()),
+        false => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+/// Get the changes queued for a subscription.
+///
+/// Consumers start without a cursor, and request the next page using the `next` cursor of the page, until there are no
+/// `more` events. Only the latest events of each subscription are kept, so consumers falling behind may miss events.
+#[utoipa::path(
+    get,
+    path = "/api/v1/subscriptions/events",
+    responses(
+        (status = OK, description = "Page of the queue of the subscription", body = SubscriptionEvents),
+        (status = NOT_FOUND, description = "Subscription was not found"),
+    ),
+    params(EventsParams)
+)]
+#[instrument(skip(state, authorizer), err)]
+pub async fn get_events(
+    state: web::Data<AppState>,
+    web::Query(params): web::Query<EventsParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    if state
+        .db_storage
+        .select_subscriptions(Some(&params.id))
+        .await?
+        .is_empty()
+    {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    let limit = params.limit.clamp(1, MAX_EVENTS);
+    // one more, to tell whether there are more events
+    let mut events = state
+        .db_storage
+        .select_subscription_events(&params.id, params.since, limit + 1)
+        .await?;
+    let more = events.len() > limit;
+    events.truncate(limit);
+    let next = events.last().map(|event| event.seq).unwrap_or(params.since);
+
+    Ok(HttpResponse::Ok().json(SubscriptionEvents { events, next, more }))
+}
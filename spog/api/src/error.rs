@@ -1,3 +1,4 @@
+use crate::i18n;
 use crate::service::{collectorist, guac, v11y};
 use actix_web::{http::header::ContentType, HttpResponse};
 use http::StatusCode;
@@ -41,47 +42,47 @@ impl actix_web::error::ResponseError for Error {
         match self {
             Self::Response(status, error) => res.json(ErrorInformation {
                 error: format!("{}", status),
-                message: "Error response from backend service".to_string(),
+                message: i18n::message("error.backend-response", &[]),
                 details: error.to_string(),
             }),
             Self::Request(error) => res.json(ErrorInformation {
                 error: format!("{}", self.status_code()),
-                message: "Error creating request to backend service".to_string(),
+                message: i18n::message("error.backend-request", &[]),
                 details: error.to_string(),
             }),
             Self::UrlParse(error) => res.json(ErrorInformation {
                 error: format!("{}", self.status_code()),
-                message: "Error constructing url to backend service".to_string(),
+                message: i18n::message("error.backend-url", &[]),
                 details: error.to_string(),
             }),
             Self::AuthClient(error) => res.json(ErrorInformation {
                 error: format!("{}", self.status_code()),
-                message: "Error creating authentication client".to_string(),
+                message: i18n::message("error.authentication-client", &[]),
                 details: error.to_string(),
             }),
             Self::Serde(error) => res.json(ErrorInformation {
                 error: "Serialization".to_string(),
-                message: "Serialization error".to_string(),
+                message: i18n::message("error.serialization", &[]),
                 details: error.to_string(),
             }),
             Self::Guac(error) => res.json(ErrorInformation {
                 error: "Guac".to_string(),
-                message: "Error contacting GUAC".to_string(),
+                message: i18n::message("error.guac", &[]),
                 details: error.to_string(),
             }),
             Self::Collectorist(error) => res.json(ErrorInformation {
                 error: "collectorist".to_string(),
-                message: "Error contacting collectorist".to_string(),
+                message: i18n::message("error.collectorist", &[]),
                 details: error.to_string(),
             }),
             Self::V11y(error) => res.json(ErrorInformation {
                 error: "v11y".to_string(),
-                message: "Error contacting v11y".to_string(),
+                message: i18n::message("error.v11y", &[]),
                 details: error.to_string(),
             }),
             Self::PackageUrl(error) => res.json(ErrorInformation {
                 error: "PackageUrl".to_string(),
-                message: "Invalid package URL syntax".to_string(),
+                message: i18n::message("error.package-url", &[]),
                 details: error.to_string(),
             }),
             Self::Generic(error) => res.json(ErrorInformation {
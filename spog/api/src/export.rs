@@ -0,0 +1,465 @@
+//! Asynchronous export jobs.
+//!
+//! Exports run in the background, writing their result to a file in the export directory, which can be downloaded
+//! once the job completed. Jobs are kept in memory, so they are bound to the instance they were submitted to and
+//! don't survive a restart. Finished jobs expire after the retention period, removing their result.
+
+use crate::app_state::AppState;
+use crate::i18n;
+use crate::utils::get_sanitize_filename;
+use actix_web::{body::BoxBody, http::header::ContentType, HttpResponse, ResponseError};
+use bytes::Bytes;
+use flate2::{write::GzEncoder, Compression};
+use futures::TryStreamExt;
+use http::StatusCode;
+use spog_model::prelude::{ExportJob, ExportKind, ExportRequest, ExportStatus, OwnershipRule};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::io::AsyncWriteExt;
+use trustification_api::search::SearchOptions;
+use trustification_common::error::ErrorInformation;
+use vexination_model::prelude::Tlp;
+
+/// Number of packages written to the result at once.
+const PAGE_SIZE: usize = 500;
+
+/// Interval of checking for expired jobs.
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Owner of jobs submitted without authentication, when authentication is disabled.
+const ANONYMOUS_OWNER: &str = "anonymous";
+
+#[derive(Clone, Debug, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Exports")]
+pub struct ExportConfig {
+    /// Directory to store the results of export jobs in. Defaults to `exports` in the database storage base.
+    #[arg(long = "export-path", env = "EXPORT_PATH")]
+    pub path: Option<PathBuf>,
+
+    /// How long finished export jobs and their results are kept
+    #[arg(long = "export-retention", env = "EXPORT_RETENTION", default_value = "24h")]
+    pub retention: humantime::Duration,
+
+    /// Maximum number of pending or running export jobs of a single user
+    #[arg(
+        long = "export-max-jobs-per-user",
+        env = "EXPORT_MAX_JOBS_PER_USER",
+        default_value_t = 2
+    )]
+    pub max_jobs_per_user: usize,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            retention: Duration::from_secs(24 * 60 * 60).into(),
+            max_jobs_per_user: 2,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("too many export jobs, at most {0} may be pending or running at the same time")]
+    TooManyJobs(usize),
+    #[error("export job not found")]
+    NotFound,
+    #[error("export job is not completed")]
+    NotCompleted,
+}
+
+impl ResponseError for ExportError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::TooManyJobs(_) => StatusCode::TOO_MANY_REQUESTS,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::NotCompleted => StatusCode::CONFLICT,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        HttpResponse::build(self.status_code())
+            .insert_header(ContentType::json())
+            .json(ErrorInformation {
+                error: format!("{}", self.status_code()),
+                message: match self {
+                    Self::TooManyJobs(max) => i18n::message("export.too-many-jobs", &[("max", max)]),
+                    Self::NotFound => i18n::message("export.not-found", &[]),
+                    Self::NotCompleted => i18n::message("export.not-completed", &[]),
+                },
+                details: self.to_string(),
+            })
+    }
+}
+
+struct Entry {
+    owner: String,
+    job: ExportJob,
+}
+
+/// The export jobs of this instance.
+pub struct Exports {
+    path: PathBuf,
+    retention: Duration,
+    max_jobs_per_user: usize,
+    jobs: Mutex<HashMap<String, Entry>>,
+}
+
+impl Exports {
+    /// Create the export directory, removing results left behind by a previous run.
+    pub async fn new(config: ExportConfig, base: &Path) -> std::io::Result<Self> {
+        let path = config.path.unwrap_or_else(|| base.join("exports"));
+        tokio::fs::create_dir_all(&path).await?;
+
+        // jobs don't survive a restart, so neither do their results
+        let mut entries = tokio::fs::read_dir(&path).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_type().await?.is_file() {
+                tokio::fs::remove_file(entry.path()).await?;
+            }
+        }
+
+        log::info!("Storing export results in {}", path.display());
+
+        Ok(Self {
+            path,
+            retention: config.retention.into(),
+            max_jobs_per_user: config.max_jobs_per_user,
+            jobs: Default::default(),
+        })
+    }
+
+    /// The owner of jobs submitted by a user.
+    pub fn owner(id: Option<&str>) -> &str {
+        id.unwrap_or(ANONYMOUS_OWNER)
+    }
+
+    /// Register a new job, unless the owner reached the limit of active jobs.
+    pub fn submit(&self, owner: &str, request: ExportRequest) -> Result<ExportJob, ExportError> {
+        let mut jobs = self.jobs.lock().expect("export jobs lock poisoned");
+
+        let active = jobs
+            .values()
+            .filter(|entry| entry.owner == owner && entry.job.status.is_active())
+            .count();
+        if active >= self.max_jobs_per_user {
+            return Err(ExportError::TooManyJobs(self.max_jobs_per_user));
+        }
+
+        let job = ExportJob {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind: request.kind,
+            query: request.query,
+            status: ExportStatus::Pending,
+            processed: 0,
+            total: None,
+            created: OffsetDateTime::now_utc(),
+            expires: None,
+            size: None,
+            error: None,
+        };
+        jobs.insert(
+            job.id.clone(),
+            Entry {
+                owner: owner.to_string(),
+                job: job.clone(),
+            },
+        );
+
+        Ok(job)
+    }
+
+    /// Get a job, if it belongs to the owner.
+    pub fn get(&self, owner: &str, id: &str) -> Result<ExportJob, ExportError> {
+        self.jobs
+            .lock()
+            .expect("export jobs lock poisoned")
+            .get(id)
+            .filter(|entry| entry.owner == owner)
+            .map(|entry| entry.job.clone())
+            .ok_or(ExportError::NotFound)
+    }
+
+    /// All jobs of an owner, oldest first.
+    pub fn list(&self, owner: &str) -> Vec<ExportJob> {
+        let mut jobs: Vec<ExportJob> = self
+            .jobs
+            .lock()
+            .expect("export jobs lock poisoned")
+            .values()
+            .filter(|entry| entry.owner == owner)
+            .map(|entry| entry.job.clone())
+            .collect();
+        jobs.sort_by_key(|job| job.created);
+        jobs
+    }
+
+    /// The location of the result of a completed job of the owner.
+    pub fn result(&self, owner: &str, id: &str) -> Result<(ExportJob, PathBuf), ExportError> {
+        let job = self.get(owner, id)?;
+        match job.status {
+            ExportStatus::Completed => Ok((job, self.result_path(id))),
+            _ => Err(ExportError::NotCompleted),
+        }
+    }
+
+    fn result_path(&self, id: &str) -> PathBuf {
+        self.path.join(id)
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut ExportJob)) {
+        if let Some(entry) = self.jobs.lock().expect("export jobs lock poisoned").get_mut(id) {
+            f(&mut entry.job);
+        }
+    }
+
+    fn finish(&self, id: &str, result: anyhow::Result<u64>, now: OffsetDateTime) {
+        self.update(id, |job| {
+            match result {
+                Ok(size) => {
+                    job.status = ExportStatus::Completed;
+                    job.size = Some(size);
+                }
+                Err(err) => {
+                    log::warn!("Export job {id} failed: {err}");
+                    job.status = ExportStatus::Failed;
+                    job.error = Some(err.to_string());
+                }
+            }
+            job.expires = Some(now + self.retention);
+        });
+    }
+
+    /// Remove jobs which expired, returning their IDs.
+    fn expire(&self, now: OffsetDateTime) -> Vec<String> {
+        let mut jobs = self.jobs.lock().expect("export jobs lock poisoned");
+        let expired: Vec<String> = jobs
+            .values()
+            .filter(|entry| entry.job.expires.is_some_and(|expires| expires <= now))
+            .map(|entry| entry.job.id.clone())
+            .collect();
+        for id in &expired {
+            jobs.remove(id);
+        }
+        expired
+    }
+
+    /// Run a submitted job to completion, leaving out advisories with one of the hidden TLP labels.
+    pub async fn run(&self, state: &AppState, job: ExportJob, hidden: Vec<Tlp>) {
+        self.update(&job.id, |job| job.status = ExportStatus::Running);
+
+        let path = self.result_path(&job.id);
+        let result: anyhow::Result<u64> = async {
+            let mut file = tokio::fs::File::create(&path).await?;
+            match job.kind {
+                ExportKind::Packages => self.export_packages(state, &job, &mut file).await?,
+                ExportKind::Advisories => self.export_advisories(state, &job, &hidden, &mut file).await?,
+            }
+            file.flush().await?;
+            Ok(file.metadata().await?.len())
+        }
+        .await;
+
+        if result.is_err() {
+            // don't keep partial results around
+            let _ = tokio::fs::remove_file(&path).await;
+        }
+        self.finish(&job.id, result, OffsetDateTime::now_utc());
+    }
+
+    async fn export_packages(
+        &self,
+        state: &AppState,
+        job: &ExportJob,
+        file: &mut tokio::fs::File,
+    ) -> anyhow::Result<()> {
+        let rules = state.db_storage.select_ownership_rules().await?;
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(["name", "version", "purl", "sha256", "license", "supplier", "owner"])?;
+
+        // the total is only for the progress, the packages are streamed as they are read
+        let total = state
+            .search_package(&job.query, 0, 1, SearchOptions::default(), state.provider.as_ref())
+            .await?
+            .total;
+        self.update(&job.id, |job| job.total = Some(total as u64));
+
+        let packages = state
+            .export_packages(&job.query, state.provider.as_ref())
+            .await?
+            .try_chunks(PAGE_SIZE);
+        futures::pin_mut!(packages);
+        let mut processed = 0;
+        while let Some(chunk) = packages.try_next().await.map_err(|err| err.1)? {
+            for hit in &chunk {
+                let package = &hit.document;
+                writer.write_record([
+                    package.name.as_str(),
+                    &package.version,
+                    &package.purl,
+                    &package.sha256,
+                    &package.license,
+                    &package.supplier,
+                    OwnershipRule::owner(&rules, &package.purl).unwrap_or_default(),
+                ])?;
+            }
+            writer.flush()?;
+            file.write_all(&std::mem::take(writer.get_mut())).await?;
+
+            processed += chunk.len() as u64;
+            self.update(&job.id, |job| job.processed = processed);
+        }
+        Ok(())
+    }
+
+    async fn export_advisories(
+        &self,
+        state: &AppState,
+        job: &ExportJob,
+        hidden: &[Tlp],
+        file: &mut tokio::fs::File,
+    ) -> anyhow::Result<()> {
+        // collect the IDs first, so the total is known while fetching the documents
+        let mut ids = Vec::new();
+        let mut seen = HashSet::new();
+        let hits = state.export_vex(&job.query, state.provider.as_ref()).await?;
+        futures::pin_mut!(hits);
+        while let Some(hit) = hits.try_next().await? {
+            let tlp = hit.document.tlp.as_deref().map(Tlp::from_label);
+            if tlp.is_some_and(|tlp| hidden.contains(&tlp)) {
+                continue;
+            }
+            if seen.insert(hit.document.advisory_id.clone()) {
+                ids.push(hit.document.advisory_id);
+            }
+        }
+
+        let total = ids.len() as u64;
+        self.update(&job.id, |job| job.total = Some(total));
+
+        let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (n, id) in ids.into_iter().enumerate() {
+            let data: bytes::BytesMut = state.get_vex(&id, state.provider.as_ref()).await?.try_collect().await?;
+            append(&mut archive, &format!("{}.json", get_sanitize_filename(id)), &data)?;
+            file.write_all(&drain(&mut archive)).await?;
+
+            self.update(&job.id, |job| job.processed = n as u64 + 1);
+        }
+
+        let data = archive.into_inner()?.finish()?;
+        file.write_all(&data).await?;
+
+        Ok(())
+    }
+}
+
+type Archive = tar::Builder<GzEncoder<Vec<u8>>>;
+
+/// Take the compressed data written so far, leaving the archive writable.
+fn drain(archive: &mut Archive) -> Bytes {
+    Bytes::from(std::mem::take(archive.get_mut().get_mut()))
+}
+
+fn append(archive: &mut Archive, name: &str, data: &[u8]) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    archive.append_data(&mut header, name, data)
+}
+
+/// Periodically remove expired jobs and their results.
+pub async fn expire(exports: std::sync::Arc<Exports>) -> anyhow::Result<()> {
+    let mut interval = tokio::time::interval(EXPIRY_INTERVAL);
+    loop {
+        interval.tick().await;
+        for id in exports.expire(OffsetDateTime::now_utc()) {
+            log::debug!("Export job {id} expired");
+            if let Err(err) = tokio::fs::remove_file(exports.result_path(&id)).await {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!("Failed to remove result of export job {id}: {err}");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn exports(max_jobs_per_user: usize) -> Exports {
+        Exports {
+            path: PathBuf::from("exports"),
+            retention: Duration::from_secs(60),
+            max_jobs_per_user,
+            jobs: Default::default(),
+        }
+    }
+
+    fn request() -> ExportRequest {
+        ExportRequest {
+            kind: ExportKind::Packages,
+            query: String::new(),
+        }
+    }
+
+    #[test]
+    fn per_user_limit() {
+        let exports = exports(1);
+        let job = exports.submit("alice", request()).unwrap();
+        assert!(matches!(
+            exports.submit("alice", request()),
+            Err(ExportError::TooManyJobs(1))
+        ));
+        // other users have their own limit
+        assert!(exports.submit("bob", request()).is_ok());
+
+        // finished jobs don't count
+        exports.finish(&job.id, Ok(0), OffsetDateTime::now_utc());
+        assert!(exports.submit("alice", request()).is_ok());
+    }
+
+    #[test]
+    fn owner() {
+        let exports = exports(1);
+        let job = exports.submit("alice", request()).unwrap();
+        assert_eq!(exports.get("alice", &job.id).unwrap().status, ExportStatus::Pending);
+        assert!(matches!(exports.get("bob", &job.id), Err(ExportError::NotFound)));
+        assert!(exports.list("bob").is_empty());
+        assert!(matches!(
+            exports.result("alice", &job.id),
+            Err(ExportError::NotCompleted)
+        ));
+    }
+
+    #[test]
+    fn expiry() {
+        let exports = exports(2);
+        let now = OffsetDateTime::now_utc();
+        let completed = exports.submit("alice", request()).unwrap();
+        let running = exports.submit("alice", request()).unwrap();
+
+        exports.finish(&completed.id, Ok(42), now);
+        let (job, path) = exports.result("alice", &completed.id).unwrap();
+        assert_eq!(job.size, Some(42));
+        assert_eq!(job.expires, Some(now + Duration::from_secs(60)));
+        assert_eq!(path, PathBuf::from("exports").join(&completed.id));
+
+        assert!(exports.expire(now).is_empty());
+        assert_eq!(
+            exports.expire(now + Duration::from_secs(60)),
+            vec![completed.id.clone()]
+        );
+        assert!(matches!(
+            exports.get("alice", &completed.id),
+            Err(ExportError::NotFound)
+        ));
+        // unfinished jobs don't expire
+        assert!(exports.get("alice", &running.id).is_ok());
+    }
+}
@@ -0,0 +1,295 @@
+//! Localization of user-facing strings, like error messages and report details.
+//!
+//! Messages are looked up by key in the catalog of the language negotiated using the `Accept-Language` header of the
+//! request. English messages are built in, translations are loaded at startup from YAML files named after their
+//! language tag (like `de.yaml` or `pt-BR.yaml`), so they can be added without recompiling. Messages missing from a
+//! translation fall back to English.
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderValue, ACCEPT_LANGUAGE, CONTENT_LANGUAGE},
+};
+use anyhow::Context;
+use futures::future::{ready, LocalBoxFuture, Ready};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+/// The language of the built-in messages, used when no other language matches.
+const DEFAULT_LANGUAGE: &str = "en";
+
+const BUILTIN_MESSAGES: &str = include_str!("../messages/en.yaml");
+
+tokio::task_local! {
+    /// The catalog and language of the request being processed.
+    static CURRENT: Localized;
+}
+
+#[derive(Clone, Debug, Default, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Localization")]
+pub struct I18nConfig {
+    /// Directory of message catalogs translating user-facing strings, named after their language tag, like `de.yaml`
+    #[arg(long = "messages-path", env = "MESSAGES_PATH")]
+    pub messages_path: Option<PathBuf>,
+}
+
+/// Messages by language tag (in lowercase) and key.
+#[derive(Debug)]
+pub struct Catalog {
+    languages: HashMap<String, HashMap<String, String>>,
+}
+
+impl Catalog {
+    /// A catalog with the built-in English messages only.
+    pub fn builtin() -> Self {
+        let messages = serde_yaml::from_str(BUILTIN_MESSAGES).expect("built-in messages must be valid");
+        Self {
+            languages: HashMap::from([(DEFAULT_LANGUAGE.to_string(), messages)]),
+        }
+    }
+
+    /// Create the catalog, adding the translations of the messages directory, if configured.
+    pub fn new(config: I18nConfig) -> anyhow::Result<Self> {
+        let mut catalog = Self::builtin();
+        if let Some(path) = &config.messages_path {
+            catalog.load(path)?;
+        }
+        Ok(catalog)
+    }
+
+    fn load(&mut self, path: &Path) -> anyhow::Result<()> {
+        for entry in std::fs::read_dir(path).with_context(|| format!("failed to read {}", path.display()))? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("yaml") {
+                continue;
+            }
+            let Some(language) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let messages =
+                std::fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+            self.add(language, &messages)
+                .with_context(|| format!("invalid message catalog {}", path.display()))?;
+            log::info!("Loaded messages for '{language}' from {}", path.display());
+        }
+        Ok(())
+    }
+
+    /// Add the messages of a language, in YAML, replacing messages with the same key.
+    fn add(&mut self, language: &str, messages: &str) -> Result<(), serde_yaml::Error> {
+        let messages: HashMap<String, String> = serde_yaml::from_str(messages)?;
+        self.languages
+            .entry(language.to_lowercase())
+            .or_default()
+            .extend(messages);
+        Ok(())
+    }
+
+    /// Pick the language best matching an `Accept-Language` header, falling back to English.
+    ///
+    /// A language range like `de-CH` also matches the catalog of its primary language `de`.
+    pub fn negotiate(&self, accept_language: Option<&str>) -> String {
+        let mut ranges: Vec<(String, f32)> = accept_language
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim().to_lowercase();
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map(|q| q.trim().parse().unwrap_or(0.0))
+                    .unwrap_or(1.0);
+                (!tag.is_empty() && quality > 0.0).then_some((tag, quality))
+            })
+            .collect();
+        // the sort is stable, keeping the order of ranges with the same quality
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        ranges
+            .into_iter()
+            .find_map(|(tag, _)| {
+                if tag == "*" {
+                    return Some(DEFAULT_LANGUAGE.to_string());
+                }
+                let primary = tag.split('-').next().unwrap_or_default();
+                [tag.as_str(), primary]
+                    .into_iter()
+                    .find(|candidate| self.languages.contains_key(*candidate))
+                    .map(ToString::to_string)
+            })
+            .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string())
+    }
+
+    /// Look up a message, replacing its `{name}` placeholders with the arguments.
+    ///
+    /// Falls back to the English message, and to the key if there is none.
+    pub fn message(&self, language: &str, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let template = [language, DEFAULT_LANGUAGE]
+            .into_iter()
+            .find_map(|language| self.languages.get(language)?.get(key))
+            .map(String::as_str)
+            .unwrap_or(key);
+
+        args.iter().fold(template.to_string(), |message, (name, value)| {
+            message.replace(&format!("{{{name}}}"), &value.to_string())
+        })
+    }
+}
+
+#[derive(Clone)]
+struct Localized {
+    catalog: Arc<Catalog>,
+    language: String,
+}
+
+fn builtin() -> &'static Catalog {
+    static BUILTIN: OnceLock<Catalog> = OnceLock::new();
+    BUILTIN.get_or_init(Catalog::builtin)
+}
+
+/// Look up a message in the language of the current request, or in English outside of requests.
+pub fn message(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    CURRENT
+        .try_with(|current| current.catalog.message(&current.language, key, args))
+        .unwrap_or_else(|_| builtin().message(DEFAULT_LANGUAGE, key, args))
+}
+
+/// Middleware negotiating the language of a request, which [`message`] uses while processing it.
+///
+/// Responses carry the negotiated language in the `Content-Language` header.
+#[derive(Clone)]
+pub struct Localization(Arc<Catalog>);
+
+impl Localization {
+    pub fn new(catalog: Arc<Catalog>) -> Self {
+        Self(catalog)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Localization
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = LocalizationMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(LocalizationMiddleware {
+            service,
+            catalog: self.0.clone(),
+        }))
+    }
+}
+
+pub struct LocalizationMiddleware<S> {
+    service: S,
+    catalog: Arc<Catalog>,
+}
+
+impl<S, B> Service<ServiceRequest> for LocalizationMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let accept_language = req.headers().get(ACCEPT_LANGUAGE).and_then(|value| value.to_str().ok());
+        let localized = Localized {
+            language: self.catalog.negotiate(accept_language),
+            catalog: self.catalog.clone(),
+        };
+        let language = HeaderValue::from_str(&localized.language).ok();
+
+        let fut = CURRENT.scope(localized, self.service.call(req));
+        Box::pin(async move {
+            let mut res = fut.await?;
+            if let Some(language) = language {
+                res.headers_mut().insert(CONTENT_LANGUAGE, language);
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{http::StatusCode, test, web, App, HttpResponse};
+
+    fn catalog() -> Catalog {
+        let mut catalog = Catalog::builtin();
+        catalog
+            .add("de", "export.not-found: Exportauftrag nicht gefunden")
+            .unwrap();
+        catalog
+            .add("pt-BR", "export.not-found: Exportação não encontrada")
+            .unwrap();
+        catalog
+    }
+
+    #[test]
+    fn negotiate() {
+        let catalog = catalog();
+        assert_eq!(catalog.negotiate(None), "en");
+        assert_eq!(catalog.negotiate(Some("de")), "de");
+        assert_eq!(catalog.negotiate(Some("de-CH, en;q=0.8")), "de");
+        assert_eq!(catalog.negotiate(Some("fr, de;q=0.5")), "de");
+        assert_eq!(catalog.negotiate(Some("en;q=0.9, de")), "de");
+        assert_eq!(catalog.negotiate(Some("PT-br")), "pt-br");
+        assert_eq!(catalog.negotiate(Some("de;q=0, fr")), "en");
+        assert_eq!(catalog.negotiate(Some("*")), "en");
+    }
+
+    #[test]
+    fn messages() {
+        let catalog = catalog();
+        assert_eq!(
+            catalog.message("de", "export.not-found", &[]),
+            "Exportauftrag nicht gefunden"
+        );
+        // missing translations fall back to English
+        assert_eq!(
+            catalog.message("de", "export.too-many-jobs", &[("max", &2)]),
+            "Too many export jobs, at most 2 may be pending or running at the same time"
+        );
+        assert_eq!(catalog.message("en", "unknown.key", &[]), "unknown.key");
+    }
+
+    #[actix_web::test]
+    async fn localization() {
+        async fn handler() -> HttpResponse {
+            HttpResponse::Ok().body(message("export.not-found", &[]))
+        }
+
+        let app = test::init_service(
+            App::new()
+                .wrap(Localization::new(Arc::new(catalog())))
+                .route("/", web::get().to(handler)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((ACCEPT_LANGUAGE, "de-DE, en;q=0.5"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(CONTENT_LANGUAGE).unwrap(), "de");
+        assert_eq!(test::read_body(resp).await, "Exportauftrag nicht gefunden");
+
+        // outside of requests, messages are in English
+        assert_eq!(message("export.not-found", &[]), "Export job not found");
+    }
+}
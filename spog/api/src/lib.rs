@@ -1,16 +1,31 @@
 mod analytics;
 mod app_state;
+mod bundle;
+mod cache;
 mod config;
 mod db;
 mod endpoints;
 mod error;
+mod export;
+mod i18n;
 mod license;
 mod openapi;
+mod posture;
+mod routing;
 mod search;
 mod server;
 mod service;
+mod subscriptions;
 mod utils;
 
+pub use cache::CacheConfig;
+pub use export::ExportConfig;
+pub use i18n::I18nConfig;
+pub use posture::PostureConfig;
+pub use routing::OwnershipConfig;
+pub use service::registry::RegistryConfig;
+pub use subscriptions::SubscriptionsConfig;
+
 use hide::Hide;
 use std::process::ExitCode;
 use std::{net::TcpListener, path::PathBuf};
@@ -95,6 +110,27 @@ pub struct Run {
     #[command(flatten)]
     pub client: ClientConfig,
 
+    #[command(flatten)]
+    pub cache: CacheConfig,
+
+    #[command(flatten)]
+    pub registry: RegistryConfig,
+
+    #[command(flatten)]
+    pub export: ExportConfig,
+
+    #[command(flatten)]
+    pub i18n: I18nConfig,
+
+    #[command(flatten)]
+    pub posture: PostureConfig,
+
+    #[command(flatten)]
+    pub ownership: OwnershipConfig,
+
+    #[command(flatten)]
+    pub subscriptions: SubscriptionsConfig,
+
     /// Base path to the database store. Defaults to the local directory.
     #[arg(env, long = "db-storage-base")]
     pub db_storage_base: Option<PathBuf>,
@@ -0,0 +1,159 @@
+//! Periodic snapshots of the vulnerability posture of SBOMs.
+//!
+//! A background task computes the vulnerability report of each SBOM, storing the number of vulnerabilities by
+//! severity along with the name and version of the SBOM. The snapshots form a time series, showing whether the posture
+//! of a product improves or worsens, over time and over its releases. Snapshots are kept for the retention period.
+
+use crate::app_state::AppState;
+use crate::endpoints::sbom::{process_get_vulnerabilities, GetParams};
+use crate::error::Error;
+use crate::service::{guac::GuacService, registry::RegistryService, v11y::V11yService};
+use actix_web::web;
+use spog_model::posture::PostureSnapshot;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::time::MissedTickBehavior;
+
+/// Number of SBOMs requested from bombastic at once.
+const PAGE_SIZE: usize = 500;
+
+/// The source of the scores the vulnerabilities are counted by.
+pub(crate) const SOURCE: &str = "mitre";
+
+#[derive(Clone, Debug, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Vulnerability posture")]
+pub struct PostureConfig {
+    /// Periodically take snapshots of the vulnerability posture of all SBOMs
+    #[arg(long = "posture-enabled", env = "POSTURE_ENABLED", default_value_t = false)]
+    pub enabled: bool,
+
+    /// Interval of taking snapshots
+    #[arg(long = "posture-interval", env = "POSTURE_INTERVAL", default_value = "24h")]
+    pub interval: humantime::Duration,
+
+    /// How long snapshots are kept
+    #[arg(long = "posture-retention", env = "POSTURE_RETENTION", default_value = "365d")]
+    pub retention: humantime::Duration,
+}
+
+impl Default for PostureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: Duration::from_secs(24 * 60 * 60).into(),
+            retention: Duration::from_secs(365 * 24 * 60 * 60).into(),
+        }
+    }
+}
+
+/// Takes the snapshots of all SBOMs.
+pub struct Collector {
+    interval: Duration,
+    retention: Duration,
+    state: web::Data<AppState>,
+    v11y: web::Data<V11yService>,
+    guac: web::Data<GuacService>,
+    registry: web::Data<RegistryService>,
+}
+
+impl Collector {
+    pub fn new(
+        config: &PostureConfig,
+        state: web::Data<AppState>,
+        v11y: web::Data<V11yService>,
+        guac: web::Data<GuacService>,
+        registry: web::Data<RegistryService>,
+    ) -> Self {
+        Self {
+            interval: config.interval.into(),
+            retention: config.retention.into(),
+            state,
+            v11y,
+            guac,
+            registry,
+        }
+    }
+
+    pub async fn run(self) -> anyhow::Result<()> {
+        let mut interval = tokio::time::interval(self.interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.collect().await {
+                log::warn!("Failed to take snapshots of the vulnerability posture: {err}");
+            }
+        }
+    }
+
+    /// Take a snapshot of each SBOM, and remove the expired snapshots.
+    async fn collect(&self) -> anyhow::Result<()> {
+        let ids = sbom_ids(&self.state).await?;
+
+        let mut taken = 0;
+        for id in ids {
+            // SBOMs failing to be analyzed are skipped, not recorded as having no vulnerabilities
+            match self.snapshot(&id).await {
+                Ok(Some(snapshot)) => {
+                    self.state.db_storage.record_posture_snapshot(&snapshot).await?;
+                    taken += 1;
+                }
+                Ok(None) => {}
+                Err(err) => log::warn!("Unable to take a snapshot of the vulnerability posture of {id}: {err}"),
+            }
+        }
+        log::info!("Took snapshots of the vulnerability posture of {taken} SBOMs");
+
+        let deleted = self
+            .state
+            .db_storage
+            .delete_posture_snapshots(OffsetDateTime::now_utc() - self.retention)
+            .await?;
+        log::debug!("Removed {deleted} expired snapshots of the vulnerability posture");
+
+        Ok(())
+    }
+
+    async fn snapshot(&self, id: &str) -> Result<Option<PostureSnapshot>, Error> {
+        let params = GetParams {
+            id: id.to_string(),
+            offset: None,
+            limit: None,
+            retrieve_remediation: None,
+        };
+        let report = process_get_vulnerabilities(
+            &self.state,
+            &self.v11y,
+            &self.guac,
+            &self.registry,
+            self.state.provider.as_ref(),
+            &params,
+        )
+        .await?;
+
+        Ok(report.map(|report| PostureSnapshot {
+            sbom: params.id,
+            summary: report.summary(SOURCE).map(<[_]>::to_vec).unwrap_or_default(),
+            name: report.name,
+            version: report.version,
+            timestamp: OffsetDateTime::now_utc(),
+        }))
+    }
+}
+
+/// The identifiers of all SBOMs, as seen by the service itself.
+///
+/// They are read from the changes feed, which pages by the time SBOMs were indexed, as search offsets are limited.
+pub(crate) async fn sbom_ids(state: &AppState) -> Result<Vec<String>, Error> {
+    let mut ids = Vec::new();
+    let mut since = 0;
+    loop {
+        let page = state
+            .get_sbom_changes(since, PAGE_SIZE, state.provider.as_ref())
+            .await?;
+        ids.extend(page.changes.into_iter().map(|change| change.id));
+        if !page.more {
+            return Ok(ids);
+        }
+        since = page.next;
+    }
+}
@@ -0,0 +1,239 @@
+//! Routing vulnerabilities newly found in SBOMs to the teams owning the affected packages.
+//!
+//! Routing follows the events of the indexers, using a consumer group of its own: the report of an indexed SBOM is
+//! computed right away, and its new findings are notified to the owning teams. An indexed advisory may affect any SBOM,
+//! so it only marks the reports as outdated. Outdated reports, and failed notifications, are handled by a periodic
+//! sweep over all SBOMs, which runs next to consuming events, so that a long sweep doesn't stall the consumer. An SBOM
+//! which can't be analyzed is logged and left alone until it is indexed again, rather than retried by the sweep.
+
+use crate::app_state::AppState;
+use crate::endpoints::{
+    ownership::notify_owners,
+    sbom::{process_get_vulnerabilities, GetParams},
+};
+use crate::posture::sbom_ids;
+use crate::service::{guac::GuacService, registry::RegistryService, v11y::V11yService};
+use actix_web::web;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use trustification_event_bus::{Error as BusError, EventBus};
+use trustification_storage::{Storage, StorageEvent};
+
+#[derive(Clone, Debug, clap::Args)]
+#[command(
+    rename_all_env = "SCREAMING_SNAKE_CASE",
+    next_help_heading = "Ownership notifications"
+)]
+pub struct OwnershipConfig {
+    /// Notify the owning teams of new vulnerabilities of indexed SBOMs, using the events of the indexers
+    #[arg(
+        long = "ownership-notifications-enabled",
+        env = "OWNERSHIP_NOTIFICATIONS_ENABLED",
+        default_value_t = false
+    )]
+    pub enabled: bool,
+
+    /// Interval of checking all SBOMs, if advisories were indexed or notifications failed since the last check
+    #[arg(
+        long = "ownership-sweep-interval",
+        env = "OWNERSHIP_SWEEP_INTERVAL",
+        default_value = "1h"
+    )]
+    pub sweep_interval: humantime::Duration,
+
+    /// Consumer group of routing findings, which is committed independently of the cache
+    #[arg(
+        long = "ownership-consumer-group",
+        env = "OWNERSHIP_CONSUMER_GROUP",
+        default_value = "spog-api-ownership"
+    )]
+    pub consumer_group: String,
+
+    /// Topic of SBOMs being indexed, to route the findings of
+    #[arg(
+        long = "ownership-sbom-indexed-topic",
+        env = "OWNERSHIP_SBOM_INDEXED_TOPIC",
+        default_value = "sbom-indexed"
+    )]
+    pub sbom_indexed_topic: String,
+
+    /// Topic of advisories being indexed, outdating the findings of all SBOMs
+    #[arg(
+        long = "ownership-vex-indexed-topic",
+        env = "OWNERSHIP_VEX_INDEXED_TOPIC",
+        default_value = "vex-indexed"
+    )]
+    pub vex_indexed_topic: String,
+}
+
+impl Default for OwnershipConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sweep_interval: Duration::from_secs(60 * 60).into(),
+            consumer_group: "spog-api-ownership".into(),
+            sbom_indexed_topic: "sbom-indexed".into(),
+            vex_indexed_topic: "vex-indexed".into(),
+        }
+    }
+}
+
+/// Routes the findings of SBOMs as they, or advisories, are indexed.
+pub struct Router {
+    sweep_interval: Duration,
+    consumer_group: String,
+    sbom_indexed_topic: String,
+    vex_indexed_topic: String,
+    state: web::Data<AppState>,
+    v11y: web::Data<V11yService>,
+    guac: web::Data<GuacService>,
+    registry: web::Data<RegistryService>,
+}
+
+impl Router {
+    pub fn new(
+        config: &OwnershipConfig,
+        state: web::Data<AppState>,
+        v11y: web::Data<V11yService>,
+        guac: web::Data<GuacService>,
+        registry: web::Data<RegistryService>,
+    ) -> Self {
+        Self {
+            sweep_interval: config.sweep_interval.into(),
+            consumer_group: config.consumer_group.clone(),
+            sbom_indexed_topic: config.sbom_indexed_topic.clone(),
+            vex_indexed_topic: config.vex_indexed_topic.clone(),
+            state,
+            v11y,
+            guac,
+            registry,
+        }
+    }
+
+    pub async fn run(self, bus: Arc<EventBus>) -> anyhow::Result<()> {
+        let outdated = AtomicBool::new(false);
+        tokio::select! {
+            result = self.consume(&bus, &outdated) => result,
+            _ = self.sweeps(&outdated) => Ok(()),
+        }
+    }
+
+    /// Route the findings of indexed SBOMs, and mark all reports as outdated for indexed advisories.
+    async fn consume(&self, bus: &EventBus, outdated: &AtomicBool) -> anyhow::Result<()> {
+        let topics = [self.sbom_indexed_topic.as_str(), self.vex_indexed_topic.as_str()];
+        let consumer = bus.subscribe(&self.consumer_group, &topics).await?;
+        log::info!("Routing findings to the owning teams using events from: {topics:?}");
+
+        loop {
+            match consumer.next().await {
+                Ok(Some(event)) => {
+                    let sbom = event.topic() == self.sbom_indexed_topic;
+                    match event.payload().map(serde_json::from_slice::<StorageEvent>) {
+                        Some(Ok(data)) if sbom => {
+                            for record in data.records {
+                                match Storage::key_from_event(&record) {
+                                    Ok((_, key)) => {
+                                        if !self.route(&key).await {
+                                            outdated.store(true, Ordering::Relaxed);
+                                        }
+                                    }
+                                    Err(e) => log::warn!("Error decoding event key, skipping: {e:?}"),
+                                }
+                            }
+                        }
+                        Some(Ok(_)) => outdated.store(true, Ordering::Relaxed),
+                        Some(Err(e)) => {
+                            // we don't know what changed, so check everything
+                            log::warn!("Error decoding event, checking all SBOMs: {e:?}");
+                            outdated.store(true, Ordering::Relaxed);
+                        }
+                        None => log::warn!("No event for payload, skipping"),
+                    }
+
+                    if let Err(e) = consumer.commit(&[event]).await {
+                        log::warn!("Error committing event: {e:?}");
+                    }
+                }
+                Ok(None) => {
+                    log::debug!("Polling returned no events, retrying");
+                }
+                Err(BusError::Critical(s)) => {
+                    log::warn!("Critical error while polling, exiting: {s:?}");
+                    return Err(anyhow::anyhow!(s));
+                }
+                Err(e) => {
+                    log::warn!("Error polling for event: {e:?}");
+                }
+            }
+        }
+    }
+
+    /// Sweep all SBOMs at each interval in which their reports were outdated.
+    async fn sweeps(&self, outdated: &AtomicBool) {
+        let mut interval = tokio::time::interval(self.sweep_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        // the first tick completes right away, before anything could be outdated
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if outdated.swap(false, Ordering::Relaxed) && !self.sweep().await {
+                outdated.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Route the findings of all SBOMs, returning whether all of them were delivered.
+    async fn sweep(&self) -> bool {
+        let ids = match sbom_ids(&self.state).await {
+            Ok(ids) => ids,
+            Err(err) => {
+                log::warn!("Failed to list the SBOMs to route findings of: {err}");
+                return false;
+            }
+        };
+
+        let mut delivered = true;
+        for id in &ids {
+            delivered &= self.route(id).await;
+        }
+        log::info!("Routed the findings of {} SBOMs", ids.len());
+        delivered
+    }
+
+    /// Route the findings of an SBOM, returning whether they were delivered.
+    async fn route(&self, id: &str) -> bool {
+        let params = GetParams {
+            id: id.to_string(),
+            offset: None,
+            limit: None,
+            retrieve_remediation: None,
+        };
+        let report = match process_get_vulnerabilities(
+            &self.state,
+            &self.v11y,
+            &self.guac,
+            &self.registry,
+            self.state.provider.as_ref(),
+            &params,
+        )
+        .await
+        {
+            Ok(Some(report)) => report,
+            Ok(None) => return true,
+            Err(err) => {
+                log::warn!("Unable to compute the vulnerability report of {id}, skipping: {err}");
+                return true;
+            }
+        };
+
+        match notify_owners(&self.state, id, &report).await {
+            Ok(()) => true,
+            Err(err) => {
+                log::warn!("Unable to notify the owners of {id}: {err}");
+                false
+            }
+        }
+    }
+}
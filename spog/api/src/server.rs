@@ -1,10 +1,14 @@
 use crate::db::Db;
 use crate::{
     app_state::AppState,
+    cache::{self, DerivedCache},
     config,
     endpoints::{self, wellknown::endpoints::Endpoints},
-    service::{collectorist::CollectoristService, guac::GuacService, v11y::V11yService},
-    Run,
+    export::{self, Exports},
+    i18n::{Catalog, Localization},
+    posture, routing,
+    service::{collectorist::CollectoristService, guac::GuacService, registry::RegistryService, v11y::V11yService},
+    subscriptions, Run,
 };
 use actix_web::web;
 use anyhow::Context;
@@ -16,7 +20,6 @@ use trustification_analytics::Tracker;
 use trustification_auth::{authenticator::Authenticator, authorizer::Authorizer, swagger_ui::SwaggerUiOidc};
 use trustification_infrastructure::{app::http::HttpServerBuilder, MainContext};
 use trustification_version::version;
-use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 pub struct Server {
@@ -37,8 +40,9 @@ impl Server {
             vexination: self.run.vexination_url.clone(),
             exhort: self.run.exhort_url.clone(),
             provider: provider.clone(),
-            db_storage: Db::new(db_path).await?,
+            db_storage: Db::new(&db_path).await?,
         });
+        let exports = Arc::new(Exports::new(self.run.export, &db_path).await?);
 
         let (authn, authz) = self.run.auth.split(self.run.devmode)?.unzip();
         let authenticator: Option<Arc<Authenticator>> = Authenticator::from_config(authn)
@@ -86,9 +90,59 @@ impl Server {
             provider.clone(),
         ));
 
+        let registry = web::Data::new(RegistryService::new(self.run.client.build_client()?, self.run.registry));
+
         let (tracker, flusher) = Tracker::new(self.run.analytics);
         let tracker = web::Data::from(tracker);
 
+        let cache_config = self.run.cache;
+        let ownership = self.run.ownership;
+        // the cache and the routing of findings share the connection to the event bus, but consume separately
+        let bus = match cache_config.enabled || ownership.enabled {
+            true => Some(Arc::new(cache_config.bus.create(context.metrics.registry()).await?)),
+            false => None,
+        };
+
+        let router = match (&bus, ownership.enabled) {
+            (Some(bus), true) => Some(
+                routing::Router::new(&ownership, state.clone(), v11y.clone(), guac.clone(), registry.clone())
+                    .run(bus.clone()),
+            ),
+            _ => None,
+        };
+
+        let cache = Arc::new(DerivedCache::new(&cache_config));
+        let cache_listener = match (bus, cache_config.enabled) {
+            (Some(bus), true) => Some(cache::run(cache.clone(), bus, cache_config)),
+            _ => None,
+        };
+        let cache = web::Data::from(cache);
+
+        let posture = self.run.posture.enabled.then(|| {
+            posture::Collector::new(
+                &self.run.posture,
+                state.clone(),
+                v11y.clone(),
+                guac.clone(),
+                registry.clone(),
+            )
+        });
+
+        let publisher = self.run.subscriptions.enabled.then(|| {
+            subscriptions::Publisher::new(
+                &self.run.subscriptions,
+                state.clone(),
+                v11y.clone(),
+                guac.clone(),
+                registry.clone(),
+            )
+        });
+
+        let export_expiry = export::expire(exports.clone());
+        let exports = web::Data::from(exports);
+
+        let catalog = Arc::new(Catalog::new(self.run.i18n)?);
+
         let mut http = HttpServerBuilder::try_from(self.run.http)?
             .tracing(self.run.infra.tracing)
             .metrics(context.metrics.registry().clone(), "spog_api")
@@ -100,30 +154,43 @@ impl Server {
                     .app_data(tracker.clone())
                     .app_data(v11y.clone())
                     .app_data(collectorist.clone())
-                    .configure(endpoints::index::configure())
-                    .configure(version::configurator(version!()))
-                    .configure(endpoints::wellknown::endpoints::configurator(endpoints.clone()))
-                    .configure(endpoints::sbom::configure(authenticator.clone()))
-                    .configure(endpoints::advisory::configure(authenticator.clone()))
-                    .configure(endpoints::cve::configure(authenticator.clone()))
-                    .configure(endpoints::package::configure(authenticator.clone()))
-                    .configure(endpoints::suggestion::configure(authenticator.clone()))
-                    .configure(endpoints::dashboard::configure(
-                        authenticator.clone(),
-                        crda_payload_limit,
-                    ))
-                    .configure(endpoints::license::configure(crda_payload_limit))
-                    .configure(config_configurator.clone())
-                    .service({
-                        let mut openapi = endpoints::ApiDoc::openapi();
-                        let mut swagger = SwaggerUi::new("/swagger-ui/{_:.*}");
-
-                        if let Some(swagger_ui_oidc) = &swagger_oidc {
-                            swagger = swagger_ui_oidc.apply(swagger, &mut openapi);
-                        }
-
-                        swagger.url("/openapi.json", openapi)
-                    });
+                    .app_data(registry.clone())
+                    .app_data(cache.clone())
+                    .app_data(exports.clone())
+                    .service(
+                        // all endpoints are localized, as errors may occur on any of them
+                        web::scope("")
+                            .wrap(Localization::new(catalog.clone()))
+                            .configure(endpoints::index::configure())
+                            .configure(version::configurator(version!()))
+                            .configure(endpoints::wellknown::endpoints::configurator(endpoints.clone()))
+                            .configure(endpoints::sbom::configure(authenticator.clone()))
+                            .configure(endpoints::advisory::configure(authenticator.clone()))
+                            .configure(endpoints::cve::configure(authenticator.clone()))
+                            .configure(endpoints::package::configure(authenticator.clone()))
+                            .configure(endpoints::suggestion::configure(authenticator.clone()))
+                            .configure(endpoints::score::configure(authenticator.clone()))
+                            .configure(endpoints::ownership::configure(authenticator.clone()))
+                            .configure(endpoints::subscription::configure(authenticator.clone()))
+                            .configure(endpoints::export::configure(authenticator.clone()))
+                            .configure(endpoints::dashboard::configure(
+                                authenticator.clone(),
+                                crda_payload_limit,
+                            ))
+                            .configure(endpoints::license::configure(crda_payload_limit))
+                            .configure(endpoints::configure(authenticator.clone()))
+                            .configure(config_configurator.clone())
+                            .service({
+                                let mut openapi = endpoints::api_doc();
+                                let mut swagger = SwaggerUi::new("/swagger-ui/{_:.*}");
+
+                                if let Some(swagger_ui_oidc) = &swagger_oidc {
+                                    swagger = swagger_ui_oidc.apply(swagger, &mut openapi);
+                                }
+
+                                swagger.url("/openapi.json", openapi)
+                            }),
+                    );
             });
 
         if let Some(v) = listener {
@@ -139,6 +206,19 @@ impl Server {
         let mut tasks = vec![http];
 
         tasks.extend(flusher);
+        tasks.push(Box::pin(export_expiry));
+        if let Some(cache_listener) = cache_listener {
+            tasks.push(Box::pin(cache_listener));
+        }
+        if let Some(router) = router {
+            tasks.push(Box::pin(router));
+        }
+        if let Some(posture) = posture {
+            tasks.push(Box::pin(posture.run()));
+        }
+        if let Some(publisher) = publisher {
+            tasks.push(Box::pin(publisher.run()));
+        }
 
         // run all tasks
 
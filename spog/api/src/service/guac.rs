@@ -13,6 +13,7 @@ use http::StatusCode;
 use packageurl::PackageUrl;
 use tracing::instrument;
 
+use crate::i18n;
 use spog_model::prelude::{
     CveDetails, PackageDependencies, PackageDependents, PackageRefList, PackageRelatedToProductCve, ProductCveStatus,
     ProductRelatedToPackage,
@@ -65,12 +66,12 @@ impl actix_web::error::ResponseError for Error {
             }),
             Self::PurlFormat(error) => res.json(ErrorInformation {
                 error: format!("{}", self.status_code()),
-                message: "Purl parsing error".to_string(),
+                message: i18n::message("error.purl-parsing", &[]),
                 details: error.to_string(),
             }),
             Self::Client(error) => res.json(ErrorInformation {
                 error: format!("{}", self.status_code()),
-                message: "Guac client error".to_string(),
+                message: i18n::message("error.guac-client", &[]),
                 details: error.to_string(),
             }),
         }
@@ -108,6 +109,22 @@ impl GuacService {
         Ok(PackageRefList::from(pkgs))
     }
 
+    /// Check if GUAC knows a package, by its Package URL
+    #[instrument(skip(self), err)]
+    pub async fn has_package(&self, purl: &str) -> Result<bool, Error> {
+        let purl = PackageUrl::from_str(purl)?;
+        let packages = self.client.intrinsic().packages(&purl.into()).await?;
+        Ok(!packages.is_empty())
+    }
+
+    /// Lookup the URIs of the SBOMs GUAC has for a package
+    #[instrument(skip(self), err)]
+    pub async fn sbom_uris(&self, purl: &str) -> Result<Vec<String>, Error> {
+        let purl = PackageUrl::from_str(purl)?;
+        let sboms = self.client.intrinsic().has_sbom(&purl.into()).await?;
+        Ok(sboms.into_iter().map(|sbom| sbom.uri).collect())
+    }
+
     /// Lookup dependencies for a provided Package URL
     #[instrument(skip(self), err)]
     pub async fn get_dependencies(&self, purl: &str) -> Result<PackageDependencies, Error> {
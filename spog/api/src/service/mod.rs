@@ -1,3 +1,4 @@
 pub mod collectorist;
 pub mod guac;
+pub mod registry;
 pub mod v11y;
@@ -0,0 +1,428 @@
+//! Lookup of components in their upstream package registries.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use http::StatusCode;
+use packageurl::PackageUrl;
+use reqwest::header::USER_AGENT;
+use serde::Deserialize;
+use spog_model::provenance::Registry;
+use std::collections::HashMap;
+use tracing::instrument;
+use url::Url;
+
+#[derive(Clone, Debug, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Package registries")]
+pub struct RegistryConfig {
+    /// Base URL of crates.io
+    #[arg(
+        long = "registry-crates-io-url",
+        env = "REGISTRY_CRATES_IO_URL",
+        default_value = "https://crates.io"
+    )]
+    pub crates_io_url: Url,
+
+    /// Base URL of the npm registry
+    #[arg(
+        long = "registry-npm-url",
+        env = "REGISTRY_NPM_URL",
+        default_value = "https://registry.npmjs.org"
+    )]
+    pub npm_url: Url,
+
+    /// Base URL of PyPI
+    #[arg(
+        long = "registry-pypi-url",
+        env = "REGISTRY_PYPI_URL",
+        default_value = "https://pypi.org"
+    )]
+    pub pypi_url: Url,
+
+    /// Base URL of the Maven repository
+    #[arg(
+        long = "registry-maven-url",
+        env = "REGISTRY_MAVEN_URL",
+        default_value = "https://repo1.maven.org/maven2"
+    )]
+    pub maven_url: Url,
+
+    /// Number of concurrent registry lookups of a provenance check
+    #[arg(long = "registry-concurrency", env = "REGISTRY_CONCURRENCY", default_value_t = 8)]
+    pub concurrency: usize,
+
+    /// Maximum number of components checked per SBOM, the components of larger SBOMs are sampled
+    #[arg(
+        long = "registry-max-components",
+        env = "REGISTRY_MAX_COMPONENTS",
+        default_value_t = 500
+    )]
+    pub max_components: usize,
+}
+
+impl Default for RegistryConfig {
+    fn default() -> Self {
+        let url = |url: &str| Url::parse(url).expect("valid registry URL");
+        Self {
+            crates_io_url: url("https://crates.io"),
+            npm_url: url("https://registry.npmjs.org"),
+            pypi_url: url("https://pypi.org"),
+            maven_url: url("https://repo1.maven.org/maven2"),
+            concurrency: 8,
+            max_components: 500,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("request error: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("unexpected response status {0}")]
+    Response(StatusCode),
+    #[error("incomplete Package URL: {0}")]
+    Purl(String),
+    #[error("invalid registry URL: {0}")]
+    Url(Url),
+}
+
+/// Algorithm of a checksum.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// A checksum, with its value as lowercase hex.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Checksum {
+    pub algorithm: Algorithm,
+    pub value: String,
+}
+
+impl Checksum {
+    pub fn new(algorithm: Algorithm, value: impl AsRef<str>) -> Self {
+        Self {
+            algorithm,
+            value: value.as_ref().trim().to_lowercase(),
+        }
+    }
+
+    /// Parse a subresource integrity value, like `sha512-<base64>`, as used by npm.
+    fn from_integrity(integrity: &str) -> Option<Self> {
+        let (algorithm, value) = integrity.split_once('-')?;
+        let algorithm = match algorithm {
+            "sha1" => Algorithm::Sha1,
+            "sha256" => Algorithm::Sha256,
+            "sha512" => Algorithm::Sha512,
+            _ => return None,
+        };
+        let value = STANDARD.decode(value).ok()?;
+        Some(Self {
+            algorithm,
+            value: value.iter().map(|b| format!("{b:02x}")).collect(),
+        })
+    }
+}
+
+/// A component as published by its registry.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Published {
+    /// Checksums of the published artifacts
+    pub checksums: Vec<Checksum>,
+}
+
+pub struct RegistryService {
+    client: reqwest::Client,
+    config: RegistryConfig,
+}
+
+impl RegistryService {
+    pub fn new(client: reqwest::Client, config: RegistryConfig) -> Self {
+        Self { client, config }
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.config.concurrency.max(1)
+    }
+
+    pub fn max_components(&self) -> usize {
+        self.config.max_components
+    }
+
+    /// Look up the component in its registry, `None` if the registry doesn't know it.
+    #[instrument(skip(self, purl), fields(purl = %purl), err)]
+    pub async fn lookup(&self, registry: Registry, purl: &PackageUrl<'_>) -> Result<Option<Published>, Error> {
+        let version = purl.version().ok_or_else(|| Error::Purl("missing version".into()))?;
+        match registry {
+            Registry::Cargo => self.lookup_cargo(purl.name(), version).await,
+            Registry::Npm => self.lookup_npm(purl.namespace(), purl.name(), version).await,
+            Registry::Pypi => self.lookup_pypi(purl.name(), version).await,
+            Registry::Maven => self.lookup_maven(purl, version).await,
+        }
+    }
+
+    /// The published versions of the component, `None` if the registry doesn't know it.
+    ///
+    /// Yanked and unpublished versions are left out.
+    #[instrument(skip(self, purl), fields(purl = %purl), err)]
+    pub async fn versions(&self, registry: Registry, purl: &PackageUrl<'_>) -> Result<Option<Vec<String>>, Error> {
+        match registry {
+            Registry::Cargo => self.versions_cargo(purl.name()).await,
+            Registry::Npm => self.versions_npm(purl.namespace(), purl.name()).await,
+            Registry::Pypi => self.versions_pypi(purl.name()).await,
+            Registry::Maven => self.versions_maven(purl).await,
+        }
+    }
+
+    async fn versions_cargo(&self, name: &str) -> Result<Option<Vec<String>>, Error> {
+        #[derive(Deserialize)]
+        struct Response {
+            versions: Vec<Version>,
+        }
+        #[derive(Deserialize)]
+        struct Version {
+            num: String,
+            #[serde(default)]
+            yanked: bool,
+        }
+
+        let url = join(&self.config.crates_io_url, ["api", "v1", "crates", name])?;
+        Ok(self.get_json::<Response>(url).await?.map(|response| {
+            response
+                .versions
+                .into_iter()
+                .filter(|version| !version.yanked)
+                .map(|version| version.num)
+                .collect()
+        }))
+    }
+
+    async fn versions_npm(&self, scope: Option<&str>, name: &str) -> Result<Option<Vec<String>>, Error> {
+        #[derive(Deserialize)]
+        struct Response {
+            #[serde(default)]
+            versions: HashMap<String, serde::de::IgnoredAny>,
+        }
+
+        let url = join(&self.config.npm_url, scope.into_iter().chain([name]))?;
+        Ok(self
+            .get_json::<Response>(url)
+            .await?
+            .map(|response| response.versions.into_keys().collect()))
+    }
+
+    async fn versions_pypi(&self, name: &str) -> Result<Option<Vec<String>>, Error> {
+        #[derive(Deserialize)]
+        struct Response {
+            #[serde(default)]
+            releases: HashMap<String, Vec<File>>,
+        }
+        #[derive(Deserialize)]
+        struct File {
+            #[serde(default)]
+            yanked: bool,
+        }
+
+        let url = join(&self.config.pypi_url, ["pypi", name, "json"])?;
+        Ok(self.get_json::<Response>(url).await?.map(|response| {
+            response
+                .releases
+                .into_iter()
+                // releases without files were never published, or removed again
+                .filter(|(_, files)| files.iter().any(|file| !file.yanked))
+                .map(|(version, _)| version)
+                .collect()
+        }))
+    }
+
+    async fn versions_maven(&self, purl: &PackageUrl<'_>) -> Result<Option<Vec<String>>, Error> {
+        let group = purl.namespace().ok_or_else(|| Error::Purl("missing group id".into()))?;
+        let url = join(
+            &self.config.maven_url,
+            group.split('.').chain([purl.name(), "maven-metadata.xml"]),
+        )?;
+        let response = self.client.get(url).header(USER_AGENT, USER_AGENT_VALUE).send().await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(Some(metadata_versions(&response.text().await?))),
+            status => Err(Error::Response(status)),
+        }
+    }
+
+    async fn lookup_cargo(&self, name: &str, version: &str) -> Result<Option<Published>, Error> {
+        #[derive(Deserialize)]
+        struct Response {
+            version: Version,
+        }
+        #[derive(Deserialize)]
+        struct Version {
+            checksum: Option<String>,
+        }
+
+        let url = join(&self.config.crates_io_url, ["api", "v1", "crates", name, version])?;
+        Ok(self.get_json::<Response>(url).await?.map(|response| Published {
+            checksums: response
+                .version
+                .checksum
+                .map(|checksum| Checksum::new(Algorithm::Sha256, checksum))
+                .into_iter()
+                .collect(),
+        }))
+    }
+
+    async fn lookup_npm(&self, scope: Option<&str>, name: &str, version: &str) -> Result<Option<Published>, Error> {
+        #[derive(Deserialize)]
+        struct Response {
+            dist: Dist,
+        }
+        #[derive(Deserialize)]
+        struct Dist {
+            shasum: Option<String>,
+            integrity: Option<String>,
+        }
+
+        let url = join(&self.config.npm_url, scope.into_iter().chain([name, version]))?;
+        Ok(self.get_json::<Response>(url).await?.map(|response| Published {
+            checksums: response
+                .dist
+                .shasum
+                .map(|shasum| Checksum::new(Algorithm::Sha1, shasum))
+                .into_iter()
+                .chain(response.dist.integrity.as_deref().and_then(Checksum::from_integrity))
+                .collect(),
+        }))
+    }
+
+    async fn lookup_pypi(&self, name: &str, version: &str) -> Result<Option<Published>, Error> {
+        #[derive(Deserialize)]
+        struct Response {
+            #[serde(default)]
+            urls: Vec<File>,
+        }
+        #[derive(Deserialize)]
+        struct File {
+            digests: Digests,
+        }
+        #[derive(Deserialize)]
+        struct Digests {
+            sha256: Option<String>,
+        }
+
+        let url = join(&self.config.pypi_url, ["pypi", name, version, "json"])?;
+        Ok(self.get_json::<Response>(url).await?.map(|response| Published {
+            checksums: response
+                .urls
+                .into_iter()
+                .filter_map(|file| file.digests.sha256)
+                .map(|sha256| Checksum::new(Algorithm::Sha256, sha256))
+                .collect(),
+        }))
+    }
+
+    async fn lookup_maven(&self, purl: &PackageUrl<'_>, version: &str) -> Result<Option<Published>, Error> {
+        let group = purl.namespace().ok_or_else(|| Error::Purl("missing group id".into()))?;
+        let artifact = purl.name();
+        let extension = purl.qualifiers().get("type").map(|ty| &**ty).unwrap_or("jar");
+        let file = match purl.qualifiers().get("classifier") {
+            Some(classifier) => format!("{artifact}-{version}-{classifier}.{extension}.sha1"),
+            None => format!("{artifact}-{version}.{extension}.sha1"),
+        };
+
+        let url = join(
+            &self.config.maven_url,
+            group.split('.').chain([artifact, version, file.as_str()]),
+        )?;
+        let response = self.client.get(url).header(USER_AGENT, USER_AGENT_VALUE).send().await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                // the checksum file may be followed by the file name
+                let body = response.text().await?;
+                Ok(Some(Published {
+                    checksums: body
+                        .split_whitespace()
+                        .next()
+                        .map(|sha1| Checksum::new(Algorithm::Sha1, sha1))
+                        .into_iter()
+                        .collect(),
+                }))
+            }
+            status => Err(Error::Response(status)),
+        }
+    }
+
+    async fn get_json<T: for<'de> Deserialize<'de>>(&self, url: Url) -> Result<Option<T>, Error> {
+        let response = self.client.get(url).header(USER_AGENT, USER_AGENT_VALUE).send().await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(Some(response.json().await?)),
+            status => Err(Error::Response(status)),
+        }
+    }
+}
+
+/// Registries like crates.io reject requests without a user agent.
+const USER_AGENT_VALUE: &str = "trustification";
+
+/// The versions listed by a `maven-metadata.xml` document.
+fn metadata_versions(metadata: &str) -> Vec<String> {
+    metadata
+        .split("<version>")
+        .skip(1)
+        .filter_map(|rest| rest.split_once("</version>"))
+        .map(|(version, _)| version.trim().to_string())
+        .filter(|version| !version.is_empty())
+        .collect()
+}
+
+fn join<'a>(base: &Url, segments: impl IntoIterator<Item = &'a str>) -> Result<Url, Error> {
+    let mut url = base.clone();
+    url.path_segments_mut()
+        .map_err(|()| Error::Url(base.clone()))?
+        .pop_if_empty()
+        .extend(segments);
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integrity() {
+        let checksum = Checksum::from_integrity("sha1-AAEC/w==").unwrap();
+        assert_eq!(checksum, Checksum::new(Algorithm::Sha1, "000102ff"));
+        assert!(Checksum::from_integrity("md5-AAEC/w==").is_none());
+        assert!(Checksum::from_integrity("sha512").is_none());
+    }
+
+    #[test]
+    fn registry_urls() {
+        let base = Url::parse("https://repo1.maven.org/maven2/").unwrap();
+        let url = join(&base, "org.apache".split('.').chain(["commons", "1.0"])).unwrap();
+        assert_eq!(url.as_str(), "https://repo1.maven.org/maven2/org/apache/commons/1.0");
+
+        let base = Url::parse("https://registry.npmjs.org").unwrap();
+        let url = join(&base, ["@types", "node", "18.0.0"]).unwrap();
+        assert_eq!(url.as_str(), "https://registry.npmjs.org/@types/node/18.0.0");
+    }
+
+    #[test]
+    fn maven_metadata() {
+        let metadata = r#"<?xml version="1.0" encoding="UTF-8"?>
+<metadata>
+  <groupId>io.vertx</groupId>
+  <artifactId>vertx-core</artifactId>
+  <versioning>
+    <latest>4.4.6</latest>
+    <release>4.4.6</release>
+    <versions>
+      <version>4.4.4</version>
+      <version>4.4.5</version>
+      <version>4.4.6</version>
+    </versions>
+  </versioning>
+</metadata>"#;
+        assert_eq!(metadata_versions(metadata), ["4.4.4", "4.4.5", "4.4.6"]);
+    }
+}
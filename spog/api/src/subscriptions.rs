@@ -0,0 +1,338 @@
+//! Delivering the changes of SBOMs to the subscriptions matching them.
+//!
+//! A background task follows the changes feed of bombastic, from a cursor stored along with the subscriptions. The
+//! vulnerability report of each changed SBOM is computed, and the change is queued for each subscription matching it,
+//! along with the matching findings. Queued events are posted to the webhooks of their subscriptions. A failed delivery
+//! holds back the later events of its subscription, and is retried with an exponential backoff, until it is given up on
+//! after a number of attempts. A change of an SBOM which can't be analyzed is dropped without queueing events, as the
+//! cursor moves on.
+
+use crate::app_state::AppState;
+use crate::db::PendingEvent;
+use crate::endpoints::sbom::{process_get_vulnerabilities, vuln::into_severity, GetParams};
+use crate::posture::SOURCE;
+use crate::service::{guac::GuacService, registry::RegistryService, v11y::V11yService};
+use actix_web::web;
+use bombastic_model::changes::Change;
+use spog_model::subscription::{Subscription, SubscriptionEvent, SubscriptionFinding};
+use std::collections::{BTreeSet, HashSet};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::time::MissedTickBehavior;
+
+/// Number of changes requested from bombastic at once.
+const PAGE_SIZE: usize = 100;
+
+/// How far the changes feed is requested again before the cursor, as SBOMs may show up after SBOMs indexed later.
+const OVERLAP: Duration = Duration::from_secs(60 * 60);
+
+/// Longest time waited before retrying a failed delivery.
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone, Debug, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Subscriptions")]
+pub struct SubscriptionsConfig {
+    /// Deliver the changes of SBOMs to the subscriptions matching them, following the changes feed of bombastic
+    #[arg(
+        long = "subscriptions-enabled",
+        env = "SUBSCRIPTIONS_ENABLED",
+        default_value_t = false
+    )]
+    pub enabled: bool,
+
+    /// Interval of checking the changes feed, and retrying failed deliveries
+    #[arg(
+        long = "subscriptions-interval",
+        env = "SUBSCRIPTIONS_INTERVAL",
+        default_value = "1m"
+    )]
+    pub interval: humantime::Duration,
+
+    /// Number of events kept in the queue of each subscription
+    #[arg(
+        long = "subscriptions-retained",
+        env = "SUBSCRIPTIONS_RETAINED",
+        default_value_t = 1000
+    )]
+    pub retained: usize,
+
+    /// Number of failed attempts of posting an event to a webhook, after which the event isn't posted anymore
+    #[arg(
+        long = "subscriptions-max-attempts",
+        env = "SUBSCRIPTIONS_MAX_ATTEMPTS",
+        default_value_t = 10
+    )]
+    pub max_attempts: u32,
+}
+
+impl Default for SubscriptionsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: Duration::from_secs(60).into(),
+            retained: 1000,
+            max_attempts: 10,
+        }
+    }
+}
+
+/// Matches the changes of SBOMs against the subscriptions, and delivers the events.
+pub struct Publisher {
+    interval: Duration,
+    retained: usize,
+    max_attempts: u32,
+    state: web::Data<AppState>,
+    v11y: web::Data<V11yService>,
+    guac: web::Data<GuacService>,
+    registry: web::Data<RegistryService>,
+}
+
+impl Publisher {
+    pub fn new(
+        config: &SubscriptionsConfig,
+        state: web::Data<AppState>,
+        v11y: web::Data<V11yService>,
+        guac: web::Data<GuacService>,
+        registry: web::Data<RegistryService>,
+    ) -> Self {
+        Self {
+            interval: config.interval.into(),
+            retained: config.retained,
+            max_attempts: config.max_attempts.max(1),
+            state,
+            v11y,
+            guac,
+            registry,
+        }
+    }
+
+    pub async fn run(self) -> anyhow::Result<()> {
+        // the changes within the overlap, which were matched already
+        let mut seen = HashSet::new();
+        let mut interval = tokio::time::interval(self.interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.publish(&mut seen).await {
+                log::warn!("Failed to match the changes of SBOMs against the subscriptions: {err}");
+            }
+            if let Err(err) = self.deliver().await {
+                log::warn!("Failed to deliver the events of subscriptions: {err}");
+            }
+        }
+    }
+
+    /// Queue the changes after the cursor for the matching subscriptions, and advance the cursor.
+    async fn publish(&self, seen: &mut HashSet<(String, i64)>) -> anyhow::Result<()> {
+        let db = &self.state.db_storage;
+        let subscriptions = db.select_subscriptions(None).await?;
+        let Some(cursor) = db.select_subscription_cursor().await? else {
+            // start with the changes from now on, instead of all SBOMs
+            let now = OffsetDateTime::now_utc().unix_timestamp_nanos() as i64;
+            db.update_subscription_cursor(now).await?;
+            return Ok(());
+        };
+
+        let overlap = OVERLAP.as_nanos() as i64;
+        let mut since = cursor.saturating_sub(overlap);
+        let mut next = cursor;
+        let mut queued = 0;
+        loop {
+            let page = self
+                .state
+                .get_sbom_changes(since, PAGE_SIZE, self.state.provider.as_ref())
+                .await?;
+            for change in page.changes {
+                let key = (change.id.clone(), change.indexed_timestamp);
+                if seen.contains(&key) {
+                    continue;
+                }
+                if !subscriptions.is_empty() {
+                    queued += self.queue(&subscriptions, change).await?;
+                }
+                seen.insert(key);
+            }
+            next = next.max(page.next);
+            since = page.next;
+            if !page.more {
+                break;
+            }
+        }
+
+        seen.retain(|(_, indexed_timestamp)| *indexed_timestamp > next.saturating_sub(overlap));
+        db.update_subscription_cursor(next).await?;
+        if queued > 0 {
+            log::info!("Queued {queued} events for subscriptions");
+        }
+        Ok(())
+    }
+
+    /// Queue a change for the matching subscriptions, returning the number of queued events.
+    async fn queue(&self, subscriptions: &[Subscription], change: Change) -> anyhow::Result<usize> {
+        let Some(findings) = self.analyze(&change.id).await else {
+            return Ok(0);
+        };
+
+        let mut queued = 0;
+        for subscription in subscriptions {
+            let Some(findings) = subscription.matches(&change.labels, &findings) else {
+                continue;
+            };
+            let event = SubscriptionEvent {
+                seq: 0,
+                subscription: subscription.id.clone(),
+                sbom: change.id.clone(),
+                sha256: change.sha256.clone(),
+                indexed_timestamp: change.indexed_timestamp,
+                labels: change.labels.clone(),
+                findings,
+            };
+            let pending = subscription.webhook.is_some();
+            if let Some(event) = self
+                .state
+                .db_storage
+                .queue_subscription_event(event, pending, self.retained)
+                .await?
+            {
+                log::debug!(
+                    "Queued change of {} for {} at {}",
+                    event.sbom,
+                    event.subscription,
+                    event.seq
+                );
+                queued += 1;
+            }
+        }
+        Ok(queued)
+    }
+
+    /// The findings of an SBOM, or `None` if it can't be analyzed.
+    async fn analyze(&self, id: &str) -> Option<Vec<SubscriptionFinding>> {
+        let params = GetParams {
+            id: id.to_string(),
+            offset: None,
+            limit: None,
+            retrieve_remediation: None,
+        };
+        let report = match process_get_vulnerabilities(
+            &self.state,
+            &self.v11y,
+            &self.guac,
+            &self.registry,
+            self.state.provider.as_ref(),
+            &params,
+        )
+        .await
+        {
+            Ok(Some(report)) => report,
+            // deleted since
+            Ok(None) => return None,
+            Err(err) => {
+                log::warn!("Unable to compute the vulnerability report of {id}, skipping: {err}");
+                return None;
+            }
+        };
+
+        let findings = report
+            .details
+            .iter()
+            .flat_map(|vuln| {
+                vuln.affected_packages.keys().map(|purl| SubscriptionFinding {
+                    vulnerability: vuln.id.clone(),
+                    purl: purl.clone(),
+                    severity: vuln.score(SOURCE).map(into_severity),
+                })
+            })
+            .collect();
+        Some(findings)
+    }
+
+    /// Post the pending events to the webhooks of their subscriptions, in the order they were queued.
+    ///
+    /// After a failed delivery, the later events of the subscription wait for the next attempt, unless the event is
+    /// given up on.
+    async fn deliver(&self) -> anyhow::Result<()> {
+        let db = &self.state.db_storage;
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let mut failed = BTreeSet::new();
+        for PendingEvent {
+            event,
+            attempts,
+            retry_at,
+        } in db.select_undelivered_subscription_events().await?
+        {
+            if failed.contains(&event.subscription) {
+                continue;
+            }
+            if retry_at > now {
+                // backing off, the later events keep waiting as well
+                failed.insert(event.subscription);
+                continue;
+            }
+            let webhook = db
+                .select_subscriptions(Some(&event.subscription))
+                .await?
+                .pop()
+                .and_then(|subscription| subscription.webhook);
+            let Some(webhook) = webhook else {
+                // the webhook was removed since
+                db.record_delivered_subscription_event(event.seq).await?;
+                continue;
+            };
+
+            match self
+                .state
+                .client
+                .post(&webhook)
+                .json(&event)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+            {
+                Ok(_) => db.record_delivered_subscription_event(event.seq).await?,
+                Err(err) if attempts + 1 >= self.max_attempts => {
+                    log::warn!(
+                        "Failed to post event {} to the webhook of {} {} times, giving up: {err}",
+                        event.seq,
+                        event.subscription,
+                        attempts + 1
+                    );
+                    db.record_failed_subscription_event(event.seq, attempts + 1, None)
+                        .await?;
+                }
+                Err(err) => {
+                    let retry_at = now.saturating_add(backoff(self.interval, attempts + 1).as_secs() as i64);
+                    log::warn!(
+                        "Failed to post an event to the webhook of {}, retrying after {retry_at}: {err}",
+                        event.subscription
+                    );
+                    db.record_failed_subscription_event(event.seq, attempts + 1, Some(retry_at))
+                        .await?;
+                    failed.insert(event.subscription);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The time waited before retrying a delivery which failed a number of times, doubling with each attempt.
+fn backoff(interval: Duration, attempts: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+    interval.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn delivery_backoff() {
+        let interval = Duration::from_secs(60);
+        assert_eq!(backoff(interval, 1), interval);
+        assert_eq!(backoff(interval, 2), Duration::from_secs(120));
+        assert_eq!(backoff(interval, 4), Duration::from_secs(480));
+        assert_eq!(backoff(interval, 10), MAX_BACKOFF);
+        assert_eq!(backoff(interval, u32::MAX), MAX_BACKOFF);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::score::AdjustedScore;
 use std::collections::BTreeMap;
 use std::ops::Deref;
 use v11y_model::search::SearchDocument;
@@ -50,6 +51,10 @@ pub struct CveSearchDocument {
     pub related_advisories: usize,
     #[serde(default)]
     pub related_products: usize,
+
+    /// The score adjusted by the temporal and environmental metrics of this deployment, if there are any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adjusted_score: Option<AdjustedScore>,
 }
 
 impl Deref for CveSearchDocument {
@@ -0,0 +1,85 @@
+//! Asynchronous export jobs, producing large results which are downloaded once they are ready.
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+/// The kind of data to export.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportKind {
+    /// Packages matching a query, as CSV
+    Packages,
+    /// Advisories matching a query, as gzipped tarball of their documents
+    Advisories,
+}
+
+impl ExportKind {
+    /// The file name of the result, used when downloading it.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Self::Packages => "packages.csv",
+            Self::Advisories => "advisories.tar.gz",
+        }
+    }
+
+    /// The content type of the result.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Packages => "text/csv",
+            Self::Advisories => "application/gzip",
+        }
+    }
+}
+
+/// A request to start an export.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct ExportRequest {
+    pub kind: ExportKind,
+    /// The search query selecting the exported entries, exporting everything if empty
+    #[serde(default)]
+    pub query: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportStatus {
+    /// Waiting to be started
+    Pending,
+    /// Collecting the data of the export
+    Running,
+    /// The result is ready to be downloaded, until the job expires
+    Completed,
+    Failed,
+}
+
+impl ExportStatus {
+    /// Whether the job still occupies one of the jobs a user may run at the same time.
+    pub fn is_active(&self) -> bool {
+        matches!(self, Self::Pending | Self::Running)
+    }
+}
+
+/// The state of an export job.
+#[derive(Clone, Debug, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct ExportJob {
+    pub id: String,
+    pub kind: ExportKind,
+    pub query: String,
+    pub status: ExportStatus,
+    /// Number of entries exported so far
+    pub processed: u64,
+    /// Total number of entries to export, once known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    pub created: OffsetDateTime,
+    /// When the job and its result will be removed, set once the job has finished
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<OffsetDateTime>,
+    /// Size of the result in bytes, once completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Why the job failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
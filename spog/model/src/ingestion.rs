@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Report of cross-checking an SBOM of Bombastic against the graph of GUAC.
+///
+/// Vulnerabilities are correlated with SBOMs using the graph, so SBOMs or packages missing from it lead to missing
+/// vulnerabilities in the reports of the SBOMs.
+#[derive(Clone, Debug, Default, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct IngestionReport {
+    /// ID of the SBOM in Bombastic
+    pub id: String,
+    /// URI identifying the SBOM in GUAC, its SPDX document namespace or CycloneDX serial number
+    pub uri: String,
+    /// Whether GUAC has an SBOM with the URI for any of the packages described by the SBOM
+    pub sbom_ingested: bool,
+    /// The packages described by the SBOM, with the URIs of the SBOMs GUAC has for them
+    pub described: Vec<DescribedPackage>,
+    /// Number of packages with a Package URL in the SBOM
+    pub packages: usize,
+    /// Number of packages looked up in GUAC, less than all packages when sampling
+    pub checked: usize,
+    /// Share of the looked up packages known to GUAC, from `0.0` to `1.0`
+    pub coverage: f64,
+    /// Whether the SBOM and all checked packages are present in GUAC
+    pub complete: bool,
+    /// Package URLs of checked packages GUAC doesn't know
+    pub missing: Vec<String>,
+    /// Packages which could not be looked up, with the reason
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// A package described by an SBOM, like the product it is about.
+#[derive(Clone, Debug, Default, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct DescribedPackage {
+    pub purl: String,
+    /// URIs of the SBOMs GUAC has for the package
+    pub sboms: Vec<String>,
+}
+
+impl IngestionReport {
+    /// Create a report from the results of looking up packages, `Ok(true)` meaning GUAC knows a package.
+    pub fn new(
+        id: String,
+        uri: String,
+        described: Vec<DescribedPackage>,
+        packages: usize,
+        lookups: Vec<(String, Result<bool, String>)>,
+    ) -> Self {
+        let sbom_ingested = described.iter().any(|package| package.sboms.contains(&uri));
+        let checked = lookups.len();
+
+        let mut missing = Vec::new();
+        let mut errors = Vec::new();
+        for (purl, result) in lookups {
+            match result {
+                Ok(true) => {}
+                Ok(false) => missing.push(purl),
+                Err(err) => errors.push(format!("{purl}: {err}")),
+            }
+        }
+        missing.sort_unstable();
+        errors.sort_unstable();
+
+        let coverage = match checked - errors.len() {
+            0 => 0.0,
+            n => (n - missing.len()) as f64 / n as f64,
+        };
+
+        Self {
+            id,
+            complete: sbom_ingested && missing.is_empty() && errors.is_empty(),
+            uri,
+            sbom_ingested,
+            described,
+            packages,
+            checked,
+            coverage,
+            missing,
+            errors,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report() {
+        let described = vec![DescribedPackage {
+            purl: "pkg:oci/ubi9@sha256:abcd".to_string(),
+            sboms: vec!["https://example.com/ubi9".to_string()],
+        }];
+        let lookups = vec![
+            ("pkg:rpm/redhat/openssl@3.0.7".to_string(), Ok(true)),
+            ("pkg:rpm/redhat/zlib@1.2.11".to_string(), Ok(false)),
+            ("pkg:rpm/redhat/bash@5.1.8".to_string(), Ok(true)),
+            ("pkg:rpm/redhat/curl@7.76.1".to_string(), Err("timeout".to_string())),
+        ];
+        let report = IngestionReport::new(
+            "ubi9".to_string(),
+            "https://example.com/ubi9".to_string(),
+            described,
+            10,
+            lookups,
+        );
+
+        assert!(report.sbom_ingested);
+        assert!(!report.complete);
+        assert_eq!(report.checked, 4);
+        assert_eq!(report.missing, vec!["pkg:rpm/redhat/zlib@1.2.11"]);
+        assert_eq!(report.errors, vec!["pkg:rpm/redhat/curl@7.76.1: timeout"]);
+        assert_eq!(report.coverage, 2.0 / 3.0);
+    }
+
+    #[test]
+    fn not_ingested() {
+        let report = IngestionReport::new("empty".to_string(), "urn:uuid:1".to_string(), vec![], 0, vec![]);
+        assert!(!report.sbom_ingested);
+        assert!(!report.complete);
+        assert_eq!(report.coverage, 0.0);
+    }
+}
@@ -2,12 +2,22 @@ pub mod config;
 pub mod csaf;
 pub mod cve;
 pub mod dashboard;
+pub mod export;
+pub mod ingestion;
+pub mod ownership;
 pub mod package_info;
 pub mod pkg;
+pub mod posture;
+pub mod provenance;
+pub mod score;
 pub mod search;
+pub mod subscription;
 pub mod suggestion;
 pub mod vuln;
 
 pub mod prelude {
-    pub use crate::{config::*, cve::*, dashboard::*, package_info::*, pkg::*, search::*, suggestion::*, vuln::*};
+    pub use crate::{
+        config::*, cve::*, dashboard::*, export::*, ingestion::*, ownership::*, package_info::*, pkg::*, posture::*,
+        provenance::*, score::*, search::*, subscription::*, suggestion::*, vuln::*,
+    };
 }
@@ -0,0 +1,153 @@
+//! Ownership of packages by teams, CODEOWNERS-style.
+//!
+//! Rules map Package URL patterns to the team owning the matching packages. Like CODEOWNERS, a general rule can be
+//! refined by a more specific one: when several rules match a package, the one with the longest pattern wins.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A rule assigning the packages matching a pattern to a team.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[schema(example = json!(OwnershipRule {
+    pattern: "pkg:maven/io.quarkus/*".to_string(),
+    team: "quarkus".to_string(),
+}))]
+pub struct OwnershipRule {
+    /// Package URL pattern, where `*` matches any sequence of characters
+    pub pattern: String,
+    /// The team owning the matching packages
+    pub team: String,
+}
+
+impl OwnershipRule {
+    /// Whether the rule matches a Package URL.
+    pub fn matches(&self, purl: &str) -> bool {
+        matches_pattern(&self.pattern, purl)
+    }
+
+    /// The team owning a package: the one of the matching rule with the longest pattern.
+    pub fn owner<'a>(rules: impl IntoIterator<Item = &'a OwnershipRule>, purl: &str) -> Option<&'a str> {
+        rules
+            .into_iter()
+            .filter(|rule| rule.matches(purl))
+            .max_by_key(|rule| rule.pattern.len())
+            .map(|rule| rule.team.as_str())
+    }
+}
+
+/// Whether a Package URL matches a pattern, where `*` matches any sequence of characters.
+pub fn matches_pattern(pattern: &str, purl: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = purl.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // no wildcard
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Where the notifications of a team are sent to.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[schema(example = json!(TeamWebhook {
+    team: "quarkus".to_string(),
+    url: "https://hooks.example.com/services/quarkus".to_string(),
+    template: Some(
+        r#"{% set count = findings | length %}{% set text = count ~ " new vulnerabilities in " ~ sbom %}{"text": {{ text | json_encode() }}}"#
+            .to_string()
+    ),
+    content_type: None,
+}))]
+pub struct TeamWebhook {
+    /// The team, as referenced by ownership rules
+    pub team: String,
+    /// URL the notifications are posted to
+    pub url: String,
+    /// Tera template of the payload, rendering the fields of the notification. Without one, the notification is posted
+    /// as JSON. Tera doesn't escape the rendered values, so values inserted into JSON need the `json_encode()` filter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Content type of the rendered payload, defaults to `application/json`, which must be a valid header value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+/// A vulnerability affecting a package.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ToSchema, Serialize, Deserialize)]
+pub struct Finding {
+    /// The ID of the vulnerability
+    pub vulnerability: String,
+    /// The Package URL of the affected package
+    pub purl: String,
+}
+
+/// Notification of vulnerabilities newly found in the packages of a team, posted to the webhook of the team.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct OwnershipNotification {
+    /// The team owning the affected packages
+    pub team: String,
+    /// The ID of the SBOM the vulnerabilities were found in
+    pub sbom: String,
+    /// The new findings
+    pub findings: Vec<Finding>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rule(pattern: &str, team: &str) -> OwnershipRule {
+        OwnershipRule {
+            pattern: pattern.to_string(),
+            team: team.to_string(),
+        }
+    }
+
+    #[test]
+    fn matches() {
+        let quarkus = rule("pkg:maven/io.quarkus/*", "quarkus");
+        assert!(quarkus.matches("pkg:maven/io.quarkus/quarkus-core@3.2.0?type=jar"));
+        assert!(!quarkus.matches("pkg:maven/io.vertx/vertx-core@4.4.4?type=jar"));
+
+        let openssl = rule("pkg:rpm/redhat/openssl@*?arch=*", "crypto");
+        assert!(openssl.matches("pkg:rpm/redhat/openssl@1.1.1k-7.el8_6?arch=x86_64"));
+        assert!(!openssl.matches("pkg:rpm/redhat/openssl-libs@1.1.1k-7.el8_6?arch=x86_64"));
+        assert!(!openssl.matches("pkg:rpm/redhat/openssl@1.1.1k-7.el8_6"));
+
+        // without wildcards, only the exact Package URL matches
+        let exact = rule("pkg:npm/left-pad@1.3.0", "web");
+        assert!(exact.matches("pkg:npm/left-pad@1.3.0"));
+        assert!(!exact.matches("pkg:npm/left-pad@1.3.0-rc"));
+    }
+
+    #[test]
+    fn owner() {
+        let rules = [
+            rule("pkg:maven/*", "java"),
+            rule("pkg:maven/io.quarkus/*", "quarkus"),
+            rule("pkg:maven/io.quarkus/quarkus-vertx*", "vertx"),
+        ];
+        assert_eq!(
+            OwnershipRule::owner(&rules, "pkg:maven/io.quarkus/quarkus-core@3.2.0"),
+            Some("quarkus")
+        );
+        assert_eq!(
+            OwnershipRule::owner(&rules, "pkg:maven/io.quarkus/quarkus-vertx-http@3.2.0"),
+            Some("vertx")
+        );
+        assert_eq!(
+            OwnershipRule::owner(&rules, "pkg:maven/org.apache/commons-io@2.11.0"),
+            Some("java")
+        );
+        assert_eq!(OwnershipRule::owner(&rules, "pkg:npm/left-pad@1.3.0"), None);
+    }
+}
@@ -2,7 +2,7 @@ use packageurl::PackageUrl;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, ToSchema, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[schema(example = json!(PackageInfo {
     purl: "pkg:rpm/redhat/openssl@1.1.1k-7.el8_6".to_string(),
@@ -10,11 +10,19 @@ use utoipa::ToSchema;
         cve: "cve-2023-0286".into(),
         severity: "low".to_string()
     }],
+    repository: None,
+    scorecard: None,
 }))]
 pub struct PackageInfo {
     pub purl: String,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub vulnerabilities: Vec<V11yRef>,
+    /// The source repository of the package, like `github.com/ossf/scorecard`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    /// The OpenSSF Scorecard score of the source repository, from 0 to 10
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scorecard: Option<f64>,
 }
 
 impl PackageInfo {
@@ -0,0 +1,22 @@
+//! The vulnerability posture of SBOMs over time.
+
+use crate::vuln::SummaryEntry;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+/// The number of vulnerabilities by severity of an SBOM, at a point in time.
+#[derive(Clone, Debug, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct PostureSnapshot {
+    /// ID of the SBOM
+    pub sbom: String,
+    /// The SBOM name
+    pub name: String,
+    /// The SBOM version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// When the snapshot was taken
+    pub timestamp: OffsetDateTime,
+    /// Number of vulnerabilities by severity, using the base scores
+    pub summary: Vec<SummaryEntry>,
+}
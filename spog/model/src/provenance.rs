@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+/// Upstream package registry a component is checked against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Registry {
+    /// crates.io, for `pkg:cargo` components
+    Cargo,
+    /// The npm registry, for `pkg:npm` components
+    Npm,
+    /// PyPI, for `pkg:pypi` components
+    Pypi,
+    /// Maven Central, for `pkg:maven` components
+    Maven,
+}
+
+impl Registry {
+    /// The registry of a Package URL type, if supported.
+    pub fn from_purl_type(ty: &str) -> Option<Self> {
+        match ty {
+            "cargo" => Some(Self::Cargo),
+            "npm" => Some(Self::Npm),
+            "pypi" => Some(Self::Pypi),
+            "maven" => Some(Self::Maven),
+            _ => None,
+        }
+    }
+}
+
+/// Outcome of checking a component against its registry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProvenanceStatus {
+    /// The registry publishes the component, with a checksum matching the one of the SBOM
+    Verified,
+    /// The registry publishes the component, but there is no checksum to compare with
+    Unverified,
+    /// The registry publishes the component, with a checksum different from the one of the SBOM
+    Mismatch,
+    /// The registry doesn't know the component
+    Unknown,
+    /// The registry could not be queried
+    Error,
+}
+
+impl ProvenanceStatus {
+    /// Whether the component needs attention, as it might have been substituted (e.g. by dependency confusion).
+    pub fn is_flagged(&self) -> bool {
+        matches!(self, Self::Mismatch | Self::Unknown)
+    }
+}
+
+/// Provenance of a single component.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct ComponentProvenance {
+    /// Package URL of the component
+    pub purl: String,
+    pub registry: Registry,
+    pub status: ProvenanceStatus,
+    /// Details on mismatches and errors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+}
+
+/// Report of checking the components of an SBOM against their upstream package registries.
+#[derive(Clone, Debug, Default, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct ProvenanceReport {
+    /// Number of components with a Package URL of a supported registry
+    pub supported: usize,
+    /// Number of components checked, less than the supported ones when sampling
+    pub checked: usize,
+    /// Number of checked components by status
+    pub summary: BTreeMap<ProvenanceStatus, usize>,
+    /// The checked components, flagged components first
+    pub components: Vec<ComponentProvenance>,
+}
+
+impl ProvenanceReport {
+    pub fn new(supported: usize, mut components: Vec<ComponentProvenance>) -> Self {
+        let mut summary = BTreeMap::new();
+        for component in &components {
+            *summary.entry(component.status).or_default() += 1;
+        }
+        components.sort_by(|a, b| {
+            b.status
+                .is_flagged()
+                .cmp(&a.status.is_flagged())
+                .then_with(|| a.purl.cmp(&b.purl))
+        });
+
+        Self {
+            supported,
+            checked: components.len(),
+            summary,
+            components,
+        }
+    }
+
+    /// The components which need attention.
+    pub fn flagged(&self) -> impl Iterator<Item = &ComponentProvenance> {
+        self.components.iter().filter(|c| c.status.is_flagged())
+    }
+}
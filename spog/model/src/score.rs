@@ -0,0 +1,328 @@
+//! Adjustment of CVSS v3 base scores, using temporal and environmental metrics.
+//!
+//! The base score of a vulnerability doesn't consider the deployment it affects. Overrides store temporal and
+//! environmental metrics (the "modifiers") for a CVE, either for all products or a single one, and scores are
+//! adjusted by calculating the temporal or environmental score of the CVSS v3.1 specification.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+/// Temporal and environmental metrics for the CVSS score of a CVE.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[schema(example = json!(ScoreOverride {
+    cve: "CVE-2023-0286".to_string(),
+    product: None,
+    modifiers: "E:P/RL:O/MAV:L/CR:H".to_string(),
+    comment: Some("Only reachable from the host".to_string()),
+}))]
+pub struct ScoreOverride {
+    /// The CVE the override applies to
+    pub cve: String,
+    /// The product (SBOM ID) the override is limited to, applying to all products if missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product: Option<String>,
+    /// Temporal and environmental metrics, in CVSS v3 vector notation
+    pub modifiers: String,
+    /// Why the score was adjusted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl ScoreOverride {
+    /// Select the override for a product, preferring one specific to the product over one for all products.
+    pub fn select<'a>(
+        overrides: impl IntoIterator<Item = &'a ScoreOverride>,
+        cve: &str,
+        product: Option<&str>,
+    ) -> Option<&'a ScoreOverride> {
+        let mut result = None;
+        for o in overrides {
+            if !o.cve.eq_ignore_ascii_case(cve) {
+                continue;
+            }
+            match o.product.as_deref() {
+                None => result = result.or(Some(o)),
+                Some(p) if Some(p) == product => return Some(o),
+                Some(_) => {}
+            }
+        }
+        result
+    }
+
+    /// Adjust the score of a CVSS v3 base vector.
+    pub fn adjust(&self, vector: &str) -> Result<AdjustedScore, ScoreError> {
+        let base = Metrics::parse(
+            vector
+                .strip_prefix("CVSS:3.1/")
+                .or(vector.strip_prefix("CVSS:3.0/"))
+                .unwrap_or(vector),
+        )?;
+        validate_modifiers(&self.modifiers)?;
+        let modifiers = Metrics::parse(&self.modifiers)?;
+        Ok(AdjustedScore {
+            base: base_score(&base)? as f32,
+            score: adjusted_score(&base, &modifiers)? as f32,
+            modifiers: self.modifiers.clone(),
+            product: self.product.clone(),
+            comment: self.comment.clone(),
+        })
+    }
+}
+
+/// A score, adjusted by an override.
+#[derive(Clone, Debug, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct AdjustedScore {
+    /// The unadjusted base score
+    pub base: f32,
+    /// The adjusted score
+    pub score: f32,
+    /// The temporal and environmental metrics the score was adjusted with
+    pub modifiers: String,
+    /// The product the override is limited to, if it doesn't apply to all products
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product: Option<String>,
+    /// Why the score was adjusted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ScoreError {
+    #[error("invalid metric: {0}")]
+    InvalidMetric(String),
+    #[error("missing base metric: {0}")]
+    MissingBaseMetric(&'static str),
+    #[error("base metrics can't be overridden: {0}")]
+    BaseMetric(String),
+}
+
+const BASE_METRICS: &[&str] = &["AV", "AC", "PR", "UI", "S", "C", "I", "A"];
+
+/// Valid values of the temporal and environmental metrics.
+const MODIFIER_METRICS: &[(&str, &[&str])] = &[
+    ("E", &["X", "H", "F", "P", "U"]),
+    ("RL", &["X", "U", "W", "T", "O"]),
+    ("RC", &["X", "C", "R", "U"]),
+    ("CR", &["X", "H", "M", "L"]),
+    ("IR", &["X", "H", "M", "L"]),
+    ("AR", &["X", "H", "M", "L"]),
+    ("MAV", &["X", "N", "A", "L", "P"]),
+    ("MAC", &["X", "L", "H"]),
+    ("MPR", &["X", "N", "L", "H"]),
+    ("MUI", &["X", "N", "R"]),
+    ("MS", &["X", "U", "C"]),
+    ("MC", &["X", "H", "L", "N"]),
+    ("MI", &["X", "H", "L", "N"]),
+    ("MA", &["X", "H", "L", "N"]),
+];
+
+/// Check modifiers to be valid temporal and environmental metrics.
+pub fn validate_modifiers(modifiers: &str) -> Result<(), ScoreError> {
+    let metrics = Metrics::parse(modifiers)?;
+    for (metric, value) in &metrics.0 {
+        if BASE_METRICS.contains(&metric.as_str()) {
+            return Err(ScoreError::BaseMetric(metric.clone()));
+        }
+        match MODIFIER_METRICS.iter().find(|(name, _)| name == metric) {
+            Some((_, values)) if values.contains(&value.as_str()) => {}
+            _ => return Err(ScoreError::InvalidMetric(format!("{metric}:{value}"))),
+        }
+    }
+    Ok(())
+}
+
+/// Metrics of a vector, by their abbreviation.
+struct Metrics(BTreeMap<String, String>);
+
+impl Metrics {
+    fn parse(vector: &str) -> Result<Self, ScoreError> {
+        let mut metrics = BTreeMap::new();
+        for part in vector.split('/').filter(|part| !part.is_empty()) {
+            match part.split_once(':') {
+                Some((metric, value)) if !metric.is_empty() && !value.is_empty() => {
+                    metrics.insert(metric.to_string(), value.to_string());
+                }
+                _ => return Err(ScoreError::InvalidMetric(part.to_string())),
+            }
+        }
+        Ok(Self(metrics))
+    }
+
+    fn get(&self, metric: &str) -> Option<&str> {
+        self.0.get(metric).map(String::as_str).filter(|value| *value != "X")
+    }
+
+    fn base(&self, metric: &'static str) -> Result<&str, ScoreError> {
+        self.get(metric).ok_or(ScoreError::MissingBaseMetric(metric))
+    }
+}
+
+fn weight(metric: &str, value: &str, scope_changed: bool) -> Result<f64, ScoreError> {
+    Ok(match (metric, value) {
+        ("AV", "N") => 0.85,
+        ("AV", "A") => 0.62,
+        ("AV", "L") => 0.55,
+        ("AV", "P") => 0.2,
+        ("AC", "L") => 0.77,
+        ("AC", "H") => 0.44,
+        ("PR", "N") => 0.85,
+        ("PR", "L") if scope_changed => 0.68,
+        ("PR", "L") => 0.62,
+        ("PR", "H") if scope_changed => 0.5,
+        ("PR", "H") => 0.27,
+        ("UI", "N") => 0.85,
+        ("UI", "R") => 0.62,
+        ("C" | "I" | "A", "H") => 0.56,
+        ("C" | "I" | "A", "L") => 0.22,
+        ("C" | "I" | "A", "N") => 0.0,
+        ("E", "H") | ("RL", "U") | ("RC", "C") => 1.0,
+        ("E", "F") | ("RL", "W") => 0.97,
+        ("E", "P") => 0.94,
+        ("E", "U") => 0.91,
+        ("RL", "T") | ("RC", "R") => 0.96,
+        ("RL", "O") => 0.95,
+        ("RC", "U") => 0.92,
+        ("CR" | "IR" | "AR", "H") => 1.5,
+        ("CR" | "IR" | "AR", "M") => 1.0,
+        ("CR" | "IR" | "AR", "L") => 0.5,
+        _ => return Err(ScoreError::InvalidMetric(format!("{metric}:{value}"))),
+    })
+}
+
+/// Round up to one decimal, as defined by the CVSS v3.1 specification.
+fn roundup(value: f64) -> f64 {
+    let int = (value * 100_000.0).round() as i64;
+    if int % 10_000 == 0 {
+        int as f64 / 100_000.0
+    } else {
+        ((int / 10_000) + 1) as f64 / 10.0
+    }
+}
+
+fn base_score(base: &Metrics) -> Result<f64, ScoreError> {
+    let changed = base.base("S")? == "C";
+    let w = |metric: &'static str| -> Result<f64, ScoreError> { weight(metric, base.base(metric)?, changed) };
+
+    let iss = 1.0 - (1.0 - w("C")?) * (1.0 - w("I")?) * (1.0 - w("A")?);
+    let impact = match changed {
+        true => 7.52 * (iss - 0.029) - 3.25 * (iss - 0.02).powi(15),
+        false => 6.42 * iss,
+    };
+    let exploitability = 8.22 * w("AV")? * w("AC")? * w("PR")? * w("UI")?;
+
+    Ok(match (impact <= 0.0, changed) {
+        (true, _) => 0.0,
+        (false, true) => roundup(f64::min(1.08 * (impact + exploitability), 10.0)),
+        (false, false) => roundup(f64::min(impact + exploitability, 10.0)),
+    })
+}
+
+/// The environmental score if there are environmental metrics, the temporal score otherwise.
+fn adjusted_score(base: &Metrics, modifiers: &Metrics) -> Result<f64, ScoreError> {
+    let optional = |metric: &str| -> Result<f64, ScoreError> {
+        match modifiers.get(metric) {
+            Some(value) => weight(metric, value, false),
+            None => Ok(1.0),
+        }
+    };
+    let temporal = optional("E")? * optional("RL")? * optional("RC")?;
+
+    let environmental = modifiers
+        .0
+        .iter()
+        .any(|(metric, value)| value != "X" && !matches!(metric.as_str(), "E" | "RL" | "RC"));
+    if !environmental {
+        return Ok(roundup(base_score(base)? * temporal));
+    }
+
+    // modified base metrics, falling back to the base metrics
+    let modified = |metric: &'static str| -> Result<&str, ScoreError> {
+        match modifiers.get(&format!("M{metric}")) {
+            Some(value) => Ok(value),
+            None => base.base(metric),
+        }
+    };
+    let changed = modified("S")? == "C";
+    let w = |metric: &'static str| -> Result<f64, ScoreError> { weight(metric, modified(metric)?, changed) };
+
+    let miss = f64::min(
+        1.0 - (1.0 - optional("CR")? * w("C")?) * (1.0 - optional("IR")? * w("I")?) * (1.0 - optional("AR")? * w("A")?),
+        0.915,
+    );
+    let impact = match changed {
+        true => 7.52 * (miss - 0.029) - 3.25 * (miss * 0.9731 - 0.02).powi(13),
+        false => 6.42 * miss,
+    };
+    let exploitability = 8.22 * w("AV")? * w("AC")? * w("PR")? * w("UI")?;
+
+    Ok(match (impact <= 0.0, changed) {
+        (true, _) => 0.0,
+        (false, true) => roundup(roundup(f64::min(1.08 * (impact + exploitability), 10.0)) * temporal),
+        (false, false) => roundup(roundup(f64::min(impact + exploitability, 10.0)) * temporal),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CRITICAL: &str = "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H";
+
+    fn adjust(vector: &str, modifiers: &str) -> AdjustedScore {
+        ScoreOverride {
+            cve: "CVE-2023-0286".into(),
+            product: None,
+            modifiers: modifiers.into(),
+            comment: None,
+        }
+        .adjust(vector)
+        .unwrap()
+    }
+
+    #[test]
+    fn base() {
+        assert_eq!(adjust(CRITICAL, "").base, 9.8);
+        assert_eq!(adjust(CRITICAL, "").score, 9.8);
+        assert_eq!(adjust("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:C/C:H/I:H/A:H", "").base, 10.0);
+        assert_eq!(adjust("CVSS:3.0/AV:L/AC:H/PR:L/UI:R/S:U/C:L/I:N/A:N", "").base, 2.2);
+    }
+
+    #[test]
+    fn temporal() {
+        assert_eq!(adjust(CRITICAL, "E:U/RL:O/RC:C").score, 8.5);
+        assert_eq!(adjust(CRITICAL, "E:X").score, 9.8);
+    }
+
+    #[test]
+    fn environmental() {
+        assert_eq!(adjust(CRITICAL, "CR:L/IR:L/AR:L").score, 8.0);
+        assert_eq!(adjust(CRITICAL, "MAV:L/E:P").score, 7.9);
+        assert_eq!(adjust(CRITICAL, "MC:N/MI:N/MA:N").score, 0.0);
+    }
+
+    #[test]
+    fn validate() {
+        assert!(validate_modifiers("E:P/RL:O/MAV:L/CR:H").is_ok());
+        assert_eq!(validate_modifiers("AV:L"), Err(ScoreError::BaseMetric("AV".into())));
+        assert!(validate_modifiers("E:Z").is_err());
+        assert!(validate_modifiers("E").is_err());
+    }
+
+    #[test]
+    fn select() {
+        let o = |product: Option<&str>| ScoreOverride {
+            cve: "CVE-2023-0286".into(),
+            product: product.map(ToString::to_string),
+            modifiers: "E:U".into(),
+            comment: None,
+        };
+        let overrides = [o(None), o(Some("a"))];
+
+        let selected = ScoreOverride::select(&overrides, "cve-2023-0286", Some("a")).unwrap();
+        assert_eq!(selected.product.as_deref(), Some("a"));
+        let selected = ScoreOverride::select(&overrides, "CVE-2023-0286", Some("b")).unwrap();
+        assert_eq!(selected.product, None);
+        assert!(ScoreOverride::select(&overrides, "CVE-2023-0001", None).is_none());
+    }
+}
@@ -14,6 +14,9 @@ pub struct AdvisorySummary {
     pub cvss_max: Option<f64>,
     pub href: String,
     pub cve_severity_count: HashMap<String, u64>,
+    /// Vendors of the products of the advisory, or its publisher if it doesn't name any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vendors: Vec<String>,
 
     #[serde(default, skip_serializing_if = "Value::is_null", rename = "$metadata")]
     pub metadata: Value,
@@ -44,6 +47,14 @@ pub struct SbomSummary {
     pub description: String,
     pub supplier: String,
     pub dependencies: u64,
+    #[serde(default)]
+    pub ecosystems: HashMap<String, u64>,
+    /// External references of the SBOM, like its source repository
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub external_references: Vec<SbomReference>,
+    /// Labels added when indexing the SBOM, like `owner:team-a`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
     pub href: String,
     pub advisories: Option<u64>,
     pub created: OffsetDateTime,
@@ -52,6 +63,13 @@ pub struct SbomSummary {
     pub metadata: Value,
 }
 
+/// An external reference of an SBOM, like its version control repository.
+#[derive(utoipa::ToSchema, serde::Deserialize, serde::Serialize, Debug, PartialEq, Eq, Clone)]
+pub struct SbomReference {
+    pub category: String,
+    pub url: String,
+}
+
 impl SbomSummary {
     pub fn advisories_query(&self) -> Option<String> {
         let mut terms = Vec::new();
@@ -0,0 +1,169 @@
+//! Subscriptions to the changes of SBOMs.
+//!
+//! Instead of following the changes feed or the event bus, consumers register subscriptions filtering the indexed SBOMs
+//! by label, and by the Package URL and severity of their vulnerabilities. The matching changes are queued for each
+//! subscription, and posted to its webhook if it has one.
+
+use crate::ownership::matches_pattern;
+use crate::vuln::schema;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A subscription to the changes of the SBOMs matching its filters. Without filters, it matches all changes.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[schema(example = json!(Subscription {
+    id: "quarkus-critical".to_string(),
+    purl: Some("pkg:maven/io.quarkus/*".to_string()),
+    severity: Some(cvss::Severity::Critical),
+    label: None,
+    webhook: Some("https://hooks.example.com/services/quarkus".to_string()),
+}))]
+pub struct Subscription {
+    /// The ID of the subscription, chosen by the subscriber
+    pub id: String,
+    /// Package URL pattern of vulnerable packages, where `*` matches any sequence of characters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purl: Option<String>,
+    /// Minimum severity of vulnerabilities
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline, schema_with=schema::severity)]
+    pub severity: Option<cvss::Severity>,
+    /// Label of the SBOMs, like `product:rhel-9`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// URL the matching changes are posted to, in addition to queueing them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+}
+
+impl Subscription {
+    /// The findings of a changed SBOM matching the subscription, or `None` if the change doesn't match.
+    ///
+    /// With a Package URL pattern or a severity, only changes of SBOMs with matching findings match, carrying those.
+    /// Otherwise, all findings are kept.
+    pub fn matches(&self, labels: &[String], findings: &[SubscriptionFinding]) -> Option<Vec<SubscriptionFinding>> {
+        if let Some(label) = &self.label {
+            if !labels.contains(label) {
+                return None;
+            }
+        }
+        if self.purl.is_none() && self.severity.is_none() {
+            return Some(findings.to_vec());
+        }
+
+        let findings: Vec<_> = findings
+            .iter()
+            .filter(|finding| match &self.purl {
+                Some(pattern) => matches_pattern(pattern, &finding.purl),
+                None => true,
+            })
+            .filter(|finding| match self.severity {
+                Some(threshold) => finding.severity.is_some_and(|severity| severity >= threshold),
+                None => true,
+            })
+            .cloned()
+            .collect();
+        (!findings.is_empty()).then_some(findings)
+    }
+}
+
+/// A vulnerability affecting a package of a changed SBOM.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct SubscriptionFinding {
+    /// The ID of the vulnerability
+    pub vulnerability: String,
+    /// The Package URL of the affected package
+    pub purl: String,
+    /// The severity of the vulnerability, using its base score
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(inline, schema_with=schema::severity)]
+    pub severity: Option<cvss::Severity>,
+}
+
+/// A change of an SBOM, matching a subscription.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct SubscriptionEvent {
+    /// The position of the event in the queue of the subscription
+    pub seq: i64,
+    /// The ID of the subscription
+    pub subscription: String,
+    /// The ID of the SBOM
+    pub sbom: String,
+    /// SHA256 digest of the SBOM, as it was indexed
+    pub sha256: String,
+    /// The time the SBOM was indexed, in nanoseconds since the epoch
+    pub indexed_timestamp: i64,
+    /// The labels of the SBOM
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// The findings of the SBOM matching the subscription
+    pub findings: Vec<SubscriptionFinding>,
+}
+
+/// A page of the queue of a subscription.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct SubscriptionEvents {
+    /// The events queued after the requested cursor, oldest first
+    pub events: Vec<SubscriptionEvent>,
+    /// The cursor to request the next page with, which is the requested one if there were no events
+    pub next: i64,
+    /// Whether there are more events after this page
+    pub more: bool,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cvss::Severity;
+
+    fn finding(vulnerability: &str, purl: &str, severity: Option<Severity>) -> SubscriptionFinding {
+        SubscriptionFinding {
+            vulnerability: vulnerability.to_string(),
+            purl: purl.to_string(),
+            severity,
+        }
+    }
+
+    #[test]
+    fn subscription_matches() {
+        let labels = vec!["product:quarkus-3".to_string()];
+        let openssl = finding(
+            "CVE-2023-0286",
+            "pkg:rpm/redhat/openssl@1.1.1k-7.el8_6",
+            Some(Severity::High),
+        );
+        let vertx = finding(
+            "CVE-2023-44487",
+            "pkg:maven/io.vertx/vertx-core@4.4.4",
+            Some(Severity::Medium),
+        );
+        let unscored = finding("CVE-2023-1370", "pkg:maven/net.minidev/json-smart@2.4.8", None);
+        let findings = [openssl.clone(), vertx.clone(), unscored];
+
+        let mut subscription = Subscription {
+            id: "all".to_string(),
+            purl: None,
+            severity: None,
+            label: None,
+            webhook: None,
+        };
+        // without filters, all changes match, even of SBOMs without findings
+        assert_eq!(subscription.matches(&labels, &findings).map(|f| f.len()), Some(3));
+        assert_eq!(subscription.matches(&[], &[]), Some(vec![]));
+
+        subscription.label = Some("product:quarkus-3".to_string());
+        assert!(subscription.matches(&labels, &findings).is_some());
+        assert_eq!(subscription.matches(&[], &findings), None);
+
+        subscription.purl = Some("pkg:maven/io.vertx/*".to_string());
+        assert_eq!(subscription.matches(&labels, &findings), Some(vec![vertx]));
+        assert_eq!(subscription.matches(&labels, &[]), None);
+
+        subscription.severity = Some(Severity::High);
+        assert_eq!(subscription.matches(&labels, &findings), None);
+
+        // unscored vulnerabilities don't reach any threshold
+        subscription.purl = None;
+        assert_eq!(subscription.matches(&labels, &findings), Some(vec![openssl]));
+    }
+}
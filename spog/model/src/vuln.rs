@@ -1,4 +1,4 @@
-use super::pkg::PackageRef;
+use super::{pkg::PackageRef, score::AdjustedScore};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, HashMap};
@@ -64,6 +64,9 @@ pub struct SbomReport {
     /// Traces from the vulnerable PURL back to the SBOM root
     #[schema(schema_with=schema::backtraces)]
     pub backtraces: BTreeMap<String, BTreeSet<Backtrace>>,
+    /// Teams owning the affected packages, by PURL
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub owners: BTreeMap<String, String>,
 }
 
 impl SbomReport {
@@ -86,7 +89,7 @@ pub struct SummaryEntry {
     pub count: usize,
 }
 
-mod schema {
+pub(crate) mod schema {
     use crate::vuln::Backtrace;
     use cvss::Severity;
     use utoipa::openapi::schema::AdditionalProperties;
@@ -171,9 +174,16 @@ pub struct SbomReportVulnerability {
     /// A map listing the packages affected by this vulnerability, and the available remediations.
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub affected_packages: BTreeMap<String, Vec<Remediation>>,
+    /// The nearest version of affected packages without known vulnerabilities, by PURL of the affected package
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub recommended_version: BTreeMap<String, String>,
 
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub sources: HashMap<String, SourceDetails>,
+
+    /// The score adjusted by the temporal and environmental metrics of this deployment, if there are any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adjusted: Option<AdjustedScore>,
 }
 
 impl SbomReportVulnerability {
@@ -181,6 +191,14 @@ impl SbomReportVulnerability {
     pub fn score(&self, source: &str) -> Option<f32> {
         self.sources.get(source).and_then(|details| details.score)
     }
+
+    /// Get the adjusted score, falling back to the score of a specific source
+    pub fn adjusted_score(&self, source: &str) -> Option<f32> {
+        match &self.adjusted {
+            Some(adjusted) => Some(adjusted.score),
+            None => self.score(source),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, ToSchema, serde::Serialize, serde::Deserialize)]
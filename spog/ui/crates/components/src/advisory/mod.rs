@@ -15,7 +15,7 @@ use csaf::{
 use patternfly_yew::prelude::*;
 use spog_model::csaf::{ProductsCache, RelationshipsCache};
 use spog_model::prelude::*;
-use spog_ui_backend::{use_backend, Endpoint};
+use spog_ui_backend::{use_backend, ApplyAccessToken, Endpoint};
 use spog_ui_common::{
     components::Markdown,
     utils::{csaf::trace_product, time::date},
@@ -29,11 +29,17 @@ use url::Url;
 use yew::prelude::*;
 use yew_more_hooks::prelude::UseAsyncState;
 use yew_nested_router::components::Link;
+use yew_oauth2::hook::use_latest_access_token;
+
+/// Maximum number of advisories which can be downloaded as a bundle, must match the backend
+const MAX_BUNDLE_SIZE: usize = 100;
 
 #[derive(PartialEq, Properties, Clone)]
 pub struct AdvisoryEntry {
     summary: AdvisorySummary,
     url: Option<Url>,
+    selected: bool,
+    onselect: Callback<(String, bool)>,
 }
 
 #[derive(PartialEq, Properties)]
@@ -44,8 +50,10 @@ pub struct AdvisoryResultProperties {
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum Column {
+    Select,
     Id,
     Title,
+    Vendor,
     Severity,
     Revision,
     Download,
@@ -55,12 +63,22 @@ pub enum Column {
 impl TableEntryRenderer<Column> for AdvisoryEntry {
     fn render_cell(&self, context: CellContext<'_, Column>) -> Cell {
         match context.column {
+            Column::Select => {
+                let id = self.summary.id.clone();
+                html!(
+                    <Checkbox
+                        checked={self.selected}
+                        onchange={self.onselect.reform(move |state: CheckboxState| (id.clone(), state.into()))}
+                    />
+                )
+            }
             Column::Id => html!(
                 <Link<AppRoute>
                     to={AppRoute::Advisory(View::Content {id: self.summary.id.clone()})}
                 >{ self.summary.id.clone() }</Link<AppRoute>>
             ),
             Column::Title => html!(&self.summary.title),
+            Column::Vendor => html!(self.summary.vendors.join(", ")),
             Column::Severity => html!(
                 if let Some(severity) = self.summary.severity.clone() {
                     <Severity {severity} />
@@ -97,9 +115,21 @@ impl TableEntryRenderer<Column> for AdvisoryEntry {
 #[function_component(AdvisoryResult)]
 pub fn advisory_result(props: &AdvisoryResultProperties) -> Html {
     let backend = use_backend();
+    let access_token = use_latest_access_token();
 
     let data = use_state_eq(|| None);
 
+    // selected advisories, kept across result pages
+    let selected = use_state_eq(Vec::<String>::new);
+    let onselect = use_callback(selected.clone(), |(id, state): (String, bool), selected| {
+        let mut ids = (**selected).clone();
+        ids.retain(|i| i != &id);
+        if state {
+            ids.push(id);
+        }
+        selected.set(ids);
+    });
+
     if let UseAsyncState::Ready(Ok(val)) = &props.state {
         let response: Vec<_> = val
             .result
@@ -109,12 +139,26 @@ pub fn advisory_result(props: &AdvisoryResultProperties) -> Html {
                 AdvisoryEntry {
                     summary: summary.clone(),
                     url,
+                    selected: selected.contains(&summary.id),
+                    onselect: onselect.clone(),
                 }
             })
             .collect();
         data.set(Some(response));
     }
 
+    let ondownload = use_callback(
+        (selected.clone(), backend.clone(), access_token.clone()),
+        |_, (selected, backend, access_token)| {
+            if let Ok(mut href) = backend.join(Endpoint::Api, "/api/v1/advisory/bundle") {
+                href.query_pairs_mut().append_pair("ids", &selected.join(","));
+                let href = href.latest_access_token(access_token);
+                let _ = gloo_utils::window().location().set_href(href.as_str());
+            }
+        },
+    );
+    let onclear = use_callback(selected.clone(), |_, selected| selected.set(vec![]));
+
     let sortby: UseStateHandle<Option<TableHeaderSortBy<Column>>> = use_state_eq(|| None);
     let onsort = use_callback(
         (sortby.clone(), props.onsort.clone()),
@@ -129,6 +173,11 @@ pub fn advisory_result(props: &AdvisoryResultProperties) -> Html {
     let (entries, onexpand) = use_table_data(MemoizedTableModel::new(Rc::new((*data).clone().unwrap_or_default())));
 
     let header = vec![
+        yew::props!(TableColumnProperties<Column> {
+            index: Column::Select,
+            label: "",
+            width: ColumnWidth::FitContent
+        }),
         yew::props!(TableColumnProperties<Column> {
             index: Column::Id,
             label: "ID",
@@ -137,7 +186,12 @@ pub fn advisory_result(props: &AdvisoryResultProperties) -> Html {
         yew::props!(TableColumnProperties<Column> {
             index: Column::Title,
             label: "Title",
-            width: ColumnWidth::Percent(50)
+            width: ColumnWidth::Percent(40)
+        }),
+        yew::props!(TableColumnProperties<Column> {
+            index: Column::Vendor,
+            label: "Vendor",
+            width: ColumnWidth::Percent(10)
         }),
         yew::props!(TableColumnProperties<Column> {
             index: Column::Severity,
@@ -164,7 +218,37 @@ pub fn advisory_result(props: &AdvisoryResultProperties) -> Html {
         }),
     ];
 
-    html!(
+    let too_many = selected.len() > MAX_BUNDLE_SIZE;
+
+    html!(<>
+        if !selected.is_empty() {
+            <Toolbar>
+                <ToolbarContent>
+                    <ToolbarItem>
+                        <Button
+                            icon={Icon::Download}
+                            label={format!("Download selected ({})", selected.len())}
+                            variant={ButtonVariant::Secondary}
+                            disabled={too_many}
+                            onclick={ondownload}
+                        />
+                    </ToolbarItem>
+                    <ToolbarItem>
+                        <Button label="Clear selection" variant={ButtonVariant::Link} onclick={onclear} />
+                    </ToolbarItem>
+                    if too_many {
+                        <ToolbarItem>
+                            <Alert
+                                inline=true
+                                plain=true
+                                r#type={AlertType::Warning}
+                                title={format!("At most {MAX_BUNDLE_SIZE} advisories can be downloaded at once")}
+                            />
+                        </ToolbarItem>
+                    }
+                </ToolbarContent>
+            </Toolbar>
+        }
         <TableWrapper<Column, UseTableData<Column, MemoizedTableModel<AdvisoryEntry>>>
             loading={&props.state.is_processing()}
             error={props.state.error().cloned()}
@@ -177,7 +261,7 @@ pub fn advisory_result(props: &AdvisoryResultProperties) -> Html {
                 {onexpand}
             />
         </TableWrapper<Column, UseTableData<Column, MemoizedTableModel<AdvisoryEntry>>>>
-    )
+    </>)
 }
 
 pub fn cat_label(cat: &PublisherCategory) -> &'static str {
@@ -60,7 +60,11 @@ impl TableEntryRenderer<Column> for CveEntry {
             </>),
             Column::Severity => html!(
                 <>
-                    if let Some(score)= &self.cve.cvss3x_score {
+                    if let Some(adjusted) = &self.cve.adjusted_score {
+                        <CvssScore cvss={Cvss{score: adjusted.score}} />
+                        {" "}
+                        <Label compact=true color={Color::Blue} label="Adjusted" />
+                    } else if let Some(score)= &self.cve.cvss3x_score {
                         <CvssScore cvss={Cvss{score: (*score) as _}} />
                     }
                 </>
@@ -1,5 +1,6 @@
 mod search;
 
+use crate::common::ExternalLinkMarker;
 use crate::cvss::CvssMap;
 use crate::table_wrapper::TableWrapper;
 use packageurl::PackageUrl;
@@ -36,6 +37,7 @@ pub enum Column {
     PackageType,
     Qualifiers,
     Path,
+    Scorecard,
     Vulnerabilities,
 }
 
@@ -56,6 +58,15 @@ impl TableEntryRenderer<Column> for PackagesEntry {
                 html!({ for self.purl.qualifiers().iter().map(|(k,v)| html!(<Label label={format!("{k}={v}")} />)) })
                     .into()
             }
+            Column::Scorecard => match (&self.package.repository, self.package.scorecard) {
+                (Some(repository), Some(score)) => html!(
+                    <a href={format!("https://scorecard.dev/viewer/?uri={repository}")} target="_blank">
+                        { format!("{score:.1}") } <ExternalLinkMarker/>
+                    </a>
+                )
+                .into(),
+                _ => html!({ "N/A" }).into(),
+            },
             Column::Vulnerabilities => {
                 let l = self.summary.len();
                 if l == 0 {
@@ -133,7 +144,7 @@ pub fn package_result(props: &PackagesResultProperties) -> Html {
         yew::props!(TableColumnProperties<Column> {
             index: Column::Namespace,
             label: "Namespace",
-            width: ColumnWidth::Percent(20),
+            width: ColumnWidth::Percent(15),
         }),
         yew::props!(TableColumnProperties<Column> {
             index: Column::Version,
@@ -155,10 +166,15 @@ pub fn package_result(props: &PackagesResultProperties) -> Html {
             label: "Qualifiers",
             width: ColumnWidth::Percent(10),
         }),
+        yew::props!(TableColumnProperties<Column> {
+            index: Column::Scorecard,
+            label: "Scorecard",
+            width: ColumnWidth::Percent(10),
+        }),
         yew::props!(TableColumnProperties<Column> {
             index: Column::Vulnerabilities,
             label: "Vulnerabilities",
-            width: ColumnWidth::Percent(20),
+            width: ColumnWidth::Percent(15),
         }),
     ];
 
@@ -123,7 +123,15 @@ pub fn result_view(props: &ResultViewProperties) -> Html {
                             <Title>
                                 {props.id.clone()} { " "}
                                 if let UseAsyncState::Ready(Ok(Some(cve))) = &*cve_index_data {
-                                    if let Some(score) = &cve.document.cvss3x_score {
+                                    if let Some(adjusted) = &cve.adjusted_score {
+                                        {html!(<Cvss3LabelFromCvss cvss={Cvss {
+                                            score: adjusted.score
+                                        }}/>)}
+                                        {" "}
+                                        <Tooltip text={format!("Adjusted from {} using {}", adjusted.base, adjusted.modifiers)}>
+                                            <Label compact=true color={Color::Blue} label="Adjusted" />
+                                        </Tooltip>
+                                    } else if let Some(score) = &cve.document.cvss3x_score {
                                         {html!(<Cvss3LabelFromCvss cvss={Cvss {
                                             score: *score as f32
                                         }}/>)}
@@ -61,7 +61,13 @@ pub fn details(props: &DetailsProps) -> Html {
                 .into(),
                 Column::Cvss => html!(
                     <>
-                        if let Some(score) = self.vuln.score("mitre") {
+                        if let Some(adjusted) = &self.vuln.adjusted {
+                            <CvssScore cvss={adjusted.score} />
+                            {" "}
+                            <Tooltip text={format!("Adjusted from {} using {}", adjusted.base, adjusted.modifiers)}>
+                                <Label compact=true color={Color::Blue} label="Adjusted" />
+                            </Tooltip>
+                        } else if let Some(score) = self.vuln.score("mitre") {
                             <CvssScore cvss={score} />
                         }
                     </>
@@ -141,8 +147,8 @@ pub fn details(props: &DetailsProps) -> Html {
             let result = match sort_by.index {
                 Column::Cvss => a
                     .vuln
-                    .score("mitre")
-                    .partial_cmp(&b.vuln.score("mitre"))
+                    .adjusted_score("mitre")
+                    .partial_cmp(&b.vuln.adjusted_score("mitre"))
                     .unwrap_or(Ordering::Equal),
                 Column::AffectedPackages => a.vuln.affected_packages.len().cmp(&b.vuln.affected_packages.len()),
                 Column::Published => a.vuln.published.cmp(&b.vuln.published),
@@ -1,4 +1,5 @@
-use super::CommonHeader;
+use super::{quality::Quality, CommonHeader};
+use crate::model;
 use analytics_next::TrackingEvent;
 use patternfly_yew::prelude::*;
 use reqwest::Body;
@@ -6,14 +7,18 @@ use serde_json::json;
 use spog_ui_backend::{use_backend, AnalyzeService};
 use spog_ui_common::error::components::ApiError;
 use spog_ui_common::error::ApiErrorKind;
+use spog_ui_components::cyclonedx::CycloneDxPackages;
 use spog_ui_components::editor::ReadonlyEditor;
 use spog_ui_components::sbom::Report;
-use spog_ui_utils::analytics::use_analytics;
+use spog_ui_components::spdx::SpdxPackages;
+use spog_ui_utils::{analytics::use_analytics, tracking_event};
 use std::rc::Rc;
 use yew::prelude::*;
 use yew_more_hooks::hooks::r#async::*;
 use yew_oauth2::hook::use_latest_access_token;
 
+tracking_event!(RequestAnalysis: "Request SBOM scanner vulnerability analysis" => None);
+
 struct AnalyzeResult<'a>(&'a Result<Rc<String>, spog_ui_common::error::ApiError>);
 
 impl<'a> From<AnalyzeResult<'a>> for TrackingEvent<'static> {
@@ -47,68 +52,105 @@ pub struct InspectProperties {
     pub onreset: Callback<()>,
 }
 
+/// Inspect an SBOM.
+///
+/// The inventory and quality checks are processed locally, in the browser. The SBOM is only sent to the backend once
+/// the user requests a vulnerability analysis.
 #[function_component(Inspect)]
 pub fn inspect(props: &InspectProperties) -> Html {
     #[derive(Copy, Clone, Eq, PartialEq)]
     enum TabIndex {
+        Inventory,
+        Quality,
         Report,
         Raw,
     }
 
     let analytics = use_analytics();
-    let tab = use_state_eq(|| TabIndex::Report);
+    let tab = use_state_eq(|| TabIndex::Inventory);
     let onselect = use_callback(tab.clone(), |index, tab| tab.set(index));
 
+    let sbom = use_memo(props.raw.clone(), |raw| model::SBOM::parse((**raw).clone()));
+
     let backend = use_backend();
     let access_token = use_latest_access_token();
 
     let fetch = {
-        use_async_with_cloned_deps(
-            move |raw| async move {
+        let raw = props.raw.clone();
+        let analytics = analytics.clone();
+        use_async_with_options(
+            async move {
                 let service = AnalyzeService::new(backend, access_token);
                 let result = service.report(Body::from((*raw).clone())).await.map(Rc::new);
                 analytics.track(AnalyzeResult(&result));
                 result
             },
-            props.raw.clone(),
+            UseAsyncOptions::default(),
         )
     };
 
+    let onanalyze = {
+        let fetch = fetch.clone();
+        let tab = tab.clone();
+        Callback::from(move |()| {
+            analytics.track(RequestAnalysis);
+            tab.set(TabIndex::Report);
+            fetch.run();
+        })
+    };
+
     html!(
         <>
             <CommonHeader onreset={props.onreset.clone()}/>
-            {
-                match &*fetch {
-                    UseAsyncState::Pending | UseAsyncState::Processing => html!(
-                        <PageSection fill={PageSectionFill::Fill}>
-                            <Spinner />
-                        </PageSection>
-                    ),
-                    UseAsyncState::Ready(state) => html!(
-                        <>
-                            <PageSection r#type={PageSectionType::Tabs} variant={PageSectionVariant::Light} sticky={[PageSectionSticky::Top]}>
-                                <Tabs<TabIndex> inset={TabInset::Page} detached=true selected={*tab} {onselect}>
-                                    <Tab<TabIndex> index={TabIndex::Report} title="Report" />
-                                    <Tab<TabIndex> index={TabIndex::Raw} title="Raw SBOM"/>
-                                </Tabs<TabIndex>>
-                            </PageSection>
-
-                            <PageSection hidden={*tab != TabIndex::Report} variant={PageSectionVariant::Light} fill={PageSectionFill::Fill}>
-                                {
-                                    match state {
-                                        Ok(data) => html!(<Report data={data.clone()} />),
-                                        Err(err) => html!(<ApiError title="Failed to process report" error={err.clone()} />),
-                                    }
-                                }
-                            </PageSection>
-
-                            <PageSection hidden={*tab != TabIndex::Raw} variant={PageSectionVariant::Light} fill={PageSectionFill::Fill}>
-                                <ReadonlyEditor content={props.raw.clone()} />
-                           </PageSection>
-                        </>
-                    ),
+
+            <PageSection r#type={PageSectionType::Tabs} variant={PageSectionVariant::Light} sticky={[PageSectionSticky::Top]}>
+                <Tabs<TabIndex> inset={TabInset::Page} detached=true selected={*tab} {onselect}>
+                    <Tab<TabIndex> index={TabIndex::Inventory} title="Inventory" />
+                    <Tab<TabIndex> index={TabIndex::Quality} title="Quality" />
+                    <Tab<TabIndex> index={TabIndex::Report} title="Vulnerabilities" />
+                    <Tab<TabIndex> index={TabIndex::Raw} title="Raw SBOM"/>
+                </Tabs<TabIndex>>
+            </PageSection>
+
+            <PageSection hidden={*tab != TabIndex::Inventory} variant={PageSectionVariant::Light} fill={PageSectionFill::Fill}>
+                {
+                    match &*sbom {
+                        model::SBOM::SPDX { bom, .. } => html!(<SpdxPackages bom={bom.clone()} />),
+                        model::SBOM::CycloneDX { bom, .. } => html!(<CycloneDxPackages bom={bom.clone()} />),
+                        model::SBOM::Unknown(_) => html!(
+                            <Alert inline=true r#type={AlertType::Warning} title="Unable to list the packages of this SBOM" />
+                        ),
+                    }
                 }
-            }
+            </PageSection>
+
+            <PageSection hidden={*tab != TabIndex::Quality} variant={PageSectionVariant::Light} fill={PageSectionFill::Fill}>
+                <Quality raw={props.raw.clone()} />
+            </PageSection>
+
+            <PageSection hidden={*tab != TabIndex::Report} variant={PageSectionVariant::Light} fill={PageSectionFill::Fill}>
+                {
+                    match &*fetch {
+                        UseAsyncState::Pending => html!(
+                            <EmptyState
+                                title="Vulnerability analysis"
+                                icon={Icon::Search}
+                                size={Size::Large}
+                                primary={Action::new("Analyze vulnerabilities", onanalyze)}
+                            >
+                                { "Analyzing the vulnerabilities of the SBOM sends it to the Dependency Analytics service. Nothing was sent so far." }
+                            </EmptyState>
+                        ),
+                        UseAsyncState::Processing => html!(<Spinner />),
+                        UseAsyncState::Ready(Ok(data)) => html!(<Report data={data.clone()} />),
+                        UseAsyncState::Ready(Err(err)) => html!(<ApiError title="Failed to process report" error={err.clone()} />),
+                    }
+                }
+            </PageSection>
+
+            <PageSection hidden={*tab != TabIndex::Raw} variant={PageSectionVariant::Light} fill={PageSectionFill::Fill}>
+                <ReadonlyEditor content={props.raw.clone()} />
+            </PageSection>
         </>
     )
 }
@@ -1,4 +1,5 @@
 mod inspect;
+mod quality;
 // mod unknown;
 pub mod upload;
 
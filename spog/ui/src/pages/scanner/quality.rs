@@ -0,0 +1,96 @@
+use bombastic_model::lint::{lint, LintFinding, LintSeverity};
+use patternfly_yew::prelude::*;
+use spog_ui_common::use_apply_pagination;
+use std::rc::Rc;
+use yew::prelude::*;
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct QualityProperties {
+    pub raw: Rc<String>,
+}
+
+/// Quality checks of an SBOM, performed locally.
+#[function_component(Quality)]
+pub fn quality(props: &QualityProperties) -> Html {
+    #[derive(Clone, Eq, PartialEq)]
+    enum Column {
+        Severity,
+        Message,
+        Location,
+    }
+
+    impl TableEntryRenderer<Column> for LintFinding {
+        fn render_cell(&self, context: CellContext<'_, Column>) -> Cell {
+            match context.column {
+                Column::Severity => match self.severity {
+                    LintSeverity::Error => html!(<Label color={Color::Red} label="Error" />),
+                    LintSeverity::Warning => html!(<Label color={Color::Orange} label="Warning" />),
+                },
+                Column::Message => html!({ &self.message }),
+                Column::Location => html!(<code>{ self.location.clone().unwrap_or_default() }</code>),
+            }
+            .into()
+        }
+    }
+
+    let report = use_memo(props.raw.clone(), |raw| lint(raw.as_bytes()));
+    let findings = use_memo(report.clone(), |report| {
+        let mut findings = report.findings.clone();
+        // errors first, keeping the order of the document
+        findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+        findings
+    });
+
+    let errors = report.findings(LintSeverity::Error).count();
+    let warnings = report.findings(LintSeverity::Warning).count();
+
+    let total = findings.len();
+    let pagination = use_pagination(Some(total), Default::default);
+    let entries = use_apply_pagination(findings, pagination.control);
+    let (entries, onexpand) = use_table_data(MemoizedTableModel::new(entries));
+
+    let header = html_nested!(
+        <TableHeader<Column>>
+            <TableColumn<Column> width={ColumnWidth::Percent(10)} index={Column::Severity} label="Severity" />
+            <TableColumn<Column> width={ColumnWidth::Percent(50)} index={Column::Message} label="Finding" />
+            <TableColumn<Column> width={ColumnWidth::Percent(40)} index={Column::Location} label="Location" />
+        </TableHeader<Column>>
+    );
+
+    html!(
+        <>
+            <Content>
+                <p>
+                    { format!("{errors} error(s) and {warnings} warning(s) found") }
+                    if let Some(format) = &report.format {
+                        { format!(" in this {format} document") }
+                    }
+                    { ". The checks were performed in your browser, the SBOM was not uploaded." }
+                </p>
+            </Content>
+
+            if total > 0 {
+                <Toolbar>
+                    <ToolbarContent>
+                        <ToolbarItem r#type={ToolbarItemType::Pagination}>
+                            <SimplePagination pagination={pagination.clone()} {total} />
+                        </ToolbarItem>
+                    </ToolbarContent>
+                </Toolbar>
+
+                <Table<Column, UseTableData<Column, MemoizedTableModel<LintFinding>>>
+                    mode={TableMode::Compact}
+                    {header}
+                    {entries}
+                    {onexpand}
+                />
+
+                <SimplePagination
+                    {pagination}
+                    {total}
+                    position={PaginationPosition::Bottom}
+                />
+            }
+        </>
+    )
+}
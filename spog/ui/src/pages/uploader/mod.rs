@@ -1,6 +1,7 @@
 mod inspect;
 
-use crate::pages::scanner::{parse, validate};
+use crate::pages::scanner::parse;
+use bombastic_model::lint::{lint, LintReport, LintSeverity};
 use inspect::Inspect;
 use patternfly_yew::prelude::*;
 use spog_ui_components::upload_file::UploadFile;
@@ -9,6 +10,32 @@ use std::rc::Rc;
 use yew::prelude::*;
 use yew_more_hooks::prelude::*;
 
+/// Maximum number of lint findings to show
+const MAX_FINDINGS: usize = 10;
+
+/// Render the findings of a severity, or `None` if there are none.
+fn findings(report: &LintReport, severity: LintSeverity) -> Option<String> {
+    let total = report.findings(severity).count();
+    if total == 0 {
+        return None;
+    }
+
+    let mut result = report
+        .findings(severity)
+        .take(MAX_FINDINGS)
+        .map(|f| match &f.location {
+            Some(location) => format!("{} ({location})", f.message),
+            None => f.message.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    if total > MAX_FINDINGS {
+        result.push_str(&format!(", and {} more", total - MAX_FINDINGS));
+    }
+
+    Some(result)
+}
+
 const EMPTY_BODY_CONTENT: &str = r#"
 <div>
     <p>Start by <strong>dragging and dropping a file here</strong> or clicking the <strong>Load an SBOM</strong> button.</p>
@@ -27,18 +54,20 @@ pub fn sbom_uploader() -> Html {
     });
 
     let onvalidate = use_callback((), |data: Rc<String>, ()| {
-        let result = parse(data.as_bytes());
-        match result {
-            Ok(_sbom) => Ok(data),
-            Err(err) => Err(format!("Failed to parse SBOM: {err}")),
+        if let Err(err) = parse(data.as_bytes()) {
+            return Err(format!("Failed to parse SBOM: {err}"));
+        }
+        // same checks as performed by the validation API
+        match findings(&lint(data.as_bytes()), LintSeverity::Error) {
+            None => Ok(data),
+            Some(errors) => Err(format!("Invalid SBOM: {errors}")),
         }
     });
 
     let onvalidate_warnings = use_callback((), |data: Rc<String>, ()| {
-        let result = validate(data.as_bytes());
-        match result {
-            Ok(_sbom) => Ok(data),
-            Err(err) => Err(format!("Warning: {err}")),
+        match findings(&lint(data.as_bytes()), LintSeverity::Warning) {
+            None => Ok(data),
+            Some(warnings) => Err(format!("Warning: {warnings}")),
         }
     });
 
@@ -0,0 +1,74 @@
+use std::time::Duration;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+/// Storage class documents are archived to by default, readable without being restored first.
+pub const DEFAULT_ARCHIVE_CLASS: &str = "GLACIER_IR";
+
+/// Storage class of objects which are not archived.
+const STANDARD_CLASS: &str = "STANDARD";
+
+/// Storage classes whose objects need to be restored before they can be read, which can't be read transparently.
+const RESTORE_CLASSES: [&str; 2] = ["GLACIER", "DEEP_ARCHIVE"];
+
+/// When and where documents are archived.
+#[derive(Clone, Debug)]
+pub(crate) struct ArchivePolicy {
+    /// Documents not stored for this long are archived
+    pub(crate) after: Duration,
+    /// Storage class of archived documents
+    pub(crate) class: String,
+}
+
+impl ArchivePolicy {
+    pub(crate) fn new(after: Duration, class: Option<String>) -> Result<Self, crate::Error> {
+        let class = class.unwrap_or_else(|| DEFAULT_ARCHIVE_CLASS.to_string());
+        if RESTORE_CLASSES.contains(&class.as_str()) {
+            return Err(crate::Error::UnsupportedStorageClass(class));
+        }
+        Ok(Self { after, class })
+    }
+
+    /// Whether an object last modified at a time (RFC 3339) and stored using a storage class is due to be archived.
+    ///
+    /// Objects with an invalid modification time are left alone.
+    pub(crate) fn is_due(&self, now: OffsetDateTime, last_modified: &str, class: Option<&str>) -> bool {
+        if class == Some(self.class.as_str()) {
+            return false;
+        }
+        match OffsetDateTime::parse(last_modified, &Rfc3339) {
+            Ok(modified) => now - modified >= self.after,
+            Err(_) => false,
+        }
+    }
+}
+
+/// Whether an object of a storage class is archived, objects without a storage class being stored in the standard one.
+pub(crate) fn is_archived(class: Option<&str>) -> bool {
+    class.is_some_and(|class| class != STANDARD_CLASS)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn due() {
+        let policy = ArchivePolicy::new(Duration::from_secs(30 * 24 * 60 * 60), None).unwrap();
+        let now = OffsetDateTime::parse("2024-03-01T00:00:00Z", &Rfc3339).unwrap();
+
+        assert!(policy.is_due(now, "2024-01-01T12:00:00.000Z", Some("STANDARD")));
+        assert!(policy.is_due(now, "2024-01-01T12:00:00.000Z", None));
+        assert!(!policy.is_due(now, "2024-01-01T12:00:00.000Z", Some(DEFAULT_ARCHIVE_CLASS)));
+        assert!(!policy.is_due(now, "2024-02-15T12:00:00.000Z", Some("STANDARD")));
+        assert!(!policy.is_due(now, "yesterday", Some("STANDARD")));
+
+        assert!(ArchivePolicy::new(Duration::ZERO, Some("DEEP_ARCHIVE".into())).is_err());
+    }
+
+    #[test]
+    fn archived() {
+        assert!(!is_archived(None));
+        assert!(!is_archived(Some("STANDARD")));
+        assert!(is_archived(Some("GLACIER_IR")));
+    }
+}
@@ -0,0 +1,126 @@
+//! Content addressed storage of documents.
+//!
+//! Documents are stored once under the SHA-256 digest of their decoded content, as blobs. The object of a document
+//! identifier is an empty alias, whose metadata refers to the digest of its blob, so that identical documents stored
+//! under different identifiers share a blob.
+//!
+//! Each object referring to a blob, an alias or a prior revision of one, is recorded by an empty reference object next
+//! to the blob. Once the last of them is gone, the blob is removed.
+
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime};
+
+/// Metadata of an alias, referring to the digest of its blob.
+const DIGEST: &str = "digest";
+
+const DIGEST_PREFIX: &str = "sha256:";
+
+/// How long a reference counts even if its object doesn't refer to the blob (yet), as it's stored before the alias.
+const PENDING: Duration = Duration::hours(1);
+
+/// Hashes a document while it's streamed, into its digest.
+#[derive(Clone, Default)]
+pub(crate) struct Hasher(Arc<Mutex<Sha256>>);
+
+impl Hasher {
+    pub(crate) fn update(&self, data: &[u8]) {
+        if let Ok(mut hash) = self.0.lock() {
+            hash.update(data);
+        }
+    }
+
+    /// The digest of the data hashed so far, like `sha256:<hex>`.
+    pub(crate) fn digest(&self) -> Option<String> {
+        let hash = self.0.lock().ok()?.clone().finalize();
+        Some(format!("{DIGEST_PREFIX}{}", hex::encode(hash)))
+    }
+}
+
+/// The hex encoded SHA-256 hash of a digest, `None` if it isn't a valid SHA-256 digest.
+pub(crate) fn parse_digest(digest: &str) -> Option<&str> {
+    let hash = digest.strip_prefix(DIGEST_PREFIX)?;
+    (hash.len() == 64 && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))).then_some(hash)
+}
+
+/// The name of the metadata header referring to the blob of an alias.
+pub(crate) fn digest_header() -> String {
+    format!("x-amz-meta-{DIGEST}")
+}
+
+/// The digest of the blob an object is an alias of, `None` if it's a document on its own.
+pub(crate) fn alias_of(metadata: &HashMap<String, String>) -> Option<&str> {
+    // depending on the backend, keys may be returned with different casing
+    metadata
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(DIGEST))
+        .map(|(_, value)| value.as_str())
+        .filter(|digest| parse_digest(digest).is_some())
+}
+
+/// Whether a reference last modified at a time (RFC 3339) may be for an alias being stored.
+///
+/// References with an invalid modification time are pending, so that the blob is kept.
+pub(crate) fn is_pending(now: OffsetDateTime, last_modified: &str) -> bool {
+    match OffsetDateTime::parse(last_modified, &Rfc3339) {
+        Ok(modified) => now - modified < PENDING,
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn digest(data: &[u8]) -> String {
+        let hasher = Hasher::default();
+        hasher.update(data);
+        hasher.digest().unwrap()
+    }
+
+    #[test]
+    fn test_digest() {
+        let digest = digest(b"{}");
+        assert_eq!(
+            digest,
+            "sha256:44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+        );
+        assert_eq!(parse_digest(&digest), Some(&digest[7..]));
+
+        // hashed in chunks, as streamed
+        let hasher = Hasher::default();
+        hasher.update(b"{");
+        hasher.update(b"}");
+        assert_eq!(hasher.digest(), Some(digest.clone()));
+
+        assert_eq!(
+            parse_digest("44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"),
+            None
+        );
+        assert_eq!(parse_digest("sha256:44136fa3"), None);
+        assert_eq!(
+            parse_digest("sha256:44136FA355B3678A1146AD16F7E8649E94FB4FC21FE77E8310C060F61CAAFF8A"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_alias() {
+        let digest = digest(b"{}");
+        let metadata = HashMap::from([("Digest".to_string(), digest.clone())]);
+        assert_eq!(alias_of(&metadata), Some(digest.as_str()));
+
+        assert_eq!(alias_of(&HashMap::new()), None);
+        let metadata = HashMap::from([("digest".to_string(), "md5:99914b932bd37a50b983c5e7c90ae93b".to_string())]);
+        assert_eq!(alias_of(&metadata), None);
+    }
+
+    #[test]
+    fn test_pending() {
+        let now = OffsetDateTime::parse("2024-03-01T12:00:00Z", &Rfc3339).unwrap();
+        assert!(is_pending(now, "2024-03-01T11:30:00.000Z"));
+        assert!(!is_pending(now, "2024-03-01T10:00:00.000Z"));
+        assert!(is_pending(now, "yesterday"));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::Provenance;
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+
+/// A variant of a document, as stored from one of its sources.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Variant {
+    /// SHA-256 digest of the variant, hex encoded
+    pub digest: String,
+    /// Version the document declares, like the tracking version of an advisory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Whether this variant is the one stored under the identifier of the document
+    pub current: bool,
+    /// Who stored the variant, from where and when
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+impl Variant {
+    pub fn new(data: &[u8], version: Option<String>, provenance: Option<Provenance>) -> Self {
+        Self {
+            digest: digest(data),
+            version,
+            current: false,
+            provenance,
+        }
+    }
+
+    /// The origin the variant was retrieved from: the host of its source URL, or how it was ingested if it has none.
+    pub fn origin(&self) -> Option<String> {
+        origin(self.provenance.as_ref())
+    }
+}
+
+/// Variants of a document stored under the same identifier with different content, by different sources.
+///
+/// The variants are kept until an operator adjudicates the conflict, deciding which variant is stored.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Conflict {
+    /// Identifier of the document
+    pub id: String,
+    /// When the conflict was detected
+    #[serde(with = "time::serde::rfc3339")]
+    pub detected: OffsetDateTime,
+    /// The variants, in the order they were stored
+    pub variants: Vec<Variant>,
+}
+
+impl Conflict {
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            detected: OffsetDateTime::now_utc(),
+            variants: Vec::new(),
+        }
+    }
+
+    pub fn variant(&self, digest: &str) -> Option<&Variant> {
+        self.variants.iter().find(|v| v.digest == digest)
+    }
+
+    /// Add a variant, replacing the variant with the same digest or an earlier variant from the same origin.
+    ///
+    /// Returns the digests of the replaced variants, whose documents are no longer needed.
+    pub fn add(&mut self, variant: Variant) -> Vec<String> {
+        let origin = variant.origin();
+        let mut replaced = Vec::new();
+        self.variants.retain(|v| {
+            let keep = v.digest != variant.digest && v.origin() != origin;
+            if !keep && v.digest != variant.digest {
+                replaced.push(v.digest.clone());
+            }
+            keep
+        });
+        self.variants.push(variant);
+        replaced
+    }
+
+    /// Mark the variant stored under the identifier of the document.
+    pub fn set_current(&mut self, digest: &str) {
+        for variant in &mut self.variants {
+            variant.current = variant.digest == digest;
+        }
+    }
+
+    /// Whether there still are variants from different origins.
+    pub fn is_conflicting(&self) -> bool {
+        self.variants.len() > 1
+    }
+}
+
+/// SHA-256 digest of a document, hex encoded.
+fn digest(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// The origin a document was retrieved from: the host of its source URL, or how it was ingested if it has none.
+///
+/// Documents from the same origin replace each other, while documents from different origins are conflicting.
+fn origin(provenance: Option<&Provenance>) -> Option<String> {
+    let provenance = provenance?;
+    let host = provenance.source_url.as_deref().and_then(|url| {
+        let url = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+        let host = url.split(['/', '?', '#']).next().unwrap_or_default();
+        // drop user information
+        let host = host.rsplit_once('@').map(|(_, host)| host).unwrap_or(host);
+        (!host.is_empty()).then(|| host.to_ascii_lowercase())
+    });
+    Some(host.unwrap_or_else(|| provenance.source.as_str().to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Source;
+
+    fn variant(data: &str, source_url: Option<&str>) -> Variant {
+        Variant::new(
+            data.as_bytes(),
+            Some("1".to_string()),
+            Some(Provenance::new(Source::Walker, None, source_url)),
+        )
+    }
+
+    #[test]
+    fn test_origin() {
+        let origin = |source, url| origin(Some(&Provenance::new(source, None, url)));
+        assert_eq!(
+            origin(
+                Source::Walker,
+                Some("https://Access.redhat.com/security/data/csaf/v2/advisories/")
+            ),
+            Some("access.redhat.com".to_string())
+        );
+        assert_eq!(
+            origin(Source::Walker, Some("https://user@example.com:8443?q")),
+            Some("example.com:8443".to_string())
+        );
+        assert_eq!(origin(Source::Api, None), Some("api".to_string()));
+        assert_eq!(super::origin(None), None);
+    }
+
+    #[test]
+    fn test_add() {
+        let mut conflict = Conflict::new("RHSA-2023:1441");
+        assert!(conflict
+            .add(variant("a", Some("https://a.example.com/csaf/")))
+            .is_empty());
+        assert!(!conflict.is_conflicting());
+        assert!(conflict
+            .add(variant("b", Some("https://b.example.com/csaf/")))
+            .is_empty());
+        assert!(conflict.is_conflicting());
+
+        // the same document again
+        assert!(conflict
+            .add(variant("b", Some("https://b.example.com/csaf/")))
+            .is_empty());
+        assert_eq!(conflict.variants.len(), 2);
+
+        // an update from the same origin
+        assert_eq!(
+            conflict.add(variant("c", Some("https://a.example.com/csaf/"))),
+            vec![digest(b"a")]
+        );
+        assert_eq!(conflict.variants.len(), 2);
+
+        conflict.set_current(&digest(b"c"));
+        assert!(conflict.variant(&digest(b"c")).unwrap().current);
+        assert!(!conflict.variant(&digest(b"b")).unwrap().current);
+    }
+}
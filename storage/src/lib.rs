@@ -1,32 +1,57 @@
+mod archive;
+mod blob;
+mod conflict;
 mod key;
+mod provenance;
+mod revision;
 mod stream;
+mod tombstone;
+mod upload;
 pub mod validator;
+mod walker;
 
+pub use archive::DEFAULT_ARCHIVE_CLASS;
+pub use conflict::*;
 pub use key::*;
+pub use provenance::*;
+pub use revision::*;
+pub use tombstone::*;
+pub use upload::*;
+pub use walker::*;
 
 use async_stream::try_stream;
 use bytes::Bytes;
 use bytesize::ByteSize;
 use futures::pin_mut;
-use futures::{future::ok, stream::once, Stream, StreamExt};
+use futures::{future::ok, stream::once, Stream, StreamExt, TryStreamExt};
 use hide::Hide;
-use http::{header::CONTENT_ENCODING, HeaderValue, StatusCode};
+use http::{header::CONTENT_ENCODING, HeaderName, HeaderValue, StatusCode};
 use prometheus::{
     histogram_opts, opts, register_histogram_with_registry, register_int_counter_with_registry, Histogram, IntCounter,
     Registry,
 };
-use s3::{creds::error::CredentialsError, error::S3Error, Bucket};
+use s3::{
+    creds::error::CredentialsError,
+    error::S3Error,
+    serde_types::{HeadObjectResult, Part},
+    Bucket,
+};
 pub use s3::{creds::Credentials, Region};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 use urlencoding::decode;
 use validator::Validator;
 
+use crate::archive::{is_archived, ArchivePolicy};
+
 pub struct Storage {
     bucket: Bucket,
     metrics: Metrics,
     validator: Validator,
     max_size: ByteSize,
+    archive: Option<ArchivePolicy>,
+    content_addressable: bool,
 }
 
 #[derive(Clone)]
@@ -147,6 +172,22 @@ pub struct StorageConfig {
     /// Maximum document size
     #[arg(long, default_value_t = ByteSize::gb(1))]
     pub max_size: ByteSize,
+
+    /// Archive documents which were not stored again for this long, moving them to the archive storage class
+    #[arg(env = "STORAGE_ARCHIVE_AFTER", long = "storage-archive-after")]
+    pub archive_after: Option<humantime::Duration>,
+
+    /// Storage class of archived documents (default: GLACIER_IR), which must be readable without restoring objects
+    #[arg(env = "STORAGE_ARCHIVE_CLASS", long = "storage-archive-class")]
+    pub archive_class: Option<String>,
+
+    /// Store documents once under the SHA-256 digest of their content, their identifiers being aliases of the digest
+    #[arg(
+        env = "STORAGE_CONTENT_ADDRESSABLE",
+        long = "storage-content-addressable",
+        default_value_t = false
+    )]
+    pub content_addressable: bool,
 }
 
 impl TryInto<Bucket> for StorageConfig {
@@ -234,6 +275,8 @@ pub enum Error {
     Encoding(String),
     #[error("Prometheus error {0}")]
     Prometheus(prometheus::Error),
+    #[error("unsupported archive storage class {0}, objects must be readable without being restored")]
+    UnsupportedStorageClass(String),
 }
 
 impl From<CredentialsError> for Error {
@@ -288,6 +331,18 @@ impl From<prometheus::Error> for Error {
 
 const DATA_PATH: &str = "/data/";
 const INDEX_PATH: &str = "/index";
+const INDEX_DELTAS_PATH: &str = "/index-deltas/";
+const ORIGINAL_PATH: &str = "/original/";
+const WALKER_RUNS_PATH: &str = "/walker-runs/";
+const STATS_PATH: &str = "/stats/";
+const UPLOADS_PATH: &str = "/uploads/";
+const TOMBSTONES_PATH: &str = "/tombstones/";
+const REVISIONS_PATH: &str = "/revisions/";
+const CONFLICTS_PATH: &str = "/conflicts/";
+const VARIANTS_PATH: &str = "/variants/";
+const BLOBS_PATH: &str = "/blobs/sha256/";
+const BLOB_REFS_PATH: &str = "/blobs/refs/";
+const BLOB_STAGING_PATH: &str = "/blobs/staging/";
 const VERSION_HEADER: &str = "x-amz-meta-version";
 const VERSION: u32 = 1;
 const DEFAULT_ENCODING: &str = "zstd";
@@ -295,18 +350,34 @@ const DEFAULT_ENCODING: &str = "zstd";
 pub struct Head {
     pub status: StatusCode,
     pub content_encoding: Option<String>,
+    pub provenance: Option<Provenance>,
+    /// Whether the object was moved to an archive storage class
+    pub archived: bool,
+    /// The entity tag of the content, as reported by the object storage
+    pub etag: Option<String>,
+    /// When the object was last modified, as HTTP date
+    pub last_modified: Option<String>,
+    /// The length of the content as it is stored, so before decoding it
+    pub content_length: Option<u64>,
 }
 
 impl Storage {
     pub fn new(config: StorageConfig, registry: &Registry) -> Result<Self, Error> {
         let validator = config.validator.clone();
         let max_size = config.max_size;
+        let content_addressable = config.content_addressable;
+        let archive = match config.archive_after {
+            Some(after) => Some(ArchivePolicy::new(after.into(), config.archive_class.clone())?),
+            None => None,
+        };
         let bucket = config.try_into()?;
         Ok(Self {
             bucket,
             metrics: Metrics::register(registry)?,
             validator,
             max_size,
+            archive,
+            content_addressable,
         })
     }
 
@@ -314,6 +385,43 @@ impl Storage {
         format!("/{}", key).starts_with(INDEX_PATH)
     }
 
+    pub fn is_original(&self, key: &str) -> bool {
+        format!("/{}", key).starts_with(ORIGINAL_PATH)
+    }
+
+    pub fn is_walker_run(&self, key: &str) -> bool {
+        format!("/{}", key).starts_with(WALKER_RUNS_PATH)
+    }
+
+    pub fn is_stats(&self, key: &str) -> bool {
+        format!("/{}", key).starts_with(STATS_PATH)
+    }
+
+    pub fn is_upload(&self, key: &str) -> bool {
+        format!("/{}", key).starts_with(UPLOADS_PATH)
+    }
+
+    pub fn is_tombstone(&self, key: &str) -> bool {
+        format!("/{}", key).starts_with(TOMBSTONES_PATH)
+    }
+
+    pub fn is_revision(&self, key: &str) -> bool {
+        format!("/{}", key).starts_with(REVISIONS_PATH)
+    }
+
+    /// Whether the key is a content addressed blob, stored once for all the documents with the same content, or a
+    /// reference to one.
+    pub fn is_blob(&self, key: &str) -> bool {
+        let key = format!("/{}", key);
+        key.starts_with(BLOBS_PATH) || key.starts_with(BLOB_REFS_PATH) || key.starts_with(BLOB_STAGING_PATH)
+    }
+
+    /// Whether the key is a conflict record, or a variant kept by one.
+    pub fn is_conflict(&self, key: &str) -> bool {
+        let key = format!("/{}", key);
+        key.starts_with(CONFLICTS_PATH) || key.starts_with(VARIANTS_PATH)
+    }
+
     pub fn key_from_event(record: &Record) -> Result<(Cow<str>, String), Error> {
         if let Ok(decoded) = decode(record.key()) {
             let key = decoded
@@ -327,13 +435,27 @@ impl Storage {
         }
     }
 
+    /// An event of storing the document in this bucket, see [`StorageEvent::put`].
+    pub fn put_event(&self, key: &str) -> StorageEvent {
+        StorageEvent::put(&self.bucket.name, key)
+    }
+
+    /// An event of deleting the document from this bucket, see [`StorageEvent::delete`].
+    pub fn delete_event(&self, key: &str) -> StorageEvent {
+        StorageEvent::delete(&self.bucket.name, key)
+    }
+
     pub async fn put_stream<'a>(
         &self,
         key: Key<'a>,
         content_type: &'a str,
         encoding: Option<&str>,
+        provenance: &Provenance,
         data: impl Stream<Item = Result<Bytes, Error>>,
     ) -> Result<usize, Error> {
+        if self.content_addressable {
+            return self.put_addressed(key, content_type, encoding, provenance, data).await;
+        }
         self.metrics.puts_total.inc();
         let put_start = self.metrics.put_latency_seconds.start_timer();
         let mut headers = http::HeaderMap::new();
@@ -342,6 +464,7 @@ impl Storage {
             CONTENT_ENCODING,
             HeaderValue::from_str(encoding.unwrap_or(DEFAULT_ENCODING))?,
         );
+        provenance.insert_headers(&mut headers)?;
         let bucket = self.bucket.with_extra_headers(headers);
 
         let data = self.validator.validate(self.max_size, encoding, Box::pin(data)).await?;
@@ -360,39 +483,290 @@ impl Storage {
         Ok(len)
     }
 
-    pub async fn put_json_slice<'a>(&self, key: Key<'a>, json: &'a [u8]) -> Result<usize, Error> {
+    /// Store a document as a blob under the digest of its decoded content, unless the same content was stored before,
+    /// and its key as an alias of the blob.
+    ///
+    /// Returns the size of the blob.
+    async fn put_addressed<'a>(
+        &self,
+        key: Key<'a>,
+        content_type: &'a str,
+        encoding: Option<&str>,
+        provenance: &Provenance,
+        data: impl Stream<Item = Result<Bytes, Error>>,
+    ) -> Result<usize, Error> {
+        self.metrics.puts_total.inc();
+        let put_start = self.metrics.put_latency_seconds.start_timer();
+        let res = async {
+            let data = self.validator.validate(self.max_size, encoding, Box::pin(data)).await?;
+            self.store_addressed(key, content_type, encoding, Some(provenance), data)
+                .await
+        }
+        .await;
+        if res.is_err() {
+            self.metrics.puts_failed_total.inc();
+        }
+        put_start.observe_duration();
+        res
+    }
+
+    /// Store a document by its digest, without validating it.
+    ///
+    /// The document is hashed while it's streamed to a staging object, which is then moved to its blob.
+    async fn store_addressed<'a>(
+        &self,
+        key: Key<'a>,
+        content_type: &str,
+        encoding: Option<&str>,
+        provenance: Option<&Provenance>,
+        data: stream::ObjectStream<'a>,
+    ) -> Result<usize, Error> {
+        // the digest is the one of the decoded content, so that the encoding doesn't tell documents apart
+        let hasher = blob::Hasher::default();
+        let decoded = stream::decode(encoding, data)?.inspect_ok({
+            let hasher = hasher.clone();
+            move |chunk| hasher.update(chunk)
+        });
+        let mut rdr = stream::encoded_reader(DEFAULT_ENCODING, None, decoded)?;
+        let mut headers = http::HeaderMap::new();
+        headers.insert(VERSION_HEADER, VERSION.into());
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static(DEFAULT_ENCODING));
+        let staging = format!("{}{}", BLOB_STAGING_PATH, uuid::Uuid::new_v4());
+        let staged = self
+            .bucket
+            .with_extra_headers(headers)
+            .put_object_stream_with_content_type(&mut rdr, &staging, content_type)
+            .await?
+            .uploaded_bytes();
+        drop(rdr);
+
+        let res = async {
+            let digest = hasher.digest().ok_or(Error::Internal)?;
+            let path = S3Path::from_digest(&digest).ok_or(Error::Internal)?.path;
+            let alias = format!("{}{}", DATA_PATH, key);
+            let previous = self.alias_of(&alias).await?;
+            // referenced before looking for the blob, so that it's not removed along with another alias meanwhile
+            self.bucket.put_object(blob_ref(&digest, &alias)?, &[]).await?;
+
+            let len = match self.bucket.head_object(&path).await.map_err(Error::from) {
+                Ok((head, _status)) => {
+                    log::debug!("Content of {key} is already stored as {digest}");
+                    head.content_length.unwrap_or_default().max(0) as usize
+                }
+                Err(Error::NotFound) => {
+                    self.bucket.copy_object_internal(&staging, &path).await?;
+                    staged
+                }
+                Err(e) => return Err(e),
+            };
+
+            let mut headers = http::HeaderMap::new();
+            headers.insert(VERSION_HEADER, VERSION.into());
+            headers.insert(
+                HeaderName::from_bytes(blob::digest_header().as_bytes())?,
+                HeaderValue::from_str(&digest)?,
+            );
+            if let Some(provenance) = provenance {
+                provenance.insert_headers(&mut headers)?;
+            }
+            self.bucket
+                .with_extra_headers(headers)
+                .put_object_with_content_type(&alias, &[], content_type)
+                .await?;
+            if let Some(previous) = previous.filter(|previous| *previous != digest) {
+                self.release_blob(&previous, &alias).await?;
+            }
+            Ok::<_, Error>(len)
+        }
+        .await;
+        self.bucket.delete_object(&staging).await?;
+        res
+    }
+
+    /// The digest of the blob an object is an alias of, `None` if it's missing or a document on its own.
+    async fn alias_of(&self, object: &str) -> Result<Option<String>, Error> {
+        match self.bucket.head_object(object).await.map_err(Error::from) {
+            Ok((head, _status)) => Ok(head.metadata.as_ref().and_then(blob::alias_of).map(ToString::to_string)),
+            Err(Error::NotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The references to a blob, as the key of the reference, the path of the referring object and the time the
+    /// reference was stored.
+    async fn blob_refs(&self, digest: &str) -> Result<Vec<(String, String, String)>, Error> {
+        let hash = blob::parse_digest(digest).ok_or(Error::Internal)?;
+        let prefix = format!("{}{hash}", &BLOB_REFS_PATH[1..]);
+        let results = self.bucket.list(format!("{prefix}/"), None).await?;
+        Ok(results
+            .into_iter()
+            .flat_map(|result| result.contents)
+            .filter_map(|obj| {
+                let object = obj.key.strip_prefix(&prefix)?.to_string();
+                Some((obj.key, object, obj.last_modified))
+            })
+            .collect())
+    }
+
+    /// Drop the reference of an object to a blob, removing the blob if no other object refers to it anymore.
+    async fn release_blob(&self, digest: &str, object: &str) -> Result<(), Error> {
+        self.bucket.delete_object(blob_ref(digest, object)?).await?;
+        let now = OffsetDateTime::now_utc();
+        for (reference, object, last_modified) in self.blob_refs(digest).await? {
+            if blob::is_pending(now, &last_modified) || self.alias_of(&object).await?.as_deref() == Some(digest) {
+                return Ok(());
+            }
+            // the object was overwritten or deleted without dropping its reference
+            self.bucket.delete_object(reference).await?;
+        }
+        log::debug!("Removing blob {digest}, which isn't referred to anymore");
+        let path = S3Path::from_digest(digest).ok_or(Error::Internal)?;
+        self.bucket.delete_object(path.path).await?;
+        Ok(())
+    }
+
+    /// The path of the blob of a digest, if a stored document has that content.
+    ///
+    /// Blobs are only read by their digest through a document referring to them, so that deleted documents can't be
+    /// read by their digest.
+    pub async fn resolve_digest(&self, digest: &str) -> Result<S3Path, Error> {
+        let path = S3Path::from_digest(digest).ok_or(Error::NotFound)?;
+        for (_, object, _) in self.blob_refs(digest).await? {
+            if !object.starts_with(DATA_PATH) {
+                // prior revisions don't make their content readable by digest
+                continue;
+            }
+            let key = S3Path::from_path(&object).key().to_string();
+            if self.alias_of(&object).await?.as_deref() == Some(digest)
+                && self.get_tombstone(Key::from(&key)).await?.is_none()
+            {
+                return Ok(path);
+            }
+        }
+        Err(Error::NotFound)
+    }
+
+    pub async fn put_json_slice<'a>(
+        &self,
+        key: Key<'a>,
+        provenance: &Provenance,
+        json: &'a [u8],
+    ) -> Result<usize, Error> {
         let stream = once(ok::<_, Error>(Bytes::copy_from_slice(json)));
-        self.put_stream(key, "application/json", None, stream).await
+        self.put_stream(key, "application/json", None, provenance, stream).await
+    }
+
+    /// Validate a JSON document like when storing it, without storing it.
+    pub async fn validate_json(&self, json: &[u8]) -> Result<(), Error> {
+        let stream = once(ok::<_, Error>(Bytes::copy_from_slice(json)));
+        self.validator.validate(self.max_size, None, Box::pin(stream)).await?;
+        Ok(())
+    }
+
+    /// Store the original document, when the stored document had to be converted to be processed.
+    pub async fn put_original<'a>(&self, key: Key<'a>, content_type: &str, data: &[u8]) -> Result<(), Error> {
+        self.metrics.puts_total.inc();
+        let path = format!("{}{}", ORIGINAL_PATH, key);
+        self.bucket
+            .put_object_with_content_type(path, data, content_type)
+            .await
+            .map_err(|e| {
+                self.metrics.puts_failed_total.inc();
+                e
+            })?;
+        Ok(())
+    }
+
+    /// Get the original document and its content type, as stored by [`Storage::put_original`].
+    pub async fn get_original(&self, key: Key<'_>) -> Result<(Option<String>, Vec<u8>), Error> {
+        self.metrics.gets_total.inc();
+        let path = format!("{}{}", ORIGINAL_PATH, key);
+        let res = async {
+            let (head, _status) = self.bucket.head_object(&path).await?;
+            let data = self.bucket.get_object(&path).await?;
+            Ok::<_, Error>((head.content_type, data.to_vec()))
+        }
+        .await;
+        if res.is_err() {
+            self.metrics.gets_failed_total.inc();
+        }
+        res
+    }
+
+    /// Delete the original document, which is outdated once the document is stored again without one.
+    pub async fn delete_original(&self, key: Key<'_>) -> Result<(), Error> {
+        // there may be no original, which is not an error when deleting
+        self.bucket.delete_object(format!("{}{}", ORIGINAL_PATH, key)).await?;
+        Ok(())
     }
 
     pub async fn get_head(&self, path: S3Path) -> Result<Head, Error> {
         let (head, status) = self.bucket.head_object(&path.path).await?;
+        let provenance = head.metadata.as_ref().and_then(Provenance::from_metadata);
+        let archived = is_archived(head.storage_class.as_deref());
+        // an alias is modified when storing a document, even if its content was stored before
+        let last_modified = head.last_modified.clone();
+        let (_, content) = self.resolve(&path, head).await?;
         Ok(Head {
             status: StatusCode::from_u16(status).map_err(|_| Error::Internal)?,
-            content_encoding: head.content_encoding,
+            provenance,
+            archived,
+            content_encoding: content.content_encoding,
+            etag: content.e_tag,
+            last_modified,
+            content_length: content.content_length.and_then(|len| u64::try_from(len).ok()),
         })
     }
 
+    /// The path and head of the content of an object: the blob it's an alias of, or the object itself.
+    async fn resolve(&self, path: &S3Path, head: HeadObjectResult) -> Result<(S3Path, HeadObjectResult), Error> {
+        let Some(path) = head
+            .metadata
+            .as_ref()
+            .and_then(blob::alias_of)
+            .and_then(S3Path::from_digest)
+        else {
+            return Ok((path.clone(), head));
+        };
+        let (head, _status) = self.bucket.head_object(&path.path).await?;
+        Ok((path, head))
+    }
+
+    /// Get the provenance of an object, `None` if it was stored without.
+    pub async fn get_provenance(&self, path: &S3Path) -> Result<Option<Provenance>, Error> {
+        let (head, _status) = self.bucket.head_object(&path.path).await?;
+        Ok(head.metadata.as_ref().and_then(Provenance::from_metadata))
+    }
+
     // Get the data associated with an event record.
     // This will load the entire S3 object into memory
     pub async fn get_for_event(&self, record: &Record, decode: bool) -> Result<S3Result, Error> {
         // Record keys are URL encoded
         if let Ok((decoded, key)) = Self::key_from_event(record) {
             let path: S3Path = S3Path::from_path(&decoded);
+            let (head, _status) = self.bucket.head_object(&decoded).await?;
+            let provenance = head.metadata.as_ref().and_then(Provenance::from_metadata);
+            let archived = is_archived(head.storage_class.as_deref());
+            let (path, head) = self.resolve(&path, head).await?;
             if decode {
-                let data = self.get_decoded_object(&path).await?;
+                // the head is known already, no need to request it again for decoding
+                let stream = self.decode_content(path, head.content_encoding.as_deref()).await;
+                let data = self.get_object_from_stream(stream?).await?;
                 Ok(S3Result {
                     key,
                     data,
                     encoding: None,
+                    provenance,
+                    archived,
                 })
             } else {
-                let (head, _status) = self.bucket.head_object(&decoded).await?;
                 let data = self.get_encoded_object(path).await?;
                 Ok(S3Result {
                     key,
                     data,
                     encoding: head.content_encoding,
+                    provenance,
+                    archived,
                 })
             }
         } else {
@@ -457,6 +831,568 @@ impl Storage {
         Ok(data.to_vec())
     }
 
+    /// List the names of all published index snapshots, including the ones of partitions.
+    pub async fn list_indexes(&self) -> Result<Vec<String>, Error> {
+        let prefix = format!("{}/", &INDEX_PATH[1..]);
+        let results = self.bucket.list(prefix.clone(), None).await?;
+        Ok(results
+            .into_iter()
+            .flat_map(|result| result.contents)
+            .filter_map(|obj| obj.key.strip_prefix(&prefix).map(ToString::to_string))
+            .filter(|name| !name.is_empty())
+            .collect())
+    }
+
+    /// Store a delta snapshot of an index, the `sequence`th one on top of the full snapshot identified by `base`.
+    pub async fn put_index_delta(&self, name: &str, base: &str, sequence: u64, data: &[u8]) -> Result<(), Error> {
+        self.bucket
+            .put_object(format!("{}{}/{}/{}", INDEX_DELTAS_PATH, name, base, sequence), data)
+            .await?;
+        self.metrics.index_puts_total.inc();
+        Ok(())
+    }
+
+    /// Get a delta snapshot stored with [`Self::put_index_delta`].
+    pub async fn get_index_delta(&self, name: &str, base: &str, sequence: u64) -> Result<Vec<u8>, Error> {
+        let data = self
+            .bucket
+            .get_object(format!("{}{}/{}/{}", INDEX_DELTAS_PATH, name, base, sequence))
+            .await?;
+        Ok(data.to_vec())
+    }
+
+    /// Remove all delta snapshots of an index, once a full snapshot replaced them.
+    pub async fn delete_index_deltas(&self, name: &str) -> Result<(), Error> {
+        let prefix = format!("{}{}/", &INDEX_DELTAS_PATH[1..], name);
+        let manifest = format!("{}manifest.json", prefix);
+        for result in self.bucket.list(prefix, None).await? {
+            for obj in result.contents.into_iter().filter(|obj| obj.key != manifest) {
+                self.bucket.delete_object(obj.key).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Store the manifest of the latest snapshot of an index, describing its delta snapshots.
+    pub async fn put_index_manifest(&self, name: &str, data: &[u8]) -> Result<(), Error> {
+        self.bucket
+            .put_object(format!("{}{}/manifest.json", INDEX_DELTAS_PATH, name), data)
+            .await?;
+        Ok(())
+    }
+
+    /// Get the manifest stored with [`Self::put_index_manifest`], `None` if there is none.
+    pub async fn get_index_manifest(&self, name: &str) -> Result<Option<Vec<u8>>, Error> {
+        match self
+            .bucket
+            .get_object(format!("{}{}/manifest.json", INDEX_DELTAS_PATH, name))
+            .await
+        {
+            Ok(data) => Ok(Some(data.to_vec())),
+            Err(S3Error::HttpFailWithBody(404, _)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Remove the manifest of an index, so that its full snapshot is synced, like after restoring it.
+    pub async fn delete_index_manifest(&self, name: &str) -> Result<(), Error> {
+        self.bucket
+            .delete_object(format!("{}{}/manifest.json", INDEX_DELTAS_PATH, name))
+            .await?;
+        Ok(())
+    }
+
+    /// Store an object at a path outside of the paths managed by this storage, like a backup.
+    pub async fn put_object(&self, path: &str, data: &[u8]) -> Result<(), Error> {
+        self.bucket.put_object(path, data).await?;
+        Ok(())
+    }
+
+    /// Store an object at a path outside of the paths managed by this storage, reading it from a file or the like,
+    /// so that it never has to be held in memory.
+    ///
+    /// Returns the number of stored bytes.
+    pub async fn put_object_stream<R: tokio::io::AsyncRead + Unpin>(
+        &self,
+        path: &str,
+        content_type: &str,
+        data: &mut R,
+    ) -> Result<usize, Error> {
+        let len = self
+            .bucket
+            .put_object_stream_with_content_type(data, path, content_type)
+            .await?
+            .uploaded_bytes();
+        Ok(len)
+    }
+
+    /// Get an object stored with [`Self::put_object`].
+    pub async fn get_object(&self, path: &str) -> Result<Vec<u8>, Error> {
+        let data = self.bucket.get_object(path).await?;
+        Ok(data.to_vec())
+    }
+
+    /// List the paths of the objects stored with [`Self::put_object`] below a prefix.
+    pub async fn list_objects(&self, prefix: &str) -> Result<Vec<String>, Error> {
+        let results = self.bucket.list(prefix.to_string(), None).await?;
+        Ok(results
+            .into_iter()
+            .flat_map(|result| result.contents)
+            .map(|obj| obj.key)
+            .collect())
+    }
+
+    /// Delete an object stored with [`Self::put_object`].
+    pub async fn delete_object(&self, path: &str) -> Result<(), Error> {
+        self.bucket.delete_object(path).await?;
+        Ok(())
+    }
+
+    /// Store statistics, like the ones of ingested documents.
+    pub async fn put_stats(&self, name: &str, data: &[u8]) -> Result<(), Error> {
+        self.bucket.put_object(format!("{}{}", STATS_PATH, name), data).await?;
+        Ok(())
+    }
+
+    /// Get previously stored statistics, `None` if there are none.
+    pub async fn get_stats(&self, name: &str) -> Result<Option<Vec<u8>>, Error> {
+        match self.bucket.get_object(format!("{}{}", STATS_PATH, name)).await {
+            Ok(data) => Ok(Some(data.to_vec())),
+            Err(S3Error::HttpFailWithBody(404, _)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Store the report of a walker run, only keeping the reports of the latest `retain` runs.
+    pub async fn put_walker_run(&self, run: &WalkerRun, retain: usize) -> Result<(), Error> {
+        let data = serde_json::to_vec(run).map_err(|_| Error::InvalidContent)?;
+        self.bucket
+            .put_object(format!("{}{}", WALKER_RUNS_PATH, run.name()), &data)
+            .await?;
+
+        let names = self.list_walker_runs().await?;
+        for name in names.iter().rev().skip(retain.max(1)) {
+            self.bucket
+                .delete_object(format!("{}{}", WALKER_RUNS_PATH, name))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Start uploading a document in parts.
+    ///
+    /// Unlike [`Self::put_stream`], parts are stored as they are sent: the document isn't validated nor encoded, so
+    /// that it never has to be held in memory. If the storage is content addressable, parts are staged, and the
+    /// completed document is then stored by its digest.
+    pub async fn start_upload(
+        &self,
+        id: &str,
+        content_type: &str,
+        encoding: Option<&str>,
+        provenance: &Provenance,
+    ) -> Result<Upload, Error> {
+        let mut headers = http::HeaderMap::new();
+        headers.insert(VERSION_HEADER, VERSION.into());
+        if let Some(encoding) = encoding {
+            headers.insert(CONTENT_ENCODING, HeaderValue::from_str(encoding)?);
+        }
+        provenance.insert_headers(&mut headers)?;
+        let mut upload = Upload {
+            id: id.to_string(),
+            upload_id: String::new(),
+            content_type: content_type.to_string(),
+            encoding: encoding.map(ToString::to_string),
+            started: time::OffsetDateTime::now_utc(),
+            staging: self
+                .content_addressable
+                .then(|| format!("{}{}", BLOB_STAGING_PATH, uuid::Uuid::new_v4())),
+        };
+        let response = self
+            .bucket
+            .with_extra_headers(headers)
+            .initiate_multipart_upload(&upload.path(), content_type)
+            .await?;
+        upload.upload_id = response.upload_id;
+        Ok(upload)
+    }
+
+    /// Store a part of an upload, replacing the part of the same number if it was already stored.
+    pub async fn put_upload_part(&self, upload: &Upload, part: u32, data: Vec<u8>) -> Result<UploadPart, Error> {
+        self.metrics.puts_total.inc();
+        let stored = self
+            .bucket
+            .put_multipart_chunk(data, &upload.path(), part, &upload.upload_id, &upload.content_type)
+            .await
+            .map_err(|e| {
+                self.metrics.puts_failed_total.inc();
+                e
+            })?;
+        Ok(UploadPart {
+            part: stored.part_number,
+            etag: stored.etag,
+        })
+    }
+
+    /// Complete an upload, storing the document made of its parts, in the order of their numbers.
+    pub async fn complete_upload(&self, upload: &Upload, mut parts: Vec<UploadPart>) -> Result<(), Error> {
+        parts.sort_by_key(|part| part.part);
+        let parts = parts
+            .into_iter()
+            .map(|part| Part {
+                part_number: part.part,
+                etag: part.etag,
+            })
+            .collect();
+        let path = upload.path();
+        self.bucket
+            .complete_multipart_upload(&path, &upload.upload_id, parts)
+            .await?;
+        if upload.staging.is_none() {
+            return Ok(());
+        }
+
+        // stored by its digest like any other document, still without validating it
+        let res = async {
+            let provenance = self.get_provenance(&S3Path::from_path(&path)).await?;
+            let data = self.get_content_stream(S3Path::from_path(&path)).await?;
+            self.store_addressed(
+                Key::from(&upload.id),
+                &upload.content_type,
+                upload.encoding.as_deref(),
+                provenance.as_ref(),
+                Box::pin(data),
+            )
+            .await
+        }
+        .await;
+        self.bucket.delete_object(&path).await?;
+        res.map(|_| ())
+    }
+
+    /// Abort an upload, dropping its stored parts.
+    pub async fn abort_upload(&self, upload: &Upload) -> Result<(), Error> {
+        self.bucket.abort_upload(&upload.path(), &upload.upload_id).await?;
+        Ok(())
+    }
+
+    /// Store an upload in progress, so that it can be resumed from its token.
+    pub async fn put_upload(&self, token: &str, upload: &Upload) -> Result<(), Error> {
+        let data = serde_json::to_vec(upload).map_err(|_| Error::InvalidContent)?;
+        self.bucket
+            .put_object(format!("{}{}", UPLOADS_PATH, token), &data)
+            .await?;
+        Ok(())
+    }
+
+    /// Get an upload in progress by its token, `None` if it was completed, aborted or never started.
+    pub async fn get_upload(&self, token: &str) -> Result<Option<Upload>, Error> {
+        match self.bucket.get_object(format!("{}{}", UPLOADS_PATH, token)).await {
+            Ok(data) => Ok(Some(
+                serde_json::from_slice(&data.to_vec()).map_err(|_| Error::InvalidContent)?,
+            )),
+            Err(S3Error::HttpFailWithBody(404, _)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Forget an upload, once completed or aborted.
+    pub async fn delete_upload(&self, token: &str) -> Result<(), Error> {
+        self.bucket.delete_object(format!("{}{}", UPLOADS_PATH, token)).await?;
+        Ok(())
+    }
+
+    /// Store the tombstone of a deleted document.
+    pub async fn put_tombstone(&self, tombstone: &Tombstone) -> Result<(), Error> {
+        let data = serde_json::to_vec(tombstone).map_err(|_| Error::InvalidContent)?;
+        self.bucket
+            .put_object(format!("{}{}", TOMBSTONES_PATH, Key::from(&tombstone.id)), &data)
+            .await?;
+        Ok(())
+    }
+
+    /// Get the tombstone of a document, `None` if the document was never deleted, or stored again since.
+    pub async fn get_tombstone(&self, key: Key<'_>) -> Result<Option<Tombstone>, Error> {
+        match self.bucket.get_object(format!("{}{}", TOMBSTONES_PATH, key)).await {
+            Ok(data) => Ok(Some(
+                serde_json::from_slice(&data.to_vec()).map_err(|_| Error::InvalidContent)?,
+            )),
+            Err(S3Error::HttpFailWithBody(404, _)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Remove the tombstone of a document, if any, when it's stored again.
+    pub async fn delete_tombstone(&self, key: Key<'_>) -> Result<(), Error> {
+        self.bucket.delete_object(format!("{}{}", TOMBSTONES_PATH, key)).await?;
+        Ok(())
+    }
+
+    /// Store the conflict record of a document.
+    pub async fn put_conflict(&self, conflict: &Conflict) -> Result<(), Error> {
+        let data = serde_json::to_vec(conflict).map_err(|_| Error::InvalidContent)?;
+        self.bucket
+            .put_object(format!("{}{}", CONFLICTS_PATH, Key::from(&conflict.id)), &data)
+            .await?;
+        Ok(())
+    }
+
+    /// Get the conflict record of a document, `None` if it has no conflicting variants.
+    pub async fn get_conflict(&self, key: Key<'_>) -> Result<Option<Conflict>, Error> {
+        match self.bucket.get_object(format!("{}{}", CONFLICTS_PATH, key)).await {
+            Ok(data) => Ok(Some(
+                serde_json::from_slice(&data.to_vec()).map_err(|_| Error::InvalidContent)?,
+            )),
+            Err(S3Error::HttpFailWithBody(404, _)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Get the conflict records of all documents, ordered by their identifier.
+    pub async fn list_conflicts(&self) -> Result<Vec<Conflict>, Error> {
+        let results = self.bucket.list(CONFLICTS_PATH[1..].to_string(), None).await?;
+        let mut conflicts = Vec::new();
+        for obj in results.into_iter().flat_map(|result| result.contents) {
+            let data = match self.bucket.get_object(&obj.key).await {
+                Ok(data) => data,
+                // resolved concurrently
+                Err(S3Error::HttpFailWithBody(404, _)) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            match serde_json::from_slice::<Conflict>(&data.to_vec()) {
+                Ok(conflict) => conflicts.push(conflict),
+                Err(e) => log::warn!("Ignoring invalid conflict record {}: {e}", obj.key),
+            }
+        }
+        conflicts.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(conflicts)
+    }
+
+    /// Remove the conflict record of a document, along with the variants it kept.
+    pub async fn delete_conflict(&self, key: Key<'_>) -> Result<(), Error> {
+        let prefix = format!("{}{}/", &VARIANTS_PATH[1..], key);
+        for result in self.bucket.list(prefix, None).await? {
+            for obj in result.contents {
+                self.bucket.delete_object(obj.key).await?;
+            }
+        }
+        self.bucket.delete_object(format!("{}{}", CONFLICTS_PATH, key)).await?;
+        Ok(())
+    }
+
+    /// Keep a variant of a document, as it was received.
+    pub async fn put_variant(&self, key: Key<'_>, digest: &str, data: &[u8]) -> Result<(), Error> {
+        self.bucket
+            .put_object(format!("{}{}/{}", VARIANTS_PATH, key, Key::from(digest)), data)
+            .await?;
+        Ok(())
+    }
+
+    /// Get a variant of a document, kept by [`Self::put_variant`].
+    pub async fn get_variant(&self, key: Key<'_>, digest: &str) -> Result<Vec<u8>, Error> {
+        let data = self
+            .bucket
+            .get_object(format!("{}{}/{}", VARIANTS_PATH, key, Key::from(digest)))
+            .await?;
+        Ok(data.to_vec())
+    }
+
+    /// Remove a variant of a document, once it was replaced.
+    pub async fn delete_variant(&self, key: Key<'_>, digest: &str) -> Result<(), Error> {
+        self.bucket
+            .delete_object(format!("{}{}/{}", VARIANTS_PATH, key, Key::from(digest)))
+            .await?;
+        Ok(())
+    }
+
+    /// Keep the stored document as a prior revision before it's replaced, pruning the oldest revisions beyond `retain`.
+    ///
+    /// Returns the identifier of the kept revision, `None` if no document is stored or no revisions are retained.
+    pub async fn put_revision(&self, key: Key<'_>, retain: usize) -> Result<Option<String>, Error> {
+        if retain == 0 {
+            return Ok(None);
+        }
+        let path = format!("{}{}", DATA_PATH, key);
+        let head = match self.bucket.head_object(&path).await.map_err(Error::from) {
+            Ok((head, _status)) => head,
+            Err(Error::NotFound) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let provenance = head.metadata.as_ref().and_then(Provenance::from_metadata);
+        let revision = revision_id(stored_at(provenance.as_ref()));
+        let object = format!("{}{}/{}", REVISIONS_PATH, key, revision);
+        // the revision of an alias refers to the blob too
+        if let Some(digest) = head.metadata.as_ref().and_then(blob::alias_of) {
+            self.bucket.put_object(blob_ref(digest, &object)?, &[]).await?;
+        }
+        self.bucket.copy_object_internal(&path, &object).await?;
+
+        for (object, _, _) in self.prior_revisions(key).await?.into_iter().skip(retain) {
+            self.delete_revision(&object).await?;
+        }
+        Ok(Some(revision))
+    }
+
+    /// Store a document in place of the stored one, like [`Self::put_stream`].
+    ///
+    /// The stored document is kept as a prior revision, see [`Self::put_revision`], and its original is removed, as it
+    /// would be outdated by the new document.
+    pub async fn replace_stream<'a>(
+        &self,
+        key: Key<'a>,
+        content_type: &'a str,
+        encoding: Option<&str>,
+        provenance: &Provenance,
+        data: impl Stream<Item = Result<Bytes, Error>>,
+        retain: usize,
+    ) -> Result<usize, Error> {
+        if let Some(revision) = self.put_revision(key, retain).await? {
+            log::debug!("Kept revision {revision} of {key}");
+        }
+        let size = self.put_stream(key, content_type, encoding, provenance, data).await?;
+        self.delete_original(key).await?;
+        Ok(size)
+    }
+
+    /// Delete a prior revision by its object key, dropping its reference to the blob of an alias.
+    async fn delete_revision(&self, object: &str) -> Result<(), Error> {
+        let path = S3Path::from_path(object).path;
+        let digest = self.alias_of(&path).await?;
+        self.bucket.delete_object(&path).await?;
+        if let Some(digest) = digest {
+            self.release_blob(&digest, &path).await?;
+        }
+        Ok(())
+    }
+
+    /// The versions of a document, the current one first, followed by the prior revisions from the newest to the
+    /// oldest.
+    pub async fn list_revisions(&self, key: Key<'_>) -> Result<Vec<Revision>, Error> {
+        let path = S3Path::from_key(key);
+        let (head, _status) = self.bucket.head_object(&path.path).await?;
+        let provenance = head.metadata.as_ref().and_then(Provenance::from_metadata);
+        let (_, content) = self.resolve(&path, head).await?;
+        let mut revisions = vec![Revision {
+            revision: revision_id(stored_at(provenance.as_ref())),
+            current: true,
+            size: content.content_length.unwrap_or_default().max(0) as u64,
+            provenance,
+        }];
+
+        for (object, revision, size) in self.prior_revisions(key).await? {
+            let path = S3Path::from_path(&object);
+            let (head, _status) = match self.bucket.head_object(&path.path).await.map_err(Error::from) {
+                Ok(head) => head,
+                // pruned by a concurrent put
+                Err(Error::NotFound) => continue,
+                Err(e) => return Err(e),
+            };
+            let provenance = head.metadata.as_ref().and_then(Provenance::from_metadata);
+            // the revision of an alias is an alias too, whose size is the one of its blob
+            let (_, content) = self.resolve(&path, head).await?;
+            revisions.push(Revision {
+                revision,
+                current: false,
+                size: content.content_length.map_or(size, |len| len.max(0) as u64),
+                provenance,
+            });
+        }
+        Ok(revisions)
+    }
+
+    /// The object keys, identifiers and sizes of the prior revisions of a document, newest first.
+    async fn prior_revisions(&self, key: Key<'_>) -> Result<Vec<(String, String, u64)>, Error> {
+        let prefix = format!("{}{}/", &REVISIONS_PATH[1..], key);
+        let results = self.bucket.list(prefix.clone(), None).await?;
+        let mut revisions: Vec<_> = results
+            .into_iter()
+            .flat_map(|result| result.contents)
+            .filter_map(|obj| {
+                let revision = obj.key.strip_prefix(&prefix)?.to_string();
+                Some((obj.key, revision, obj.size))
+            })
+            .collect();
+        revisions.sort_by_key(|(_, revision, _)| newest_first(revision));
+        Ok(revisions)
+    }
+
+    /// Remove the prior revisions of a document, when it's deleted.
+    pub async fn delete_revisions(&self, key: Key<'_>) -> Result<(), Error> {
+        for (object, _, _) in self.prior_revisions(key).await? {
+            self.delete_revision(&object).await?;
+        }
+        Ok(())
+    }
+
+    /// Move documents and prior revisions which were not stored again for the configured time to the archive storage
+    /// class, copying them in place so that they stay readable under the same key.
+    ///
+    /// Returns the number of archived objects, none if archiving isn't configured.
+    pub async fn archive(&self) -> Result<usize, Error> {
+        let Some(policy) = &self.archive else {
+            return Ok(0);
+        };
+        let mut bucket = self.bucket.clone();
+        bucket.add_header("x-amz-storage-class", &policy.class);
+
+        let now = OffsetDateTime::now_utc();
+        let mut results = self.bucket.list(DATA_PATH[1..].to_string(), None).await?;
+        results.extend(self.bucket.list(REVISIONS_PATH[1..].to_string(), None).await?);
+        let mut archived = 0;
+        for obj in results.into_iter().flat_map(|result| result.contents) {
+            if policy.is_due(now, &obj.last_modified, obj.storage_class.as_deref()) {
+                bucket.copy_object_internal(&obj.key, &obj.key).await?;
+                log::debug!("Archived {} to {}", obj.key, policy.class);
+                archived += 1;
+            }
+        }
+        Ok(archived)
+    }
+
+    /// Get the reports of the latest `limit` walker runs, newest first.
+    pub async fn get_walker_runs(&self, limit: usize) -> Result<Vec<WalkerRun>, Error> {
+        let names = self.list_walker_runs().await?;
+        let mut runs = Vec::new();
+        for name in names.iter().rev().take(limit) {
+            let data = match self.bucket.get_object(format!("{}{}", WALKER_RUNS_PATH, name)).await {
+                Ok(data) => data,
+                // pruned by a concurrent put
+                Err(S3Error::HttpFailWithBody(404, _)) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            match serde_json::from_slice(&data.to_vec()) {
+                Ok(run) => runs.push(run),
+                Err(e) => log::warn!("Ignoring invalid walker run report {name}: {e}"),
+            }
+        }
+        Ok(runs)
+    }
+
+    /// Names of the stored walker run reports, oldest first.
+    async fn list_walker_runs(&self) -> Result<Vec<String>, Error> {
+        let results = self.bucket.list(WALKER_RUNS_PATH[1..].to_string(), None).await?;
+        let mut names: Vec<String> = results
+            .into_iter()
+            .flat_map(|result| result.contents)
+            .filter_map(|obj| obj.key.strip_prefix(&WALKER_RUNS_PATH[1..]).map(ToString::to_string))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Decode data using one of the supported content encodings, failing once the decoded data exceeds `max`.
+    pub async fn decode_bytes(encoding: Option<&str>, data: Bytes, max: ByteSize) -> Result<Vec<u8>, Error> {
+        let stream = stream::decode(encoding, once(ok::<_, Error>(data)).boxed_local())?;
+        pin_mut!(stream);
+        let mut bytes = vec![];
+        while let Some(chunk) = stream.next().await {
+            bytes.extend_from_slice(&chunk?);
+            if bytes.len() as u64 > max.as_u64() {
+                return Err(Error::ExceedsMaxSize(max));
+            }
+        }
+        Ok(bytes)
+    }
+
     pub fn decode_event(&self, event: &[u8]) -> Result<StorageEvent, Error> {
         serde_json::from_slice::<StorageEvent>(event).map_err(|_e| Error::Internal)
     }
@@ -466,9 +1402,9 @@ impl Storage {
         self.get_object_from_stream(self.get_decoded_stream(path).await?).await
     }
 
-    // This will load the encoded S3 object into memory
+    // This will load the encoded S3 object into memory, expecting the path of its content
     async fn get_encoded_object(&self, path: S3Path) -> Result<Vec<u8>, Error> {
-        self.get_object_from_stream(self.get_encoded_stream(path).await?).await
+        self.get_object_from_stream(self.get_content_stream(path).await?).await
     }
 
     async fn get_object_from_stream(&self, stream: impl Stream<Item = Result<Bytes, Error>>) -> Result<Vec<u8>, Error> {
@@ -487,8 +1423,8 @@ impl Storage {
         self.metrics.gets_total.inc();
         let res = {
             let (head, _status) = self.bucket.head_object(path.path.clone()).await?;
-            let stream = self.get_encoded_stream(path.clone()).await?;
-            stream::decode(head.content_encoding.as_deref(), Box::pin(stream))
+            let (path, head) = self.resolve(path, head).await?;
+            self.decode_content(path, head.content_encoding.as_deref()).await
         };
         if res.is_err() {
             self.metrics.gets_failed_total.inc();
@@ -496,30 +1432,74 @@ impl Storage {
         res
     }
 
+    // Expects the path of the content and its encoding, as returned by `resolve`
+    async fn decode_content(
+        &self,
+        path: S3Path,
+        encoding: Option<&str>,
+    ) -> Result<impl Stream<Item = Result<Bytes, Error>>, Error> {
+        let stream = self.get_content_stream(path).await?;
+        stream::decode(encoding, Box::pin(stream))
+    }
+
     // Expects the actual S3 path and returns encoded JSON stream
     pub async fn get_encoded_stream(&self, path: S3Path) -> Result<impl Stream<Item = Result<Bytes, Error>>, Error> {
+        let (head, _status) = self.bucket.head_object(&path.path).await?;
+        let (path, _) = self.resolve(&path, head).await?;
+        self.get_content_stream(path).await
+    }
+
+    /// Get a range of the encoded object, from the first to the last byte, both inclusive.
+    ///
+    /// Unlike the streams, this will load the range into memory.
+    pub async fn get_encoded_range(&self, path: S3Path, start: u64, end: u64) -> Result<Bytes, Error> {
+        self.metrics.gets_total.inc();
+        let res = async {
+            let (head, _status) = self.bucket.head_object(&path.path).await?;
+            let (path, _) = self.resolve(&path, head).await?;
+            let data = self.bucket.get_object_range(&path.path, start, Some(end)).await?;
+            Ok::<_, Error>(Bytes::from(data.to_vec()))
+        }
+        .await;
+        if res.is_err() {
+            self.metrics.gets_failed_total.inc();
+        }
+        res
+    }
+
+    // Expects the path of the content itself, not of an alias
+    async fn get_content_stream(&self, path: S3Path) -> Result<impl Stream<Item = Result<Bytes, Error>>, Error> {
         let mut s = self.bucket.get_object_stream(path.path).await?;
         Ok(try_stream! { while let Some(chunk) = s.bytes().next().await { yield chunk?; }})
     }
 
+    /// Delete a document, and the blob of its content once no other document refers to it.
     pub async fn delete(&self, key: Key<'_>) -> Result<u16, Error> {
         self.metrics.deletes_total.inc();
         let path = format!("{}{}", DATA_PATH, key);
-        let res = self
-            .bucket
-            .delete_object(path)
-            .await
-            .map(|r| r.status_code())
-            .map_err(|e| {
-                self.metrics.deletes_failed_total.inc();
-                e
-            })?;
+        let res = async {
+            let digest = self.alias_of(&path).await?;
+            let status = self.bucket.delete_object(&path).await?.status_code();
+            if let Some(digest) = digest {
+                self.release_blob(&digest, &path).await?;
+            }
+            Ok::<_, Error>(status)
+        }
+        .await
+        .map_err(|e| {
+            self.metrics.deletes_failed_total.inc();
+            e
+        })?;
+        self.delete_original(key).await?;
         Ok(res)
     }
 
-    // Deletes all data in the bucket (except index)
+    // Deletes all data in the bucket, including prior revisions, blobs and their references (except index)
     pub async fn delete_all(&self) -> Result<(), Error> {
-        let results = self.bucket.list(DATA_PATH[1..].to_string(), None).await?;
+        let mut results = self.bucket.list(DATA_PATH[1..].to_string(), None).await?;
+        results.extend(self.bucket.list(REVISIONS_PATH[1..].to_string(), None).await?);
+        results.extend(self.bucket.list(BLOBS_PATH[1..].to_string(), None).await?);
+        results.extend(self.bucket.list(BLOB_REFS_PATH[1..].to_string(), None).await?);
         for result in results {
             for obj in result.contents {
                 self.metrics.deletes_total.inc();
@@ -537,10 +1517,18 @@ impl Storage {
     }
 }
 
+/// Path of the reference of an object to the blob of a digest.
+fn blob_ref(digest: &str, object: &str) -> Result<String, Error> {
+    let hash = blob::parse_digest(digest).ok_or(Error::Internal)?;
+    Ok(format!("{}{hash}{object}", BLOB_REFS_PATH))
+}
+
 #[derive(Clone, Default)]
 pub struct ContinuationToken(Option<String>);
 
 const PUT_EVENT: &str = "ObjectCreated:Put";
+// archiving copies documents in place, changing their storage class
+const COPY_EVENT: &str = "ObjectCreated:Copy";
 const MULTIPART_PUT_EVENT: &str = "ObjectCreated:CompleteMultipartUpload";
 const DELETE_EVENT: &str = "ObjectRemoved:Delete";
 const DELETE_MARKER_EVENT: &str = "ObjectRemoved:DeleteMarkerCreated";
@@ -552,23 +1540,63 @@ pub enum EventType {
     Other,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct StorageEvent {
     #[serde(rename = "Records")]
     pub records: Vec<Record>,
 }
 
-#[derive(Deserialize, Debug)]
+impl StorageEvent {
+    /// An event of storing a document, like the bucket sends when the document is stored.
+    ///
+    /// Used to process a document again, by sending the event to the topic of stored documents.
+    pub fn put(bucket: &str, key: &str) -> Self {
+        Self::new(bucket, key, PUT_EVENT)
+    }
+
+    /// An event of deleting a document, like the bucket sends when the document is deleted.
+    ///
+    /// Used to remove a document from the index, whose stored document is gone already.
+    pub fn delete(bucket: &str, key: &str) -> Self {
+        Self::new(bucket, key, DELETE_EVENT)
+    }
+
+    fn new(bucket: &str, key: &str, event_name: &str) -> Self {
+        let path = format!("{}{}", DATA_PATH, Key::from(key));
+        Self {
+            records: vec![Record {
+                s3: S3Data {
+                    object: S3Object {
+                        key: urlencoding::encode(path.trim_start_matches('/')).into_owned(),
+                    },
+                    bucket: S3Bucket {
+                        name: bucket.to_string(),
+                    },
+                },
+                event_name: event_name.to_string(),
+                // not the time the document was stored or deleted
+                event_time: None,
+            }],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Record {
     #[serde(rename = "s3")]
     s3: S3Data,
     #[serde(rename = "eventName")]
     event_name: String,
+    #[serde(rename = "eventTime", default, skip_serializing_if = "Option::is_none")]
+    event_time: Option<String>,
 }
 
 impl Record {
     pub fn event_type(&self) -> EventType {
-        if self.event_name.ends_with(PUT_EVENT) || self.event_name.ends_with(MULTIPART_PUT_EVENT) {
+        if self.event_name.ends_with(PUT_EVENT)
+            || self.event_name.ends_with(MULTIPART_PUT_EVENT)
+            || self.event_name.ends_with(COPY_EVENT)
+        {
             EventType::Put
         } else if self.event_name.ends_with(DELETE_EVENT) || self.event_name.ends_with(DELETE_MARKER_EVENT) {
             EventType::Delete
@@ -584,6 +1612,11 @@ impl Record {
     pub fn bucket(&self) -> &str {
         &self.s3.bucket.name
     }
+
+    /// When the event occurred, like when the object was stored, if reported and valid.
+    pub fn event_time(&self) -> Option<OffsetDateTime> {
+        OffsetDateTime::parse(self.event_time.as_deref()?, &Rfc3339).ok()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -609,6 +1642,20 @@ impl S3Path {
         }
     }
 
+    // Path of a prior revision of a document
+    pub fn from_revision(key: Key<'_>, revision: &str) -> S3Path {
+        S3Path {
+            path: format!("{}{key}/{}", REVISIONS_PATH, Key::from(revision)),
+        }
+    }
+
+    // Path of the blob of a content addressed document, by its digest like `sha256:<hex>`, `None` if it's invalid
+    pub fn from_digest(digest: &str) -> Option<S3Path> {
+        blob::parse_digest(digest).map(|hash| S3Path {
+            path: format!("{}{hash}", BLOBS_PATH),
+        })
+    }
+
     // Key without prefix
     pub fn key(&self) -> Cow<'_, str> {
         self.path
@@ -618,7 +1665,7 @@ impl S3Path {
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct S3Data {
     #[serde(rename = "object")]
     object: S3Object,
@@ -626,13 +1673,13 @@ pub struct S3Data {
     bucket: S3Bucket,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct S3Object {
     #[serde(rename = "key")]
     key: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct S3Bucket {
     #[serde(rename = "name")]
     name: String,
@@ -642,6 +1689,9 @@ pub struct S3Result {
     pub key: String,
     pub data: Vec<u8>,
     pub encoding: Option<String>,
+    pub provenance: Option<Provenance>,
+    /// Whether the object was moved to an archive storage class
+    pub archived: bool,
 }
 
 #[cfg(test)]
@@ -708,6 +1758,36 @@ mod tests {
         assert_eq!(decoded.event_type(), EventType::Put);
         assert_eq!(decoded.key(), "index");
         assert_eq!(decoded.bucket(), "vexination");
+        assert_eq!(
+            decoded.event_time(),
+            OffsetDateTime::parse("2023-06-05T11:04:06.851Z", &Rfc3339).ok()
+        );
+    }
+
+    #[test]
+    fn test_put_event() {
+        let event = serde_json::to_vec(&StorageEvent::put("bombastic", "foo/bar baz")).unwrap();
+        let decoded = serde_json::from_slice::<StorageEvent>(&event).unwrap();
+
+        assert_eq!(1, decoded.records.len());
+        let decoded = &decoded.records[0];
+        assert_eq!(decoded.event_type(), EventType::Put);
+        assert_eq!(decoded.bucket(), "bombastic");
+        let (path, key) = Storage::key_from_event(decoded).unwrap();
+        assert_eq!(path, "data/foo%2Fbar%20baz");
+        assert_eq!(key, "foo/bar baz");
+        assert_eq!(decoded.event_time(), None);
+    }
+
+    #[test]
+    fn test_delete_event() {
+        let event = serde_json::to_vec(&StorageEvent::delete("bombastic", "foo")).unwrap();
+        let decoded = serde_json::from_slice::<StorageEvent>(&event).unwrap();
+
+        let decoded = &decoded.records[0];
+        assert_eq!(decoded.event_type(), EventType::Delete);
+        let (_, key) = Storage::key_from_event(decoded).unwrap();
+        assert_eq!(key, "foo");
     }
 
     #[test]
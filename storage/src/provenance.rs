@@ -0,0 +1,271 @@
+use crate::Error;
+use http::{HeaderMap, HeaderName, HeaderValue};
+use std::collections::HashMap;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use urlencoding::{decode, encode};
+
+const PRINCIPAL: &str = "provenance-principal";
+const SOURCE: &str = "provenance-source";
+const SOURCE_URL: &str = "provenance-source-url";
+const TIMESTAMP: &str = "provenance-timestamp";
+const SIGNATURE: &str = "provenance-signature";
+const SIGNER: &str = "provenance-signer";
+const SIGNATURE_ERROR: &str = "provenance-signature-error";
+const INVALID: &str = "provenance-invalid";
+
+/// Maximum length of a stored verification error, as the metadata of an object is limited to 2 KiB.
+const MAX_ERROR_LEN: usize = 256;
+
+const METADATA_PREFIX: &str = "x-amz-meta-";
+
+/// How a document was ingested.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// Uploaded through the API.
+    #[default]
+    Api,
+    /// Discovered and uploaded by a walker.
+    Walker,
+    /// Mirrored from another Trustification instance.
+    Federation,
+}
+
+impl Source {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Api => "api",
+            Self::Walker => "walker",
+            Self::Federation => "federation",
+        }
+    }
+}
+
+impl std::str::FromStr for Source {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "api" => Ok(Self::Api),
+            "walker" => Ok(Self::Walker),
+            "federation" => Ok(Self::Federation),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Who stored a document, from where and when. Stored as metadata of the object.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Provenance {
+    /// The authenticated principal which stored the document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+    /// How the document was ingested
+    pub source: Source,
+    /// Where the document was retrieved from, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    /// When the document was stored
+    #[serde(with = "time::serde::rfc3339")]
+    pub timestamp: OffsetDateTime,
+    /// The outcome of verifying the signature the document was uploaded with, if there was one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<Signature>,
+    /// Why the document failed validation, if it was stored nonetheless
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invalid: Option<String>,
+}
+
+/// The outcome of verifying the signature of a document.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Signature {
+    /// Whether the signature was verified against the trust roots
+    pub verified: bool,
+    /// Who signed the document: the identity of the signing certificate, or the name of the trusted key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+    /// Why the signature couldn't be verified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Provenance {
+    /// Provenance of a document stored now.
+    pub fn new(source: Source, principal: Option<&str>, source_url: Option<&str>) -> Self {
+        Self {
+            principal: principal.map(ToString::to_string),
+            source,
+            source_url: source_url.map(ToString::to_string),
+            timestamp: OffsetDateTime::now_utc(),
+            signature: None,
+            invalid: None,
+        }
+    }
+
+    /// The provenance without the principal which stored the document, for readers who aren't authenticated.
+    pub fn without_principal(self) -> Self {
+        Self {
+            principal: None,
+            ..self
+        }
+    }
+
+    /// Labels of the document in the search indexes, like `signature:verified`, `signer:<identity>` and
+    /// `validation:failed`.
+    pub fn labels(&self) -> Vec<String> {
+        let mut labels = Vec::new();
+        if let Some(signature) = &self.signature {
+            labels.push(match signature.verified {
+                true => "signature:verified".to_string(),
+                false => "signature:failed".to_string(),
+            });
+            if let Some(signer) = &signature.signer {
+                labels.push(format!("signer:{signer}"));
+            }
+        }
+        if self.invalid.is_some() {
+            labels.push("validation:failed".to_string());
+        }
+        labels
+    }
+
+    /// Add the provenance as object metadata headers.
+    ///
+    /// Metadata must be ASCII, so values provided by users are URL encoded.
+    pub(crate) fn insert_headers(&self, headers: &mut HeaderMap) -> Result<(), Error> {
+        let mut insert = |name: &str, value: &str| -> Result<(), Error> {
+            let name = HeaderName::from_bytes(format!("{METADATA_PREFIX}{name}").as_bytes())
+                .expect("metadata header names must be valid");
+            headers.insert(name, HeaderValue::from_str(value)?);
+            Ok(())
+        };
+
+        insert(SOURCE, self.source.as_str())?;
+        if let Some(principal) = &self.principal {
+            insert(PRINCIPAL, &encode(principal))?;
+        }
+        if let Some(source_url) = &self.source_url {
+            insert(SOURCE_URL, &encode(source_url))?;
+        }
+        if let Ok(timestamp) = self.timestamp.format(&Rfc3339) {
+            insert(TIMESTAMP, &timestamp)?;
+        }
+        if let Some(signature) = &self.signature {
+            insert(SIGNATURE, if signature.verified { "verified" } else { "failed" })?;
+            if let Some(signer) = &signature.signer {
+                insert(SIGNER, &encode(signer))?;
+            }
+            if let Some(error) = &signature.error {
+                let error: String = error.chars().take(MAX_ERROR_LEN).collect();
+                insert(SIGNATURE_ERROR, &encode(&error))?;
+            }
+        }
+        if let Some(invalid) = &self.invalid {
+            let invalid: String = invalid.chars().take(MAX_ERROR_LEN).collect();
+            insert(INVALID, &encode(&invalid))?;
+        }
+        Ok(())
+    }
+
+    /// Read the provenance from the metadata of an object, `None` if it was stored without.
+    pub(crate) fn from_metadata(metadata: &HashMap<String, String>) -> Option<Self> {
+        // depending on the backend, keys may be returned with different casing
+        let get = |name: &str| {
+            metadata
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        };
+        let decoded = |name: &str| get(name).map(|value| decode(value).map(|v| v.into_owned()).unwrap_or_default());
+
+        let source = get(SOURCE)?.parse().ok()?;
+        Some(Self {
+            principal: decoded(PRINCIPAL),
+            source,
+            source_url: decoded(SOURCE_URL),
+            timestamp: get(TIMESTAMP)
+                .and_then(|timestamp| OffsetDateTime::parse(timestamp, &Rfc3339).ok())
+                .unwrap_or(OffsetDateTime::UNIX_EPOCH),
+            signature: get(SIGNATURE).map(|signature| Signature {
+                verified: signature == "verified",
+                signer: decoded(SIGNER),
+                error: decoded(SIGNATURE_ERROR),
+            }),
+            invalid: decoded(INVALID),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let provenance = Provenance {
+            principal: Some("walker@example.com".into()),
+            source: Source::Walker,
+            source_url: Some("https://example.com/csaf/rhsa-2023_1441.json?x=ä".into()),
+            timestamp: OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap(),
+            signature: Some(Signature {
+                verified: true,
+                signer: Some("https://github.com/example/sboms/.github/workflows/publish.yml@refs/heads/main".into()),
+                error: None,
+            }),
+            invalid: Some("missing definition of product id CSAFPID-0001".into()),
+        };
+
+        let mut headers = HeaderMap::new();
+        provenance.insert_headers(&mut headers).unwrap();
+
+        // backends return the metadata without prefix
+        let metadata = headers
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.as_str().trim_start_matches(METADATA_PREFIX).to_string(),
+                    value.to_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(Provenance::from_metadata(&metadata), Some(provenance));
+    }
+
+    #[test]
+    fn without_principal() {
+        let provenance = Provenance::new(Source::Api, Some("uploader"), Some("https://example.com/sbom.json"));
+        let redacted = provenance.clone().without_principal();
+        assert_eq!(redacted.principal, None);
+        assert_eq!(
+            redacted,
+            Provenance {
+                principal: None,
+                ..provenance
+            }
+        );
+    }
+
+    #[test]
+    fn labels() {
+        let mut provenance = Provenance::new(Source::Api, None, None);
+        assert!(provenance.labels().is_empty());
+
+        provenance.signature = Some(Signature {
+            verified: true,
+            signer: Some("release".into()),
+            error: None,
+        });
+        assert_eq!(provenance.labels(), ["signature:verified", "signer:release"]);
+
+        provenance.invalid = Some("missing revision history".into());
+        assert_eq!(
+            provenance.labels(),
+            ["signature:verified", "signer:release", "validation:failed"]
+        );
+    }
+
+    #[test]
+    fn missing() {
+        assert_eq!(Provenance::from_metadata(&HashMap::new()), None);
+    }
+}
@@ -0,0 +1,49 @@
+use crate::Provenance;
+use std::cmp::Reverse;
+use time::OffsetDateTime;
+
+/// A version of a document, the current one or a prior one kept when the document was replaced.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Revision {
+    /// Identifier of the revision, derived from the time it was stored
+    pub revision: String,
+    /// Whether this is the current version of the document
+    pub current: bool,
+    /// Size of the stored document in bytes, as encoded in the storage
+    pub size: u64,
+    /// Who stored the revision, from where and when, if it was stored with its provenance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+/// The identifier of a revision stored at a time: milliseconds since the epoch, so that revisions sort by their
+/// numeric value.
+pub fn revision_id(stored: OffsetDateTime) -> String {
+    (stored.unix_timestamp_nanos() / 1_000_000).to_string()
+}
+
+/// When a document was stored, documents stored without provenance being the oldest possible.
+pub fn stored_at(provenance: Option<&Provenance>) -> OffsetDateTime {
+    provenance.map_or(OffsetDateTime::UNIX_EPOCH, |provenance| provenance.timestamp)
+}
+
+/// The sort key ordering revisions from the newest to the oldest.
+pub(crate) fn newest_first(revision: &str) -> Reverse<u128> {
+    Reverse(revision.parse().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ids() {
+        let stored = OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_123_456_789).unwrap();
+        assert_eq!(revision_id(stored), "1700000000123");
+        assert_eq!(revision_id(stored_at(None)), "0");
+
+        let mut revisions = vec!["999999999999", "1700000000123", "1600000000000"];
+        revisions.sort_by_key(|revision| newest_first(revision));
+        assert_eq!(revisions, vec!["1700000000123", "1600000000000", "999999999999"]);
+    }
+}
@@ -1,4 +1,4 @@
-use async_compression::tokio::bufread::{BzDecoder, BzEncoder, ZstdDecoder, ZstdEncoder};
+use async_compression::tokio::bufread::{BzDecoder, BzEncoder, GzipDecoder, GzipEncoder, ZstdDecoder, ZstdEncoder};
 use bytes::Bytes;
 use futures::{stream::LocalBoxStream, Stream, StreamExt, TryStreamExt};
 use tokio::io::AsyncRead;
@@ -16,7 +16,7 @@ pub fn encoded_reader<'a>(
     Ok(match encoding {
         None => encode(Some(default), Box::pin(data)),
         Some(s) => match s {
-            "zstd" | "bzip2" => encode(None, Box::pin(data)),
+            "zstd" | "bzip2" | "gzip" => encode(None, Box::pin(data)),
             e => Err(Error::Encoding(e.to_string())),
         },
     }
@@ -28,6 +28,7 @@ pub fn decode<'a>(encoding: Option<&str>, stream: ObjectStream<'a>) -> Result<Ob
         Some(s) => match s {
             "zstd" => Ok(boxed(ZstdDecoder::new(StreamReader::new(stream)))),
             "bzip2" => Ok(boxed(BzDecoder::new(StreamReader::new(stream)))),
+            "gzip" => Ok(boxed(GzipDecoder::new(StreamReader::new(stream)))),
             _ => Err(Error::Encoding(s.to_string())),
         },
         None => Ok(stream),
@@ -39,6 +40,7 @@ pub fn encode<'a>(encoding: Option<&str>, stream: ObjectStream<'a>) -> Result<Ob
         Some(s) => match s {
             "zstd" => Ok(boxed(ZstdEncoder::new(StreamReader::new(stream)))),
             "bzip2" => Ok(boxed(BzEncoder::new(StreamReader::new(stream)))),
+            "gzip" => Ok(boxed(GzipEncoder::new(StreamReader::new(stream)))),
             _ => Err(Error::Encoding(s.to_string())),
         },
         None => Ok(stream),
@@ -0,0 +1,55 @@
+use std::fmt::{self, Display, Formatter};
+
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+/// A record of a deleted document.
+///
+/// It's kept after the document is deleted, to tell a deleted document from one which was never stored, and to keep
+/// automated sources from storing the document again.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Tombstone {
+    /// Identifier of the deleted document
+    pub id: String,
+    /// The authenticated principal which deleted the document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub deleted: OffsetDateTime,
+}
+
+impl Tombstone {
+    pub fn new(id: &str, principal: Option<&str>) -> Self {
+        Self {
+            id: id.to_string(),
+            principal: principal.map(ToString::to_string),
+            deleted: OffsetDateTime::now_utc(),
+        }
+    }
+}
+
+impl Display for Tombstone {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let deleted = self.deleted.format(&Rfc3339).map_err(|_| fmt::Error)?;
+        write!(f, "{} was deleted at {deleted}", self.id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let tombstone = Tombstone {
+            id: "ubi9-sbom".to_string(),
+            principal: None,
+            deleted: OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap(),
+        };
+        assert_eq!(tombstone.to_string(), "ubi9-sbom was deleted at 2023-11-14T22:13:20Z");
+        let json = serde_json::to_value(&tombstone).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"id": "ubi9-sbom", "deleted": "2023-11-14T22:13:20Z"})
+        );
+    }
+}
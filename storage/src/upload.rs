@@ -0,0 +1,39 @@
+use time::OffsetDateTime;
+
+/// A document being uploaded in parts, using an S3 multipart upload.
+///
+/// It's stored until the upload is completed or aborted, so that the upload can be resumed from any API instance.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Upload {
+    /// Identifier of the uploaded document
+    pub id: String,
+    /// Id of the S3 multipart upload
+    pub upload_id: String,
+    pub content_type: String,
+    /// Content encoding of the document, whose parts are stored as they are sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub started: OffsetDateTime,
+    /// Object the parts are stored in until the document is stored by its digest, if the storage is content addressable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub staging: Option<String>,
+}
+
+impl Upload {
+    /// The object the parts are stored in.
+    pub(crate) fn path(&self) -> String {
+        match &self.staging {
+            Some(staging) => staging.clone(),
+            None => format!("{}{}", crate::DATA_PATH, crate::Key::from(&self.id)),
+        }
+    }
+}
+
+/// A stored part of an upload, which is required to complete the upload.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct UploadPart {
+    /// Number of the part, starting at 1
+    pub part: u32,
+    pub etag: String,
+}
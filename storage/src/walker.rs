@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+use time::OffsetDateTime;
+
+/// Outcome of a walker run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    /// The walker processed all documents of its source
+    Succeeded,
+    /// The walker was unable to complete the run
+    Failed,
+}
+
+/// Report of a single walker run, stored by the API service the walker uploads its documents to.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct WalkerRun {
+    /// Where the walker discovered documents
+    pub source: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub start_date: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub end_date: OffsetDateTime,
+    /// Duration of the run, in milliseconds
+    pub duration_ms: u64,
+    pub status: RunStatus,
+    /// Why the run failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Number of documents discovered
+    #[serde(default)]
+    pub discovered: usize,
+    /// Number of discovered documents skipped by filters
+    #[serde(default)]
+    pub skipped: usize,
+    /// Number of documents retrieved
+    #[serde(default)]
+    pub downloaded: usize,
+    /// Number of documents which failed to be retrieved, validated or uploaded
+    #[serde(default)]
+    pub failed: usize,
+    /// Reasons of the failures, by document
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failures: BTreeMap<String, Vec<String>>,
+}
+
+impl WalkerRun {
+    /// Name of the stored report, ordering reports by the start of their run.
+    pub(crate) fn name(&self) -> String {
+        format!("{:020}.json", self.start_date.unix_timestamp_nanos().max(0))
+    }
+}
+
+/// The latest runs of the walkers of an API service.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct WalkerRuns {
+    /// The most recent run, for dashboards
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latest: Option<WalkerRun>,
+    /// The most recent runs, newest first
+    pub runs: Vec<WalkerRun>,
+}
+
+impl From<Vec<WalkerRun>> for WalkerRuns {
+    fn from(runs: Vec<WalkerRun>) -> Self {
+        Self {
+            latest: runs.first().cloned(),
+            runs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(start: i64) -> WalkerRun {
+        WalkerRun {
+            source: "https://example.com".into(),
+            start_date: OffsetDateTime::from_unix_timestamp(start).unwrap(),
+            end_date: OffsetDateTime::from_unix_timestamp(start + 60).unwrap(),
+            duration_ms: 60_000,
+            status: RunStatus::Succeeded,
+            error: None,
+            discovered: 3,
+            skipped: 1,
+            downloaded: 2,
+            failed: 0,
+            failures: Default::default(),
+        }
+    }
+
+    #[test]
+    fn names_sort_by_start() {
+        let mut names = vec![run(1_700_000_000).name(), run(999).name(), run(1_600_000_000).name()];
+        names.sort();
+        assert_eq!(names[0], run(999).name());
+        assert_eq!(names[2], run(1_700_000_000).name());
+    }
+
+    #[test]
+    fn roundtrip() {
+        let run = run(1_700_000_000);
+        let json = serde_json::to_string(&run).unwrap();
+        assert_eq!(serde_json::from_str::<WalkerRun>(&json).unwrap(), run);
+    }
+}
@@ -24,8 +24,16 @@ pub enum Command {
 
     Exporter(exporter::Run),
 
+    Dump(exporter::dump::Dump),
+
     #[command(subcommand)]
     Admin(trustification_admin::Command),
+
+    #[command(subcommand)]
+    Index(trustification_admin::index::Index),
+
+    #[command(subcommand)]
+    Devmode(trustification_admin::devmode::Devmode),
 }
 
 #[derive(clap::Parser, Debug)]
@@ -67,8 +75,11 @@ impl Cli {
             Command::Collectorist(run) => run.run().await,
             Command::Collector(run) => run.run().await,
             Command::Exporter(run) => run.run().await,
+            Command::Dump(run) => run.run().await,
             Command::V11y(run) => run.run().await,
             Command::Admin(run) => run.run().await,
+            Command::Index(run) => run.run().await,
+            Command::Devmode(run) => run.run().await,
         }
     }
 }
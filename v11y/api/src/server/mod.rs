@@ -24,6 +24,7 @@ mod vulnerability;
         crate::server::vulnerability::ingest_vulnerability,
         crate::server::vulnerability::get,
         crate::server::search::search_cve,
+        crate::server::search::search_cve_schema,
         //crate::server::vulnerability::get_by_alias,
     ),
     components(
@@ -35,6 +36,8 @@ mod vulnerability;
             v11y_model::Version,
             v11y_model::ScoreType,
             v11y_model::Reference,
+            trustification_api::search::SearchField,
+            trustification_api::search::SearchFieldType,
         )
     )
 )]
@@ -57,7 +60,8 @@ pub fn config(
             )
             .service(vulnerability::get_cve)
             .service(search::cve_status)
-            .service(search::search_cve),
+            .service(search::search_cve)
+            .service(search::search_cve_schema),
     )
     .service(swagger_ui_with_auth(ApiDoc::openapi(), swagger_ui_oidc));
 }
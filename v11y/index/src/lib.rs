@@ -7,12 +7,12 @@ use serde_json::Value;
 use sikula::prelude::*;
 use std::time::Duration;
 use time::OffsetDateTime;
-use trustification_api::search::SearchOptions;
+use trustification_api::search::{SearchField, SearchOptions};
 use trustification_index::{
     create_boolean_query, create_date_query, create_float_query, create_i64_query, create_string_query_case,
     create_text_query, field2bool, field2date_opt, field2str, field2strvec,
     metadata::doc2metadata,
-    sort_by,
+    search_field, search_predicate, sort_by,
     tantivy::{
         self,
         collector::TopDocs,
@@ -373,6 +373,32 @@ impl trustification_index::Index for Index {
             metadata,
         })
     }
+
+    fn search_fields(&self) -> Vec<SearchField> {
+        let field = |qualifier: &str, fields: &[Field], description: &str| {
+            search_field(&self.schema, qualifier, fields, description, |q| Cves::parse(q).is_ok())
+        };
+        let f = &self.fields;
+
+        vec![
+            field("id", &[f.id], "CVE identifier"),
+            field("title", &[f.title], "Title of the CVE"),
+            field("description", &[f.description], "Description of the CVE"),
+            field("score", &[f.cvss3x_score], "Highest CVSS v3 base score of the CVE"),
+            field("dateReserved", &[f.date_reserved], "Date the CVE was reserved"),
+            field("datePublished", &[f.date_published], "Date the CVE was published"),
+            field("dateUpdated", &[f.date_updated], "Date the CVE was last updated"),
+            field("dateRejected", &[f.date_rejected], "Date the CVE was rejected"),
+            field("indexedTimestamp", &[f.indexed_timestamp], "Time the CVE was indexed"),
+            field("severity", &[f.severity], "Severity derived from the CVSS score"),
+            search_predicate("low", "CVEs with low severity"),
+            search_predicate("medium", "CVEs with medium severity"),
+            search_predicate("high", "CVEs with high severity"),
+            search_predicate("critical", "CVEs with critical severity"),
+            search_predicate("published", "CVEs which are published"),
+            search_predicate("rejected", "CVEs which are rejected"),
+        ]
+    }
 }
 
 impl trustification_index::WriteIndex for Index {
@@ -578,4 +604,65 @@ mod test {
             assert_eq!(result.0.len(), 1);
         });
     }
+
+    #[tokio::test]
+    async fn test_search_fields() {
+        assert_search(|index| {
+            let fields = index.search_fields();
+            for field in &fields {
+                search(&index, &field.example);
+            }
+
+            let score = fields.iter().find(|f| f.qualifier == "score").unwrap();
+            assert!(score.sortable);
+        });
+    }
+
+    #[test]
+    fn test_search_fields_match_query_language() {
+        // the match is exhaustive, so a qualifier added to the query language fails to compile until it's listed here
+        macro_rules! qualifiers {
+            ($($variant:ident $(($value:pat))? => $qualifier:literal,)*) => {
+                fn qualifier(term: &Cves) -> &'static str {
+                    match term {
+                        $(Cves::$variant $(($value))? => $qualifier,)*
+                    }
+                }
+                const QUALIFIERS: &[&str] = &[$($qualifier),*];
+            };
+        }
+
+        qualifiers! {
+            Id(_) => "id",
+            Title(_) => "title",
+            Description(_) => "description",
+            Score(_) => "score",
+            DateReserved(_) => "dateReserved",
+            DatePublished(_) => "datePublished",
+            DateUpdated(_) => "dateUpdated",
+            DateRejected(_) => "dateRejected",
+            IndexedTimestamp(_) => "indexedTimestamp",
+            Severity(_) => "severity",
+            Low => "low",
+            Medium => "medium",
+            High => "high",
+            Critical => "critical",
+            Published => "published",
+            Rejected => "rejected",
+        }
+
+        let fields = Index::new().search_fields();
+        for field in &fields {
+            let query = Cves::parse(&field.example).unwrap();
+            match query.term {
+                sikula::prelude::Term::Match(term) => assert_eq!(qualifier(&term), field.qualifier),
+                term => panic!("unexpected example of {}: {term:?}", field.qualifier),
+            }
+        }
+
+        let described: Vec<_> = fields.iter().map(|field| field.qualifier.as_str()).collect();
+        for qualifier in QUALIFIERS {
+            assert!(described.contains(qualifier), "{qualifier} is not described");
+        }
+    }
 }
@@ -6,8 +6,14 @@ use tokio::sync::{mpsc, Mutex};
 use tokio::task::block_in_place;
 use trustification_event_bus::EventBusConfig;
 use trustification_index::{IndexConfig, IndexStore, WriteIndex};
-use trustification_indexer::{actix::configure, Indexer, IndexerStatus, ReindexMode};
-use trustification_infrastructure::health::checks::FailureRate;
+use trustification_indexer::{
+    actix::configure,
+    audit::Auditor,
+    batch::{BatchConfig, Batching},
+    stats::IngestionStats,
+    Indexer, IndexerStatus, ReindexMode,
+};
+use trustification_infrastructure::health::checks::{FailureRate, Probe};
 use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
 use trustification_storage::{Storage, StorageConfig};
 
@@ -40,6 +46,9 @@ pub struct Run {
 
     #[command(flatten)]
     pub index: IndexConfig,
+
+    #[command(flatten)]
+    pub batch: BatchConfig,
 }
 
 impl Run {
@@ -47,6 +56,10 @@ impl Run {
         let (command_sender, command_receiver) = mpsc::channel(1);
         let status = Arc::new(Mutex::new(IndexerStatus::Running));
         let s = status.clone();
+        let ingestion = Arc::new(Mutex::new(IngestionStats::default()));
+        let i = ingestion.clone();
+        let audit = Arc::new(Mutex::new(None));
+        let a = audit.clone();
         let c = command_sender.clone();
         let storage = self.storage.clone();
         Infrastructure::from(self.infra)
@@ -69,6 +82,12 @@ impl Run {
                     let state = check.handle();
                     context.health.liveness.register("index_state", check).await;
 
+                    let batching =
+                        Batching::new(&self.batch, self.index.sync_interval.into(), context.metrics.registry())?;
+
+                    let (ready, ready_check) = Probe::new("Indexer paused");
+                    context.health.readiness.register("indexer.paused", ready_check).await;
+
                     let mut indexer = Indexer {
                         indexes: vec![index],
                         storage,
@@ -76,17 +95,22 @@ impl Run {
                         stored_topic: self.stored_topic.as_str(),
                         indexed_topic: self.indexed_topic.as_str(),
                         failed_topic: self.failed_topic.as_str(),
+                        deleted_topic: None,
                         sync_interval: self.index.sync_interval.into(),
                         status: s.clone(),
                         commands: command_receiver,
                         command_sender: c,
                         reindex: self.reindex,
                         state,
+                        ingestion: i,
+                        batching,
+                        ready,
+                        audit: Auditor::new(a, context.metrics.registry())?,
                     };
                     indexer.run().await
                 },
                 move |config| {
-                    configure(status, command_sender, config);
+                    configure(status, command_sender, ingestion, audit, None, config);
                 },
             )
             .await?;
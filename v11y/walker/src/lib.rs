@@ -10,7 +10,7 @@ use std::ffi::OsStr;
 use std::path::PathBuf;
 use std::process::ExitCode;
 use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
-use trustification_storage::{Storage, StorageConfig};
+use trustification_storage::{Provenance, Source, Storage, StorageConfig};
 use walkdir::WalkDir;
 
 mod delta;
@@ -136,9 +136,10 @@ impl Run {
                         log::info!("Processing: {key}");
                         let data = Self::get_cve_data(&osv_client, path).await?;
 
+                        let provenance = Provenance::new(Source::Walker, None, None);
                         const MAX_RETRIES: usize = 10;
                         for retry in 0..MAX_RETRIES {
-                            match storage.put_json_slice(key.into(), &data).await {
+                            match storage.put_json_slice(key.into(), &provenance, &data).await {
                                 Ok(_) => break,
                                 Err(e) => {
                                     log::warn!("Failed to store {} (attempt {}/{}): {:?}", key, retry, MAX_RETRIES, e);
@@ -0,0 +1,242 @@
+//! Structured differences between versions of an advisory.
+
+use csaf::{
+    definitions::ProductIdT,
+    vulnerability::{ProductStatus, RemediationCategory, Vulnerability},
+    Csaf,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use vexination_model::prelude::*;
+
+/// Compare two versions of an advisory: the vulnerabilities added and removed, and the changes of the product statuses
+/// and remediations of the vulnerabilities listed by both.
+pub fn diff(advisory_id: &str, from: (&Csaf, DiffVersion), to: (&Csaf, DiffVersion)) -> AdvisoryDiff {
+    let before = vulnerabilities(from.0);
+    let after = vulnerabilities(to.0);
+
+    let added_vulnerabilities = after.keys().filter(|id| !before.contains_key(*id)).cloned().collect();
+    let removed_vulnerabilities = before.keys().filter(|id| !after.contains_key(*id)).cloned().collect();
+    let changed_vulnerabilities = before
+        .iter()
+        .filter_map(|(id, before)| {
+            let after = after.get(id)?;
+            let changes = VulnerabilityChanges {
+                id: id.clone(),
+                product_status: product_status_changes(before, after),
+                added_remediations: remediations(after).difference(&remediations(before)).cloned().collect(),
+                removed_remediations: remediations(before).difference(&remediations(after)).cloned().collect(),
+            };
+            (!changes.is_empty()).then_some(changes)
+        })
+        .collect();
+
+    AdvisoryDiff {
+        advisory_id: advisory_id.to_string(),
+        from: from.1,
+        to: to.1,
+        added_vulnerabilities,
+        removed_vulnerabilities,
+        changed_vulnerabilities,
+    }
+}
+
+/// The vulnerabilities of an advisory by their identifier: the CVE, or the title or position for the ones without.
+fn vulnerabilities(csaf: &Csaf) -> BTreeMap<String, &Vulnerability> {
+    csaf.vulnerabilities
+        .iter()
+        .flatten()
+        .enumerate()
+        .map(|(n, vuln)| {
+            let id = vuln
+                .cve
+                .clone()
+                .or_else(|| vuln.title.clone())
+                .unwrap_or_else(|| format!("#{}", n + 1));
+            (id, vuln)
+        })
+        .collect()
+}
+
+fn product_status_changes(before: &Vulnerability, after: &Vulnerability) -> Vec<ProductStatusChange> {
+    let before = product_statuses(before.product_status.as_ref());
+    let after = product_statuses(after.product_status.as_ref());
+
+    let products: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    products
+        .into_iter()
+        .filter_map(|product| {
+            let from = before.get(product).cloned().unwrap_or_default();
+            let to = after.get(product).cloned().unwrap_or_default();
+            (from != to).then(|| ProductStatusChange {
+                product_id: product.clone(),
+                from,
+                to,
+            })
+        })
+        .collect()
+}
+
+/// The statuses of each product, like `known_affected` or `fixed`, in the order of the CSAF specification.
+fn product_statuses(status: Option<&ProductStatus>) -> BTreeMap<String, Vec<String>> {
+    let mut result = BTreeMap::<String, Vec<String>>::new();
+    let Some(status) = status else {
+        return result;
+    };
+    for (name, products) in [
+        ("first_affected", &status.first_affected),
+        ("first_fixed", &status.first_fixed),
+        ("fixed", &status.fixed),
+        ("known_affected", &status.known_affected),
+        ("known_not_affected", &status.known_not_affected),
+        ("last_affected", &status.last_affected),
+        ("recommended", &status.recommended),
+        ("under_investigation", &status.under_investigation),
+    ] {
+        for ProductIdT(product) in products.iter().flatten() {
+            result.entry(product.clone()).or_default().push(name.to_string());
+        }
+    }
+    result
+}
+
+fn remediations(vuln: &Vulnerability) -> BTreeSet<RemediationEntry> {
+    vuln.remediations
+        .iter()
+        .flatten()
+        .map(|remediation| {
+            let mut product_ids: Vec<String> = remediation
+                .product_ids
+                .iter()
+                .flatten()
+                .map(|ProductIdT(product)| product.clone())
+                .collect();
+            product_ids.sort();
+            RemediationEntry {
+                category: category_str(&remediation.category).to_string(),
+                details: remediation.details.clone(),
+                product_ids,
+                url: remediation.url.as_ref().map(|url| url.to_string()),
+            }
+        })
+        .collect()
+}
+
+fn category_str(category: &RemediationCategory) -> &'static str {
+    match category {
+        RemediationCategory::Mitigation => "mitigation",
+        RemediationCategory::NoFixPlanned => "no_fix_planned",
+        RemediationCategory::NoneAvailable => "none_available",
+        RemediationCategory::VendorFix => "vendor_fix",
+        RemediationCategory::Workaround => "workaround",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn advisory(version: &str, vulnerabilities: serde_json::Value) -> Csaf {
+        serde_json::from_value(serde_json::json!({
+            "document": {
+                "category": "csaf_vex",
+                "csaf_version": "2.0",
+                "publisher": {
+                    "category": "vendor",
+                    "name": "Red Hat Product Security",
+                    "namespace": "https://www.redhat.com"
+                },
+                "title": "Test advisory",
+                "tracking": {
+                    "current_release_date": "2023-03-21T00:00:00Z",
+                    "id": "RHSA-2023:1441",
+                    "initial_release_date": "2023-03-21T00:00:00Z",
+                    "revision_history": [
+                        {"date": "2023-03-21T00:00:00Z", "number": version, "summary": "Update"}
+                    ],
+                    "status": "final",
+                    "version": version
+                }
+            },
+            "vulnerabilities": vulnerabilities
+        }))
+        .unwrap()
+    }
+
+    fn version(version: &str) -> DiffVersion {
+        DiffVersion {
+            version: version.to_string(),
+            revision: format!("170000000000{version}"),
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = advisory(
+            "1",
+            serde_json::json!([
+                {
+                    "cve": "CVE-2023-0001",
+                    "product_status": {"known_affected": ["openshift-4.12", "openshift-4.13"]},
+                    "remediations": [
+                        {"category": "workaround", "details": "Disable the feature", "product_ids": ["openshift-4.13"]}
+                    ]
+                },
+                {"cve": "CVE-2023-0002", "product_status": {"known_affected": ["openshift-4.12"]}}
+            ]),
+        );
+        let after = advisory(
+            "2",
+            serde_json::json!([
+                {
+                    "cve": "CVE-2023-0001",
+                    "product_status": {"known_affected": ["openshift-4.12"], "fixed": ["openshift-4.13"]},
+                    "remediations": [
+                        {
+                            "category": "vendor_fix",
+                            "details": "Update to 4.13.1",
+                            "product_ids": ["openshift-4.13"],
+                            "url": "https://access.redhat.com/errata/RHSA-2023:1441"
+                        }
+                    ]
+                },
+                {"cve": "CVE-2023-0002", "product_status": {"known_affected": ["openshift-4.12"]}},
+                {"cve": "CVE-2023-0003", "product_status": {"known_affected": ["openshift-4.12"]}}
+            ]),
+        );
+
+        let changes = diff("RHSA-2023:1441", (&before, version("1")), (&after, version("2")));
+        assert_eq!(changes.from, version("1"));
+        assert_eq!(changes.to, version("2"));
+        assert_eq!(changes.added_vulnerabilities, vec!["CVE-2023-0003"]);
+        assert!(changes.removed_vulnerabilities.is_empty());
+        assert_eq!(
+            changes.changed_vulnerabilities,
+            vec![VulnerabilityChanges {
+                id: "CVE-2023-0001".to_string(),
+                product_status: vec![ProductStatusChange {
+                    product_id: "openshift-4.13".to_string(),
+                    from: vec!["known_affected".to_string()],
+                    to: vec!["fixed".to_string()],
+                }],
+                added_remediations: vec![RemediationEntry {
+                    category: "vendor_fix".to_string(),
+                    details: "Update to 4.13.1".to_string(),
+                    product_ids: vec!["openshift-4.13".to_string()],
+                    url: Some("https://access.redhat.com/errata/RHSA-2023:1441".to_string()),
+                }],
+                removed_remediations: vec![RemediationEntry {
+                    category: "workaround".to_string(),
+                    details: "Disable the feature".to_string(),
+                    product_ids: vec!["openshift-4.13".to_string()],
+                    url: None,
+                }],
+            }]
+        );
+
+        // nothing changed
+        let changes = diff("RHSA-2023:1441", (&after, version("2")), (&after, version("2")));
+        assert!(changes.added_vulnerabilities.is_empty());
+        assert!(changes.removed_vulnerabilities.is_empty());
+        assert!(changes.changed_vulnerabilities.is_empty());
+    }
+}
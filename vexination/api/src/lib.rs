@@ -9,16 +9,26 @@ use trustification_auth::{
     authorizer::Authorizer,
     swagger_ui::{SwaggerUiOidc, SwaggerUiOidcConfig},
 };
-use trustification_index::{IndexConfig, IndexStore};
+use trustification_index::{
+    cache::{SearchCache, SearchCacheConfig},
+    Facets, IndexConfig, IndexStore,
+};
 use trustification_infrastructure::{
-    app::http::{BinaryByteSize, HttpServerBuilder, HttpServerConfig},
+    app::{
+        http::{BinaryByteSize, HttpServerBuilder, HttpServerConfig},
+        multi_get::{MultiGet, MultiGetConfig},
+    },
     endpoint::Vexination,
     health::checks::Probe,
     Infrastructure, InfrastructureConfig,
 };
 use trustification_storage::{Storage, StorageConfig};
 
+mod diff;
 mod server;
+mod validation;
+
+pub use validation::{ValidationConfig, ValidationMode};
 
 #[derive(clap::Args, Debug)]
 #[command(about = "Run the api server", args_conflicts_with_subcommands = true)]
@@ -41,17 +51,31 @@ pub struct Run {
     #[command(flatten)]
     pub index: IndexConfig,
 
+    #[command(flatten)]
+    pub search_cache: SearchCacheConfig,
+
     #[command(flatten)]
     pub http: HttpServerConfig<Vexination>,
 
     /// Request limit for publish requests
     #[arg(long, default_value_t = ByteSize::mib(64).into())]
     pub publish_limit: BinaryByteSize,
+
+    #[command(flatten)]
+    pub mget: MultiGetConfig,
+
+    /// Number of prior revisions kept when an advisory is stored again, `0` disables keeping them
+    #[arg(long, default_value_t = 10)]
+    pub max_revisions: usize,
+
+    #[command(flatten)]
+    pub validation: ValidationConfig,
 }
 
 impl Run {
     pub async fn run(self, listener: Option<TcpListener>) -> anyhow::Result<ExitCode> {
         let index = self.index;
+        let search_cache = self.search_cache;
         let storage = self.storage;
 
         let (authn, authz) = self.auth.split(self.devmode)?.unzip();
@@ -69,6 +93,9 @@ impl Run {
 
         let tracing = self.infra.tracing;
         let publish_limit = self.publish_limit.as_u64() as usize;
+        let mget = self.mget;
+        let max_revisions = self.max_revisions;
+        let validator = validation::Validator::load(&self.validation)?;
 
         Infrastructure::from(self.infra)
             .run(
@@ -77,7 +104,17 @@ impl Run {
                 |context| async move {
                     let (probe, check) = Probe::new("Index not synced");
                     context.health.readiness.register("available.index", check).await;
-                    let state = Self::configure(index, storage, probe, context.metrics.registry(), self.devmode)?;
+                    let state = Self::configure(
+                        index,
+                        search_cache,
+                        storage,
+                        probe,
+                        context.metrics.registry(),
+                        self.devmode,
+                        mget,
+                        max_revisions,
+                        validator,
+                    )?;
                     let mut http = HttpServerBuilder::try_from(self.http)?
                         .tracing(tracing)
                         .metrics(context.metrics.registry().clone(), "vexination_api")
@@ -103,24 +140,37 @@ impl Run {
         Ok(ExitCode::SUCCESS)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn configure(
         index_config: IndexConfig,
+        search_cache: SearchCacheConfig,
         storage: StorageConfig,
         probe: Probe,
         registry: &Registry,
         devmode: bool,
+        mget: MultiGetConfig,
+        max_revisions: usize,
+        validator: validation::Validator,
     ) -> anyhow::Result<Arc<AppState>> {
         let index =
             block_in_place(|| IndexStore::new(&storage, &index_config, vexination_index::Index::new(), registry))?;
+        let cache = SearchCache::new(&search_cache, registry, "vex")?;
         let storage = Storage::new(storage.process("vexination", devmode), registry)?;
 
-        let state = Arc::new(AppState { storage, index });
+        let state = Arc::new(AppState {
+            storage,
+            index,
+            cache,
+            mget: MultiGet::new(mget),
+            max_revisions,
+            validator,
+        });
 
         let sinker = state.clone();
         let sync_interval = index_config.sync_interval.into();
         tokio::task::spawn(async move {
             loop {
-                if sinker.sync_index().await.is_ok() {
+                if sinker.sync_index(true).await.is_ok() {
                     log::info!("Initial vexination index synced");
                     break;
                 } else {
@@ -131,7 +181,7 @@ impl Run {
             probe.set(true);
 
             loop {
-                if let Err(e) = sinker.sync_index().await {
+                if let Err(e) = sinker.sync_index(false).await {
                     log::info!("Unable to synchronize vexination index: {:?}", e);
                 }
                 tokio::time::sleep(sync_interval).await;
@@ -143,18 +193,31 @@ impl Run {
 }
 
 pub(crate) type Index = IndexStore<vexination_index::Index>;
+pub(crate) type Cache = SearchCache<(Vec<vexination_model::prelude::SearchHit>, usize, Facets)>;
 pub struct AppState {
     storage: Storage,
     index: Index,
+    /// Cached results of searches
+    cache: Cache,
+    mget: MultiGet,
+    /// Number of prior revisions kept when an advisory is stored again
+    max_revisions: usize,
+    /// Validates published documents
+    validator: validation::Validator,
 }
 
 pub(crate) type SharedState = Arc<AppState>;
 
 impl AppState {
-    async fn sync_index(&self) -> Result<(), anyhow::Error> {
+    /// Synchronize the index, the initial synchronization skips yearly partitions to become ready sooner.
+    async fn sync_index(&self, initial: bool) -> Result<(), anyhow::Error> {
         let storage = &self.storage;
         let index = &self.index;
-        index.sync(storage).await?;
+        if initial {
+            index.sync_hot(storage).await?;
+        } else {
+            index.sync(storage).await?;
+        }
         Ok(())
     }
 }
@@ -1,62 +1,172 @@
 use actix_web::{
     delete, get, guard,
     http::{header::ContentType, Method, StatusCode},
+    post,
     web::{self, Bytes},
-    HttpResponse, Responder,
+    HttpRequest, HttpResponse, Responder,
 };
 use derive_more::{Display, Error, From};
+use futures::{StreamExt, TryStreamExt};
 use serde::Deserialize;
 use std::sync::Arc;
-use trustification_api::search::SearchOptions;
+use trustification_api::{
+    multi_get::{MultiGetEntry, MultiGetRequest},
+    search::{IndexFreshness, SearchField, SearchFieldType, SearchOptions},
+    version::ApiVersion,
+};
 use trustification_auth::{
     authenticator::{user::UserInformation, Authenticator},
     authorizer::Authorizer,
-    swagger_ui::{swagger_ui_with_auth, SwaggerUiOidc},
+    openapi::{annotate_permissions, permitted_operations, OperationPermission},
+    swagger_ui::{versioned_swagger_ui_with_auth, SwaggerUiOidc},
     Permission,
 };
 use trustification_index::tantivy::time::OffsetDateTime;
-use trustification_index::Error as IndexError;
-use trustification_infrastructure::new_auth;
-use trustification_storage::{Error as StorageError, Key, S3Path, Storage};
-use utoipa::OpenApi;
+use trustification_index::{cache::SearchKey, Error as IndexError};
+use trustification_infrastructure::{
+    app::{conditional::Validators, ndjson::ndjson_response, search::SearchParams, version::versioned_scope},
+    new_auth,
+};
+use trustification_storage::{
+    Conflict, Error as StorageError, Head, Key, Provenance, S3Path, Source, Storage, Variant, WalkerRun, WalkerRuns,
+};
+use utoipa::{openapi::path::PathItemType, OpenApi};
+use vexination_index::Withdrawal;
 use vexination_model::prelude::*;
 
-use crate::SharedState;
+use crate::{validation::ValidationMode, AppState, SharedState};
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(fetch_vex, publish_vex, search_vex),
-    components(schemas(SearchDocument, SearchResult),)
+    paths(
+        fetch_vex,
+        mget_vex,
+        publish_vex,
+        search_vex,
+        export_search_vex,
+        search_vex_schema,
+        vex_revisions,
+        vex_diff,
+        vex_timeline,
+        vex_provenance,
+        vex_freshness,
+        vex_conflicts,
+        vex_conflict_variant,
+        resolve_vex_conflict,
+        publish_walker_run,
+        walker_runs
+    ),
+    components(schemas(
+        SearchDocument,
+        SearchResult,
+        SearchHit,
+        SearchField,
+        SearchFieldType,
+        MultiGetRequest,
+        MultiGetEntry,
+        AdvisoryRevisions,
+        RevisionEntry,
+        AdvisoryDiff,
+        DiffVersion,
+        VulnerabilityChanges,
+        ProductStatusChange,
+        RemediationEntry,
+        CveTimeline,
+        TimelineEvent,
+        TimelineEventKind,
+        IndexFreshness
+    ),)
 )]
 pub struct ApiDoc;
 
+/// The permissions required by the operations of [`ApiDoc`].
+const PERMISSIONS: &[OperationPermission] = &[
+    ("/api/v1/vex", PathItemType::Get, Permission::ReadVex),
+    ("/api/v1/vex", PathItemType::Put, Permission::CreateVex),
+    ("/api/v1/vex", PathItemType::Delete, Permission::DeleteVex),
+    ("/api/v1/vex/_mget", PathItemType::Post, Permission::ReadVex),
+    ("/api/v1/vex/search", PathItemType::Get, Permission::ReadVex),
+    ("/api/v1/vex/search/export", PathItemType::Get, Permission::ReadVex),
+    ("/api/v1/vex/search/schema", PathItemType::Get, Permission::ReadVex),
+    ("/api/v1/vex/revisions", PathItemType::Get, Permission::ReadVex),
+    ("/api/v1/vex/{id}/diff", PathItemType::Get, Permission::ReadVex),
+    ("/api/v1/vex/timeline", PathItemType::Get, Permission::ReadVex),
+    ("/api/v1/vex/provenance", PathItemType::Get, Permission::ReadVex),
+    ("/api/v1/vex/status", PathItemType::Get, Permission::ReadSbom),
+    ("/api/v1/vex/freshness", PathItemType::Get, Permission::ReadVex),
+    ("/api/v1/vex/conflicts", PathItemType::Get, Permission::CreateVex),
+    ("/api/v1/vex/conflicts", PathItemType::Delete, Permission::CreateVex),
+    (
+        "/api/v1/vex/conflicts/variant",
+        PathItemType::Get,
+        Permission::CreateVex,
+    ),
+    ("/api/v1/walker/runs", PathItemType::Get, Permission::ReadVex),
+    ("/api/v1/walker/runs", PathItemType::Post, Permission::CreateVex),
+];
+
+/// The OpenAPI document of the handlers, mentioning the permission each operation requires.
+fn api_doc() -> utoipa::openapi::OpenApi {
+    let mut openapi = ApiDoc::openapi();
+    annotate_permissions(&mut openapi, PERMISSIONS);
+    openapi
+}
+
 pub fn config(
     cfg: &mut web::ServiceConfig,
     auth: Option<Arc<Authenticator>>,
     swagger_ui_oidc: Option<Arc<SwaggerUiOidc>>,
     publish_limit: usize,
 ) {
-    cfg.service(
-        web::scope("/api/v1")
-            .wrap(new_auth!(auth))
-            .service(fetch_vex)
-            .service(
-                web::resource("/vex")
-                    .app_data(web::PayloadConfig::new(publish_limit))
-                    .guard(guard::Any(guard::Method(Method::PUT)).or(guard::Method(Method::POST)))
-                    .to(publish_vex),
-            )
-            .service(search_vex)
-            .service(delete_vex)
-            .service(vex_status)
-            .service(delete_vexes),
-    )
-    .service(swagger_ui_with_auth(ApiDoc::openapi(), swagger_ui_oidc));
+    for version in ApiVersion::ALL {
+        cfg.service(
+            versioned_scope(version)
+                .wrap(new_auth!(auth.clone()))
+                .configure(|svc| services(svc, publish_limit)),
+        );
+    }
+    cfg.service(versioned_swagger_ui_with_auth(
+        ApiVersion::ALL.map(|version| (version.as_str(), version.openapi_url(), version.openapi(api_doc()))),
+        swagger_ui_oidc,
+    ));
+}
+
+/// The routes shared by all API versions.
+fn services(cfg: &mut web::ServiceConfig, publish_limit: usize) {
+    cfg.service(fetch_vex)
+        .service(mget_vex)
+        .service(
+            web::resource("/vex")
+                .app_data(web::PayloadConfig::new(publish_limit))
+                .guard(guard::Any(guard::Method(Method::PUT)).or(guard::Method(Method::POST)))
+                .to(publish_vex),
+        )
+        .service(search_vex)
+        .service(export_search_vex)
+        .service(search_vex_schema)
+        .service(vex_revisions)
+        .service(vex_diff)
+        .service(vex_timeline)
+        .service(vex_provenance)
+        .service(delete_vex)
+        .service(vex_status)
+        .service(vex_freshness)
+        .service(vex_conflicts)
+        .service(vex_conflict_variant)
+        .service(resolve_vex_conflict)
+        .service(publish_walker_run)
+        .service(walker_runs)
+        .service(permitted_openapi)
+        .service(delete_vexes);
 }
 
-async fn fetch_object(storage: &Storage, key: Key<'_>) -> HttpResponse {
+async fn fetch_object(storage: &Storage, key: Key<'_>, validators: &Validators) -> HttpResponse {
     match storage.get_decoded_stream(&S3Path::from_key(key)).await {
-        Ok(stream) => HttpResponse::Ok().content_type(ContentType::json()).streaming(stream),
+        Ok(stream) => {
+            let mut response = HttpResponse::Ok();
+            validators.apply(&mut response);
+            response.content_type(ContentType::json()).streaming(stream)
+        }
         Err(e) => {
             log::warn!("Unable to locate object with key {}: {:?}", key, e);
             HttpResponse::NotFound().finish()
@@ -70,6 +180,18 @@ enum Error {
     Storage(StorageError),
     #[display(fmt = "index error: {}", "_0")]
     Index(IndexError),
+    #[display(fmt = "document error: {}", "_0")]
+    Document(serde_json::Error),
+    #[display(fmt = "VEX not found")]
+    Restricted,
+    #[display(fmt = "no variant {} of the VEX", "_0")]
+    UnknownVariant(#[error(not(source))] String),
+    #[display(fmt = "no stored version {} of the VEX", "_0")]
+    UnknownVersion(#[error(not(source))] String),
+    #[display(fmt = "invalid CSAF document: {}", "_0")]
+    Invalid(#[error(not(source))] String),
+    #[display(fmt = "invalid CVE identifier: {}", "_0")]
+    InvalidCve(#[error(not(source))] String),
 }
 
 impl actix_web::error::ResponseError for Error {
@@ -85,7 +207,14 @@ impl actix_web::error::ResponseError for Error {
     fn status_code(&self) -> StatusCode {
         match self {
             Self::Storage(StorageError::NotFound) => StatusCode::NOT_FOUND,
+            // don't disclose the existence of advisories the user may not read
+            Self::Restricted => StatusCode::NOT_FOUND,
             Self::Index(IndexError::QueryParser(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::InvalidFacet(_)) => StatusCode::BAD_REQUEST,
+            Self::UnknownVariant(_) => StatusCode::BAD_REQUEST,
+            Self::UnknownVersion(_) => StatusCode::NOT_FOUND,
+            Self::Invalid(_) => StatusCode::BAD_REQUEST,
+            Self::InvalidCve(_) => StatusCode::BAD_REQUEST,
             e => {
                 log::error!("{e:?}");
                 StatusCode::INTERNAL_SERVER_ERROR
@@ -94,6 +223,22 @@ impl actix_web::error::ResponseError for Error {
     }
 }
 
+/// Identifies the hidden TLP labels in cached searches.
+fn tlp_filter_key(hidden: &[Tlp]) -> Option<String> {
+    if hidden.is_empty() {
+        return None;
+    }
+    Some(hidden.iter().map(|tlp| tlp.as_str()).collect::<Vec<_>>().join(","))
+}
+
+/// Fail if an advisory has one of the hidden TLP labels, advisories without a label being public.
+fn check_tlp(data: &[u8], hidden: &[Tlp]) -> Result<(), Error> {
+    match Tlp::of(data) {
+        Some(tlp) if hidden.contains(&tlp) => Err(Error::Restricted),
+        _ => Ok(()),
+    }
+}
+
 /// Parameters passed when fetching an advisory.
 #[derive(Debug, Deserialize)]
 struct QueryParams {
@@ -102,21 +247,28 @@ struct QueryParams {
 }
 
 /// Retrieve an SBOM using its identifier.
+///
+/// Responses carry an `ETag` and a `Last-Modified` header. Clients polling for changes can pass them back using
+/// `If-None-Match` or `If-Modified-Since`, and receive `304 Not Modified` without the document if it didn't change.
 #[utoipa::path(
     get,
     tag = "vexination",
     path = "/api/v1/vex",
     responses(
         (status = 200, description = "VEX found"),
-        (status = NOT_FOUND, description = "VEX not found in archive"),
+        (status = NOT_MODIFIED, description = "VEX didn't change since the client retrieved it"),
+        (status = NOT_FOUND, description = "VEX not found in archive, or restricted by its TLP label"),
         (status = BAD_REQUEST, description = "Missing valid id or index entry"),
     ),
     params(
         ("advisory" = String, Query, description = "Identifier of VEX to fetch"),
+        ("If-None-Match" = Option<String>, Header, description = "Entity tag of the VEX the client has"),
+        ("If-Modified-Since" = Option<String>, Header, description = "When the client retrieved the VEX"),
     )
 )]
 #[get("/vex")]
 async fn fetch_vex(
+    req: HttpRequest,
     state: web::Data<SharedState>,
     params: web::Query<QueryParams>,
     authorizer: web::Data<Authorizer>,
@@ -124,7 +276,155 @@ async fn fetch_vex(
 ) -> actix_web::Result<HttpResponse> {
     authorizer.require(&user, Permission::ReadVex)?;
 
-    Ok(fetch_object(&state.storage, (&params.advisory).into()).await)
+    let path = S3Path::from_key((&params.advisory).into());
+    let validators = match state.storage.get_head(path).await {
+        Ok(head) => Validators::new(head.etag.as_deref(), head.last_modified.as_deref()),
+        // failing to fetch the document reports it
+        Err(_) => Validators::default(),
+    };
+
+    let hidden = Tlp::hidden(&authorizer, &user);
+    if hidden.is_empty() {
+        if validators.is_not_modified(&req) {
+            return Ok(validators.not_modified());
+        }
+        return Ok(fetch_object(&state.storage, (&params.advisory).into(), &validators).await);
+    }
+    // the TLP label of the advisory is required before serving it, or telling it didn't change
+    let data = read_object(&state.storage, (&params.advisory).into())
+        .await
+        .map_err(Error::Storage)?;
+    check_tlp(&data, &hidden)?;
+    if validators.is_not_modified(&req) {
+        return Ok(validators.not_modified());
+    }
+    let mut response = HttpResponse::Ok();
+    validators.apply(&mut response);
+    Ok(response.content_type(ContentType::json()).body(data))
+}
+
+/// Retrieve the provenance of an advisory: who stored it, from where and when.
+///
+/// The principal which stored the advisory is left out for anonymous readers.
+#[utoipa::path(
+    get,
+    tag = "vexination",
+    path = "/api/v1/vex/provenance",
+    responses(
+        (status = 200, description = "Provenance of the VEX"),
+        (status = NOT_FOUND, description = "VEX not found, restricted by its TLP label, or stored without provenance"),
+    ),
+    params(
+        ("advisory" = String, Query, description = "Identifier of the VEX"),
+    )
+)]
+#[get("/vex/provenance")]
+async fn vex_provenance(
+    state: web::Data<SharedState>,
+    params: web::Query<QueryParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadVex)?;
+
+    let hidden = Tlp::hidden(&authorizer, &user);
+    if !hidden.is_empty() {
+        match read_object(&state.storage, (&params.advisory).into()).await {
+            Ok(data) => check_tlp(&data, &hidden)?,
+            Err(e) => {
+                log::warn!("Unable to locate object with key {}: {:?}", params.advisory, e);
+                return Ok(HttpResponse::NotFound().finish());
+            }
+        }
+    }
+
+    let path = S3Path::from_key((&params.advisory).into());
+    match state.storage.get_head(path).await {
+        Ok(Head {
+            provenance: Some(provenance),
+            ..
+        }) => Ok(HttpResponse::Ok().json(match user {
+            // principals are accounts of the uploaders, only shown to authenticated readers
+            UserInformation::Authenticated(_) => provenance,
+            UserInformation::Anonymous => provenance.without_principal(),
+        })),
+        Ok(_) => Ok(HttpResponse::NotFound().finish()),
+        Err(e) => {
+            log::warn!("Unable to locate object with key {}: {:?}", params.advisory, e);
+            Ok(HttpResponse::NotFound().finish())
+        }
+    }
+}
+
+/// Fetch multiple VEX documents using their identifiers.
+///
+/// The response is a stream of newline delimited JSON objects, one for each requested identifier, in the
+/// order of the request. Each entry carries its own status code, as well as the document or an error.
+#[utoipa::path(
+    post,
+    tag = "vexination",
+    path = "/api/v1/vex/_mget",
+    request_body = MultiGetRequest,
+    responses(
+        (status = 200, description = "Stream of results", body = MultiGetEntry, content_type = "application/x-ndjson"),
+        (status = BAD_REQUEST, description = "Too many identifiers requested"),
+        (status = 401, description = "Not authenticated"),
+    ),
+)]
+#[post("/vex/_mget")]
+async fn mget_vex(
+    state: web::Data<SharedState>,
+    request: web::Json<MultiGetRequest>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadVex)?;
+
+    let hidden = Arc::new(Tlp::hidden(&authorizer, &user));
+    let request = request.into_inner();
+    log::debug!("Fetching {} VEX documents", request.ids.len());
+
+    let fetcher = SharedState::clone(&state);
+    Ok(state.mget.respond(request, move |id| {
+        let state = fetcher.clone();
+        let hidden = hidden.clone();
+        async move { fetch_entry(&state, id, &hidden).await }
+    })?)
+}
+
+/// Fetch a single document for a multi-get request.
+async fn fetch_entry(state: &AppState, id: String, hidden: &[Tlp]) -> MultiGetEntry {
+    match read_object(&state.storage, Key::from(&id)).await {
+        Ok(data) if check_tlp(&data, hidden).is_err() => MultiGetEntry::failed(id, 404, "Not found"),
+        Ok(data) => match serde_json::from_slice(&data) {
+            Ok(document) => MultiGetEntry::found(id, document),
+            Err(err) => {
+                log::warn!("Failed to parse VEX {id}: {err}");
+                MultiGetEntry::failed(id, 500, "Invalid document")
+            }
+        },
+        Err(StorageError::NotFound) => MultiGetEntry::failed(id, 404, "Not found"),
+        Err(err) => {
+            log::warn!("Failed to fetch VEX {id}: {err}");
+            MultiGetEntry::failed(id, 500, "Internal server error")
+        }
+    }
+}
+
+/// Read a complete, decoded, object from the storage.
+async fn read_object(storage: &Storage, key: Key<'_>) -> Result<Vec<u8>, StorageError> {
+    read_path(storage, &S3Path::from_key(key)).await
+}
+
+async fn read_path(storage: &Storage, path: &S3Path) -> Result<Vec<u8>, StorageError> {
+    storage
+        .get_decoded_stream(path)
+        .await?
+        .try_fold(Vec::new(), |mut data, chunk| async move {
+            data.extend_from_slice(&chunk);
+            Ok(data)
+        })
+        .await
 }
 
 /// Parameters passed when publishing advisory.
@@ -132,11 +432,22 @@ async fn fetch_vex(
 struct PublishParams {
     /// Optional: Advisory identifier (overrides identifier derived from document)
     advisory: Option<String>,
+    /// How the advisory was ingested, defaults to `api`
+    #[serde(default)]
+    source: Source,
+    /// Where the advisory was retrieved from
+    source_url: Option<String>,
 }
 
 /// Upload a VEX document.
 ///
-/// The document must be in the CSAF v2.0 format.
+/// The document must be in the CSAF v2.0 format. If an advisory with the same identifier was stored from another
+/// origin (the host of its source URL) with different content, both are kept as variants of a conflict, and the one
+/// with the latest release date is stored.
+///
+/// The document is validated: against the constraints of the CSAF 2.0 schema, against its checksums and the detached
+/// OpenPGP signature of its provider, if it's published with them. Depending on the configuration, invalid documents
+/// are refused, stored or stored with the validation errors recorded in their provenance, labeled `validation:failed`.
 #[utoipa::path(
     put,
     tag = "vexination",
@@ -144,13 +455,20 @@ struct PublishParams {
     request_body(content = Value, description = "The VEX doc to be uploaded", content_type = "application/json"),
     responses(
         (status = 200, description = "VEX uploaded successfully"),
-        (status = BAD_REQUEST, description = "Missing valid id"),
+        (status = 202, description = "VEX kept as a conflicting variant, another variant remains stored"),
+        (status = BAD_REQUEST, description = "Missing valid id, or invalid document"),
     ),
     params(
         ("advisory" = String, Query, description = "Identifier assigned to the VEX"),
+        ("source" = Option<String>, Query, description = "How the VEX was ingested: api (default), walker or federation, the latter two requiring the ingest.document permission"),
+        ("source_url" = Option<String>, Query, description = "Where the VEX was retrieved from"),
+        ("x-signature" = Option<String>, Header, description = "Base64 encoded detached OpenPGP signature of the VEX"),
+        ("x-checksum-sha256" = Option<String>, Header, description = "SHA-256 checksum of the VEX"),
+        ("x-checksum-sha512" = Option<String>, Header, description = "SHA-512 checksum of the VEX"),
     )
 )]
 async fn publish_vex(
+    req: HttpRequest,
     state: web::Data<SharedState>,
     params: web::Query<PublishParams>,
     data: Bytes,
@@ -172,11 +490,32 @@ async fn publish_vex(
         Some(advisory) => advisory.to_string(),
         None => vex.document.tracking.id,
     };
+    if params.source != Source::Api {
+        // documents of walkers and other instances are treated differently, only trusted clients may claim them
+        authorizer.require(&user, Permission::IngestDocument)?;
+    }
+    let mut provenance = Provenance::new(params.source, user.id(), params.source_url.as_deref());
 
+    let validation = state.validator.validate(req.headers(), &data);
+    provenance.signature = validation.signature;
+    if !validation.errors.is_empty() {
+        let errors = validation.errors.join("; ");
+        match state.validator.mode() {
+            ValidationMode::Reject => return Err(Error::Invalid(errors).into()),
+            ValidationMode::Warn => log::warn!("Storing invalid VEX {advisory}: {errors}"),
+            ValidationMode::Annotate => provenance.invalid = Some(errors),
+        }
+    }
+
+    if !check_conflict(&state.storage, &advisory, &vex, &data, &provenance).await? {
+        return Ok(HttpResponse::Accepted().body(format!("VEX {advisory} kept as a conflicting variant")));
+    }
+
+    keep_revision(&state, &advisory).await?;
     log::debug!("Storing new VEX with id: {advisory}");
     state
         .storage
-        .put_json_slice((&advisory).into(), &data)
+        .put_json_slice((&advisory).into(), &provenance, &data)
         .await
         .map_err(Error::Storage)?;
     let msg = format!("VEX of size {} stored successfully", &data[..].len());
@@ -184,60 +523,88 @@ async fn publish_vex(
     Ok(HttpResponse::Created().body(msg))
 }
 
-/// Parameters for search query.
-#[derive(Debug, Deserialize)]
-pub struct SearchParams {
-    /// Search query string
-    pub q: String,
-    /// Offset of documents to return (for pagination)
-    #[serde(default = "default_offset")]
-    pub offset: usize,
-    /// Max number of documents to return
-    #[serde(default = "default_limit")]
-    pub limit: usize,
-    /// Provide a detailed explanation of query matches
-    #[serde(default = "default_explain")]
-    pub explain: bool,
-    /// Provide additional metadata from the index
-    #[serde(default = "default_metadata")]
-    pub metadata: bool,
-    /// Enable fetching document summaries
-    #[serde(default = "default_summaries")]
-    pub summaries: bool,
-}
-
-const fn default_offset() -> usize {
-    0
-}
-
-const fn default_limit() -> usize {
-    10
+/// Keep the stored advisory as a prior revision, before it's replaced.
+async fn keep_revision(state: &AppState, advisory: &str) -> Result<(), Error> {
+    if let Some(revision) = state.storage.put_revision(advisory.into(), state.max_revisions).await? {
+        log::debug!("Kept revision {revision} of VEX {advisory}");
+    }
+    Ok(())
 }
 
-const fn default_explain() -> bool {
-    false
-}
+/// Compare a published advisory with the stored one, keeping both as variants if they come from different origins.
+///
+/// Advisories from the origin of the stored one replace it, like updates. Otherwise, the variant with the latest
+/// release date is stored, and the conflict is recorded for operators to adjudicate. Returns whether the published
+/// advisory is to be stored.
+async fn check_conflict(
+    storage: &Storage,
+    advisory: &str,
+    vex: &csaf::Csaf,
+    data: &[u8],
+    provenance: &Provenance,
+) -> Result<bool, Error> {
+    let key = Key::from(advisory);
+    let stored = match read_object(storage, key).await {
+        Ok(stored) => stored,
+        Err(StorageError::NotFound) => return Ok(true),
+        Err(e) => return Err(e.into()),
+    };
+    let published = Variant::new(
+        data,
+        Some(vex.document.tracking.version.to_string()),
+        Some(provenance.clone()),
+    );
+    let stored_vex = serde_json::from_slice::<csaf::Csaf>(&stored).ok();
+    let current = Variant::new(
+        &stored,
+        stored_vex.as_ref().map(|v| v.document.tracking.version.to_string()),
+        storage.get_provenance(&S3Path::from_key(key)).await?,
+    );
+    if published.digest == current.digest {
+        return Ok(true);
+    }
+    let same_origin = published.origin() == current.origin();
+    let conflict = storage.get_conflict(key).await?;
+    if conflict.is_none() && same_origin {
+        return Ok(true);
+    }
 
-const fn default_metadata() -> bool {
-    false
-}
+    let replace = same_origin
+        || match &stored_vex {
+            Some(stored) => vex.document.tracking.current_release_date > stored.document.tracking.current_release_date,
+            None => true,
+        };
 
-const fn default_summaries() -> bool {
-    true
-}
+    let mut conflict = conflict.unwrap_or_else(|| Conflict::new(advisory));
+    let mut replaced = Vec::new();
+    if conflict.variant(&current.digest).is_none() {
+        storage.put_variant(key, &current.digest, &stored).await?;
+        replaced.extend(conflict.add(current.clone()));
+    }
+    storage.put_variant(key, &published.digest, data).await?;
+    replaced.extend(conflict.add(published.clone()));
+    conflict.set_current(if replace { &published.digest } else { &current.digest });
+    for digest in replaced {
+        storage.delete_variant(key, &digest).await?;
+    }
 
-impl From<&SearchParams> for SearchOptions {
-    fn from(value: &SearchParams) -> Self {
-        Self {
-            explain: value.explain,
-            metadata: value.metadata,
-            summaries: value.summaries,
-        }
+    if conflict.is_conflicting() {
+        log::warn!(
+            "VEX {advisory} has {} conflicting variants, storing {}",
+            conflict.variants.len(),
+            if replace { &published.digest } else { &current.digest }
+        );
+        storage.put_conflict(&conflict).await?;
+    } else {
+        storage.delete_conflict(key).await?;
     }
+    Ok(replace)
 }
 
 /// Search for a VEX using a free form search query.
 ///
+/// Advisories with a TLP label the user may not read are left out of the results.
+///
 /// See the [documentation](https://docs.trustification.dev/trustification/user/retrieve.html) for a description of the query language.
 #[utoipa::path(
     get,
@@ -245,33 +612,447 @@ impl From<&SearchParams> for SearchOptions {
     path = "/api/v1/vex/search",
     responses(
         (status = 200, description = "Search completed"),
-        (status = BAD_REQUEST, description = "Bad query"),
+        (status = BAD_REQUEST, description = "Bad query, or search limits exceeded"),
     ),
     params(
         ("q" = String, Query, description = "Search query"),
+        ("facets" = Option<String>, Query, description = "Comma separated list of facets to return counts for (severity, status)"),
     )
 )]
 #[get("/vex/search")]
 async fn search_vex(
     state: web::Data<SharedState>,
-    params: web::Query<SearchParams>,
+    params: SearchParams,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadVex)?;
+
+    log::info!("Querying VEX using {}", params.q);
+
+    let index_updated_at = state.index.updated_at();
+    let hidden = Tlp::hidden(&authorizer, &user);
+    let key = SearchKey::new(&params.q, params.offset, params.limit, (&params).into())
+        .with_filter(tlp_filter_key(&hidden))
+        .with_facets(params.facets());
+    let (result, total, facets) = web::block(move || {
+        let generation = state.index.generation();
+        state.cache.get_or_search(generation, key, |key| {
+            let filter = state.index.index().tlp_filter(&hidden);
+            let (result, total) =
+                state
+                    .index
+                    .search_filtered(&key.q, filter, key.offset, key.limit, key.options.clone())?;
+            let filter = state.index.index().tlp_filter(&hidden);
+            let facets = state.index.facets_filtered(&key.q, filter, &key.facets)?;
+            Ok::<_, IndexError>((result, total, facets))
+        })
+    })
+    .await?
+    .map_err(Error::Index)?;
+    params.response(&SearchResult {
+        total,
+        result,
+        facets,
+        index_updated_at,
+    })
+}
+
+/// Number of documents read from the index at once when exporting search results.
+const EXPORT_PAGE_SIZE: usize = 500;
+
+/// Parameters passed when exporting search results.
+#[derive(Debug, Deserialize)]
+struct SearchExportParams {
+    /// Search query
+    q: String,
+}
+
+/// Export all the VEX documents matching a free form search query.
+///
+/// Unlike the search endpoint, results aren't limited to a page: the response is a chunked stream of newline delimited
+/// JSON objects, one for each matching advisory, read page by page from a snapshot of the index taken when the request
+/// was received. Lines starting with `#` are heartbeat comments, sent to keep the connection alive while the next page
+/// is read, which must be skipped.
+///
+/// Advisories with a TLP label the user may not read are left out.
+#[utoipa::path(
+    get,
+    tag = "vexination",
+    path = "/api/v1/vex/search/export",
+    responses(
+        (status = 200, description = "Stream of matching advisories", body = SearchHit, content_type = "application/x-ndjson"),
+        (status = BAD_REQUEST, description = "Bad query"),
+    ),
+    params(
+        ("q" = String, Query, description = "Search query"),
+    )
+)]
+#[get("/vex/search/export")]
+async fn export_search_vex(
+    state: web::Data<SharedState>,
+    params: web::Query<SearchExportParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadVex)?;
+
+    log::info!("Exporting VEX matching {}", params.q);
+
+    let state = SharedState::clone(&state);
+    let hidden = Tlp::hidden(&authorizer, &user);
+    let q = params.into_inner().q;
+    let cursor = {
+        let state = state.clone();
+        web::block(move || {
+            let filter = state.index.index().tlp_filter(&hidden);
+            state.index.cursor(&q, filter)
+        })
+        .await?
+        .map_err(Error::Index)?
+    };
+
+    let hits = futures::stream::try_unfold((state, cursor), |(state, mut cursor)| async move {
+        if cursor.is_done() {
+            return Ok(None);
+        }
+        let (hits, state, cursor) = web::block(move || {
+            let hits = state
+                .index
+                .next_page(&mut cursor, EXPORT_PAGE_SIZE, &SearchOptions::default());
+            (hits, state, cursor)
+        })
+        .await?;
+        let hits = hits.map_err(Error::Index)?;
+        Ok::<_, actix_web::Error>(Some((futures::stream::iter(hits.into_iter().map(Ok)), (state, cursor))))
+    })
+    .try_flatten();
+
+    Ok(ndjson_response(hits))
+}
+
+/// List the qualifiers supported by the VEX search query language.
+#[utoipa::path(
+    get,
+    tag = "vexination",
+    path = "/api/v1/vex/search/schema",
+    responses(
+        (status = 200, description = "Qualifiers of the query language", body = Vec<SearchField>),
+        (status = 401, description = "Not authenticated"),
+    ),
+)]
+#[get("/vex/search/schema")]
+async fn search_vex_schema(
+    state: web::Data<SharedState>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadVex)?;
+
+    Ok(HttpResponse::Ok().json(state.index.search_fields()))
+}
+
+/// Retrieve the revision metadata of an advisory.
+///
+/// The revision history is taken from the latest stored version of the advisory. Consumers can compare
+/// the current version with the one they fetched last, to detect updates. Withdrawn advisories are marked as such,
+/// listing the advisories superseding them.
+#[utoipa::path(
+    get,
+    tag = "vexination",
+    path = "/api/v1/vex/revisions",
+    responses(
+        (status = 200, description = "Revisions of the advisory", body = AdvisoryRevisions),
+        (status = NOT_FOUND, description = "VEX not found in archive, or restricted by its TLP label"),
+    ),
+    params(
+        ("advisory" = String, Query, description = "Identifier of the VEX"),
+    )
+)]
+#[get("/vex/revisions")]
+async fn vex_revisions(
+    state: web::Data<SharedState>,
+    params: web::Query<QueryParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadVex)?;
+
+    let data = read_object(&state.storage, (&params.advisory).into())
+        .await
+        .map_err(Error::Storage)?;
+    check_tlp(&data, &Tlp::hidden(&authorizer, &user))?;
+    let csaf: csaf::Csaf = serde_json::from_slice(&data).map_err(Error::Document)?;
+    let withdrawal = Withdrawal::of(&csaf);
+    let tracking = csaf.document.tracking;
+
+    Ok(HttpResponse::Ok().json(AdvisoryRevisions {
+        advisory_id: params.into_inner().advisory,
+        version: tracking.version.to_string(),
+        status: status_str(&tracking.status).to_string(),
+        initial_release_date: to_offset_date_time(tracking.initial_release_date.timestamp()),
+        current_release_date: to_offset_date_time(tracking.current_release_date.timestamp()),
+        revisions: tracking
+            .revision_history
+            .into_iter()
+            .map(|revision| RevisionEntry {
+                number: revision.number.to_string(),
+                date: to_offset_date_time(revision.date.timestamp()),
+                summary: revision.summary,
+                legacy_version: revision.legacy_version,
+            })
+            .collect(),
+        withdrawn: withdrawal.withdrawn,
+        superseded_by: withdrawal.superseded_by,
+    }))
+}
+
+/// Parameters of advisory diff requests.
+#[derive(Debug, Deserialize)]
+struct DiffParams {
+    /// Version to compare from, defaults to the version stored before the one compared to
+    from: Option<String>,
+    /// Version to compare to, defaults to the current version
+    to: Option<String>,
+}
+
+/// Compare two stored versions of an advisory.
+///
+/// Versions are identified by the version of their tracking information, as listed by the revisions of the advisory,
+/// but only the versions kept by the server can be compared. The diff lists the vulnerabilities which were added and
+/// removed, and for the others, the products whose status changed and the remediations which were added or removed.
+#[utoipa::path(
+    get,
+    tag = "vexination",
+    path = "/api/v1/vex/{id}/diff",
+    responses(
+        (status = 200, description = "Changes between the versions", body = AdvisoryDiff),
+        (status = NOT_FOUND, description = "VEX or version not found, or restricted by its TLP label"),
+    ),
+    params(
+        ("id" = String, Path, description = "Identifier of the VEX"),
+        ("from" = Option<String>, Query, description = "Version to compare from, defaults to the prior stored version"),
+        ("to" = Option<String>, Query, description = "Version to compare to, defaults to the current version"),
+    )
+)]
+#[get("/vex/{id}/diff")]
+async fn vex_diff(
+    state: web::Data<SharedState>,
+    id: web::Path<String>,
+    params: web::Query<DiffParams>,
     authorizer: web::Data<Authorizer>,
     user: UserInformation,
 ) -> actix_web::Result<HttpResponse> {
     authorizer.require(&user, Permission::ReadVex)?;
 
+    let id = id.into_inner();
+    let key = Key::from(&id);
+    let hidden = Tlp::hidden(&authorizer, &user);
+    // newest first
+    let mut versions = Vec::new();
+    for revision in state.storage.list_revisions(key).await.map_err(Error::Storage)? {
+        let path = match revision.current {
+            true => S3Path::from_key(key),
+            false => S3Path::from_revision(key, &revision.revision),
+        };
+        let data = match read_path(&state.storage, &path).await {
+            Ok(data) => data,
+            // pruned by a concurrent publish
+            Err(StorageError::NotFound) => continue,
+            Err(e) => return Err(Error::Storage(e).into()),
+        };
+        check_tlp(&data, &hidden)?;
+        let csaf: csaf::Csaf = serde_json::from_slice(&data).map_err(Error::Document)?;
+        let version = DiffVersion {
+            version: csaf.document.tracking.version.to_string(),
+            revision: revision.revision,
+        };
+        versions.push((csaf, version));
+    }
+
     let params = params.into_inner();
+    let position = |version: &str, skip: usize| {
+        versions
+            .iter()
+            .skip(skip)
+            .position(|(_, v)| v.version == version)
+            .map(|n| n + skip)
+            .ok_or_else(|| Error::UnknownVersion(version.to_string()))
+    };
+    let to = match &params.to {
+        Some(version) => position(version, 0)?,
+        None => 0,
+    };
+    let from = match &params.from {
+        Some(version) => position(version, 0)?,
+        None if to + 1 < versions.len() => to + 1,
+        None => return Err(Error::UnknownVersion(format!("prior to {}", versions[to].1.version)).into()),
+    };
 
-    log::info!("Querying VEX using {}", params.q);
+    let (from, to) = (&versions[from], &versions[to]);
+    Ok(HttpResponse::Ok().json(crate::diff::diff(&id, (&from.0, from.1.clone()), (&to.0, to.1.clone()))))
+}
+
+fn to_offset_date_time(timestamp: i64) -> OffsetDateTime {
+    OffsetDateTime::from_unix_timestamp(timestamp).unwrap_or(OffsetDateTime::UNIX_EPOCH)
+}
+
+fn status_str(status: &csaf::document::Status) -> &'static str {
+    match status {
+        csaf::document::Status::Draft => "draft",
+        csaf::document::Status::Interim => "interim",
+        csaf::document::Status::Final => "final",
+    }
+}
+
+/// Maximum number of advisories considered for a timeline.
+const MAX_TIMELINE_ADVISORIES: usize = 1000;
+
+/// Parameters passed when fetching the timeline of a CVE.
+#[derive(Debug, Deserialize)]
+struct TimelineParams {
+    /// Identifier of the CVE
+    cve: String,
+}
 
+/// Retrieve the timeline of advisory events for a CVE.
+///
+/// The timeline is assembled from all advisories referencing the CVE, containing their releases, revisions and
+/// current status, as well as the discovery and disclosure dates they report for the CVE. Events are ordered by date.
+/// Advisories with a TLP label the user may not read are left out.
+#[utoipa::path(
+    get,
+    tag = "vexination",
+    path = "/api/v1/vex/timeline",
+    responses(
+        (status = 200, description = "Timeline of the CVE", body = CveTimeline),
+        (status = BAD_REQUEST, description = "Invalid CVE identifier"),
+    ),
+    params(
+        ("cve" = String, Query, description = "Identifier of the CVE"),
+    )
+)]
+#[get("/vex/timeline")]
+async fn vex_timeline(
+    state: web::Data<SharedState>,
+    params: web::Query<TimelineParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadVex)?;
+
+    let cve = params.into_inner().cve.to_uppercase();
+    // the identifier becomes part of the query, so it must not contain anything else
+    if !is_cve_id(&cve) {
+        return Err(Error::InvalidCve(cve).into());
+    }
+    let query = format!(r#"cve:"{cve}""#);
+    let hidden = Tlp::hidden(&authorizer, &user);
+    let index = SharedState::clone(&state);
     let (result, total) = web::block(move || {
-        state
-            .index
-            .search(&params.q, params.offset, params.limit, (&params).into())
+        index.index.search_filtered(
+            &query,
+            index.index.index().tlp_filter(&hidden),
+            0,
+            MAX_TIMELINE_ADVISORIES,
+            SearchOptions {
+                metadata: false,
+                explain: false,
+                summaries: true,
+            },
+        )
     })
     .await?
     .map_err(Error::Index)?;
-    Ok(HttpResponse::Ok().json(SearchResult { total, result }))
+    if total > MAX_TIMELINE_ADVISORIES {
+        log::info!("Timeline of {cve} limited to {MAX_TIMELINE_ADVISORIES} of {total} advisories");
+    }
+
+    let storage = &state.storage;
+    let documents: Vec<_> = futures::stream::iter(result)
+        .map(|hit| async move {
+            let advisory = hit.document.advisory_id;
+            let data = match read_object(storage, (&advisory).into()).await {
+                Ok(data) => data,
+                // removed since it was indexed
+                Err(StorageError::NotFound) => return Ok(None),
+                Err(e) => return Err(Error::Storage(e)),
+            };
+            let csaf: csaf::Csaf = serde_json::from_slice(&data).map_err(Error::Document)?;
+            Ok(Some((advisory, csaf)))
+        })
+        .buffered(state.mget.concurrency())
+        .try_filter_map(|document| async move { Ok(document) })
+        .try_collect()
+        .await?;
+
+    Ok(HttpResponse::Ok().json(cve_timeline(cve, documents)))
+}
+
+/// Check if a value is a CVE identifier, like `CVE-2023-0286`.
+fn is_cve_id(value: &str) -> bool {
+    let mut parts = value.split('-');
+    let digits =
+        |part: Option<&str>, min: usize| part.is_some_and(|p| p.len() >= min && p.bytes().all(|b| b.is_ascii_digit()));
+    parts.next() == Some("CVE") && digits(parts.next(), 4) && digits(parts.next(), 4) && parts.next().is_none()
+}
+
+/// Assemble the timeline of a CVE from the advisories referencing it.
+fn cve_timeline(cve: String, documents: Vec<(String, csaf::Csaf)>) -> CveTimeline {
+    let advisories = documents.len();
+    let mut events = Vec::new();
+
+    for (advisory_id, csaf) in documents {
+        let tracking = csaf.document.tracking;
+        let event = |date: i64, kind| TimelineEvent {
+            date: to_offset_date_time(date),
+            kind,
+            advisory_id: advisory_id.clone(),
+            version: None,
+            status: None,
+            summary: None,
+        };
+
+        for vuln in csaf.vulnerabilities.iter().flatten() {
+            if !vuln.cve.as_deref().is_some_and(|id| id.eq_ignore_ascii_case(&cve)) {
+                continue;
+            }
+            if let Some(date) = &vuln.discovery_date {
+                events.push(event(date.timestamp(), TimelineEventKind::Discovery));
+            }
+            if let Some(date) = &vuln.release_date {
+                events.push(event(date.timestamp(), TimelineEventKind::Disclosure));
+            }
+        }
+
+        events.push(TimelineEvent {
+            summary: Some(csaf.document.title.clone()),
+            ..event(
+                tracking.initial_release_date.timestamp(),
+                TimelineEventKind::InitialRelease,
+            )
+        });
+        for revision in tracking.revision_history {
+            events.push(TimelineEvent {
+                version: Some(revision.number.to_string()),
+                summary: Some(revision.summary),
+                ..event(revision.date.timestamp(), TimelineEventKind::Revision)
+            });
+        }
+        events.push(TimelineEvent {
+            version: Some(tracking.version.to_string()),
+            status: Some(status_str(&tracking.status).to_string()),
+            ..event(tracking.current_release_date.timestamp(), TimelineEventKind::Status)
+        });
+    }
+
+    events.sort_by(|a, b| (a.date, a.kind, &a.advisory_id).cmp(&(b.date, b.kind, &b.advisory_id)));
+
+    CveTimeline {
+        cve,
+        advisories,
+        events,
+    }
 }
 
 /// Search status of vulnerability using a free form search query.
@@ -297,11 +1078,13 @@ async fn vex_status(
 ) -> actix_web::Result<HttpResponse> {
     authorizer.require(&user, Permission::ReadSbom)?;
 
+    let hidden = Tlp::hidden(&authorizer, &user);
     let state_clone = Arc::clone(&state);
 
     let (result, _total) = actix_web::web::block(move || {
-        state_clone.index.search(
+        state_clone.index.search_filtered(
             "-sort:indexedTimestamp",
+            state_clone.index.index().tlp_filter(&hidden),
             0,
             1,
             SearchOptions {
@@ -334,6 +1117,27 @@ async fn vex_status(
     }
 }
 
+/// Report how current the VEX index is, suitable for monitoring.
+#[utoipa::path(
+    get,
+    tag = "vexination",
+    path = "/api/v1/vex/freshness",
+    responses(
+        (status = 200, description = "Freshness of the index", body = IndexFreshness),
+        (status = 401, description = "Not authenticated"),
+    ),
+)]
+#[get("/vex/freshness")]
+async fn vex_freshness(
+    state: web::Data<SharedState>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadVex)?;
+
+    Ok(HttpResponse::Ok().json(IndexFreshness::new(state.index.updated_at(), state.index.synced_at())))
+}
+
 /// Delete a VEX doc using its identifier.
 #[utoipa::path(
     delete,
@@ -363,6 +1167,141 @@ async fn delete_vex(
     log::trace!("Deleting VEX using id {id}");
 
     state.storage.delete(id.into()).await.map_err(Error::Storage)?;
+    state
+        .storage
+        .delete_revisions(id.into())
+        .await
+        .map_err(Error::Storage)?;
+    state.storage.delete_conflict(id.into()).await.map_err(Error::Storage)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// List the advisories with conflicting variants.
+///
+/// Conflicts are recorded when advisories with the same identifier but different content are stored from different
+/// origins. Each conflict lists its variants with their digest, tracking version and provenance, marking the one which
+/// is currently stored.
+#[utoipa::path(
+    get,
+    tag = "vexination",
+    path = "/api/v1/vex/conflicts",
+    responses(
+        (status = 200, description = "Conflicts, ordered by the identifier of the advisory"),
+        (status = 401, description = "Not authenticated"),
+    ),
+)]
+#[get("/vex/conflicts")]
+async fn vex_conflicts(
+    state: web::Data<SharedState>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::CreateVex)?;
+
+    let conflicts = state.storage.list_conflicts().await.map_err(Error::Storage)?;
+    Ok(HttpResponse::Ok().json(conflicts))
+}
+
+/// Parameters identifying a variant of an advisory.
+#[derive(Debug, Deserialize)]
+struct VariantParams {
+    /// Identifier of the advisory
+    advisory: String,
+    /// Digest of the variant
+    digest: String,
+}
+
+/// Retrieve a variant of an advisory with conflicting variants.
+#[utoipa::path(
+    get,
+    tag = "vexination",
+    path = "/api/v1/vex/conflicts/variant",
+    responses(
+        (status = 200, description = "The variant"),
+        (status = NOT_FOUND, description = "Advisory has no conflict, or no variant with the digest the user may read"),
+    ),
+    params(
+        ("advisory" = String, Query, description = "Identifier of the VEX"),
+        ("digest" = String, Query, description = "SHA-256 digest of the variant"),
+    )
+)]
+#[get("/vex/conflicts/variant")]
+async fn vex_conflict_variant(
+    state: web::Data<SharedState>,
+    params: web::Query<VariantParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::CreateVex)?;
+
+    let data = state
+        .storage
+        .get_variant((&params.advisory).into(), &params.digest)
+        .await
+        .map_err(Error::Storage)?;
+    // variants are labeled on their own, and may be more restricted than the stored advisory
+    check_tlp(&data, &Tlp::hidden(&authorizer, &user))?;
+    Ok(HttpResponse::Ok().content_type(ContentType::json()).body(data))
+}
+
+/// Parameters passed when resolving a conflict.
+#[derive(Debug, Deserialize)]
+struct ResolveParams {
+    /// Identifier of the advisory
+    advisory: String,
+    /// Digest of the variant to store, keeps the stored one if missing
+    keep: Option<String>,
+}
+
+/// Resolve the conflict of an advisory, storing one of its variants and discarding the others.
+#[utoipa::path(
+    delete,
+    tag = "vexination",
+    path = "/api/v1/vex/conflicts",
+    responses(
+        (status = 204, description = "Conflict resolved"),
+        (status = BAD_REQUEST, description = "No variant with the digest to keep"),
+        (status = NOT_FOUND, description = "Advisory has no conflict"),
+    ),
+    params(
+        ("advisory" = String, Query, description = "Identifier of the VEX"),
+        ("keep" = Option<String>, Query, description = "Digest of the variant to store, defaults to the stored one"),
+    )
+)]
+#[delete("/vex/conflicts")]
+async fn resolve_vex_conflict(
+    state: web::Data<SharedState>,
+    params: web::Query<ResolveParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::CreateVex)?;
+
+    let key = Key::from(&params.advisory);
+    let Some(conflict) = state.storage.get_conflict(key).await.map_err(Error::Storage)? else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    if let Some(keep) = &params.keep {
+        let variant = conflict
+            .variant(keep)
+            .ok_or_else(|| Error::UnknownVariant(keep.clone()))?;
+        if !variant.current {
+            let data = state.storage.get_variant(key, keep).await.map_err(Error::Storage)?;
+            let provenance = variant
+                .provenance
+                .clone()
+                .unwrap_or_else(|| Provenance::new(Source::Api, user.id(), None));
+            log::info!("Resolving conflict of VEX {}, storing variant {keep}", params.advisory);
+            state
+                .storage
+                .put_json_slice(key, &provenance, &data)
+                .await
+                .map_err(Error::Storage)?;
+        }
+    }
+    state.storage.delete_conflict(key).await.map_err(Error::Storage)?;
 
     Ok(HttpResponse::NoContent().finish())
 }
@@ -380,3 +1319,136 @@ async fn delete_vexes(
 
     Ok(HttpResponse::NoContent().finish())
 }
+
+/// Number of walker run reports kept in storage.
+const WALKER_RUNS_RETAINED: usize = 100;
+
+/// Parameters to walker run requests.
+#[derive(Debug, Deserialize)]
+struct WalkerRunsParams {
+    /// Maximum number of runs to return
+    #[serde(default = "default_walker_runs_limit")]
+    limit: usize,
+}
+
+const fn default_walker_runs_limit() -> usize {
+    10
+}
+
+/// Record the report of a run of the VEX walker.
+#[utoipa::path(
+    post,
+    tag = "vexination",
+    path = "/api/v1/walker/runs",
+    responses(
+        (status = 201, description = "Report recorded"),
+        (status = BAD_REQUEST, description = "Invalid report"),
+        (status = 401, description = "Not authenticated"),
+    ),
+)]
+#[post("/walker/runs")]
+async fn publish_walker_run(
+    state: web::Data<SharedState>,
+    run: web::Json<WalkerRun>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::CreateVex)?;
+
+    state
+        .storage
+        .put_walker_run(&run, WALKER_RUNS_RETAINED)
+        .await
+        .map_err(Error::Storage)?;
+
+    Ok(HttpResponse::Created().finish())
+}
+
+/// Get the reports of the latest runs of the VEX walker.
+///
+/// The most recent run is also returned as `latest`, for dashboards to show the current status of the walker.
+#[utoipa::path(
+    get,
+    tag = "vexination",
+    path = "/api/v1/walker/runs",
+    responses(
+        (status = 200, description = "Reports of the latest runs, newest first"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("limit" = Option<usize>, Query, description = "Maximum number of runs to return, defaults to 10"),
+    )
+)]
+#[get("/walker/runs")]
+async fn walker_runs(
+    state: web::Data<SharedState>,
+    params: web::Query<WalkerRunsParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadVex)?;
+
+    let runs = state
+        .storage
+        .get_walker_runs(params.limit.min(WALKER_RUNS_RETAINED))
+        .await
+        .map_err(Error::Storage)?;
+
+    Ok(HttpResponse::Ok().json(WalkerRuns::from(runs)))
+}
+
+/// Get the OpenAPI document of this API version, only containing the operations the caller is permitted to call.
+#[get("/openapi.json")]
+async fn permitted_openapi(
+    version: web::Data<ApiVersion>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> HttpResponse {
+    let openapi = permitted_operations(api_doc(), PERMISSIONS, &authorizer, &user);
+    HttpResponse::Ok().json(version.openapi(openapi))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use trustification_auth::{authenticator::user::UserDetails, authorizer::AuthorizerConfig};
+
+    fn variant(label: &str) -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({
+            "document": {
+                "distribution": { "tlp": { "label": label } },
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn restricted_variants() {
+        let authorizer = Authorizer::new(Some(AuthorizerConfig { anonymous_read: true }));
+        let maintainer = |permissions: &[Permission]| {
+            UserInformation::Authenticated(UserDetails {
+                id: "maintainer".to_string(),
+                permissions: permissions.iter().map(|p| p.as_ref().to_string()).collect(),
+            })
+        };
+
+        // maintainers resolving conflicts only see the variants their labels allow
+        let hidden = Tlp::hidden(&authorizer, &maintainer(&[Permission::CreateVex]));
+        assert!(check_tlp(&variant("GREEN"), &hidden).is_ok());
+        assert!(matches!(check_tlp(&variant("AMBER"), &hidden), Err(Error::Restricted)));
+        assert!(matches!(check_tlp(&variant("RED"), &hidden), Err(Error::Restricted)));
+
+        let hidden = Tlp::hidden(
+            &authorizer,
+            &maintainer(&[Permission::CreateVex, Permission::ReadVexAmber]),
+        );
+        assert!(check_tlp(&variant("AMBER+STRICT"), &hidden).is_ok());
+        assert!(matches!(check_tlp(&variant("RED"), &hidden), Err(Error::Restricted)));
+
+        let hidden = Tlp::hidden(
+            &authorizer,
+            &maintainer(&[Permission::CreateVex, Permission::ReadVexRed]),
+        );
+        assert!(check_tlp(&variant("RED"), &hidden).is_ok());
+    }
+}
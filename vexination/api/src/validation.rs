@@ -0,0 +1,451 @@
+//! Validation of published CSAF documents.
+//!
+//! Parsing a document already enforces most of the CSAF 2.0 JSON schema: required properties, their types and
+//! enumerations. On top of that, the constraints parsing doesn't enforce are checked, along with the checksums and the
+//! detached OpenPGP signature of its provider a document may be published with, like the walker does when retrieving
+//! documents from a provider.
+
+use actix_web::http::header::HeaderMap;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sequoia_openpgp::{
+    parse::{
+        stream::{DetachedVerifierBuilder, MessageLayer, MessageStructure, VerificationHelper},
+        Parse,
+    },
+    policy::StandardPolicy,
+    Cert, KeyHandle,
+};
+use serde_json::Value;
+use sha2::{Digest, Sha256, Sha512};
+use std::{collections::HashSet, path::PathBuf};
+use trustification_storage::Signature;
+
+/// Header carrying the base64 encoded detached OpenPGP signature of the published document.
+pub(crate) const SIGNATURE_HEADER: &str = "x-signature";
+/// Header carrying the hex encoded SHA-256 checksum of the published document.
+pub(crate) const SHA256_HEADER: &str = "x-checksum-sha256";
+/// Header carrying the hex encoded SHA-512 checksum of the published document.
+pub(crate) const SHA512_HEADER: &str = "x-checksum-sha512";
+
+/// What happens to documents failing validation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ValidationMode {
+    /// Refuse them
+    Reject,
+    /// Store them, logging why they are invalid
+    #[default]
+    Warn,
+    /// Store them, recording why they are invalid with their provenance and labeling them in the index
+    Annotate,
+}
+
+/// Configuration of the validation of published documents.
+#[derive(Clone, Debug, Default, clap::Args)]
+#[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "CSAF validation")]
+pub struct ValidationConfig {
+    /// What happens to documents failing validation.
+    #[arg(env = "CSAF_VALIDATION", long = "csaf-validation", value_enum, default_value_t)]
+    pub mode: ValidationMode,
+
+    /// OpenPGP keys of the trusted providers, verifying the signatures of documents. Identified by their file name.
+    #[arg(env = "CSAF_PROVIDER_KEYS", long = "csaf-provider-keys", value_delimiter = ',')]
+    pub keys: Vec<PathBuf>,
+
+    /// Consider documents published without a signature of a trusted provider invalid.
+    #[arg(
+        env = "CSAF_SIGNATURE_REQUIRED",
+        long = "csaf-signature-required",
+        default_value_t = false,
+        requires = "keys"
+    )]
+    pub signature_required: bool,
+}
+
+/// The outcome of validating a document.
+#[derive(Debug, Default)]
+pub struct Validation {
+    /// Why the document is invalid, valid if empty
+    pub errors: Vec<String>,
+    /// The outcome of verifying its signature, if it was published with one or one is required
+    pub signature: Option<Signature>,
+}
+
+/// Validates published documents.
+#[derive(Default)]
+pub struct Validator {
+    mode: ValidationMode,
+    keys: Vec<(String, Cert)>,
+    signature_required: bool,
+}
+
+impl Validator {
+    /// Load the provider keys of the configuration.
+    pub fn load(config: &ValidationConfig) -> anyhow::Result<Self> {
+        let mut keys = Vec::new();
+        for path in &config.keys {
+            let name = path
+                .file_stem()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            keys.push((name, Cert::from_file(path)?));
+        }
+
+        log::info!(
+            "Validating CSAF documents ({:?}) with {} provider keys, signatures are {}",
+            config.mode,
+            keys.len(),
+            if config.signature_required {
+                "required"
+            } else {
+                "optional"
+            }
+        );
+        Ok(Self {
+            mode: config.mode,
+            keys,
+            signature_required: config.signature_required,
+        })
+    }
+
+    /// What happens to documents failing validation.
+    pub(crate) fn mode(&self) -> ValidationMode {
+        self.mode
+    }
+
+    /// Validate a document, which was successfully parsed already, against its checksums and signature.
+    pub(crate) fn validate(&self, headers: &HeaderMap, data: &[u8]) -> Validation {
+        let mut validation = Validation::default();
+
+        match serde_json::from_slice::<Value>(data) {
+            Ok(document) => validation.errors.extend(check_document(&document)),
+            Err(err) => validation.errors.push(format!("invalid JSON: {err}")),
+        }
+
+        if let Some(expected) = header(headers, SHA256_HEADER) {
+            validation.errors.extend(check_digest(
+                "SHA-256",
+                expected,
+                &format!("{:x}", Sha256::digest(data)),
+            ));
+        }
+        if let Some(expected) = header(headers, SHA512_HEADER) {
+            validation.errors.extend(check_digest(
+                "SHA-512",
+                expected,
+                &format!("{:x}", Sha512::digest(data)),
+            ));
+        }
+
+        let signature = header(headers, SIGNATURE_HEADER);
+        if signature.is_some() || self.signature_required {
+            let signature = self.verify(signature, data);
+            if let Some(error) = &signature.error {
+                validation.errors.push(format!("invalid signature: {error}"));
+            }
+            validation.signature = Some(signature);
+        }
+
+        validation
+    }
+
+    /// Verify the base64 encoded detached signature of a document, made by one of the provider keys.
+    fn verify(&self, signature: Option<&str>, data: &[u8]) -> Signature {
+        let result = match signature {
+            Some(signature) => STANDARD
+                .decode(signature)
+                .map_err(|err| format!("invalid encoding: {err}"))
+                .and_then(|signature| self.signer(&signature, data)),
+            None => Err("missing signature".to_string()),
+        };
+        match result {
+            Ok(signer) => Signature {
+                verified: true,
+                signer: Some(signer),
+                error: None,
+            },
+            Err(error) => Signature {
+                verified: false,
+                signer: None,
+                error: Some(error),
+            },
+        }
+    }
+
+    /// The name of the provider key which made the signature.
+    fn signer(&self, signature: &[u8], data: &[u8]) -> Result<String, String> {
+        if self.keys.is_empty() {
+            return Err("no provider keys are configured".to_string());
+        }
+        let policy = StandardPolicy::new();
+        for (name, cert) in &self.keys {
+            let verified = DetachedVerifierBuilder::from_bytes(signature)
+                .and_then(|builder| {
+                    builder.with_policy(
+                        &policy,
+                        None,
+                        Helper {
+                            certs: std::slice::from_ref(cert),
+                        },
+                    )
+                })
+                .and_then(|mut verifier| verifier.verify_bytes(data));
+            if verified.is_ok() {
+                return Ok(name.clone());
+            }
+        }
+        Err("not signed by a trusted provider".to_string())
+    }
+}
+
+struct Helper<'a> {
+    certs: &'a [Cert],
+}
+
+impl VerificationHelper for Helper<'_> {
+    fn get_certs(&mut self, _ids: &[KeyHandle]) -> sequoia_openpgp::Result<Vec<Cert>> {
+        Ok(self.certs.to_vec())
+    }
+
+    fn check(&mut self, structure: MessageStructure) -> sequoia_openpgp::Result<()> {
+        for layer in structure.into_iter() {
+            if let MessageLayer::SignatureGroup { results } = layer {
+                if results.iter().any(|result| result.is_ok()) {
+                    return Ok(());
+                }
+            }
+        }
+        Err(anyhow::anyhow!("no valid signature of a provider key"))
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn check_digest(algorithm: &str, expected: &str, actual: &str) -> Option<String> {
+    // checksum files contain the file name after the digest
+    let expected = expected.split_whitespace().next().unwrap_or_default();
+    match expected.eq_ignore_ascii_case(actual) {
+        true => None,
+        false => Some(format!("{algorithm} mismatch - expected: {expected}, actual: {actual}")),
+    }
+}
+
+/// Check the constraints of the CSAF 2.0 JSON schema parsing doesn't enforce, and that the referenced products are
+/// defined in the product tree (mandatory test 6.1.1).
+fn check_document(document: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    if document["document"]["csaf_version"] != "2.0" {
+        errors.push("unsupported CSAF version, must be 2.0".to_string());
+    }
+    for (name, value) in [
+        ("document title", &document["document"]["title"]),
+        ("publisher name", &document["document"]["publisher"]["name"]),
+        ("tracking id", &document["document"]["tracking"]["id"]),
+    ] {
+        if value.as_str().map_or(true, |value| value.trim().is_empty()) {
+            errors.push(format!("empty {name}"));
+        }
+    }
+    if document["document"]["tracking"]["revision_history"]
+        .as_array()
+        .map_or(true, Vec::is_empty)
+    {
+        errors.push("empty revision history".to_string());
+    }
+
+    let mut defined = HashSet::new();
+    defined_products(&document["product_tree"], &mut defined);
+    let mut missing = Vec::new();
+    for vulnerability in document["vulnerabilities"].as_array().into_iter().flatten() {
+        let referenced = vulnerability["product_status"]
+            .as_object()
+            .into_iter()
+            .flat_map(|status| status.values())
+            .chain(
+                ["remediations", "scores", "threats", "flags"]
+                    .into_iter()
+                    .flat_map(|name| vulnerability[name].as_array().into_iter().flatten())
+                    .flat_map(|entry| [&entry["product_ids"], &entry["products"]]),
+            )
+            .flat_map(|ids| ids.as_array().into_iter().flatten())
+            .filter_map(Value::as_str);
+        for id in referenced {
+            if !defined.contains(id) && !missing.contains(&id) {
+                missing.push(id);
+            }
+        }
+    }
+    if !missing.is_empty() {
+        errors.push(format!("missing definition of product ids: {}", missing.join(", ")));
+    }
+
+    errors
+}
+
+/// Collect the ids of the products defined in (a branch of) the product tree.
+fn defined_products<'a>(tree: &'a Value, defined: &mut HashSet<&'a str>) {
+    let full_product_names = tree["full_product_names"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .chain(tree.get("product"))
+        .chain(
+            tree["relationships"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|relationship| &relationship["full_product_name"]),
+        );
+    defined.extend(full_product_names.filter_map(|product| product["product_id"].as_str()));
+
+    for branch in tree["branches"].as_array().into_iter().flatten() {
+        defined_products(branch, defined);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::http::header::{HeaderName, HeaderValue};
+    use sequoia_openpgp::{
+        cert::prelude::*,
+        serialize::stream::{Message, Signer},
+    };
+    use serde_json::json;
+    use std::io::Write;
+
+    fn advisory(product_status: Value) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "document": {
+                "category": "csaf_vex",
+                "csaf_version": "2.0",
+                "publisher": {
+                    "category": "vendor",
+                    "name": "Red Hat Product Security",
+                    "namespace": "https://www.redhat.com"
+                },
+                "title": "Test advisory",
+                "tracking": {
+                    "current_release_date": "2023-03-21T00:00:00Z",
+                    "id": "RHSA-2023:1441",
+                    "initial_release_date": "2023-03-21T00:00:00Z",
+                    "revision_history": [
+                        {"date": "2023-03-21T00:00:00Z", "number": "1", "summary": "Initial"}
+                    ],
+                    "status": "final",
+                    "version": "1"
+                }
+            },
+            "product_tree": {
+                "branches": [{
+                    "category": "vendor",
+                    "name": "Red Hat",
+                    "branches": [{
+                        "category": "product_name",
+                        "name": "Red Hat Enterprise Linux 8",
+                        "product": {"name": "Red Hat Enterprise Linux 8", "product_id": "BaseOS-8.6.0.Z.EUS"}
+                    }]
+                }],
+                "relationships": [{
+                    "category": "default_component_of",
+                    "product_reference": "openssl-1:1.1.1k-9.el8_6.x86_64",
+                    "relates_to_product_reference": "BaseOS-8.6.0.Z.EUS",
+                    "full_product_name": {
+                        "name": "openssl as a component of Red Hat Enterprise Linux 8",
+                        "product_id": "BaseOS-8.6.0.Z.EUS:openssl-1:1.1.1k-9.el8_6.x86_64"
+                    }
+                }]
+            },
+            "vulnerabilities": [{"cve": "CVE-2023-0286", "product_status": product_status}]
+        }))
+        .unwrap()
+    }
+
+    fn headers(values: &[(&'static str, String)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in values {
+            headers.insert(HeaderName::from_static(name), HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn document() {
+        let valid = advisory(json!({"fixed": ["BaseOS-8.6.0.Z.EUS:openssl-1:1.1.1k-9.el8_6.x86_64"]}));
+        let validation = Validator::default().validate(&HeaderMap::new(), &valid);
+        assert!(validation.errors.is_empty(), "{:?}", validation.errors);
+        assert_eq!(validation.signature, None);
+
+        let invalid = advisory(json!({"known_affected": ["AppStream-8.6.0.Z.EUS:nodejs"]}));
+        assert_eq!(
+            Validator::default().validate(&HeaderMap::new(), &invalid).errors,
+            ["missing definition of product ids: AppStream-8.6.0.Z.EUS:nodejs"]
+        );
+    }
+
+    #[test]
+    fn checksums() {
+        let data = advisory(json!({}));
+        let sha256 = format!("{:x}", Sha256::digest(&data));
+
+        let validation = Validator::default().validate(
+            &headers(&[(SHA256_HEADER, format!("{sha256}  rhsa-2023_1441.json"))]),
+            &data,
+        );
+        assert!(validation.errors.is_empty(), "{:?}", validation.errors);
+
+        let validation = Validator::default().validate(&headers(&[(SHA512_HEADER, sha256)]), &data);
+        assert_eq!(validation.errors.len(), 1);
+        assert!(validation.errors[0].starts_with("SHA-512 mismatch"));
+    }
+
+    #[test]
+    fn signature() {
+        let (cert, _) = CertBuilder::general_purpose(None, Some("csaf@example.com"))
+            .generate()
+            .unwrap();
+        let data = advisory(json!({}));
+
+        let keypair = cert
+            .keys()
+            .unencrypted_secret()
+            .with_policy(&StandardPolicy::new(), None)
+            .for_signing()
+            .next()
+            .unwrap()
+            .key()
+            .clone()
+            .into_keypair()
+            .unwrap();
+        let mut signature = Vec::new();
+        let mut signer = Signer::new(Message::new(&mut signature), keypair)
+            .detached()
+            .build()
+            .unwrap();
+        signer.write_all(&data).unwrap();
+        signer.finalize().unwrap();
+
+        let validator = Validator {
+            keys: vec![("redhat".to_string(), cert)],
+            signature_required: true,
+            ..Default::default()
+        };
+        let validation = validator.validate(&headers(&[(SIGNATURE_HEADER, STANDARD.encode(&signature))]), &data);
+        assert!(validation.errors.is_empty(), "{:?}", validation.errors);
+        assert_eq!(validation.signature.unwrap().signer.as_deref(), Some("redhat"));
+
+        let validation = validator.validate(&HeaderMap::new(), &data);
+        assert_eq!(validation.errors, ["invalid signature: missing signature"]);
+        assert!(!validation.signature.unwrap().verified);
+
+        let mut tampered = data.clone();
+        tampered.push(b'\n');
+        let validation = validator.validate(&headers(&[(SIGNATURE_HEADER, STANDARD.encode(&signature))]), &tampered);
+        assert_eq!(
+            validation.errors,
+            ["invalid signature: not signed by a trusted provider"]
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! CVSS v4 scores of CSAF documents.
+//!
+//! The CSAF model only knows CVSS v2 and v3, the v4 scores are therefore extracted from the raw document.
+
+use serde::Deserialize;
+
+/// A CVSS v4 score, as found in the `cvss_v4` property of a vulnerability score.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cvss4 {
+    pub base_score: f64,
+    #[serde(default)]
+    base_severity: Option<String>,
+    #[serde(default)]
+    pub vector_string: Option<String>,
+}
+
+impl Cvss4 {
+    /// The lowercase severity, derived from the score if the document does not provide it.
+    pub fn severity(&self) -> String {
+        match &self.base_severity {
+            Some(severity) => severity.to_lowercase(),
+            None => match self.base_score {
+                score if score >= 9.0 => "critical",
+                score if score >= 7.0 => "high",
+                score if score >= 4.0 => "medium",
+                score if score > 0.0 => "low",
+                _ => "none",
+            }
+            .to_string(),
+        }
+    }
+}
+
+/// The CVSS v4 scores of all vulnerabilities of a document, in the order of the vulnerabilities.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct Cvss4Scores {
+    #[serde(default)]
+    vulnerabilities: Vec<Vulnerability>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+struct Vulnerability {
+    #[serde(default)]
+    scores: Vec<Score>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+struct Score {
+    #[serde(default)]
+    cvss_v4: Option<Cvss4>,
+}
+
+impl Cvss4Scores {
+    /// Extract the scores from a CSAF document. If any score is malformed, none are returned.
+    pub fn parse(data: &[u8]) -> Self {
+        serde_json::from_slice(data).unwrap_or_else(|err| {
+            log::debug!("Unable to extract CVSS v4 scores: {err}");
+            Self::default()
+        })
+    }
+
+    /// The scores of the vulnerability at the given position.
+    pub fn of(&self, vulnerability: usize) -> impl Iterator<Item = &Cvss4> {
+        self.vulnerabilities
+            .get(vulnerability)
+            .into_iter()
+            .flat_map(|v| v.scores.iter().filter_map(|score| score.cvss_v4.as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_scores() {
+        let scores = Cvss4Scores::parse(
+            br#"{"vulnerabilities": [
+                {"scores": [{"cvss_v3": {"baseScore": 7.4}}]},
+                {"scores": [{"cvss_v4": {"baseScore": 9.3, "baseSeverity": "CRITICAL"}}, {"cvss_v4": {"baseScore": 5.1}}]}
+            ]}"#,
+        );
+
+        assert_eq!(scores.of(0).count(), 0);
+        let second: Vec<_> = scores.of(1).map(|s| (s.base_score, s.severity())).collect();
+        assert_eq!(second, vec![(9.3, "critical".to_string()), (5.1, "medium".to_string())]);
+        assert_eq!(scores.of(2).count(), 0);
+    }
+
+    #[test]
+    fn invalid_scores() {
+        let scores = Cvss4Scores::parse(br#"{"vulnerabilities": [{"scores": [{"cvss_v4": {"baseScore": "high"}}]}]}"#);
+        assert_eq!(scores, Cvss4Scores::default());
+    }
+}
@@ -1,33 +1,43 @@
+mod cvss4;
+mod withdrawal;
+
+pub use withdrawal::Withdrawal;
+
+use crate::cvss4::Cvss4Scores;
 use csaf::{
-    definitions::{BranchesT, NoteCategory, ProductIdT, ProductIdentificationHelper},
+    definitions::{BranchCategory, BranchesT, NoteCategory, ProductIdT, ProductIdentificationHelper},
     product_tree::ProductTree,
+    vulnerability::RemediationCategory,
     Csaf,
 };
 use log::{debug, warn};
 use serde_json::{Map, Value};
 use sikula::prelude::*;
 use std::{
-    collections::{hash_map::Entry, HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
     time::Duration,
 };
 use time::OffsetDateTime;
-use trustification_api::search::SearchOptions;
+use trustification_api::search::{SearchField, SearchOptions};
 use trustification_index::{
-    boost, create_date_query, create_float_query, create_i64_query, create_string_query, create_string_query_case,
-    create_text_query, field2date, field2float, field2str, field2str_opt, field2strvec,
+    boost, create_boolean_query, create_date_query, create_float_query, create_i64_query, create_string_query,
+    create_string_query_case, create_text_query, create_tokenized_query,
+    export::{Column, ColumnType},
+    field2date, field2float, field2str, field2str_opt, field2strvec,
     metadata::doc2metadata,
-    sort_by,
+    search_field, search_predicate, sort_by,
     tantivy::{
         self,
         collector::TopDocs,
         doc,
-        query::{AllQuery, BooleanQuery, Query, TermSetQuery},
+        query::{AllQuery, BooleanQuery, Occur, Query, TermSetQuery},
         schema::{Field, Schema, Term, FAST, INDEXED, STORED, STRING, TEXT},
         store::ZstdCompressor,
         DateTime, DocAddress, DocId, IndexSettings, Score, Searcher, SegmentReader, SnippetGenerator,
     },
-    term2query, Case, Document, Error as SearchError, SearchQuery,
+    term2query, term_any, Case, Document, Error as SearchError, SearchQuery,
 };
+use url::Url;
 use vexination_model::prelude::*;
 
 pub struct Index {
@@ -44,12 +54,40 @@ struct Fields {
     advisory_id_raw: Field,
 
     advisory_status: Field,
+    /// the CSAF profile of the document, like `csaf_vex`
+    advisory_category: Field,
     advisory_title: Field,
     advisory_description: Field,
     advisory_severity: Field,
     advisory_revision: Field,
+    /// the current version of the advisory
+    advisory_version: Field,
+    /// the version numbers of all revisions of the advisory
+    advisory_revision_number: Field,
+    /// the URLs of the document references, normalized by [`normalize_reference`]
+    advisory_reference: Field,
+    /// how the advisory was ingested, like `api` or `walker`
+    advisory_source: Field,
+    /// labels added by the enrichment pipeline, like `owner:team-a`
+    advisory_labels: Field,
+    /// the Traffic Light Protocol label of the advisory, unset if not labeled
+    advisory_tlp: Field,
     advisory_initial: Field,
     advisory_current: Field,
+    /// whether the advisory was withdrawn, excluding it from results by default
+    advisory_withdrawn: Field,
+    /// the URLs of the advisories superseding the advisory
+    advisory_superseded_by: Field,
+    /// whether the stored advisory was archived
+    advisory_archived: Field,
+    /// the names of the products and product versions of the product tree
+    advisory_product_name: Field,
+    /// the names of the vendors of the product tree, or the name of the publisher if there are none
+    advisory_vendor: Field,
+    /// the vendor names and the publisher namespace, normalized by [`normalize_vendor`]
+    advisory_vendor_normalized: Field,
+    /// the notes of the advisory and its vulnerabilities, other than descriptions, summaries and legal disclaimers
+    advisory_notes: Field,
 
     advisory_severity_score: Field,
 
@@ -66,7 +104,15 @@ struct Fields {
     cve_affected: Field,
     cve_not_affected: Field,
     cve_cwe: Field,
+    /// the product IDs and URLs of the vendor fixes of the vulnerabilities
+    cve_vendor_fix: Field,
     cve_cvss_max: Field,
+
+    /// the CVSS v4 scores, parallel to the v3 ones in `cve_cvss`
+    cve_cvss4: Field,
+    /// the severities of the CVSS v4 scores, parallel to the v3 ones in `cve_severity`
+    cve_severity4: Field,
+    cve_cvss4_max: Field,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
@@ -91,12 +137,33 @@ impl trustification_index::Index for Index {
             VulnerabilitiesSortable::IndexedTimestamp => sort_by(f.direction, self.fields.indexed_timestamp),
         });
 
+        // withdrawn advisories are only found when asking for them
+        let withdrawn = term_any(&query.term, &|resource| match resource {
+            Vulnerabilities::Withdrawn => true,
+            Vulnerabilities::Include(value) => value.eq_ignore_ascii_case("withdrawn"),
+            _ => false,
+        });
+
         let query = if query.term.is_empty() {
             Box::new(AllQuery)
         } else {
             term2query(&query.term, &|resource| self.resource2query(resource))
         };
 
+        let query = match withdrawn {
+            true => query,
+            false => Box::new(BooleanQuery::new(vec![
+                (Occur::Must, query),
+                (
+                    Occur::MustNot,
+                    create_boolean_query(
+                        Occur::Should,
+                        Term::from_field_bool(self.fields.advisory_withdrawn, true),
+                    ),
+                ),
+            ])),
+        };
+
         log::trace!("Processed query: {:?}", query);
         Ok(SearchQuery { query, sort_by })
     }
@@ -174,6 +241,7 @@ impl trustification_index::Index for Index {
         let advisory_id = field2str(&self.schema, &doc, self.fields.advisory_id_raw)?;
 
         let advisory_title = field2str(&self.schema, &doc, self.fields.advisory_title)?;
+        let advisory_version = field2str_opt(&doc, self.fields.advisory_version).unwrap_or_default();
         let advisory_severity = field2str_opt(&doc, self.fields.advisory_severity);
         let advisory_date = field2date(&self.schema, &doc, self.fields.advisory_current)?;
         let advisory_desc = field2str(&self.schema, &doc, self.fields.advisory_description).unwrap_or("");
@@ -183,7 +251,8 @@ impl trustification_index::Index for Index {
             .map(|s| s.to_string())
             .collect();
 
-        let cvss_max: Option<f64> = field2float(&self.schema, &doc, self.fields.cve_cvss_max).ok();
+        let cvss3_max: Option<f64> = field2float(&self.schema, &doc, self.fields.cve_cvss_max).ok();
+        let cvss4_max: Option<f64> = field2float(&self.schema, &doc, self.fields.cve_cvss4_max).ok();
 
         let mut cve_severity_count: HashMap<String, u64> = HashMap::new();
         if let Some(Some(data)) = doc.get_first(self.fields.cve_severity_count).map(|d| d.as_json()) {
@@ -201,17 +270,49 @@ impl trustification_index::Index for Index {
                     .unwrap_or(time::OffsetDateTime::UNIX_EPOCH.unix_timestamp_nanos() as i64)
             })
             .unwrap_or(time::OffsetDateTime::UNIX_EPOCH.unix_timestamp_nanos() as i64);
+
+        let withdrawn = doc
+            .get_first(self.fields.advisory_withdrawn)
+            .and_then(|value| value.as_bool())
+            .unwrap_or_default();
+        let archived = doc
+            .get_first(self.fields.advisory_archived)
+            .and_then(|value| value.as_bool())
+            .unwrap_or_default();
+        let superseded_by = field2strvec(&doc, self.fields.advisory_superseded_by)?
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let labels = field2strvec(&doc, self.fields.advisory_labels)?
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let tlp = field2str_opt(&doc, self.fields.advisory_tlp).map(ToString::to_string);
+        let vendors = field2strvec(&doc, self.fields.advisory_vendor)?
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
         let document = SearchDocument {
             advisory_id: advisory_id.to_string(),
             advisory_title: advisory_title.to_string(),
+            advisory_version: advisory_version.to_string(),
             advisory_date,
             advisory_snippet,
             advisory_severity: advisory_severity.map(ToString::to_string),
             advisory_desc: advisory_desc.to_string(),
             cves,
-            cvss_max,
+            cvss_max: cvss4_max.or(cvss3_max),
+            cvss3_max,
+            cvss4_max,
             cve_severity_count,
             indexed_timestamp,
+            withdrawn,
+            superseded_by,
+            archived,
+            labels,
+            tlp,
+            vendors,
         };
 
         let explanation = if options.explain {
@@ -235,10 +336,186 @@ impl trustification_index::Index for Index {
             metadata,
         })
     }
+
+    fn search_fields(&self) -> Vec<SearchField> {
+        let field = |qualifier: &str, fields: &[Field], description: &str| {
+            search_field(&self.schema, qualifier, fields, description, |q| {
+                Vulnerabilities::parse(q).is_ok()
+            })
+        };
+        let f = &self.fields;
+
+        vec![
+            field("id", &[f.advisory_id], "Advisory identifier"),
+            field("cve", &[f.cve_id], "CVE identifier of a vulnerability in the advisory"),
+            field(
+                "title",
+                &[f.advisory_title, f.cve_title],
+                "Title of the advisory or its vulnerabilities",
+            ),
+            field(
+                "description",
+                &[f.advisory_description, f.cve_description],
+                "Description of the advisory or its vulnerabilities",
+            ),
+            field(
+                "product",
+                &[f.advisory_product_name],
+                "Name of a product or product version of the advisory, matching its words",
+            ),
+            field(
+                "vendor",
+                &[f.advisory_vendor_normalized],
+                "Vendor of the products or publisher namespace of the advisory, ignoring the letter case",
+            ),
+            field(
+                "vendorFix",
+                &[f.cve_vendor_fix],
+                "Product ID or URL of a vendor fix of a vulnerability, matching its words",
+            ),
+            field(
+                "notes",
+                &[f.advisory_notes],
+                "Notes of the advisory or its vulnerabilities, other than descriptions",
+            ),
+            field("status", &[f.advisory_status], "Status of the advisory"),
+            field(
+                "category",
+                &[f.advisory_category],
+                "CSAF profile of the advisory, like vex or security_advisory",
+            ),
+            field(
+                "revision",
+                &[f.advisory_version, f.advisory_revision_number],
+                "Current version, or version of any revision, of the advisory",
+            ),
+            field(
+                "reference",
+                &[f.advisory_reference],
+                "URL of a reference of the advisory, ignoring the scheme and letter case",
+            ),
+            field(
+                "source",
+                &[f.advisory_source],
+                "How the advisory was ingested: api, walker or federation",
+            ),
+            field(
+                "label",
+                &[f.advisory_labels],
+                "Label added when indexing the advisory, like \"owner:team-a\"",
+            ),
+            field(
+                "tlp",
+                &[f.advisory_tlp],
+                "Traffic Light Protocol label of the advisory: clear, green, amber or red",
+            ),
+            field("severity", &[f.advisory_severity], "Aggregate severity of the advisory"),
+            field(
+                "cvss",
+                &[f.cve_cvss],
+                "CVSS v3 score of a vulnerability in the advisory",
+            ),
+            field(
+                "cvss4",
+                &[f.cve_cvss4],
+                "CVSS v4 score of a vulnerability in the advisory",
+            ),
+            field(
+                "severity4",
+                &[f.cve_severity4],
+                "CVSS v4 severity of a vulnerability in the advisory",
+            ),
+            field(
+                "package",
+                &[f.cve_affected, f.cve_fixed, f.cve_not_affected],
+                "Package URL or CPE of a product referenced by the advisory",
+            ),
+            field("fixed", &[f.cve_fixed], "Package URL or CPE of a fixed product"),
+            field(
+                "affected",
+                &[f.cve_affected],
+                "Package URL or CPE of an affected product",
+            ),
+            field(
+                "notAffected",
+                &[f.cve_not_affected],
+                "Package URL or CPE of a product not affected",
+            ),
+            field("initial", &[f.advisory_initial], "Initial release date of the advisory"),
+            field("release", &[f.advisory_current], "Current release date of the advisory"),
+            field("cveRelease", &[f.cve_release], "Release date of a vulnerability"),
+            field("cveDiscovery", &[f.cve_discovery], "Discovery date of a vulnerability"),
+            field(
+                "indexedTimestamp",
+                &[f.indexed_timestamp],
+                "Time the advisory was indexed",
+            ),
+            field(
+                "supersededBy",
+                &[f.advisory_superseded_by],
+                "URL of the advisory superseding an advisory",
+            ),
+            SearchField {
+                example: "include:withdrawn".to_string(),
+                ..field(
+                    "include",
+                    &[],
+                    "Include results which are excluded by default, like withdrawn advisories",
+                )
+            },
+            search_predicate("final", "Advisories with status final"),
+            search_predicate(
+                "withdrawn",
+                "Withdrawn or superseded advisories, only found with this predicate or include:withdrawn",
+            ),
+            search_predicate("archived", "Advisories whose stored document was archived"),
+            search_predicate("vex", "Advisories using the CSAF VEX profile"),
+            search_predicate("advisory", "Advisories using the CSAF security advisory profile"),
+            search_predicate("critical", "Advisories with critical severity"),
+            search_predicate("high", "Advisories with high (important) severity"),
+            search_predicate("medium", "Advisories with medium (moderate) severity"),
+            search_predicate("low", "Advisories with low severity"),
+        ]
+    }
+
+    fn facets(&self) -> Vec<(&'static str, Field)> {
+        let f = &self.fields;
+        vec![("severity", f.advisory_severity), ("status", f.advisory_status)]
+    }
+
+    fn export_columns(&self) -> Vec<Column> {
+        let f = &self.fields;
+        vec![
+            Column::new("id", f.advisory_id_raw, ColumnType::Text).key(),
+            Column::new("category", f.advisory_category, ColumnType::Text),
+            Column::new("title", f.advisory_title, ColumnType::Text),
+            Column::new("description", f.advisory_description, ColumnType::TextList),
+            Column::new("version", f.advisory_version, ColumnType::Text),
+            Column::new("revision_numbers", f.advisory_revision_number, ColumnType::TextList),
+            Column::new("current_release_date", f.advisory_current, ColumnType::Timestamp),
+            Column::new("severity", f.advisory_severity, ColumnType::Text),
+            Column::new("severity_count", f.cve_severity_count, ColumnType::Json),
+            Column::new("source", f.advisory_source, ColumnType::Text),
+            Column::new("labels", f.advisory_labels, ColumnType::TextList),
+            Column::new("tlp", f.advisory_tlp, ColumnType::Text),
+            Column::new("vendors", f.advisory_vendor, ColumnType::TextList),
+            Column::new("withdrawn", f.advisory_withdrawn, ColumnType::Bool),
+            Column::new("superseded_by", f.advisory_superseded_by, ColumnType::TextList),
+            Column::new("archived", f.advisory_archived, ColumnType::Bool),
+            Column::new("cves", f.cve_id, ColumnType::TextList),
+            Column::new("cve_titles", f.cve_title, ColumnType::TextList),
+            Column::new("cvss_max", f.cve_cvss_max, ColumnType::Float64),
+            Column::new("cvss4_max", f.cve_cvss4_max, ColumnType::Float64),
+            Column::new("cwes", f.cve_cwe, ColumnType::TextList),
+            Column::new("affected", f.cve_affected, ColumnType::TextList),
+            Column::new("not_affected", f.cve_not_affected, ColumnType::TextList),
+            Column::new("fixed", f.cve_fixed, ColumnType::TextList),
+        ]
+    }
 }
 
 impl trustification_index::WriteIndex for Index {
-    type Document = Csaf;
+    type Document = (Csaf, Cvss4Scores, Option<Tlp>);
 
     fn name(&self) -> &str {
         "vex"
@@ -251,11 +528,12 @@ impl trustification_index::WriteIndex for Index {
         }
     }
 
-    fn parse_doc(&self, data: &[u8]) -> Result<Csaf, SearchError> {
-        serde_json::from_slice::<Csaf>(data).map_err(|e| SearchError::DocParser(e.to_string()))
+    fn parse_doc(&self, data: &[u8]) -> Result<Self::Document, SearchError> {
+        let csaf = serde_json::from_slice::<Csaf>(data).map_err(|e| SearchError::DocParser(e.to_string()))?;
+        Ok((csaf, Cvss4Scores::parse(data), Tlp::of(data)))
     }
 
-    fn index_doc(&self, id: &str, csaf: &Csaf) -> Result<Vec<(String, Document)>, SearchError> {
+    fn index_doc(&self, id: &str, (csaf, cvss4, tlp): &Self::Document) -> Result<Vec<(String, Document)>, SearchError> {
         let document_status = match &csaf.document.tracking.status {
             csaf::document::Status::Draft => "draft",
             csaf::document::Status::Interim => "interim",
@@ -268,6 +546,7 @@ impl trustification_index::WriteIndex for Index {
             self.fields.advisory_id => id.to_uppercase(),
             self.fields.advisory_id_raw => id,
             self.fields.advisory_status => document_status,
+            self.fields.advisory_category => document_category(&csaf.document.category),
             self.fields.advisory_title => csaf.document.title.clone(),
         );
 
@@ -282,11 +561,33 @@ impl trustification_index::WriteIndex for Index {
                     NoteCategory::Description | NoteCategory::Summary => {
                         document.add_text(self.fields.advisory_description, &note.text);
                     }
-                    _ => {}
+                    NoteCategory::LegalDisclaimer => {}
+                    _ => {
+                        document.add_text(self.fields.advisory_notes, &note.text);
+                    }
                 }
             }
         }
 
+        if let Some(tree) = &csaf.product_tree {
+            for name in product_names(tree) {
+                document.add_text(self.fields.advisory_product_name, name);
+            }
+        }
+
+        let mut vendors = csaf.product_tree.as_ref().map(vendor_names).unwrap_or_default();
+        if vendors.is_empty() {
+            vendors.insert(&csaf.document.publisher.name);
+        }
+        for vendor in vendors {
+            document.add_text(self.fields.advisory_vendor, vendor);
+            document.add_text(self.fields.advisory_vendor_normalized, normalize_vendor(vendor));
+        }
+        document.add_text(
+            self.fields.advisory_vendor_normalized,
+            normalize_vendor(csaf.document.publisher.namespace.as_str()),
+        );
+
         if let Some(severity) = &csaf.document.aggregate_severity {
             let severity = severity.text.to_lowercase();
             document.add_text(self.fields.advisory_severity, &severity);
@@ -300,8 +601,29 @@ impl trustification_index::WriteIndex for Index {
             document.add_f64(self.fields.advisory_severity_score, score);
         }
 
+        document.add_text(self.fields.advisory_version, csaf.document.tracking.version.to_string());
         for revision in &csaf.document.tracking.revision_history {
             document.add_text(self.fields.advisory_revision, &revision.summary);
+            document.add_text(self.fields.advisory_revision_number, revision.number.to_string());
+        }
+
+        if let Some(references) = &csaf.document.references {
+            for reference in references {
+                document.add_text(
+                    self.fields.advisory_reference,
+                    normalize_reference(reference.url.as_str()),
+                );
+            }
+        }
+
+        if let Some(tlp) = tlp {
+            document.add_text(self.fields.advisory_tlp, tlp.as_str());
+        }
+
+        let withdrawal = Withdrawal::of(csaf);
+        document.add_bool(self.fields.advisory_withdrawn, withdrawal.withdrawn);
+        for url in withdrawal.superseded_by {
+            document.add_text(self.fields.advisory_superseded_by, url);
         }
 
         document.add_date(
@@ -314,14 +636,15 @@ impl trustification_index::WriteIndex for Index {
             DateTime::from_timestamp_millis(csaf.document.tracking.current_release_date.timestamp_millis()),
         );
 
-        let mut cve_severities: HashMap<&str, usize> = HashMap::new();
+        let mut cve_severities: HashMap<String, usize> = HashMap::new();
         let mut cvss_max: Option<f64> = None;
+        let mut cvss4_max: Option<f64> = None;
         let mut fixed: HashSet<String> = HashSet::new();
         let mut affected: HashSet<String> = HashSet::new();
         let mut no_affected: HashSet<String> = HashSet::new();
 
         if let Some(vulns) = &csaf.vulnerabilities {
-            for vuln in vulns {
+            for (index, vuln) in vulns.iter().enumerate() {
                 if let Some(title) = &vuln.title {
                     document.add_text(self.fields.cve_title, title);
                 }
@@ -330,31 +653,28 @@ impl trustification_index::WriteIndex for Index {
                     document.add_text(self.fields.cve_id, cve.to_uppercase());
                 }
 
+                // the severities are counted by CVSS v4, if the vulnerability has such scores
+                let mut has_cvss4 = false;
+                for score in cvss4.of(index) {
+                    has_cvss4 = true;
+                    document.add_f64(self.fields.cve_cvss4, score.base_score);
+                    update_max(&mut cvss4_max, score.base_score);
+
+                    let severity = score.severity();
+                    document.add_text(self.fields.cve_severity4, &severity);
+                    *cve_severities.entry(severity).or_default() += 1;
+                }
+
                 if let Some(scores) = &vuln.scores {
                     for score in scores {
                         if let Some(cvss3) = &score.cvss_v3 {
                             document.add_f64(self.fields.cve_cvss, cvss3.score().value());
-
-                            match &mut cvss_max {
-                                Some(current) => {
-                                    if cvss3.score().value() > *current {
-                                        *current = cvss3.score().value();
-                                    }
-                                }
-                                None => {
-                                    cvss_max.replace(cvss3.score().value());
-                                }
-                            }
+                            update_max(&mut cvss_max, cvss3.score().value());
 
                             document.add_text(self.fields.cve_severity, cvss3.severity().as_str());
-                            match cve_severities.entry(cvss3.severity().as_str()) {
-                                Entry::Occupied(o) => {
-                                    *o.into_mut() += 1;
-                                }
-                                Entry::Vacant(v) => {
-                                    v.insert(1);
-                                }
-                            };
+                            if !has_cvss4 {
+                                *cve_severities.entry(cvss3.severity().as_str().to_string()).or_default() += 1;
+                            }
                         }
                     }
                 }
@@ -365,12 +685,30 @@ impl trustification_index::WriteIndex for Index {
 
                 if let Some(notes) = &vuln.notes {
                     for note in notes {
-                        if let NoteCategory::Description = note.category {
-                            document.add_text(self.fields.cve_description, note.text.as_str());
+                        match note.category {
+                            NoteCategory::Description => {
+                                document.add_text(self.fields.cve_description, note.text.as_str());
+                            }
+                            NoteCategory::LegalDisclaimer => {}
+                            _ => {
+                                document.add_text(self.fields.advisory_notes, note.text.as_str());
+                            }
                         }
                     }
                 }
 
+                for remediation in vuln.remediations.iter().flatten() {
+                    if !matches!(remediation.category, RemediationCategory::VendorFix) {
+                        continue;
+                    }
+                    if let Some(url) = &remediation.url {
+                        document.add_text(self.fields.cve_vendor_fix, url.as_str());
+                    }
+                    for product_id in remediation.product_ids.iter().flatten() {
+                        document.add_text(self.fields.cve_vendor_fix, &product_id.0);
+                    }
+                }
+
                 if let Some(status) = &vuln.product_status {
                     if let Some(products) = &status.known_affected {
                         for product in products {
@@ -470,14 +808,17 @@ impl trustification_index::WriteIndex for Index {
             }
 
             let mut json_severities: Map<String, Value> = Map::new();
-            for (key, value) in cve_severities.iter() {
-                json_severities.insert(key.to_string(), Value::Number((*value).into()));
+            for (key, value) in cve_severities {
+                json_severities.insert(key, Value::Number(value.into()));
             }
             document.add_json_object(self.fields.cve_severity_count, json_severities);
 
             if let Some(cvss_max) = cvss_max {
                 document.add_f64(self.fields.cve_cvss_max, cvss_max);
             }
+            if let Some(cvss4_max) = cvss4_max {
+                document.add_f64(self.fields.cve_cvss4_max, cvss4_max);
+            }
             debug!("Adding doc: {:?}", document);
         }
         documents.push((id.to_string(), document));
@@ -491,6 +832,24 @@ impl trustification_index::WriteIndex for Index {
             .expect("the document schema defines this field")
     }
 
+    fn partition_year(&self, (csaf, ..): &Self::Document) -> Option<i32> {
+        OffsetDateTime::from_unix_timestamp(csaf.document.tracking.initial_release_date.timestamp())
+            .ok()
+            .map(|date| date.year())
+    }
+
+    fn source_field(&self) -> Option<Field> {
+        Some(self.fields.advisory_source)
+    }
+
+    fn label_field(&self) -> Option<Field> {
+        Some(self.fields.advisory_labels)
+    }
+
+    fn archived_field(&self) -> Option<Field> {
+        Some(self.fields.advisory_archived)
+    }
+
     fn schema(&self) -> Schema {
         self.schema.clone()
     }
@@ -510,14 +869,28 @@ impl Index {
 
         let advisory_id = schema.add_text_field("advisory_id", STRING | FAST);
         let advisory_id_raw = schema.add_text_field("advisory_id_raw", STRING | STORED);
-        let advisory_status = schema.add_text_field("advisory_status", STRING);
+        let advisory_status = schema.add_text_field("advisory_status", STRING | FAST);
+        let advisory_category = schema.add_text_field("advisory_category", STRING | FAST | STORED);
         let advisory_title = schema.add_text_field("advisory_title", TEXT | STORED);
         let advisory_description = schema.add_text_field("advisory_description", TEXT | STORED);
         let advisory_revision = schema.add_text_field("advisory_revision", STRING | STORED);
-        let advisory_severity = schema.add_text_field("advisory_severity", STRING | STORED);
+        let advisory_version = schema.add_text_field("advisory_version", STRING | FAST | STORED);
+        let advisory_revision_number = schema.add_text_field("advisory_revision_number", STRING | STORED);
+        let advisory_severity = schema.add_text_field("advisory_severity", STRING | FAST | STORED);
+        let advisory_reference = schema.add_text_field("advisory_reference", STRING);
+        let advisory_source = schema.add_text_field("advisory_source", STRING | STORED);
+        let advisory_labels = schema.add_text_field("advisory_labels", STRING | STORED);
+        let advisory_tlp = schema.add_text_field("advisory_tlp", STRING | STORED);
         let advisory_initial = schema.add_date_field("advisory_initial_date", INDEXED);
         let advisory_current = schema.add_date_field("advisory_current_date", INDEXED | FAST | STORED);
         let advisory_severity_score = schema.add_f64_field("advisory_severity_score", FAST);
+        let advisory_withdrawn = schema.add_bool_field("advisory_withdrawn", INDEXED | STORED);
+        let advisory_superseded_by = schema.add_text_field("advisory_superseded_by", STRING | STORED);
+        let advisory_archived = schema.add_bool_field("advisory_archived", INDEXED | STORED);
+        let advisory_product_name = schema.add_text_field("advisory_product_name", TEXT);
+        let advisory_vendor = schema.add_text_field("advisory_vendor", STRING | STORED);
+        let advisory_vendor_normalized = schema.add_text_field("advisory_vendor_normalized", STRING);
+        let advisory_notes = schema.add_text_field("advisory_notes", TEXT);
 
         let cve_id = schema.add_text_field("cve_id", STRING | FAST | STORED);
         let cve_title = schema.add_text_field("cve_title", TEXT | STORED);
@@ -531,6 +904,10 @@ impl Index {
         let cve_cvss = schema.add_f64_field("cve_cvss", FAST | INDEXED | STORED);
         let cve_cvss_max = schema.add_f64_field("cve_cvss_max", FAST | STORED);
         let cve_cwe = schema.add_text_field("cve_cwe", STRING | STORED);
+        let cve_vendor_fix = schema.add_text_field("cve_vendor_fix", TEXT);
+        let cve_cvss4 = schema.add_f64_field("cve_cvss4", FAST | INDEXED | STORED);
+        let cve_severity4 = schema.add_text_field("cve_severity4", STRING | FAST);
+        let cve_cvss4_max = schema.add_f64_field("cve_cvss4_max", FAST | STORED);
 
         let cve_severity_count = schema.add_json_field("cve_severity_count", STORED);
 
@@ -542,13 +919,27 @@ impl Index {
                 advisory_id,
                 advisory_id_raw,
                 advisory_status,
+                advisory_category,
                 advisory_title,
                 advisory_description,
                 advisory_revision,
+                advisory_version,
+                advisory_revision_number,
+                advisory_reference,
+                advisory_source,
+                advisory_labels,
+                advisory_tlp,
                 advisory_severity,
                 advisory_initial,
                 advisory_current,
                 advisory_severity_score,
+                advisory_withdrawn,
+                advisory_superseded_by,
+                advisory_archived,
+                advisory_product_name,
+                advisory_vendor,
+                advisory_vendor_normalized,
+                advisory_notes,
 
                 cve_id,
                 cve_title,
@@ -561,17 +952,39 @@ impl Index {
                 cve_cvss,
                 cve_cvss_max,
                 cve_cwe,
+                cve_vendor_fix,
                 cve_severity_count,
                 cve_not_affected,
+                cve_cvss4,
+                cve_severity4,
+                cve_cvss4_max,
             },
         }
     }
 
+    /// A filter excluding the advisories with any of the given TLP labels, `None` if no label is excluded.
+    pub fn tlp_filter(&self, excluded: &[Tlp]) -> Option<Box<dyn Query>> {
+        if excluded.is_empty() {
+            return None;
+        }
+        let terms = excluded
+            .iter()
+            .map(|tlp| Term::from_field_text(self.fields.advisory_tlp, tlp.as_str()))
+            .collect();
+        Some(Box::new(BooleanQuery::new(vec![
+            (Occur::Must, Box::new(AllQuery)),
+            (Occur::MustNot, Box::new(TermSetQuery::new(terms))),
+        ])))
+    }
+
     fn resource2query(&self, resource: &Vulnerabilities) -> Box<dyn Query> {
         const ID_WEIGHT: f32 = 1.5;
         const CVE_ID_WEIGHT: f32 = 1.4;
         const ADV_TITLE_WEIGHT: f32 = 1.3;
         const CVE_TITLE_WEIGHT: f32 = 1.3;
+        const PRODUCT_WEIGHT: f32 = 1.2;
+        const VENDOR_FIX_WEIGHT: f32 = 1.1;
+        const NOTES_WEIGHT: f32 = 0.5;
         match resource {
             Vulnerabilities::Id(primary) => boost(
                 create_string_query_case(self.fields.advisory_id, primary, Case::Uppercase),
@@ -594,6 +1007,21 @@ impl Index {
                 Box::new(BooleanQuery::union(vec![q1, q2]))
             }
 
+            Vulnerabilities::Product(primary) => boost(
+                create_tokenized_query(self.fields.advisory_product_name, primary),
+                PRODUCT_WEIGHT,
+            ),
+
+            Vulnerabilities::VendorFix(primary) => boost(
+                create_tokenized_query(self.fields.cve_vendor_fix, primary),
+                VENDOR_FIX_WEIGHT,
+            ),
+
+            Vulnerabilities::Notes(primary) => boost(
+                create_tokenized_query(self.fields.advisory_notes, primary),
+                NOTES_WEIGHT,
+            ),
+
             Vulnerabilities::Package(primary) => {
                 let q1 = create_rewrite_string_query(self.fields.cve_affected, primary);
                 let q2 = create_rewrite_string_query(self.fields.cve_fixed, primary);
@@ -618,24 +1046,93 @@ impl Index {
                 value,
             )])),
 
+            Vulnerabilities::Source(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                self.fields.advisory_source,
+                &value.to_ascii_lowercase(),
+            )])),
+
+            Vulnerabilities::Vendor(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                self.fields.advisory_vendor_normalized,
+                &normalize_vendor(value),
+            )])),
+
+            Vulnerabilities::Label(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                self.fields.advisory_labels,
+                value,
+            )])),
+
+            Vulnerabilities::Tlp(value) => {
+                let value = match value.to_ascii_lowercase() {
+                    // the TLP v1 name of the clear label
+                    value if value == "white" => Tlp::Clear.as_str().to_string(),
+                    value => value,
+                };
+                Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                    self.fields.advisory_tlp,
+                    &value,
+                )]))
+            }
+
+            Vulnerabilities::Category(value) => {
+                let value = value.to_ascii_lowercase();
+                // allow omitting the "csaf_" prefix of the profile
+                let mut terms = vec![Term::from_field_text(self.fields.advisory_category, &value)];
+                if !value.starts_with(CATEGORY_PREFIX) {
+                    terms.push(Term::from_field_text(
+                        self.fields.advisory_category,
+                        &format!("{CATEGORY_PREFIX}{value}"),
+                    ));
+                }
+                Box::new(TermSetQuery::new(terms))
+            }
+
+            Vulnerabilities::Revision(primary) => {
+                let q1 = create_string_query(self.fields.advisory_version, primary);
+                let q2 = create_string_query(self.fields.advisory_revision_number, primary);
+                Box::new(BooleanQuery::union(vec![q1, q2]))
+            }
+
+            Vulnerabilities::Reference(Primary::Equal(value)) => create_string_query(
+                self.fields.advisory_reference,
+                &Primary::Equal(&normalize_reference(value)),
+            ),
+
+            Vulnerabilities::Reference(Primary::Partial(value)) => create_string_query(
+                self.fields.advisory_reference,
+                &Primary::Partial(&normalize_reference(value)),
+            ),
+
             Vulnerabilities::Final => create_string_query(self.fields.advisory_status, &Primary::Equal("final")),
+            Vulnerabilities::Vex => create_string_query(self.fields.advisory_category, &Primary::Equal("csaf_vex")),
+            Vulnerabilities::Advisory => {
+                create_string_query(self.fields.advisory_category, &Primary::Equal("csaf_security_advisory"))
+            }
             Vulnerabilities::Critical => Box::new(TermSetQuery::new(vec![
                 Term::from_field_text(self.fields.cve_severity, "critical"),
+                Term::from_field_text(self.fields.cve_severity4, "critical"),
                 Term::from_field_text(self.fields.advisory_severity, "critical"),
             ])),
             Vulnerabilities::High => Box::new(TermSetQuery::new(vec![
                 Term::from_field_text(self.fields.cve_severity, "high"),
+                Term::from_field_text(self.fields.cve_severity4, "high"),
                 Term::from_field_text(self.fields.advisory_severity, "important"),
             ])),
             Vulnerabilities::Medium => Box::new(TermSetQuery::new(vec![
                 Term::from_field_text(self.fields.cve_severity, "medium"),
+                Term::from_field_text(self.fields.cve_severity4, "medium"),
                 Term::from_field_text(self.fields.advisory_severity, "moderate"),
             ])),
             Vulnerabilities::Low => Box::new(TermSetQuery::new(vec![
                 Term::from_field_text(self.fields.cve_severity, "low"),
+                Term::from_field_text(self.fields.cve_severity4, "low"),
                 Term::from_field_text(self.fields.advisory_severity, "low"),
             ])),
             Vulnerabilities::Cvss(ordered) => create_float_query(&self.schema, [self.fields.cve_cvss], ordered),
+            Vulnerabilities::Cvss4(ordered) => create_float_query(&self.schema, [self.fields.cve_cvss4], ordered),
+            Vulnerabilities::Severity4(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                self.fields.cve_severity4,
+                &value.to_ascii_lowercase(),
+            )])),
             Vulnerabilities::Initial(ordered) => create_date_query(&self.schema, self.fields.advisory_initial, ordered),
             Vulnerabilities::Release(ordered) => create_date_query(&self.schema, self.fields.advisory_current, ordered),
             Vulnerabilities::CveRelease(ordered) => create_date_query(&self.schema, self.fields.cve_release, ordered),
@@ -645,10 +1142,64 @@ impl Index {
             Vulnerabilities::IndexedTimestamp(value) => {
                 create_i64_query(&self.schema, self.fields.indexed_timestamp, value)
             }
+            Vulnerabilities::SupersededBy(primary) => create_string_query(self.fields.advisory_superseded_by, primary),
+            // only changes which results are excluded, see `prepare_query`
+            Vulnerabilities::Include(_) => Box::new(AllQuery),
+            Vulnerabilities::Withdrawn => create_boolean_query(
+                Occur::Should,
+                Term::from_field_bool(self.fields.advisory_withdrawn, true),
+            ),
+            Vulnerabilities::Archived => create_boolean_query(
+                Occur::Should,
+                Term::from_field_bool(self.fields.advisory_archived, true),
+            ),
         }
     }
 }
 
+const CATEGORY_PREFIX: &str = "csaf_";
+
+fn update_max(max: &mut Option<f64>, value: f64) {
+    match max {
+        Some(current) if *current >= value => {}
+        _ => *max = Some(value),
+    }
+}
+
+/// The indexed value of the document category, as it is used in the CSAF document.
+fn document_category(category: &csaf::document::Category) -> String {
+    match category {
+        csaf::document::Category::Base => "csaf_base".to_string(),
+        csaf::document::Category::SecurityAdvisory => "csaf_security_advisory".to_string(),
+        csaf::document::Category::Vex => "csaf_vex".to_string(),
+        csaf::document::Category::Other(other) => other.to_ascii_lowercase(),
+    }
+}
+
+/// Normalize a reference URL, so that different spellings of the same link match.
+///
+/// The scheme, a `www.` prefix, the fragment and a trailing slash are dropped, and the result is lowercase.
+fn normalize_reference(value: &str) -> String {
+    let value = value.trim();
+    let normalized = match Url::parse(value) {
+        Ok(url) if url.has_host() => {
+            let host = url.host_str().unwrap_or_default();
+            let mut normalized = host.strip_prefix("www.").unwrap_or(host).to_string();
+            if let Some(port) = url.port() {
+                normalized.push_str(&format!(":{port}"));
+            }
+            normalized.push_str(url.path().trim_end_matches('/'));
+            if let Some(query) = url.query() {
+                normalized.push('?');
+                normalized.push_str(query);
+            }
+            normalized
+        }
+        _ => value.trim_end_matches('/').to_string(),
+    };
+    normalized.to_lowercase()
+}
+
 fn find_product_identifier<'m, F: Fn(&'m ProductIdentificationHelper) -> Option<R>, R>(
     branches: &'m BranchesT,
     product_id: &'m ProductIdT,
@@ -674,6 +1225,65 @@ fn find_product_identifier<'m, F: Fn(&'m ProductIdentificationHelper) -> Option<
     None
 }
 
+/// The names of the products and product versions of a product tree, without duplicates.
+fn product_names(tree: &ProductTree) -> BTreeSet<&str> {
+    fn collect<'m>(branches: &'m BranchesT, names: &mut BTreeSet<&'m str>) {
+        for branch in &branches.0 {
+            if let Some(product) = &branch.product {
+                names.insert(&product.name);
+            }
+            if let Some(branches) = &branch.branches {
+                collect(branches, names);
+            }
+        }
+    }
+
+    let mut names = BTreeSet::new();
+    if let Some(branches) = &tree.branches {
+        collect(branches, &mut names);
+    }
+    for product in tree.full_product_names.iter().flatten() {
+        names.insert(&product.name);
+    }
+    names
+}
+
+/// The names of the vendor branches of a product tree, without duplicates.
+fn vendor_names(tree: &ProductTree) -> BTreeSet<&str> {
+    fn collect<'m>(branches: &'m BranchesT, names: &mut BTreeSet<&'m str>) {
+        for branch in &branches.0 {
+            if matches!(branch.category, BranchCategory::Vendor) {
+                names.insert(&branch.name);
+            }
+            if let Some(branches) = &branch.branches {
+                collect(branches, names);
+            }
+        }
+    }
+
+    let mut names = BTreeSet::new();
+    if let Some(branches) = &tree.branches {
+        collect(branches, &mut names);
+    }
+    names
+}
+
+/// Normalize the name of a vendor, or a publisher namespace, for case-insensitive matching.
+///
+/// Namespaces are reduced to their host, without a `www.` prefix, so that `https://www.redhat.com` matches
+/// `redhat.com`.
+fn normalize_vendor(value: &str) -> String {
+    let value = value.trim().to_lowercase();
+    let value = match Url::parse(&value) {
+        Ok(url) if url.has_host() => url.host_str().unwrap_or_default().to_string(),
+        _ => value,
+    };
+    match value.strip_prefix("www.") {
+        Some(host) => host.to_string(),
+        None => value,
+    }
+}
+
 fn find_product_ref<'m>(tree: &'m ProductTree, product_id: &ProductIdT) -> Option<(&'m ProductIdT, &'m ProductIdT)> {
     if let Some(rs) = &tree.relationships {
         for r in rs {
@@ -747,7 +1357,7 @@ fn rewrite_cpe_partial(value: &str) -> String {
 #[cfg(test)]
 mod tests {
     use std::fmt::Display;
-    use trustification_index::IndexStore;
+    use trustification_index::{IndexStore, PartitionConfig};
 
     use super::*;
 
@@ -770,8 +1380,15 @@ mod tests {
         let _ = env_logger::try_init();
 
         let index = Index::new();
-        let mut store = IndexStore::new_in_memory(index).unwrap();
+        assert_search_in(IndexStore::new_in_memory(index).unwrap(), advisories, f)
+    }
 
+    fn assert_search_in<F, I, S>(mut store: IndexStore<Index>, advisories: I, f: F)
+    where
+        F: FnOnce(IndexStore<Index>),
+        I: IntoIterator<Item = S>,
+        S: Display,
+    {
         let mut writer = store.writer().unwrap();
         for advisory in advisories {
             let data = std::fs::read_to_string(format!("../testdata/{}.json", advisory)).unwrap();
@@ -787,6 +1404,40 @@ mod tests {
         f(store);
     }
 
+    /// How an advisory is stored, besides its document.
+    #[derive(Default)]
+    struct Stored {
+        source: Option<&'static str>,
+        archived: bool,
+    }
+
+    /// Index the advisories after applying `edit` to each of their documents, which may also change how they are
+    /// stored, and search them with `f`.
+    fn assert_search_edited<F, E>(advisories: &[&str], mut edit: E, f: F)
+    where
+        F: FnOnce(IndexStore<Index>),
+        E: FnMut(&str, &mut Value, &mut Stored),
+    {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        for advisory in advisories {
+            let data = std::fs::read_to_string(format!("../testdata/{}.json", advisory)).unwrap();
+            let mut csaf: Value = serde_json::from_str(&data).unwrap();
+            let mut stored = Stored::default();
+            edit(advisory, &mut csaf, &mut stored);
+            let id = csaf["document"]["tracking"]["id"].as_str().unwrap().to_string();
+            let data = serde_json::to_vec(&csaf).unwrap();
+            writer
+                .add_stored_document(store.index_as_mut(), &id, &data, stored.source, stored.archived, &[])
+                .unwrap();
+        }
+        writer.commit().unwrap();
+
+        f(store);
+    }
+
     fn search(index: &IndexStore<Index>, query: &str) -> (Vec<SearchHit>, usize) {
         index
             .search(
@@ -815,6 +1466,35 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_partitioned() {
+        let _ = env_logger::try_init();
+
+        let config = PartitionConfig {
+            enabled: true,
+            hot_years: 1,
+            first_year: 2020,
+            ..Default::default()
+        };
+        let store = IndexStore::new_in_memory_with_partitions(Index::new(), &config).unwrap();
+        assert_search_in(
+            store,
+            ["rhsa-2023_1441", "rhsa-2021_3029", "rhsa-2023_3408", "rhsa-2023_4378"],
+            |index| {
+                assert_eq!(index.get_total_docs().unwrap(), 4);
+
+                let (result, total) = search(&index, "-sort:release");
+                assert_eq!(total, 4);
+                assert_eq!("RHSA-2023:4378", &result[0].document.advisory_id);
+                assert_eq!("RHSA-2021:3029", &result[3].document.advisory_id);
+
+                let (result, total) = search(&index, "openssl");
+                assert_eq!(total, 2);
+                assert_eq!(result.len(), 2);
+            },
+        );
+    }
+
     #[tokio::test]
     async fn test_lowercase_id() {
         assert_search_with(["lowercase-id"], |index| {
@@ -887,6 +1567,26 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_category() {
+        assert_search(|index| {
+            let result = search(&index, "is:vex");
+            assert_eq!(result.0.len(), 4);
+
+            let result = search(&index, "is:advisory");
+            assert_eq!(result.0.len(), 0);
+
+            let result = search(&index, "category:vex");
+            assert_eq!(result.0.len(), 4);
+
+            let result = search(&index, "category:CSAF_VEX");
+            assert_eq!(result.0.len(), 4);
+
+            let result = search(&index, "openssl is:advisory");
+            assert_eq!(result.0.len(), 0);
+        });
+    }
+
     #[tokio::test]
     async fn test_free_form_predicate_high() {
         assert_search(|index| {
@@ -903,6 +1603,165 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_revision() {
+        assert_search(|index| {
+            let result = search(&index, "revision:1");
+            assert_eq!(result.0.len(), 4);
+            assert!(result.0.iter().all(|hit| hit.document.advisory_version == "1"));
+
+            let result = search(&index, "revision:2");
+            assert_eq!(result.0.len(), 0);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_reference() {
+        assert_search(|index| {
+            let result = search(&index, r#"reference:"https://access.redhat.com/errata/RHSA-2023:1441""#);
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.advisory_id, "RHSA-2023:1441");
+
+            let result = search(&index, r#"reference:"http://access.redhat.com/errata/rhsa-2023:1441/""#);
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, r#"reference:"https://access.redhat.com/errata/RHSA-2023:9999""#);
+            assert_eq!(result.0.len(), 0);
+
+            let result = search(&index, r#"reference:~"https://access.redhat.com/errata/""#);
+            assert_eq!(result.0.len(), 4);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_source() {
+        let edit = |advisory: &str, _: &mut Value, stored: &mut Stored| {
+            if advisory == "rhsa-2023_1441" {
+                stored.source = Some("walker");
+            }
+        };
+        assert_search_edited(&["rhsa-2023_1441", "rhsa-2023_3408"], edit, |store| {
+            let result = search(&store, "source:walker");
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.advisory_id, "RHSA-2023:1441");
+
+            let result = search(&store, "source:api");
+            assert_eq!(result.0.len(), 0);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_archived() {
+        let edit = |advisory: &str, _: &mut Value, stored: &mut Stored| {
+            stored.archived = advisory == "rhsa-2023_1441";
+        };
+        assert_search_edited(&["rhsa-2023_1441", "rhsa-2023_3408"], edit, |store| {
+            let result = search(&store, "is:archived");
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.advisory_id, "RHSA-2023:1441");
+            assert!(result.0[0].document.archived);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_tlp() {
+        let advisories = ["rhsa-2023_1441", "rhsa-2021_3029", "rhsa-2023_3408", "rhsa-2023_4378"];
+        let edit = |advisory: &str, csaf: &mut Value, _: &mut Stored| match advisory {
+            "rhsa-2021_3029" => csaf["document"]["distribution"]["tlp"]["label"] = "WHITE".into(),
+            "rhsa-2023_3408" => csaf["document"]["distribution"]["tlp"]["label"] = "AMBER+STRICT".into(),
+            "rhsa-2023_4378" => csaf["document"]["distribution"]["tlp"]["label"] = "RED".into(),
+            _ => {
+                csaf["document"].as_object_mut().unwrap().remove("distribution");
+            }
+        };
+        assert_search_edited(&advisories, edit, |store| {
+            let result = search(&store, "tlp:white");
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.advisory_id, "RHSA-2021:3029");
+            assert_eq!(result.0[0].document.tlp.as_deref(), Some("clear"));
+
+            let result = search(&store, "tlp:amber");
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.advisory_id, "RHSA-2023:3408");
+
+            let filtered = |excluded: &[Tlp]| {
+                let filter = store.index().tlp_filter(excluded);
+                let (hits, total) = store
+                    .search_filtered("", filter, 0, 100, SearchOptions::default())
+                    .unwrap();
+                let mut ids: Vec<_> = hits.into_iter().map(|hit| hit.document.advisory_id).collect();
+                ids.sort();
+                assert_eq!(ids.len(), total);
+                ids
+            };
+            assert_eq!(filtered(&[]).len(), 4);
+            assert_eq!(
+                filtered(&[Tlp::Red]),
+                vec!["RHSA-2021:3029", "RHSA-2023:1441", "RHSA-2023:3408"]
+            );
+            assert_eq!(
+                filtered(&[Tlp::Amber, Tlp::Red]),
+                vec!["RHSA-2021:3029", "RHSA-2023:1441"]
+            );
+        });
+    }
+
+    #[tokio::test]
+    async fn test_withdrawn() {
+        let edit = |advisory: &str, csaf: &mut Value, _: &mut Stored| {
+            if advisory == "rhsa-2023_3408" {
+                // supersede the advisory by the other one
+                csaf["document"]["category"] = "csaf_superseded".into();
+                csaf["document"]["references"]
+                    .as_array_mut()
+                    .unwrap()
+                    .push(serde_json::json!({
+                        "category": "external",
+                        "summary": "Superseding document",
+                        "url": "https://access.redhat.com/errata/RHSA-2023:1441"
+                    }));
+            }
+        };
+        assert_search_edited(&["rhsa-2023_1441", "rhsa-2023_3408"], edit, |store| {
+            let result = search(&store, "");
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.advisory_id, "RHSA-2023:1441");
+            assert!(!result.0[0].document.withdrawn);
+
+            let result = search(&store, "include:withdrawn");
+            assert_eq!(result.0.len(), 2);
+
+            let result = search(&store, "is:withdrawn");
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.advisory_id, "RHSA-2023:3408");
+            assert!(result.0[0].document.withdrawn);
+            assert_eq!(
+                result.0[0].document.superseded_by,
+                vec!["https://access.redhat.com/errata/RHSA-2023:1441"]
+            );
+
+            let result = search(
+                &store,
+                r#"supersededBy:"https://access.redhat.com/errata/RHSA-2023:1441" include:withdrawn"#,
+            );
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.advisory_id, "RHSA-2023:3408");
+        });
+    }
+
+    #[test]
+    fn test_normalize_reference() {
+        assert_eq!(
+            normalize_reference("https://www.example.com/Advisories/ID-1/#section"),
+            "example.com/advisories/id-1"
+        );
+        assert_eq!(
+            normalize_reference("http://example.com:8080/a?b=c"),
+            "example.com:8080/a?b=c"
+        );
+        assert_eq!(normalize_reference(" RHSA-2023:1441/ "), "rhsa-2023:1441");
+    }
+
     #[tokio::test]
     async fn test_free_form_ranges() {
         assert_search(|index| {
@@ -914,6 +1773,36 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_cvss4() {
+        assert_search_with(["rhsa-2023_1441", "cvss-v4"], |index| {
+            let result = search(&index, "cvss4:>7");
+            assert_eq!(result.0.len(), 1);
+            let document = &result.0[0].document;
+            assert_eq!(document.advisory_id, "RHSA-2024:0001");
+            assert_eq!(document.cvss_max, Some(9.3));
+            assert_eq!(document.cvss3_max, Some(7.4));
+            assert_eq!(document.cvss4_max, Some(9.3));
+            assert_eq!(
+                document.cve_severity_count,
+                HashMap::from([("critical".to_string(), 1)])
+            );
+
+            // v3 scores are still indexed
+            let result = search(&index, "cvss:>7");
+            assert_eq!(result.0.len(), 2);
+
+            let result = search(&index, "severity4:critical");
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, "id:\"RHSA-2023:1441\"");
+            assert_eq!(result.0.len(), 1);
+            let document = &result.0[0].document;
+            assert_eq!(document.cvss_max, Some(7.4));
+            assert_eq!(document.cvss4_max, None);
+        });
+    }
+
     #[tokio::test]
     async fn test_free_form_dates() {
         assert_search(|index| {
@@ -1000,6 +1889,56 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_facets() {
+        assert_search(|index| {
+            let names = ["severity".to_string(), "status".to_string()];
+            let facets = index.facets("", &names).unwrap();
+            assert_eq!(facets["severity"]["important"], 3);
+            assert_eq!(facets["severity"]["moderate"], 1);
+            assert_eq!(facets["status"]["final"], 4);
+
+            let facets = index.facets("severity:Moderate", &names).unwrap();
+            assert_eq!(facets["severity"].len(), 1);
+            assert_eq!(facets["status"]["final"], 1);
+
+            let filter = index.index().tlp_filter(&[Tlp::Red]);
+            let facets = index.facets_filtered("", filter, &names).unwrap();
+            assert_eq!(facets["status"]["final"], 4);
+
+            assert!(index.facets("", &["unknown".to_string()]).is_err());
+        });
+    }
+
+    #[tokio::test]
+    async fn test_vendor() {
+        let edit = |advisory: &str, csaf: &mut Value, _: &mut Stored| {
+            if advisory == "rhsa-2021_3029" {
+                csaf["document"]["publisher"]["namespace"] = "https://example.com".into();
+                csaf["product_tree"]["branches"][0]["name"] = "Example Inc.".into();
+            }
+        };
+        assert_search_edited(&["rhsa-2023_1441", "rhsa-2021_3029"], edit, |store| {
+            for query in [
+                r#"vendor:"Red Hat""#,
+                r#"vendor:"red hat""#,
+                "vendor:redhat.com",
+                r#"vendor:"https://www.redhat.com""#,
+            ] {
+                let result = search(&store, query);
+                assert_eq!(result.0.len(), 1, "{query}");
+                assert_eq!(result.0[0].document.advisory_id, "RHSA-2023:1441");
+                assert_eq!(result.0[0].document.vendors, vec!["Red Hat"]);
+            }
+
+            let result = search(&store, r#"vendor:"Example Inc.""#);
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.advisory_id, "RHSA-2021:3029");
+            assert_eq!(search(&store, "vendor:example.com").0.len(), 1);
+            assert_eq!(search(&store, "vendor:redhat").0.len(), 0);
+        });
+    }
+
     #[tokio::test]
     async fn test_packages() {
         assert_search(|index| {
@@ -1034,6 +1973,35 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_free_form_products() {
+        assert_search(|index| {
+            let result = search(&index, "kernel-rt 9.2");
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.advisory_id, "RHSA-2023:4378");
+
+            let result = search(&index, "nfv");
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, "product:openssl");
+            assert_eq!(result.0.len(), 2);
+
+            let result = search(&index, r#"product:"Enterprise Linux NFV""#);
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, r#"vendorFix:"RHSA-2023:1441""#);
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.advisory_id, "RHSA-2023:1441");
+
+            let result = search(&index, r#"notes:"Secure Sockets Layer""#);
+            assert_eq!(result.0.len(), 2);
+
+            // legal disclaimers are not indexed
+            let result = search(&index, r#"notes:"Creative Commons""#);
+            assert_eq!(result.0.len(), 0);
+        });
+    }
+
     #[tokio::test]
     async fn test_products_partial() {
         assert_search(|index| {
@@ -1151,4 +2119,90 @@ mod tests {
             }
         });
     }
+
+    #[tokio::test]
+    async fn test_search_fields() {
+        assert_search(|index| {
+            let fields = index.search_fields();
+            for field in &fields {
+                search(&index, &field.example);
+            }
+
+            let severity = fields.iter().find(|f| f.qualifier == "severity").unwrap();
+            assert!(severity.sortable);
+            let package = fields.iter().find(|f| f.qualifier == "package").unwrap();
+            assert!(package.scope);
+        });
+    }
+
+    #[test]
+    fn test_search_fields_match_query_language() {
+        // the match is exhaustive, so a qualifier added to the query language fails to compile until it's listed here
+        macro_rules! qualifiers {
+            ($($variant:ident $(($value:pat))? => $qualifier:literal,)*) => {
+                fn qualifier(term: &Vulnerabilities) -> &'static str {
+                    match term {
+                        $(Vulnerabilities::$variant $(($value))? => $qualifier,)*
+                    }
+                }
+                const QUALIFIERS: &[&str] = &[$($qualifier),*];
+            };
+        }
+
+        qualifiers! {
+            Id(_) => "id",
+            Cve(_) => "cve",
+            Title(_) => "title",
+            Description(_) => "description",
+            Product(_) => "product",
+            VendorFix(_) => "vendorFix",
+            Vendor(_) => "vendor",
+            Notes(_) => "notes",
+            Status(_) => "status",
+            Category(_) => "category",
+            Revision(_) => "revision",
+            Reference(_) => "reference",
+            Source(_) => "source",
+            Label(_) => "label",
+            Tlp(_) => "tlp",
+            Severity(_) => "severity",
+            Cvss(_) => "cvss",
+            Cvss4(_) => "cvss4",
+            Severity4(_) => "severity4",
+            Package(_) => "package",
+            Fixed(_) => "fixed",
+            Affected(_) => "affected",
+            NotAffected(_) => "notAffected",
+            Initial(_) => "initial",
+            Release(_) => "release",
+            CveRelease(_) => "cveRelease",
+            CveDiscovery(_) => "cveDiscovery",
+            IndexedTimestamp(_) => "indexedTimestamp",
+            SupersededBy(_) => "supersededBy",
+            Include(_) => "include",
+            Withdrawn => "withdrawn",
+            Archived => "archived",
+            Final => "final",
+            Vex => "vex",
+            Advisory => "advisory",
+            Critical => "critical",
+            High => "high",
+            Medium => "medium",
+            Low => "low",
+        }
+
+        let fields = Index::new().search_fields();
+        for field in &fields {
+            let query = Vulnerabilities::parse(&field.example).unwrap();
+            match query.term {
+                sikula::prelude::Term::Match(term) => assert_eq!(qualifier(&term), field.qualifier),
+                term => panic!("unexpected example of {}: {term:?}", field.qualifier),
+            }
+        }
+
+        let described: Vec<_> = fields.iter().map(|field| field.qualifier.as_str()).collect();
+        for qualifier in QUALIFIERS {
+            assert!(described.contains(qualifier), "{qualifier} is not described");
+        }
+    }
 }
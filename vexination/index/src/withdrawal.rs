@@ -0,0 +1,135 @@
+//! Detection of withdrawn and superseded advisories.
+//!
+//! CSAF 2.0 has no status for withdrawn advisories. Following the profiles of CSAF 2.1, an advisory is withdrawn if
+//! it uses the `csaf_withdrawn` or `csaf_superseded` category, or carries a description note explaining its
+//! withdrawal or supersession. Superseded advisories reference the advisories replacing them as external references,
+//! with a summary starting with `Superseding document`. References alone don't make an advisory withdrawn, as active
+//! advisories may use any summary.
+
+use csaf::{
+    definitions::{NoteCategory, ReferenceCategory},
+    Csaf,
+};
+
+/// Categories of withdrawn advisories.
+const WITHDRAWN_CATEGORIES: &[&str] = &["csaf_withdrawn", "csaf_superseded"];
+
+/// Titles of notes explaining the withdrawal of an advisory, in lowercase.
+const WITHDRAWN_NOTES: &[&str] = &["reasoning for withdrawal", "reasoning for supersession"];
+
+/// Start of the summary of references to superseding advisories, in lowercase.
+const SUPERSEDING_SUMMARY: &str = "superseding document";
+
+/// The withdrawal state of an advisory.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Withdrawal {
+    /// Whether the advisory was withdrawn, which includes superseded advisories
+    pub withdrawn: bool,
+    /// URLs of the advisories superseding this one
+    pub superseded_by: Vec<String>,
+}
+
+impl Withdrawal {
+    pub fn of(csaf: &Csaf) -> Self {
+        let category = crate::document_category(&csaf.document.category);
+        let noted = csaf.document.notes.iter().flatten().any(|note| {
+            matches!(note.category, NoteCategory::Description)
+                && note
+                    .title
+                    .as_deref()
+                    .is_some_and(|title| WITHDRAWN_NOTES.contains(&title.trim().to_lowercase().as_str()))
+        });
+        let withdrawn = WITHDRAWN_CATEGORIES.contains(&category.as_str()) || noted;
+        if !withdrawn {
+            return Self::default();
+        }
+
+        let superseded_by = csaf
+            .document
+            .references
+            .iter()
+            .flatten()
+            .filter(|reference| matches!(reference.category, Some(ReferenceCategory::External)))
+            .filter(|reference| {
+                reference
+                    .summary
+                    .trim_start()
+                    .to_lowercase()
+                    .starts_with(SUPERSEDING_SUMMARY)
+            })
+            .map(|reference| reference.url.to_string())
+            .collect();
+
+        Self {
+            withdrawn,
+            superseded_by,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn csaf(category: &str, notes: serde_json::Value, references: serde_json::Value) -> Csaf {
+        serde_json::from_value(json!({
+            "document": {
+                "category": category,
+                "csaf_version": "2.0",
+                "title": "Example",
+                "publisher": {"category": "vendor", "name": "Example", "namespace": "https://example.com"},
+                "notes": notes,
+                "references": references,
+                "tracking": {
+                    "id": "EX-2023:0001",
+                    "status": "final",
+                    "version": "2",
+                    "initial_release_date": "2023-01-01T00:00:00Z",
+                    "current_release_date": "2023-02-01T00:00:00Z",
+                    "revision_history": [
+                        {"number": "1", "date": "2023-01-01T00:00:00Z", "summary": "Initial version"},
+                        {"number": "2", "date": "2023-02-01T00:00:00Z", "summary": "Withdrawn"}
+                    ]
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn active() {
+        let withdrawal = Withdrawal::of(&csaf("csaf_vex", json!([]), json!([])));
+        assert_eq!(withdrawal, Withdrawal::default());
+    }
+
+    #[test]
+    fn withdrawn() {
+        let withdrawal = Withdrawal::of(&csaf("csaf_withdrawn", json!([]), json!([])));
+        assert!(withdrawal.withdrawn);
+        assert!(withdrawal.superseded_by.is_empty());
+
+        let notes = json!([{"category": "description", "title": "Reasoning for Withdrawal", "text": "Duplicate"}]);
+        assert!(Withdrawal::of(&csaf("csaf_security_advisory", notes, json!([]))).withdrawn);
+    }
+
+    #[test]
+    fn superseded() {
+        let references = json!([
+            {"category": "self", "summary": "Canonical URL", "url": "https://example.com/ex-2023_0001.json"},
+            {"category": "external", "summary": "Superseding document", "url": "https://example.com/ex-2023_0002.json"}
+        ]);
+        let withdrawal = Withdrawal::of(&csaf("csaf_superseded", json!([]), references.clone()));
+        assert!(withdrawal.withdrawn);
+        assert_eq!(withdrawal.superseded_by, vec!["https://example.com/ex-2023_0002.json"]);
+
+        // references alone don't withdraw an advisory
+        let withdrawal = Withdrawal::of(&csaf("csaf_security_advisory", json!([]), references));
+        assert_eq!(withdrawal, Withdrawal::default());
+        let references = json!([
+            {"category": "external", "summary": "Not superseding RHSA-2023:0001", "url": "https://example.com/rhsa.json"}
+        ]);
+        let withdrawal = Withdrawal::of(&csaf("csaf_vex", json!([]), references));
+        assert_eq!(withdrawal, Withdrawal::default());
+    }
+}
@@ -4,10 +4,20 @@ use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{mpsc, Mutex};
 use tokio::task::block_in_place;
+use trustification_auth::{
+    auth::AuthConfigArguments, authenticator::Authenticator, authorizer::Authorizer, Permission,
+};
 use trustification_event_bus::EventBusConfig;
 use trustification_index::{IndexConfig, IndexStore, WriteIndex};
-use trustification_indexer::{actix::configure, Indexer, IndexerStatus, ReindexMode};
-use trustification_infrastructure::health::checks::FailureRate;
+use trustification_indexer::{
+    actix::{configure, AdminAuth},
+    audit::Auditor,
+    batch::{BatchConfig, Batching},
+    failures::{FailuresCommand, Topics},
+    stats::IngestionStats,
+    Indexer, IndexerStatus, ReindexMode,
+};
+use trustification_infrastructure::health::checks::{FailureRate, Probe};
 use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
 use trustification_storage::{Storage, StorageConfig};
 use vexination_index::Index;
@@ -41,13 +51,51 @@ pub struct Run {
 
     #[command(flatten)]
     pub index: IndexConfig,
+
+    #[command(flatten)]
+    pub batch: BatchConfig,
+
+    /// Authentication of the administrative endpoints, like `POST /admin/reindex`
+    #[command(flatten)]
+    pub auth: AuthConfigArguments,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum Command {
+    #[command(subcommand)]
+    Failures(FailuresCommand),
 }
 
 impl Run {
     pub async fn run(self) -> anyhow::Result<ExitCode> {
+        if let Some(Command::Failures(command)) = self.command {
+            let topics = Topics {
+                stored: &self.stored_topic,
+                failed: &self.failed_topic,
+            };
+            return command.run("vexination", topics).await;
+        }
+
+        let (authn, authz) = self.auth.split(self.devmode)?.unzip();
+        let admin = AdminAuth {
+            authenticator: Authenticator::from_config(authn).await?.map(Arc::new),
+            authorizer: Authorizer::new(authz),
+            permission: Permission::UpdateVex,
+        };
+        if admin.authenticator.is_none() {
+            log::warn!("Authentication is disabled");
+        }
+
         let (command_sender, command_receiver) = mpsc::channel(1);
         let status = Arc::new(Mutex::new(IndexerStatus::Running));
         let s = status.clone();
+        let ingestion = Arc::new(Mutex::new(IngestionStats::default()));
+        let i = ingestion.clone();
+        let audit = Arc::new(Mutex::new(None));
+        let a = audit.clone();
         let c = command_sender.clone();
         let storage = self.storage.clone();
         Infrastructure::from(self.infra)
@@ -70,6 +118,12 @@ impl Run {
                     let state = check.handle();
                     context.health.liveness.register("index_state", check).await;
 
+                    let batching =
+                        Batching::new(&self.batch, self.index.sync_interval.into(), context.metrics.registry())?;
+
+                    let (ready, ready_check) = Probe::new("Indexer paused");
+                    context.health.readiness.register("indexer.paused", ready_check).await;
+
                     let mut indexer = Indexer {
                         indexes: vec![index],
                         storage,
@@ -77,17 +131,22 @@ impl Run {
                         stored_topic: self.stored_topic.as_str(),
                         indexed_topic: self.indexed_topic.as_str(),
                         failed_topic: self.failed_topic.as_str(),
+                        deleted_topic: None,
                         sync_interval: self.index.sync_interval.into(),
                         status: s.clone(),
                         commands: command_receiver,
                         command_sender: c,
                         reindex: self.reindex,
                         state,
+                        ingestion: i,
+                        batching,
+                        ready,
+                        audit: Auditor::new(a, context.metrics.registry())?,
                     };
                     indexer.run().await
                 },
                 move |config| {
-                    configure(status, command_sender, config);
+                    configure(status, command_sender, ingestion, audit, Some(admin), config);
                 },
             )
             .await?;
@@ -0,0 +1,78 @@
+use utoipa::ToSchema;
+
+/// Changes between two stored versions of an advisory.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct AdvisoryDiff {
+    /// Advisory identifier
+    pub advisory_id: String,
+    /// The version compared from
+    pub from: DiffVersion,
+    /// The version compared to
+    pub to: DiffVersion,
+    /// Vulnerabilities only listed by the later version
+    pub added_vulnerabilities: Vec<String>,
+    /// Vulnerabilities only listed by the earlier version
+    pub removed_vulnerabilities: Vec<String>,
+    /// Vulnerabilities listed by both versions, which changed
+    pub changed_vulnerabilities: Vec<VulnerabilityChanges>,
+}
+
+/// A stored version of an advisory.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct DiffVersion {
+    /// Version of the advisory, from its tracking information
+    pub version: String,
+    /// Identifier of the stored revision, derived from the time it was stored
+    pub revision: String,
+}
+
+/// Changes of a vulnerability of an advisory.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct VulnerabilityChanges {
+    /// CVE identifier of the vulnerability, or another identifier if it has none
+    pub id: String,
+    /// Products whose status changed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub product_status: Vec<ProductStatusChange>,
+    /// Remediations only listed by the later version
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_remediations: Vec<RemediationEntry>,
+    /// Remediations only listed by the earlier version
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_remediations: Vec<RemediationEntry>,
+}
+
+/// The change of the status of a product, like from `known_affected` to `fixed`.
+///
+/// A product without status in one of the versions has an empty list of statuses for it.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct ProductStatusChange {
+    /// Identifier of the product in the advisory
+    pub product_id: String,
+    /// Statuses of the product in the earlier version
+    pub from: Vec<String>,
+    /// Statuses of the product in the later version
+    pub to: Vec<String>,
+}
+
+/// A remediation of a vulnerability.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+pub struct RemediationEntry {
+    /// Category of the remediation, like `vendor_fix` or `workaround`
+    pub category: String,
+    /// Details of the remediation
+    pub details: String,
+    /// Products the remediation applies to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub product_ids: Vec<String>,
+    /// URL of the remediation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl VulnerabilityChanges {
+    /// Whether anything changed.
+    pub fn is_empty(&self) -> bool {
+        self.product_status.is_empty() && self.added_remediations.is_empty() && self.removed_remediations.is_empty()
+    }
+}
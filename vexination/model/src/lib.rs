@@ -1,5 +1,13 @@
+pub mod diff;
+pub mod revision;
 pub mod search;
+pub mod timeline;
+pub mod tlp;
 
 pub mod prelude {
+    pub use crate::diff::*;
+    pub use crate::revision::*;
     pub use crate::search::*;
+    pub use crate::timeline::*;
+    pub use crate::tlp::*;
 }
@@ -0,0 +1,45 @@
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+/// Revision metadata of an advisory, as tracked by the document itself.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct AdvisoryRevisions {
+    /// Advisory identifier
+    pub advisory_id: String,
+    /// Current version of the advisory
+    pub version: String,
+    /// Status of the advisory (draft, interim or final)
+    pub status: String,
+    /// Initial release date in RFC3339 format
+    #[serde(with = "time::serde::rfc3339")]
+    #[schema(value_type = String)]
+    pub initial_release_date: OffsetDateTime,
+    /// Current release date in RFC3339 format
+    #[serde(with = "time::serde::rfc3339")]
+    #[schema(value_type = String)]
+    pub current_release_date: OffsetDateTime,
+    /// Revision history, oldest first
+    pub revisions: Vec<RevisionEntry>,
+    /// Whether the advisory was withdrawn or superseded
+    #[serde(default)]
+    pub withdrawn: bool,
+    /// URLs of the advisories superseding this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub superseded_by: Vec<String>,
+}
+
+/// A single entry of the revision history.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct RevisionEntry {
+    /// Version number of the revision
+    pub number: String,
+    /// Date of the revision in RFC3339 format
+    #[serde(with = "time::serde::rfc3339")]
+    #[schema(value_type = String)]
+    pub date: OffsetDateTime,
+    /// Summary of the changes of the revision
+    pub summary: String,
+    /// Version as used by the issuing party, if different
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub legacy_version: Option<String>,
+}
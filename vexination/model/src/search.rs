@@ -15,10 +15,65 @@ pub enum Vulnerabilities<'a> {
     Title(Primary<'a>),
     #[search(default)]
     Description(Primary<'a>),
+    /// Search advisories by the names of their products and product versions, matching the words of the names.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// product:"kernel-rt 9.2"
+    /// ```
+    #[search(default)]
+    Product(Primary<'a>),
+    /// Search advisories by the product IDs and URLs of the vendor fixes of their vulnerabilities, like the ID of an
+    /// erratum.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// vendorFix:"RHSA-2023:4378"
+    /// ```
+    #[search(default)]
+    VendorFix(Primary<'a>),
+    /// Search advisories by the vendor of their products, or the namespace of their publisher, ignoring the letter
+    /// case. Namespaces also match by their host name.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// vendor:"Red Hat"
+    /// vendor:redhat.com
+    /// ```
+    Vendor(&'a str),
+    /// Search the notes of advisories and their vulnerabilities, other than their descriptions.
+    #[search(default)]
+    Notes(Primary<'a>),
     Status(&'a str),
+    Category(&'a str),
+    Revision(Primary<'a>),
+    Reference(Primary<'a>),
+    Source(&'a str),
+    /// Search advisories by a label added when indexing them, as `key:value`.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// label:"owner:team-a"
+    /// ```
+    Label(&'a str),
+    /// Search advisories by their Traffic Light Protocol label: clear (or white), green, amber or red. Advisories
+    /// labeled amber or red are only found by users allowed to read them.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// tlp:green
+    /// ```
+    Tlp(&'a str),
     #[search(sort)]
     Severity(&'a str),
     Cvss(PartialOrdered<f64>),
+    Cvss4(PartialOrdered<f64>),
+    Severity4(&'a str),
     #[search(scope)]
     Package(Primary<'a>),
     #[search(scope)]
@@ -37,7 +92,23 @@ pub enum Vulnerabilities<'a> {
     CveDiscovery(Ordered<time::OffsetDateTime>),
     #[search(sort)]
     IndexedTimestamp(Ordered<i64>),
+    /// Search advisories superseded by another one, by the URL of the superseding advisory.
+    SupersededBy(Primary<'a>),
+    /// Include results which are excluded by default, like withdrawn advisories.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// openssl include:withdrawn
+    /// ```
+    Include(&'a str),
+    /// Withdrawn or superseded advisories, which are excluded from results unless searched for explicitly.
+    Withdrawn,
+    /// Advisories whose stored document was moved to the archive storage class.
+    Archived,
     Final,
+    Vex,
+    Advisory,
     Critical,
     High,
     Medium,
@@ -45,12 +116,15 @@ pub enum Vulnerabilities<'a> {
 }
 
 /// A document returned from the search index for every match.
-#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, ToSchema)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, ToSchema)]
 pub struct SearchDocument {
     /// Advisory identifier
     pub advisory_id: String,
     /// Advisory title
     pub advisory_title: String,
+    /// Current version of the advisory
+    #[serde(default)]
+    pub advisory_version: String,
     /// Advisory release date in RFC3339 format
     #[schema(value_type = String)]
     pub advisory_date: time::OffsetDateTime,
@@ -62,16 +136,40 @@ pub struct SearchDocument {
     pub advisory_severity: Option<String>,
     /// List of CVE identifiers that matched within the advisory
     pub cves: Vec<String>,
-    /// Highest CVSS score in vulnerabilities matched within the advisory
+    /// Highest CVSS score in vulnerabilities matched within the advisory, preferring CVSS v4 over v3
     pub cvss_max: Option<f64>,
+    /// Highest CVSS v3 score in vulnerabilities matched within the advisory
+    #[serde(default)]
+    pub cvss3_max: Option<f64>,
+    /// Highest CVSS v4 score in vulnerabilities matched within the advisory
+    #[serde(default)]
+    pub cvss4_max: Option<f64>,
     /// Number of severities by level
     pub cve_severity_count: HashMap<String, u64>,
     /// Time stamp for doc
     pub indexed_timestamp: i64,
+    /// Whether the advisory was withdrawn or superseded
+    #[serde(default)]
+    pub withdrawn: bool,
+    /// URLs of the advisories superseding this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub superseded_by: Vec<String>,
+    /// Whether the stored advisory was archived, retrieving it may be slower
+    #[serde(default)]
+    pub archived: bool,
+    /// Labels added when indexing the advisory, like `owner:team-a`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// Traffic Light Protocol label of the advisory (clear, green, amber or red), if labeled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tlp: Option<String>,
+    /// Vendors of the products of the advisory, or its publisher if it doesn't name any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vendors: Vec<String>,
 }
 
 /// The hit describes the document, its score and optionally an explanation of why that score was given.
-#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
 pub struct SearchHit {
     /// The document that was matched.
     pub document: SearchDocument,
@@ -92,6 +190,13 @@ pub struct SearchResult {
     pub total: usize,
     /// Documents matched up to max requested
     pub result: Vec<SearchHit>,
+    /// Number of matching documents per value, for each requested facet
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub facets: HashMap<String, HashMap<String, u64>>,
+    /// Time of the last commit of the searched index, in RFC3339 format
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    #[schema(value_type = Option<String>)]
+    pub index_updated_at: Option<OffsetDateTime>,
 }
 
 /// This payload returns the total number of docs and the last updated doc.
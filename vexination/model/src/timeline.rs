@@ -0,0 +1,50 @@
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+/// Timeline of the advisory events related to a CVE.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct CveTimeline {
+    /// CVE identifier
+    pub cve: String,
+    /// Number of advisories referencing the CVE
+    pub advisories: usize,
+    /// Events of all advisories, oldest first
+    pub events: Vec<TimelineEvent>,
+}
+
+/// A single event of the timeline.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct TimelineEvent {
+    /// Date of the event in RFC3339 format
+    #[serde(with = "time::serde::rfc3339")]
+    #[schema(value_type = String)]
+    pub date: OffsetDateTime,
+    /// Kind of event
+    pub kind: TimelineEventKind,
+    /// Identifier of the advisory the event was taken from
+    pub advisory_id: String,
+    /// Version of the advisory, for releases and revisions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Status of the advisory (draft, interim or final), for status events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Summary of the event, e.g. the changes of a revision
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum TimelineEventKind {
+    /// The vulnerability was discovered, as reported by the advisory
+    Discovery,
+    /// The vulnerability was disclosed, as reported by the advisory
+    Disclosure,
+    /// Initial release of the advisory
+    InitialRelease,
+    /// A revision of the advisory
+    Revision,
+    /// The current status of the advisory, as of its current release
+    Status,
+}
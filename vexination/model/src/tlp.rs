@@ -0,0 +1,149 @@
+use serde::Deserialize;
+#[cfg(feature = "auth")]
+use trustification_auth::{authenticator::user::UserInformation, authorizer::Authorizer, Permission};
+
+/// The Traffic Light Protocol label of an advisory, restricting who may read it.
+///
+/// Labels of TLP v1, used by CSAF 2.0, and TLP v2 are supported: `WHITE` and `CLEAR` are the same label, and
+/// `AMBER+STRICT` is handled as `AMBER`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Tlp {
+    Clear,
+    Green,
+    Amber,
+    Red,
+}
+
+/// The part of a CSAF document holding its TLP label.
+#[derive(Deserialize)]
+struct Labeled {
+    document: LabeledDocument,
+}
+
+#[derive(Deserialize)]
+struct LabeledDocument {
+    #[serde(default)]
+    distribution: Option<Distribution>,
+}
+
+#[derive(Deserialize)]
+struct Distribution {
+    #[serde(default)]
+    tlp: Option<Label>,
+}
+
+#[derive(Deserialize)]
+struct Label {
+    label: String,
+}
+
+impl Tlp {
+    /// All labels, from the least to the most restricted.
+    pub const ALL: [Tlp; 4] = [Tlp::Clear, Tlp::Green, Tlp::Amber, Tlp::Red];
+
+    /// Parse a label, unknown labels being handled as the most restricted one.
+    pub fn from_label(label: &str) -> Self {
+        match label.to_uppercase().as_str() {
+            "CLEAR" | "WHITE" => Self::Clear,
+            "GREEN" => Self::Green,
+            "AMBER" | "AMBER+STRICT" => Self::Amber,
+            _ => Self::Red,
+        }
+    }
+
+    /// The label of a CSAF document, `None` if it's not labeled, or not a CSAF document.
+    pub fn of(data: &[u8]) -> Option<Self> {
+        serde_json::from_slice::<Labeled>(data)
+            .ok()?
+            .document
+            .distribution?
+            .tlp
+            .map(|tlp| Self::from_label(&tlp.label))
+    }
+
+    /// The lowercase name of the label, as indexed.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Clear => "clear",
+            Self::Green => "green",
+            Self::Amber => "amber",
+            Self::Red => "red",
+        }
+    }
+
+    /// Whether advisories with this label must not be served publicly.
+    pub fn is_restricted(&self) -> bool {
+        matches!(self, Self::Amber | Self::Red)
+    }
+}
+
+#[cfg(feature = "auth")]
+impl Tlp {
+    /// Whether a user may read advisories with this label, reading `RED` advisories implying `AMBER` ones.
+    pub fn may_read(&self, authorizer: &Authorizer, user: &UserInformation) -> bool {
+        match self {
+            Self::Clear | Self::Green => true,
+            Self::Amber => {
+                authorizer.allows(user, Permission::ReadVexAmber) || authorizer.allows(user, Permission::ReadVexRed)
+            }
+            Self::Red => authorizer.allows(user, Permission::ReadVexRed),
+        }
+    }
+
+    /// The labels of the advisories a user may not read.
+    pub fn hidden(authorizer: &Authorizer, user: &UserInformation) -> Vec<Self> {
+        Self::ALL
+            .into_iter()
+            .filter(|tlp| !tlp.may_read(authorizer, user))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels() {
+        assert_eq!(Tlp::from_label("WHITE"), Tlp::Clear);
+        assert_eq!(Tlp::from_label("clear"), Tlp::Clear);
+        assert_eq!(Tlp::from_label("AMBER+STRICT"), Tlp::Amber);
+        assert_eq!(Tlp::from_label("PURPLE"), Tlp::Red);
+
+        assert_eq!(
+            Tlp::of(br#"{"document":{"distribution":{"tlp":{"label":"GREEN"}}}}"#),
+            Some(Tlp::Green)
+        );
+        assert_eq!(Tlp::of(br#"{"document":{"distribution":{"text":"internal"}}}"#), None);
+        assert_eq!(Tlp::of(br#"{"document":{}}"#), None);
+        assert_eq!(Tlp::of(b"not json"), None);
+    }
+
+    #[cfg(feature = "auth")]
+    #[test]
+    fn hidden() {
+        use trustification_auth::{authenticator::user::UserDetails, authorizer::AuthorizerConfig};
+
+        let user = |permissions: &[Permission]| {
+            UserInformation::Authenticated(UserDetails {
+                id: "user".to_string(),
+                permissions: permissions.iter().map(|p| p.as_ref().to_string()).collect(),
+            })
+        };
+        let authorizer = Authorizer::new(Some(AuthorizerConfig { anonymous_read: true }));
+
+        assert_eq!(
+            Tlp::hidden(&authorizer, &UserInformation::Anonymous),
+            vec![Tlp::Amber, Tlp::Red]
+        );
+        assert_eq!(Tlp::hidden(&authorizer, &user(&[])), vec![Tlp::Amber, Tlp::Red]);
+        assert_eq!(
+            Tlp::hidden(&authorizer, &user(&[Permission::ReadVexAmber])),
+            vec![Tlp::Red]
+        );
+        assert_eq!(Tlp::hidden(&authorizer, &user(&[Permission::ReadVexRed])), vec![]);
+
+        // without authorization, everything may be read
+        assert_eq!(Tlp::hidden(&Authorizer::new(None), &UserInformation::Anonymous), vec![]);
+    }
+}
@@ -0,0 +1,105 @@
+use csaf::{
+    definitions::{BranchCategory, BranchesT},
+    Csaf,
+};
+use csaf_walker::{
+    discover::{DiscoveredAdvisory, DiscoveredContext, DiscoveredVisitor},
+    validation::{ValidatedAdvisory, ValidatedVisitor, ValidationContext, ValidationError},
+};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use trustification_common_walker::{filter::DocumentFilter, report::ReportBuilder};
+
+/// Skips discovered advisories not matching the filter, before they get retrieved.
+pub struct DocumentFilterVisitor<V> {
+    pub filter: DocumentFilter,
+    pub next: V,
+    /// the report to count discovered and skipped advisories in
+    pub report: Arc<Mutex<ReportBuilder>>,
+}
+
+impl<V: DiscoveredVisitor> DiscoveredVisitor for DocumentFilterVisitor<V> {
+    type Error = V::Error;
+    type Context = V::Context;
+
+    async fn visit_context(&self, context: &DiscoveredContext<'_>) -> Result<Self::Context, Self::Error> {
+        self.next.visit_context(context).await
+    }
+
+    async fn visit_advisory(&self, context: &Self::Context, advisory: DiscoveredAdvisory) -> Result<(), Self::Error> {
+        self.report.lock().discover();
+        if !self.filter.matches(&advisory.url, advisory.modified) {
+            log::debug!("Skipping filtered advisory: {}", advisory.url);
+            self.report.lock().skip();
+            return Ok(());
+        }
+
+        self.next.visit_advisory(context, advisory).await
+    }
+}
+
+/// Skips advisories which don't mention any of the product families.
+///
+/// The product tree is only known once the document got retrieved, so this must run after the
+/// validation. Documents which fail to parse are passed on, and will be rejected further down.
+pub struct ProductFamilyVisitor<V> {
+    /// the product families to accept, accept all if empty
+    pub product_families: Vec<String>,
+    pub next: V,
+    /// the report to count skipped advisories in
+    pub report: Arc<Mutex<ReportBuilder>>,
+}
+
+impl<V: ValidatedVisitor> ValidatedVisitor for ProductFamilyVisitor<V> {
+    type Error = V::Error;
+    type Context = V::Context;
+
+    async fn visit_context(&self, context: &ValidationContext<'_>) -> Result<Self::Context, Self::Error> {
+        self.next.visit_context(context).await
+    }
+
+    async fn visit_advisory(
+        &self,
+        context: &Self::Context,
+        result: Result<ValidatedAdvisory, ValidationError>,
+    ) -> Result<(), Self::Error> {
+        if let Ok(advisory) = &result {
+            if !self.accepts(&advisory.retrieved.data) {
+                log::debug!("Skipping advisory of other product family: {}", advisory.url);
+                self.report.lock().skip();
+                return Ok(());
+            }
+        }
+
+        self.next.visit_advisory(context, result).await
+    }
+}
+
+impl<V> ProductFamilyVisitor<V> {
+    fn accepts(&self, data: &[u8]) -> bool {
+        if self.product_families.is_empty() {
+            return true;
+        }
+
+        match serde_json::from_slice::<Csaf>(data) {
+            Ok(csaf) => csaf
+                .product_tree
+                .as_ref()
+                .and_then(|tree| tree.branches.as_ref())
+                .map(|branches| has_product_family(branches, &self.product_families))
+                .unwrap_or_default(),
+            Err(_) => true,
+        }
+    }
+}
+
+fn has_product_family(branches: &BranchesT, families: &[String]) -> bool {
+    branches.0.iter().any(|branch| {
+        (matches!(branch.category, BranchCategory::ProductFamily) && families.contains(&branch.name))
+            || branch
+                .branches
+                .as_ref()
+                .map(|branches| has_product_family(branches, families))
+                .unwrap_or_default()
+    })
+}
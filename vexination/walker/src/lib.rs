@@ -3,13 +3,19 @@ use clap::ArgAction;
 use std::{path::PathBuf, process::ExitCode, sync::Arc, time::SystemTime};
 use time::{Date, Month, UtcOffset};
 use trustification_auth::client::{OpenIdTokenProviderConfig, OpenIdTokenProviderConfigArguments};
-use trustification_common_walker::report::{handle_report, ReportGenerateOption, SplitScannerError};
+use trustification_common_walker::{
+    filter::FilterArgs,
+    report::{handle_report, ReportGenerateOption, SplitScannerError},
+    run::{RunMetrics, RunPublisher},
+};
 use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
 use url::Url;
 use walker_common::sender::provider::TokenProvider;
 
+mod filter;
 mod report;
 mod scanner;
+mod state;
 
 #[derive(clap::Args, Debug)]
 #[command(about = "Run the api server", args_conflicts_with_subcommands = true)]
@@ -48,6 +54,16 @@ pub struct Run {
     #[arg(long = "since-file")]
     pub since_file: Option<PathBuf>,
 
+    /// Sync advisories modified since this point in time, like `2024-01-01T00:00:00Z`, overriding the since file.
+    /// Advisories are retrieved and uploaded even if the sync state says they didn't change.
+    #[arg(long = "since")]
+    pub since: Option<humantime::Timestamp>,
+
+    /// A file to keep the state of incremental syncs in: when advisories were last modified and the digests of the
+    /// uploaded ones. Advisories which didn't change since they were uploaded are skipped.
+    #[arg(long = "state-file")]
+    pub state_file: Option<PathBuf>,
+
     /// Additional root certificates for the destination
     #[arg(long = "sender-root-certificates")]
     pub additional_root_certificates: Vec<PathBuf>,
@@ -72,9 +88,20 @@ pub struct Run {
     #[arg(long, env, default_value_t = true, action = ArgAction::Set)]
     pub report_enable: bool,
 
+    /// Publish a report of each run to the API service, see `/api/v1/walker/runs`.
+    #[arg(long, env, default_value_t = true, action = ArgAction::Set)]
+    pub publish_runs: bool,
+
     /// Define report output path
     #[arg(long, env, default_value = "/tmp/share/reports")]
     pub report_path: String,
+
+    /// Only upload advisories containing any of these product families.
+    #[arg(long = "product-family", env = "FILTER_PRODUCT_FAMILY", value_delimiter = ',')]
+    pub product_families: Vec<String>,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
 }
 
 impl Run {
@@ -83,7 +110,7 @@ impl Run {
             .run(
                 "vexination-walker",
                 |_context| async { Ok(()) },
-                |_| async move {
+                |context| async move {
                     let validation_date: Option<SystemTime> = match (self.policy_date, self.v3_signatures) {
                         (_, true) => Some(SystemTime::from(
                             Date::from_calendar_date(2007, Month::January, 1)
@@ -97,7 +124,18 @@ impl Run {
 
                     log::debug!("Policy date: {validation_date:?}");
 
-                    let provider = match OpenIdTokenProviderConfig::from_args_or_devmode(self.oidc, self.devmode) {
+                    let oidc = OpenIdTokenProviderConfig::from_args_or_devmode(self.oidc, self.devmode);
+                    let runs = match self.publish_runs {
+                        true => Some(RunPublisher::new(
+                            &self.sink,
+                            &self.source,
+                            OpenIdTokenProviderConfig::new_provider(oidc.clone()).await?,
+                            &self.additional_root_certificates,
+                        )?),
+                        false => None,
+                    };
+
+                    let provider = match oidc {
                         Some(OpenIdTokenProviderConfig {
                             issuer_url,
                             client_id,
@@ -120,17 +158,32 @@ impl Run {
                         None => Arc::new(()),
                     };
 
+                    // record where the documents came from
+                    let mut target = self.sink.join("/api/v1/vex")?;
+                    target
+                        .query_pairs_mut()
+                        .append_pair("source", "walker")
+                        .append_pair("source_url", &self.source);
+
+                    let mut filter = self.filter.into_config()?;
+                    filter.product_families.extend(self.product_families);
+
                     let scanner = Scanner::new(Options {
                         source: self.source,
-                        target: self.sink.join("/api/v1/vex")?,
+                        target,
                         provider,
                         validation_date,
                         since_file: self.since_file,
+                        since: self.since.map(|since| since.into()),
+                        state_file: self.state_file,
                         additional_root_certificates: self.additional_root_certificates,
                         required_prefixes: self.required_prefixes,
                         retries: self.retries,
                         retry_delay: self.retry_delay.map(|d| d.into()),
                         ignore_distributions: self.ignore_distributions,
+                        runs,
+                        metrics: RunMetrics::register(context.metrics.registry())?,
+                        filter,
                     });
 
                     if let Some(interval) = self.scan_interval {
@@ -1,4 +1,8 @@
-use crate::report::AdvisoryReportVisitor;
+use crate::{
+    filter::{DocumentFilterVisitor, ProductFamilyVisitor},
+    report::AdvisoryReportVisitor,
+    state::{SyncState, SyncStateVisitor, UnmodifiedFilterVisitor},
+};
 use csaf_walker::{
     discover::DiscoverConfig,
     retrieve::RetrievingVisitor,
@@ -13,7 +17,11 @@ use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use tokio::time::MissedTickBehavior;
 use tracing::{instrument, log};
-use trustification_common_walker::report::{Report, ReportBuilder, ReportVisitor, ScannerError};
+use trustification_common_walker::{
+    filter::{self, DocumentFilter},
+    report::{Report, ReportBuilder, ReportVisitor, ScannerError},
+    run::{walker_run, RunMetrics, RunPublisher},
+};
 use url::Url;
 use walker_common::{
     fetcher::FetcherOptions,
@@ -30,9 +38,18 @@ pub struct Options {
     pub required_prefixes: Vec<String>,
     pub ignore_distributions: Vec<String>,
     pub since_file: Option<PathBuf>,
+    /// Sync only from this point in time, retrieving and uploading advisories even if they didn't change
+    pub since: Option<SystemTime>,
+    /// Where to keep the state of incremental syncs, if at all
+    pub state_file: Option<PathBuf>,
     pub additional_root_certificates: Vec<PathBuf>,
     pub retries: usize,
     pub retry_delay: Option<Duration>,
+    /// Where to publish the reports of runs, if at all
+    pub runs: Option<RunPublisher>,
+    /// Metrics of the latest run
+    pub metrics: RunMetrics,
+    pub filter: filter::FilterConfig,
 }
 
 pub struct Scanner {
@@ -56,11 +73,29 @@ impl Scanner {
         }
     }
 
-    #[instrument(skip(self))]
     pub async fn run_once(&self) -> Result<Report, ScannerError> {
+        let result = self.scan().await;
+        self.options.metrics.observe(&walker_run(&self.options.source, &result));
+        if let Some(runs) = &self.options.runs {
+            runs.publish(&result).await;
+        }
+        result
+    }
+
+    #[instrument(skip(self))]
+    async fn scan(&self) -> Result<Report, ScannerError> {
         let report = Arc::new(Mutex::new(ReportBuilder::new()));
 
-        let since = Since::new(None::<SystemTime>, self.options.since_file.clone(), Default::default())?;
+        let since = Since::new(self.options.since, self.options.since_file.clone(), Default::default())?;
+
+        let mut state = match &self.options.state_file {
+            Some(path) => SyncState::load(path)?,
+            None => SyncState::default(),
+        };
+        if self.options.since.is_some() {
+            state = state.forced();
+        }
+        let state = Arc::new(Mutex::new(state));
 
         let source = new_source(
             DiscoverConfig {
@@ -81,10 +116,28 @@ impl Scanner {
             .retries(self.options.retries);
         storage.retry_delay = self.options.retry_delay;
 
-        let validation = ValidationVisitor::new(AdvisoryReportVisitor(ReportVisitor::new(report.clone(), storage)))
+        let product_families = ProductFamilyVisitor {
+            product_families: self.options.filter.product_families.clone(),
+            next: SyncStateVisitor {
+                state: state.clone(),
+                next: AdvisoryReportVisitor(ReportVisitor::new(report.clone(), storage)),
+                report: report.clone(),
+            },
+            report: report.clone(),
+        };
+
+        let validation = ValidationVisitor::new(product_families)
             .with_options(ValidationOptions::new().validation_date(self.options.validation_date));
 
-        let retriever = RetrievingVisitor::new(source.clone(), validation);
+        let retriever = DocumentFilterVisitor {
+            filter: DocumentFilter::new(&self.options.filter)?,
+            next: UnmodifiedFilterVisitor {
+                state: state.clone(),
+                next: RetrievingVisitor::new(source.clone(), validation),
+                report: report.clone(),
+            },
+            report: report.clone(),
+        };
 
         let filtered = FilteringVisitor {
             visitor: retriever,
@@ -94,9 +147,14 @@ impl Scanner {
         };
 
         let walker = Walker::new(source.clone());
-        walker
-            .walk(filtered)
-            .await
+        let result = walker.walk(filtered).await;
+
+        // the state only records uploaded advisories, so it's kept even if the walker fails
+        if let Some(path) = &self.options.state_file {
+            state.lock().store(path)?;
+        }
+
+        result
             // if the walker fails, we record the outcome as part of the report, but skip any
             // further processing, like storing the marker
             .map_err(|err| ScannerError::Normal {
@@ -0,0 +1,190 @@
+//! The state of incremental syncs: when each advisory was last modified and the digest of what was uploaded.
+//!
+//! Advisories which were not modified since they were uploaded aren't retrieved again, and retrieved advisories whose
+//! content didn't change aren't uploaded again.
+
+use anyhow::Context;
+use csaf_walker::{
+    discover::{DiscoveredAdvisory, DiscoveredContext, DiscoveredVisitor},
+    validation::{ValidatedAdvisory, ValidatedVisitor, ValidationContext, ValidationError},
+};
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, path::Path, sync::Arc, time::SystemTime};
+use time::OffsetDateTime;
+use trustification_common_walker::report::ReportBuilder;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct SyncState {
+    /// The uploaded advisories, by their URL
+    advisories: BTreeMap<String, AdvisoryState>,
+    /// Retrieve and upload all advisories, only recording them
+    #[serde(skip)]
+    force: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+struct AdvisoryState {
+    /// When the advisory was last modified, according to the source
+    #[serde(with = "time::serde::rfc3339")]
+    modified: OffsetDateTime,
+    /// SHA-256 digest of the uploaded document
+    sha256: String,
+}
+
+impl SyncState {
+    /// Load the state from a file, starting from scratch if there's none yet.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        match std::fs::read(path) {
+            Ok(data) => {
+                serde_json::from_slice(&data).with_context(|| format!("failed to parse sync state: {}", path.display()))
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).with_context(|| format!("failed to read sync state: {}", path.display())),
+        }
+    }
+
+    /// Store the state, replacing the file atomically so that an interrupted run doesn't lose it.
+    pub fn store(&self, path: &Path) -> anyhow::Result<()> {
+        let temp = path.with_extension("tmp");
+        std::fs::write(&temp, serde_json::to_vec(self)?)
+            .with_context(|| format!("failed to write sync state: {}", temp.display()))?;
+        std::fs::rename(&temp, path).with_context(|| format!("failed to store sync state: {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Don't skip any advisory, like when re-syncing from a point in time.
+    pub fn forced(self) -> Self {
+        Self { force: true, ..self }
+    }
+
+    /// Whether an advisory wasn't modified since it was uploaded.
+    fn is_unmodified(&self, url: &str, modified: SystemTime) -> bool {
+        !self.force
+            && self
+                .advisories
+                .get(url)
+                .is_some_and(|state| state.modified == OffsetDateTime::from(modified))
+    }
+
+    /// Whether an advisory has the content it was uploaded with.
+    fn is_unchanged(&self, url: &str, sha256: &str) -> bool {
+        !self.force && self.advisories.get(url).is_some_and(|state| state.sha256 == sha256)
+    }
+
+    fn record(&mut self, url: String, modified: SystemTime, sha256: String) {
+        self.advisories.insert(
+            url,
+            AdvisoryState {
+                modified: modified.into(),
+                sha256,
+            },
+        );
+    }
+}
+
+/// Skips discovered advisories which weren't modified since they were uploaded, before they get retrieved.
+pub struct UnmodifiedFilterVisitor<V> {
+    pub state: Arc<Mutex<SyncState>>,
+    pub next: V,
+    /// the report to count skipped advisories in
+    pub report: Arc<Mutex<ReportBuilder>>,
+}
+
+impl<V: DiscoveredVisitor> DiscoveredVisitor for UnmodifiedFilterVisitor<V> {
+    type Error = V::Error;
+    type Context = V::Context;
+
+    async fn visit_context(&self, context: &DiscoveredContext<'_>) -> Result<Self::Context, Self::Error> {
+        self.next.visit_context(context).await
+    }
+
+    async fn visit_advisory(&self, context: &Self::Context, advisory: DiscoveredAdvisory) -> Result<(), Self::Error> {
+        if self
+            .state
+            .lock()
+            .is_unmodified(advisory.url.as_str(), advisory.modified)
+        {
+            log::debug!("Skipping unmodified advisory: {}", advisory.url);
+            self.report.lock().skip();
+            return Ok(());
+        }
+
+        self.next.visit_advisory(context, advisory).await
+    }
+}
+
+/// Skips retrieved advisories whose content didn't change, and records the uploaded ones.
+pub struct SyncStateVisitor<V> {
+    pub state: Arc<Mutex<SyncState>>,
+    pub next: V,
+    /// the report to count skipped advisories in
+    pub report: Arc<Mutex<ReportBuilder>>,
+}
+
+impl<V: ValidatedVisitor> ValidatedVisitor for SyncStateVisitor<V> {
+    type Error = V::Error;
+    type Context = V::Context;
+
+    async fn visit_context(&self, context: &ValidationContext<'_>) -> Result<Self::Context, Self::Error> {
+        self.next.visit_context(context).await
+    }
+
+    async fn visit_advisory(
+        &self,
+        context: &Self::Context,
+        result: Result<ValidatedAdvisory, ValidationError>,
+    ) -> Result<(), Self::Error> {
+        let Ok(advisory) = &result else {
+            return self.next.visit_advisory(context, result).await;
+        };
+
+        let url = advisory.url.to_string();
+        let modified = advisory.modified;
+        let sha256 = format!("{:x}", Sha256::digest(&advisory.retrieved.data));
+        let unchanged = self.state.lock().is_unchanged(&url, &sha256);
+        if unchanged {
+            log::debug!("Skipping unchanged advisory: {url}");
+            self.report.lock().skip();
+        } else {
+            self.next.visit_advisory(context, result).await?;
+        }
+
+        // record the modification time of unchanged advisories too, so they aren't retrieved again
+        self.state.lock().record(url, modified, sha256);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn state() {
+        let url = "https://example.com/2023/rhsa-2023_1441.json";
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        let mut state = SyncState::default();
+        assert!(!state.is_unmodified(url, modified));
+        state.record(url.to_string(), modified, "abc".to_string());
+
+        assert!(state.is_unmodified(url, modified));
+        assert!(!state.is_unmodified(url, modified + Duration::from_secs(1)));
+        assert!(state.is_unchanged(url, "abc"));
+        assert!(!state.is_unchanged(url, "def"));
+
+        let forced = state.clone().forced();
+        assert!(!forced.is_unmodified(url, modified));
+        assert!(!forced.is_unchanged(url, "abc"));
+
+        let dir = std::env::temp_dir().join(format!("vexination-walker-state-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.json");
+        assert_eq!(SyncState::load(&path).unwrap(), SyncState::default());
+        state.store(&path).unwrap();
+        assert_eq!(SyncState::load(&path).unwrap(), state);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}